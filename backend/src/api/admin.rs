@@ -4,25 +4,67 @@ use calamine::Reader;
 use futures_util::StreamExt;
 use uuid::Uuid;
 
+use crate::api::claim::claim_error_response;
+use crate::api::verification::verification_error_response;
 use crate::db::AppState;
 use crate::models::CurrentUser;
 use crate::models::{
     BatchDeleteCoursesRequest, BatchDeleteTeachersRequest, BatchImportCourseItem,
-    BatchImportCoursesRequest, BatchImportTeacherItem, BatchImportTeachersRequest, CourseListQuery,
-    CreateCourseRequest, CreateTeacherRequest, TeacherListQuery, UpdateCourseRequest,
-    UpdateCourseStatusRequest, UpdateTeacherRequest, UpdateTeacherStatusRequest,
+    BatchImportCoursesRequest, BatchImportTeacherItem, BatchImportTeachersRequest, ClaimListQuery,
+    CourseListQuery, CreateCourseRequest, CreateModerationRuleRequest, CreateTeacherRequest,
+    Permission, ReviewClaimRequest, ReviewVerificationRequest, StartStorageCheckRequest,
+    StartStorageMigrationRequest, StorageCheckIssueQuery, TeacherListQuery, TestModerationRequest,
+    UpdateCourseRequest, UpdateCourseStatusRequest, UpdateModerationRuleRequest,
+    UpdateStorageQuotaRequest, UpdateTeacherRequest, UpdateTeacherStatusRequest,
+    UpdateUserPermissionsRequest, VerificationListQuery,
 };
 use crate::services::{
-    AdminError, AdminService, AuditLogQuery, AuditLogService, AuditResourceRequest, CourseError,
-    CourseService, TeacherError, TeacherService, UpdateUserStatusRequest,
+    AdminError, AdminService, AiService, AuditLogQuery, AuditLogService, AuditResourceRequest,
+    ClaimService, CourseError, CourseService, ModerationService, PermissionService,
+    StorageCheckService, StorageMigrationService, StorageQuotaService, TeacherError,
+    TeacherService, UpdateUserStatusRequest, VerificationService,
+};
+use crate::utils::{
+    bad_request, client_ip, conflict, forbidden, internal_error, no_content, not_found,
 };
-use crate::utils::{bad_request, forbidden, internal_error, no_content, not_found};
 
-/// 检查用户是否是管理员
+/// 检查用户是否是管理员，且本次登录完成了两步验证
+///
+/// 用于不能单独授予的功能（用户管理、权限授予）
 fn check_admin(current_user: &CurrentUser) -> Result<(), AdminError> {
     if !matches!(current_user.role, crate::models::UserRole::Admin) {
         return Err(AdminError::Forbidden("需要管理员权限".to_string()));
     }
+    check_mfa(current_user)
+}
+
+/// 检查用户是否拥有指定的管理权限，且本次登录完成了两步验证
+///
+/// 管理员拥有全部权限，其他用户需要被单独授予
+async fn check_permission(
+    pool: &sqlx::PgPool,
+    current_user: &CurrentUser,
+    permission: Permission,
+) -> Result<(), AdminError> {
+    let granted = PermissionService::has_permission(pool, current_user, permission)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+    if !granted {
+        return Err(AdminError::Forbidden(format!(
+            "需要「{}」权限",
+            permission.description()
+        )));
+    }
+    check_mfa(current_user)
+}
+
+/// 使用管理功能需要启用两步验证，并在本次登录时完成验证
+fn check_mfa(current_user: &CurrentUser) -> Result<(), AdminError> {
+    if !current_user.mfa {
+        return Err(AdminError::Forbidden(
+            "使用管理功能需要启用两步验证，并在登录时完成验证".to_string(),
+        ));
+    }
     Ok(())
 }
 
@@ -33,6 +75,7 @@ fn handle_admin_error(err: AdminError) -> HttpResponse {
         AdminError::NotFound(msg) => not_found(&msg),
         AdminError::ValidationError(msg) => bad_request(&msg),
         AdminError::Forbidden(msg) => forbidden(&msg),
+        AdminError::Conflict(msg) => conflict(&msg),
         AdminError::DatabaseError(msg) => {
             log::error!("[Admin] 数据库错误 | error={}", msg);
             internal_error("服务器内部错误")
@@ -73,7 +116,7 @@ async fn get_dashboard(
     let user = current_user.into_inner();
     log::info!("[Admin] 获取仪表盘数据 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ViewStats).await {
         return handle_admin_error(e);
     }
 
@@ -150,7 +193,7 @@ async fn update_user_status(
             );
 
             // 记录审计日志
-            let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
+            let ip_address = client_ip(&http_req);
             if let Err(e) = AuditLogService::log_update_user_status(
                 &data.pool,
                 user.id,
@@ -176,6 +219,98 @@ async fn update_user_status(
     }
 }
 
+/// 获取被授予管理权限的用户列表及可授予的权限
+#[get("/admin/permissions")]
+async fn get_permission_grants(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    log::info!("[Admin] 获取权限授予列表 | admin_id={}", user.id);
+
+    if let Err(e) = check_admin(&user) {
+        return handle_admin_error(e);
+    }
+
+    match PermissionService::list_grants(&data.pool).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => handle_admin_error(e),
+    }
+}
+
+/// 获取指定用户的管理权限
+#[get("/admin/users/{user_id}/permissions")]
+async fn get_user_permissions(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+
+    if let Err(e) = check_admin(&user) {
+        return handle_admin_error(e);
+    }
+
+    match PermissionService::get_user_permissions(&data.pool, path.into_inner()).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => handle_admin_error(e),
+    }
+}
+
+/// 设置指定用户的管理权限（整体替换，传空列表即收回全部权限）
+#[put("/admin/users/{user_id}/permissions")]
+async fn update_user_permissions(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+    req: web::Json<UpdateUserPermissionsRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user = current_user.into_inner();
+
+    if let Err(e) = check_admin(&user) {
+        return handle_admin_error(e);
+    }
+
+    let user_id = path.into_inner();
+    let permissions = match req.validate() {
+        Ok(permissions) => permissions,
+        Err(msg) => return bad_request(&msg),
+    };
+    log::info!(
+        "[Admin] 更新用户权限 | admin_id={}, target_user_id={}, permissions={:?}",
+        user.id,
+        user_id,
+        permissions
+    );
+
+    match PermissionService::set_user_permissions(&data.pool, user.id, user_id, &permissions).await
+    {
+        Ok(response) => {
+            let ip_address = client_ip(&http_req);
+            if let Err(e) = AuditLogService::log_update_user_permissions(
+                &data.pool,
+                user.id,
+                user_id,
+                &response.permissions,
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录更新用户权限日志失败 | admin_id={}, target_user_id={}, error={}",
+                    user.id,
+                    user_id,
+                    e
+                );
+            }
+
+            HttpResponse::Ok().json(response)
+        }
+        Err(e) => handle_admin_error(e),
+    }
+}
+
 /// 获取待审核资源列表
 #[get("/admin/resources/pending")]
 async fn get_pending_resources(
@@ -186,7 +321,7 @@ async fn get_pending_resources(
     let user = current_user.into_inner();
     log::info!("[Admin] 获取待审核资源列表 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&data.pool, &user, Permission::AuditResources).await {
         return handle_admin_error(e);
     }
 
@@ -205,6 +340,35 @@ async fn get_pending_resources(
     }
 }
 
+/// 获取重复资源报告
+#[get("/admin/resources/duplicates")]
+async fn get_duplicate_resources(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    query: web::Query<std::collections::HashMap<String, String>>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    log::info!("[Admin] 获取重复资源报告 | admin_id={}", user.id);
+
+    if let Err(e) = check_permission(&data.pool, &user, Permission::AuditResources).await {
+        return handle_admin_error(e);
+    }
+
+    let page = query
+        .get("page")
+        .and_then(|p| p.parse::<i32>().ok())
+        .unwrap_or(1);
+    let per_page = query
+        .get("perPage")
+        .and_then(|p| p.parse::<i32>().ok())
+        .unwrap_or(20);
+
+    match AdminService::get_duplicate_resources(&data.pool, page, per_page).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => handle_admin_error(e),
+    }
+}
+
 /// 审核资源
 #[put("/admin/resources/{resource_id}/audit")]
 async fn audit_resource(
@@ -215,7 +379,7 @@ async fn audit_resource(
 ) -> impl Responder {
     let user = current_user.into_inner();
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&data.pool, &user, Permission::AuditResources).await {
         return handle_admin_error(e);
     }
 
@@ -259,7 +423,7 @@ async fn get_comment_list(
     let user = current_user.into_inner();
     log::info!("[Admin] 获取评论列表 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ModerateComments).await {
         return handle_admin_error(e);
     }
 
@@ -279,6 +443,56 @@ async fn get_comment_list(
     }
 }
 
+/// 获取评论举报队列
+#[get("/admin/comments/reports")]
+async fn get_reported_comments(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    query: web::Query<std::collections::HashMap<String, String>>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    log::info!("[Admin] 获取评论举报队列 | admin_id={}", user.id);
+
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ModerateComments).await {
+        return handle_admin_error(e);
+    }
+
+    let page = query
+        .get("page")
+        .and_then(|p| p.parse::<i32>().ok())
+        .unwrap_or(1);
+    let per_page = query
+        .get("perPage")
+        .and_then(|p| p.parse::<i32>().ok())
+        .unwrap_or(20);
+
+    match AdminService::get_reported_comments(&data.pool, page, per_page).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => handle_admin_error(e),
+    }
+}
+
+/// 获取评论的举报记录
+#[get("/admin/comments/{comment_id}/reports")]
+async fn get_comment_reports(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ModerateComments).await {
+        return handle_admin_error(e);
+    }
+
+    match AdminService::get_comment_reports(&data.pool, path.into_inner()).await {
+        Ok(reports) => HttpResponse::Ok().json(serde_json::json!({
+            "reports": reports
+        })),
+        Err(e) => handle_admin_error(e),
+    }
+}
+
 /// 删除评论
 #[delete("/admin/comments/{comment_id}")]
 async fn delete_comment(
@@ -289,7 +503,7 @@ async fn delete_comment(
 ) -> impl Responder {
     let user = current_user.into_inner();
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ModerateComments).await {
         return handle_admin_error(e);
     }
 
@@ -309,7 +523,7 @@ async fn delete_comment(
             );
 
             // 记录审计日志
-            let ip_address = req.peer_addr().map(|addr| addr.ip().to_string());
+            let ip_address = client_ip(&req);
             if let Err(e) = AuditLogService::log_delete_comment(
                 &data.pool,
                 user.id,
@@ -340,10 +554,11 @@ async fn audit_comment(
     current_user: actix_web::web::ReqData<CurrentUser>,
     path: web::Path<Uuid>,
     req: web::Json<std::collections::HashMap<String, String>>,
+    http_req: HttpRequest,
 ) -> impl Responder {
     let user = current_user.into_inner();
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ModerateComments).await {
         return handle_admin_error(e);
     }
 
@@ -356,13 +571,36 @@ async fn audit_comment(
         status
     );
 
-    match AdminService::audit_comment(&data.pool, comment_id, status).await {
-        Ok(_) => {
+    match AdminService::audit_comment(&data.pool, comment_id, status.clone(), user.id).await {
+        Ok(resolved_reports) => {
             log::info!(
-                "[Admin] 评论审核完成 | admin_id={}, comment_id={}",
+                "[Admin] 评论审核完成 | admin_id={}, comment_id={}, resolved_reports={}",
                 user.id,
-                comment_id
+                comment_id,
+                resolved_reports
             );
+
+            if resolved_reports > 0 {
+                let ip_address = client_ip(&http_req);
+                if let Err(e) = AuditLogService::log_resolve_comment_reports(
+                    &data.pool,
+                    user.id,
+                    comment_id,
+                    &status,
+                    resolved_reports,
+                    ip_address.as_deref(),
+                )
+                .await
+                {
+                    log::warn!(
+                        "[Audit] 记录处理举报日志失败 | admin_id={}, comment_id={}, error={}",
+                        user.id,
+                        comment_id,
+                        e
+                    );
+                }
+            }
+
             HttpResponse::Ok().json(serde_json::json!({
                 "message": "评论审核完成"
             }))
@@ -386,7 +624,7 @@ async fn send_notification(
         req.title
     );
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&data.pool, &user, Permission::SendNotifications).await {
         return handle_admin_error(e);
     }
 
@@ -410,7 +648,7 @@ async fn send_notification(
             log::info!("[Admin] 系统通知发送成功 | admin_id={}", user.id);
 
             // 记录审计日志
-            let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
+            let ip_address = client_ip(&http_req);
             if let Err(e) = AuditLogService::log_send_notification(
                 &data.pool,
                 user.id,
@@ -444,7 +682,7 @@ async fn get_detailed_stats(
     let user = current_user.into_inner();
     log::info!("[Admin] 获取详细统计数据 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ViewStats).await {
         return handle_admin_error(e);
     }
 
@@ -464,7 +702,7 @@ async fn get_audit_logs(
     let user = current_user.into_inner();
     log::info!("[Admin] 获取审计日志 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ViewAuditLogs).await {
         return handle_admin_error(e);
     }
 
@@ -483,183 +721,457 @@ async fn get_audit_logs(
     }
 }
 
-/// ==================== 教师管理接口 ====================
+// ==================== 内容审核规则接口 ====================
 
-/// 获取教师列表（管理员）
-#[get("/admin/teachers")]
-async fn get_teacher_list(
+/// 获取审核规则列表
+#[get("/admin/moderation/rules")]
+async fn get_moderation_rules(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
-    query: web::Query<TeacherListQuery>,
 ) -> impl Responder {
     let user = current_user.into_inner();
-    log::info!("[Admin] 获取教师列表 | admin_id={}", user.id);
+    log::info!("[Admin] 获取审核规则列表 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ManageModerationRules).await {
         return handle_admin_error(e);
     }
 
-    match TeacherService::get_teacher_list(&data.pool, query.into_inner()).await {
-        Ok(response) => HttpResponse::Ok().json(response),
-        Err(e) => handle_teacher_error(e),
+    match ModerationService::list_rules(&data.pool).await {
+        Ok(rules) => HttpResponse::Ok().json(rules),
+        Err(e) => handle_admin_error(e),
     }
 }
 
-/// 添加教师
-#[post("/admin/teachers")]
-async fn create_teacher(
+/// 添加审核规则
+#[post("/admin/moderation/rules")]
+async fn create_moderation_rule(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
-    req: web::Json<CreateTeacherRequest>,
+    req: web::Json<CreateModerationRuleRequest>,
 ) -> impl Responder {
     let user = current_user.into_inner();
-    log::info!("[Admin] 添加教师 | admin_id={}", user.id);
+    log::info!(
+        "[Admin] 添加审核规则 | admin_id={}, match_type={}, target={}",
+        user.id,
+        req.match_type,
+        req.target
+    );
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ManageModerationRules).await {
         return handle_admin_error(e);
     }
 
-    match TeacherService::create_teacher(&data.pool, req.into_inner()).await {
-        Ok(teacher) => HttpResponse::Created().json(teacher),
-        Err(e) => handle_teacher_error(e),
+    match ModerationService::create_rule(&data.pool, user.id, req.into_inner()).await {
+        Ok(rule) => HttpResponse::Created().json(rule),
+        Err(e) => handle_admin_error(e),
     }
 }
 
-/// 更新教师信息
-#[put("/admin/teachers/{sn}")]
-async fn update_teacher(
+/// 更新审核规则
+#[put("/admin/moderation/rules/{rule_id}")]
+async fn update_moderation_rule(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
-    path: web::Path<i64>,
-    req: web::Json<UpdateTeacherRequest>,
+    path: web::Path<Uuid>,
+    req: web::Json<UpdateModerationRuleRequest>,
 ) -> impl Responder {
     let user = current_user.into_inner();
-    let sn = path.into_inner();
+    let rule_id = path.into_inner();
     log::info!(
-        "[Admin] 更新教师信息 | admin_id={}, teacher_sn={}",
+        "[Admin] 更新审核规则 | admin_id={}, rule_id={}",
         user.id,
-        sn
+        rule_id
     );
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ManageModerationRules).await {
         return handle_admin_error(e);
     }
 
-    match TeacherService::update_teacher(&data.pool, sn, req.into_inner()).await {
-        Ok(teacher) => HttpResponse::Ok().json(teacher),
-        Err(e) => handle_teacher_error(e),
+    match ModerationService::update_rule(&data.pool, rule_id, req.into_inner()).await {
+        Ok(rule) => HttpResponse::Ok().json(rule),
+        Err(e) => handle_admin_error(e),
     }
 }
 
-/// 更新教师状态
-#[put("/admin/teachers/{sn}/status")]
-async fn update_teacher_status(
+/// 删除审核规则
+#[delete("/admin/moderation/rules/{rule_id}")]
+async fn delete_moderation_rule(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
-    path: web::Path<i64>,
-    req: web::Json<UpdateTeacherStatusRequest>,
+    path: web::Path<Uuid>,
 ) -> impl Responder {
     let user = current_user.into_inner();
-    let sn = path.into_inner();
+    let rule_id = path.into_inner();
     log::info!(
-        "[Admin] 更新教师状态 | admin_id={}, teacher_sn={}, is_active={}",
+        "[Admin] 删除审核规则 | admin_id={}, rule_id={}",
         user.id,
-        sn,
-        req.is_active
+        rule_id
     );
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ManageModerationRules).await {
         return handle_admin_error(e);
     }
 
-    match TeacherService::update_teacher_status(&data.pool, sn, req.into_inner()).await {
-        Ok(teacher) => HttpResponse::Ok().json(teacher),
-        Err(e) => handle_teacher_error(e),
+    match ModerationService::delete_rule(&data.pool, rule_id).await {
+        Ok(_) => no_content(),
+        Err(e) => handle_admin_error(e),
     }
 }
 
-/// 删除教师
-#[delete("/admin/teachers/{sn}")]
-async fn delete_teacher(
+/// 试运行内容审核（使用当前配置的全部审核提供方，不写入数据）
+#[post("/admin/moderation/test")]
+async fn test_moderation(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
-    path: web::Path<i64>,
+    req: web::Json<TestModerationRequest>,
 ) -> impl Responder {
     let user = current_user.into_inner();
-    let sn = path.into_inner();
-    log::info!("[Admin] 删除教师 | admin_id={}, teacher_sn={}", user.id, sn);
+    log::info!("[Admin] 试运行内容审核 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ManageModerationRules).await {
         return handle_admin_error(e);
     }
 
-    match TeacherService::delete_teacher(&data.pool, sn).await {
-        Ok(_) => no_content(),
-        Err(e) => handle_teacher_error(e),
-    }
+    let result = match req.target.as_deref() {
+        Some("comment") => AiService::audit_comment(&data.moderation, &req.content).await,
+        None | Some("resource") => {
+            AiService::audit_resource(&data.moderation, &req.content, None, None).await
+        }
+        Some(_) => return bad_request("target 只能是 resource 或 comment"),
+    };
+
+    HttpResponse::Ok().json(result)
 }
 
-/// ==================== 课程管理接口 ====================
+// ==================== 作者申领接口 ====================
 
-/// 获取课程列表（管理员）
-#[get("/admin/courses")]
-async fn get_course_list(
+/// 获取作者申领列表
+#[get("/admin/claims")]
+async fn get_claim_list(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
-    query: web::Query<CourseListQuery>,
+    query: web::Query<ClaimListQuery>,
 ) -> impl Responder {
     let user = current_user.into_inner();
-    log::info!("[Admin] 获取课程列表 | admin_id={}", user.id);
+    log::info!("[Admin] 获取作者申领列表 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ReviewClaims).await {
         return handle_admin_error(e);
     }
 
-    match CourseService::get_course_list(&data.pool, query.into_inner()).await {
+    match ClaimService::list_claims(&data.pool, None, &query, &data.config.image_base_url).await {
         Ok(response) => HttpResponse::Ok().json(response),
-        Err(e) => handle_course_error(e),
+        Err(e) => claim_error_response(e),
     }
 }
 
-/// 添加课程
-#[post("/admin/courses")]
-async fn create_course(
+/// 审核作者申领
+#[put("/admin/claims/{claim_id}/review")]
+async fn review_claim(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
-    req: web::Json<CreateCourseRequest>,
+    path: web::Path<Uuid>,
+    req: web::Json<ReviewClaimRequest>,
+    http_req: HttpRequest,
 ) -> impl Responder {
     let user = current_user.into_inner();
-    log::info!("[Admin] 添加课程 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ReviewClaims).await {
         return handle_admin_error(e);
     }
 
-    match CourseService::create_course(&data.pool, req.into_inner()).await {
-        Ok(course) => HttpResponse::Created().json(course),
-        Err(e) => handle_course_error(e),
-    }
-}
-
-/// 更新课程信息
-#[put("/admin/courses/{sn}")]
-async fn update_course(
-    data: web::Data<AppState>,
-    current_user: actix_web::web::ReqData<CurrentUser>,
-    path: web::Path<i64>,
-    req: web::Json<UpdateCourseRequest>,
-) -> impl Responder {
-    let user = current_user.into_inner();
-    let sn = path.into_inner();
+    let claim_id = path.into_inner();
     log::info!(
-        "[Admin] 更新课程信息 | admin_id={}, course_sn={}",
+        "[Admin] 审核作者申领 | admin_id={}, claim_id={}, status={}",
         user.id,
-        sn
+        claim_id,
+        req.status
     );
 
-    if let Err(e) = check_admin(&user) {
-        return handle_admin_error(e);
-    }
+    match ClaimService::review_claim(&data.pool, user.id, claim_id, &req).await {
+        Ok(outcome) => {
+            let ip_address = client_ip(&http_req);
+            if let Err(e) = AuditLogService::log_review_claim(
+                &data.pool,
+                user.id,
+                outcome.claim_id,
+                outcome.resource_id,
+                outcome.applicant_id,
+                outcome.status.as_str(),
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录审核申领日志失败 | admin_id={}, claim_id={}, error={}",
+                    user.id,
+                    claim_id,
+                    e
+                );
+            }
+
+            HttpResponse::Ok().json(serde_json::json!({
+                "message": "申领审核完成",
+                "status": outcome.status.as_str(),
+                "autoRejected": outcome.auto_rejected
+            }))
+        }
+        Err(e) => claim_error_response(e),
+    }
+}
+
+// ==================== 实名认证审核接口 ====================
+
+/// 获取实名认证申请列表
+#[get("/admin/verifications")]
+async fn get_verification_list(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    query: web::Query<VerificationListQuery>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    log::info!("[Admin] 获取实名认证申请列表 | admin_id={}", user.id);
+
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ReviewVerifications).await {
+        return handle_admin_error(e);
+    }
+
+    match VerificationService::list_requests(&data.pool, &query).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => verification_error_response(e),
+    }
+}
+
+/// 审核实名认证申请
+#[put("/admin/verifications/{request_id}/review")]
+async fn review_verification(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+    req: web::Json<ReviewVerificationRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user = current_user.into_inner();
+
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ReviewVerifications).await {
+        return handle_admin_error(e);
+    }
+
+    let request_id = path.into_inner();
+    log::info!(
+        "[Admin] 审核实名认证申请 | admin_id={}, request_id={}, status={}",
+        user.id,
+        request_id,
+        req.status
+    );
+
+    match VerificationService::review_request(&data.pool, user.id, request_id, &req).await {
+        Ok(outcome) => {
+            let ip_address = client_ip(&http_req);
+            if let Err(e) = AuditLogService::log_review_verification(
+                &data.pool,
+                user.id,
+                outcome.request_id,
+                outcome.user_id,
+                outcome.status.as_str(),
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录实名认证审核日志失败 | admin_id={}, request_id={}, error={}",
+                    user.id,
+                    request_id,
+                    e
+                );
+            }
+
+            HttpResponse::Ok().json(serde_json::json!({
+                "message": "实名认证审核完成",
+                "status": outcome.status.as_str()
+            }))
+        }
+        Err(e) => verification_error_response(e),
+    }
+}
+
+// ==================== 教师管理接口 ====================
+
+/// 获取教师列表（管理员）
+#[get("/admin/teachers")]
+async fn get_teacher_list(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    query: web::Query<TeacherListQuery>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    log::info!("[Admin] 获取教师列表 | admin_id={}", user.id);
+
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ManageCourses).await {
+        return handle_admin_error(e);
+    }
+
+    match TeacherService::get_teacher_list(&data.pool, query.into_inner()).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => handle_teacher_error(e),
+    }
+}
+
+/// 添加教师
+#[post("/admin/teachers")]
+async fn create_teacher(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    req: web::Json<CreateTeacherRequest>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    log::info!("[Admin] 添加教师 | admin_id={}", user.id);
+
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ManageCourses).await {
+        return handle_admin_error(e);
+    }
+
+    match TeacherService::create_teacher(&data.pool, req.into_inner()).await {
+        Ok(teacher) => HttpResponse::Created().json(teacher),
+        Err(e) => handle_teacher_error(e),
+    }
+}
+
+/// 更新教师信息
+#[put("/admin/teachers/{sn}")]
+async fn update_teacher(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    path: web::Path<i64>,
+    req: web::Json<UpdateTeacherRequest>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    let sn = path.into_inner();
+    log::info!(
+        "[Admin] 更新教师信息 | admin_id={}, teacher_sn={}",
+        user.id,
+        sn
+    );
+
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ManageCourses).await {
+        return handle_admin_error(e);
+    }
+
+    match TeacherService::update_teacher(&data.pool, sn, req.into_inner()).await {
+        Ok(teacher) => HttpResponse::Ok().json(teacher),
+        Err(e) => handle_teacher_error(e),
+    }
+}
+
+/// 更新教师状态
+#[put("/admin/teachers/{sn}/status")]
+async fn update_teacher_status(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    path: web::Path<i64>,
+    req: web::Json<UpdateTeacherStatusRequest>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    let sn = path.into_inner();
+    log::info!(
+        "[Admin] 更新教师状态 | admin_id={}, teacher_sn={}, is_active={}",
+        user.id,
+        sn,
+        req.is_active
+    );
+
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ManageCourses).await {
+        return handle_admin_error(e);
+    }
+
+    match TeacherService::update_teacher_status(&data.pool, sn, req.into_inner()).await {
+        Ok(teacher) => HttpResponse::Ok().json(teacher),
+        Err(e) => handle_teacher_error(e),
+    }
+}
+
+/// 删除教师
+#[delete("/admin/teachers/{sn}")]
+async fn delete_teacher(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    let sn = path.into_inner();
+    log::info!("[Admin] 删除教师 | admin_id={}, teacher_sn={}", user.id, sn);
+
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ManageCourses).await {
+        return handle_admin_error(e);
+    }
+
+    match TeacherService::delete_teacher(&data.pool, sn).await {
+        Ok(_) => no_content(),
+        Err(e) => handle_teacher_error(e),
+    }
+}
+
+// ==================== 课程管理接口 ====================
+
+/// 获取课程列表（管理员）
+#[get("/admin/courses")]
+async fn get_course_list(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    query: web::Query<CourseListQuery>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    log::info!("[Admin] 获取课程列表 | admin_id={}", user.id);
+
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ManageCourses).await {
+        return handle_admin_error(e);
+    }
+
+    match CourseService::get_course_list(&data.pool, query.into_inner()).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => handle_course_error(e),
+    }
+}
+
+/// 添加课程
+#[post("/admin/courses")]
+async fn create_course(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    req: web::Json<CreateCourseRequest>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    log::info!("[Admin] 添加课程 | admin_id={}", user.id);
+
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ManageCourses).await {
+        return handle_admin_error(e);
+    }
+
+    match CourseService::create_course(&data.pool, req.into_inner()).await {
+        Ok(course) => HttpResponse::Created().json(course),
+        Err(e) => handle_course_error(e),
+    }
+}
+
+/// 更新课程信息
+#[put("/admin/courses/{sn}")]
+async fn update_course(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    path: web::Path<i64>,
+    req: web::Json<UpdateCourseRequest>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    let sn = path.into_inner();
+    log::info!(
+        "[Admin] 更新课程信息 | admin_id={}, course_sn={}",
+        user.id,
+        sn
+    );
+
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ManageCourses).await {
+        return handle_admin_error(e);
+    }
 
     match CourseService::update_course(&data.pool, sn, req.into_inner()).await {
         Ok(course) => HttpResponse::Ok().json(course),
@@ -684,7 +1196,7 @@ async fn update_course_status(
         req.is_active
     );
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ManageCourses).await {
         return handle_admin_error(e);
     }
 
@@ -705,7 +1217,7 @@ async fn delete_course(
     let sn = path.into_inner();
     log::info!("[Admin] 删除课程 | admin_id={}, course_sn={}", user.id, sn);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ManageCourses).await {
         return handle_admin_error(e);
     }
 
@@ -725,7 +1237,7 @@ async fn batch_import_teachers(
     let user = current_user.into_inner();
     log::info!("[Admin] 批量导入教师 | admin_id={}, count={}", user.id, req.teachers.len());
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ManageCourses).await {
         return handle_admin_error(e);
     }
 
@@ -757,7 +1269,7 @@ async fn batch_import_courses(
     let user = current_user.into_inner();
     log::info!("[Admin] 批量导入课程 | admin_id={}, count={}", user.id, req.courses.len());
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ManageCourses).await {
         return handle_admin_error(e);
     }
 
@@ -822,8 +1334,7 @@ fn parse_teachers_from_bytes(
 
             for (idx, row) in range.rows().enumerate().skip(1) {
                 // 跳过标题行
-                let name_cell = row
-                    .get(0)
+                let name_cell = row.first()
                     .ok_or_else(|| format!("Excel第{}行: 缺少姓名", idx + 1))?;
                 let name = name_cell.to_string().trim().to_string();
                 let department: Option<String> = row.get(1).map(|c| c.to_string().trim().to_string());
@@ -891,8 +1402,7 @@ fn parse_courses_from_bytes(
 
             for (idx, row) in range.rows().enumerate().skip(1) {
                 // 跳过标题行
-                let name_cell = row
-                    .get(0)
+                let name_cell = row.first()
                     .ok_or_else(|| format!("Excel第{}行: 缺少课程名称", idx + 1))?;
                 let name = name_cell.to_string().trim().to_string();
                 let semester: Option<String> = row.get(1).map(|c| c.to_string().trim().to_string());
@@ -927,7 +1437,7 @@ async fn batch_import_teachers_from_file(
     let user = current_user.into_inner();
     log::info!("[Admin] 开始从文件批量导入教师 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ManageCourses).await {
         return handle_admin_error(e);
     }
 
@@ -1020,7 +1530,7 @@ async fn batch_import_courses_from_file(
     let user = current_user.into_inner();
     log::info!("[Admin] 开始从文件批量导入课程 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ManageCourses).await {
         return handle_admin_error(e);
     }
 
@@ -1117,7 +1627,7 @@ async fn batch_delete_teachers(
         req.sns
     );
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ManageCourses).await {
         return handle_admin_error(e);
     }
 
@@ -1154,7 +1664,7 @@ async fn batch_delete_courses(
         req.sns
     );
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&data.pool, &user, Permission::ManageCourses).await {
         return handle_admin_error(e);
     }
 
@@ -1177,19 +1687,462 @@ async fn batch_delete_courses(
     }
 }
 
+// ==================== 存储迁移接口 ====================
+
+/// 发起存储迁移任务
+#[post("/admin/storage/migrations")]
+async fn start_storage_migration(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    req: web::Json<StartStorageMigrationRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    log::info!(
+        "[Admin] 发起存储迁移 | admin_id={}, source={}, target={}",
+        user.id,
+        req.source,
+        req.target
+    );
+
+    if let Err(e) = check_admin(&user) {
+        return handle_admin_error(e);
+    }
+
+    if let Err(msg) = req.validate() {
+        return bad_request(&msg);
+    }
+
+    match StorageMigrationService::start_migration(&data.pool, &data.storages, &user, &req).await {
+        Ok(migration) => {
+            let ip_address = client_ip(&http_req);
+            if let Err(e) = AuditLogService::log_start_storage_migration(
+                &data.pool,
+                user.id,
+                &migration,
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录发起存储迁移日志失败 | admin_id={}, migration_id={}, error={}",
+                    user.id,
+                    migration.id,
+                    e
+                );
+            }
+
+            HttpResponse::Created().json(migration)
+        }
+        Err(e) => handle_admin_error(e),
+    }
+}
+
+/// 获取最近的存储迁移任务
+#[get("/admin/storage/migrations")]
+async fn get_storage_migrations(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+
+    if let Err(e) = check_admin(&user) {
+        return handle_admin_error(e);
+    }
+
+    match StorageMigrationService::list_migrations(&data.pool).await {
+        Ok(migrations) => HttpResponse::Ok().json(migrations),
+        Err(e) => handle_admin_error(e),
+    }
+}
+
+/// 获取存储迁移任务进度和失败条目
+#[get("/admin/storage/migrations/{migration_id}")]
+async fn get_storage_migration(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+
+    if let Err(e) = check_admin(&user) {
+        return handle_admin_error(e);
+    }
+
+    match StorageMigrationService::get_migration(&data.pool, path.into_inner()).await {
+        Ok(detail) => HttpResponse::Ok().json(detail),
+        Err(e) => handle_admin_error(e),
+    }
+}
+
+/// 取消存储迁移任务（已迁移的文件保持在目标存储）
+#[post("/admin/storage/migrations/{migration_id}/cancel")]
+async fn cancel_storage_migration(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    let migration_id = path.into_inner();
+    log::info!(
+        "[Admin] 取消存储迁移 | admin_id={}, migration_id={}",
+        user.id,
+        migration_id
+    );
+
+    if let Err(e) = check_admin(&user) {
+        return handle_admin_error(e);
+    }
+
+    match StorageMigrationService::cancel_migration(&data.pool, migration_id).await {
+        Ok(migration) => {
+            let ip_address = client_ip(&http_req);
+            if let Err(e) = AuditLogService::log_cancel_storage_migration(
+                &data.pool,
+                user.id,
+                migration_id,
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录取消存储迁移日志失败 | admin_id={}, migration_id={}, error={}",
+                    user.id,
+                    migration_id,
+                    e
+                );
+            }
+
+            HttpResponse::Ok().json(migration)
+        }
+        Err(e) => handle_admin_error(e),
+    }
+}
+
+// ==================== 存储一致性检查接口 ====================
+
+/// 发起存储一致性检查（默认只生成报告，cleanup=true 时清理孤儿文件）
+#[post("/admin/storage/checks")]
+async fn start_storage_check(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    req: web::Json<StartStorageCheckRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    log::info!(
+        "[Admin] 发起存储一致性检查 | admin_id={}, backend={:?}, cleanup={:?}",
+        user.id,
+        req.backend,
+        req.cleanup
+    );
+
+    if let Err(e) = check_admin(&user) {
+        return handle_admin_error(e);
+    }
+
+    if let Err(msg) = req.validate() {
+        return bad_request(&msg);
+    }
+
+    match StorageCheckService::start_check(&data.pool, &data.storages, &user, &req).await {
+        Ok(check) => {
+            let ip_address = client_ip(&http_req);
+            if let Err(e) = AuditLogService::log_start_storage_check(
+                &data.pool,
+                user.id,
+                &check,
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录发起存储一致性检查日志失败 | admin_id={}, check_id={}, error={}",
+                    user.id,
+                    check.id,
+                    e
+                );
+            }
+
+            HttpResponse::Created().json(check)
+        }
+        Err(e) => handle_admin_error(e),
+    }
+}
+
+/// 获取最近的存储一致性检查任务
+#[get("/admin/storage/checks")]
+async fn get_storage_checks(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+
+    if let Err(e) = check_admin(&user) {
+        return handle_admin_error(e);
+    }
+
+    match StorageCheckService::list_checks(&data.pool).await {
+        Ok(checks) => HttpResponse::Ok().json(checks),
+        Err(e) => handle_admin_error(e),
+    }
+}
+
+/// 获取存储一致性检查报告
+#[get("/admin/storage/checks/{check_id}")]
+async fn get_storage_check(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+    query: web::Query<StorageCheckIssueQuery>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+
+    if let Err(e) = check_admin(&user) {
+        return handle_admin_error(e);
+    }
+
+    match StorageCheckService::get_check(&data.pool, path.into_inner(), &query).await {
+        Ok(detail) => HttpResponse::Ok().json(detail),
+        Err(e) => handle_admin_error(e),
+    }
+}
+
+// ==================== 存储配额接口 ====================
+
+/// 获取各角色的存储配额
+#[get("/admin/storage/quotas")]
+async fn get_role_storage_quotas(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+
+    if let Err(e) = check_admin(&user) {
+        return handle_admin_error(e);
+    }
+
+    match StorageQuotaService::list_role_quotas(&data.pool).await {
+        Ok(quotas) => HttpResponse::Ok().json(quotas),
+        Err(e) => handle_admin_error(e),
+    }
+}
+
+/// 设置角色存储配额，返回设置后的各角色配额
+#[put("/admin/storage/quotas/{role}")]
+async fn update_role_storage_quota(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    path: web::Path<String>,
+    req: web::Json<UpdateStorageQuotaRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    let role = path.into_inner();
+    log::info!(
+        "[Admin] 设置角色存储配额 | admin_id={}, role={}, quota_bytes={:?}, unlimited={}",
+        user.id,
+        role,
+        req.quota_bytes,
+        req.unlimited
+    );
+
+    if let Err(e) = check_admin(&user) {
+        return handle_admin_error(e);
+    }
+
+    let quota_bytes = match req.validate() {
+        Ok(quota_bytes) => quota_bytes,
+        Err(msg) => return bad_request(&msg),
+    };
+
+    if let Err(e) =
+        StorageQuotaService::set_role_quota(&data.pool, user.id, &role, quota_bytes).await
+    {
+        return handle_admin_error(e);
+    }
+
+    let ip_address = client_ip(&http_req);
+    if let Err(e) = AuditLogService::log_update_role_storage_quota(
+        &data.pool,
+        user.id,
+        &role,
+        quota_bytes,
+        ip_address.as_deref(),
+    )
+    .await
+    {
+        log::warn!(
+            "[Audit] 记录设置角色存储配额日志失败 | admin_id={}, role={}, error={}",
+            user.id,
+            role,
+            e
+        );
+    }
+
+    match StorageQuotaService::list_role_quotas(&data.pool).await {
+        Ok(quotas) => HttpResponse::Ok().json(quotas),
+        Err(e) => handle_admin_error(e),
+    }
+}
+
+/// 获取用户的存储配额和用量
+#[get("/admin/users/{user_id}/storage-quota")]
+async fn get_user_storage_quota(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+
+    if let Err(e) = check_admin(&user) {
+        return handle_admin_error(e);
+    }
+
+    match StorageQuotaService::get_user_quota(&data.pool, path.into_inner()).await {
+        Ok(quota) => HttpResponse::Ok().json(quota),
+        Err(e) => handle_admin_error(e),
+    }
+}
+
+/// 为用户单独设置存储配额（优先于角色配额）
+#[put("/admin/users/{user_id}/storage-quota")]
+async fn update_user_storage_quota(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+    req: web::Json<UpdateStorageQuotaRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    let user_id = path.into_inner();
+    log::info!(
+        "[Admin] 设置用户存储配额 | admin_id={}, target_user_id={}, quota_bytes={:?}, unlimited={}",
+        user.id,
+        user_id,
+        req.quota_bytes,
+        req.unlimited
+    );
+
+    if let Err(e) = check_admin(&user) {
+        return handle_admin_error(e);
+    }
+
+    let quota_bytes = match req.validate() {
+        Ok(quota_bytes) => quota_bytes,
+        Err(msg) => return bad_request(&msg),
+    };
+    let note = req
+        .note
+        .as_deref()
+        .map(str::trim)
+        .filter(|note| !note.is_empty());
+
+    match StorageQuotaService::set_user_quota(&data.pool, user.id, user_id, quota_bytes, note).await
+    {
+        Ok(quota) => {
+            let ip_address = client_ip(&http_req);
+            if let Err(e) = AuditLogService::log_update_user_storage_quota(
+                &data.pool,
+                user.id,
+                user_id,
+                quota_bytes,
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录设置用户存储配额日志失败 | admin_id={}, target_user_id={}, error={}",
+                    user.id,
+                    user_id,
+                    e
+                );
+            }
+
+            HttpResponse::Ok().json(quota)
+        }
+        Err(e) => handle_admin_error(e),
+    }
+}
+
+/// 取消用户的单独存储配额，恢复为角色配额
+#[delete("/admin/users/{user_id}/storage-quota")]
+async fn clear_user_storage_quota(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    let user_id = path.into_inner();
+    log::info!(
+        "[Admin] 取消用户单独存储配额 | admin_id={}, target_user_id={}",
+        user.id,
+        user_id
+    );
+
+    if let Err(e) = check_admin(&user) {
+        return handle_admin_error(e);
+    }
+
+    match StorageQuotaService::clear_user_quota(&data.pool, user_id).await {
+        Ok(quota) => {
+            let ip_address = client_ip(&http_req);
+            if let Err(e) = AuditLogService::log_clear_user_storage_quota(
+                &data.pool,
+                user.id,
+                user_id,
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录取消用户存储配额日志失败 | admin_id={}, target_user_id={}, error={}",
+                    user.id,
+                    user_id,
+                    e
+                );
+            }
+
+            HttpResponse::Ok().json(quota)
+        }
+        Err(e) => handle_admin_error(e),
+    }
+}
+
 /// 配置管理后台路由
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(get_dashboard)
         .service(get_user_list)
         .service(update_user_status)
+        // 管理权限授予
+        .service(get_permission_grants)
+        .service(get_user_permissions)
+        .service(update_user_permissions)
         .service(get_pending_resources)
+        .service(get_duplicate_resources)
         .service(audit_resource)
         .service(get_comment_list)
+        .service(get_reported_comments)
+        .service(get_comment_reports)
         .service(delete_comment)
         .service(audit_comment)
         .service(send_notification)
         .service(get_detailed_stats)
         .service(get_audit_logs)
+        // 内容审核规则
+        .service(get_moderation_rules)
+        .service(create_moderation_rule)
+        .service(update_moderation_rule)
+        .service(delete_moderation_rule)
+        .service(test_moderation)
+        // 作者申领
+        .service(get_claim_list)
+        .service(review_claim)
+        // 实名认证审核
+        .service(get_verification_list)
+        .service(review_verification)
         // 教师管理
         .service(get_teacher_list)
         .service(create_teacher)
@@ -1210,5 +2163,20 @@ pub fn config(cfg: &mut web::ServiceConfig) {
         .service(batch_import_courses_from_file)
         // 批量删除
         .service(batch_delete_teachers)
-        .service(batch_delete_courses);
+        .service(batch_delete_courses)
+        // 存储迁移
+        .service(start_storage_migration)
+        .service(get_storage_migrations)
+        .service(get_storage_migration)
+        .service(cancel_storage_migration)
+        // 存储一致性检查
+        .service(start_storage_check)
+        .service(get_storage_checks)
+        .service(get_storage_check)
+        // 存储配额
+        .service(get_role_storage_quotas)
+        .service(update_role_storage_quota)
+        .service(get_user_storage_quota)
+        .service(update_user_storage_quota)
+        .service(clear_user_storage_quota);
 }
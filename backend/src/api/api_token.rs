@@ -0,0 +1,106 @@
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Responder};
+use uuid::Uuid;
+
+use crate::db::AppState;
+use crate::models::{CreateApiTokenRequest, CurrentUser};
+use crate::services::{ApiTokenService, AuditLogService, ResourceError};
+use crate::utils::{bad_request, client_ip, internal_error, not_found};
+
+/// 将个人访问令牌相关错误转换为 HTTP 响应
+fn api_token_error_response(err: ResourceError) -> HttpResponse {
+    match err {
+        ResourceError::NotFound(msg) => not_found(&msg),
+        ResourceError::ValidationError(msg) => bad_request(&msg),
+        e => {
+            log::error!("[ApiToken] 服务器内部错误 | error={}", e);
+            internal_error("服务器内部错误")
+        }
+    }
+}
+
+/// 获取我的个人访问令牌列表（含最近使用时间）
+#[get("/users/me/tokens")]
+pub async fn get_my_api_tokens(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+) -> impl Responder {
+    match ApiTokenService::list_tokens(&state.pool, user.id).await {
+        Ok(tokens) => HttpResponse::Ok().json(tokens),
+        Err(e) => api_token_error_response(e),
+    }
+}
+
+/// 创建个人访问令牌，令牌明文只在响应中出现一次
+#[post("/users/me/tokens")]
+pub async fn create_api_token(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    req: web::Json<CreateApiTokenRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    match ApiTokenService::create_token(&state.pool, user.id, req.into_inner()).await {
+        Ok(created) => {
+            let ip_address = client_ip(&http_req);
+            if let Err(e) = AuditLogService::log_create_api_token(
+                &state.pool,
+                user.id,
+                created.info.id,
+                &created.info.name,
+                &created.info.scopes,
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录创建令牌日志失败 | user_id={}, error={}",
+                    user.id,
+                    e
+                );
+            }
+            HttpResponse::Created().json(created)
+        }
+        Err(e) => api_token_error_response(e),
+    }
+}
+
+/// 吊销个人访问令牌
+#[delete("/users/me/tokens/{token_id}")]
+pub async fn revoke_api_token(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let token_id = path.into_inner();
+
+    match ApiTokenService::revoke_token(&state.pool, user.id, token_id).await {
+        Ok(()) => {
+            let ip_address = client_ip(&http_req);
+            if let Err(e) = AuditLogService::log_revoke_api_token(
+                &state.pool,
+                user.id,
+                token_id,
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录吊销令牌日志失败 | user_id={}, error={}",
+                    user.id,
+                    e
+                );
+            }
+            HttpResponse::Ok().json(serde_json::json!({
+                "message": "令牌已吊销"
+            }))
+        }
+        Err(e) => api_token_error_response(e),
+    }
+}
+
+/// 配置个人访问令牌路由（需要认证，且不接受令牌本身调用）
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_my_api_tokens)
+        .service(create_api_token)
+        .service(revoke_api_token);
+}
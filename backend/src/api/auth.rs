@@ -1,9 +1,18 @@
 use crate::db::AppState;
-use crate::models::{LoginRequest, RegisterRequest};
-use crate::services::{AuditLogService, AuthError, AuthService};
-use crate::utils::{bad_request, conflict, internal_error, unauthorized};
+use crate::models::{
+    AuthResponse, ClientInfo, ConfirmEmailRequest, ForgotPasswordRequest, LoginOutcome,
+    LoginRequest, MfaLoginRequest, RegisterRequest, ResetPasswordRequest, REVOKE_REASON_LOGOUT,
+};
+use crate::services::{
+    AuditLogService, AuthError, AuthService, ResourceError, SessionService, VerificationService,
+};
+use crate::utils::{
+    bad_request, client_ip, conflict, error_response, internal_error, unauthorized, verify_token,
+};
 use actix_web::cookie::{time::Duration as CookieDuration, Cookie, SameSite};
-use actix_web::{post, web, HttpRequest, HttpResponse, Responder};
+use actix_web::http::header;
+use actix_web::{post, web, HttpRequest, HttpResponse, HttpResponseBuilder, Responder};
+use uuid::Uuid;
 
 /// Cookie 名称常量
 const ACCESS_TOKEN_COOKIE: &str = "access_token";
@@ -36,6 +45,18 @@ fn clear_auth_cookie<'a>(name: &'a str, secure: bool) -> Cookie<'a> {
         .finish()
 }
 
+/// 从请求中提取客户端信息（记录到登录会话）
+pub(crate) fn client_info(req: &HttpRequest) -> ClientInfo {
+    ClientInfo {
+        ip_address: client_ip(req),
+        user_agent: req
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|h| h.to_str().ok())
+            .map(|ua| ua.chars().take(500).collect()),
+    }
+}
+
 /// 注册
 #[post("/auth/register")]
 pub async fn register(
@@ -46,7 +67,9 @@ pub async fn register(
     let username = req.username.clone();
     log::info!("[Auth] 用户注册请求 | username={}", username);
 
-    match AuthService::register(&state.pool, &state.jwt_secret, req.into_inner()).await {
+    let client = client_info(&http_req);
+
+    match AuthService::register(&state.pool, &state.jwt_secret, req.into_inner(), &client).await {
         Ok(response) => {
             log::info!(
                 "[Auth] 用户注册成功 | user_id={}, username={}",
@@ -55,7 +78,7 @@ pub async fn register(
             );
 
             // 获取 IP 地址
-            let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
+            let ip_address = client_ip(&http_req);
 
             // 记录审计日志
             let _ = AuditLogService::log_register(
@@ -66,6 +89,24 @@ pub async fn register(
             )
             .await;
 
+            // 填写了邮箱时发送确认邮件，失败不影响注册
+            if response.user.email.is_some() {
+                if let Err(e) = VerificationService::send_email_confirmation(
+                    &state.pool,
+                    state.mailer.clone(),
+                    &state.config,
+                    response.user.id,
+                )
+                .await
+                {
+                    log::warn!(
+                        "[Auth] 发送邮箱确认邮件失败 | user_id={}, error={}",
+                        response.user.id,
+                        e
+                    );
+                }
+            }
+
             // 设置 HttpOnly Cookies
             let access_cookie = build_auth_cookie(
                 ACCESS_TOKEN_COOKIE,
@@ -97,7 +138,73 @@ pub async fn register(
     }
 }
 
+/// 登录成功：记录审计日志并在响应中设置 HttpOnly Cookies
+pub(crate) async fn set_login_cookies(
+    state: &AppState,
+    http_req: &HttpRequest,
+    builder: &mut HttpResponseBuilder,
+    response: &AuthResponse,
+) {
+    log::info!(
+        "[Auth] 用户登录成功 | user_id={}, username={}",
+        response.user.id,
+        response.user.username
+    );
+
+    // 获取 IP 地址
+    let ip_address = client_ip(http_req);
+
+    // 记录审计日志
+    let _ = AuditLogService::log_login(
+        &state.pool,
+        response.user.id,
+        &response.user.username,
+        ip_address.as_deref(),
+    )
+    .await;
+
+    // 设置 HttpOnly Cookies
+    builder
+        .cookie(build_auth_cookie(
+            ACCESS_TOKEN_COOKIE,
+            &response.tokens.access_token,
+            1, // 1天
+            state.cookie_secure,
+        ))
+        .cookie(build_auth_cookie(
+            REFRESH_TOKEN_COOKIE,
+            &response.tokens.refresh_token,
+            7, // 7天
+            state.cookie_secure,
+        ));
+}
+
+/// 登录成功：设置 Cookies 并返回用户信息
+async fn login_success_response(
+    state: &AppState,
+    http_req: &HttpRequest,
+    response: AuthResponse,
+) -> HttpResponse {
+    let mut builder = HttpResponse::Ok();
+    set_login_cookies(state, http_req, &mut builder, &response).await;
+
+    // 返回用户信息（不包含token），直接返回用户对象（符合API规范）
+    builder.json(response.user)
+}
+
+/// 将登录错误转换为 HTTP 响应
+fn login_error_response(err: AuthError) -> HttpResponse {
+    match err {
+        AuthError::InvalidCredentials(msg) => unauthorized(&msg),
+        AuthError::ValidationError(msg) => bad_request(&msg),
+        AuthError::TooManyAttempts(msg) => error_response(429, &msg),
+        _ => internal_error("登录失败"),
+    }
+}
+
 /// 登录
+///
+/// 启用了两步验证的用户返回 `mfaRequired` 和临时凭证，需再调用 /auth/login/mfa
 #[post("/auth/login")]
 pub async fn login(
     state: web::Data<AppState>,
@@ -107,53 +214,54 @@ pub async fn login(
     let username = req.username.clone();
     log::info!("[Auth] 用户登录请求 | username={}", username);
 
-    match AuthService::login(&state.pool, &state.jwt_secret, req.into_inner()).await {
-        Ok(response) => {
-            log::info!(
-                "[Auth] 用户登录成功 | user_id={}, username={}",
-                response.user.id,
-                response.user.username
-            );
-
-            // 获取 IP 地址
-            let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
-
-            // 记录审计日志
-            let _ = AuditLogService::log_login(
-                &state.pool,
-                response.user.id,
-                &response.user.username,
-                ip_address.as_deref(),
-            )
-            .await;
-
-            // 设置 HttpOnly Cookies
-            let access_cookie = build_auth_cookie(
-                ACCESS_TOKEN_COOKIE,
-                &response.tokens.access_token,
-                1, // 1天
-                state.cookie_secure,
-            );
-            let refresh_cookie = build_auth_cookie(
-                REFRESH_TOKEN_COOKIE,
-                &response.tokens.refresh_token,
-                7, // 7天
-                state.cookie_secure,
-            );
+    let client = client_info(&http_req);
 
-            // 返回用户信息（不包含token），直接返回用户对象（符合API规范）
-            HttpResponse::Ok()
-                .cookie(access_cookie)
-                .cookie(refresh_cookie)
-                .json(response.user)
+    match AuthService::login(
+        &state.pool,
+        &state.jwt_secret,
+        req.into_inner(),
+        &client,
+        &state.login_lockout,
+    )
+    .await
+    {
+        Ok(LoginOutcome::Authenticated(response)) => {
+            login_success_response(&state, &http_req, response).await
+        }
+        Ok(LoginOutcome::MfaRequired(challenge)) => {
+            log::info!("[Auth] 等待两步验证 | username={}", username);
+            HttpResponse::Ok().json(challenge)
         }
         Err(e) => {
             log::warn!("[Auth] 用户登录失败 | username={}, error={}", username, e);
-            match e {
-                AuthError::InvalidCredentials(msg) => unauthorized(&msg),
-                AuthError::ValidationError(msg) => bad_request(&msg),
-                _ => internal_error("登录失败"),
-            }
+            login_error_response(e)
+        }
+    }
+}
+
+/// 登录第二步：提交动态码或恢复码
+#[post("/auth/login/mfa")]
+pub async fn login_mfa(
+    state: web::Data<AppState>,
+    req: web::Json<MfaLoginRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let client = client_info(&http_req);
+
+    match AuthService::complete_mfa_login(
+        &state.pool,
+        &state.jwt_secret,
+        &state.totp_cipher,
+        req.into_inner(),
+        &client,
+        &state.login_lockout,
+    )
+    .await
+    {
+        Ok(response) => login_success_response(&state, &http_req, response).await,
+        Err(e) => {
+            log::warn!("[Auth] 两步验证失败 | error={}", e);
+            login_error_response(e)
         }
     }
 }
@@ -174,8 +282,9 @@ pub async fn refresh(state: web::Data<AppState>, req: HttpRequest) -> impl Respo
     }
 
     let refresh_token = refresh_token.unwrap();
+    let client = client_info(&req);
 
-    match AuthService::refresh_token(&state.pool, &state.jwt_secret, refresh_token).await {
+    match AuthService::refresh_token(&state.pool, &state.jwt_secret, refresh_token, &client).await {
         Ok(tokens) => {
             log::info!("[Auth] Token刷新成功");
 
@@ -210,11 +319,41 @@ pub async fn refresh(state: web::Data<AppState>, req: HttpRequest) -> impl Respo
     }
 }
 
-/// 登出
+/// 登出（吊销当前会话）
 #[post("/auth/logout")]
-pub async fn logout(state: web::Data<AppState>) -> impl Responder {
+pub async fn logout(state: web::Data<AppState>, req: HttpRequest) -> impl Responder {
     log::info!("[Auth] 用户登出");
 
+    // 优先从 Refresh Token 中读取会话，Access Token 过期时也能正常登出
+    let session = [
+        (REFRESH_TOKEN_COOKIE, "refresh"),
+        (ACCESS_TOKEN_COOKIE, "access"),
+    ]
+    .iter()
+    .filter_map(|(name, token_type)| {
+        let cookie = req.cookie(name)?;
+        let claims = verify_token(cookie.value(), &state.jwt_secret, Some(token_type)).ok()?;
+        Some((
+            Uuid::parse_str(&claims.sub).ok()?,
+            Uuid::parse_str(&claims.sid).ok()?,
+        ))
+    })
+    .next();
+
+    if let Some((user_id, session_id)) = session {
+        if let Err(e) =
+            SessionService::revoke_session(&state.pool, user_id, session_id, REVOKE_REASON_LOGOUT)
+                .await
+        {
+            log::warn!(
+                "[Auth] 登出时吊销会话失败 | user_id={}, session_id={}, error={}",
+                user_id,
+                session_id,
+                e
+            );
+        }
+    }
+
     // 清除 Cookies
     let access_cookie = clear_auth_cookie(ACCESS_TOKEN_COOKIE, state.cookie_secure);
     let refresh_cookie = clear_auth_cookie(REFRESH_TOKEN_COOKIE, state.cookie_secure);
@@ -227,10 +366,120 @@ pub async fn logout(state: web::Data<AppState>) -> impl Responder {
         }))
 }
 
+/// 申请通过邮件重置密码（邮箱是否注册都返回相同结果）
+#[post("/auth/password/forgot")]
+pub async fn forgot_password(
+    state: web::Data<AppState>,
+    req: web::Json<ForgotPasswordRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    if let Err(msg) = req.validate() {
+        return bad_request(&msg);
+    }
+
+    let ip_address = client_ip(&http_req);
+
+    match AuthService::request_password_reset(
+        &state.pool,
+        state.mailer.clone(),
+        &state.config,
+        &req.email,
+        ip_address.as_deref(),
+    )
+    .await
+    {
+        Ok(()) => HttpResponse::Ok().json(serde_json::json!({
+            "message": "如果该邮箱已注册，重置密码邮件将很快送达"
+        })),
+        Err(e) => {
+            log::error!("[Auth] 申请重置密码失败 | error={}", e);
+            internal_error("申请重置密码失败")
+        }
+    }
+}
+
+/// 使用邮件中的链接重置密码，成功后所有设备需要重新登录
+#[post("/auth/password/reset")]
+pub async fn reset_password(
+    state: web::Data<AppState>,
+    req: web::Json<ResetPasswordRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    match AuthService::reset_password(&state.pool, &req).await {
+        Ok((user_id, revoked)) => {
+            let ip_address = client_ip(&http_req);
+            if let Err(e) = AuditLogService::log_reset_password(
+                &state.pool,
+                user_id,
+                revoked,
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录重置密码日志失败 | user_id={}, error={}",
+                    user_id,
+                    e
+                );
+            }
+
+            // 当前浏览器中的旧登录状态也一并清除
+            HttpResponse::Ok()
+                .cookie(clear_auth_cookie(ACCESS_TOKEN_COOKIE, state.cookie_secure))
+                .cookie(clear_auth_cookie(REFRESH_TOKEN_COOKIE, state.cookie_secure))
+                .json(serde_json::json!({
+                    "message": "密码已重置，请使用新密码登录"
+                }))
+        }
+        Err(e) => {
+            log::warn!("[Auth] 重置密码失败 | error={}", e);
+            match e {
+                AuthError::ValidationError(msg) | AuthError::TokenInvalid(msg) => bad_request(&msg),
+                _ => internal_error("重置密码失败"),
+            }
+        }
+    }
+}
+
+/// 确认邮箱（邮件中的链接，无需登录）
+///
+/// 确认校园邮箱后，待审核的实名认证申请自动通过，刷新 Token 后生效
+#[post("/auth/email/confirm")]
+pub async fn confirm_email(
+    state: web::Data<AppState>,
+    req: web::Json<ConfirmEmailRequest>,
+) -> impl Responder {
+    match VerificationService::confirm_email(&state.pool, &state.config, &req.token).await {
+        Ok(outcome) => {
+            log::info!(
+                "[Auth] 邮箱确认成功 | user_id={}, auto_approved={}",
+                outcome.user_id,
+                outcome.auto_approved
+            );
+            HttpResponse::Ok().json(serde_json::json!({
+                "message": "邮箱确认成功",
+                "email": outcome.email,
+                "verificationApproved": outcome.auto_approved
+            }))
+        }
+        Err(e) => {
+            log::warn!("[Auth] 邮箱确认失败 | error={}", e);
+            match e {
+                ResourceError::ValidationError(msg) => bad_request(&msg),
+                _ => internal_error("邮箱确认失败"),
+            }
+        }
+    }
+}
+
 /// 配置认证路由
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(register)
         .service(login)
+        .service(login_mfa)
         .service(refresh)
-        .service(logout);
+        .service(logout)
+        .service(forgot_password)
+        .service(reset_password)
+        .service(confirm_email);
 }
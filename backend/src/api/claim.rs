@@ -0,0 +1,116 @@
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Responder};
+use uuid::Uuid;
+
+use crate::db::AppState;
+use crate::models::{ClaimListQuery, CreateClaimRequest, CurrentUser};
+use crate::services::{AuditLogService, ClaimService, ResourceError};
+use crate::utils::{
+    bad_request, client_ip, conflict, created, forbidden, internal_error, no_content, not_found,
+};
+
+/// 将申领相关错误转换为 HTTP 响应
+pub(crate) fn claim_error_response(err: ResourceError) -> HttpResponse {
+    match err {
+        ResourceError::NotFound(msg) => not_found(&msg),
+        ResourceError::ValidationError(msg) => bad_request(&msg),
+        ResourceError::Unauthorized(msg) => forbidden(&msg),
+        ResourceError::Conflict(msg) => conflict(&msg),
+        e => {
+            log::error!("[Claim] 服务器内部错误 | error={}", e);
+            internal_error("服务器内部错误")
+        }
+    }
+}
+
+/// 提交作者申领（声明自己是资源的真实作者）
+#[post("/resources/{resource_id}/claims")]
+pub async fn create_claim(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+    request: web::Json<CreateClaimRequest>,
+    req: HttpRequest,
+) -> impl Responder {
+    let resource_id = path.into_inner();
+
+    match ClaimService::create_claim(
+        &state.pool,
+        user.id,
+        resource_id,
+        &request,
+        &state.config.image_base_url,
+    )
+    .await
+    {
+        Ok(claim) => {
+            let ip_address = client_ip(&req);
+            if let Err(e) = AuditLogService::log_submit_claim(
+                &state.pool,
+                user.id,
+                claim.id,
+                resource_id,
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录提交申领日志失败 | user_id={}, claim_id={}, error={}",
+                    user.id,
+                    claim.id,
+                    e
+                );
+            }
+
+            created(claim)
+        }
+        Err(e) => {
+            log::warn!(
+                "[Claim] 提交作者申领失败 | user_id={}, resource_id={}, error={}",
+                user.id,
+                resource_id,
+                e
+            );
+            claim_error_response(e)
+        }
+    }
+}
+
+/// 获取我的申领列表
+#[get("/claims/my")]
+pub async fn get_my_claims(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    query: web::Query<ClaimListQuery>,
+) -> impl Responder {
+    match ClaimService::list_claims(
+        &state.pool,
+        Some(user.id),
+        &query,
+        &state.config.image_base_url,
+    )
+    .await
+    {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => claim_error_response(e),
+    }
+}
+
+/// 撤回待审核的申领
+#[delete("/claims/{claim_id}")]
+pub async fn withdraw_claim(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    match ClaimService::withdraw_claim(&state.pool, user.id, path.into_inner()).await {
+        Ok(()) => no_content(),
+        Err(e) => claim_error_response(e),
+    }
+}
+
+/// 配置申领路由（需要认证）
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(create_claim)
+        .service(get_my_claims)
+        .service(withdraw_claim);
+}
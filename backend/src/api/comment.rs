@@ -1,10 +1,177 @@
-use actix_web::{delete, web, HttpRequest, HttpResponse, Responder};
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
 use uuid::Uuid;
 
 use crate::db::AppState;
-use crate::models::{CurrentUser, UserRole};
-use crate::services::{AuditLogService, CommentService, ResourceError};
-use crate::utils::{forbidden, internal_error, not_found};
+use crate::models::{CurrentUser, Permission, ReportCommentRequest, UpdateCommentRequest};
+use crate::services::{AuditLogService, CommentService, PermissionService, ResourceError};
+use crate::utils::{bad_request, client_ip, conflict, forbidden, internal_error, not_found};
+
+/// 将评论相关错误转换为 HTTP 响应
+fn comment_error_response(err: ResourceError) -> HttpResponse {
+    match err {
+        ResourceError::NotFound(msg) => not_found(&msg),
+        ResourceError::ValidationError(msg) => bad_request(&msg),
+        ResourceError::Unauthorized(msg) => forbidden(&msg),
+        ResourceError::Conflict(msg) => conflict(&msg),
+        e => {
+            log::error!("[Comment] 服务器内部错误 | error={}", e);
+            internal_error("服务器内部错误")
+        }
+    }
+}
+
+/// 是否可以管理他人的评论：拥有「管理评论」权限，且本次登录完成了两步验证
+async fn can_moderate_comments(
+    state: &AppState,
+    user: &CurrentUser,
+) -> Result<bool, ResourceError> {
+    if !user.mfa {
+        return Ok(false);
+    }
+    PermissionService::has_permission(&state.pool, user, Permission::ModerateComments)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))
+}
+
+/// 编辑评论（仅作者，发表后一定时间内）
+#[put("/comments/{comment_id}")]
+pub async fn update_comment(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+    request: web::Json<UpdateCommentRequest>,
+    req: HttpRequest,
+) -> impl Responder {
+    let comment_id = path.into_inner();
+
+    match CommentService::update_comment(
+        &state.pool,
+        &state.moderation,
+        comment_id,
+        user.id,
+        request.into_inner(),
+        state.config.comment_edit_window_minutes,
+    )
+    .await
+    {
+        Ok(comment) => {
+            let ip_address = client_ip(&req);
+            if let Err(e) = AuditLogService::log_update_comment(
+                &state.pool,
+                user.id,
+                comment_id,
+                &comment.audit_status,
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录编辑评论日志失败 | comment_id={}, error={}",
+                    comment_id,
+                    e
+                );
+            }
+
+            HttpResponse::Ok().json(comment)
+        }
+        Err(e) => {
+            log::warn!(
+                "[Comment] 编辑评论失败 | comment_id={}, user_id={}, error={}",
+                comment_id,
+                user.id,
+                e
+            );
+            comment_error_response(e)
+        }
+    }
+}
+
+/// 获取评论编辑历史
+#[get("/comments/{comment_id}/history")]
+pub async fn get_comment_history(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let can_moderate = match can_moderate_comments(&state, &user).await {
+        Ok(can_moderate) => can_moderate,
+        Err(e) => return comment_error_response(e),
+    };
+
+    match CommentService::get_edit_history(&state.pool, path.into_inner(), user.id, can_moderate)
+        .await
+    {
+        Ok(history) => HttpResponse::Ok().json(history),
+        Err(e) => comment_error_response(e),
+    }
+}
+
+/// 点赞/取消点赞评论
+#[post("/comments/{comment_id}/like")]
+pub async fn toggle_comment_like(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    match CommentService::toggle_like(&state.pool, path.into_inner(), user.id).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => comment_error_response(e),
+    }
+}
+
+/// 举报评论
+#[post("/comments/{comment_id}/report")]
+pub async fn report_comment(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+    request: web::Json<ReportCommentRequest>,
+    req: HttpRequest,
+) -> impl Responder {
+    let comment_id = path.into_inner();
+    let reason = request.reason.clone();
+
+    match CommentService::report_comment(
+        &state.pool,
+        comment_id,
+        user.id,
+        request.into_inner(),
+        state.config.comment_report_hide_threshold,
+    )
+    .await
+    {
+        Ok(response) => {
+            let ip_address = client_ip(&req);
+            if let Err(e) = AuditLogService::log_report_comment(
+                &state.pool,
+                user.id,
+                comment_id,
+                &reason,
+                response.hidden,
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录举报评论日志失败 | comment_id={}, error={}",
+                    comment_id,
+                    e
+                );
+            }
+
+            HttpResponse::Created().json(response)
+        }
+        Err(e) => {
+            log::warn!(
+                "[Comment] 举报评论失败 | comment_id={}, user_id={}, error={}",
+                comment_id,
+                user.id,
+                e
+            );
+            comment_error_response(e)
+        }
+    }
+}
 
 /// 删除评论
 #[delete("/comments/{comment_id}")]
@@ -15,17 +182,20 @@ pub async fn delete_comment(
     req: HttpRequest,
 ) -> impl Responder {
     let comment_id = path.into_inner();
-    let is_admin = user.role == UserRole::Admin;
+    let can_moderate = match can_moderate_comments(&state, &user).await {
+        Ok(can_moderate) => can_moderate,
+        Err(e) => return comment_error_response(e),
+    };
 
-    match CommentService::delete_comment(&state.pool, comment_id, user.id, is_admin).await {
+    match CommentService::delete_comment(&state.pool, comment_id, user.id, can_moderate).await {
         Ok(true) => {
             // 记录审计日志
-            let ip_address = req.peer_addr().map(|addr| addr.ip().to_string());
+            let ip_address = client_ip(&req);
             if let Err(e) = AuditLogService::log_delete_comment(
                 &state.pool,
                 user.id,
                 comment_id,
-                is_admin,
+                can_moderate,
                 ip_address.as_deref(),
             )
             .await
@@ -50,5 +220,9 @@ pub async fn delete_comment(
 
 /// 配置评论路由
 pub fn config(cfg: &mut web::ServiceConfig) {
-    cfg.service(delete_comment);
+    cfg.service(update_comment)
+        .service(delete_comment)
+        .service(get_comment_history)
+        .service(toggle_comment_like)
+        .service(report_comment);
 }
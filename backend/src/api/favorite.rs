@@ -1,13 +1,12 @@
 use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
 use uuid::Uuid;
 
-use crate::config::Config;
 use crate::db::AppState;
 use crate::models::{
     AddToFavoriteRequest, CreateFavoriteRequest, CurrentUser, UpdateFavoriteRequest,
 };
 use crate::services::{AuditLogService, FavoriteService, ResourceError};
-use crate::utils::{bad_request, conflict, forbidden, internal_error, not_found};
+use crate::utils::{bad_request, client_ip, conflict, forbidden, internal_error, not_found};
 
 /// 对文件名进行 RFC 5987 编码，用于支持中文等非 ASCII 字符
 /// 参考: https://datatracker.ietf.org/doc/html/rfc5987
@@ -29,7 +28,7 @@ fn encode_rfc5987(filename: &str) -> String {
 
 /// 检查文件名是否只包含 ASCII 字符
 fn is_ascii_filename(filename: &str) -> bool {
-    filename.chars().all(|c| c.is_ascii())
+    filename.is_ascii()
 }
 
 /// 构建 Content-Disposition 头部值
@@ -80,7 +79,7 @@ pub async fn create_favorite(
             );
 
             // 记录审计日志
-            let ip_address = req.peer_addr().map(|addr| addr.ip().to_string());
+            let ip_address = client_ip(&req);
             if let Err(e) = AuditLogService::log_create_favorite(
                 &state.pool,
                 user.id,
@@ -390,33 +389,27 @@ pub async fn download_favorite(
         };
 
     let favorite_name = favorite_detail.name.clone();
-    let resource_count = favorite_detail.resource_count as usize;
 
-    // 打包下载
-    // 加载配置用于创建存储后端（支持混合存储）
-    let config = Config::from_env();
+    // 打包下载（按每个资源的 storage_type 从对应存储读取）
     match FavoriteService::pack_favorite_resources(
         &state.pool,
-        &state.storage,
-        &config,
+        &state.storages,
         favorite_id,
         user.id,
         &favorite_name,
     )
     .await
     {
-        Ok((zip_data, filename)) => {
-            let download_size = zip_data.len() as i64;
-
-            // 记录审计日志
-            let ip_address = req.peer_addr().map(|addr| addr.ip().to_string());
+        Ok(package) => {
+            // 压缩包以流的形式发送，大小事先未知，审计日志记录资源原始总大小
+            let ip_address = client_ip(&req);
             if let Err(e) = AuditLogService::log_pack_download(
                 &state.pool,
                 user.id,
                 favorite_id,
                 &favorite_name,
-                download_size,
-                resource_count,
+                package.total_size,
+                package.file_count,
                 ip_address.as_deref(),
             )
             .await
@@ -429,12 +422,12 @@ pub async fn download_favorite(
             }
 
             // 构建 Content-Disposition 头，支持中文文件名
-            let content_disposition = build_content_disposition(&filename);
+            let content_disposition = build_content_disposition(&package.file_name);
 
             HttpResponse::Ok()
                 .content_type("application/zip")
                 .append_header(("Content-Disposition", content_disposition))
-                .body(zip_data)
+                .streaming(package.stream)
         }
         Err(e) => match e {
             ResourceError::ValidationError(msg) => bad_request(&msg),
@@ -137,7 +137,7 @@ pub async fn delete_image(
 ) -> impl Responder {
     let image_id = path.into_inner();
 
-    match ImageService::delete_image(&state.pool, &user, &state.storage, image_id).await {
+    match ImageService::delete_image(&state.pool, &user, &state.storages, image_id).await {
         Ok(_) => no_content(),
         Err(e) => {
             log::warn!("删除图片失败: {}", e);
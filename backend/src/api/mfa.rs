@@ -0,0 +1,181 @@
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+
+use crate::db::AppState;
+use crate::models::{CurrentUser, MfaCodeRequest};
+use crate::services::{AuditLogService, MfaService, PermissionService, ResourceError};
+use crate::utils::{bad_request, client_ip, conflict, forbidden, internal_error, not_found};
+
+/// 将两步验证相关错误转换为 HTTP 响应
+fn mfa_error_response(err: ResourceError) -> HttpResponse {
+    match err {
+        ResourceError::NotFound(msg) => not_found(&msg),
+        ResourceError::ValidationError(msg) => bad_request(&msg),
+        ResourceError::Unauthorized(msg) => forbidden(&msg),
+        ResourceError::Conflict(msg) => conflict(&msg),
+        e => {
+            log::error!("[MFA] 服务器内部错误 | error={}", e);
+            internal_error("服务器内部错误")
+        }
+    }
+}
+
+/// 拥有任一管理权限（含管理员）的账号必须启用两步验证
+async fn is_mfa_required(state: &AppState, user: &CurrentUser) -> Result<bool, ResourceError> {
+    PermissionService::get_effective_permissions(&state.pool, user)
+        .await
+        .map(|permissions| !permissions.is_empty())
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))
+}
+
+/// 获取两步验证状态
+#[get("/users/me/mfa")]
+pub async fn get_mfa_status(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+) -> impl Responder {
+    let required = match is_mfa_required(&state, &user).await {
+        Ok(required) => required,
+        Err(e) => return mfa_error_response(e),
+    };
+
+    match MfaService::get_status(&state.pool, user.id, required).await {
+        Ok(status) => HttpResponse::Ok().json(status),
+        Err(e) => mfa_error_response(e),
+    }
+}
+
+/// 开始绑定身份验证器，返回密钥和 otpauth:// 链接
+#[post("/users/me/mfa/enroll")]
+pub async fn begin_mfa_enrollment(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+) -> impl Responder {
+    match MfaService::begin_enrollment(&state.pool, &state.config, user.id, &user.username).await {
+        Ok(enrollment) => HttpResponse::Ok().json(enrollment),
+        Err(e) => mfa_error_response(e),
+    }
+}
+
+/// 提交首个动态码完成绑定，返回恢复码
+///
+/// 当前会话随之视为已完成两步验证，刷新 Token 后生效
+#[post("/users/me/mfa/confirm")]
+pub async fn confirm_mfa_enrollment(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    req: web::Json<MfaCodeRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    match MfaService::confirm_enrollment(
+        &state.pool,
+        &state.totp_cipher,
+        user.id,
+        user.session_id,
+        &req.code,
+    )
+    .await
+    {
+        Ok(codes) => {
+            let ip_address = client_ip(&http_req);
+            if let Err(e) =
+                AuditLogService::log_enable_mfa(&state.pool, user.id, ip_address.as_deref()).await
+            {
+                log::warn!(
+                    "[Audit] 记录启用两步验证日志失败 | user_id={}, error={}",
+                    user.id,
+                    e
+                );
+            }
+            HttpResponse::Ok().json(codes)
+        }
+        Err(e) => {
+            log::warn!("[MFA] 确认绑定失败 | user_id={}, error={}", user.id, e);
+            mfa_error_response(e)
+        }
+    }
+}
+
+/// 关闭两步验证（拥有管理权限的账号不能关闭）
+#[post("/users/me/mfa/disable")]
+pub async fn disable_mfa(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    req: web::Json<MfaCodeRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let required = match is_mfa_required(&state, &user).await {
+        Ok(required) => required,
+        Err(e) => return mfa_error_response(e),
+    };
+
+    match MfaService::disable(
+        &state.pool,
+        &state.totp_cipher,
+        user.id,
+        required,
+        &req.code,
+    )
+    .await
+    {
+        Ok(()) => {
+            let ip_address = client_ip(&http_req);
+            if let Err(e) =
+                AuditLogService::log_disable_mfa(&state.pool, user.id, ip_address.as_deref()).await
+            {
+                log::warn!(
+                    "[Audit] 记录关闭两步验证日志失败 | user_id={}, error={}",
+                    user.id,
+                    e
+                );
+            }
+            HttpResponse::Ok().json(serde_json::json!({
+                "message": "已关闭两步验证"
+            }))
+        }
+        Err(e) => {
+            log::warn!("[MFA] 关闭两步验证失败 | user_id={}, error={}", user.id, e);
+            mfa_error_response(e)
+        }
+    }
+}
+
+/// 重新生成恢复码，旧恢复码全部作废
+#[post("/users/me/mfa/recovery-codes")]
+pub async fn regenerate_recovery_codes(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    req: web::Json<MfaCodeRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    match MfaService::regenerate_recovery_codes(&state.pool, &state.totp_cipher, user.id, &req.code)
+        .await
+    {
+        Ok(codes) => {
+            let ip_address = client_ip(&http_req);
+            if let Err(e) = AuditLogService::log_regenerate_recovery_codes(
+                &state.pool,
+                user.id,
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录重新生成恢复码日志失败 | user_id={}, error={}",
+                    user.id,
+                    e
+                );
+            }
+            HttpResponse::Ok().json(codes)
+        }
+        Err(e) => mfa_error_response(e),
+    }
+}
+
+/// 配置两步验证路由（需要认证）
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_mfa_status)
+        .service(begin_mfa_enrollment)
+        .service(confirm_mfa_enrollment)
+        .service(disable_mfa)
+        .service(regenerate_recovery_codes);
+}
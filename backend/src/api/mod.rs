@@ -1,11 +1,19 @@
 pub mod admin;
+pub mod api_token;
 pub mod auth;
+pub mod claim;
 pub mod comment;
 pub mod course;
 pub mod favorite;
 pub mod image_host;
+pub mod mfa;
 pub mod notification;
 pub mod oss;
 pub mod resource;
+pub mod resource_editor;
+pub mod resource_version;
+pub mod sso;
 pub mod teacher;
+pub mod upload_session;
 pub mod user;
+pub mod verification;
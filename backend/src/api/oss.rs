@@ -8,9 +8,9 @@ use crate::db::AppState;
 use crate::models::{resource::ResourceType, resource::UploadResourceRequest, CurrentUser};
 use crate::services::{
     AuditLogService, FileService, ImageError, ImageService, ResourceError, ResourceService,
-    StorageBackendType, StorageFileMetadata,
+    StorageBackendType, StorageFileMetadata, StorageQuotaError, StorageQuotaService,
 };
-use crate::utils::{bad_request, created, forbidden, internal_error};
+use crate::utils::{bad_request, client_ip, created, forbidden, internal_error};
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -92,8 +92,8 @@ async fn get_sts_token(
     user: web::ReqData<CurrentUser>,
     payload: web::Json<OssStsTokenRequest>,
 ) -> impl Responder {
-    if state.storage.backend_type() != StorageBackendType::Oss {
-        return bad_request("当前不是 OSS 存储模式，无法申请直传凭证");
+    if !state.storage.backend_type().is_remote() {
+        return bad_request("当前不是 OSS/S3 存储模式，无法申请直传凭证");
     }
 
     let (folder, max_size) = match payload.file_type.as_str() {
@@ -114,6 +114,17 @@ async fn get_sts_token(
         }
     }
 
+    // 提前检查存储配额；未提供大小时只要求还有剩余空间，回调入库时会按实际大小再检查
+    let required_bytes = payload.file_size.unwrap_or(1) as i64;
+    match StorageQuotaService::ensure_quota(&state.pool, user.id, required_bytes).await {
+        Ok(()) => {}
+        Err(StorageQuotaError::QuotaExceeded(msg)) => return bad_request(&msg),
+        Err(e) => {
+            log::error!("[OSS] 检查存储配额失败 | user_id={}, error={}", user.id, e);
+            return internal_error("检查存储配额失败");
+        }
+    }
+
     let extension = pick_extension(folder, &payload.file_name, payload.content_type.as_deref())
         .unwrap_or_else(|| {
             if folder == "images" {
@@ -136,7 +147,7 @@ async fn get_sts_token(
                     upload_mode: "sts".to_string(),
                     upload_key: credentials.upload_key,
                     expires_in: credentials.expires_in,
-                    storage_backend: "oss".to_string(),
+                    storage_backend: state.storage.backend_type().as_str().to_string(),
                     upload_url: None,
                     access_key_id: Some(credentials.access_key_id),
                     access_key_secret: Some(credentials.access_key_secret),
@@ -193,7 +204,7 @@ async fn get_sts_token(
         upload_mode: "signed_url".to_string(),
         upload_key,
         expires_in,
-        storage_backend: "oss".to_string(),
+        storage_backend: state.storage.backend_type().as_str().to_string(),
         upload_url: Some(upload_url),
         access_key_id: None,
         access_key_secret: None,
@@ -212,8 +223,8 @@ async fn resource_upload_callback(
     req: HttpRequest,
     payload: web::Json<ResourceUploadCallbackRequest>,
 ) -> impl Responder {
-    if state.storage.backend_type() != StorageBackendType::Oss {
-        return internal_error("当前未启用OSS存储模式");
+    if !state.storage.backend_type().is_remote() {
+        return internal_error("当前未启用OSS/S3存储模式");
     }
 
     if !key_in_scope(&payload.oss_key, "resources") {
@@ -241,6 +252,7 @@ async fn resource_upload_callback(
         &state.pool,
         &user,
         &state.storage,
+        &state.moderation,
         upload_request,
         &payload.oss_key,
         metadata,
@@ -248,7 +260,7 @@ async fn resource_upload_callback(
     .await
     {
         Ok(response) => {
-            let ip_address = req.peer_addr().map(|addr| addr.ip().to_string());
+            let ip_address = client_ip(&req);
             let _ = AuditLogService::log_upload_resource(
                 &state.pool,
                 user.id,
@@ -282,8 +294,8 @@ async fn image_upload_callback(
     user: web::ReqData<CurrentUser>,
     payload: web::Json<ImageUploadCallbackRequest>,
 ) -> impl Responder {
-    if state.storage.backend_type() != StorageBackendType::Oss {
-        return internal_error("当前未启用OSS存储模式");
+    if !state.storage.backend_type().is_remote() {
+        return internal_error("当前未启用OSS/S3存储模式");
     }
 
     if !key_in_scope(&payload.oss_key, "images") {
@@ -339,11 +351,9 @@ fn key_in_scope(key: &str, scope: &str) -> bool {
     let path = std::path::Path::new(normalized);
 
     // 获取路径的第一个组件
-    if let Some(first_component) = path.components().next() {
-        if let std::path::Component::Normal(first) = first_component {
-            if let Some(first_str) = first.to_str() {
-                return first_str == scope;
-            }
+    if let Some(std::path::Component::Normal(first)) = path.components().next() {
+        if let Some(first_str) = first.to_str() {
+            return first_str == scope;
         }
     }
 
@@ -9,10 +9,13 @@ use crate::models::{
     UpdateResourceContentRequest, UpdateResourceRelationsRequest,
 };
 use crate::services::{
-    AuditLogService, CommentService, LikeService, RatingService, ResourceError, ResourceService,
-    StorageBackendType, StorageError,
+    AuditLogService, CommentService, LikeService, RatingService, RecommendationService,
+    ResourceError, ResourceService, StorageError,
+};
+use crate::utils::{
+    bad_request, client_ip, conflict, conflict_with, forbidden, internal_error,
+    is_initial_transfer, not_found, stream_storage_file,
 };
-use crate::utils::{bad_request, conflict, forbidden, internal_error, not_found};
 
 /// 上传资源
 #[post("/resources")]
@@ -103,7 +106,7 @@ pub async fn upload_resource(
             }
             _ => {
                 // 忽略未知字段
-                while let Some(_) = field.next().await {}
+                while field.next().await.is_some() {}
             }
         }
     }
@@ -129,16 +132,19 @@ pub async fn upload_resource(
         &state.pool,
         &user,
         &state.storage,
+        &state.moderation,
         metadata,
-        &filename,
-        data,
-        mime_type.as_deref(),
+        UploadedFile {
+            name: &filename,
+            data,
+            mime_type: mime_type.as_deref(),
+        },
     )
     .await
     {
         Ok(response) => {
             // 记录审计日志
-            let ip_address = req.peer_addr().map(|addr| addr.ip().to_string());
+            let ip_address = client_ip(&req);
 
             let _ = AuditLogService::log_upload_resource(
                 &state.pool,
@@ -165,19 +171,30 @@ pub async fn upload_resource(
                 user.id,
                 e
             );
-            match e {
-                ResourceError::ValidationError(msg) => bad_request(&msg),
-                ResourceError::FileError(msg) => internal_error(&msg),
-                ResourceError::DatabaseError(msg) => {
-                    log::error!("数据库错误详情: {}", msg);
-                    internal_error(&format!("数据库错误: {}", msg))
-                }
-                ResourceError::AiError(msg) => internal_error(&msg),
-                ResourceError::NotFound(msg) => not_found(&msg),
-                ResourceError::Unauthorized(msg) => forbidden(&msg),
-                ResourceError::Conflict(msg) => conflict(&msg),
-            }
+            upload_error_response(e)
+        }
+    }
+}
+
+/// 上传相关错误转换为 HTTP 响应（普通上传与分片上传共用）
+pub(crate) fn upload_error_response(e: ResourceError) -> HttpResponse {
+    match e {
+        ResourceError::ValidationError(msg) => bad_request(&msg),
+        ResourceError::FileError(msg) => internal_error(&msg),
+        ResourceError::DatabaseError(msg) => {
+            log::error!("数据库错误详情: {}", msg);
+            internal_error(&format!("数据库错误: {}", msg))
         }
+        ResourceError::NotFound(msg) => not_found(&msg),
+        ResourceError::Unauthorized(msg) => forbidden(&msg),
+        ResourceError::Conflict(msg) => conflict(&msg),
+        ResourceError::Duplicate(existing) => conflict_with(
+            "该文件已存在，可将课程/教师关联追加到已有资源",
+            serde_json::json!({
+                "link": format!("/resources/{}", existing.id),
+                "existingResource": existing,
+            }),
+        ),
     }
 }
 
@@ -259,10 +276,10 @@ pub async fn delete_resource(
         user.id
     );
 
-    match ResourceService::delete_resource(&state.pool, &user, &state.storage, resource_id).await {
+    match ResourceService::delete_resource(&state.pool, &user, &state.storages, resource_id).await {
         Ok(title) => {
             // 获取 IP 地址
-            let ip_address = req.peer_addr().map(|addr| addr.ip().to_string());
+            let ip_address = client_ip(&req);
 
             // 记录审计日志
             let _ = AuditLogService::log_delete_resource(
@@ -341,13 +358,22 @@ pub async fn download_resource(
             let content_disposition = build_content_disposition(&filename);
 
             // 根据资源实际的存储类型决定读取方式
-            let is_oss = storage_type.as_deref() == Some("oss");
+            let storage = match state.storages.for_storage_type(storage_type.as_deref()) {
+                Ok(storage) => storage,
+                Err(e) => {
+                    log::warn!(
+                        "[Resource] 资源所在的存储不可用(下载) | resource_id={}, error={}",
+                        resource_id,
+                        e
+                    );
+                    return internal_error("无法访问资源所在的存储");
+                }
+            };
 
-            if is_oss {
-                // OSS 存储：生成签名下载 URL
-                let expires_secs = state.storage.default_signed_url_expiry();
-                match state
-                    .storage
+            if storage.backend_type().is_remote() {
+                // OSS/S3 存储：生成签名下载 URL
+                let expires_secs = storage.default_signed_url_expiry();
+                match storage
                     .get_download_url(&file_path, &filename, expires_secs)
                     .await
                 {
@@ -369,50 +395,47 @@ pub async fn download_resource(
                     }
                 }
             } else {
-                // 本地存储：需要创建本地存储实例来读取文件
-                let config = crate::config::Config::from_env();
-                match crate::services::create_local_storage(&config) {
-                    Ok(local_storage) => {
-                        match local_storage.read_file(&file_path).await {
-                            Ok(file_content) => {
-                                record_download_events(&state, resource_id, user_id, &title, &req).await;
-
-                                log::info!(
-                                    "[Resource] 资源下载成功 | resource_id={}, user_id={:?}, storage=local",
-                                    resource_id,
-                                    user_id
-                                );
-
-                                HttpResponse::Ok()
-                                    .content_type(content_type)
-                                    .insert_header(("Content-Disposition", content_disposition))
-                                    .body(file_content)
-                            }
-                            Err(StorageError::NotFound(_)) => {
-                                log::warn!(
-                                    "[Resource] 下载文件不存在 | resource_id={}, path={}",
-                                    resource_id,
-                                    file_path
-                                );
-                                not_found("文件不存在")
-                            }
-                            Err(e) => {
-                                log::warn!(
-                                    "[Resource] 读取资源文件失败(下载) | resource_id={}, path={}, error={}",
-                                    resource_id,
-                                    file_path,
-                                    e
-                                );
-                                internal_error("文件读取失败")
-                            }
+                // 本地存储：流式读取文件
+                match stream_storage_file(
+                    &req,
+                    storage,
+                    &file_path,
+                    &content_type,
+                    &[("Content-Disposition", content_disposition.as_str())],
+                )
+                .await
+                {
+                    Ok(response) => {
+                        // 分段下载只在第一段记一次下载
+                        if is_initial_transfer(&response) {
+                            record_download_events(&state, resource_id, user_id, &title, &req)
+                                .await;
+
+                            log::info!(
+                                "[Resource] 资源下载成功 | resource_id={}, user_id={:?}, storage=local",
+                                resource_id,
+                                user_id
+                            );
                         }
+
+                        response
+                    }
+                    Err(StorageError::NotFound(_)) => {
+                        log::warn!(
+                            "[Resource] 下载文件不存在 | resource_id={}, path={}",
+                            resource_id,
+                            file_path
+                        );
+                        not_found("文件不存在")
                     }
                     Err(e) => {
-                        log::error!(
-                            "[Resource] 创建本地存储失败 | error={}",
+                        log::warn!(
+                            "[Resource] 读取资源文件失败(下载) | resource_id={}, path={}, error={}",
+                            resource_id,
+                            file_path,
                             e
                         );
-                        internal_error("无法访问本地存储")
+                        internal_error("文件读取失败")
                     }
                 }
             }
@@ -441,10 +464,7 @@ async fn record_download_events(
 ) {
     let _ = ResourceService::increment_downloads(&state.pool, resource_id).await;
 
-    let ip_address = req
-        .peer_addr()
-        .map(|addr| addr.ip().to_string())
-        .unwrap_or_else(|| "0.0.0.0".to_string());
+    let ip_address = client_ip(req).unwrap_or_else(|| "0.0.0.0".to_string());
 
     let _ = ResourceService::record_download(&state.pool, resource_id, user_id, &ip_address).await;
 
@@ -459,7 +479,7 @@ async fn record_download_events(
 }
 
 /// 清理文件名，移除不合法字符
-fn sanitize_filename(filename: &str) -> String {
+pub(crate) fn sanitize_filename(filename: &str) -> String {
     // 移除或替换文件系统不支持的字符
     filename
         .chars()
@@ -490,7 +510,7 @@ fn encode_rfc5987(filename: &str) -> String {
 
 /// 检查文件名是否只包含 ASCII 字符
 fn is_ascii_filename(filename: &str) -> bool {
-    filename.chars().all(|c| c.is_ascii())
+    filename.is_ascii()
 }
 
 /// 构建 Content-Disposition 头部值
@@ -500,7 +520,7 @@ fn is_ascii_filename(filename: &str) -> bool {
 /// 2. 对于含中文的文件名：同时提供 filename 和 filename*
 ///    - filename：包含原始中文，HTTP 库会自动处理编码
 ///    - filename*：RFC 5987 编码，现代浏览器优先使用
-fn build_content_disposition(filename: &str) -> String {
+pub(crate) fn build_content_disposition(filename: &str) -> String {
     if is_ascii_filename(filename) {
         // 纯 ASCII 文件名，直接使用
         format!("attachment; filename=\"{}\"", filename)
@@ -532,23 +552,35 @@ pub async fn get_resource_preview_url(
     // 获取资源文件路径和存储类型（带权限检查）
     match ResourceService::get_resource_file_path_for_preview(&state.pool, resource_id, &user).await {
         Ok((file_path, resource_type, storage_type, updated_at)) => {
-            let is_oss = storage_type.as_deref() == Some("oss");
+            let storage = match state.storages.for_storage_type(storage_type.as_deref()) {
+                Ok(storage) => storage,
+                Err(e) => {
+                    log::warn!(
+                        "[Resource] 资源所在的存储不可用(预览) | resource_id={}, error={}",
+                        resource_id,
+                        e
+                    );
+                    return internal_error("无法访问资源所在的存储");
+                }
+            };
+            let row_backend = storage.backend_type();
 
             // 将 updated_at 格式化为 ISO 8601 字符串
             let updated_at_str = updated_at.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
 
-            if is_oss {
-                // OSS 存储：生成签名 URL，前端直接从 OSS 获取
-                let expires_secs = state.storage.default_signed_url_expiry();
-                match state.storage.get_file_url(&file_path, expires_secs).await {
+            if row_backend.is_remote() {
+                // OSS/S3 存储：生成签名 URL，前端直接从对象存储获取
+                let expires_secs = storage.default_signed_url_expiry();
+                match storage.get_file_url(&file_path, expires_secs).await {
                     Ok(preview_url) => {
                         log::debug!(
-                            "[Resource] 生成 OSS 预览 URL | resource_id={}, storage=oss",
-                            resource_id
+                            "[Resource] 生成对象存储预览 URL | resource_id={}, storage={}",
+                            resource_id,
+                            row_backend.as_str()
                         );
                         HttpResponse::Ok().json(serde_json::json!({
                             "previewUrl": preview_url,
-                            "storageType": "oss",
+                            "storageType": row_backend.as_str(),
                             "resourceType": resource_type,
                             "directAccess": true,
                             "updatedAt": updated_at_str
@@ -600,6 +632,7 @@ pub async fn get_resource_content(
     state: web::Data<AppState>,
     user: web::ReqData<CurrentUser>,
     path: web::Path<Uuid>,
+    req: HttpRequest,
 ) -> impl Responder {
     let resource_id = path.into_inner();
 
@@ -608,71 +641,56 @@ pub async fn get_resource_content(
         Ok((file_path, resource_type, storage_type, updated_at)) => {
             // 根据资源实际的存储类型选择正确的存储后端读取文件
             // 使用后端代理模式，避免浏览器直接访问 OSS 产生 CORS 问题
-            let is_oss = storage_type.as_deref() == Some("oss");
+            let storage = match state.storages.for_storage_type(storage_type.as_deref()) {
+                Ok(storage) => storage,
+                Err(e) => {
+                    log::warn!(
+                        "[Resource] 资源所在的存储不可用(预览) | resource_id={}, error={}",
+                        resource_id,
+                        e
+                    );
+                    return internal_error("无法访问资源所在的存储");
+                }
+            };
+            let row_backend = storage.backend_type();
 
             // 将 updated_at 格式化为 ISO 8601 字符串
             let updated_at_str = updated_at.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
 
-            let read_result = if is_oss {
-                // OSS 存储：使用主 storage（如果是 OSS 模式）或创建 OSS 存储实例
-                if state.storage.backend_type() == StorageBackendType::Oss {
-                    state.storage.read_file(&file_path).await
-                } else {
-                    // 当前是 local 模式，但需要读取 OSS 文件
-                    // 创建临时 OSS 存储实例
-                    let config = crate::config::Config::from_env();
-                    match crate::services::create_storage_backend(&config) {
-                        Ok(oss_storage) if oss_storage.backend_type() == StorageBackendType::Oss => {
-                            oss_storage.read_file(&file_path).await
-                        }
-                        _ => {
-                            log::warn!(
-                                "[Resource] 无法创建 OSS 存储实例来读取资源 | resource_id={}",
-                                resource_id
-                            );
-                            return internal_error("无法读取 OSS 资源");
-                        }
+            // 获取 MIME 类型 - 优先使用 resource_type，因为它更准确
+            let content_type = crate::services::FileService::get_mime_type_by_type(&resource_type);
+
+            log::debug!(
+                "[Resource] 预览资源 | resource_id={}, path={}, type={}, mime={}, storage={}",
+                resource_id,
+                file_path,
+                resource_type,
+                content_type,
+                row_backend.as_str()
+            );
+
+            let cache_headers = [
+                ("Cache-Control", "public, max-age=3600"),
+                ("X-Resource-Updated-At", updated_at_str.as_str()),
+            ];
+
+            let response_result = if row_backend.is_remote() {
+                // OSS/S3 存储：整体读取后返回文件内容（inline 显示，不是下载）
+                storage.read_file(&file_path).await.map(|file_content| {
+                    let mut builder = HttpResponse::Ok();
+                    builder.content_type(content_type.as_str());
+                    for header in cache_headers {
+                        builder.insert_header(header);
                     }
-                }
+                    builder.body(file_content)
+                })
             } else {
-                // 本地存储：使用主 storage（如果是 Local 模式）或创建本地存储实例
-                if state.storage.backend_type() == StorageBackendType::Local {
-                    state.storage.read_file(&file_path).await
-                } else {
-                    // 当前是 OSS 模式，但需要读取本地文件
-                    let config = crate::config::Config::from_env();
-                    match crate::services::create_local_storage(&config) {
-                        Ok(local_storage) => local_storage.read_file(&file_path).await,
-                        Err(e) => {
-                            log::error!("[Resource] 创建本地存储失败 | error={}", e);
-                            return internal_error("无法访问本地存储");
-                        }
-                    }
-                }
+                // 本地存储：流式返回，支持 Range 分段读取（PDF.js 按需加载）和缓存校验
+                stream_storage_file(&req, storage, &file_path, &content_type, &cache_headers).await
             };
 
-            match read_result {
-                Ok(file_content) => {
-                    // 获取 MIME 类型 - 优先使用 resource_type，因为它更准确
-                    let content_type =
-                        crate::services::FileService::get_mime_type_by_type(&resource_type);
-
-                    log::debug!(
-                        "[Resource] 预览资源 | resource_id={}, path={}, type={}, mime={}, storage={}",
-                        resource_id,
-                        file_path,
-                        resource_type,
-                        content_type,
-                        if is_oss { "oss" } else { "local" }
-                    );
-
-                    // 返回文件内容（inline 显示，不是下载）
-                    HttpResponse::Ok()
-                        .content_type(content_type)
-                        .insert_header(("Cache-Control", "public, max-age=3600"))
-                        .insert_header(("X-Resource-Updated-At", updated_at_str.as_str()))
-                        .body(file_content)
-                }
+            match response_result {
+                Ok(response) => response,
                 Err(StorageError::NotFound(_)) => {
                     log::warn!(
                         "[Resource] 预览文件不存在 | resource_id={}, path={}",
@@ -716,12 +734,10 @@ pub async fn get_resource_raw_content(
 ) -> impl Responder {
     let resource_id = path.into_inner();
 
-    match ResourceService::get_resource_content_raw(&state.pool, &state.storage, &user, resource_id)
+    match ResourceService::get_resource_content_raw(&state.pool, &state.storages, &user, resource_id)
         .await
     {
-        Ok(content) => HttpResponse::Ok().json(serde_json::json!({
-            "content": content
-        })),
+        Ok(response) => HttpResponse::Ok().json(response),
         Err(e) => {
             log::warn!(
                 "[Resource] 获取资源原始内容失败 | resource_id={}, user_id={}, error={}",
@@ -774,15 +790,16 @@ pub async fn update_resource_content(
     match ResourceService::update_resource_content(
         &state.pool,
         &user,
-        &state.storage,
+        &state.storages,
+        &state.moderation,
         resource_id,
-        request.content.clone(),
+        request.into_inner(),
     )
     .await
     {
         Ok(response) => {
             // 记录审计日志
-            let ip_address = req.peer_addr().map(|addr| addr.ip().to_string());
+            let ip_address = client_ip(&req);
             if let Err(e) = AuditLogService::log_update_resource(
                 &state.pool,
                 user.id,
@@ -808,6 +825,7 @@ pub async fn update_resource_content(
                 ResourceError::NotFound(msg) => not_found(&msg),
                 ResourceError::Unauthorized(msg) => forbidden(&msg),
                 ResourceError::ValidationError(msg) => bad_request(&msg),
+                ResourceError::Conflict(msg) => conflict(&msg),
                 _ => internal_error("更新资源内容失败"),
             }
         }
@@ -904,6 +922,33 @@ pub async fn get_resource_relations(
     }
 }
 
+/// 获取相似资源（下载过此资源的同学也下载了）
+#[get("/resources/{resource_id}/similar")]
+pub async fn get_similar_resources(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    query: web::Query<RecommendationQuery>,
+) -> impl Responder {
+    let resource_id = path.into_inner();
+
+    match RecommendationService::similar_resources(&state.pool, resource_id, query.get_limit())
+        .await
+    {
+        Ok(resources) => HttpResponse::Ok().json(resources),
+        Err(e) => {
+            log::warn!(
+                "[Resource] 获取相似资源失败 | resource_id={}, error={}",
+                resource_id,
+                e
+            );
+            match e {
+                ResourceError::NotFound(msg) => not_found(&msg),
+                _ => internal_error("获取相似资源失败"),
+            }
+        }
+    }
+}
+
 /// 搜索可关联资源的查询参数
 #[derive(Debug, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -929,7 +974,8 @@ pub fn config_public(cfg: &mut web::ServiceConfig) {
         .service(get_like_status) // 获取点赞状态（支持未登录用户）
         .service(get_comments) // 获取评论列表（公开）
         .service(get_resource_ratings) // 获取资源评分信息（支持未登录用户）
-        .service(get_resource_relations); // /resources/{id}/relations
+        .service(get_resource_relations) // /resources/{id}/relations
+        .service(get_similar_resources); // /resources/{id}/similar
 }
 
 /// 提交评分
@@ -973,7 +1019,7 @@ pub async fn rate_resource(
     {
         Ok(rating) => {
             // 记录审计日志
-            let ip_address = req.peer_addr().map(|addr| addr.ip().to_string());
+            let ip_address = client_ip(&req);
             if let Err(e) = AuditLogService::log_rate_resource(
                 &state.pool,
                 user.id,
@@ -1104,7 +1150,7 @@ pub async fn toggle_like(
     match LikeService::toggle_like(&state.pool, resource_id, user.id).await {
         Ok(result) => {
             // 记录审计日志
-            let ip_address = req.peer_addr().map(|addr| addr.ip().to_string());
+            let ip_address = client_ip(&req);
             if let Err(e) = AuditLogService::log_like_resource(
                 &state.pool,
                 user.id,
@@ -1193,12 +1239,16 @@ pub async fn get_like_status(
 #[get("/resources/{resource_id}/comments")]
 pub async fn get_comments(
     state: web::Data<AppState>,
+    user: Option<web::ReqData<CurrentUser>>,
     path: web::Path<Uuid>,
     query: web::Query<CommentListQuery>,
 ) -> impl Responder {
     let resource_id = path.into_inner();
+    let viewer_id = user.map(|u| u.id);
 
-    match CommentService::get_comments(&state.pool, resource_id, query.into_inner()).await {
+    match CommentService::get_comments(&state.pool, resource_id, query.into_inner(), viewer_id)
+        .await
+    {
         Ok(comments) => HttpResponse::Ok().json(comments),
         Err(e) => {
             log::warn!(
@@ -1243,12 +1293,18 @@ pub async fn create_comment(
         }
     };
 
-    match CommentService::create_comment(&state.pool, resource_id, user.id, request.into_inner())
-        .await
+    match CommentService::create_comment(
+        &state.pool,
+        &state.moderation,
+        resource_id,
+        user.id,
+        request.into_inner(),
+    )
+    .await
     {
         Ok(comment) => {
             // 记录审计日志
-            let ip_address = req.peer_addr().map(|addr| addr.ip().to_string());
+            let ip_address = client_ip(&req);
             if let Err(e) = AuditLogService::log_create_comment(
                 &state.pool,
                 user.id,
@@ -1313,7 +1369,7 @@ pub async fn update_resource_relations(
     };
 
     // 检查权限（上传者或管理员可以修改）
-    if resource_detail.uploader_id != user.id && user.role != crate::models::UserRole::Admin {
+    if resource_detail.uploader_id != user.id && !user.is_admin() {
         return forbidden("只有资源上传者或管理员可以修改关联信息");
     }
 
@@ -1328,7 +1384,7 @@ pub async fn update_resource_relations(
     {
         Ok(_) => {
             // 记录审计日志
-            let ip_address = req.peer_addr().map(|addr| addr.ip().to_string());
+            let ip_address = client_ip(&req);
             if let Err(e) = AuditLogService::log_update_resource(
                 &state.pool,
                 user.id,
@@ -1366,6 +1422,61 @@ pub async fn update_resource_relations(
     }
 }
 
+/// 为已有资源追加课程/教师关联
+/// 重复上传被拦截后，上传者可选择把自己的关联信息补充到原资源上
+#[post("/resources/{resource_id}/relations/attach")]
+pub async fn attach_resource_relations(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+    request: web::Json<AttachResourceRelationsRequest>,
+    req: HttpRequest,
+) -> impl Responder {
+    let resource_id = path.into_inner();
+
+    match ResourceService::attach_resource_relations(&state.pool, &user, resource_id, &request)
+        .await
+    {
+        Ok(title) => {
+            // 记录审计日志
+            let ip_address = client_ip(&req);
+            if let Err(e) = AuditLogService::log_update_resource(
+                &state.pool,
+                user.id,
+                resource_id,
+                &title,
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录追加资源关联日志失败 | resource_id={}, error={}",
+                    resource_id,
+                    e
+                );
+            }
+
+            HttpResponse::Ok().json(serde_json::json!({
+                "message": "关联信息已追加"
+            }))
+        }
+        Err(e) => {
+            log::warn!(
+                "[Resource] 追加资源关联失败 | resource_id={}, user_id={}, error={}",
+                resource_id,
+                user.id,
+                e
+            );
+            match e {
+                ResourceError::NotFound(msg) => not_found(&msg),
+                ResourceError::Unauthorized(msg) => forbidden(&msg),
+                ResourceError::ValidationError(msg) => bad_request(&msg),
+                _ => internal_error("追加关联信息失败"),
+            }
+        }
+    }
+}
+
 /// 配置资源路由（需要认证）
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(upload_resource)
@@ -1378,5 +1489,6 @@ pub fn config(cfg: &mut web::ServiceConfig) {
         .service(create_comment)
         .service(update_resource_content)
         .service(get_resource_raw_content)
-        .service(update_resource_relations);
+        .service(update_resource_relations)
+        .service(attach_resource_relations);
 }
@@ -0,0 +1,103 @@
+use actix_web::{delete, get, post, web, HttpResponse, Responder};
+use uuid::Uuid;
+
+use crate::db::AppState;
+use crate::models::{AddResourceEditorRequest, CurrentUser};
+use crate::services::{ResourceEditorService, ResourceError};
+use crate::utils::{
+    bad_request, conflict, created, forbidden, internal_error, no_content, not_found, unauthorized,
+};
+
+/// 将协作者相关错误转换为 HTTP 响应
+fn editor_error_response(err: ResourceError) -> HttpResponse {
+    match err {
+        ResourceError::NotFound(msg) => not_found(&msg),
+        ResourceError::ValidationError(msg) => bad_request(&msg),
+        ResourceError::Unauthorized(msg) => forbidden(&msg),
+        ResourceError::Conflict(msg) => conflict(&msg),
+        e => {
+            log::error!("[Resource] 资源协作者操作失败 | error={}", e);
+            internal_error("服务器内部错误")
+        }
+    }
+}
+
+/// 获取资源协作者列表
+/// GET /api/resources 对游客公开，这里需要自行检查登录状态
+#[get("/resources/{resource_id}/editors")]
+pub async fn list_editors(
+    state: web::Data<AppState>,
+    user: Option<web::ReqData<CurrentUser>>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let Some(user) = user else {
+        return unauthorized("请先登录");
+    };
+    let resource_id = path.into_inner();
+
+    match ResourceEditorService::list_editors(&state.pool, &user, resource_id).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => editor_error_response(e),
+    }
+}
+
+/// 邀请协作者
+#[post("/resources/{resource_id}/editors")]
+pub async fn add_editor(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+    request: web::Json<AddResourceEditorRequest>,
+) -> impl Responder {
+    let resource_id = path.into_inner();
+
+    if let Err(msg) = request.validate() {
+        return bad_request(&msg);
+    }
+
+    match ResourceEditorService::add_editor(&state.pool, &user, resource_id, &request.username)
+        .await
+    {
+        Ok(editor) => created(editor),
+        Err(e) => {
+            log::warn!(
+                "[Resource] 邀请协作者失败 | resource_id={}, user_id={}, error={}",
+                resource_id,
+                user.id,
+                e
+            );
+            editor_error_response(e)
+        }
+    }
+}
+
+/// 移除协作者（协作者传入自己的 ID 即为退出协作）
+#[delete("/resources/{resource_id}/editors/{user_id}")]
+pub async fn remove_editor(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<(Uuid, Uuid)>,
+) -> impl Responder {
+    let (resource_id, editor_id) = path.into_inner();
+
+    match ResourceEditorService::remove_editor(&state.pool, &user, resource_id, editor_id).await {
+        Ok(()) => no_content(),
+        Err(e) => {
+            log::warn!(
+                "[Resource] 移除协作者失败 | resource_id={}, editor_id={}, user_id={}, error={}",
+                resource_id,
+                editor_id,
+                user.id,
+                e
+            );
+            editor_error_response(e)
+        }
+    }
+}
+
+/// 配置资源协作者路由
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_editors)
+        .service(add_editor)
+        .service(remove_editor);
+}
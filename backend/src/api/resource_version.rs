@@ -0,0 +1,344 @@
+use actix_multipart::Multipart;
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use futures_util::StreamExt;
+use uuid::Uuid;
+
+use crate::api::resource::{build_content_disposition, sanitize_filename};
+use crate::db::AppState;
+use crate::models::{CurrentUser, ResourceVersionDiffQuery, UploadedFile};
+use crate::services::{
+    AuditLogService, FileService, ResourceError, ResourceVersionService, StorageError,
+};
+use crate::utils::{
+    bad_request, client_ip, conflict, created, forbidden, internal_error, not_found,
+    stream_storage_file,
+};
+
+/// 将版本相关错误转换为 HTTP 响应
+fn version_error_response(err: ResourceError) -> HttpResponse {
+    match err {
+        ResourceError::NotFound(msg) => not_found(&msg),
+        ResourceError::ValidationError(msg) => bad_request(&msg),
+        ResourceError::Unauthorized(msg) => forbidden(&msg),
+        ResourceError::Conflict(msg) => conflict(&msg),
+        e => {
+            log::error!("[Resource] 资源版本操作失败 | error={}", e);
+            internal_error("服务器内部错误")
+        }
+    }
+}
+
+/// 获取资源版本列表
+#[get("/resources/{resource_id}/versions")]
+pub async fn list_versions(
+    state: web::Data<AppState>,
+    user: Option<web::ReqData<CurrentUser>>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let resource_id = path.into_inner();
+
+    match ResourceVersionService::list_versions(&state.pool, user.as_deref(), resource_id).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => version_error_response(e),
+    }
+}
+
+/// 比较两个 Markdown 版本的差异
+#[get("/resources/{resource_id}/versions/diff")]
+pub async fn diff_versions(
+    state: web::Data<AppState>,
+    user: Option<web::ReqData<CurrentUser>>,
+    path: web::Path<Uuid>,
+    query: web::Query<ResourceVersionDiffQuery>,
+) -> impl Responder {
+    let resource_id = path.into_inner();
+
+    match ResourceVersionService::diff_versions(
+        &state.pool,
+        &state.storages,
+        user.as_deref(),
+        resource_id,
+        &query,
+    )
+    .await
+    {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => version_error_response(e),
+    }
+}
+
+/// 下载资源的指定版本
+#[get("/resources/{resource_id}/versions/{version}/download")]
+pub async fn download_version(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<(Uuid, i32)>,
+    req: HttpRequest,
+) -> impl Responder {
+    let (resource_id, version_number) = path.into_inner();
+
+    let (version, title) = match ResourceVersionService::get_version_file(
+        &state.pool,
+        Some(&*user),
+        resource_id,
+        version_number,
+    )
+    .await
+    {
+        Ok(result) => result,
+        Err(e) => return version_error_response(e),
+    };
+
+    let storage = match state
+        .storages
+        .for_storage_type(version.storage_type.as_deref())
+    {
+        Ok(storage) => storage,
+        Err(e) => return version_error_response(e.into()),
+    };
+
+    let content_type = FileService::get_mime_type_by_type(&version.resource_type);
+    let extension = FileService::get_extension_by_type(&version.resource_type);
+    let filename = format!(
+        "{}_v{}.{}",
+        sanitize_filename(&title),
+        version_number,
+        extension
+    );
+
+    log::info!(
+        "[Resource] 下载资源历史版本 | resource_id={}, version={}, user_id={}",
+        resource_id,
+        version_number,
+        user.id
+    );
+
+    if storage.backend_type().is_remote() {
+        // OSS/S3 存储：生成签名下载 URL
+        let expires_secs = storage.default_signed_url_expiry();
+        return match storage
+            .get_download_url(&version.file_path, &filename, expires_secs)
+            .await
+        {
+            Ok(download_url) => HttpResponse::Found()
+                .insert_header(("Location", download_url))
+                .finish(),
+            Err(e) => {
+                log::warn!(
+                    "[Resource] 生成版本下载链接失败 | resource_id={}, version={}, error={}",
+                    resource_id,
+                    version_number,
+                    e
+                );
+                internal_error("生成下载链接失败")
+            }
+        };
+    }
+
+    let content_disposition = build_content_disposition(&filename);
+    match stream_storage_file(
+        &req,
+        storage,
+        &version.file_path,
+        &content_type,
+        &[("Content-Disposition", content_disposition.as_str())],
+    )
+    .await
+    {
+        Ok(response) => response,
+        Err(StorageError::NotFound(_)) => not_found("文件不存在"),
+        Err(e) => {
+            log::warn!(
+                "[Resource] 读取版本文件失败 | resource_id={}, version={}, error={}",
+                resource_id,
+                version_number,
+                e
+            );
+            internal_error("文件读取失败")
+        }
+    }
+}
+
+/// 重新上传文件作为资源的新版本
+/// multipart 表单：file 为新文件，changeNote 为可选的版本说明
+#[post("/resources/{resource_id}/versions")]
+pub async fn upload_version(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+    mut payload: Multipart,
+    req: HttpRequest,
+) -> impl Responder {
+    let resource_id = path.into_inner();
+    let mut change_note: Option<String> = None;
+    let mut file_data: Option<(String, Vec<u8>, Option<String>)> = None;
+
+    while let Some(item) = payload.next().await {
+        let mut field = match item {
+            Ok(field) => field,
+            Err(e) => {
+                log::warn!(
+                    "[Resource] 解析版本上传数据失败 | user_id={}, error={}",
+                    user.id,
+                    e
+                );
+                return bad_request("解析上传数据失败");
+            }
+        };
+
+        let content_disposition = field.content_disposition();
+        let field_name = content_disposition
+            .get_name()
+            .unwrap_or("unknown")
+            .to_string();
+        let filename = content_disposition
+            .get_filename()
+            .unwrap_or("unnamed.bin")
+            .to_string();
+        let mime_type = field.content_type().map(|m| m.to_string());
+
+        let mut data = Vec::new();
+        while let Some(chunk) = field.next().await {
+            match chunk {
+                Ok(bytes) => data.extend_from_slice(&bytes),
+                Err(e) => {
+                    log::warn!(
+                        "[Resource] 读取版本上传数据失败 | user_id={}, error={}",
+                        user.id,
+                        e
+                    );
+                    return bad_request("读取上传数据失败");
+                }
+            }
+        }
+
+        match field_name.as_str() {
+            "file" => file_data = Some((filename, data, mime_type)),
+            "changeNote" => change_note = Some(String::from_utf8_lossy(&data).into_owned()),
+            _ => {}
+        }
+    }
+
+    let Some((filename, data, mime_type)) = file_data else {
+        return bad_request("请选择要上传的文件");
+    };
+
+    match ResourceVersionService::upload_version(
+        &state.pool,
+        &user,
+        &state.storages,
+        &state.moderation,
+        resource_id,
+        UploadedFile {
+            name: &filename,
+            data,
+            mime_type: mime_type.as_deref(),
+        },
+        change_note.as_deref(),
+    )
+    .await
+    {
+        Ok(response) => {
+            let ip_address = client_ip(&req);
+            if let Err(e) = AuditLogService::log_update_resource(
+                &state.pool,
+                user.id,
+                resource_id,
+                &response.title,
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录资源更新日志失败 | resource_id={}, error={}",
+                    resource_id,
+                    e
+                );
+            }
+
+            created(response)
+        }
+        Err(e) => {
+            log::warn!(
+                "[Resource] 上传资源新版本失败 | resource_id={}, user_id={}, error={}",
+                resource_id,
+                user.id,
+                e
+            );
+            version_error_response(e)
+        }
+    }
+}
+
+/// 回滚到指定版本（以该版本内容创建新版本）
+#[post("/resources/{resource_id}/versions/{version}/rollback")]
+pub async fn rollback_version(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<(Uuid, i32)>,
+    req: HttpRequest,
+) -> impl Responder {
+    let (resource_id, version_number) = path.into_inner();
+
+    match ResourceVersionService::rollback(
+        &state.pool,
+        &user,
+        &state.storages,
+        &state.moderation,
+        resource_id,
+        version_number,
+    )
+    .await
+    {
+        Ok(response) => {
+            let ip_address = client_ip(&req);
+            if let Err(e) = AuditLogService::log_rollback_resource(
+                &state.pool,
+                user.id,
+                resource_id,
+                &response.title,
+                version_number,
+                response.version_number,
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录资源回滚日志失败 | resource_id={}, error={}",
+                    resource_id,
+                    e
+                );
+            }
+
+            log::info!(
+                "[Resource] 资源版本回滚成功 | resource_id={}, target_version={}, new_version={}, user_id={}",
+                resource_id,
+                version_number,
+                response.version_number,
+                user.id
+            );
+
+            HttpResponse::Ok().json(response)
+        }
+        Err(e) => {
+            log::warn!(
+                "[Resource] 资源版本回滚失败 | resource_id={}, version={}, user_id={}, error={}",
+                resource_id,
+                version_number,
+                user.id,
+                e
+            );
+            version_error_response(e)
+        }
+    }
+}
+
+/// 配置资源版本路由
+/// 版本列表和差异随 /api/resources 公开（游客只能看到通过审核的版本），下载与资源下载一样需要登录
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_versions)
+        .service(diff_versions) // /versions/diff（先于 /versions/{version}/... 注册）
+        .service(download_version)
+        .service(upload_version)
+        .service(rollback_version);
+}
@@ -0,0 +1,455 @@
+use actix_web::cookie::{time::Duration as CookieDuration, Cookie, SameSite};
+use actix_web::http::header;
+use actix_web::{delete, get, web, HttpRequest, HttpResponse, Responder};
+use reqwest::Url;
+use uuid::Uuid;
+
+use crate::api::auth::{client_info, set_login_cookies};
+use crate::config::Config;
+use crate::db::AppState;
+use crate::models::{CurrentUser, ExternalIdentity, LoginOutcome, SsoCallbackQuery, SsoProvider};
+use crate::services::{
+    AuditLogService, AuthService, CasClient, OidcClient, SsoError, SsoLoginState, SsoService,
+    SSO_LOGIN_STATE_TTL_MINUTES,
+};
+use crate::utils::{bad_request, client_ip, conflict, forbidden, internal_error, not_found};
+
+/// 登录状态 Cookie：把 state 绑定到发起登录的浏览器，防止登录 CSRF
+const SSO_STATE_COOKIE: &str = "sso_state";
+const SSO_STATE_COOKIE_PATH: &str = "/api/auth/sso";
+
+fn state_cookie(value: &str, secure: bool) -> Cookie<'_> {
+    Cookie::build(SSO_STATE_COOKIE, value)
+        .http_only(true)
+        .secure(secure)
+        // 身份提供方跳转回来属于跨站的顶层 GET 导航，Lax 下 Cookie 仍会携带
+        .same_site(SameSite::Lax)
+        .path(SSO_STATE_COOKIE_PATH)
+        .max_age(CookieDuration::minutes(SSO_LOGIN_STATE_TTL_MINUTES))
+        .finish()
+}
+
+fn clear_state_cookie(secure: bool) -> Cookie<'static> {
+    Cookie::build(SSO_STATE_COOKIE, "")
+        .http_only(true)
+        .secure(secure)
+        .same_site(SameSite::Lax)
+        .path(SSO_STATE_COOKIE_PATH)
+        .max_age(CookieDuration::seconds(0))
+        .finish()
+}
+
+/// 前端回调页地址，附带结果参数
+///
+/// 凭证类参数放在 URL 片段中：片段不会发送给服务器，也不会出现在访问日志和 Referer 里
+fn frontend_url(
+    config: &Config,
+    params: &[(&str, &str)],
+    fragment_params: &[(&str, &str)],
+) -> String {
+    match Url::parse(&config.sso_redirect_url) {
+        Ok(mut url) => {
+            if !params.is_empty() {
+                url.query_pairs_mut().extend_pairs(params);
+            }
+            if !fragment_params.is_empty() {
+                let fragment = url::form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(fragment_params)
+                    .finish();
+                url.set_fragment(Some(&fragment));
+            }
+            url.into()
+        }
+        Err(e) => {
+            log::error!(
+                "[SSO] SSO_REDIRECT_URL 无效 | url={}, error={}",
+                config.sso_redirect_url,
+                e
+            );
+            config.sso_redirect_url.clone()
+        }
+    }
+}
+
+/// 跳转回前端并清除登录状态 Cookie
+fn redirect_to_frontend(
+    state: &AppState,
+    config: &Config,
+    params: &[(&str, &str)],
+) -> HttpResponse {
+    HttpResponse::Found()
+        .insert_header((header::LOCATION, frontend_url(config, params, &[])))
+        .cookie(clear_state_cookie(state.cookie_secure))
+        .finish()
+}
+
+/// 统一身份认证失败：跳转回前端，通过 ssoError 参数展示原因
+fn redirect_with_error(
+    state: &AppState,
+    config: &Config,
+    provider: SsoProvider,
+    err: SsoError,
+) -> HttpResponse {
+    let message = match err {
+        SsoError::DatabaseError(_) | SsoError::InternalError(_) => {
+            log::error!(
+                "[SSO] 服务器内部错误 | provider={}, error={}",
+                provider.as_str(),
+                err
+            );
+            "服务器内部错误".to_string()
+        }
+        SsoError::NotConfigured(_) => {
+            log::warn!(
+                "[SSO] 统一身份认证未启用 | provider={}, error={}",
+                provider.as_str(),
+                err
+            );
+            "该登录方式未启用".to_string()
+        }
+        SsoError::ProviderError(_) => {
+            log::warn!(
+                "[SSO] 身份提供方错误 | provider={}, error={}",
+                provider.as_str(),
+                err
+            );
+            "统一身份认证服务暂时不可用，请稍后重试".to_string()
+        }
+        SsoError::InvalidState(msg)
+        | SsoError::InvalidAssertion(msg)
+        | SsoError::Conflict(msg)
+        | SsoError::NotFound(msg)
+        | SsoError::Forbidden(msg) => {
+            log::warn!(
+                "[SSO] 统一身份认证失败 | provider={}, error={}",
+                provider.as_str(),
+                msg
+            );
+            msg
+        }
+    };
+    redirect_to_frontend(state, config, &[("ssoError", &message)])
+}
+
+/// 创建登录状态并跳转到身份提供方
+async fn start_login(
+    state: &AppState,
+    config: &Config,
+    provider: SsoProvider,
+    link_user_id: Option<Uuid>,
+) -> HttpResponse {
+    let result = async {
+        let login_state =
+            SsoService::create_login_state(&state.pool, provider, link_user_id).await?;
+        let url = match provider {
+            SsoProvider::Oidc => {
+                OidcClient::from_config(config)?
+                    .authorization_url(
+                        &login_state.state,
+                        login_state.nonce.as_deref().unwrap_or_default(),
+                        login_state.code_verifier.as_deref().unwrap_or_default(),
+                    )
+                    .await?
+            }
+            SsoProvider::Cas => CasClient::from_config(config)?.login_url(&login_state.state)?,
+        };
+        Ok::<_, SsoError>((login_state.state, url))
+    }
+    .await;
+
+    match result {
+        Ok((login_state, url)) => HttpResponse::Found()
+            .insert_header((header::LOCATION, url))
+            .cookie(state_cookie(&login_state, state.cookie_secure))
+            .finish(),
+        Err(e) => redirect_with_error(state, config, provider, e),
+    }
+}
+
+/// 校验回调中的 state 与发起登录的浏览器一致，取出登录状态
+async fn take_login_state(
+    state: &AppState,
+    http_req: &HttpRequest,
+    provider: SsoProvider,
+    query: &SsoCallbackQuery,
+) -> Result<SsoLoginState, SsoError> {
+    let presented = query.state.as_deref().unwrap_or_default();
+    let expected = http_req.cookie(SSO_STATE_COOKIE);
+    if presented.is_empty() || expected.as_ref().map(|c| c.value()) != Some(presented) {
+        return Err(SsoError::InvalidState(
+            "登录请求无效或已过期，请重新登录".to_string(),
+        ));
+    }
+    SsoService::consume_login_state(&state.pool, provider, presented).await
+}
+
+/// 身份提供方认证通过：关联账号后登录，或完成绑定
+async fn finish_login(
+    state: &AppState,
+    config: &Config,
+    http_req: &HttpRequest,
+    provider: SsoProvider,
+    login_state: SsoLoginState,
+    identity: ExternalIdentity,
+) -> HttpResponse {
+    let result = match SsoService::complete_login(
+        &state.pool,
+        provider,
+        &identity,
+        login_state.link_user_id,
+    )
+    .await
+    {
+        Ok(result) => result,
+        Err(e) => return redirect_with_error(state, config, provider, e),
+    };
+
+    // 绑定操作：用户已经登录，不创建新会话
+    if let Some(user_id) = login_state.link_user_id {
+        let ip_address = client_ip(http_req);
+        if let Err(e) = AuditLogService::log_link_identity(
+            &state.pool,
+            user_id,
+            result.identity_id,
+            provider.as_str(),
+            ip_address.as_deref(),
+        )
+        .await
+        {
+            log::warn!(
+                "[Audit] 记录绑定外部身份日志失败 | user_id={}, error={}",
+                user_id,
+                e
+            );
+        }
+        return redirect_to_frontend(state, config, &[("ssoLinked", provider.as_str())]);
+    }
+
+    // 首次登录自动创建的账号可提示用户修改用户名；完成实名认证时提示刷新状态
+    let mut params = Vec::new();
+    if result.created {
+        params.push(("ssoCreated", "true"));
+    }
+    if result.verified {
+        params.push(("ssoVerified", "true"));
+    }
+
+    let client = client_info(http_req);
+    match AuthService::login_with_identity(&state.pool, &state.jwt_secret, result.user_id, &client)
+        .await
+    {
+        Ok(LoginOutcome::Authenticated(response)) => {
+            let mut builder = HttpResponse::Found();
+            builder
+                .insert_header((header::LOCATION, frontend_url(config, &params, &[])))
+                .cookie(clear_state_cookie(state.cookie_secure));
+            set_login_cookies(state, http_req, &mut builder, &response).await;
+            builder.finish()
+        }
+        // 临时凭证只能配合动态码或恢复码使用，前端从 URL 片段读取后继续调用 /auth/login/mfa
+        Ok(LoginOutcome::MfaRequired(challenge)) => {
+            log::info!("[SSO] 等待两步验证 | user_id={}", result.user_id);
+            let location = frontend_url(config, &params, &[("mfaToken", &challenge.mfa_token)]);
+            HttpResponse::Found()
+                .insert_header((header::LOCATION, location))
+                .cookie(clear_state_cookie(state.cookie_secure))
+                .finish()
+        }
+        Err(e) => {
+            log::warn!("[SSO] 登录失败 | user_id={}, error={}", result.user_id, e);
+            redirect_with_error(
+                state,
+                config,
+                provider,
+                SsoError::Forbidden("登录失败，账号不存在或已被禁用".to_string()),
+            )
+        }
+    }
+}
+
+/// 已启用的统一身份认证方式
+#[get("/auth/sso/providers")]
+pub async fn get_sso_providers(state: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().json(SsoService::providers(&state.config))
+}
+
+/// 发起 OIDC 登录：跳转到身份提供方的授权页面
+#[get("/auth/sso/oidc/login")]
+pub async fn oidc_login(state: web::Data<AppState>) -> impl Responder {
+    start_login(&state, &state.config, SsoProvider::Oidc, None).await
+}
+
+/// OIDC 授权回调：用授权码换取并校验 ID Token
+#[get("/auth/sso/oidc/callback")]
+pub async fn oidc_callback(
+    state: web::Data<AppState>,
+    query: web::Query<SsoCallbackQuery>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let result = async {
+        let login_state = take_login_state(&state, &http_req, SsoProvider::Oidc, &query).await?;
+        if let Some(error) = &query.error {
+            return Err(SsoError::InvalidAssertion(format!(
+                "身份提供方拒绝了授权: {}",
+                error
+            )));
+        }
+        let code = query
+            .code
+            .as_deref()
+            .ok_or_else(|| SsoError::InvalidAssertion("回调缺少授权码".to_string()))?;
+        let identity = OidcClient::from_config(&state.config)?
+            .exchange_code(
+                code,
+                login_state.nonce.as_deref().unwrap_or_default(),
+                login_state.code_verifier.as_deref().unwrap_or_default(),
+            )
+            .await?;
+        Ok((login_state, identity))
+    }
+    .await;
+
+    match result {
+        Ok((login_state, identity)) => {
+            finish_login(
+                &state,
+                &state.config,
+                &http_req,
+                SsoProvider::Oidc,
+                login_state,
+                identity,
+            )
+            .await
+        }
+        Err(e) => redirect_with_error(&state, &state.config, SsoProvider::Oidc, e),
+    }
+}
+
+/// 发起 CAS 登录：跳转到 CAS 登录页
+#[get("/auth/sso/cas/login")]
+pub async fn cas_login(state: web::Data<AppState>) -> impl Responder {
+    start_login(&state, &state.config, SsoProvider::Cas, None).await
+}
+
+/// CAS 登录回调：向 CAS 服务器校验票据
+#[get("/auth/sso/cas/callback")]
+pub async fn cas_callback(
+    state: web::Data<AppState>,
+    query: web::Query<SsoCallbackQuery>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let result = async {
+        let login_state = take_login_state(&state, &http_req, SsoProvider::Cas, &query).await?;
+        let ticket = query
+            .ticket
+            .as_deref()
+            .ok_or_else(|| SsoError::InvalidAssertion("回调缺少票据".to_string()))?;
+        let identity = CasClient::from_config(&state.config)?
+            .validate_ticket(ticket, &login_state.state)
+            .await?;
+        Ok((login_state, identity))
+    }
+    .await;
+
+    match result {
+        Ok((login_state, identity)) => {
+            finish_login(
+                &state,
+                &state.config,
+                &http_req,
+                SsoProvider::Cas,
+                login_state,
+                identity,
+            )
+            .await
+        }
+        Err(e) => redirect_with_error(&state, &state.config, SsoProvider::Cas, e),
+    }
+}
+
+/// 绑定外部身份：跳转到身份提供方，回调后绑定到当前账号
+#[get("/users/me/identities/{provider}/link")]
+pub async fn link_identity(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let Some(provider) = SsoProvider::parse(&path) else {
+        return not_found("不支持的登录方式");
+    };
+    start_login(&state, &state.config, provider, Some(user.id)).await
+}
+
+/// 获取已绑定的外部身份
+#[get("/users/me/identities")]
+pub async fn get_my_identities(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+) -> impl Responder {
+    match SsoService::list_identities(&state.pool, user.id).await {
+        Ok(identities) => HttpResponse::Ok().json(identities),
+        Err(e) => identity_error_response(e),
+    }
+}
+
+/// 解除绑定外部身份
+#[delete("/users/me/identities/{identity_id}")]
+pub async fn unlink_identity(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let identity_id = path.into_inner();
+
+    match SsoService::unlink_identity(&state.pool, user.id, identity_id).await {
+        Ok(provider) => {
+            let ip_address = client_ip(&http_req);
+            if let Err(e) = AuditLogService::log_unlink_identity(
+                &state.pool,
+                user.id,
+                identity_id,
+                &provider,
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录解除绑定外部身份日志失败 | user_id={}, error={}",
+                    user.id,
+                    e
+                );
+            }
+            HttpResponse::Ok().json(serde_json::json!({
+                "message": "已解除绑定"
+            }))
+        }
+        Err(e) => identity_error_response(e),
+    }
+}
+
+/// 将外部身份管理相关错误转换为 HTTP 响应
+fn identity_error_response(err: SsoError) -> HttpResponse {
+    match err {
+        SsoError::NotFound(msg) => not_found(&msg),
+        SsoError::Conflict(msg) => conflict(&msg),
+        SsoError::Forbidden(msg) => forbidden(&msg),
+        SsoError::InvalidState(msg) | SsoError::InvalidAssertion(msg) => bad_request(&msg),
+        e => {
+            log::error!("[SSO] 服务器内部错误 | error={}", e);
+            internal_error("服务器内部错误")
+        }
+    }
+}
+
+/// 配置统一身份认证路由（/auth/sso 公开，/users/me/identities 需要认证）
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_sso_providers)
+        .service(oidc_login)
+        .service(oidc_callback)
+        .service(cas_login)
+        .service(cas_callback)
+        .service(link_identity)
+        .service(get_my_identities)
+        .service(unlink_identity);
+}
@@ -0,0 +1,170 @@
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
+use futures_util::StreamExt;
+use uuid::Uuid;
+
+use crate::api::resource::upload_error_response;
+use crate::db::AppState;
+use crate::models::{resource::UploadResourceRequest, CurrentUser, InitUploadSessionRequest};
+use crate::services::{AuditLogService, UploadSessionService};
+use crate::utils::{bad_request, client_ip, created, no_content};
+
+/// 创建分片上传会话
+#[post("/resources/uploads")]
+pub async fn init_upload_session(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    request: web::Json<InitUploadSessionRequest>,
+) -> impl Responder {
+    match UploadSessionService::init_session(&state.pool, &user, &state.storage, &request).await {
+        Ok(session) => created(session),
+        Err(e) => {
+            log::warn!(
+                "[Upload] 创建分片上传会话失败 | user_id={}, error={}",
+                user.id,
+                e
+            );
+            upload_error_response(e)
+        }
+    }
+}
+
+/// 查询分片上传会话状态
+#[get("/resources/uploads/{session_id}")]
+pub async fn get_upload_session(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    match UploadSessionService::get_session(&state.pool, &user, path.into_inner()).await {
+        Ok(session) => HttpResponse::Ok().json(session),
+        Err(e) => upload_error_response(e),
+    }
+}
+
+/// 上传单个分片（请求体为分片原始字节）
+#[put("/resources/uploads/{session_id}/chunks/{index}")]
+pub async fn upload_chunk(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<(Uuid, i32)>,
+    mut payload: web::Payload,
+) -> impl Responder {
+    let (session_id, index) = path.into_inner();
+
+    // 直接读取原始请求体，超过最大分片大小立即中止
+    let mut data = Vec::new();
+    while let Some(chunk) = payload.next().await {
+        match chunk {
+            Ok(bytes) => {
+                if (data.len() + bytes.len()) as i64 > UploadSessionService::MAX_CHUNK_SIZE {
+                    return bad_request("分片大小超过限制");
+                }
+                data.extend_from_slice(&bytes);
+            }
+            Err(e) => {
+                log::warn!(
+                    "[Upload] 读取分片数据失败 | session_id={}, index={}, error={}",
+                    session_id,
+                    index,
+                    e
+                );
+                return bad_request("读取分片数据失败");
+            }
+        }
+    }
+
+    match UploadSessionService::put_chunk(
+        &state.pool,
+        &user,
+        &state.storage,
+        session_id,
+        index,
+        data,
+    )
+    .await
+    {
+        Ok(session) => HttpResponse::Ok().json(session),
+        Err(e) => {
+            log::warn!(
+                "[Upload] 上传分片失败 | session_id={}, index={}, error={}",
+                session_id,
+                index,
+                e
+            );
+            upload_error_response(e)
+        }
+    }
+}
+
+/// 合并分片并创建资源（请求体与普通上传的 metadata 相同）
+#[post("/resources/uploads/{session_id}/complete")]
+pub async fn complete_upload_session(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+    metadata: web::Json<UploadResourceRequest>,
+    req: HttpRequest,
+) -> impl Responder {
+    let session_id = path.into_inner();
+
+    match UploadSessionService::complete_session(
+        &state.pool,
+        &user,
+        &state.storage,
+        &state.moderation,
+        session_id,
+        metadata.into_inner(),
+    )
+    .await
+    {
+        Ok(response) => {
+            // 记录审计日志
+            let ip_address = client_ip(&req);
+
+            let _ = AuditLogService::log_upload_resource(
+                &state.pool,
+                user.id,
+                response.id,
+                &response.title,
+                &response.resource_type,
+                ip_address.as_deref(),
+            )
+            .await;
+
+            HttpResponse::Created().json(response)
+        }
+        Err(e) => {
+            log::warn!(
+                "[Upload] 完成分片上传失败 | session_id={}, user_id={}, error={}",
+                session_id,
+                user.id,
+                e
+            );
+            upload_error_response(e)
+        }
+    }
+}
+
+/// 取消分片上传
+#[delete("/resources/uploads/{session_id}")]
+pub async fn abort_upload_session(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    match UploadSessionService::abort_session(&state.pool, &user, &state.storage, path.into_inner())
+        .await
+    {
+        Ok(()) => no_content(),
+        Err(e) => upload_error_response(e),
+    }
+}
+
+/// 配置分片上传路由（需要认证）
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(init_upload_session)
+        .service(get_upload_session)
+        .service(upload_chunk)
+        .service(complete_upload_session)
+        .service(abort_upload_session);
+}
@@ -1,14 +1,17 @@
+use crate::api::verification::verification_error_response;
 use crate::db::AppState;
 use crate::models::{
-    CurrentUser, UpdateProfileRequest, UserHomepageQuery, UserRole, VerificationRequest,
+    resource::RecommendationQuery, ChangePasswordRequest, ClientInfo, CurrentUser,
+    CurrentUserResponse, PermissionInfo, UpdateProfileRequest, UserHomepageQuery, UserRole,
+    VerificationRequest, VerificationStatus, REVOKE_REASON_USER,
 };
-use crate::services::{AuditLogService, UserError, UserService};
-use crate::utils::{
-    bad_request, forbidden, generate_access_token, generate_refresh_token, internal_error,
-    not_found,
+use crate::services::{
+    AiService, AuditLogService, AuthError, AuthService, PermissionService, SessionService,
+    StorageQuotaService, UserError, UserService, VerificationService,
 };
+use crate::utils::{bad_request, client_ip, forbidden, internal_error, not_found, unauthorized};
 use actix_web::cookie::{time::Duration as CookieDuration, Cookie, SameSite};
-use actix_web::{get, post, put, web, HttpRequest, HttpResponse, Responder};
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
 use uuid::Uuid;
 
 /// Cookie 名称常量
@@ -46,7 +49,21 @@ pub async fn get_current_user(
                 user.id,
                 user_info.username
             );
-            HttpResponse::Ok().json(user_info)
+            let storage = match StorageQuotaService::get_usage(&state.pool, user.id).await {
+                Ok(storage) => Some(storage),
+                Err(e) => {
+                    log::error!(
+                        "[User] 获取存储空间使用情况失败 | user_id={}, error={}",
+                        user.id,
+                        e
+                    );
+                    None
+                }
+            };
+            HttpResponse::Ok().json(CurrentUserResponse {
+                user: user_info,
+                storage,
+            })
         }
         Err(e) => {
             log::warn!(
@@ -62,6 +79,26 @@ pub async fn get_current_user(
     }
 }
 
+/// 获取当前用户拥有的管理权限（管理员为全部权限），前端据此展示管理入口
+#[get("/users/me/permissions")]
+pub async fn get_my_permissions(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+) -> impl Responder {
+    match PermissionService::get_effective_permissions(&state.pool, &user).await {
+        Ok(permissions) => HttpResponse::Ok().json(serde_json::json!({
+            "permissions": permissions
+                .into_iter()
+                .map(PermissionInfo::from)
+                .collect::<Vec<_>>()
+        })),
+        Err(e) => {
+            log::error!("[User] 获取管理权限失败 | user_id={}, error={}", user.id, e);
+            internal_error("获取管理权限失败")
+        }
+    }
+}
+
 /// 更新当前用户资料
 #[put("/users/me")]
 pub async fn update_profile(
@@ -81,12 +118,40 @@ pub async fn update_profile(
 
     log::info!("[User] 更新用户资料 | user_id={}", user.id);
 
+    // 记录修改前的邮箱，邮箱变化时发送确认邮件
+    let previous_email = UserService::get_current_user(&state.pool, user.id)
+        .await
+        .ok()
+        .and_then(|info| info.email);
+
     match UserService::update_profile(&state.pool, user.id, req.into_inner(), is_verified).await {
         Ok(user_info) => {
             log::info!("[User] 用户资料更新成功 | user_id={}", user.id);
 
+            let email_changed = user_info
+                .email
+                .as_deref()
+                .is_some_and(|email| !email.trim().is_empty())
+                && user_info.email != previous_email;
+            if email_changed {
+                if let Err(e) = VerificationService::send_email_confirmation(
+                    &state.pool,
+                    state.mailer.clone(),
+                    &state.config,
+                    user.id,
+                )
+                .await
+                {
+                    log::warn!(
+                        "[User] 发送邮箱确认邮件失败 | user_id={}, error={}",
+                        user.id,
+                        e
+                    );
+                }
+            }
+
             // 记录审计日志
-            let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
+            let ip_address = client_ip(&http_req);
             if let Err(e) = AuditLogService::log_update_profile(
                 &state.pool,
                 user.id,
@@ -120,6 +185,9 @@ pub async fn update_profile(
 }
 
 /// 实名认证
+///
+/// 已确认校园邮箱的用户立即通过并更新 Token；
+/// 否则申请进入待审核状态（返回 202），由管理员审核或确认校园邮箱后自动通过
 #[post("/users/verify")]
 pub async fn verify_user(
     state: web::Data<AppState>,
@@ -131,7 +199,22 @@ pub async fn verify_user(
         return bad_request("用户已完成实名认证");
     }
 
-    match UserService::verify_user(&state.pool, user.id, req.into_inner()).await {
+    let request =
+        match VerificationService::submit_verification(&state.pool, &state.config, user.id, &req)
+            .await
+        {
+            Ok(request) => request,
+            Err(e) => {
+                log::warn!("[User] 实名认证失败 | user_id={}, error={}", user.id, e);
+                return verification_error_response(e);
+            }
+        };
+
+    if request.status != VerificationStatus::Approved.as_str() {
+        return HttpResponse::Accepted().json(request);
+    }
+
+    match UserService::get_current_user(&state.pool, user.id).await {
         Ok(user_info) => {
             // 实名认证成功，生成新的 Token（保持原有角色）
             let user_role = match user_info.role.as_str() {
@@ -140,35 +223,30 @@ pub async fn verify_user(
                 "user" => UserRole::User,
                 _ => UserRole::Guest,
             };
-            let access_token = match generate_access_token(
-                user_info.id,
-                user_info.username.clone(),
-                user_role.clone(),
-                user_info.is_verified,
-                &state.jwt_secret,
-            ) {
-                Ok(token) => token,
-                Err(e) => {
-                    log::error!(
-                        "[Auth] 生成访问令牌失败 | user_id={}, error={}",
-                        user_info.id,
-                        e
-                    );
-                    return internal_error("认证成功但生成令牌失败，请重新登录");
-                }
-            };
-
-            let refresh_token = match generate_refresh_token(
+            // 沿用当前会话，轮换 Refresh Token
+            let tokens = match SessionService::rotate_refresh_token(
+                &state.pool,
+                user.session_id,
                 user_info.id,
-                user_info.username.clone(),
-                user_role,
-                user_info.is_verified,
-                &state.jwt_secret,
-            ) {
-                Ok(token) => token,
+                None,
+                &ClientInfo::default(),
+            )
+            .await
+            .and_then(|(refresh_jti, mfa)| {
+                let subject = CurrentUser {
+                    id: user_info.id,
+                    username: user_info.username.clone(),
+                    role: user_role,
+                    is_verified: user_info.is_verified,
+                    session_id: user.session_id,
+                    mfa,
+                };
+                AuthService::issue_tokens(&subject, refresh_jti, &state.jwt_secret)
+            }) {
+                Ok(tokens) => tokens,
                 Err(e) => {
                     log::error!(
-                        "[Auth] 生成刷新令牌失败 | user_id={}, error={}",
+                        "[Auth] 生成令牌失败 | user_id={}, error={}",
                         user_info.id,
                         e
                     );
@@ -179,13 +257,13 @@ pub async fn verify_user(
             // 设置 HttpOnly Cookies
             let access_cookie = build_auth_cookie(
                 ACCESS_TOKEN_COOKIE,
-                &access_token,
+                &tokens.access_token,
                 1, // 1天
                 state.cookie_secure,
             );
             let refresh_cookie = build_auth_cookie(
                 REFRESH_TOKEN_COOKIE,
-                &refresh_token,
+                &tokens.refresh_token,
                 7, // 7天
                 state.cookie_secure,
             );
@@ -197,11 +275,136 @@ pub async fn verify_user(
                 .json(user_info)
         }
         Err(e) => {
-            log::warn!("[User] 实名认证失败 | user_id={}, error={}", user.id, e);
+            log::warn!(
+                "[User] 实名认证已通过，但获取用户信息失败 | user_id={}, error={}",
+                user.id,
+                e
+            );
+            internal_error("认证成功但获取用户信息失败，请重新登录")
+        }
+    }
+}
+
+/// 获取当前用户的个性化推荐
+#[get("/users/me/recommendations")]
+pub async fn get_my_recommendations(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    query: web::Query<RecommendationQuery>,
+) -> impl Responder {
+    match AiService::recommend_resources(user.id, Some(&state.pool), query.get_limit()).await {
+        Ok(resources) => HttpResponse::Ok().json(resources),
+        Err(e) => {
+            log::warn!("[User] 获取推荐资源失败 | user_id={}, error={}", user.id, e);
+            internal_error("获取推荐资源失败")
+        }
+    }
+}
+
+/// 获取当前用户的登录会话列表
+#[get("/users/me/sessions")]
+pub async fn get_my_sessions(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+) -> impl Responder {
+    match SessionService::list_sessions(&state.pool, user.id, user.session_id).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => {
+            log::warn!("[User] 获取会话列表失败 | user_id={}, error={}", user.id, e);
+            internal_error("获取会话列表失败")
+        }
+    }
+}
+
+/// 吊销指定会话（可以是当前会话）
+#[delete("/users/me/sessions/{session_id}")]
+pub async fn revoke_my_session(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let session_id = path.into_inner();
+
+    match SessionService::revoke_session(&state.pool, user.id, session_id, REVOKE_REASON_USER).await
+    {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => not_found("会话不存在或已失效"),
+        Err(e) => {
+            log::warn!(
+                "[User] 吊销会话失败 | user_id={}, session_id={}, error={}",
+                user.id,
+                session_id,
+                e
+            );
+            internal_error("吊销会话失败")
+        }
+    }
+}
+
+/// 吊销当前会话以外的全部会话（在其他设备上退出登录）
+#[delete("/users/me/sessions")]
+pub async fn revoke_other_sessions(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+) -> impl Responder {
+    match SessionService::revoke_all_sessions(
+        &state.pool,
+        user.id,
+        Some(user.session_id),
+        REVOKE_REASON_USER,
+    )
+    .await
+    {
+        Ok(count) => HttpResponse::Ok().json(serde_json::json!({
+            "revoked": count,
+            "message": "已退出其他设备的登录"
+        })),
+        Err(e) => {
+            log::warn!("[User] 吊销会话失败 | user_id={}, error={}", user.id, e);
+            internal_error("吊销会话失败")
+        }
+    }
+}
+
+/// 修改密码（需要原密码），其他设备上的登录随之失效
+#[put("/users/me/password")]
+pub async fn change_password(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    req: web::Json<ChangePasswordRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    match AuthService::change_password(&state.pool, user.id, user.session_id, &req).await {
+        Ok(revoked) => {
+            let ip_address = client_ip(&http_req);
+            if let Err(e) = AuditLogService::log_change_password(
+                &state.pool,
+                user.id,
+                revoked,
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录修改密码日志失败 | user_id={}, error={}",
+                    user.id,
+                    e
+                );
+            }
+
+            HttpResponse::Ok().json(serde_json::json!({
+                "revokedSessions": revoked,
+                "message": "密码已修改，其他设备需要重新登录"
+            }))
+        }
+        Err(e) => {
+            log::warn!("[User] 修改密码失败 | user_id={}, error={}", user.id, e);
             match e {
-                UserError::UserNotFound(msg) => not_found(&msg),
-                UserError::ValidationError(msg) => bad_request(&msg),
-                _ => internal_error("认证失败"),
+                AuthError::ValidationError(msg) => bad_request(&msg),
+                // 不返回 401，避免前端误以为登录已失效
+                AuthError::InvalidCredentials(msg) => forbidden(&msg),
+                AuthError::UserNotFound(msg) => unauthorized(&msg),
+                _ => internal_error("修改密码失败"),
             }
         }
     }
@@ -249,6 +452,12 @@ pub async fn get_user_homepage(
 /// 配置用户路由
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(get_current_user)
+        .service(get_my_permissions)
+        .service(get_my_recommendations)
+        .service(get_my_sessions)
+        .service(revoke_my_session)
+        .service(revoke_other_sessions)
+        .service(change_password)
         .service(update_profile)
         .service(verify_user)
         .service(get_user_homepage) // 必须在 get_user_profile 之前注册
@@ -0,0 +1,66 @@
+use actix_web::{get, post, web, HttpResponse, Responder};
+
+use crate::db::AppState;
+use crate::models::CurrentUser;
+use crate::services::{ResourceError, VerificationService};
+use crate::utils::{bad_request, conflict, forbidden, internal_error, not_found};
+
+/// 将邮箱确认/实名认证相关错误转换为 HTTP 响应
+pub(crate) fn verification_error_response(err: ResourceError) -> HttpResponse {
+    match err {
+        ResourceError::NotFound(msg) => not_found(&msg),
+        ResourceError::ValidationError(msg) => bad_request(&msg),
+        ResourceError::Unauthorized(msg) => forbidden(&msg),
+        ResourceError::Conflict(msg) => conflict(&msg),
+        e => {
+            log::error!("[Verification] 服务器内部错误 | error={}", e);
+            internal_error("服务器内部错误")
+        }
+    }
+}
+
+/// 获取当前用户的邮箱确认和实名认证状态
+#[get("/users/me/verification")]
+pub async fn get_my_verification(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+) -> impl Responder {
+    match VerificationService::get_my_verification(&state.pool, &state.config, user.id).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => verification_error_response(e),
+    }
+}
+
+/// 重新发送邮箱确认邮件
+#[post("/users/me/email/confirmation")]
+pub async fn send_email_confirmation(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+) -> impl Responder {
+    match VerificationService::send_email_confirmation(
+        &state.pool,
+        state.mailer.clone(),
+        &state.config,
+        user.id,
+    )
+    .await
+    {
+        Ok(()) => HttpResponse::Ok().json(serde_json::json!({
+            "message": "确认邮件已发送，请查收"
+        })),
+        Err(e) => {
+            log::warn!(
+                "[Verification] 发送确认邮件失败 | user_id={}, error={}",
+                user.id,
+                e
+            );
+            verification_error_response(e)
+        }
+    }
+}
+
+/// 配置邮箱确认/实名认证路由（需要认证）
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_my_verification)
+        .service(send_email_confirmation);
+}
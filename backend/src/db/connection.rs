@@ -1,8 +1,13 @@
 use sqlx::postgres::{PgPool, PgPoolOptions};
+use std::net::IpAddr;
 use std::sync::Arc;
 use std::time::Duration;
 
-use crate::services::StorageBackend;
+use crate::config::Config;
+use crate::services::{
+    LoginLockoutPolicy, Mailer, MfaService, ModerationProvider, StorageBackend, StorageRegistry,
+};
+use crate::utils::TotpSecretCipher;
 
 /// 创建数据库连接池
 ///
@@ -33,23 +38,43 @@ pub async fn create_pool(database_url: &str) -> Result<PgPool, sqlx::Error> {
 #[derive(Clone)]
 pub struct AppState {
     pub pool: PgPool,
+    /// 启动时加载的配置，处理请求时直接读取，不再重新解析环境变量
+    pub config: Arc<Config>,
     pub jwt_secret: String,
     pub cookie_secure: bool,
+    /// 当前配置的存储后端，新文件写入这里
     pub storage: Arc<dyn StorageBackend>,
+    /// 所有已配置的存储后端，读取已有文件时按记录的 storage_type 选择
+    pub storages: StorageRegistry,
+    pub moderation: Arc<dyn ModerationProvider>,
+    pub mailer: Arc<dyn Mailer>,
+    /// 受信任的反向代理地址，来自这些地址的请求按转发头确定客户端 IP
+    pub trusted_proxies: Vec<IpAddr>,
+    pub login_lockout: LoginLockoutPolicy,
+    /// TOTP 密钥的加解密器
+    pub totp_cipher: TotpSecretCipher,
 }
 
 impl AppState {
     pub fn new(
         pool: PgPool,
-        jwt_secret: String,
-        cookie_secure: bool,
-        storage: Arc<dyn StorageBackend>,
+        config: Config,
+        storages: StorageRegistry,
+        moderation: Arc<dyn ModerationProvider>,
+        mailer: Arc<dyn Mailer>,
     ) -> Self {
         Self {
             pool,
-            jwt_secret,
-            cookie_secure,
-            storage,
+            jwt_secret: config.jwt_secret.clone(),
+            cookie_secure: config.cookie_secure,
+            storage: storages.active().clone(),
+            storages,
+            moderation,
+            mailer,
+            trusted_proxies: config.trusted_proxies.clone(),
+            login_lockout: LoginLockoutPolicy::from_config(&config),
+            totp_cipher: MfaService::secret_cipher(&config),
+            config: Arc::new(config),
         }
     }
 }
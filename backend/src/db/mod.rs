@@ -1,5 +1,7 @@
 // 数据库模块
 
 pub mod connection;
+#[cfg(test)]
+pub mod test_support;
 
 pub use connection::*;
@@ -1,6 +1,7 @@
 use actix_cors::Cors;
 use actix_web::{
-    get, http::Method, middleware::Logger, web, App, HttpResponse, HttpServer, Responder,
+    get, http::Method, middleware::Logger, web, App, HttpRequest, HttpResponse, HttpServer,
+    Responder,
 };
 use serde::Serialize;
 use uuid::Uuid;
@@ -13,10 +14,10 @@ mod models;
 mod services;
 mod utils;
 
-use crate::utils::{internal_error, not_found};
+use crate::utils::{internal_error, not_found, stream_storage_file};
 use config::Config;
 use db::AppState;
-use middleware::{JwtAuth, PublicPathRule};
+use middleware::{rate_limit_rules, JwtAuth, PublicPathRule, RateLimit};
 
 #[derive(Serialize)]
 struct HelloResponse {
@@ -52,9 +53,11 @@ async fn health_check() -> impl Responder {
 /// 获取图片文件（公开访问）
 /// 使用后端代理模式读取文件，避免浏览器直接访问 OSS 产生 CORS 问题
 #[get("/images/{image_id}")]
-async fn serve_image(data: web::Data<AppState>, path: web::Path<Uuid>) -> impl Responder {
-    use crate::services::StorageBackendType;
-
+async fn serve_image(
+    data: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    req: HttpRequest,
+) -> impl Responder {
     let image_id = path.into_inner();
 
     // 从数据库获取图片路径和存储类型
@@ -62,63 +65,44 @@ async fn serve_image(data: web::Data<AppState>, path: web::Path<Uuid>) -> impl R
         Ok((file_path, mime_type, storage_type)) => {
             // 根据图片实际的存储类型选择正确的存储后端读取文件
             // 使用后端代理模式，避免浏览器直接访问 OSS 产生 CORS 问题
-            let is_oss = storage_type.as_deref() == Some("oss");
-
-            let read_result = if is_oss {
-                // OSS 存储：使用主 storage（如果是 OSS 模式）或创建 OSS 存储实例
-                if data.storage.backend_type() == StorageBackendType::Oss {
-                    data.storage.read_file(&file_path).await
-                } else {
-                    // 当前是 local 模式，但需要读取 OSS 文件
-                    let config = config::Config::from_env();
-                    match services::create_storage_backend(&config) {
-                        Ok(oss_storage) if oss_storage.backend_type() == StorageBackendType::Oss => {
-                            oss_storage.read_file(&file_path).await
-                        }
-                        _ => {
-                            log::warn!(
-                                "[Image] 无法创建 OSS 存储实例来读取图片 | image_id={}",
-                                image_id
-                            );
-                            return internal_error("无法读取 OSS 图片");
-                        }
-                    }
-                }
-            } else {
-                // 本地存储：使用主 storage（如果是 Local 模式）或创建本地存储实例
-                if data.storage.backend_type() == StorageBackendType::Local {
-                    data.storage.read_file(&file_path).await
-                } else {
-                    // 当前是 OSS 模式，但需要读取本地文件
-                    let config = config::Config::from_env();
-                    match services::create_local_storage(&config) {
-                        Ok(local_storage) => local_storage.read_file(&file_path).await,
-                        Err(e) => {
-                            log::error!("[Image] 创建本地存储失败 | error={}", e);
-                            return internal_error("无法访问本地存储");
-                        }
-                    }
+            let storage = match data.storages.for_storage_type(storage_type.as_deref()) {
+                Ok(storage) => storage,
+                Err(e) => {
+                    log::warn!(
+                        "[Image] 图片所在的存储不可用 | image_id={}, error={}",
+                        image_id,
+                        e
+                    );
+                    return internal_error("无法访问图片所在的存储");
                 }
             };
+            let row_backend = storage.backend_type();
 
-            match read_result {
-                Ok(file_content) => {
-                    // 根据MIME类型设置Content-Type
-                    let content_type = mime_type
-                        .map(|m| m.parse::<mime::Mime>().ok())
-                        .flatten()
-                        .unwrap_or(mime::APPLICATION_OCTET_STREAM);
+            // 根据MIME类型设置Content-Type
+            let content_type = mime_type
+                .and_then(|m| m.parse::<mime::Mime>().ok())
+                .unwrap_or(mime::APPLICATION_OCTET_STREAM);
 
+            let response_result = if row_backend.is_remote() {
+                // OSS/S3 存储：整体读取后返回
+                storage.read_file(&file_path).await.map(|file_content| {
                     HttpResponse::Ok()
                         .content_type(content_type)
                         .body(file_content)
-                }
+                })
+            } else {
+                // 本地存储：流式返回，支持 ETag / Last-Modified 缓存校验
+                stream_storage_file(&req, storage, &file_path, content_type.as_ref(), &[]).await
+            };
+
+            match response_result {
+                Ok(response) => response,
                 Err(e) => {
                     log::warn!(
                         "[Image] 读取图片文件失败 | image_id={}, path={}, storage={}, error={}",
                         image_id,
                         file_path,
-                        if is_oss { "oss" } else { "local" },
+                        row_backend.as_str(),
                         e
                     );
                     not_found("图片不存在")
@@ -223,25 +207,141 @@ async fn main() -> std::io::Result<()> {
         }
     }
 
-    // 初始化存储后端
-    let storage = match services::create_storage_backend(&config) {
-        Ok(storage) => storage,
+    // 为尚未建立全文检索索引的资源补建索引（后台执行，不阻塞启动）
+    let reindex_pool = pool.clone();
+    tokio::spawn(async move {
+        match services::SearchService::index_missing_resources(&reindex_pool).await {
+            Ok(count) => {
+                if count > 0 {
+                    log::info!("[System] 已为 {} 个资源补建检索索引", count);
+                }
+            }
+            Err(e) => {
+                log::warn!("[System] 补建检索索引失败 | error={}", e);
+            }
+        }
+    });
+
+    // 定期刷新推荐使用的用户行为汇总
+    let recommend_pool = pool.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(services::RecommendationService::REFRESH_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) =
+                services::RecommendationService::refresh_interactions(&recommend_pool).await
+            {
+                log::warn!("[System] 刷新推荐行为汇总失败 | error={}", e);
+            }
+        }
+    });
+
+    // 定期把心跳超时（执行进程已退出）的存储迁移任务标记为失败，以便重新发起
+    let migration_pool = pool.clone();
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(services::StorageMigrationService::HEARTBEAT_INTERVAL);
+        loop {
+            interval.tick().await;
+            match services::StorageMigrationService::fail_interrupted_migrations(&migration_pool)
+                .await
+            {
+                Ok(count) => {
+                    if count > 0 {
+                        log::warn!("[Storage] 已将 {} 个中断的存储迁移任务标记为失败", count);
+                    }
+                }
+                Err(e) => {
+                    log::warn!("[Storage] 检查中断的存储迁移任务失败 | error={}", e);
+                }
+            }
+        }
+    });
+
+    // 上次运行中断的一致性检查任务无法继续，标记为失败以便重新发起
+    match services::StorageCheckService::fail_interrupted_checks(&pool).await {
+        Ok(count) => {
+            if count > 0 {
+                log::warn!(
+                    "[Storage] 已将 {} 个中断的存储一致性检查任务标记为失败",
+                    count
+                );
+            }
+        }
+        Err(e) => {
+            log::warn!("[Storage] 检查中断的存储一致性检查任务失败 | error={}", e);
+        }
+    }
+
+    // 初始化存储后端（当前后端用于写入，其余已配置的后端用于读取已有文件）
+    let storages = match services::StorageRegistry::from_config(&config) {
+        Ok(storages) => storages,
         Err(e) => {
             log::error!("[System] 初始化存储后端失败 | error={}", e);
             std::process::exit(1);
         }
     };
     log::info!(
-        "[System] Storage backend: {}",
-        storage.backend_type().as_str()
+        "[System] Storage backend: {}, available: {:?}",
+        storages.active().backend_type().as_str(),
+        storages
+            .backend_types()
+            .iter()
+            .map(|backend_type| backend_type.as_str())
+            .collect::<Vec<_>>()
+    );
+
+    // 初始化内容审核
+    let moderation = match services::create_moderation_provider(&config, pool.clone()) {
+        Ok(moderation) => moderation,
+        Err(e) => {
+            log::error!("[System] 初始化内容审核失败 | error={}", e);
+            std::process::exit(1);
+        }
+    };
+    log::info!(
+        "[System] Moderation providers: {:?}",
+        config.moderation_providers
+    );
+
+    // 初始化邮件发送
+    let mailer = match services::create_mailer(&config) {
+        Ok(mailer) => mailer,
+        Err(e) => {
+            log::error!("[System] 初始化邮件发送失败 | error={}", e);
+            std::process::exit(1);
+        }
+    };
+    log::info!("[System] Mail provider: {}", mailer.name());
+
+    // 初始化限流
+    let rate_limit_store = match services::create_rate_limit_store(&config, pool.clone()) {
+        Ok(store) => store,
+        Err(e) => {
+            log::error!("[System] 初始化限流失败 | error={}", e);
+            std::process::exit(1);
+        }
+    };
+    let rate_limit_rules = match rate_limit_rules(&config) {
+        Ok(rules) => rules,
+        Err(e) => {
+            log::error!("[System] 初始化限流失败 | error={}", e);
+            std::process::exit(1);
+        }
+    };
+    log::info!(
+        "[System] Rate limit store: {}, rules: {}",
+        rate_limit_store.name(),
+        rate_limit_rules.len()
     );
 
     // 创建应用状态
     let app_state = web::Data::new(AppState::new(
         pool,
-        config.jwt_secret.clone(),
-        config.cookie_secure,
-        storage,
+        config.clone(),
+        storages,
+        moderation,
+        mailer,
     ));
 
     log::info!("[System] Server starting at http://{}", server_addr);
@@ -249,11 +349,39 @@ async fn main() -> std::io::Result<()> {
     log::debug!("[System] API endpoints:");
     log::debug!("[System]   POST /api/auth/register - 用户注册");
     log::debug!("[System]   POST /api/auth/login    - 用户登录");
+    log::debug!("[System]   POST /api/auth/login/mfa - 登录第二步（动态码或恢复码）");
     log::debug!("[System]   POST /api/auth/refresh  - 刷新Token");
     log::debug!("[System]   POST /api/auth/logout   - 用户登出");
+    log::debug!("[System]   POST /api/auth/password/forgot - 申请重置密码邮件");
+    log::debug!("[System]   POST /api/auth/password/reset  - 通过邮件链接重置密码");
+    log::debug!("[System]   POST /api/auth/email/confirm   - 通过邮件链接确认邮箱");
+    log::debug!("[System]   GET  /api/auth/sso/providers - 已启用的统一身份认证方式");
+    log::debug!("[System]   GET  /api/auth/sso/oidc/login - 跳转到 OIDC 身份提供方登录");
+    log::debug!("[System]   GET  /api/auth/sso/oidc/callback - OIDC 授权回调");
+    log::debug!("[System]   GET  /api/auth/sso/cas/login - 跳转到 CAS 登录");
+    log::debug!("[System]   GET  /api/auth/sso/cas/callback - CAS 票据校验回调");
     log::debug!("[System]   GET  /api/users/me      - 获取当前用户");
     log::debug!("[System]   PUT  /api/users/me      - 更新用户资料");
-    log::debug!("[System]   POST /api/users/verify  - 实名认证");
+    log::debug!("[System]   PUT  /api/users/me/password - 修改密码");
+    log::debug!("[System]   GET  /api/users/me/permissions - 当前用户的管理权限");
+    log::debug!("[System]   GET  /api/users/me/mfa  - 两步验证状态");
+    log::debug!("[System]   POST /api/users/me/mfa/enroll - 开始绑定身份验证器");
+    log::debug!("[System]   POST /api/users/me/mfa/confirm - 确认绑定并获取恢复码");
+    log::debug!("[System]   POST /api/users/me/mfa/disable - 关闭两步验证");
+    log::debug!("[System]   POST /api/users/me/mfa/recovery-codes - 重新生成恢复码");
+    log::debug!("[System]   GET  /api/users/me/tokens - 个人访问令牌列表");
+    log::debug!("[System]   POST /api/users/me/tokens - 创建个人访问令牌");
+    log::debug!("[System]   DEL  /api/users/me/tokens/{{id}} - 吊销个人访问令牌");
+    log::debug!("[System]   GET  /api/users/me/identities - 已绑定的外部身份");
+    log::debug!("[System]   GET  /api/users/me/identities/{{provider}}/link - 绑定外部身份");
+    log::debug!("[System]   DEL  /api/users/me/identities/{{id}} - 解除绑定外部身份");
+    log::debug!("[System]   GET  /api/users/me/verification - 邮箱确认与实名认证状态");
+    log::debug!("[System]   POST /api/users/me/email/confirmation - 重新发送邮箱确认邮件");
+    log::debug!("[System]   GET  /api/users/me/recommendations - 个性化推荐");
+    log::debug!("[System]   GET  /api/users/me/sessions - 获取登录会话列表");
+    log::debug!("[System]   DEL  /api/users/me/sessions - 退出其他设备的登录");
+    log::debug!("[System]   DEL  /api/users/me/sessions/{{id}} - 吊销指定会话");
+    log::debug!("[System]   POST /api/users/verify  - 实名认证（校园邮箱自动通过，否则待审核）");
     log::debug!("[System]   GET  /api/users/{{user_id}} - 获取用户资料");
     log::debug!("[System]   POST /api/images/upload - 上传图片");
     log::debug!("[System]   GET  /api/images        - 获取我的图片列表");
@@ -266,7 +394,22 @@ async fn main() -> std::io::Result<()> {
     log::debug!("[System]   GET  /api/resources/my  - 获取我的资源列表");
     log::debug!("[System]   GET  /api/resources/{{id}} - 获取资源详情");
     log::debug!("[System]   GET  /api/resources/{{id}}/download - 下载资源");
+    log::debug!("[System]   GET  /api/resources/{{id}}/similar - 相似资源推荐");
+    log::debug!("[System]   POST /api/resources/{{id}}/claims - 提交作者申领");
     log::debug!("[System]   DEL  /api/resources/{{id}} - 删除资源");
+    log::debug!("[System]   GET  /api/resources/{{id}}/versions - 资源版本列表");
+    log::debug!("[System]   GET  /api/resources/{{id}}/versions/diff - 版本差异对比");
+    log::debug!("[System]   GET  /api/resources/{{id}}/versions/{{n}}/download - 下载历史版本");
+    log::debug!("[System]   POST /api/resources/{{id}}/versions - 上传新版本");
+    log::debug!("[System]   POST /api/resources/{{id}}/versions/{{n}}/rollback - 回滚到指定版本");
+    log::debug!("[System]   GET  /api/resources/{{id}}/editors - 资源协作者列表");
+    log::debug!("[System]   POST /api/resources/{{id}}/editors - 邀请协作者");
+    log::debug!("[System]   DELETE /api/resources/{{id}}/editors/{{user_id}} - 移除协作者");
+    log::debug!("[System]   POST /api/resources/uploads - 创建分片上传会话");
+    log::debug!("[System]   PUT  /api/resources/uploads/{{id}}/chunks/{{n}} - 上传分片");
+    log::debug!("[System]   GET  /api/resources/uploads/{{id}} - 查询分片上传状态");
+    log::debug!("[System]   POST /api/resources/uploads/{{id}}/complete - 合并分片并创建资源");
+    log::debug!("[System]   DEL  /api/resources/uploads/{{id}} - 取消分片上传");
     log::debug!("[System]   POST /api/favorites     - 创建收藏夹");
     log::debug!("[System]   GET  /api/favorites     - 获取我的收藏夹列表");
     log::debug!("[System]   GET  /api/favorites/{{id}} - 获取收藏夹详情");
@@ -275,6 +418,10 @@ async fn main() -> std::io::Result<()> {
     log::debug!("[System]   POST /api/favorites/{{id}}/resources - 添加资源到收藏夹");
     log::debug!("[System]   DEL  /api/favorites/{{id}}/resources/{{rid}} - 从收藏夹移除资源");
     log::debug!("[System]   GET  /api/favorites/check/{{rid}} - 检查资源收藏状态");
+    log::debug!("[System]   PUT  /api/comments/{{id}} - 编辑评论");
+    log::debug!("[System]   GET  /api/comments/{{id}}/history - 评论编辑历史");
+    log::debug!("[System]   POST /api/comments/{{id}}/like - 点赞/取消点赞评论");
+    log::debug!("[System]   POST /api/comments/{{id}}/report - 举报评论");
     log::debug!("[System]   GET  /api/health        - 健康检查");
     log::debug!("[System]   GET  /api/hello         - 测试接口");
 
@@ -295,7 +442,11 @@ async fn main() -> std::io::Result<()> {
             PublicPathRule::all_methods("/api/auth"),
             // /api/resources GET 方法公开（列表、搜索、详情、下载），但排除需要登录的接口
             PublicPathRule::with_methods("/api/resources", vec![Method::GET])
-                .exclude(vec!["/api/resources/my", "/api/resources/{id}/rate"]),
+                .exclude(vec![
+                    "/api/resources/my",
+                    "/api/resources/{id}/rate",
+                    "/api/resources/uploads",
+                ]),
             // /api/users/{user_id} 和 /api/users/{user_id}/homepage GET 方法公开
             // 排除 /api/users/me 和 /api/users/verify
             PublicPathRule::with_methods("/api/users", vec![Method::GET])
@@ -306,6 +457,8 @@ async fn main() -> std::io::Result<()> {
         ];
 
         let jwt_auth = JwtAuth::new(jwt_secret.clone()).with_public_rules(public_rules);
+        let rate_limit =
+            RateLimit::new(rate_limit_store.clone()).with_rules(rate_limit_rules.clone());
 
         // 构建 CORS 配置
         // 注意：使用 Cookie 认证必须设置 supports_credentials(true)
@@ -343,6 +496,8 @@ async fn main() -> std::io::Result<()> {
             // 注意：config 必须在 config_public 之前注册，否则 /resources/my 会被 /resources/{id} 匹配
             .service(
                 web::scope("/api")
+                    // 后注册的中间件先执行：先认证，再按用户或 IP 限流
+                    .wrap(rate_limit)
                     .wrap(jwt_auth)
                     .configure(api::auth::config)
                     .configure(api::user::config)
@@ -352,8 +507,16 @@ async fn main() -> std::io::Result<()> {
                     .configure(api::notification::config) // 通知路由
                     .configure(api::admin::config) // 管理后台路由
                     .configure(api::favorite::config) // 收藏夹路由
+                    .configure(api::claim::config) // 作者申领路由
+                    .configure(api::verification::config) // 邮箱确认与实名认证路由
+                    .configure(api::mfa::config) // 两步验证路由
+                    .configure(api::api_token::config) // 个人访问令牌路由
+                    .configure(api::sso::config) // 统一身份认证路由
                     .configure(api::teacher::config) // 教师路由（公开）
                     .configure(api::course::config) // 课程路由（公开）
+                    .configure(api::upload_session::config) // 分片上传路由
+                    .configure(api::resource_version::config) // 资源版本路由
+                    .configure(api::resource_editor::config) // 资源协作者路由
                     .configure(api::resource::config) // 需要认证的资源路由（先注册）
                     .configure(api::resource::config_public), // 公开资源路由（后注册）
             )
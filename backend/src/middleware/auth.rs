@@ -1,9 +1,9 @@
 use actix_web::{
     body::MessageBody,
     dev::{Service, ServiceRequest, ServiceResponse, Transform},
-    error::ErrorUnauthorized,
+    error::{ErrorForbidden, ErrorInternalServerError, ErrorUnauthorized},
     http::{header, Method},
-    Error, HttpMessage,
+    web, Error, HttpMessage,
 };
 use futures_util::future::LocalBoxFuture;
 use std::{
@@ -12,12 +12,72 @@ use std::{
     task::{Context, Poll},
 };
 
-use crate::models::CurrentUser;
-use crate::utils::{extract_current_user, verify_token};
+use super::rate_limit::path_pattern_matches;
+use crate::db::AppState;
+use crate::models::{ApiTokenScope, CurrentUser, API_TOKEN_PREFIX};
+use crate::services::{ApiTokenService, SessionService};
+use crate::utils::{client_ip, extract_current_user, verify_token};
 
 /// Cookie 名称常量
 const ACCESS_TOKEN_COOKIE: &str = "access_token";
 
+/// 个人访问令牌可以调用的接口及所需的权限范围（按顺序匹配），未列出的接口不接受令牌
+const API_TOKEN_SCOPE_RULES: &[(&[&str], &str, ApiTokenScope)] = &[
+    (&["POST"], "/api/resources", ApiTokenScope::ResourcesWrite),
+    (
+        &["POST"],
+        "/api/resources/uploads",
+        ApiTokenScope::ResourcesWrite,
+    ),
+    (
+        &["GET", "PUT", "POST", "DELETE"],
+        "/api/resources/uploads/*",
+        ApiTokenScope::ResourcesWrite,
+    ),
+    (
+        &["DELETE"],
+        "/api/resources/{id}",
+        ApiTokenScope::ResourcesWrite,
+    ),
+    (
+        &["PUT"],
+        "/api/resources/{id}/content",
+        ApiTokenScope::ResourcesWrite,
+    ),
+    (
+        &["PUT"],
+        "/api/resources/{id}/relations",
+        ApiTokenScope::ResourcesWrite,
+    ),
+    (
+        &["POST"],
+        "/api/resources/{id}/relations/attach",
+        ApiTokenScope::ResourcesWrite,
+    ),
+    (&["GET"], "/api/resources", ApiTokenScope::ResourcesRead),
+    (&["GET"], "/api/resources/*", ApiTokenScope::ResourcesRead),
+    (&["GET"], "/api/courses", ApiTokenScope::ResourcesRead),
+    (&["GET"], "/api/teachers", ApiTokenScope::ResourcesRead),
+    (&["GET"], "/api/favorites", ApiTokenScope::FavoritesRead),
+    (&["GET"], "/api/favorites/*", ApiTokenScope::FavoritesRead),
+    (&["POST"], "/api/favorites", ApiTokenScope::FavoritesWrite),
+    (
+        &["POST", "PUT", "DELETE"],
+        "/api/favorites/*",
+        ApiTokenScope::FavoritesWrite,
+    ),
+];
+
+/// 个人访问令牌调用该接口所需的权限范围，返回 None 表示该接口不接受令牌
+pub fn required_api_token_scope(method: &Method, path: &str) -> Option<ApiTokenScope> {
+    API_TOKEN_SCOPE_RULES
+        .iter()
+        .find(|(methods, pattern, _)| {
+            methods.contains(&method.as_str()) && path_pattern_matches(pattern, path)
+        })
+        .map(|(_, _, scope)| *scope)
+}
+
 /// 公开路径规则
 #[derive(Clone)]
 pub struct PublicPathRule {
@@ -165,33 +225,57 @@ where
 
             // 如果有认证信息，尝试验证
             if let Some(token) = token {
-                // 验证Token
-                match verify_token(&token, &jwt_secret, Some("access")) {
-                    Ok(claims) => {
-                        match extract_current_user(claims) {
-                            Ok(current_user) => {
-                                log::debug!(
-                                    "用户认证成功: {}, 角色: {:?}",
-                                    current_user.username,
-                                    current_user.role
-                                );
-                                // 将用户信息存入请求扩展
-                                req.extensions_mut().insert(current_user);
-                            }
-                            Err(e) => {
-                                log::warn!("提取用户信息失败: {}", e);
-                                // 非公开路径需要返回错误
-                                if !is_public {
-                                    return Err(ErrorUnauthorized("无效的认证信息"));
+                if token.starts_with(API_TOKEN_PREFIX) {
+                    // 个人访问令牌：校验令牌并检查权限范围
+                    if let Some(current_user) =
+                        Self::authenticate_api_token(&req, &token, is_public).await?
+                    {
+                        req.extensions_mut().insert(current_user);
+                    }
+                } else {
+                    // 验证Token
+                    match verify_token(&token, &jwt_secret, Some("access")) {
+                        Ok(claims) => {
+                            match extract_current_user(claims) {
+                                Ok(current_user) => {
+                                    // 会话被吊销或用户被禁用后 Token 立即失效
+                                    if Self::is_session_active(&req, &current_user).await? {
+                                        log::debug!(
+                                            "用户认证成功: {}, 角色: {:?}",
+                                            current_user.username,
+                                            current_user.role
+                                        );
+                                        // 将用户信息存入请求扩展
+                                        req.extensions_mut().insert(current_user);
+                                    } else {
+                                        log::debug!(
+                                            "会话已失效: user_id={}, session_id={}",
+                                            current_user.id,
+                                            current_user.session_id
+                                        );
+                                        // 非公开路径需要返回错误
+                                        if !is_public {
+                                            return Err(ErrorUnauthorized(
+                                                "会话已失效，请重新登录",
+                                            ));
+                                        }
+                                    }
+                                }
+                                Err(e) => {
+                                    log::warn!("提取用户信息失败: {}", e);
+                                    // 非公开路径需要返回错误
+                                    if !is_public {
+                                        return Err(ErrorUnauthorized("无效的认证信息"));
+                                    }
                                 }
                             }
                         }
-                    }
-                    Err(e) => {
-                        log::warn!("Token验证失败: {}", e);
-                        // 非公开路径需要返回错误
-                        if !is_public {
-                            return Err(ErrorUnauthorized("认证已过期或无效"));
+                        Err(e) => {
+                            log::warn!("Token验证失败: {}", e);
+                            // 非公开路径需要返回错误
+                            if !is_public {
+                                return Err(ErrorUnauthorized("认证已过期或无效"));
+                            }
                         }
                     }
                 }
@@ -217,6 +301,67 @@ where
 }
 
 impl<S> JwtAuthMiddleware<S> {
+    /// 校验个人访问令牌
+    ///
+    /// 令牌有效且具备该接口所需的权限范围时返回令牌所属用户；
+    /// 令牌无效时公开路径按游客处理，权限范围不足时一律拒绝
+    async fn authenticate_api_token(
+        req: &ServiceRequest,
+        token: &str,
+        is_public: bool,
+    ) -> Result<Option<CurrentUser>, Error> {
+        let Some(state) = req.app_data::<web::Data<AppState>>() else {
+            return Err(ErrorUnauthorized("无效的认证信息"));
+        };
+        let ip_address = client_ip(req.request());
+
+        let auth =
+            match ApiTokenService::authenticate(&state.pool, token, ip_address.as_deref()).await {
+                Ok(Some(auth)) => auth,
+                Ok(None) => {
+                    log::warn!("个人访问令牌无效、已过期或已吊销");
+                    if is_public {
+                        return Ok(None);
+                    }
+                    return Err(ErrorUnauthorized("令牌无效、已过期或已吊销"));
+                }
+                Err(e) => {
+                    log::error!("校验个人访问令牌失败: {}", e);
+                    if is_public {
+                        return Ok(None);
+                    }
+                    return Err(ErrorUnauthorized("认证已过期或无效"));
+                }
+            };
+
+        match required_api_token_scope(req.method(), req.path()) {
+            Some(scope) if auth.scopes.contains(&scope) => {
+                log::debug!("令牌认证成功: {}, 权限范围: {}", auth.user.username, scope);
+                Ok(Some(auth.user))
+            }
+            Some(scope) => Err(ErrorForbidden(format!("令牌缺少权限范围: {}", scope))),
+            None => Err(ErrorForbidden("该接口不支持使用个人访问令牌调用")),
+        }
+    }
+
+    /// 检查 Token 所属会话是否仍然有效（未吊销、未过期且用户未被禁用）
+    ///
+    /// 无法确认会话状态时按失效处理，缺少应用状态属于配置错误
+    async fn is_session_active(req: &ServiceRequest, user: &CurrentUser) -> Result<bool, Error> {
+        let Some(state) = req.app_data::<web::Data<AppState>>() else {
+            log::error!("认证中间件缺少应用状态，无法检查会话");
+            return Err(ErrorInternalServerError("服务器内部错误"));
+        };
+
+        match SessionService::is_session_active(&state.pool, user.session_id, user.id).await {
+            Ok(active) => Ok(active),
+            Err(e) => {
+                log::error!("检查会话状态失败: {}", e);
+                Ok(false)
+            }
+        }
+    }
+
     /// 从请求中提取 Token（从 Authorization 头或 Cookie）
     fn extract_token_from_request(req: &ServiceRequest) -> Option<String> {
         // 首先尝试从 Authorization 头中提取
@@ -246,28 +391,6 @@ pub fn get_current_user(req: &ServiceRequest) -> Option<CurrentUser> {
     req.extensions().get::<CurrentUser>().cloned()
 }
 
-/// 角色检查中间件工厂（预留接口）
-#[allow(dead_code)]
-pub struct RequireRole {
-    role: String,
-}
-
-impl RequireRole {
-    #[allow(dead_code)]
-    pub fn admin() -> Self {
-        Self {
-            role: "admin".to_string(),
-        }
-    }
-
-    #[allow(dead_code)]
-    pub fn verified() -> Self {
-        Self {
-            role: "verified".to_string(),
-        }
-    }
-}
-
 /// 需要认证的处理函数包装器（预留接口）
 #[allow(dead_code)]
 pub async fn auth_required<F, Fut>(
@@ -283,3 +406,76 @@ where
     }
     f(req).await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_required_api_token_scope() {
+        let cases = [
+            (
+                Method::GET,
+                "/api/resources",
+                Some(ApiTokenScope::ResourcesRead),
+            ),
+            (
+                Method::GET,
+                "/api/resources/abc/download",
+                Some(ApiTokenScope::ResourcesRead),
+            ),
+            (
+                Method::POST,
+                "/api/resources",
+                Some(ApiTokenScope::ResourcesWrite),
+            ),
+            (
+                Method::PUT,
+                "/api/resources/uploads/abc/chunks/0",
+                Some(ApiTokenScope::ResourcesWrite),
+            ),
+            (
+                Method::GET,
+                "/api/resources/uploads/abc",
+                Some(ApiTokenScope::ResourcesWrite),
+            ),
+            (
+                Method::DELETE,
+                "/api/resources/abc",
+                Some(ApiTokenScope::ResourcesWrite),
+            ),
+            (
+                Method::GET,
+                "/api/favorites",
+                Some(ApiTokenScope::FavoritesRead),
+            ),
+            (
+                Method::GET,
+                "/api/favorites/abc/download",
+                Some(ApiTokenScope::FavoritesRead),
+            ),
+            (
+                Method::DELETE,
+                "/api/favorites/abc/resources/def",
+                Some(ApiTokenScope::FavoritesWrite),
+            ),
+            // 评论、评分、账号和管理接口不接受令牌
+            (Method::POST, "/api/resources/abc/comments", None),
+            (Method::POST, "/api/resources/abc/rate", None),
+            (Method::GET, "/api/users/me/tokens", None),
+            (Method::POST, "/api/users/me/tokens", None),
+            (Method::PUT, "/api/users/me/password", None),
+            (Method::GET, "/api/admin/users", None),
+        ];
+
+        for (method, path, expected) in cases {
+            assert_eq!(
+                required_api_token_scope(&method, path),
+                expected,
+                "{} {}",
+                method,
+                path
+            );
+        }
+    }
+}
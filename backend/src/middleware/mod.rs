@@ -1,7 +1,12 @@
 // 中间件模块
 
 pub mod auth;
+pub mod rate_limit;
 
 // JwtAuth 和 PublicPathRule 在主程序中使用
 pub use auth::JwtAuth;
 pub use auth::PublicPathRule;
+
+// RateLimit 和限流规则在主程序中使用
+pub use rate_limit::rate_limit_rules;
+pub use rate_limit::RateLimit;
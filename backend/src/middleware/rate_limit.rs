@@ -0,0 +1,306 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    error::InternalError,
+    http::{
+        header::{HeaderName, HeaderValue},
+        Method,
+    },
+    Error, HttpMessage,
+};
+use futures_util::future::LocalBoxFuture;
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use crate::config::Config;
+use crate::models::CurrentUser;
+use crate::services::{RateLimitError, RateLimitQuota, RateLimitStore};
+use crate::utils::{client_ip, too_many_requests};
+
+/// 限流规则
+#[derive(Clone)]
+pub struct RateLimitRule {
+    /// 规则名称，作为令牌桶键的前缀
+    pub name: String,
+    pub methods: Vec<Method>,
+    /// 路径模式：`{param}` 匹配单个路径段，结尾的 `*` 匹配剩余的任意路径段
+    pub path_pattern: String,
+    pub quota: RateLimitQuota,
+}
+
+impl RateLimitRule {
+    pub fn new(
+        name: &str,
+        methods: Vec<Method>,
+        path_pattern: &str,
+        quota: RateLimitQuota,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            methods,
+            path_pattern: path_pattern.to_string(),
+            quota,
+        }
+    }
+
+    /// 检查是否匹配
+    pub fn matches(&self, path: &str, method: &Method) -> bool {
+        if !self.methods.is_empty() && !self.methods.contains(method) {
+            return false;
+        }
+        path_pattern_matches(&self.path_pattern, path)
+    }
+}
+
+/// 检查路径是否匹配模式：`{param}` 匹配单个路径段，结尾的 `*` 匹配剩余的任意路径段
+pub(crate) fn path_pattern_matches(path_pattern: &str, path: &str) -> bool {
+    let mut segments = path.trim_end_matches('/').split('/');
+    for pattern in path_pattern.split('/') {
+        if pattern == "*" {
+            return segments.any(|s| !s.is_empty());
+        }
+        match segments.next() {
+            Some(segment) if pattern.starts_with('{') && pattern.ends_with('}') => {
+                if segment.is_empty() {
+                    return false;
+                }
+            }
+            Some(segment) if segment == pattern => {}
+            _ => return false,
+        }
+    }
+    segments.next().is_none()
+}
+
+/// 根据配置生成默认的限流规则，未启用限流时返回空列表
+pub fn rate_limit_rules(config: &Config) -> Result<Vec<RateLimitRule>, RateLimitError> {
+    if !config.rate_limit_enabled {
+        return Ok(vec![]);
+    }
+
+    let specs = [
+        (
+            "login",
+            vec![Method::POST],
+            "/api/auth/login",
+            &config.rate_limit_login,
+        ),
+        (
+            "login",
+            vec![Method::POST],
+            "/api/auth/login/mfa",
+            &config.rate_limit_login,
+        ),
+        (
+            "login",
+            vec![Method::GET],
+            "/api/auth/sso/{provider}/*",
+            &config.rate_limit_login,
+        ),
+        (
+            "password_reset",
+            vec![Method::POST],
+            "/api/auth/password/forgot",
+            &config.rate_limit_password_reset,
+        ),
+        (
+            "download",
+            vec![Method::GET],
+            "/api/resources/{id}/download",
+            &config.rate_limit_download,
+        ),
+        (
+            "comment",
+            vec![Method::POST],
+            "/api/resources/{id}/comments",
+            &config.rate_limit_comment,
+        ),
+        (
+            "comment",
+            vec![Method::POST, Method::PUT, Method::DELETE],
+            "/api/comments/*",
+            &config.rate_limit_comment,
+        ),
+    ];
+
+    let mut rules = Vec::new();
+    for (name, methods, path_pattern, spec) in specs {
+        if let Some(quota) = RateLimitQuota::parse(spec)? {
+            rules.push(RateLimitRule::new(name, methods, path_pattern, quota));
+        }
+    }
+    Ok(rules)
+}
+
+/// 限流中间件（令牌桶，已登录用户按用户计数，否则按 IP 计数）
+/// 需要在 JwtAuth 内层注册，才能读取到当前用户
+pub struct RateLimit {
+    store: Arc<dyn RateLimitStore>,
+    rules: Vec<RateLimitRule>,
+}
+
+impl RateLimit {
+    pub fn new(store: Arc<dyn RateLimitStore>) -> Self {
+        Self {
+            store,
+            rules: Vec::new(),
+        }
+    }
+
+    /// 设置限流规则（按顺序匹配第一条）
+    pub fn with_rules(mut self, rules: Vec<RateLimitRule>) -> Self {
+        self.rules = rules;
+        self
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RateLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service: Rc::new(service),
+            store: self.store.clone(),
+            rules: Rc::new(self.rules.clone()),
+        }))
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: Rc<S>,
+    store: Arc<dyn RateLimitStore>,
+    rules: Rc<Vec<RateLimitRule>>,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let store = self.store.clone();
+        let rules = self.rules.clone();
+
+        Box::pin(async move {
+            let Some(rule) = rules
+                .iter()
+                .find(|rule| rule.matches(req.path(), req.method()))
+            else {
+                return service.call(req).await;
+            };
+
+            let key = Self::bucket_key(&req, &rule.name);
+            let decision = match store.acquire(&key, &rule.quota).await {
+                Ok(decision) => decision,
+                Err(e) => {
+                    // 存储不可用时放行，避免限流故障影响正常访问
+                    log::warn!(
+                        "[RateLimit] 限流检查失败，已放行 | key={}, error={}",
+                        key,
+                        e
+                    );
+                    return service.call(req).await;
+                }
+            };
+
+            if !decision.allowed {
+                log::warn!(
+                    "[RateLimit] 请求过于频繁 | key={}, path={}, retry_after={}s",
+                    key,
+                    req.path(),
+                    decision.retry_after_secs
+                );
+                let message = format!("请求过于频繁，请 {} 秒后再试", decision.retry_after_secs);
+                return Err(InternalError::from_response(
+                    message.clone(),
+                    too_many_requests(&message, decision.retry_after_secs),
+                )
+                .into());
+            }
+
+            let mut res = service.call(req).await?;
+            let headers = res.headers_mut();
+            headers.insert(
+                HeaderName::from_static("x-ratelimit-limit"),
+                HeaderValue::from(rule.quota.capacity),
+            );
+            headers.insert(
+                HeaderName::from_static("x-ratelimit-remaining"),
+                HeaderValue::from(decision.remaining),
+            );
+            Ok(res)
+        })
+    }
+}
+
+impl<S> RateLimitMiddleware<S> {
+    /// 令牌桶键：已登录用户按用户 ID，否则按客户端 IP
+    fn bucket_key(req: &ServiceRequest, rule_name: &str) -> String {
+        if let Some(user) = req.extensions().get::<CurrentUser>() {
+            return format!("{}:user:{}", rule_name, user.id);
+        }
+
+        let ip = client_ip(req.request()).unwrap_or_else(|| "unknown".to_string());
+        format!("{}:ip:{}", rule_name, ip)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(methods: Vec<Method>, path_pattern: &str) -> RateLimitRule {
+        RateLimitRule::new(
+            "test",
+            methods,
+            path_pattern,
+            RateLimitQuota {
+                capacity: 1,
+                period_secs: 1,
+            },
+        )
+    }
+
+    #[test]
+    fn test_rule_matches_path_params() {
+        let download = rule(vec![Method::GET], "/api/resources/{id}/download");
+        assert!(download.matches("/api/resources/abc/download", &Method::GET));
+        assert!(download.matches("/api/resources/abc/download/", &Method::GET));
+        assert!(!download.matches("/api/resources/abc/download", &Method::POST));
+        assert!(!download.matches("/api/resources//download", &Method::GET));
+        assert!(!download.matches("/api/resources/abc", &Method::GET));
+        assert!(!download.matches("/api/resources/abc/download/x", &Method::GET));
+    }
+
+    #[test]
+    fn test_rule_matches_wildcard() {
+        let comments = rule(vec![], "/api/comments/*");
+        assert!(comments.matches("/api/comments/abc", &Method::PUT));
+        assert!(comments.matches("/api/comments/abc/like", &Method::POST));
+        assert!(!comments.matches("/api/comments", &Method::POST));
+        assert!(!comments.matches("/api/commentsx/abc", &Method::POST));
+    }
+}
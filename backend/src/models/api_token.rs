@@ -0,0 +1,150 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// 个人访问令牌的固定前缀，用于和 JWT 区分
+pub const API_TOKEN_PREFIX: &str = "sut_";
+
+/// 个人访问令牌的权限范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiTokenScope {
+    /// 浏览、搜索和下载资源
+    ResourcesRead,
+    /// 上传、修改和删除自己的资源
+    ResourcesWrite,
+    /// 查看和打包下载收藏夹
+    FavoritesRead,
+    /// 管理收藏夹
+    FavoritesWrite,
+}
+
+impl ApiTokenScope {
+    pub const ALL: [ApiTokenScope; 4] = [
+        ApiTokenScope::ResourcesRead,
+        ApiTokenScope::ResourcesWrite,
+        ApiTokenScope::FavoritesRead,
+        ApiTokenScope::FavoritesWrite,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiTokenScope::ResourcesRead => "resources:read",
+            ApiTokenScope::ResourcesWrite => "resources:write",
+            ApiTokenScope::FavoritesRead => "favorites:read",
+            ApiTokenScope::FavoritesWrite => "favorites:write",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|scope| scope.as_str() == s)
+    }
+}
+
+impl std::fmt::Display for ApiTokenScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 个人访问令牌（对应数据库 api_tokens 表）
+#[derive(Debug, FromRow)]
+pub struct ApiToken {
+    pub id: Uuid,
+    pub name: String,
+    pub token_prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub last_used_ip: Option<String>,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+/// 创建个人访问令牌请求 DTO
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiTokenRequest {
+    pub name: String,
+    pub scopes: Vec<String>,
+    /// 有效天数，为空表示永不过期
+    pub expires_in_days: Option<i64>,
+}
+
+impl CreateApiTokenRequest {
+    /// 校验请求并返回去重后的权限范围
+    pub fn validate(&self) -> Result<Vec<ApiTokenScope>, String> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err("令牌名称不能为空".to_string());
+        }
+        if name.chars().count() > 100 {
+            return Err("令牌名称不能超过100个字符".to_string());
+        }
+        if let Some(days) = self.expires_in_days {
+            if !(1..=365).contains(&days) {
+                return Err("有效天数必须在 1 到 365 之间".to_string());
+            }
+        }
+
+        let mut scopes = Vec::new();
+        for s in &self.scopes {
+            let scope =
+                ApiTokenScope::parse(s.trim()).ok_or_else(|| format!("未知的权限范围: {}", s))?;
+            if !scopes.contains(&scope) {
+                scopes.push(scope);
+            }
+        }
+        if scopes.is_empty() {
+            return Err("请至少选择一个权限范围".to_string());
+        }
+        Ok(scopes)
+    }
+}
+
+/// 个人访问令牌响应 DTO（不含令牌明文）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiTokenResponse {
+    pub id: Uuid,
+    pub name: String,
+    /// 令牌开头几位，便于辨认
+    pub token_prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub last_used_ip: Option<String>,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+impl From<ApiToken> for ApiTokenResponse {
+    fn from(token: ApiToken) -> Self {
+        Self {
+            id: token.id,
+            name: token.name,
+            token_prefix: token.token_prefix,
+            scopes: token.scopes,
+            expires_at: token.expires_at,
+            last_used_at: token.last_used_at,
+            last_used_ip: token.last_used_ip,
+            created_at: token.created_at,
+        }
+    }
+}
+
+/// 创建个人访问令牌响应（令牌明文只返回这一次）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiTokenResponse {
+    pub token: String,
+    #[serde(flatten)]
+    pub info: ApiTokenResponse,
+}
+
+/// 个人访问令牌列表响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiTokenListResponse {
+    pub tokens: Vec<ApiTokenResponse>,
+    /// 可选的权限范围
+    pub available_scopes: Vec<&'static str>,
+}
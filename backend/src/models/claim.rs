@@ -0,0 +1,224 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// 申领类型：声明自己是资源的真实作者
+pub const CLAIM_TYPE_AUTHOR: &str = "author";
+
+/// 单个申领最多附带的证明图片数量
+pub const MAX_PROOF_IMAGES: usize = 9;
+
+/// 申领记录（对应数据库 claims 表，审核和撤回时使用的字段）
+#[derive(Debug, Clone, FromRow)]
+pub struct Claim {
+    pub resource_id: Uuid,
+    pub applicant_id: Uuid,
+    pub status: Option<String>,
+}
+
+/// 申领状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClaimStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl ClaimStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClaimStatus::Pending => "pending",
+            ClaimStatus::Approved => "approved",
+            ClaimStatus::Rejected => "rejected",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(ClaimStatus::Pending),
+            "approved" => Some(ClaimStatus::Approved),
+            "rejected" => Some(ClaimStatus::Rejected),
+            _ => None,
+        }
+    }
+}
+
+/// 提交申领请求 DTO
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateClaimRequest {
+    /// 申领理由（如：原始出处、创作过程说明）
+    pub reason: String,
+    /// 证明图片 ID（需先通过图床上传）
+    #[serde(default)]
+    pub proof_image_ids: Vec<Uuid>,
+}
+
+impl CreateClaimRequest {
+    /// 验证请求
+    pub fn validate(&self) -> Result<(), String> {
+        let reason = self.reason.trim();
+        if reason.chars().count() < 10 {
+            return Err("申领理由不能少于10个字符".to_string());
+        }
+        if reason.chars().count() > 1000 {
+            return Err("申领理由不能超过1000个字符".to_string());
+        }
+        if self.proof_image_ids.len() > MAX_PROOF_IMAGES {
+            return Err(format!("证明图片不能超过{}张", MAX_PROOF_IMAGES));
+        }
+        Ok(())
+    }
+}
+
+/// 审核申领请求 DTO
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewClaimRequest {
+    /// approved 或 rejected
+    pub status: String,
+    /// 审核意见（会随通知发送给申请人）
+    pub comment: Option<String>,
+}
+
+impl ReviewClaimRequest {
+    /// 验证请求，返回审核结果状态
+    pub fn validate(&self) -> Result<ClaimStatus, String> {
+        let status = match ClaimStatus::parse(&self.status) {
+            Some(status @ (ClaimStatus::Approved | ClaimStatus::Rejected)) => status,
+            _ => return Err("状态必须是 approved 或 rejected".to_string()),
+        };
+        if let Some(ref comment) = self.comment {
+            if comment.chars().count() > 500 {
+                return Err("审核意见不能超过500个字符".to_string());
+            }
+        }
+        Ok(status)
+    }
+}
+
+/// 申领列表查询参数
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimListQuery {
+    /// 按状态筛选：pending、approved、rejected
+    pub status: Option<String>,
+    pub page: Option<i32>,
+    pub per_page: Option<i32>,
+}
+
+impl ClaimListQuery {
+    pub fn get_page(&self) -> i32 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    pub fn get_per_page(&self) -> i32 {
+        self.per_page.unwrap_or(20).clamp(1, 100)
+    }
+}
+
+/// 申领列表查询结果（关联资源标题和用户名）
+#[derive(Debug, FromRow)]
+pub struct ClaimListRow {
+    pub id: Uuid,
+    pub resource_id: Uuid,
+    pub resource_title: String,
+    pub applicant_id: Uuid,
+    pub applicant_name: String,
+    pub uploader_name: Option<String>,
+    pub current_author_id: Option<Uuid>,
+    pub claim_type: Option<String>,
+    pub reason: String,
+    pub proof_files: Option<serde_json::Value>,
+    pub status: Option<String>,
+    pub reviewer_id: Option<Uuid>,
+    pub reviewed_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+/// 证明图片
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimProofImage {
+    pub id: Uuid,
+    pub url: String,
+}
+
+/// 申领响应 DTO
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimResponse {
+    pub id: Uuid,
+    pub resource_id: Uuid,
+    pub resource_title: String,
+    pub applicant_id: Uuid,
+    pub applicant_name: String,
+    pub uploader_name: Option<String>,
+    /// 资源当前登记的作者
+    pub current_author_id: Option<Uuid>,
+    pub claim_type: String,
+    pub reason: String,
+    pub proof_images: Vec<ClaimProofImage>,
+    pub status: String,
+    pub reviewer_id: Option<Uuid>,
+    pub reviewed_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl ClaimResponse {
+    pub fn from_row(row: ClaimListRow, image_base_url: &str) -> Self {
+        let proof_images = proof_image_ids(row.proof_files.as_ref())
+            .into_iter()
+            .map(|id| ClaimProofImage {
+                id,
+                url: format!("{}/images/{}", image_base_url, id),
+            })
+            .collect();
+
+        Self {
+            id: row.id,
+            resource_id: row.resource_id,
+            resource_title: row.resource_title,
+            applicant_id: row.applicant_id,
+            applicant_name: row.applicant_name,
+            uploader_name: row.uploader_name,
+            current_author_id: row.current_author_id,
+            claim_type: row
+                .claim_type
+                .unwrap_or_else(|| CLAIM_TYPE_AUTHOR.to_string()),
+            reason: row.reason,
+            proof_images,
+            status: row
+                .status
+                .unwrap_or_else(|| ClaimStatus::Pending.as_str().to_string()),
+            reviewer_id: row.reviewer_id,
+            reviewed_at: row.reviewed_at,
+            created_at: row.created_at,
+        }
+    }
+}
+
+/// 从 proof_files 字段解析证明图片 ID（忽略无法识别的项）
+pub fn proof_image_ids(proof_files: Option<&serde_json::Value>) -> Vec<Uuid> {
+    proof_files
+        .and_then(|v| v.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.as_str())
+                .filter_map(|s| Uuid::parse_str(s).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 申领列表响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimListResponse {
+    pub claims: Vec<ClaimResponse>,
+    pub total: i64,
+    pub page: i32,
+    pub per_page: i32,
+}
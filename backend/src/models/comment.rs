@@ -2,6 +2,15 @@ use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+/// 回复的最大层级（顶层评论为 0），超过时回复挂在上级评论下
+pub const MAX_COMMENT_DEPTH: i32 = 3;
+
+/// 单条评论最多提醒的用户数
+pub const MAX_COMMENT_MENTIONS: usize = 10;
+
+/// 举报补充说明的最大长度
+pub const MAX_REPORT_DETAIL_LEN: usize = 500;
+
 /// 评论实体
 #[derive(Debug, sqlx::FromRow, Serialize)]
 pub struct Comment {
@@ -12,12 +21,30 @@ pub struct Comment {
     pub audit_status: String,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    pub parent_id: Option<Uuid>,
+    pub root_id: Option<Uuid>,
+    pub depth: i32,
+    pub reply_to_user_id: Option<Uuid>,
+    pub like_count: i32,
+    /// 未处理的举报数
+    pub report_count: i32,
+    /// 最后编辑时间（未编辑过为空）
+    pub edited_at: Option<NaiveDateTime>,
 }
 
 /// 创建评论请求
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct CreateCommentRequest {
     pub content: String,
+    /// 回复的评论 ID（发表顶层评论时为空）
+    pub parent_id: Option<Uuid>,
+}
+
+/// 编辑评论请求
+#[derive(Debug, Deserialize)]
+pub struct UpdateCommentRequest {
+    pub content: String,
 }
 
 /// 评论响应（包含用户信息）
@@ -33,8 +60,30 @@ pub struct CommentResponse {
     #[serde(rename = "userAvatar")]
     pub user_avatar: Option<String>,
     pub content: String,
+    /// approved: 已公开；pending: 自动审核未通过，等待人工审核
+    #[serde(rename = "auditStatus")]
+    pub audit_status: String,
     #[serde(rename = "createdAt")]
     pub created_at: String, // 使用 String 类型，在构造时格式化为 ISO 8601 格式
+    #[serde(rename = "parentId")]
+    pub parent_id: Option<Uuid>,
+    #[serde(rename = "rootId")]
+    pub root_id: Option<Uuid>,
+    pub depth: i32,
+    /// 被回复的用户
+    #[serde(rename = "replyToUserId")]
+    pub reply_to_user_id: Option<Uuid>,
+    #[serde(rename = "replyToUserName")]
+    pub reply_to_user_name: Option<String>,
+    #[serde(rename = "likeCount")]
+    pub like_count: i32,
+    /// 当前用户是否已点赞（未登录时为 false）
+    #[serde(rename = "isLiked")]
+    pub is_liked: bool,
+    #[serde(rename = "editedAt")]
+    pub edited_at: Option<String>,
+    /// 子回复（按时间正序）
+    pub replies: Vec<CommentResponse>,
 }
 
 /// 评论列表查询
@@ -45,12 +94,102 @@ pub struct CommentListQuery {
     pub per_page: Option<i64>,
 }
 
-/// 评论列表响应
+/// 评论列表响应（按楼层分页，每个顶层评论带完整的回复树）
 #[derive(Debug, Serialize)]
 pub struct CommentListResponse {
     pub comments: Vec<CommentResponse>,
+    /// 顶层评论（楼层）总数
     pub total: i64,
+    /// 包含回复在内的评论总数
+    #[serde(rename = "totalComments")]
+    pub total_comments: i64,
     pub page: i64,
     #[serde(rename = "perPage")]
     pub per_page: i64,
 }
+
+/// 评论编辑记录（保存编辑前的内容）
+#[derive(Debug, sqlx::FromRow, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentEditRecord {
+    pub id: Uuid,
+    pub content: String,
+    /// 被替换的时间
+    pub created_at: NaiveDateTime,
+}
+
+/// 评论编辑历史响应（按时间倒序）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentEditHistoryResponse {
+    pub comment_id: Uuid,
+    pub content: String,
+    pub edited_at: Option<NaiveDateTime>,
+    pub edits: Vec<CommentEditRecord>,
+}
+
+/// 举报原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentReportReason {
+    Spam,
+    Abuse,
+    Illegal,
+    Other,
+}
+
+impl CommentReportReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommentReportReason::Spam => "spam",
+            CommentReportReason::Abuse => "abuse",
+            CommentReportReason::Illegal => "illegal",
+            CommentReportReason::Other => "other",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "spam" => Some(CommentReportReason::Spam),
+            "abuse" => Some(CommentReportReason::Abuse),
+            "illegal" => Some(CommentReportReason::Illegal),
+            "other" => Some(CommentReportReason::Other),
+            _ => None,
+        }
+    }
+}
+
+/// 举报评论请求
+#[derive(Debug, Deserialize)]
+pub struct ReportCommentRequest {
+    /// spam、abuse、illegal 或 other
+    pub reason: String,
+    /// 补充说明（原因为 other 时必填）
+    pub detail: Option<String>,
+}
+
+impl ReportCommentRequest {
+    /// 验证请求，返回举报原因
+    pub fn validate(&self) -> Result<CommentReportReason, String> {
+        let reason = CommentReportReason::parse(&self.reason)
+            .ok_or_else(|| "举报原因必须是 spam、abuse、illegal 或 other".to_string())?;
+
+        let detail = self.detail.as_deref().map(str::trim).unwrap_or_default();
+        if reason == CommentReportReason::Other && detail.is_empty() {
+            return Err("选择其他原因时请填写补充说明".to_string());
+        }
+        if detail.chars().count() > MAX_REPORT_DETAIL_LEN {
+            return Err(format!("补充说明不能超过{}个字符", MAX_REPORT_DETAIL_LEN));
+        }
+        Ok(reason)
+    }
+}
+
+/// 举报评论响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentReportResponse {
+    pub report_count: i32,
+    /// 举报数达到阈值后评论被自动隐藏，等待管理员处理
+    pub hidden: bool,
+    pub message: String,
+}
@@ -142,3 +142,19 @@ pub struct CheckResourceInFavoriteResponse {
     pub in_favorites: Vec<Uuid>,
     pub is_favorited: bool,
 }
+
+/// 打包下载所需的资源信息
+#[derive(Debug, Clone, FromRow)]
+pub struct FavoritePackResource {
+    pub id: Uuid,
+    pub title: String,
+    pub file_path: String,
+    pub resource_type: String,
+    pub file_size: Option<i64>,
+    pub storage_type: String,
+    pub course_name: Option<String>,
+    /// 关联教师姓名（以顿号分隔）
+    pub teacher_names: Option<String>,
+    /// 关联课程名称（以顿号分隔）
+    pub course_names: Option<String>,
+}
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+use super::AuthResponse;
+
+/// 两步验证状态响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaStatusResponse {
+    pub enabled: bool,
+    /// 管理员必须启用两步验证
+    pub required: bool,
+    /// 剩余可用的恢复码数量
+    pub recovery_codes_remaining: i64,
+}
+
+/// 开始绑定身份验证器的响应（密钥只在绑定时返回）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaEnrollmentResponse {
+    pub secret: String,
+    /// otpauth:// 链接，前端生成二维码供身份验证器 App 扫描
+    pub otpauth_url: String,
+}
+
+/// 提交动态码的请求 DTO（确认绑定、关闭两步验证、重新生成恢复码）
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaCodeRequest {
+    pub code: String,
+}
+
+/// 恢复码响应（明文只返回这一次）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaRecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+/// 登录第二步请求 DTO：动态码和恢复码二选一
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaLoginRequest {
+    pub mfa_token: String,
+    pub code: Option<String>,
+    pub recovery_code: Option<String>,
+}
+
+impl MfaLoginRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        let filled =
+            |value: &Option<String>| value.as_deref().is_some_and(|v| !v.trim().is_empty());
+        match (filled(&self.code), filled(&self.recovery_code)) {
+            (true, false) | (false, true) => Ok(()),
+            _ => Err("请提供动态码或恢复码其中之一".to_string()),
+        }
+    }
+}
+
+/// 密码验证通过、等待第二步验证的响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaChallengeResponse {
+    pub mfa_required: bool,
+    /// 登录第二步使用的临时凭证
+    pub mfa_token: String,
+    pub expires_in: i64,
+}
+
+/// 登录结果：直接登录成功，或需要完成两步验证
+#[derive(Debug)]
+pub enum LoginOutcome {
+    Authenticated(AuthResponse),
+    MfaRequired(MfaChallengeResponse),
+}
@@ -1,18 +1,36 @@
 // 数据模型层模块
 
+pub mod api_token;
+pub mod claim;
 pub mod comment;
 pub mod course;
 pub mod favorite;
 pub mod image;
 pub mod like;
+pub mod mfa;
+pub mod moderation;
 pub mod notification;
+pub mod permission;
 pub mod rating;
 pub mod resource;
+pub mod resource_editor;
+pub mod resource_version;
+pub mod session;
+pub mod sso;
+pub mod storage_check;
+pub mod storage_migration;
+pub mod storage_quota;
 pub mod teacher;
+pub mod upload_session;
 pub mod user;
+pub mod verification;
 
 // 模型导出供其他模块使用
 #[allow(unused_imports)]
+pub use api_token::*;
+#[allow(unused_imports)]
+pub use claim::*;
+#[allow(unused_imports)]
 pub use comment::*;
 #[allow(unused_imports)]
 pub use course::*;
@@ -23,12 +41,36 @@ pub use image::*;
 #[allow(unused_imports)]
 pub use like::*;
 #[allow(unused_imports)]
+pub use mfa::*;
+#[allow(unused_imports)]
+pub use moderation::*;
+#[allow(unused_imports)]
 pub use notification::*;
 #[allow(unused_imports)]
+pub use permission::*;
+#[allow(unused_imports)]
 pub use rating::*;
 #[allow(unused_imports)]
 pub use resource::*;
 #[allow(unused_imports)]
+pub use resource_editor::*;
+#[allow(unused_imports)]
+pub use resource_version::*;
+#[allow(unused_imports)]
+pub use session::*;
+#[allow(unused_imports)]
+pub use sso::*;
+#[allow(unused_imports)]
+pub use storage_check::*;
+#[allow(unused_imports)]
+pub use storage_migration::*;
+#[allow(unused_imports)]
+pub use storage_quota::*;
+#[allow(unused_imports)]
 pub use teacher::*;
 #[allow(unused_imports)]
+pub use upload_session::*;
+#[allow(unused_imports)]
 pub use user::*;
+#[allow(unused_imports)]
+pub use verification::*;
@@ -0,0 +1,153 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// 审核规则实体（对应数据库 moderation_rules 表）
+#[derive(Debug, Clone, FromRow, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModerationRule {
+    pub id: Uuid,
+    pub pattern: String,
+    pub match_type: String,
+    pub target: String,
+    pub reason: Option<String>,
+    pub is_active: bool,
+    pub created_by: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// 规则匹配方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleMatchType {
+    /// 不区分大小写的子串匹配
+    Keyword,
+    /// 正则表达式
+    Regex,
+}
+
+impl RuleMatchType {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "keyword" => Some(Self::Keyword),
+            "regex" => Some(Self::Regex),
+            _ => None,
+        }
+    }
+}
+
+/// 审核对象
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModerationTarget {
+    Resource,
+    Comment,
+}
+
+impl ModerationTarget {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Resource => "resource",
+            Self::Comment => "comment",
+        }
+    }
+}
+
+/// 校验规则字段，正则规则需能成功编译
+fn validate_rule(pattern: &str, match_type: &str, target: &str) -> Result<(), String> {
+    if pattern.trim().is_empty() {
+        return Err("规则内容不能为空".to_string());
+    }
+    if pattern.chars().count() > 500 {
+        return Err("规则内容不能超过500个字符".to_string());
+    }
+
+    match RuleMatchType::parse(match_type) {
+        Some(RuleMatchType::Regex) => {
+            if let Err(e) = regex::RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+            {
+                return Err(format!("正则表达式无效: {}", e));
+            }
+        }
+        Some(RuleMatchType::Keyword) => {}
+        None => return Err("matchType 只能是 keyword 或 regex".to_string()),
+    }
+
+    if !matches!(target, "all" | "resource" | "comment") {
+        return Err("target 只能是 all、resource 或 comment".to_string());
+    }
+
+    Ok(())
+}
+
+/// 创建审核规则请求 DTO
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateModerationRuleRequest {
+    pub pattern: String,
+    #[serde(default = "default_match_type")]
+    pub match_type: String,
+    #[serde(default = "default_target")]
+    pub target: String,
+    pub reason: Option<String>,
+}
+
+fn default_match_type() -> String {
+    "keyword".to_string()
+}
+
+fn default_target() -> String {
+    "all".to_string()
+}
+
+impl CreateModerationRuleRequest {
+    /// 验证请求
+    pub fn validate(&self) -> Result<(), String> {
+        validate_rule(&self.pattern, &self.match_type, &self.target)?;
+        if let Some(ref reason) = self.reason {
+            if reason.chars().count() > 200 {
+                return Err("命中说明不能超过200个字符".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 更新审核规则请求 DTO
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateModerationRuleRequest {
+    pub pattern: Option<String>,
+    pub match_type: Option<String>,
+    pub target: Option<String>,
+    pub reason: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+impl UpdateModerationRuleRequest {
+    /// 与已有规则合并后验证
+    pub fn validate(&self, current: &ModerationRule) -> Result<(), String> {
+        validate_rule(
+            self.pattern.as_deref().unwrap_or(&current.pattern),
+            self.match_type.as_deref().unwrap_or(&current.match_type),
+            self.target.as_deref().unwrap_or(&current.target),
+        )?;
+        if let Some(ref reason) = self.reason {
+            if reason.chars().count() > 200 {
+                return Err("命中说明不能超过200个字符".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 试运行审核请求 DTO（管理员调试规则用）
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestModerationRequest {
+    pub content: String,
+    /// resource（默认）或 comment
+    pub target: Option<String>,
+}
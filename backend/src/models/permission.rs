@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 可授予的管理权限
+///
+/// 管理员拥有全部权限；普通用户（如课程助教、学生版主）可被单独授予其中几项。
+/// 用户管理和权限授予只属于管理员，不能被授予
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    /// 审核资源、查看重复资源
+    AuditResources,
+    /// 审核、删除评论及处理评论举报
+    ModerateComments,
+    /// 管理内容审核规则
+    ManageModerationRules,
+    /// 管理课程和教师信息
+    ManageCourses,
+    /// 发送系统通知
+    SendNotifications,
+    /// 审核作者申领
+    ReviewClaims,
+    /// 审核实名认证
+    ReviewVerifications,
+    /// 查看审计日志
+    ViewAuditLogs,
+    /// 查看仪表盘和统计数据
+    ViewStats,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 9] = [
+        Permission::AuditResources,
+        Permission::ModerateComments,
+        Permission::ManageModerationRules,
+        Permission::ManageCourses,
+        Permission::SendNotifications,
+        Permission::ReviewClaims,
+        Permission::ReviewVerifications,
+        Permission::ViewAuditLogs,
+        Permission::ViewStats,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::AuditResources => "audit_resources",
+            Permission::ModerateComments => "moderate_comments",
+            Permission::ManageModerationRules => "manage_moderation_rules",
+            Permission::ManageCourses => "manage_courses",
+            Permission::SendNotifications => "send_notifications",
+            Permission::ReviewClaims => "review_claims",
+            Permission::ReviewVerifications => "review_verifications",
+            Permission::ViewAuditLogs => "view_audit_logs",
+            Permission::ViewStats => "view_stats",
+        }
+    }
+
+    /// 权限说明（用于管理界面展示和错误提示）
+    pub fn description(&self) -> &'static str {
+        match self {
+            Permission::AuditResources => "审核资源",
+            Permission::ModerateComments => "管理评论",
+            Permission::ManageModerationRules => "管理内容审核规则",
+            Permission::ManageCourses => "管理课程和教师",
+            Permission::SendNotifications => "发送系统通知",
+            Permission::ReviewClaims => "审核作者申领",
+            Permission::ReviewVerifications => "审核实名认证",
+            Permission::ViewAuditLogs => "查看审计日志",
+            Permission::ViewStats => "查看统计数据",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.as_str() == s)
+    }
+}
+
+impl std::fmt::Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 权限说明 DTO
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionInfo {
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+impl From<Permission> for PermissionInfo {
+    fn from(permission: Permission) -> Self {
+        Self {
+            name: permission.as_str(),
+            description: permission.description(),
+        }
+    }
+}
+
+/// 设置用户权限请求 DTO（整体替换）
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateUserPermissionsRequest {
+    pub permissions: Vec<String>,
+}
+
+impl UpdateUserPermissionsRequest {
+    /// 校验请求并返回去重后的权限列表
+    pub fn validate(&self) -> Result<Vec<Permission>, String> {
+        let mut permissions = Vec::new();
+        for name in &self.permissions {
+            let permission =
+                Permission::parse(name.trim()).ok_or_else(|| format!("未知的权限: {}", name))?;
+            if !permissions.contains(&permission) {
+                permissions.push(permission);
+            }
+        }
+        Ok(permissions)
+    }
+}
+
+/// 用户权限响应 DTO
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserPermissionsResponse {
+    pub user_id: Uuid,
+    pub username: String,
+    pub role: String,
+    /// 实际拥有的权限（管理员为全部权限）
+    pub permissions: Vec<&'static str>,
+}
+
+/// 被授予权限的用户列表响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionGrantListResponse {
+    pub users: Vec<UserPermissionsResponse>,
+    /// 可授予的权限
+    pub available_permissions: Vec<PermissionInfo>,
+}
@@ -103,7 +103,7 @@ where
 }
 
 /// 资源类型枚举
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, sqlx::Type, PartialEq)]
 #[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
 pub enum ResourceType {
@@ -130,31 +130,27 @@ pub enum ResourceType {
     /// ZIP 压缩包（源文件）
     Zip,
     /// 其他类型
+    #[default]
     Other,
 }
 
-impl Default for ResourceType {
-    fn default() -> Self {
-        ResourceType::Other
-    }
-}
-
-impl ToString for ResourceType {
-    fn to_string(&self) -> String {
-        match self {
-            ResourceType::WebMarkdown => "web_markdown".to_string(),
-            ResourceType::Ppt => "ppt".to_string(),
-            ResourceType::Pptx => "pptx".to_string(),
-            ResourceType::Doc => "doc".to_string(),
-            ResourceType::Docx => "docx".to_string(),
-            ResourceType::Pdf => "pdf".to_string(),
-            ResourceType::Txt => "txt".to_string(),
-            ResourceType::Jpeg => "jpeg".to_string(),
-            ResourceType::Jpg => "jpg".to_string(),
-            ResourceType::Png => "png".to_string(),
-            ResourceType::Zip => "zip".to_string(),
-            ResourceType::Other => "other".to_string(),
-        }
+impl std::fmt::Display for ResourceType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            ResourceType::WebMarkdown => "web_markdown",
+            ResourceType::Ppt => "ppt",
+            ResourceType::Pptx => "pptx",
+            ResourceType::Doc => "doc",
+            ResourceType::Docx => "docx",
+            ResourceType::Pdf => "pdf",
+            ResourceType::Txt => "txt",
+            ResourceType::Jpeg => "jpeg",
+            ResourceType::Jpg => "jpg",
+            ResourceType::Png => "png",
+            ResourceType::Zip => "zip",
+            ResourceType::Other => "other",
+        };
+        f.write_str(s)
     }
 }
 
@@ -224,7 +220,7 @@ impl ResourceType {
 }
 
 /// 资源分类枚举
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, sqlx::Type, PartialEq)]
 #[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
 pub enum ResourceCategory {
@@ -241,35 +237,32 @@ pub enum ResourceCategory {
     /// 讲义
     Lecture,
     /// 其他
+    #[default]
     Other,
 }
 
-impl Default for ResourceCategory {
-    fn default() -> Self {
-        ResourceCategory::Other
-    }
-}
-
-impl ToString for ResourceCategory {
-    fn to_string(&self) -> String {
-        match self {
-            ResourceCategory::ExamResult => "exam_result".to_string(),
-            ResourceCategory::LearningNote => "learning_note".to_string(),
-            ResourceCategory::PastPaper => "past_paper".to_string(),
-            ResourceCategory::Note => "note".to_string(),
-            ResourceCategory::ReviewOutline => "review_outline".to_string(),
-            ResourceCategory::Lecture => "lecture".to_string(),
-            ResourceCategory::Other => "other".to_string(),
-        }
+impl std::fmt::Display for ResourceCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            ResourceCategory::ExamResult => "exam_result",
+            ResourceCategory::LearningNote => "learning_note",
+            ResourceCategory::PastPaper => "past_paper",
+            ResourceCategory::Note => "note",
+            ResourceCategory::ReviewOutline => "review_outline",
+            ResourceCategory::Lecture => "lecture",
+            ResourceCategory::Other => "other",
+        };
+        f.write_str(s)
     }
 }
 
 /// 审核状态枚举
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, sqlx::Type, PartialEq)]
 #[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
 pub enum AuditStatus {
     /// 待审核
+    #[default]
     Pending,
     /// 已通过
     Approved,
@@ -277,19 +270,14 @@ pub enum AuditStatus {
     Rejected,
 }
 
-impl Default for AuditStatus {
-    fn default() -> Self {
-        AuditStatus::Pending
-    }
-}
-
-impl ToString for AuditStatus {
-    fn to_string(&self) -> String {
-        match self {
-            AuditStatus::Pending => "pending".to_string(),
-            AuditStatus::Approved => "approved".to_string(),
-            AuditStatus::Rejected => "rejected".to_string(),
-        }
+impl std::fmt::Display for AuditStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            AuditStatus::Pending => "pending",
+            AuditStatus::Approved => "approved",
+            AuditStatus::Rejected => "rejected",
+        };
+        f.write_str(s)
     }
 }
 
@@ -315,6 +303,7 @@ pub struct Resource {
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
     pub storage_type: Option<String>,
+    pub description: Option<String>,
 }
 
 /// 资源统计信息（对应数据库 resource_stats 表）
@@ -462,6 +451,13 @@ impl UploadResourceRequest {
             return Err("资源标题不能超过255个字符".to_string());
         }
 
+        // 描述验证（如果提供）
+        if let Some(description) = &self.description {
+            if description.chars().count() > 2000 {
+                return Err("资源描述不能超过2000个字符".to_string());
+            }
+        }
+
         // 标签验证（如果提供）
         if let Some(tags) = &self.tags {
             if tags.len() > 10 {
@@ -566,6 +562,9 @@ pub struct ResourceListItem {
     pub uploader_name: Option<String>,
     /// 存储类型：local 或 oss
     pub storage_type: String,
+    /// 搜索命中摘要（仅搜索接口返回，命中词用 <mark> 包裹）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
 }
 
 /// 资源列表查询参数
@@ -609,7 +608,7 @@ impl ResourceListQuery {
     }
 
     pub fn get_per_page(&self) -> i32 {
-        self.per_page.unwrap_or(20).min(100).max(1)
+        self.per_page.unwrap_or(20).clamp(1, 100)
     }
 }
 
@@ -619,12 +618,20 @@ impl ResourceSearchQuery {
     }
 
     pub fn get_per_page(&self) -> i32 {
-        self.per_page.unwrap_or(20).min(100).max(1)
+        self.per_page.unwrap_or(20).clamp(1, 100)
     }
 }
 
+/// 随上传请求提交的文件
+pub struct UploadedFile<'a> {
+    pub name: &'a str,
+    pub data: Vec<u8>,
+    pub mime_type: Option<&'a str>,
+}
+
 /// AI 审核结果
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct AiAuditResult {
     pub passed: bool,
     pub reason: Option<String>,
@@ -636,6 +643,10 @@ pub struct AiAuditResult {
 #[serde(rename_all = "camelCase")]
 pub struct UpdateResourceContentRequest {
     pub content: String,
+    /// 版本说明（可选）
+    pub change_note: Option<String>,
+    /// 开始编辑时读取到的 updatedAt，保存时资源已被他人修改则拒绝（乐观锁）
+    pub expected_updated_at: NaiveDateTime,
 }
 
 /// 更新资源关联信息请求 DTO
@@ -650,6 +661,44 @@ pub struct UpdateResourceRelationsRequest {
     pub related_resource_ids: Vec<Uuid>,
 }
 
+/// 追加资源关联信息请求 DTO
+/// 用于重复上传时把课程/教师关联补充到已有资源上（只增不删）
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachResourceRelationsRequest {
+    /// 上传文件的哈希，非上传者/管理员需提供且与原资源一致
+    pub file_hash: Option<String>,
+    /// 追加的教师编号列表
+    #[serde(default)]
+    pub teacher_sns: Vec<i64>,
+    /// 追加的课程编号列表
+    #[serde(default)]
+    pub course_sns: Vec<i64>,
+}
+
+impl AttachResourceRelationsRequest {
+    /// 验证请求
+    pub fn validate(&self) -> Result<(), String> {
+        if self.teacher_sns.is_empty() && self.course_sns.is_empty() {
+            return Err("请至少选择一个教师或课程".to_string());
+        }
+        if self.teacher_sns.len() > 20 || self.course_sns.len() > 20 {
+            return Err("单次追加的教师或课程不能超过20个".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// 重复资源信息（上传内容与已有资源相同时返回）
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateResourceInfo {
+    pub id: Uuid,
+    pub title: String,
+    pub file_hash: String,
+    pub created_at: NaiveDateTime,
+}
+
 impl UpdateResourceContentRequest {
     /// 验证请求
     pub fn validate(&self) -> Result<(), String> {
@@ -671,6 +720,17 @@ impl UpdateResourceContentRequest {
 pub struct UpdateResourceContentResponse {
     pub id: Uuid,
     pub updated_at: chrono::NaiveDateTime,
+    /// 保存后的当前版本号
+    pub version_number: i32,
+}
+
+/// 资源原始内容响应 DTO（用于Markdown编辑）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceRawContentResponse {
+    pub content: String,
+    pub updated_at: NaiveDateTime,
+    pub version_number: i32,
 }
 
 /// 热门资源查询参数
@@ -692,3 +752,38 @@ pub struct HotResourceItem {
     pub views: i32,
     pub likes: i32,
 }
+
+/// 推荐查询参数
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecommendationQuery {
+    pub limit: Option<i64>,
+}
+
+impl RecommendationQuery {
+    pub fn get_limit(&self) -> i64 {
+        self.limit.unwrap_or(10).clamp(1, 50)
+    }
+}
+
+/// 推荐理由
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecommendationReason {
+    /// 与你（或下载过此资源的同学）兴趣相近的用户也下载/收藏了
+    AlsoDownloaded,
+    /// 同一课程的资源
+    SameCourse,
+    /// 近期热门资源（行为数据不足时补位）
+    Popular,
+}
+
+/// 推荐资源项 DTO
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecommendedResource {
+    #[serde(flatten)]
+    pub resource: ResourceListItem,
+    pub score: f64,
+    pub reason: RecommendationReason,
+}
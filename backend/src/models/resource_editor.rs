@@ -0,0 +1,41 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// 资源协作者（对应数据库 resource_editors 表）
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceEditorResponse {
+    pub user_id: Uuid,
+    pub username: String,
+    pub invited_by: Option<Uuid>,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+/// 资源协作者列表响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceEditorListResponse {
+    pub resource_id: Uuid,
+    /// 资源上传者（拥有者），可邀请和移除协作者
+    pub owner_id: Uuid,
+    pub editors: Vec<ResourceEditorResponse>,
+}
+
+/// 邀请协作者请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddResourceEditorRequest {
+    pub username: String,
+}
+
+impl AddResourceEditorRequest {
+    /// 验证请求
+    pub fn validate(&self) -> Result<(), String> {
+        if self.username.trim().is_empty() {
+            return Err("用户名不能为空".to_string());
+        }
+        Ok(())
+    }
+}
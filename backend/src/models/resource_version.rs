@@ -0,0 +1,87 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::utils::DiffHunk;
+
+/// 资源版本（对应数据库 resource_versions 表）
+#[derive(Debug, Clone, FromRow)]
+pub struct ResourceVersion {
+    pub version_number: i32,
+    pub resource_type: String,
+    pub file_path: String,
+    pub storage_type: Option<String>,
+    pub file_hash: Option<String>,
+    pub file_size: Option<i64>,
+    /// 被新版本替代时的审核状态，当前版本以 resources.audit_status 为准
+    pub audit_status: String,
+    pub change_note: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+/// 版本列表项
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceVersionItem {
+    pub version_number: i32,
+    pub resource_type: String,
+    pub file_size: Option<i64>,
+    pub file_hash: Option<String>,
+    pub audit_status: String,
+    pub change_note: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_by_name: Option<String>,
+    pub created_at: Option<NaiveDateTime>,
+    pub is_current: bool,
+}
+
+/// 版本列表响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceVersionListResponse {
+    pub resource_id: Uuid,
+    pub current_version: i32,
+    pub versions: Vec<ResourceVersionItem>,
+}
+
+/// 版本差异查询参数（to 为空时与当前版本比较）
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceVersionDiffQuery {
+    pub from: i32,
+    pub to: Option<i32>,
+    /// 每个差异片段保留的上下文行数，默认 3 行
+    pub context: Option<usize>,
+}
+
+impl ResourceVersionDiffQuery {
+    pub fn get_context(&self) -> usize {
+        self.context.unwrap_or(3).min(20)
+    }
+}
+
+/// 版本差异响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceVersionDiffResponse {
+    pub resource_id: Uuid,
+    pub from_version: i32,
+    pub to_version: i32,
+    pub additions: usize,
+    pub deletions: usize,
+    pub hunks: Vec<DiffHunk>,
+}
+
+/// 新建版本（重新上传或回滚）响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateResourceVersionResponse {
+    pub resource_id: Uuid,
+    pub title: String,
+    pub version_number: i32,
+    pub audit_status: String,
+    pub ai_message: Option<String>,
+    pub updated_at: NaiveDateTime,
+}
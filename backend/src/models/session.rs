@@ -0,0 +1,53 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// 会话吊销原因
+pub const REVOKE_REASON_LOGOUT: &str = "logout";
+pub const REVOKE_REASON_USER: &str = "user_revoked";
+pub const REVOKE_REASON_DISABLED: &str = "user_disabled";
+pub const REVOKE_REASON_REUSE: &str = "reuse_detected";
+pub const REVOKE_REASON_PASSWORD_CHANGED: &str = "password_changed";
+pub const REVOKE_REASON_PASSWORD_RESET: &str = "password_reset";
+
+/// 登录请求的客户端信息（记录到会话中）
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+/// 会话记录（对应数据库 user_sessions 表）
+#[derive(Debug, FromRow)]
+pub struct UserSession {
+    pub id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub expires_at: NaiveDateTime,
+}
+
+/// 会话响应 DTO
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionResponse {
+    pub id: Uuid,
+    /// 由 User-Agent 解析出的设备描述，如 "Chrome / Windows"
+    pub device: String,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub expires_at: NaiveDateTime,
+    /// 是否为发起请求的会话
+    pub is_current: bool,
+}
+
+/// 会话列表响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionListResponse {
+    pub sessions: Vec<SessionResponse>,
+}
@@ -0,0 +1,82 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// 统一身份认证方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SsoProvider {
+    /// OpenID Connect 授权码模式
+    Oidc,
+    /// CAS 2.0/3.0 票据校验
+    Cas,
+}
+
+impl SsoProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SsoProvider::Oidc => "oidc",
+            SsoProvider::Cas => "cas",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "oidc" => Some(SsoProvider::Oidc),
+            "cas" => Some(SsoProvider::Cas),
+            _ => None,
+        }
+    }
+}
+
+/// 身份提供方断言的用户信息
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExternalIdentity {
+    /// 身份提供方中的唯一标识（OIDC 的 sub，CAS 的用户名）
+    pub subject: String,
+    /// 创建新账号时参考的用户名
+    pub username_hint: Option<String>,
+    pub email: Option<String>,
+    /// 身份提供方是否确认过该邮箱
+    pub email_verified: bool,
+    pub real_name: Option<String>,
+    /// 身份提供方提供学号时自动完成实名认证
+    pub student_id: Option<String>,
+}
+
+/// 已绑定的外部身份（对应数据库 user_identities 表）
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct UserIdentityResponse {
+    pub id: Uuid,
+    pub provider: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub last_login_at: Option<NaiveDateTime>,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+/// 已绑定的外部身份列表响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserIdentityListResponse {
+    pub identities: Vec<UserIdentityResponse>,
+}
+
+/// 已启用的统一身份认证方式响应（登录页据此展示按钮）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SsoProvidersResponse {
+    pub oidc: bool,
+    pub cas: bool,
+}
+
+/// 身份提供方回调参数（OIDC 返回 code，CAS 返回 ticket）
+#[derive(Debug, Deserialize)]
+pub struct SsoCallbackQuery {
+    pub state: Option<String>,
+    pub code: Option<String>,
+    pub ticket: Option<String>,
+    /// OIDC 授权失败时身份提供方返回的错误码
+    pub error: Option<String>,
+}
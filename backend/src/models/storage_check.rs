@@ -0,0 +1,165 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// 存储一致性检查任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageCheckStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+impl StorageCheckStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StorageCheckStatus::Running => "running",
+            StorageCheckStatus::Completed => "completed",
+            StorageCheckStatus::Failed => "failed",
+        }
+    }
+}
+
+/// 存储一致性问题类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageIssueType {
+    /// 存储中有文件，但没有任何记录引用
+    OrphanFile,
+    /// 记录引用的文件在存储中不存在
+    MissingFile,
+    /// 文件大小与记录不一致
+    SizeMismatch,
+    /// 文件哈希与记录不一致
+    HashMismatch,
+}
+
+impl StorageIssueType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StorageIssueType::OrphanFile => "orphan_file",
+            StorageIssueType::MissingFile => "missing_file",
+            StorageIssueType::SizeMismatch => "size_mismatch",
+            StorageIssueType::HashMismatch => "hash_mismatch",
+        }
+    }
+}
+
+/// 对问题采取的处理
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageIssueAction {
+    /// 只记录，未做处理
+    Reported,
+    /// 孤儿文件已删除
+    Deleted,
+    /// 孤儿文件仍在宽限期内，可能是正在上传的文件，未删除
+    KeptRecent,
+    DeleteFailed,
+}
+
+impl StorageIssueAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StorageIssueAction::Reported => "reported",
+            StorageIssueAction::Deleted => "deleted",
+            StorageIssueAction::KeptRecent => "kept_recent",
+            StorageIssueAction::DeleteFailed => "delete_failed",
+        }
+    }
+}
+
+/// 发起存储一致性检查请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartStorageCheckRequest {
+    /// 只检查指定存储：local / oss / s3，默认检查所有已配置的存储
+    pub backend: Option<String>,
+    /// 是否删除孤儿文件，默认 false（只生成报告）
+    pub cleanup: Option<bool>,
+    /// 是否读取文件校验哈希，默认 false（只比较大小）
+    pub verify_hash: Option<bool>,
+    /// 孤儿文件宽限期（小时），默认 24，最少 1
+    pub grace_hours: Option<i32>,
+}
+
+impl StartStorageCheckRequest {
+    /// 默认宽限期（小时）
+    pub const DEFAULT_GRACE_HOURS: i32 = 24;
+
+    /// 验证请求
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(backend) = &self.backend {
+            if !matches!(backend.as_str(), "local" | "oss" | "s3") {
+                return Err(format!("不支持的存储类型: {}", backend));
+            }
+        }
+        if let Some(grace_hours) = self.grace_hours {
+            if !(1..=24 * 365).contains(&grace_hours) {
+                return Err("宽限期必须在 1 小时到 1 年之间".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 存储一致性检查任务（对应数据库 storage_checks 表）
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageCheckResponse {
+    pub id: Uuid,
+    /// 本次检查的存储后端，逗号分隔
+    pub backends: String,
+    pub cleanup: bool,
+    pub verify_hash: bool,
+    pub grace_hours: i32,
+    pub status: String,
+    pub scanned_files: i32,
+    pub scanned_records: i32,
+    pub orphan_files: i32,
+    pub missing_files: i32,
+    /// 大小或哈希与记录不一致的文件数
+    pub mismatched_files: i32,
+    pub deleted_files: i32,
+    pub deleted_bytes: i64,
+    pub last_error: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+    pub finished_at: Option<NaiveDateTime>,
+}
+
+/// 检查发现的问题
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageCheckIssueItem {
+    pub backend: String,
+    /// orphan_file / missing_file / size_mismatch / hash_mismatch
+    pub issue_type: String,
+    pub file_path: String,
+    /// resource / resource_version / image，孤儿文件为空
+    pub item_type: Option<String>,
+    pub item_id: Option<Uuid>,
+    pub expected_size: Option<i64>,
+    pub actual_size: Option<i64>,
+    /// reported / deleted / kept_recent / delete_failed
+    pub action: String,
+    pub detail: Option<String>,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+/// 查询检查任务详情的参数
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageCheckIssueQuery {
+    /// 只返回指定类型的问题
+    pub issue_type: Option<String>,
+}
+
+/// 存储一致性检查任务详情
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageCheckDetailResponse {
+    pub check: StorageCheckResponse,
+    /// 发现的问题（最多 200 条）
+    pub issues: Vec<StorageCheckIssueItem>,
+}
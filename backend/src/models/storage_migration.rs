@@ -0,0 +1,105 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// 存储迁移任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageMigrationStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl StorageMigrationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StorageMigrationStatus::Running => "running",
+            StorageMigrationStatus::Completed => "completed",
+            StorageMigrationStatus::Failed => "failed",
+            StorageMigrationStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// 发起存储迁移请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartStorageMigrationRequest {
+    /// 源存储：local / oss / s3
+    pub source: String,
+    /// 目标存储：local / oss / s3
+    pub target: String,
+    /// 是否迁移资源文件（含历史版本），默认 true
+    pub include_resources: Option<bool>,
+    /// 是否迁移图床图片，默认 true
+    pub include_images: Option<bool>,
+    /// 迁移成功后是否删除源文件，默认 false
+    pub delete_source: Option<bool>,
+}
+
+impl StartStorageMigrationRequest {
+    /// 验证请求
+    pub fn validate(&self) -> Result<(), String> {
+        for backend in [&self.source, &self.target] {
+            if !matches!(backend.as_str(), "local" | "oss" | "s3") {
+                return Err(format!("不支持的存储类型: {}", backend));
+            }
+        }
+        if self.source == self.target {
+            return Err("源存储和目标存储不能相同".to_string());
+        }
+        if !self.include_resources.unwrap_or(true) && !self.include_images.unwrap_or(true) {
+            return Err("至少需要迁移资源或图片中的一类".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// 存储迁移任务（对应数据库 storage_migrations 表）
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageMigrationResponse {
+    pub id: Uuid,
+    pub source_backend: String,
+    pub target_backend: String,
+    pub include_resources: bool,
+    pub include_images: bool,
+    pub delete_source: bool,
+    pub status: String,
+    pub cancel_requested: bool,
+    pub total_items: i32,
+    pub processed_items: i32,
+    pub migrated_items: i32,
+    pub failed_items: i32,
+    /// 迁移期间文件被修改或删除而跳过的条目数
+    pub skipped_items: i32,
+    pub migrated_bytes: i64,
+    pub last_error: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+    pub finished_at: Option<NaiveDateTime>,
+}
+
+/// 迁移失败的条目
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageMigrationFailureItem {
+    /// resource / resource_version / image
+    pub item_type: String,
+    pub item_id: Uuid,
+    pub file_path: Option<String>,
+    pub error: String,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+/// 存储迁移任务详情
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageMigrationDetailResponse {
+    pub migration: StorageMigrationResponse,
+    /// 最近的失败条目（最多 100 条）
+    pub failures: Vec<StorageMigrationFailureItem>,
+}
@@ -0,0 +1,96 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::UserInfo;
+
+/// 可设置存储配额的角色
+pub const QUOTA_ROLES: [&str; 3] = ["user", "verified", "admin"];
+
+/// 用户存储空间使用情况
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageUsage {
+    /// 已使用字节数（资源 + 历史版本 + 图片）
+    pub used_bytes: i64,
+    pub resource_bytes: i64,
+    /// 资源历史版本（非当前版本）文件占用的字节数
+    pub version_bytes: i64,
+    pub image_bytes: i64,
+    /// 配额字节数，null 表示不限
+    pub quota_bytes: Option<i64>,
+    /// 剩余字节数，不限配额时为 null
+    pub remaining_bytes: Option<i64>,
+    /// 配额来源：role（按角色）/ user（管理员单独设置）
+    pub quota_source: String,
+}
+
+/// 当前用户信息（附带存储空间使用情况）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrentUserResponse {
+    #[serde(flatten)]
+    pub user: UserInfo,
+    /// 获取用量失败时为 null，不影响用户信息的返回
+    pub storage: Option<StorageUsage>,
+}
+
+/// 角色存储配额
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct RoleStorageQuotaItem {
+    pub role: String,
+    /// 配额字节数，null 表示不限
+    pub quota_bytes: Option<i64>,
+    /// 是否为代码中的默认配额（未被管理员修改）
+    pub is_default: bool,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+/// 用户存储配额详情（管理员查看）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserStorageQuotaResponse {
+    pub user_id: Uuid,
+    pub username: String,
+    pub role: String,
+    /// 是否设置了单独的配额
+    pub has_override: bool,
+    pub note: Option<String>,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: Option<NaiveDateTime>,
+    pub usage: StorageUsage,
+}
+
+/// 设置存储配额请求（角色配额和用户配额共用）
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateStorageQuotaRequest {
+    /// 配额字节数，unlimited 为 true 时不填
+    pub quota_bytes: Option<i64>,
+    /// 不限配额
+    #[serde(default)]
+    pub unlimited: bool,
+    /// 备注（仅用户配额使用），如调整原因
+    pub note: Option<String>,
+}
+
+impl UpdateStorageQuotaRequest {
+    /// 验证请求，返回配额（None 表示不限）
+    pub fn validate(&self) -> Result<Option<i64>, String> {
+        if let Some(note) = &self.note {
+            if note.chars().count() > 500 {
+                return Err("备注不能超过 500 个字符".to_string());
+            }
+        }
+        match (self.unlimited, self.quota_bytes) {
+            (true, Some(_)) => Err("不限配额时不能同时指定配额大小".to_string()),
+            (true, None) => Ok(None),
+            (false, None) => Err("请指定配额大小，或设置为不限".to_string()),
+            (false, Some(bytes)) if bytes < 0 => Err("配额不能为负数".to_string()),
+            (false, Some(bytes)) => Ok(Some(bytes)),
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// 分片上传会话实体（对应数据库 upload_sessions 表）
+#[derive(Debug, Clone, FromRow)]
+pub struct UploadSession {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub file_name: String,
+    pub file_size: i64,
+    pub file_hash: String,
+    pub mime_type: Option<String>,
+    pub chunk_size: i32,
+    pub total_chunks: i32,
+    pub received_chunks: Vec<i32>,
+    pub status: String,
+    pub resource_id: Option<Uuid>,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
+
+/// 创建分片上传会话请求 DTO
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InitUploadSessionRequest {
+    pub file_name: String,
+    pub file_size: i64,
+    /// 完整文件的 SHA-256（十六进制）
+    pub file_hash: String,
+    pub mime_type: Option<String>,
+    /// 分片大小（字节，可选，服务端会限制在允许范围内）
+    pub chunk_size: Option<i64>,
+}
+
+impl InitUploadSessionRequest {
+    /// 验证请求数据
+    pub fn validate(&self, max_file_size: i64) -> Result<(), String> {
+        let file_name = self.file_name.trim();
+        if file_name.is_empty() {
+            return Err("文件名不能为空".to_string());
+        }
+        if file_name.chars().count() > 255 {
+            return Err("文件名不能超过255个字符".to_string());
+        }
+
+        if self.file_size <= 0 {
+            return Err("文件不能为空".to_string());
+        }
+        if self.file_size > max_file_size {
+            return Err(format!(
+                "文件大小超过限制。最大允许 {}MB",
+                max_file_size / 1024 / 1024
+            ));
+        }
+
+        if self.file_hash.len() != 64 || !self.file_hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err("fileHash 必须是 64 位十六进制 SHA-256".to_string());
+        }
+
+        if let Some(mime_type) = &self.mime_type {
+            if mime_type.len() > 100 {
+                return Err("mimeType 不能超过100个字符".to_string());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// 分片上传会话状态响应 DTO
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadSessionResponse {
+    pub id: Uuid,
+    pub file_name: String,
+    pub file_size: i64,
+    pub file_hash: String,
+    pub chunk_size: i32,
+    pub total_chunks: i32,
+    pub received_chunks: Vec<i32>,
+    pub missing_chunks: Vec<i32>,
+    pub status: String,
+    pub resource_id: Option<Uuid>,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<UploadSession> for UploadSessionResponse {
+    fn from(session: UploadSession) -> Self {
+        let missing_chunks = (0..session.total_chunks)
+            .filter(|index| !session.received_chunks.contains(index))
+            .collect();
+
+        Self {
+            id: session.id,
+            file_name: session.file_name,
+            file_size: session.file_size,
+            file_hash: session.file_hash,
+            chunk_size: session.chunk_size,
+            total_chunks: session.total_chunks,
+            received_chunks: session.received_chunks,
+            missing_chunks,
+            status: session.status,
+            resource_id: session.resource_id,
+            expires_at: session.expires_at,
+            created_at: session.created_at,
+        }
+    }
+}
@@ -4,13 +4,14 @@ use sqlx::FromRow;
 use uuid::Uuid;
 
 /// 用户角色枚举
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, sqlx::Type, PartialEq)]
 #[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
 pub enum UserRole {
     /// 游客（未登录）
     Guest,
     /// 注册用户
+    #[default]
     User,
     /// 实名用户
     Verified,
@@ -18,20 +19,15 @@ pub enum UserRole {
     Admin,
 }
 
-impl Default for UserRole {
-    fn default() -> Self {
-        UserRole::User
-    }
-}
-
-impl ToString for UserRole {
-    fn to_string(&self) -> String {
-        match self {
-            UserRole::Guest => "guest".to_string(),
-            UserRole::User => "user".to_string(),
-            UserRole::Verified => "verified".to_string(),
-            UserRole::Admin => "admin".to_string(),
-        }
+impl std::fmt::Display for UserRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            UserRole::Guest => "guest",
+            UserRole::User => "user",
+            UserRole::Verified => "verified",
+            UserRole::Admin => "admin",
+        };
+        f.write_str(s)
     }
 }
 
@@ -130,6 +126,12 @@ pub struct Claims {
     pub exp: i64,           // 过期时间
     pub iat: i64,           // 签发时间
     pub token_type: String, // token 类型: access | refresh
+    #[serde(default)]
+    pub sid: String, // 登录会话ID（user_sessions.id）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>, // Refresh Token 标识，每次刷新轮换
+    #[serde(default)]
+    pub mfa: bool, // 本次登录是否完成了两步验证
 }
 
 /// 当前用户信息（从 JWT 中提取）
@@ -139,6 +141,49 @@ pub struct CurrentUser {
     pub username: String,
     pub role: UserRole,
     pub is_verified: bool,
+    pub session_id: Uuid,
+    /// 本次登录是否完成了两步验证
+    pub mfa: bool,
+}
+
+impl CurrentUser {
+    /// 是否可以行使管理员权限
+    ///
+    /// 管理员需要在本次登录时完成两步验证，否则按普通用户处理
+    pub fn is_admin(&self) -> bool {
+        self.role == UserRole::Admin && self.mfa
+    }
+}
+
+/// 修改密码请求 DTO
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangePasswordRequest {
+    pub old_password: String,
+    pub new_password: String,
+}
+
+/// 申请重置密码请求 DTO
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+/// 重置密码请求 DTO
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
+/// 密码验证：至少6个字符
+fn validate_password(password: &str) -> Result<(), String> {
+    if password.len() < 6 {
+        return Err("密码长度至少为6个字符".to_string());
+    }
+    Ok(())
 }
 
 impl RegisterRequest {
@@ -156,10 +201,7 @@ impl RegisterRequest {
             return Err("用户名只能包含字母、数字和下划线".to_string());
         }
 
-        // 密码验证：至少6个字符
-        if self.password.len() < 6 {
-            return Err("密码长度至少为6个字符".to_string());
-        }
+        validate_password(&self.password)?;
 
         // 邮箱验证（如果提供）
         if let Some(email) = &self.email {
@@ -185,6 +227,40 @@ impl LoginRequest {
     }
 }
 
+impl ChangePasswordRequest {
+    /// 验证修改密码请求
+    pub fn validate(&self) -> Result<(), String> {
+        if self.old_password.is_empty() {
+            return Err("原密码不能为空".to_string());
+        }
+        validate_password(&self.new_password)?;
+        if self.old_password == self.new_password {
+            return Err("新密码不能与原密码相同".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl ForgotPasswordRequest {
+    /// 验证申请重置密码请求
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.email.contains('@') {
+            return Err("邮箱格式不正确".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl ResetPasswordRequest {
+    /// 验证重置密码请求
+    pub fn validate(&self) -> Result<(), String> {
+        if self.token.trim().is_empty() {
+            return Err("重置链接无效".to_string());
+        }
+        validate_password(&self.new_password)
+    }
+}
+
 /// 更新用户资料请求 DTO
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -205,6 +281,32 @@ pub struct VerificationRequest {
     pub grade: Option<String>,
 }
 
+impl VerificationRequest {
+    /// 验证实名认证请求
+    pub fn validate(&self) -> Result<(), String> {
+        let real_name = self.real_name.as_deref().map(str::trim).unwrap_or_default();
+        if real_name.is_empty() || real_name.chars().count() > 50 {
+            return Err("姓名不能为空且不能超过50个字符".to_string());
+        }
+
+        let student_id = self
+            .student_id
+            .as_deref()
+            .map(str::trim)
+            .unwrap_or_default();
+        if student_id.is_empty() || student_id.chars().count() > 30 {
+            return Err("学号不能为空且不能超过30个字符".to_string());
+        }
+
+        for value in [&self.major, &self.grade].into_iter().flatten() {
+            if value.chars().count() > 50 {
+                return Err("专业和年级不能超过50个字符".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
 /// 用户资料响应（公开信息）
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -254,7 +356,7 @@ impl UserHomepageQuery {
     }
 
     pub fn get_per_page(&self) -> i32 {
-        self.per_page.unwrap_or(10).min(50).max(1)
+        self.per_page.unwrap_or(10).clamp(1, 50)
     }
 }
 
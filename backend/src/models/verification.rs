@@ -0,0 +1,132 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// 实名认证方式：已确认的校园邮箱自动通过
+pub const VERIFICATION_METHOD_CAMPUS_EMAIL: &str = "campus_email";
+/// 实名认证方式：管理员审核
+pub const VERIFICATION_METHOD_ADMIN: &str = "admin";
+/// 实名认证方式：统一身份认证提供了学号，自动通过
+pub const VERIFICATION_METHOD_SSO: &str = "sso";
+
+/// 实名认证申请状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl VerificationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VerificationStatus::Pending => "pending",
+            VerificationStatus::Approved => "approved",
+            VerificationStatus::Rejected => "rejected",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(VerificationStatus::Pending),
+            "approved" => Some(VerificationStatus::Approved),
+            "rejected" => Some(VerificationStatus::Rejected),
+            _ => None,
+        }
+    }
+}
+
+/// 确认邮箱请求 DTO（令牌来自邮件中的链接）
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfirmEmailRequest {
+    pub token: String,
+}
+
+/// 审核实名认证请求 DTO
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewVerificationRequest {
+    /// approved 或 rejected
+    pub status: String,
+    /// 审核意见（会随通知发送给申请人）
+    pub comment: Option<String>,
+}
+
+impl ReviewVerificationRequest {
+    /// 验证请求，返回审核结果状态
+    pub fn validate(&self) -> Result<VerificationStatus, String> {
+        let status = match VerificationStatus::parse(&self.status) {
+            Some(status @ (VerificationStatus::Approved | VerificationStatus::Rejected)) => status,
+            _ => return Err("状态必须是 approved 或 rejected".to_string()),
+        };
+        if let Some(ref comment) = self.comment {
+            if comment.chars().count() > 500 {
+                return Err("审核意见不能超过500个字符".to_string());
+            }
+        }
+        Ok(status)
+    }
+}
+
+/// 实名认证申请列表查询参数
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationListQuery {
+    /// 按状态筛选：pending、approved、rejected
+    pub status: Option<String>,
+    pub page: Option<i32>,
+    pub per_page: Option<i32>,
+}
+
+impl VerificationListQuery {
+    pub fn get_page(&self) -> i32 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    pub fn get_per_page(&self) -> i32 {
+        self.per_page.unwrap_or(20).clamp(1, 100)
+    }
+}
+
+/// 实名认证申请（关联用户名和邮箱）
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationResponse {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub username: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub real_info: serde_json::Value,
+    pub status: String,
+    pub method: Option<String>,
+    pub reviewer_id: Option<Uuid>,
+    pub review_comment: Option<String>,
+    pub reviewed_at: Option<NaiveDateTime>,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+/// 实名认证申请列表响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationListResponse {
+    pub requests: Vec<VerificationResponse>,
+    pub total: i64,
+    pub page: i32,
+    pub per_page: i32,
+}
+
+/// 当前用户的邮箱确认和实名认证状态
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MyVerificationResponse {
+    pub email: Option<String>,
+    pub email_verified: bool,
+    /// 邮箱属于配置的校园域名
+    pub campus_email: bool,
+    pub is_verified: bool,
+    /// 最近一次实名认证申请
+    pub latest_request: Option<VerificationResponse>,
+}
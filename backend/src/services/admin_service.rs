@@ -3,6 +3,9 @@ use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use uuid::Uuid;
 
+use crate::models::REVOKE_REASON_DISABLED;
+use crate::services::SessionService;
+
 /// 管理员服务错误类型
 #[derive(Debug)]
 pub enum AdminError {
@@ -10,6 +13,8 @@ pub enum AdminError {
     NotFound(String),
     ValidationError(String),
     Forbidden(String),
+    /// 与当前状态冲突（如已有任务在运行）
+    Conflict(String),
 }
 
 impl std::fmt::Display for AdminError {
@@ -19,6 +24,7 @@ impl std::fmt::Display for AdminError {
             AdminError::NotFound(msg) => write!(f, "未找到: {}", msg),
             AdminError::ValidationError(msg) => write!(f, "验证错误: {}", msg),
             AdminError::Forbidden(msg) => write!(f, "权限不足: {}", msg),
+            AdminError::Conflict(msg) => write!(f, "状态冲突: {}", msg),
         }
     }
 }
@@ -94,6 +100,45 @@ pub struct PendingResourceListResponse {
     pub per_page: i32,
 }
 
+/// 重复资源组中的资源项
+#[derive(Debug, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateResourceItem {
+    pub id: Uuid,
+    #[serde(skip)]
+    pub file_hash: String,
+    pub title: String,
+    pub resource_type: String,
+    pub audit_status: String,
+    pub uploader_id: Uuid,
+    pub uploader_name: Option<String>,
+    pub file_size: Option<i64>,
+    pub storage_type: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+/// 重复资源组（文件哈希相同的资源）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateResourceGroup {
+    pub file_hash: String,
+    pub count: i64,
+    /// 除最早一份外其余副本占用的存储空间（字节）
+    pub wasted_size: i64,
+    /// 按上传时间升序，第一项为最早上传的原始资源
+    pub resources: Vec<DuplicateResourceItem>,
+}
+
+/// 重复资源报告响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateResourceReport {
+    pub groups: Vec<DuplicateResourceGroup>,
+    pub total: i64,
+    pub page: i32,
+    pub per_page: i32,
+}
+
 /// 资源审核请求
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -113,6 +158,8 @@ pub struct AdminCommentItem {
     pub user_name: Option<String>,
     pub content: String,
     pub audit_status: String,
+    /// 未处理的举报数
+    pub report_count: i32,
     pub created_at: NaiveDateTime,
 }
 
@@ -126,6 +173,57 @@ pub struct AdminCommentListResponse {
     pub per_page: i32,
 }
 
+/// 举报原因统计
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentReportReasonCount {
+    pub reason: String,
+    pub count: i64,
+}
+
+/// 被举报评论（举报队列项，按评论聚合）
+#[derive(Debug, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportedCommentItem {
+    pub id: Uuid,
+    pub resource_id: Uuid,
+    pub resource_title: Option<String>,
+    pub user_id: Uuid,
+    pub user_name: Option<String>,
+    pub content: String,
+    pub audit_status: String,
+    pub report_count: i32,
+    pub created_at: NaiveDateTime,
+    pub last_reported_at: Option<NaiveDateTime>,
+    #[sqlx(skip)]
+    pub reasons: Vec<CommentReportReasonCount>,
+}
+
+/// 举报队列响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportedCommentListResponse {
+    pub comments: Vec<ReportedCommentItem>,
+    pub total: i64,
+    pub page: i32,
+    pub per_page: i32,
+}
+
+/// 单条举报记录
+#[derive(Debug, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentReportItem {
+    pub id: Uuid,
+    pub reporter_id: Uuid,
+    pub reporter_name: Option<String>,
+    pub reason: String,
+    pub detail: Option<String>,
+    pub status: String,
+    pub handled_by: Option<Uuid>,
+    pub handled_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
 /// 管理员服务
 pub struct AdminService;
 
@@ -321,6 +419,13 @@ impl AdminService {
             return Err(AdminError::NotFound("用户不存在".to_string()));
         }
 
+        // 禁用用户时吊销其全部会话，已签发的 Token 立即失效
+        if !is_active {
+            SessionService::revoke_all_sessions(pool, user_id, None, REVOKE_REASON_DISABLED)
+                .await
+                .map_err(|e| AdminError::DatabaseError(format!("吊销用户会话失败: {}", e)))?;
+        }
+
         Ok(())
     }
 
@@ -373,6 +478,105 @@ impl AdminService {
         })
     }
 
+    /// 获取重复资源报告
+    /// 按文件哈希分组，列出存在多份相同内容的资源
+    pub async fn get_duplicate_resources(
+        pool: &PgPool,
+        page: i32,
+        per_page: i32,
+    ) -> Result<DuplicateResourceReport, AdminError> {
+        let page = page.max(1);
+        let per_page = per_page.clamp(1, 100);
+        let offset = (page - 1) * per_page;
+
+        let total: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM (
+                SELECT file_hash FROM resources
+                WHERE file_hash IS NOT NULL
+                GROUP BY file_hash
+                HAVING COUNT(*) > 1
+            ) t
+            "#,
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        // 先分页取出重复的哈希，副本越多越靠前
+        let hashes: Vec<(String, i64)> = sqlx::query_as(
+            r#"
+            SELECT file_hash, COUNT(*) AS count
+            FROM resources
+            WHERE file_hash IS NOT NULL
+            GROUP BY file_hash
+            HAVING COUNT(*) > 1
+            ORDER BY COUNT(*) DESC, MIN(created_at) ASC
+            LIMIT $1 OFFSET $2
+            "#,
+        )
+        .bind(per_page as i64)
+        .bind(offset as i64)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        let hash_list: Vec<String> = hashes.iter().map(|(hash, _)| hash.clone()).collect();
+        let items: Vec<DuplicateResourceItem> = sqlx::query_as(
+            r#"
+            SELECT
+                r.id,
+                r.file_hash,
+                r.title,
+                r.resource_type,
+                r.audit_status,
+                r.uploader_id,
+                u.username as uploader_name,
+                r.file_size,
+                r.storage_type,
+                r.created_at
+            FROM resources r
+            LEFT JOIN users u ON r.uploader_id = u.id
+            WHERE r.file_hash = ANY($1)
+            ORDER BY r.created_at ASC
+            "#,
+        )
+        .bind(&hash_list)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        let mut groups: Vec<DuplicateResourceGroup> = hashes
+            .into_iter()
+            .map(|(file_hash, count)| DuplicateResourceGroup {
+                file_hash,
+                count,
+                wasted_size: 0,
+                resources: Vec::new(),
+            })
+            .collect();
+        for item in items {
+            if let Some(group) = groups.iter_mut().find(|g| g.file_hash == item.file_hash) {
+                group.resources.push(item);
+            }
+        }
+        for group in &mut groups {
+            group.wasted_size = group
+                .resources
+                .iter()
+                .skip(1)
+                .map(|r| r.file_size.unwrap_or(0))
+                .sum();
+        }
+
+        Ok(DuplicateResourceReport {
+            groups,
+            total,
+            page,
+            per_page,
+        })
+    }
+
     /// 审核资源
     pub async fn audit_resource(
         pool: &PgPool,
@@ -430,6 +634,7 @@ impl AdminService {
                 u.username as user_name,
                 c.content,
                 c.audit_status,
+                c.report_count,
                 c.created_at
             FROM comments c
             JOIN users u ON c.user_id = u.id
@@ -504,31 +709,184 @@ impl AdminService {
         Ok(())
     }
 
+    /// 获取举报队列（有未处理举报的评论，举报数多的在前）
+    pub async fn get_reported_comments(
+        pool: &PgPool,
+        page: i32,
+        per_page: i32,
+    ) -> Result<ReportedCommentListResponse, AdminError> {
+        let page = page.max(1);
+        let per_page = per_page.clamp(1, 100);
+        let offset = (page - 1) * per_page;
+
+        let mut comments: Vec<ReportedCommentItem> = sqlx::query_as(
+            r#"
+            SELECT
+                c.id,
+                c.resource_id,
+                r.title as resource_title,
+                c.user_id,
+                u.username as user_name,
+                c.content,
+                c.audit_status,
+                c.report_count,
+                c.created_at,
+                (SELECT MAX(cr.created_at) FROM comment_reports cr
+                 WHERE cr.comment_id = c.id AND cr.status = 'pending') as last_reported_at
+            FROM comments c
+            JOIN users u ON c.user_id = u.id
+            JOIN resources r ON c.resource_id = r.id
+            WHERE c.report_count > 0
+            ORDER BY c.report_count DESC, last_reported_at DESC
+            LIMIT $1 OFFSET $2
+            "#,
+        )
+        .bind(per_page as i64)
+        .bind(offset as i64)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM comments WHERE report_count > 0")
+            .fetch_one(pool)
+            .await
+            .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        // 按原因统计未处理的举报
+        let comment_ids: Vec<Uuid> = comments.iter().map(|c| c.id).collect();
+        let reason_rows: Vec<(Uuid, String, i64)> = sqlx::query_as(
+            r#"
+            SELECT comment_id, reason, COUNT(*)
+            FROM comment_reports
+            WHERE comment_id = ANY($1) AND status = 'pending'
+            GROUP BY comment_id, reason
+            ORDER BY COUNT(*) DESC, reason
+            "#,
+        )
+        .bind(&comment_ids)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        for (comment_id, reason, count) in reason_rows {
+            if let Some(item) = comments.iter_mut().find(|c| c.id == comment_id) {
+                item.reasons
+                    .push(CommentReportReasonCount { reason, count });
+            }
+        }
+
+        Ok(ReportedCommentListResponse {
+            comments,
+            total,
+            page,
+            per_page,
+        })
+    }
+
+    /// 获取评论的举报记录（最新在前）
+    pub async fn get_comment_reports(
+        pool: &PgPool,
+        comment_id: Uuid,
+    ) -> Result<Vec<CommentReportItem>, AdminError> {
+        let exists: bool =
+            sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM comments WHERE id = $1)")
+                .bind(comment_id)
+                .fetch_one(pool)
+                .await
+                .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        if !exists {
+            return Err(AdminError::NotFound("评论不存在".to_string()));
+        }
+
+        sqlx::query_as(
+            r#"
+            SELECT
+                cr.id,
+                cr.reporter_id,
+                u.username as reporter_name,
+                cr.reason,
+                cr.detail,
+                cr.status,
+                cr.handled_by,
+                cr.handled_at,
+                cr.created_at
+            FROM comment_reports cr
+            LEFT JOIN users u ON cr.reporter_id = u.id
+            WHERE cr.comment_id = $1
+            ORDER BY cr.created_at DESC
+            "#,
+        )
+        .bind(comment_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))
+    }
+
     /// 审核评论
+    ///
+    /// 同时处理该评论所有未处理的举报：通过时驳回举报并清零举报数，
+    /// 拒绝时举报成立。返回处理的举报数
     pub async fn audit_comment(
         pool: &PgPool,
         comment_id: Uuid,
         status: String,
-    ) -> Result<(), AdminError> {
+        admin_id: Uuid,
+    ) -> Result<u64, AdminError> {
         if status != "approved" && status != "rejected" {
             return Err(AdminError::ValidationError(
                 "状态必须是 approved 或 rejected".to_string(),
             ));
         }
 
-        let result = sqlx::query("UPDATE comments SET audit_status = $1 WHERE id = $2")
-            .bind(&status)
-            .bind(comment_id)
-            .execute(pool)
+        let mut tx = pool
+            .begin()
             .await
             .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
 
+        let result =
+            sqlx::query("UPDATE comments SET audit_status = $1, report_count = 0 WHERE id = $2")
+                .bind(&status)
+                .bind(comment_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
         if result.rows_affected() == 0 {
             return Err(AdminError::NotFound("评论不存在".to_string()));
         }
 
-        log::info!("评论审核完成: id={}, status={}", comment_id, status);
-        Ok(())
+        let report_status = if status == "approved" {
+            "dismissed"
+        } else {
+            "accepted"
+        };
+        let resolved_reports = sqlx::query(
+            r#"
+            UPDATE comment_reports
+            SET status = $1, handled_by = $2, handled_at = CURRENT_TIMESTAMP
+            WHERE comment_id = $3 AND status = 'pending'
+            "#,
+        )
+        .bind(report_status)
+        .bind(admin_id)
+        .bind(comment_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?
+        .rows_affected();
+
+        tx.commit()
+            .await
+            .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        log::info!(
+            "评论审核完成: id={}, status={}, resolved_reports={}",
+            comment_id,
+            status,
+            resolved_reports
+        );
+        Ok(resolved_reports)
     }
 
     /// 发送系统通知
@@ -1,11 +1,21 @@
-use crate::models::resource::AiAuditResult;
+use sqlx::PgPool;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::models::resource::{AiAuditResult, RecommendedResource};
+use crate::models::ModerationTarget;
+use uuid::Uuid;
+
+use super::{HttpModerationProvider, RecommendationService, ResourceError, RuleModerationProvider};
 
 #[derive(Debug)]
 pub enum AiError {
-    #[allow(dead_code)]
     ApiError(String),
-    #[allow(dead_code)]
     TimeoutError(String),
+    Config(String),
+    Rule(String),
 }
 
 impl std::fmt::Display for AiError {
@@ -13,62 +23,209 @@ impl std::fmt::Display for AiError {
         match self {
             AiError::ApiError(msg) => write!(f, "AI API 错误: {}", msg),
             AiError::TimeoutError(msg) => write!(f, "AI 请求超时: {}", msg),
+            AiError::Config(msg) => write!(f, "AI 配置错误: {}", msg),
+            AiError::Rule(msg) => write!(f, "审核规则错误: {}", msg),
         }
     }
 }
 
 impl std::error::Error for AiError {}
 
+pub type ModerationFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<AiAuditResult, AiError>> + Send + 'a>>;
+
+/// 待审核的内容
+pub struct ModerationInput<'a> {
+    pub target: ModerationTarget,
+    pub title: Option<&'a str>,
+    pub description: Option<&'a str>,
+    /// 正文（资源提取出的文本或评论内容）
+    pub content: Option<&'a str>,
+}
+
+impl ModerationInput<'_> {
+    /// 拼接所有待审核文本
+    pub fn text(&self) -> String {
+        [self.title, self.description, self.content]
+            .into_iter()
+            .flatten()
+            .filter(|part| !part.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// 内容审核提供方
+pub trait ModerationProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn moderate<'a>(&'a self, input: &'a ModerationInput<'a>) -> ModerationFuture<'a>;
+}
+
+/// 依次执行多个审核提供方
+///
+/// 任一提供方不通过即返回其结果，后面的提供方不再调用
+/// （本地规则放在前面，命中时不必再把内容发给外部接口）
+pub struct ChainedModerationProvider {
+    providers: Vec<Arc<dyn ModerationProvider>>,
+}
+
+impl ChainedModerationProvider {
+    pub fn new(providers: Vec<Arc<dyn ModerationProvider>>) -> Self {
+        Self { providers }
+    }
+}
+
+impl ModerationProvider for ChainedModerationProvider {
+    fn name(&self) -> &'static str {
+        "chain"
+    }
+
+    fn moderate<'a>(&'a self, input: &'a ModerationInput<'a>) -> ModerationFuture<'a> {
+        Box::pin(async move {
+            let mut accuracy_score: Option<f64> = None;
+
+            for provider in &self.providers {
+                let result = provider.moderate(input).await?;
+                log::debug!(
+                    "[AI] 审核提供方结果 | provider={}, target={}, passed={}",
+                    provider.name(),
+                    input.target.as_str(),
+                    result.passed
+                );
+                if !result.passed {
+                    return Ok(result);
+                }
+                // 多个评分取最低值
+                if let Some(score) = result.accuracy_score {
+                    accuracy_score = Some(accuracy_score.map_or(score, |s| s.min(score)));
+                }
+            }
+
+            Ok(AiAuditResult {
+                passed: true,
+                reason: None,
+                accuracy_score,
+            })
+        })
+    }
+}
+
+/// 根据配置创建审核提供方链
+/// MODERATION_PROVIDERS 按顺序列出启用的提供方：rules（本地规则）、openai（OpenAI 兼容接口）
+pub fn create_moderation_provider(
+    config: &Config,
+    pool: PgPool,
+) -> Result<Arc<dyn ModerationProvider>, AiError> {
+    let mut providers: Vec<Arc<dyn ModerationProvider>> = Vec::new();
+
+    for name in &config.moderation_providers {
+        match name.as_str() {
+            "rules" => providers.push(Arc::new(RuleModerationProvider::new(pool.clone()))),
+            "openai" => providers.push(Arc::new(HttpModerationProvider::from_config(config)?)),
+            other => {
+                return Err(AiError::Config(format!(
+                    "未知的审核提供方: {}（可选 rules、openai）",
+                    other
+                )))
+            }
+        }
+    }
+
+    Ok(Arc::new(ChainedModerationProvider::new(providers)))
+}
+
 pub struct AiService;
 
 impl AiService {
     /// 审核资源内容
     ///
-    /// 当前阶段：默认返回通过
-    /// 后续阶段：接入真实 AI API
+    /// 审核不通过或审核服务不可用时返回不通过，资源进入人工审核队列，
+    /// `reason` 会写入 `ai_reject_reason` 供管理员参考
     pub async fn audit_resource(
-        _title: &str,
-        _description: Option<&str>,
-        _file_data: Option<&[u8]>,
-    ) -> Result<AiAuditResult, AiError> {
-        // TODO: 接入真实 AI 审核服务
-        // 当前阶段默认返回通过
+        moderation: &Arc<dyn ModerationProvider>,
+        title: &str,
+        description: Option<&str>,
+        content: Option<&str>,
+    ) -> AiAuditResult {
+        let input = ModerationInput {
+            target: ModerationTarget::Resource,
+            title: Some(title),
+            description,
+            content,
+        };
 
-        log::debug!("AI 审核资源: 标题={}, 描述={:?}", _title, _description);
+        log::debug!(
+            "[AI] 审核资源 | title={}, content_len={}",
+            title,
+            content.map_or(0, str::len)
+        );
 
-        // 模拟 AI 审核（直接通过）
-        Ok(AiAuditResult {
-            passed: true,
-            reason: None,
-            accuracy_score: Some(0.95), // 模拟高准确度评分
-        })
+        Self::moderate(moderation, &input).await
     }
 
-    /// 审核评论内容（预留接口）
-    ///
-    /// 当前阶段：默认返回通过
-    #[allow(dead_code)]
-    pub async fn audit_comment(_content: &str) -> Result<AiAuditResult, AiError> {
-        // TODO: 接入真实 AI 评论审核服务
-        // 当前阶段默认返回通过
+    /// 审核评论内容
+    pub async fn audit_comment(
+        moderation: &Arc<dyn ModerationProvider>,
+        content: &str,
+    ) -> AiAuditResult {
+        let input = ModerationInput {
+            target: ModerationTarget::Comment,
+            title: None,
+            description: None,
+            content: Some(content),
+        };
 
-        log::debug!("AI 审核评论: 内容长度={}", _content.len());
+        log::debug!("[AI] 审核评论 | content_len={}", content.len());
 
-        Ok(AiAuditResult {
-            passed: true,
-            reason: None,
-            accuracy_score: None,
-        })
+        Self::moderate(moderation, &input).await
     }
 
-    /// 获取 AI 不通过原因（用于人工审核参考）（预留接口）
+    /// 为用户推荐资源
     ///
-    /// 当前阶段：返回固定文本
-    #[allow(dead_code)]
-    pub fn get_rejection_reason(_file_data: Option<&[u8]>) -> String {
-        // TODO: 接入真实 AI 原因分析
-        // 当前阶段返回通用原因
-        "AI 审核检测到此资源可能包含不合适的内容，建议检查资源内容是否符合社区规范。".to_string()
+    /// 基于下载、点赞、收藏和评分数据的协同过滤，详见 `RecommendationService`；
+    /// 未提供数据库连接时返回空列表
+    pub async fn recommend_resources(
+        user_id: Uuid,
+        pool: Option<&PgPool>,
+        limit: i64,
+    ) -> Result<Vec<RecommendedResource>, ResourceError> {
+        let Some(pool) = pool else {
+            return Ok(Vec::new());
+        };
+
+        RecommendationService::recommend_for_user(pool, user_id, limit).await
+    }
+
+    async fn moderate(
+        moderation: &Arc<dyn ModerationProvider>,
+        input: &ModerationInput<'_>,
+    ) -> AiAuditResult {
+        match moderation.moderate(input).await {
+            Ok(result) => {
+                if !result.passed {
+                    log::info!(
+                        "[AI] 自动审核未通过 | target={}, reason={}",
+                        input.target.as_str(),
+                        result.reason.as_deref().unwrap_or("-")
+                    );
+                }
+                result
+            }
+            Err(e) => {
+                // 审核服务故障时不直接放行，转人工审核
+                log::warn!(
+                    "[AI] 自动审核失败，转人工审核 | target={}, error={}",
+                    input.target.as_str(),
+                    e
+                );
+                AiAuditResult {
+                    passed: false,
+                    reason: Some(format!("自动审核服务不可用，已转人工审核（{}）", e)),
+                    accuracy_score: None,
+                }
+            }
+        }
     }
 }
 
@@ -76,23 +233,81 @@ impl AiService {
 mod tests {
     use super::*;
 
+    /// 固定返回结果的审核提供方
+    struct StaticProvider(Result<AiAuditResult, String>);
+
+    impl ModerationProvider for StaticProvider {
+        fn name(&self) -> &'static str {
+            "static"
+        }
+
+        fn moderate<'a>(&'a self, _input: &'a ModerationInput<'a>) -> ModerationFuture<'a> {
+            let result = self.0.clone().map_err(AiError::ApiError);
+            Box::pin(async move { result })
+        }
+    }
+
+    fn provider(result: Result<AiAuditResult, String>) -> Arc<dyn ModerationProvider> {
+        Arc::new(StaticProvider(result))
+    }
+
+    fn pass(score: Option<f64>) -> Result<AiAuditResult, String> {
+        Ok(AiAuditResult {
+            passed: true,
+            reason: None,
+            accuracy_score: score,
+        })
+    }
+
     #[tokio::test]
     async fn test_audit_resource_passes() {
-        let result = AiService::audit_resource("测试资源", None, None).await;
-        assert!(result.is_ok());
+        let moderation: Arc<dyn ModerationProvider> =
+            Arc::new(ChainedModerationProvider::new(vec![]));
+        let audit_result = AiService::audit_resource(&moderation, "测试资源", None, None).await;
 
-        let audit_result = result.unwrap();
         assert!(audit_result.passed);
-        assert!(audit_result.accuracy_score.is_some());
+        assert!(audit_result.reason.is_none());
     }
 
     #[tokio::test]
     async fn test_audit_comment_passes() {
-        let result = AiService::audit_comment("这是一条测试评论").await;
-        assert!(result.is_ok());
+        let moderation: Arc<dyn ModerationProvider> =
+            Arc::new(ChainedModerationProvider::new(vec![
+                provider(pass(Some(0.9))),
+                provider(pass(Some(0.8))),
+            ]));
+        let audit_result = AiService::audit_comment(&moderation, "这是一条测试评论").await;
 
-        let audit_result = result.unwrap();
         assert!(audit_result.passed);
+        assert_eq!(audit_result.accuracy_score, Some(0.8));
+    }
+
+    #[tokio::test]
+    async fn test_chain_stops_at_first_rejection() {
+        let rejected = Ok(AiAuditResult {
+            passed: false,
+            reason: Some("命中审核规则".to_string()),
+            accuracy_score: None,
+        });
+        // 第二个提供方会报错，但不应被调用
+        let moderation: Arc<dyn ModerationProvider> =
+            Arc::new(ChainedModerationProvider::new(vec![
+                provider(rejected),
+                provider(Err("不应调用".to_string())),
+            ]));
+        let audit_result = AiService::audit_comment(&moderation, "内容").await;
+
+        assert!(!audit_result.passed);
+        assert_eq!(audit_result.reason.as_deref(), Some("命中审核规则"));
+    }
+
+    #[tokio::test]
+    async fn test_provider_error_falls_back_to_manual_review() {
+        let moderation = provider(Err("connection refused".to_string()));
+        let audit_result = AiService::audit_resource(&moderation, "标题", None, None).await;
+
+        assert!(!audit_result.passed);
+        assert!(audit_result.reason.unwrap().contains("connection refused"));
     }
 
     #[tokio::test]
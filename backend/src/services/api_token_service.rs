@@ -0,0 +1,357 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::{
+    ApiToken, ApiTokenListResponse, ApiTokenResponse, ApiTokenScope, CreateApiTokenRequest,
+    CreateApiTokenResponse, CurrentUser, UserRole, API_TOKEN_PREFIX,
+};
+use crate::services::ResourceError;
+use crate::utils::{generate_one_time_token, hash_one_time_token};
+
+/// 每个用户最多持有的有效令牌数
+const MAX_API_TOKENS_PER_USER: i64 = 20;
+/// 列表中展示的令牌明文长度（前缀 + 8 位）
+const TOKEN_DISPLAY_LEN: usize = API_TOKEN_PREFIX.len() + 8;
+/// 最近使用时间的更新间隔（秒），避免每个请求都写库
+const LAST_USED_UPDATE_INTERVAL_SECS: i64 = 60;
+
+/// 个人访问令牌服务
+pub struct ApiTokenService;
+
+/// 通过个人访问令牌认证的结果
+#[derive(Debug, Clone)]
+pub struct ApiTokenAuth {
+    pub user: CurrentUser,
+    pub scopes: Vec<ApiTokenScope>,
+}
+
+impl ApiTokenService {
+    /// 创建个人访问令牌，明文只在创建时返回一次
+    pub async fn create_token(
+        pool: &PgPool,
+        user_id: Uuid,
+        req: CreateApiTokenRequest,
+    ) -> Result<CreateApiTokenResponse, ResourceError> {
+        let scopes: Vec<String> = req
+            .validate()
+            .map_err(ResourceError::ValidationError)?
+            .iter()
+            .map(|scope| scope.as_str().to_string())
+            .collect();
+
+        let active_count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM api_tokens
+            WHERE user_id = $1 AND revoked_at IS NULL
+              AND (expires_at IS NULL OR expires_at > CURRENT_TIMESTAMP)
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        if active_count >= MAX_API_TOKENS_PER_USER {
+            return Err(ResourceError::ValidationError(format!(
+                "最多只能创建 {} 个有效令牌，请先吊销不再使用的令牌",
+                MAX_API_TOKENS_PER_USER
+            )));
+        }
+
+        let token = format!("{}{}", API_TOKEN_PREFIX, generate_one_time_token());
+        let expires_at = req
+            .expires_in_days
+            .map(|days| (chrono::Utc::now() + chrono::Duration::days(days)).naive_utc());
+
+        let created = sqlx::query_as::<_, ApiToken>(
+            r#"
+            INSERT INTO api_tokens (user_id, name, token_hash, token_prefix, scopes, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, name, token_prefix, scopes, expires_at, last_used_at, last_used_ip, created_at
+            "#,
+        )
+        .bind(user_id)
+        .bind(req.name.trim())
+        .bind(hash_one_time_token(&token))
+        .bind(&token[..TOKEN_DISPLAY_LEN])
+        .bind(&scopes)
+        .bind(expires_at)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        log::info!(
+            "[ApiToken] 创建个人访问令牌 | user_id={}, token_id={}, scopes={:?}",
+            user_id,
+            created.id,
+            scopes
+        );
+
+        Ok(CreateApiTokenResponse {
+            token,
+            info: created.into(),
+        })
+    }
+
+    /// 获取用户未吊销的令牌列表（最近创建的在前）
+    pub async fn list_tokens(
+        pool: &PgPool,
+        user_id: Uuid,
+    ) -> Result<ApiTokenListResponse, ResourceError> {
+        let tokens = sqlx::query_as::<_, ApiToken>(
+            r#"
+            SELECT id, name, token_prefix, scopes, expires_at, last_used_at, last_used_ip, created_at
+            FROM api_tokens
+            WHERE user_id = $1 AND revoked_at IS NULL
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        Ok(ApiTokenListResponse {
+            tokens: tokens.into_iter().map(ApiTokenResponse::from).collect(),
+            available_scopes: ApiTokenScope::ALL.iter().map(|s| s.as_str()).collect(),
+        })
+    }
+
+    /// 吊销令牌，立即失效
+    pub async fn revoke_token(
+        pool: &PgPool,
+        user_id: Uuid,
+        token_id: Uuid,
+    ) -> Result<(), ResourceError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE api_tokens SET revoked_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+            "#,
+        )
+        .bind(token_id)
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(ResourceError::NotFound("令牌不存在".to_string()));
+        }
+
+        log::info!(
+            "[ApiToken] 吊销个人访问令牌 | user_id={}, token_id={}",
+            user_id,
+            token_id
+        );
+        Ok(())
+    }
+
+    /// 校验个人访问令牌，返回令牌所属用户和权限范围
+    ///
+    /// 令牌已吊销、已过期或用户被禁用时返回 None；认证成功时顺带记录最近使用时间和 IP。
+    /// 令牌的角色最高为实名用户，不能通过令牌使用管理功能
+    pub async fn authenticate(
+        pool: &PgPool,
+        token: &str,
+        ip_address: Option<&str>,
+    ) -> Result<Option<ApiTokenAuth>, sqlx::Error> {
+        let row: Option<(Uuid, Vec<String>, Uuid, String, String, bool)> = sqlx::query_as(
+            r#"
+            SELECT t.id, t.scopes, u.id, u.username, u.role, u.is_verified
+            FROM api_tokens t
+            JOIN users u ON t.user_id = u.id
+            WHERE t.token_hash = $1
+              AND t.revoked_at IS NULL
+              AND (t.expires_at IS NULL OR t.expires_at > CURRENT_TIMESTAMP)
+              AND u.is_active = true
+            "#,
+        )
+        .bind(hash_one_time_token(token))
+        .fetch_optional(pool)
+        .await?;
+
+        let Some((token_id, scopes, user_id, username, role, is_verified)) = row else {
+            return Ok(None);
+        };
+
+        sqlx::query(
+            r#"
+            UPDATE api_tokens
+            SET last_used_at = CURRENT_TIMESTAMP, last_used_ip = $2
+            WHERE id = $1
+              AND (last_used_at IS NULL
+                   OR last_used_at < CURRENT_TIMESTAMP - make_interval(secs => $3)
+                   OR last_used_ip IS DISTINCT FROM $2)
+            "#,
+        )
+        .bind(token_id)
+        .bind(ip_address)
+        .bind(LAST_USED_UPDATE_INTERVAL_SECS as f64)
+        .execute(pool)
+        .await?;
+
+        // 令牌不能行使管理员权限，管理员的令牌按普通（实名）用户处理
+        let role = match role.as_str() {
+            "admin" if is_verified => UserRole::Verified,
+            "admin" | "user" => UserRole::User,
+            "verified" => UserRole::Verified,
+            _ => UserRole::Guest,
+        };
+
+        Ok(Some(ApiTokenAuth {
+            user: CurrentUser {
+                id: user_id,
+                username,
+                role,
+                is_verified,
+                // 令牌不属于任何登录会话
+                session_id: Uuid::nil(),
+                // 令牌无法完成两步验证，需要两步验证的接口不接受令牌
+                mfa: false,
+            },
+            scopes: scopes
+                .iter()
+                .filter_map(|s| ApiTokenScope::parse(s))
+                .collect(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{cleanup_test_user, create_test_user, test_pool};
+
+    fn request(name: &str, scopes: &[&str], expires_in_days: Option<i64>) -> CreateApiTokenRequest {
+        CreateApiTokenRequest {
+            name: name.to_string(),
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+            expires_in_days,
+        }
+    }
+
+    #[test]
+    fn test_create_request_validate() {
+        assert_eq!(
+            request(
+                " 同步脚本 ",
+                &["resources:read", " resources:read ", "favorites:write"],
+                Some(30)
+            )
+            .validate()
+            .unwrap(),
+            vec![ApiTokenScope::ResourcesRead, ApiTokenScope::FavoritesWrite]
+        );
+        assert!(request("脚本", &["resources:read"], None)
+            .validate()
+            .is_ok());
+        assert!(request("脚本", &["resources:read"], Some(365))
+            .validate()
+            .is_ok());
+
+        assert!(request("  ", &["resources:read"], None).validate().is_err());
+        assert!(request(&"长".repeat(101), &["resources:read"], None)
+            .validate()
+            .is_err());
+        assert!(request("脚本", &["resources:read"], Some(0))
+            .validate()
+            .is_err());
+        assert!(request("脚本", &["resources:read"], Some(366))
+            .validate()
+            .is_err());
+        assert!(request("脚本", &[], None).validate().is_err());
+        assert!(request("脚本", &["admin"], None).validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_token_scopes_and_revocation() {
+        let pool = test_pool().await;
+        let user = create_test_user(&pool, UserRole::User).await;
+
+        let created = ApiTokenService::create_token(
+            &pool,
+            user.id,
+            request("脚本", &["resources:read", "favorites:read"], Some(1)),
+        )
+        .await
+        .unwrap();
+        assert!(created.token.starts_with(API_TOKEN_PREFIX));
+
+        let auth = ApiTokenService::authenticate(&pool, &created.token, Some("203.0.113.9"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(auth.user.id, user.id);
+        assert_eq!(
+            auth.scopes,
+            vec![ApiTokenScope::ResourcesRead, ApiTokenScope::FavoritesRead]
+        );
+        assert!(!auth.user.mfa);
+
+        let wrong = format!("{}x", created.token);
+        assert!(ApiTokenService::authenticate(&pool, &wrong, None)
+            .await
+            .unwrap()
+            .is_none());
+
+        // 只能吊销自己的令牌，吊销后立即失效
+        let other = create_test_user(&pool, UserRole::User).await;
+        assert!(matches!(
+            ApiTokenService::revoke_token(&pool, other.id, created.info.id).await,
+            Err(ResourceError::NotFound(_))
+        ));
+        ApiTokenService::revoke_token(&pool, user.id, created.info.id)
+            .await
+            .unwrap();
+        assert!(ApiTokenService::authenticate(&pool, &created.token, None)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(matches!(
+            ApiTokenService::revoke_token(&pool, user.id, created.info.id).await,
+            Err(ResourceError::NotFound(_))
+        ));
+        assert!(ApiTokenService::list_tokens(&pool, user.id)
+            .await
+            .unwrap()
+            .tokens
+            .is_empty());
+
+        cleanup_test_user(&pool, user.id).await;
+        cleanup_test_user(&pool, other.id).await;
+    }
+
+    #[tokio::test]
+    async fn test_admin_token_is_not_admin() {
+        let pool = test_pool().await;
+        let admin = create_test_user(&pool, UserRole::Admin).await;
+
+        let created = ApiTokenService::create_token(
+            &pool,
+            admin.id,
+            request("脚本", &["resources:write"], None),
+        )
+        .await
+        .unwrap();
+        let auth = ApiTokenService::authenticate(&pool, &created.token, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(auth.user.role, UserRole::User);
+        assert!(!auth.user.is_admin());
+
+        sqlx::query("UPDATE users SET is_verified = true WHERE id = $1")
+            .bind(admin.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let auth = ApiTokenService::authenticate(&pool, &created.token, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(auth.user.role, UserRole::Verified);
+
+        cleanup_test_user(&pool, admin.id).await;
+    }
+}
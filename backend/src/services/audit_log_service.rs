@@ -1,6 +1,8 @@
 use sqlx::PgPool;
 use uuid::Uuid;
 
+use crate::models::{StorageCheckResponse, StorageMigrationResponse};
+
 /// 审计日志服务
 pub struct AuditLogService;
 
@@ -8,13 +10,27 @@ pub struct AuditLogService;
 #[derive(Debug, Clone)]
 pub enum AuditAction {
     Login,
+    LoginFailed,
+    AccountLocked,
     Register,
+    ChangePassword,
+    ResetPassword,
+    EnableMfa,
+    DisableMfa,
+    RegenerateRecoveryCodes,
+    CreateApiToken,
+    RevokeApiToken,
+    LinkIdentity,
+    UnlinkIdentity,
     UploadResource,
     DownloadResource,
     DeleteResource,
     UpdateResource,
+    RollbackResource,
     CreateComment,
+    UpdateComment,
     DeleteComment,
+    ReportComment,
     RateResource,
     LikeResource,
     UnlikeResource,
@@ -22,27 +38,44 @@ pub enum AuditAction {
     UpdateProfile,
     AdminAction,
     PackDownload, // 打包下载收藏夹
+    SubmitClaim,  // 提交作者申领
 }
 
-impl ToString for AuditAction {
-    fn to_string(&self) -> String {
-        match self {
-            AuditAction::Login => "login".to_string(),
-            AuditAction::Register => "register".to_string(),
-            AuditAction::UploadResource => "upload_resource".to_string(),
-            AuditAction::DownloadResource => "download_resource".to_string(),
-            AuditAction::DeleteResource => "delete_resource".to_string(),
-            AuditAction::UpdateResource => "update_resource".to_string(),
-            AuditAction::CreateComment => "create_comment".to_string(),
-            AuditAction::DeleteComment => "delete_comment".to_string(),
-            AuditAction::RateResource => "rate_resource".to_string(),
-            AuditAction::LikeResource => "like_resource".to_string(),
-            AuditAction::UnlikeResource => "unlike_resource".to_string(),
-            AuditAction::CreateFavorite => "create_favorite".to_string(),
-            AuditAction::UpdateProfile => "update_profile".to_string(),
-            AuditAction::AdminAction => "admin_action".to_string(),
-            AuditAction::PackDownload => "pack_download".to_string(),
-        }
+impl std::fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            AuditAction::Login => "login",
+            AuditAction::LoginFailed => "login_failed",
+            AuditAction::AccountLocked => "account_locked",
+            AuditAction::Register => "register",
+            AuditAction::ChangePassword => "change_password",
+            AuditAction::ResetPassword => "reset_password",
+            AuditAction::EnableMfa => "enable_mfa",
+            AuditAction::DisableMfa => "disable_mfa",
+            AuditAction::RegenerateRecoveryCodes => "regenerate_recovery_codes",
+            AuditAction::CreateApiToken => "create_api_token",
+            AuditAction::RevokeApiToken => "revoke_api_token",
+            AuditAction::LinkIdentity => "link_identity",
+            AuditAction::UnlinkIdentity => "unlink_identity",
+            AuditAction::UploadResource => "upload_resource",
+            AuditAction::DownloadResource => "download_resource",
+            AuditAction::DeleteResource => "delete_resource",
+            AuditAction::UpdateResource => "update_resource",
+            AuditAction::RollbackResource => "rollback_resource",
+            AuditAction::CreateComment => "create_comment",
+            AuditAction::UpdateComment => "update_comment",
+            AuditAction::DeleteComment => "delete_comment",
+            AuditAction::ReportComment => "report_comment",
+            AuditAction::RateResource => "rate_resource",
+            AuditAction::LikeResource => "like_resource",
+            AuditAction::UnlikeResource => "unlike_resource",
+            AuditAction::CreateFavorite => "create_favorite",
+            AuditAction::UpdateProfile => "update_profile",
+            AuditAction::AdminAction => "admin_action",
+            AuditAction::PackDownload => "pack_download",
+            AuditAction::SubmitClaim => "submit_claim",
+        };
+        f.write_str(s)
     }
 }
 
@@ -102,6 +135,58 @@ impl AuditLogService {
         .await
     }
 
+    /// 记录登录失败日志（用户名不存在时 user_id 为空）
+    pub async fn log_login_failed(
+        pool: &PgPool,
+        user_id: Option<Uuid>,
+        username: &str,
+        failed_count: i32,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let details = serde_json::json!({
+            "username": username,
+            "failed_count": failed_count,
+        });
+
+        Self::log(
+            pool,
+            user_id,
+            AuditAction::LoginFailed,
+            Some("user"),
+            user_id,
+            Some(details),
+            ip_address,
+        )
+        .await
+    }
+
+    /// 记录连续登录失败导致的账号锁定日志
+    pub async fn log_account_locked(
+        pool: &PgPool,
+        user_id: Option<Uuid>,
+        username: &str,
+        failed_count: i32,
+        lock_secs: i64,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let details = serde_json::json!({
+            "username": username,
+            "failed_count": failed_count,
+            "lock_secs": lock_secs,
+        });
+
+        Self::log(
+            pool,
+            user_id,
+            AuditAction::AccountLocked,
+            Some("user"),
+            user_id,
+            Some(details),
+            ip_address,
+        )
+        .await
+    }
+
     /// 记录注册日志
     pub async fn log_register(
         pool: &PgPool,
@@ -125,6 +210,199 @@ impl AuditLogService {
         .await
     }
 
+    /// 记录修改密码日志
+    pub async fn log_change_password(
+        pool: &PgPool,
+        user_id: Uuid,
+        revoked_sessions: u64,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let details = serde_json::json!({
+            "revoked_sessions": revoked_sessions,
+        });
+
+        Self::log(
+            pool,
+            Some(user_id),
+            AuditAction::ChangePassword,
+            Some("user"),
+            Some(user_id),
+            Some(details),
+            ip_address,
+        )
+        .await
+    }
+
+    /// 记录通过邮件重置密码日志
+    pub async fn log_reset_password(
+        pool: &PgPool,
+        user_id: Uuid,
+        revoked_sessions: u64,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let details = serde_json::json!({
+            "revoked_sessions": revoked_sessions,
+        });
+
+        Self::log(
+            pool,
+            Some(user_id),
+            AuditAction::ResetPassword,
+            Some("user"),
+            Some(user_id),
+            Some(details),
+            ip_address,
+        )
+        .await
+    }
+
+    /// 记录启用两步验证日志
+    pub async fn log_enable_mfa(
+        pool: &PgPool,
+        user_id: Uuid,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        Self::log(
+            pool,
+            Some(user_id),
+            AuditAction::EnableMfa,
+            Some("user"),
+            Some(user_id),
+            None,
+            ip_address,
+        )
+        .await
+    }
+
+    /// 记录关闭两步验证日志
+    pub async fn log_disable_mfa(
+        pool: &PgPool,
+        user_id: Uuid,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        Self::log(
+            pool,
+            Some(user_id),
+            AuditAction::DisableMfa,
+            Some("user"),
+            Some(user_id),
+            None,
+            ip_address,
+        )
+        .await
+    }
+
+    /// 记录重新生成两步验证恢复码日志
+    pub async fn log_regenerate_recovery_codes(
+        pool: &PgPool,
+        user_id: Uuid,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        Self::log(
+            pool,
+            Some(user_id),
+            AuditAction::RegenerateRecoveryCodes,
+            Some("user"),
+            Some(user_id),
+            None,
+            ip_address,
+        )
+        .await
+    }
+
+    /// 记录创建个人访问令牌日志
+    pub async fn log_create_api_token(
+        pool: &PgPool,
+        user_id: Uuid,
+        token_id: Uuid,
+        name: &str,
+        scopes: &[String],
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let details = serde_json::json!({
+            "name": name,
+            "scopes": scopes,
+        });
+
+        Self::log(
+            pool,
+            Some(user_id),
+            AuditAction::CreateApiToken,
+            Some("api_token"),
+            Some(token_id),
+            Some(details),
+            ip_address,
+        )
+        .await
+    }
+
+    /// 记录吊销个人访问令牌日志
+    pub async fn log_revoke_api_token(
+        pool: &PgPool,
+        user_id: Uuid,
+        token_id: Uuid,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        Self::log(
+            pool,
+            Some(user_id),
+            AuditAction::RevokeApiToken,
+            Some("api_token"),
+            Some(token_id),
+            None,
+            ip_address,
+        )
+        .await
+    }
+
+    /// 记录绑定外部身份日志
+    pub async fn log_link_identity(
+        pool: &PgPool,
+        user_id: Uuid,
+        identity_id: Uuid,
+        provider: &str,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let details = serde_json::json!({
+            "provider": provider,
+        });
+
+        Self::log(
+            pool,
+            Some(user_id),
+            AuditAction::LinkIdentity,
+            Some("user_identity"),
+            Some(identity_id),
+            Some(details),
+            ip_address,
+        )
+        .await
+    }
+
+    /// 记录解除绑定外部身份日志
+    pub async fn log_unlink_identity(
+        pool: &PgPool,
+        user_id: Uuid,
+        identity_id: Uuid,
+        provider: &str,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let details = serde_json::json!({
+            "provider": provider,
+        });
+
+        Self::log(
+            pool,
+            Some(user_id),
+            AuditAction::UnlinkIdentity,
+            Some("user_identity"),
+            Some(identity_id),
+            Some(details),
+            ip_address,
+        )
+        .await
+    }
+
     /// 记录资源上传日志
     pub async fn log_upload_resource(
         pool: &PgPool,
@@ -224,6 +502,34 @@ impl AuditLogService {
         .await
     }
 
+    /// 记录资源版本回滚日志
+    pub async fn log_rollback_resource(
+        pool: &PgPool,
+        user_id: Uuid,
+        resource_id: Uuid,
+        resource_title: &str,
+        target_version: i32,
+        new_version: i32,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let details = serde_json::json!({
+            "title": resource_title,
+            "target_version": target_version,
+            "new_version": new_version,
+        });
+
+        Self::log(
+            pool,
+            Some(user_id),
+            AuditAction::RollbackResource,
+            Some("resource"),
+            Some(resource_id),
+            Some(details),
+            ip_address,
+        )
+        .await
+    }
+
     /// 记录创建收藏夹日志
     pub async fn log_create_favorite(
         pool: &PgPool,
@@ -382,6 +688,30 @@ impl AuditLogService {
         .await
     }
 
+    /// 记录编辑评论日志
+    pub async fn log_update_comment(
+        pool: &PgPool,
+        user_id: Uuid,
+        comment_id: Uuid,
+        audit_status: &str,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let details = serde_json::json!({
+            "audit_status": audit_status,
+        });
+
+        Self::log(
+            pool,
+            Some(user_id),
+            AuditAction::UpdateComment,
+            Some("comment"),
+            Some(comment_id),
+            Some(details),
+            ip_address,
+        )
+        .await
+    }
+
     /// 记录删除评论日志
     pub async fn log_delete_comment(
         pool: &PgPool,
@@ -407,6 +737,59 @@ impl AuditLogService {
         .await
     }
 
+    /// 记录举报评论日志
+    pub async fn log_report_comment(
+        pool: &PgPool,
+        user_id: Uuid,
+        comment_id: Uuid,
+        reason: &str,
+        auto_hidden: bool,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let details = serde_json::json!({
+            "reason": reason,
+            "auto_hidden": auto_hidden,
+        });
+
+        Self::log(
+            pool,
+            Some(user_id),
+            AuditAction::ReportComment,
+            Some("comment"),
+            Some(comment_id),
+            Some(details),
+            ip_address,
+        )
+        .await
+    }
+
+    /// 记录处理评论举报日志（管理员）
+    pub async fn log_resolve_comment_reports(
+        pool: &PgPool,
+        admin_id: Uuid,
+        comment_id: Uuid,
+        status: &str,
+        resolved_reports: u64,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let details = serde_json::json!({
+            "action": "audit_comment",
+            "status": status,
+            "resolved_reports": resolved_reports,
+        });
+
+        Self::log(
+            pool,
+            Some(admin_id),
+            AuditAction::AdminAction,
+            Some("comment"),
+            Some(comment_id),
+            Some(details),
+            ip_address,
+        )
+        .await
+    }
+
     /// 记录发送通知日志（管理员）
     pub async fn log_send_notification(
         pool: &PgPool,
@@ -457,4 +840,262 @@ impl AuditLogService {
         )
         .await
     }
+
+    /// 记录更新用户权限日志（管理员授予/收回管理权限）
+    pub async fn log_update_user_permissions(
+        pool: &PgPool,
+        admin_id: Uuid,
+        target_user_id: Uuid,
+        permissions: &[&str],
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let details = serde_json::json!({
+            "action": "update_permissions",
+            "permissions": permissions,
+        });
+
+        Self::log(
+            pool,
+            Some(admin_id),
+            AuditAction::AdminAction,
+            Some("user"),
+            Some(target_user_id),
+            Some(details),
+            ip_address,
+        )
+        .await
+    }
+
+    /// 记录设置用户存储配额日志（quota_bytes 为 None 表示不限）
+    pub async fn log_update_user_storage_quota(
+        pool: &PgPool,
+        admin_id: Uuid,
+        target_user_id: Uuid,
+        quota_bytes: Option<i64>,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let details = serde_json::json!({
+            "action": "update_storage_quota",
+            "quota_bytes": quota_bytes,
+        });
+
+        Self::log(
+            pool,
+            Some(admin_id),
+            AuditAction::AdminAction,
+            Some("user"),
+            Some(target_user_id),
+            Some(details),
+            ip_address,
+        )
+        .await
+    }
+
+    /// 记录取消用户单独存储配额日志
+    pub async fn log_clear_user_storage_quota(
+        pool: &PgPool,
+        admin_id: Uuid,
+        target_user_id: Uuid,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let details = serde_json::json!({
+            "action": "clear_storage_quota",
+        });
+
+        Self::log(
+            pool,
+            Some(admin_id),
+            AuditAction::AdminAction,
+            Some("user"),
+            Some(target_user_id),
+            Some(details),
+            ip_address,
+        )
+        .await
+    }
+
+    /// 记录设置角色存储配额日志（quota_bytes 为 None 表示不限）
+    pub async fn log_update_role_storage_quota(
+        pool: &PgPool,
+        admin_id: Uuid,
+        role: &str,
+        quota_bytes: Option<i64>,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let details = serde_json::json!({
+            "action": "update_role_storage_quota",
+            "role": role,
+            "quota_bytes": quota_bytes,
+        });
+
+        Self::log(
+            pool,
+            Some(admin_id),
+            AuditAction::AdminAction,
+            Some("storage_quota"),
+            None,
+            Some(details),
+            ip_address,
+        )
+        .await
+    }
+
+    /// 记录发起存储迁移日志
+    pub async fn log_start_storage_migration(
+        pool: &PgPool,
+        admin_id: Uuid,
+        migration: &StorageMigrationResponse,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let details = serde_json::json!({
+            "action": "start_storage_migration",
+            "source": migration.source_backend,
+            "target": migration.target_backend,
+            "include_resources": migration.include_resources,
+            "include_images": migration.include_images,
+            "delete_source": migration.delete_source,
+            "total_items": migration.total_items,
+        });
+
+        Self::log(
+            pool,
+            Some(admin_id),
+            AuditAction::AdminAction,
+            Some("storage_migration"),
+            Some(migration.id),
+            Some(details),
+            ip_address,
+        )
+        .await
+    }
+
+    /// 记录取消存储迁移日志
+    pub async fn log_cancel_storage_migration(
+        pool: &PgPool,
+        admin_id: Uuid,
+        migration_id: Uuid,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let details = serde_json::json!({
+            "action": "cancel_storage_migration",
+        });
+
+        Self::log(
+            pool,
+            Some(admin_id),
+            AuditAction::AdminAction,
+            Some("storage_migration"),
+            Some(migration_id),
+            Some(details),
+            ip_address,
+        )
+        .await
+    }
+
+    /// 记录发起存储一致性检查日志
+    pub async fn log_start_storage_check(
+        pool: &PgPool,
+        admin_id: Uuid,
+        check: &StorageCheckResponse,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let details = serde_json::json!({
+            "action": "start_storage_check",
+            "backends": check.backends,
+            "cleanup": check.cleanup,
+            "verify_hash": check.verify_hash,
+            "grace_hours": check.grace_hours,
+        });
+
+        Self::log(
+            pool,
+            Some(admin_id),
+            AuditAction::AdminAction,
+            Some("storage_check"),
+            Some(check.id),
+            Some(details),
+            ip_address,
+        )
+        .await
+    }
+
+    /// 记录提交作者申领日志
+    pub async fn log_submit_claim(
+        pool: &PgPool,
+        user_id: Uuid,
+        claim_id: Uuid,
+        resource_id: Uuid,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let details = serde_json::json!({
+            "resource_id": resource_id,
+        });
+
+        Self::log(
+            pool,
+            Some(user_id),
+            AuditAction::SubmitClaim,
+            Some("claim"),
+            Some(claim_id),
+            Some(details),
+            ip_address,
+        )
+        .await
+    }
+
+    /// 记录审核作者申领日志（管理员）
+    pub async fn log_review_claim(
+        pool: &PgPool,
+        admin_id: Uuid,
+        claim_id: Uuid,
+        resource_id: Uuid,
+        applicant_id: Uuid,
+        status: &str,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let details = serde_json::json!({
+            "action": "review_claim",
+            "resource_id": resource_id,
+            "applicant_id": applicant_id,
+            "status": status,
+        });
+
+        Self::log(
+            pool,
+            Some(admin_id),
+            AuditAction::AdminAction,
+            Some("claim"),
+            Some(claim_id),
+            Some(details),
+            ip_address,
+        )
+        .await
+    }
+
+    /// 记录实名认证审核日志（管理员）
+    pub async fn log_review_verification(
+        pool: &PgPool,
+        admin_id: Uuid,
+        request_id: Uuid,
+        user_id: Uuid,
+        status: &str,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let details = serde_json::json!({
+            "action": "review_verification",
+            "user_id": user_id,
+            "status": status,
+        });
+
+        Self::log(
+            pool,
+            Some(admin_id),
+            AuditAction::AdminAction,
+            Some("verification_request"),
+            Some(request_id),
+            Some(details),
+            ip_address,
+        )
+        .await
+    }
 }
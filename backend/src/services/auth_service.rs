@@ -1,10 +1,20 @@
+use crate::config::Config;
 use crate::models::{
-    AuthResponse, LoginRequest, RegisterRequest, TokenResponse, User, UserInfo, UserRole,
+    AuthResponse, ChangePasswordRequest, ClientInfo, CurrentUser, LoginOutcome, LoginRequest,
+    MfaChallengeResponse, MfaLoginRequest, RegisterRequest, ResetPasswordRequest, TokenResponse,
+    User, UserInfo, UserRole, REVOKE_REASON_PASSWORD_CHANGED, REVOKE_REASON_PASSWORD_RESET,
+};
+use crate::services::{
+    AuditLogService, MailMessage, Mailer, MfaCredential, MfaService, SessionService,
 };
 use crate::utils::{
-    generate_access_token, generate_refresh_token, hash_password, verify_password, verify_token,
+    generate_access_token, generate_mfa_token, generate_one_time_token, generate_refresh_token,
+    get_mfa_token_expire_seconds, hash_one_time_token, hash_password, one_time_token_link,
+    verify_password, verify_token, TotpSecretCipher,
 };
 use sqlx::PgPool;
+use std::sync::Arc;
+use url::Url;
 use uuid::Uuid;
 
 /// 认证错误类型
@@ -12,11 +22,11 @@ use uuid::Uuid;
 pub enum AuthError {
     InvalidCredentials(String),
     UserExists(String),
-    #[allow(dead_code)]
     UserNotFound(String),
     TokenInvalid(String),
     DatabaseError(String),
     ValidationError(String),
+    TooManyAttempts(String),
 }
 
 impl std::fmt::Display for AuthError {
@@ -28,24 +38,101 @@ impl std::fmt::Display for AuthError {
             AuthError::TokenInvalid(msg) => write!(f, "Token无效: {}", msg),
             AuthError::DatabaseError(msg) => write!(f, "数据库错误: {}", msg),
             AuthError::ValidationError(msg) => write!(f, "验证错误: {}", msg),
+            AuthError::TooManyAttempts(msg) => write!(f, "尝试次数过多: {}", msg),
         }
     }
 }
 
 impl std::error::Error for AuthError {}
 
+/// 连续登录失败的锁定策略
+#[derive(Debug, Clone, Copy)]
+pub struct LoginLockoutPolicy {
+    /// 连续失败达到该次数后开始锁定，0 表示不锁定
+    pub threshold: i32,
+    /// 首次锁定的秒数，此后每次失败翻倍
+    pub base_secs: i64,
+    /// 锁定秒数上限
+    pub max_secs: i64,
+}
+
+impl LoginLockoutPolicy {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            threshold: config.login_lockout_threshold,
+            base_secs: config.login_lockout_base_secs,
+            max_secs: config.login_lockout_max_secs,
+        }
+    }
+
+    /// 连续失败 `failed_count` 次后需要锁定的秒数，未达到阈值时返回 None
+    pub fn lock_seconds(&self, failed_count: i32) -> Option<i64> {
+        if self.threshold <= 0 || failed_count < self.threshold {
+            return None;
+        }
+        let doublings = (failed_count - self.threshold).min(30) as u32;
+        Some(
+            self.base_secs
+                .saturating_mul(1i64 << doublings)
+                .min(self.max_secs),
+        )
+    }
+}
+
+/// 登录失败记录使用的客户端 IP（未知时为空字符串）
+fn lockout_ip(client: &ClientInfo) -> &str {
+    client.ip_address.as_deref().unwrap_or_default()
+}
+
 /// 认证服务
 pub struct AuthService;
 
 impl AuthService {
+    /// 为指定会话签发 Token 对
+    pub fn issue_tokens(
+        subject: &CurrentUser,
+        refresh_jti: Uuid,
+        jwt_secret: &str,
+    ) -> Result<TokenResponse, AuthError> {
+        let access_token = generate_access_token(
+            subject.id,
+            subject.username.clone(),
+            subject.role.clone(),
+            subject.is_verified,
+            subject.session_id,
+            subject.mfa,
+            jwt_secret,
+        )
+        .map_err(AuthError::TokenInvalid)?;
+
+        let refresh_token = generate_refresh_token(
+            subject.id,
+            subject.username.clone(),
+            subject.role.clone(),
+            subject.is_verified,
+            subject.session_id,
+            refresh_jti,
+            jwt_secret,
+        )
+        .map_err(AuthError::TokenInvalid)?;
+
+        Ok(TokenResponse {
+            access_token,
+            refresh_token,
+            token_type: "Bearer".to_string(),
+            expires_in: 15 * 60, // 15分钟
+        })
+    }
+
     /// 用户注册
     pub async fn register(
         pool: &PgPool,
         jwt_secret: &str,
         req: RegisterRequest,
+        client: &ClientInfo,
     ) -> Result<AuthResponse, AuthError> {
         // 验证请求
-        req.validate().map_err(|e| AuthError::ValidationError(e))?;
+        req.validate().map_err(AuthError::ValidationError)?;
 
         // 检查用户名是否已存在
         let existing_user: Option<(Uuid,)> =
@@ -61,7 +148,7 @@ impl AuthService {
 
         // 哈希密码
         let password_hash =
-            hash_password(&req.password).map_err(|e| AuthError::ValidationError(e))?;
+            hash_password(&req.password).map_err(AuthError::ValidationError)?;
 
         // 创建用户
         let user_id = Uuid::new_v4();
@@ -110,19 +197,21 @@ impl AuthService {
             _ => UserRole::Guest,
         };
 
-        // 生成 Token
-        let access_token = generate_access_token(
-            user_id,
-            req.username.clone(),
-            user_role.clone(),
-            false, // 新注册用户默认未实名认证
-            jwt_secret,
-        )
-        .map_err(|e| AuthError::TokenInvalid(e))?;
+        // 创建会话并生成 Token
+        let (session_id, refresh_jti) =
+            SessionService::create_session(pool, user_id, client, false)
+                .await
+                .map_err(|e| AuthError::DatabaseError(format!("创建会话失败: {}", e)))?;
 
-        let refresh_token =
-            generate_refresh_token(user_id, req.username.clone(), user_role, false, jwt_secret)
-                .map_err(|e| AuthError::TokenInvalid(e))?;
+        let subject = CurrentUser {
+            id: user_id,
+            username: req.username.clone(),
+            role: user_role,
+            is_verified: false, // 新注册用户默认未实名认证
+            session_id,
+            mfa: false,
+        };
+        let tokens = Self::issue_tokens(&subject, refresh_jti, jwt_secret)?;
 
         Ok(AuthResponse {
             user: UserInfo {
@@ -135,26 +224,39 @@ impl AuthService {
                 is_verified: false,
                 created_at: chrono::Local::now().naive_local(),
             },
-            tokens: TokenResponse {
-                access_token,
-                refresh_token,
-                token_type: "Bearer".to_string(),
-                expires_in: 15 * 60, // 15分钟
-            },
+            tokens,
         })
     }
 
     /// 用户登录
+    ///
+    /// 启用了两步验证的用户在密码验证通过后返回临时凭证，
+    /// 需要再调用 `complete_mfa_login` 提交动态码或恢复码
     pub async fn login(
         pool: &PgPool,
         jwt_secret: &str,
         req: LoginRequest,
-    ) -> Result<AuthResponse, AuthError> {
+        client: &ClientInfo,
+        lockout: &LoginLockoutPolicy,
+    ) -> Result<LoginOutcome, AuthError> {
         // 验证请求
-        req.validate().map_err(|e| AuthError::ValidationError(e))?;
+        req.validate().map_err(AuthError::ValidationError)?;
+
+        // 锁定期间直接拒绝，不再校验密码
+        if let Some(remaining) = Self::locked_remaining_secs(pool, &req.username, client).await? {
+            log::warn!(
+                "登录被拒绝，账号已锁定: {}, 剩余 {} 秒",
+                req.username,
+                remaining
+            );
+            return Err(AuthError::TooManyAttempts(format!(
+                "登录失败次数过多，请 {} 秒后再试",
+                remaining
+            )));
+        }
 
         // 查询用户
-        let user: User = sqlx::query_as::<_, User>(
+        let user: Option<User> = sqlx::query_as::<_, User>(
             "SELECT id, sn, username, password_hash, email, role, bio,
                     CASE WHEN social_links = '{}'::jsonb THEN NULL ELSE social_links END as social_links,
                     CASE WHEN real_info = '{}'::jsonb THEN NULL ELSE real_info END as real_info,
@@ -164,26 +266,170 @@ impl AuthService {
         .bind(&req.username)
         .fetch_optional(pool)
         .await
-        .map_err(|e| AuthError::DatabaseError(e.to_string()))?
-        .ok_or_else(|| AuthError::InvalidCredentials("用户名或密码错误".to_string()))?;
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+        // 用户名不存在时不记录失败次数，避免任意用户名写入失败记录表
+        // （用户名探测由登录接口的 IP 限流约束）
+        let Some(user) = user else {
+            log::warn!("登录失败，用户不存在: {}", req.username);
+            return Err(AuthError::InvalidCredentials(
+                "用户名或密码错误".to_string(),
+            ));
+        };
 
         // 验证密码
-        let valid = verify_password(&req.password, &user.password_hash)
-            .map_err(|_| AuthError::InvalidCredentials("用户名或密码错误".to_string()))?;
+        let valid = verify_password(&req.password, &user.password_hash).unwrap_or(false);
 
         if !valid {
             log::warn!("登录失败，密码错误: {}", req.username);
+            Self::record_login_failure(pool, user.id, &req.username, client, lockout).await?;
             return Err(AuthError::InvalidCredentials(
                 "用户名或密码错误".to_string(),
             ));
         }
 
+        // 启用两步验证时，失败记录保留到第二步完成，避免借重新输入密码绕过锁定
+        let mfa_enabled = MfaService::is_enabled(pool, user.id)
+            .await
+            .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+        if mfa_enabled {
+            log::info!("密码验证通过，等待两步验证: {}", req.username);
+            return Self::mfa_challenge(&user, jwt_secret).map(LoginOutcome::MfaRequired);
+        }
+
         log::info!("用户登录成功: {}, 角色: {}", req.username, user.role);
 
+        let response = Self::start_session(pool, jwt_secret, user, client, false).await?;
+        Ok(LoginOutcome::Authenticated(response))
+    }
+
+    /// 登录第二步：校验动态码或恢复码，通过后创建会话
+    pub async fn complete_mfa_login(
+        pool: &PgPool,
+        jwt_secret: &str,
+        cipher: &TotpSecretCipher,
+        req: MfaLoginRequest,
+        client: &ClientInfo,
+        lockout: &LoginLockoutPolicy,
+    ) -> Result<AuthResponse, AuthError> {
+        req.validate().map_err(AuthError::ValidationError)?;
+
+        let claims = verify_token(&req.mfa_token, jwt_secret, Some("mfa"))
+            .map_err(|_| AuthError::InvalidCredentials("两步验证已过期，请重新登录".to_string()))?;
+        let user_id = Uuid::parse_str(&claims.sub)
+            .map_err(|_| AuthError::TokenInvalid("无效的用户ID".to_string()))?;
+
+        if let Some(remaining) = Self::locked_remaining_secs(pool, &claims.username, client).await?
+        {
+            return Err(AuthError::TooManyAttempts(format!(
+                "登录失败次数过多，请 {} 秒后再试",
+                remaining
+            )));
+        }
+
+        let user = Self::find_active_user(pool, user_id).await?;
+
+        let credential = match (&req.code, &req.recovery_code) {
+            (Some(code), _) if !code.trim().is_empty() => MfaCredential::Code(code),
+            (_, Some(recovery_code)) => MfaCredential::RecoveryCode(recovery_code),
+            _ => MfaCredential::Code(""),
+        };
+        let valid = MfaService::verify(pool, cipher, user.id, credential)
+            .await
+            .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+        if !valid {
+            log::warn!("两步验证失败: {}", user.username);
+            Self::record_login_failure(pool, user.id, &user.username, client, lockout).await?;
+            return Err(AuthError::InvalidCredentials(
+                "动态码或恢复码错误".to_string(),
+            ));
+        }
+
+        log::info!(
+            "用户登录成功（两步验证）: {}, 角色: {}",
+            user.username,
+            user.role
+        );
+
+        Self::start_session(pool, jwt_secret, user, client, true).await
+    }
+
+    /// 通过统一身份认证登录（外部身份已由 SsoService 关联到账号）
+    ///
+    /// 与密码登录一样，启用了两步验证的用户需要再调用 `complete_mfa_login`
+    pub async fn login_with_identity(
+        pool: &PgPool,
+        jwt_secret: &str,
+        user_id: Uuid,
+        client: &ClientInfo,
+    ) -> Result<LoginOutcome, AuthError> {
+        let user = Self::find_active_user(pool, user_id).await?;
+
+        let mfa_enabled = MfaService::is_enabled(pool, user.id)
+            .await
+            .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+        if mfa_enabled {
+            log::info!("统一身份认证通过，等待两步验证: {}", user.username);
+            return Self::mfa_challenge(&user, jwt_secret).map(LoginOutcome::MfaRequired);
+        }
+
+        log::info!(
+            "用户通过统一身份认证登录成功: {}, 角色: {}",
+            user.username,
+            user.role
+        );
+
+        let response = Self::start_session(pool, jwt_secret, user, client, false).await?;
+        Ok(LoginOutcome::Authenticated(response))
+    }
+
+    /// 查询未被禁用的用户
+    async fn find_active_user(pool: &PgPool, user_id: Uuid) -> Result<User, AuthError> {
+        sqlx::query_as::<_, User>(
+            "SELECT id, sn, username, password_hash, email, role, bio,
+                    CASE WHEN social_links = '{}'::jsonb THEN NULL ELSE social_links END as social_links,
+                    CASE WHEN real_info = '{}'::jsonb THEN NULL ELSE real_info END as real_info,
+                    is_verified, is_active, created_at, updated_at
+             FROM users WHERE id = $1 AND is_active = true"
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| AuthError::InvalidCredentials("用户不存在或已被禁用".to_string()))
+    }
+
+    /// 第一步验证通过，签发等待两步验证的临时凭证
+    fn mfa_challenge(user: &User, jwt_secret: &str) -> Result<MfaChallengeResponse, AuthError> {
+        let mfa_token = generate_mfa_token(user.id, user.username.clone(), jwt_secret)
+            .map_err(AuthError::TokenInvalid)?;
+        Ok(MfaChallengeResponse {
+            mfa_required: true,
+            mfa_token,
+            expires_in: get_mfa_token_expire_seconds(),
+        })
+    }
+
+    /// 清除本 IP 的登录失败记录，创建会话并签发 Token
+    async fn start_session(
+        pool: &PgPool,
+        jwt_secret: &str,
+        user: User,
+        client: &ClientInfo,
+        mfa: bool,
+    ) -> Result<AuthResponse, AuthError> {
+        sqlx::query("DELETE FROM login_failures WHERE username = $1 AND ip_address = $2")
+            .bind(&user.username)
+            .bind(lockout_ip(client))
+            .execute(pool)
+            .await
+            .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
         // 解析角色
         let role = match user.role.as_str() {
             "admin" => {
-                log::info!("用户 {} 以管理员身份登录", req.username);
+                log::info!("用户 {} 以管理员身份登录", user.username);
                 UserRole::Admin
             }
             "verified" => UserRole::Verified,
@@ -191,24 +437,20 @@ impl AuthService {
             _ => UserRole::Guest,
         };
 
-        // 生成 Token
-        let access_token = generate_access_token(
-            user.id,
-            user.username.clone(),
-            role.clone(),
-            user.is_verified,
-            jwt_secret,
-        )
-        .map_err(|e| AuthError::TokenInvalid(e))?;
+        // 创建会话并生成 Token
+        let (session_id, refresh_jti) = SessionService::create_session(pool, user.id, client, mfa)
+            .await
+            .map_err(|e| AuthError::DatabaseError(format!("创建会话失败: {}", e)))?;
 
-        let refresh_token = generate_refresh_token(
-            user.id,
-            user.username.clone(),
-            role.clone(),
-            user.is_verified,
-            jwt_secret,
-        )
-        .map_err(|e| AuthError::TokenInvalid(e))?;
+        let subject = CurrentUser {
+            id: user.id,
+            username: user.username.clone(),
+            role,
+            is_verified: user.is_verified,
+            session_id,
+            mfa,
+        };
+        let tokens = Self::issue_tokens(&subject, refresh_jti, jwt_secret)?;
 
         Ok(AuthResponse {
             user: UserInfo {
@@ -221,62 +463,628 @@ impl AuthService {
                 is_verified: user.is_verified,
                 created_at: user.created_at,
             },
-            tokens: TokenResponse {
-                access_token,
-                refresh_token,
-                token_type: "Bearer".to_string(),
-                expires_in: 15 * 60, // 15分钟
-            },
+            tokens,
         })
     }
 
+    /// 查询用户名在该客户端 IP 上的剩余锁定秒数，未锁定时返回 None
+    async fn locked_remaining_secs(
+        pool: &PgPool,
+        username: &str,
+        client: &ClientInfo,
+    ) -> Result<Option<i64>, AuthError> {
+        sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT GREATEST(CEIL(EXTRACT(EPOCH FROM (locked_until - CURRENT_TIMESTAMP))), 1)::bigint
+            FROM login_failures
+            WHERE username = $1 AND ip_address = $2 AND locked_until > CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(username)
+        .bind(lockout_ip(client))
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))
+    }
+
+    /// 记录已有用户的一次登录失败，同一 IP 达到阈值后锁定该用户名在这个 IP 上的登录
+    ///
+    /// 距上次失败超过一天时重新计数，顺带清理超过一天且已解除锁定的记录
+    async fn record_login_failure(
+        pool: &PgPool,
+        user_id: Uuid,
+        username: &str,
+        client: &ClientInfo,
+        lockout: &LoginLockoutPolicy,
+    ) -> Result<(), AuthError> {
+        sqlx::query(
+            r#"
+            DELETE FROM login_failures
+            WHERE last_failed_at < CURRENT_TIMESTAMP - INTERVAL '1 day'
+              AND (locked_until IS NULL OR locked_until < CURRENT_TIMESTAMP)
+            "#,
+        )
+        .execute(pool)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+        let failed_count: i32 = sqlx::query_scalar(
+            r#"
+            INSERT INTO login_failures (username, ip_address, failed_count, last_failed_at)
+            VALUES ($1, $2, 1, CURRENT_TIMESTAMP)
+            ON CONFLICT (username, ip_address) DO UPDATE SET
+                failed_count = CASE
+                    WHEN login_failures.last_failed_at < CURRENT_TIMESTAMP - INTERVAL '1 day' THEN 1
+                    ELSE login_failures.failed_count + 1
+                END,
+                last_failed_at = CURRENT_TIMESTAMP
+            RETURNING failed_count
+            "#,
+        )
+        .bind(username)
+        .bind(lockout_ip(client))
+        .fetch_one(pool)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+        let ip_address = client.ip_address.as_deref();
+        if let Err(e) = AuditLogService::log_login_failed(
+            pool,
+            Some(user_id),
+            username,
+            failed_count,
+            ip_address,
+        )
+        .await
+        {
+            log::warn!(
+                "[Audit] 记录登录失败日志失败 | username={}, error={}",
+                username,
+                e
+            );
+        }
+
+        let Some(lock_secs) = lockout.lock_seconds(failed_count) else {
+            return Ok(());
+        };
+
+        sqlx::query(
+            "UPDATE login_failures SET locked_until = CURRENT_TIMESTAMP + $3 * INTERVAL '1 second' WHERE username = $1 AND ip_address = $2",
+        )
+        .bind(username)
+        .bind(lockout_ip(client))
+        .bind(lock_secs as f64)
+        .execute(pool)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+        log::warn!(
+            "账号已锁定: {}, ip={:?}, 连续失败 {} 次, 锁定 {} 秒",
+            username,
+            ip_address,
+            failed_count,
+            lock_secs
+        );
+
+        if let Err(e) = AuditLogService::log_account_locked(
+            pool,
+            Some(user_id),
+            username,
+            failed_count,
+            lock_secs,
+            ip_address,
+        )
+        .await
+        {
+            log::warn!(
+                "[Audit] 记录账号锁定日志失败 | username={}, error={}",
+                username,
+                e
+            );
+        }
+
+        Ok(())
+    }
+
     /// 刷新 Token
+    ///
+    /// Refresh Token 只能使用一次：每次刷新都会轮换会话中记录的标识，
+    /// 旧 Token 再次使用时整个会话被吊销
     pub async fn refresh_token(
-        _pool: &PgPool,
+        pool: &PgPool,
         jwt_secret: &str,
         refresh_token: String,
+        client: &ClientInfo,
     ) -> Result<TokenResponse, AuthError> {
         // 验证 Refresh Token
         let claims = verify_token(&refresh_token, jwt_secret, Some("refresh"))
-            .map_err(|e| AuthError::TokenInvalid(e))?;
+            .map_err(AuthError::TokenInvalid)?;
 
-        // 提取用户信息
+        // 提取用户和会话信息
         let user_id = Uuid::parse_str(&claims.sub)
             .map_err(|_| AuthError::TokenInvalid("无效的用户ID".to_string()))?;
+        let session_id = Uuid::parse_str(&claims.sid)
+            .map_err(|_| AuthError::TokenInvalid("无效的会话ID".to_string()))?;
+        let presented_jti = claims
+            .jti
+            .as_deref()
+            .and_then(|jti| Uuid::parse_str(jti).ok())
+            .ok_or_else(|| AuthError::TokenInvalid("无效的Token标识".to_string()))?;
+
+        log::info!("刷新 Token: {}", claims.username);
+
+        let (refresh_jti, mfa) = SessionService::rotate_refresh_token(
+            pool,
+            session_id,
+            user_id,
+            Some(presented_jti),
+            client,
+        )
+        .await?;
 
-        let role = match claims.role.as_str() {
+        // 角色和实名状态以数据库为准（管理员审核通过实名认证后，刷新即可生效）
+        let (username, role, is_verified): (String, String, bool) =
+            sqlx::query_as("SELECT username, role, is_verified FROM users WHERE id = $1")
+                .bind(user_id)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| AuthError::DatabaseError(e.to_string()))?
+                .ok_or_else(|| AuthError::TokenInvalid("用户不存在".to_string()))?;
+
+        let role = match role.as_str() {
             "admin" => UserRole::Admin,
             "verified" => UserRole::Verified,
             "user" => UserRole::User,
             _ => UserRole::Guest,
         };
 
-        log::info!("刷新 Token: {}", claims.username);
-
         // 生成新的 Token 对
-        let access_token = generate_access_token(
+        let subject = CurrentUser {
+            id: user_id,
+            username,
+            role,
+            is_verified,
+            session_id,
+            mfa,
+        };
+        Self::issue_tokens(&subject, refresh_jti, jwt_secret)
+    }
+
+    /// 修改密码（需要验证原密码），返回吊销的会话数
+    ///
+    /// 更新密码、作废重置链接和吊销当前会话以外的全部会话在同一事务中完成
+    pub async fn change_password(
+        pool: &PgPool,
+        user_id: Uuid,
+        session_id: Uuid,
+        req: &ChangePasswordRequest,
+    ) -> Result<u64, AuthError> {
+        req.validate().map_err(AuthError::ValidationError)?;
+
+        let new_hash = hash_password(&req.new_password).map_err(AuthError::ValidationError)?;
+
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+        let password_hash: String = sqlx::query_scalar(
+            "SELECT password_hash FROM users WHERE id = $1 AND is_active = true FOR UPDATE",
+        )
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| AuthError::UserNotFound("用户不存在".to_string()))?;
+
+        let valid = verify_password(&req.old_password, &password_hash)
+            .map_err(|_| AuthError::InvalidCredentials("原密码错误".to_string()))?;
+        if !valid {
+            log::warn!("[Auth] 修改密码失败，原密码错误 | user_id={}", user_id);
+            return Err(AuthError::InvalidCredentials("原密码错误".to_string()));
+        }
+
+        sqlx::query("UPDATE users SET password_hash = $1, updated_at = NOW() WHERE id = $2")
+            .bind(&new_hash)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+        // 未使用的重置链接随之作废
+        sqlx::query(
+            "UPDATE password_reset_tokens SET used_at = CURRENT_TIMESTAMP WHERE user_id = $1 AND used_at IS NULL",
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+        let revoked = SessionService::revoke_all_sessions(
+            &mut *tx,
             user_id,
-            claims.username.clone(),
-            role.clone(),
-            claims.is_verified,
-            jwt_secret,
+            Some(session_id),
+            REVOKE_REASON_PASSWORD_CHANGED,
         )
-        .map_err(|e| AuthError::TokenInvalid(e))?;
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
 
-        let refresh_token = generate_refresh_token(
+        tx.commit()
+            .await
+            .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+        SessionService::forget_user_sessions(user_id);
+
+        log::info!(
+            "[Auth] 修改密码成功 | user_id={}, revoked_sessions={}",
             user_id,
-            claims.username,
-            role,
-            claims.is_verified,
-            jwt_secret,
+            revoked
+        );
+
+        Ok(revoked)
+    }
+
+    /// 申请通过邮件重置密码
+    ///
+    /// 无论邮箱是否存在都返回成功，避免借此探测已注册的邮箱。
+    /// 邮件在后台发送，接口响应时间不受邮件服务器影响
+    pub async fn request_password_reset(
+        pool: &PgPool,
+        mailer: Arc<dyn Mailer>,
+        config: &Config,
+        email: &str,
+        ip_address: Option<&str>,
+    ) -> Result<(), AuthError> {
+        // 地址配置错误时无论邮箱是否存在都返回同样的错误
+        let reset_page = Url::parse(&config.password_reset_url).map_err(|e| {
+            log::error!("[Auth] PASSWORD_RESET_URL 配置错误 | error={}", e);
+            AuthError::DatabaseError(format!("重置密码页面地址配置错误: {}", e))
+        })?;
+
+        let email = email.trim();
+        let users: Vec<(Uuid, String, String)> = sqlx::query_as(
+            "SELECT id, username, email FROM users WHERE LOWER(email) = LOWER($1) AND is_active = true",
         )
-        .map_err(|e| AuthError::TokenInvalid(e))?;
+        .bind(email)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
 
-        Ok(TokenResponse {
-            access_token,
-            refresh_token,
-            token_type: "Bearer".to_string(),
-            expires_in: 15 * 60, // 15分钟
-        })
+        if users.is_empty() {
+            log::info!("[Auth] 申请重置密码，邮箱未注册 | ip={:?}", ip_address);
+            return Ok(());
+        }
+
+        for (user_id, username, user_email) in users {
+            let token = generate_one_time_token();
+
+            // 同一用户只保留最新的一条重置链接
+            sqlx::query(
+                "UPDATE password_reset_tokens SET used_at = CURRENT_TIMESTAMP WHERE user_id = $1 AND used_at IS NULL",
+            )
+            .bind(user_id)
+            .execute(pool)
+            .await
+            .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+            sqlx::query(
+                r#"
+                INSERT INTO password_reset_tokens (user_id, token_hash, expires_at, requested_ip)
+                VALUES ($1, $2, CURRENT_TIMESTAMP + make_interval(mins => $3), $4)
+                "#,
+            )
+            .bind(user_id)
+            .bind(hash_one_time_token(&token))
+            .bind(config.password_reset_token_ttl_minutes)
+            .bind(ip_address)
+            .execute(pool)
+            .await
+            .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+            let message = MailMessage {
+                to: user_email,
+                subject: "ShareUSTC 密码重置".to_string(),
+                body: format!(
+                    "{}，你好：\n\n我们收到了重置你的 ShareUSTC 账号密码的请求。请在 {} 分钟内打开下面的链接设置新密码：\n\n{}\n\n如果这不是你本人的操作，请忽略本邮件，你的密码不会被修改。\n",
+                    username,
+                    config.password_reset_token_ttl_minutes,
+                    one_time_token_link(&reset_page, &token)
+                ),
+            };
+
+            let mailer = mailer.clone();
+            tokio::spawn(async move {
+                if let Err(e) = mailer.send(&message).await {
+                    log::error!(
+                        "[Auth] 发送重置密码邮件失败 | user_id={}, mailer={}, error={}",
+                        user_id,
+                        mailer.name(),
+                        e
+                    );
+                }
+            });
+
+            log::info!(
+                "[Auth] 已生成重置密码链接 | user_id={}, ip={:?}",
+                user_id,
+                ip_address
+            );
+        }
+
+        Ok(())
+    }
+
+    /// 使用邮件中的 Token 重置密码（Token 只能使用一次），返回 (用户ID, 吊销的会话数)
+    pub async fn reset_password(
+        pool: &PgPool,
+        req: &ResetPasswordRequest,
+    ) -> Result<(Uuid, u64), AuthError> {
+        req.validate().map_err(AuthError::ValidationError)?;
+
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+        let token = sqlx::query_as::<_, (Uuid, Uuid)>(
+            r#"
+            SELECT t.id, t.user_id
+            FROM password_reset_tokens t
+            JOIN users u ON t.user_id = u.id
+            WHERE t.token_hash = $1
+              AND t.used_at IS NULL
+              AND t.expires_at > CURRENT_TIMESTAMP
+              AND u.is_active = true
+            FOR UPDATE OF t
+            "#,
+        )
+        .bind(hash_one_time_token(&req.token))
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+        let Some((token_id, user_id)) = token else {
+            return Err(AuthError::TokenInvalid("重置链接无效或已过期".to_string()));
+        };
+
+        let new_hash = hash_password(&req.new_password).map_err(AuthError::ValidationError)?;
+
+        sqlx::query("UPDATE users SET password_hash = $1, updated_at = NOW() WHERE id = $2")
+            .bind(&new_hash)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+        // 当前 Token 及该用户其他未使用的 Token 一并作废
+        sqlx::query(
+            "UPDATE password_reset_tokens SET used_at = CURRENT_TIMESTAMP WHERE user_id = $1 AND (id = $2 OR used_at IS NULL)",
+        )
+        .bind(user_id)
+        .bind(token_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+        // 密码可能已泄露，所有设备都需要重新登录
+        let revoked = SessionService::revoke_all_sessions(
+            &mut *tx,
+            user_id,
+            None,
+            REVOKE_REASON_PASSWORD_RESET,
+        )
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+        SessionService::forget_user_sessions(user_id);
+
+        log::info!(
+            "[Auth] 重置密码成功 | user_id={}, revoked_sessions={}",
+            user_id,
+            revoked
+        );
+
+        Ok((user_id, revoked))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{cleanup_test_user, create_test_user, test_pool};
+
+    #[tokio::test]
+    async fn test_change_password_revokes_other_sessions() {
+        let pool = test_pool().await;
+        let user = create_test_user(&pool, UserRole::User).await;
+        sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2")
+            .bind(hash_password("old-password").unwrap())
+            .bind(user.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO password_reset_tokens (user_id, token_hash, expires_at)
+            VALUES ($1, $2, CURRENT_TIMESTAMP + INTERVAL '30 minutes')
+            "#,
+        )
+        .bind(user.id)
+        .bind(hash_one_time_token(&generate_one_time_token()))
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let client = ClientInfo::default();
+        let (current, _) = SessionService::create_session(&pool, user.id, &client, false)
+            .await
+            .unwrap();
+        let (other, _) = SessionService::create_session(&pool, user.id, &client, false)
+            .await
+            .unwrap();
+        assert!(SessionService::is_session_active(&pool, other, user.id)
+            .await
+            .unwrap());
+
+        let change = |old: &str| ChangePasswordRequest {
+            old_password: old.to_string(),
+            new_password: "new-password".to_string(),
+        };
+
+        // 原密码错误时什么都不改
+        assert!(matches!(
+            AuthService::change_password(&pool, user.id, current, &change("wrong")).await,
+            Err(AuthError::InvalidCredentials(_))
+        ));
+        assert!(SessionService::is_session_active(&pool, other, user.id)
+            .await
+            .unwrap());
+
+        let revoked =
+            AuthService::change_password(&pool, user.id, current, &change("old-password"))
+                .await
+                .unwrap();
+        assert_eq!(revoked, 1);
+        assert!(SessionService::is_session_active(&pool, current, user.id)
+            .await
+            .unwrap());
+        assert!(!SessionService::is_session_active(&pool, other, user.id)
+            .await
+            .unwrap());
+
+        let hash: String = sqlx::query_scalar("SELECT password_hash FROM users WHERE id = $1")
+            .bind(user.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert!(verify_password("new-password", &hash).unwrap());
+
+        let pending_resets: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM password_reset_tokens WHERE user_id = $1 AND used_at IS NULL",
+        )
+        .bind(user.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(pending_resets, 0);
+
+        cleanup_test_user(&pool, user.id).await;
+    }
+
+    #[tokio::test]
+    async fn test_lockout_is_per_client_ip() {
+        let pool = test_pool().await;
+        let user = create_test_user(&pool, UserRole::User).await;
+        sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2")
+            .bind(hash_password("right-password").unwrap())
+            .bind(user.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let lockout = LoginLockoutPolicy {
+            threshold: 2,
+            base_secs: 60,
+            max_secs: 60,
+        };
+        let client = |ip: &str| ClientInfo {
+            ip_address: Some(ip.to_string()),
+            user_agent: None,
+        };
+        let attacker = client("203.0.113.7");
+        let owner = client("198.51.100.20");
+        let login = |username: &str, password: &str| LoginRequest {
+            username: username.to_string(),
+            password: password.to_string(),
+        };
+
+        for _ in 0..2 {
+            assert!(matches!(
+                AuthService::login(
+                    &pool,
+                    "secret",
+                    login(&user.username, "wrong-password"),
+                    &attacker,
+                    &lockout
+                )
+                .await,
+                Err(AuthError::InvalidCredentials(_))
+            ));
+        }
+        // 攻击方 IP 被锁定，即使密码正确也拒绝
+        assert!(matches!(
+            AuthService::login(
+                &pool,
+                "secret",
+                login(&user.username, "right-password"),
+                &attacker,
+                &lockout
+            )
+            .await,
+            Err(AuthError::TooManyAttempts(_))
+        ));
+        // 其他 IP 不受影响
+        assert!(matches!(
+            AuthService::login(
+                &pool,
+                "secret",
+                login(&user.username, "right-password"),
+                &owner,
+                &lockout
+            )
+            .await,
+            Ok(LoginOutcome::Authenticated(_))
+        ));
+
+        // 不存在的用户名不写入失败记录
+        let unknown = format!("{}_missing", user.username);
+        assert!(matches!(
+            AuthService::login(
+                &pool,
+                "secret",
+                login(&unknown, "wrong-password"),
+                &attacker,
+                &lockout
+            )
+            .await,
+            Err(AuthError::InvalidCredentials(_))
+        ));
+        let rows: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM login_failures WHERE username = $1")
+                .bind(&unknown)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(rows, 0);
+
+        sqlx::query("DELETE FROM login_failures WHERE username = $1")
+            .bind(&user.username)
+            .execute(&pool)
+            .await
+            .unwrap();
+        cleanup_test_user(&pool, user.id).await;
+    }
+
+    #[test]
+    fn test_lockout_doubles_until_max() {
+        let policy = LoginLockoutPolicy {
+            threshold: 5,
+            base_secs: 60,
+            max_secs: 3600,
+        };
+        assert_eq!(policy.lock_seconds(4), None);
+        assert_eq!(policy.lock_seconds(5), Some(60));
+        assert_eq!(policy.lock_seconds(6), Some(120));
+        assert_eq!(policy.lock_seconds(7), Some(240));
+        assert_eq!(policy.lock_seconds(11), Some(3600));
+        assert_eq!(policy.lock_seconds(1000), Some(3600));
+
+        let disabled = LoginLockoutPolicy {
+            threshold: 0,
+            ..policy
+        };
+        assert_eq!(disabled.lock_seconds(100), None);
     }
 }
@@ -0,0 +1,527 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::{
+    Claim, ClaimListQuery, ClaimListResponse, ClaimListRow, ClaimResponse, ClaimStatus,
+    CreateClaimRequest, ReviewClaimRequest, CLAIM_TYPE_AUTHOR,
+};
+use crate::services::{NotificationService, ResourceError};
+use crate::utils::is_unique_violation;
+
+/// 申领列表查询（关联资源标题、申请人和上传者用户名）
+const CLAIM_SELECT: &str = r#"
+    SELECT
+        c.id,
+        c.resource_id,
+        r.title AS resource_title,
+        c.applicant_id,
+        a.username AS applicant_name,
+        u.username AS uploader_name,
+        r.author_id AS current_author_id,
+        c.claim_type,
+        c.reason,
+        c.proof_files,
+        c.status,
+        c.reviewer_id,
+        c.reviewed_at,
+        c.created_at
+    FROM claims c
+    JOIN resources r ON r.id = c.resource_id
+    JOIN users a ON a.id = c.applicant_id
+    LEFT JOIN users u ON u.id = r.uploader_id
+"#;
+
+/// 审核结果
+pub struct ClaimReviewOutcome {
+    pub claim_id: Uuid,
+    pub resource_id: Uuid,
+    pub applicant_id: Uuid,
+    pub status: ClaimStatus,
+    /// 因申领通过而被自动驳回的其他申领数量
+    pub auto_rejected: usize,
+}
+
+pub struct ClaimService;
+
+impl ClaimService {
+    /// 提交作者申领
+    pub async fn create_claim(
+        pool: &PgPool,
+        applicant_id: Uuid,
+        resource_id: Uuid,
+        request: &CreateClaimRequest,
+        image_base_url: &str,
+    ) -> Result<ClaimResponse, ResourceError> {
+        request.validate().map_err(ResourceError::ValidationError)?;
+
+        let author_id: Option<Option<Uuid>> = sqlx::query_scalar(
+            "SELECT author_id FROM resources WHERE id = $1 AND audit_status = 'approved'",
+        )
+        .bind(resource_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        match author_id {
+            None => return Err(ResourceError::NotFound("资源不存在".to_string())),
+            Some(Some(author_id)) if author_id == applicant_id => {
+                return Err(ResourceError::Conflict("您已是该资源的作者".to_string()));
+            }
+            Some(_) => {}
+        }
+
+        let pending: bool = sqlx::query_scalar(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM claims
+                WHERE resource_id = $1 AND applicant_id = $2 AND status = 'pending'
+            )
+            "#,
+        )
+        .bind(resource_id)
+        .bind(applicant_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        if pending {
+            return Err(ResourceError::Conflict(
+                "您已提交过该资源的申领，请等待审核".to_string(),
+            ));
+        }
+
+        // 证明图片必须是申请人自己上传的
+        let mut image_ids = request.proof_image_ids.clone();
+        image_ids.sort();
+        image_ids.dedup();
+
+        if !image_ids.is_empty() {
+            let owned: i64 = sqlx::query_scalar(
+                "SELECT COUNT(*) FROM images WHERE id = ANY($1) AND uploader_id = $2",
+            )
+            .bind(&image_ids)
+            .bind(applicant_id)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+            if owned != image_ids.len() as i64 {
+                return Err(ResourceError::ValidationError(
+                    "证明图片不存在或不属于当前用户".to_string(),
+                ));
+            }
+        }
+
+        // 保持用户提交时的图片顺序
+        let mut proof_files: Vec<String> = Vec::with_capacity(image_ids.len());
+        for id in &request.proof_image_ids {
+            let id = id.to_string();
+            if !proof_files.contains(&id) {
+                proof_files.push(id);
+            }
+        }
+
+        let claim_id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO claims (resource_id, applicant_id, claim_type, reason, proof_files, status)
+            VALUES ($1, $2, $3, $4, $5, 'pending')
+            RETURNING id
+            "#,
+        )
+        .bind(resource_id)
+        .bind(applicant_id)
+        .bind(CLAIM_TYPE_AUTHOR)
+        .bind(request.reason.trim())
+        .bind(serde_json::json!(proof_files))
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            // 并发提交时前面的检查可能都通过，由待审核申领的唯一索引兜底
+            if is_unique_violation(&e) {
+                ResourceError::Conflict("您已提交过该资源的申领，请等待审核".to_string())
+            } else {
+                ResourceError::DatabaseError(e.to_string())
+            }
+        })?;
+
+        log::info!(
+            "[Claim] 提交作者申领 | claim_id={}, resource_id={}, applicant_id={}, proofs={}",
+            claim_id,
+            resource_id,
+            applicant_id,
+            proof_files.len()
+        );
+
+        Self::get_claim(pool, claim_id, image_base_url).await
+    }
+
+    /// 获取单个申领
+    pub async fn get_claim(
+        pool: &PgPool,
+        claim_id: Uuid,
+        image_base_url: &str,
+    ) -> Result<ClaimResponse, ResourceError> {
+        let sql = format!("{} WHERE c.id = $1", CLAIM_SELECT);
+        let row = sqlx::query_as::<_, ClaimListRow>(&sql)
+            .bind(claim_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| ResourceError::NotFound("申领不存在".to_string()))?;
+
+        Ok(ClaimResponse::from_row(row, image_base_url))
+    }
+
+    /// 获取申领列表
+    /// 指定 `applicant_id` 时只返回该用户的申领，否则返回全部（管理员）
+    pub async fn list_claims(
+        pool: &PgPool,
+        applicant_id: Option<Uuid>,
+        query: &ClaimListQuery,
+        image_base_url: &str,
+    ) -> Result<ClaimListResponse, ResourceError> {
+        let status = match query.status.as_deref() {
+            Some(s) => Some(
+                ClaimStatus::parse(s)
+                    .ok_or_else(|| {
+                        ResourceError::ValidationError(
+                            "status 只能是 pending、approved 或 rejected".to_string(),
+                        )
+                    })?
+                    .as_str(),
+            ),
+            None => None,
+        };
+
+        let page = query.get_page();
+        let per_page = query.get_per_page();
+        let offset = (page - 1) * per_page;
+
+        let filter = r#"
+            WHERE ($1::UUID IS NULL OR c.applicant_id = $1)
+              AND ($2::VARCHAR IS NULL OR c.status = $2)
+        "#;
+
+        let sql = format!(
+            "{} {} ORDER BY c.created_at DESC LIMIT $3 OFFSET $4",
+            CLAIM_SELECT, filter
+        );
+        let rows = sqlx::query_as::<_, ClaimListRow>(&sql)
+            .bind(applicant_id)
+            .bind(status)
+            .bind(per_page as i64)
+            .bind(offset as i64)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        let count_sql = format!(
+            "SELECT COUNT(*) FROM claims c JOIN resources r ON r.id = c.resource_id {}",
+            filter
+        );
+        let total: i64 = sqlx::query_scalar(&count_sql)
+            .bind(applicant_id)
+            .bind(status)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        Ok(ClaimListResponse {
+            claims: rows
+                .into_iter()
+                .map(|row| ClaimResponse::from_row(row, image_base_url))
+                .collect(),
+            total,
+            page,
+            per_page,
+        })
+    }
+
+    /// 撤回待审核的申领
+    pub async fn withdraw_claim(
+        pool: &PgPool,
+        applicant_id: Uuid,
+        claim_id: Uuid,
+    ) -> Result<(), ResourceError> {
+        let claim = Self::find_claim(pool, claim_id).await?;
+
+        if claim.applicant_id != applicant_id {
+            return Err(ResourceError::Unauthorized("无权撤回此申领".to_string()));
+        }
+
+        let result = sqlx::query("DELETE FROM claims WHERE id = $1 AND status = 'pending'")
+            .bind(claim_id)
+            .execute(pool)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(ResourceError::Conflict("申领已审核，无法撤回".to_string()));
+        }
+
+        log::info!(
+            "[Claim] 撤回作者申领 | claim_id={}, applicant_id={}",
+            claim_id,
+            applicant_id
+        );
+        Ok(())
+    }
+
+    /// 审核申领（管理员）
+    ///
+    /// 通过时将资源作者设为申请人，并驳回同一资源的其他待审核申领；
+    /// 审核结果以 claim_result 通知发送给相关申请人
+    pub async fn review_claim(
+        pool: &PgPool,
+        reviewer_id: Uuid,
+        claim_id: Uuid,
+        request: &ReviewClaimRequest,
+    ) -> Result<ClaimReviewOutcome, ResourceError> {
+        let status = request.validate().map_err(ResourceError::ValidationError)?;
+
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        let claim = sqlx::query_as::<_, Claim>(
+            "SELECT resource_id, applicant_id, status FROM claims WHERE id = $1 FOR UPDATE",
+        )
+        .bind(claim_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| ResourceError::NotFound("申领不存在".to_string()))?;
+
+        if claim.status.as_deref() != Some(ClaimStatus::Pending.as_str()) {
+            return Err(ResourceError::Conflict("该申领已审核".to_string()));
+        }
+
+        sqlx::query(
+            "UPDATE claims SET status = $1, reviewer_id = $2, reviewed_at = NOW() WHERE id = $3",
+        )
+        .bind(status.as_str())
+        .bind(reviewer_id)
+        .bind(claim_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        let mut auto_rejected: Vec<Uuid> = Vec::new();
+        if status == ClaimStatus::Approved {
+            sqlx::query("UPDATE resources SET author_id = $1, updated_at = NOW() WHERE id = $2")
+                .bind(claim.applicant_id)
+                .bind(claim.resource_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+            auto_rejected = sqlx::query_scalar(
+                r#"
+                UPDATE claims
+                SET status = 'rejected', reviewer_id = $1, reviewed_at = NOW()
+                WHERE resource_id = $2 AND status = 'pending' AND id <> $3
+                RETURNING applicant_id
+                "#,
+            )
+            .bind(reviewer_id)
+            .bind(claim.resource_id)
+            .bind(claim_id)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        log::info!(
+            "[Claim] 申领审核完成 | claim_id={}, resource_id={}, status={}, auto_rejected={}",
+            claim_id,
+            claim.resource_id,
+            status.as_str(),
+            auto_rejected.len()
+        );
+
+        // 通知失败不影响审核结果
+        let resource_title: String =
+            sqlx::query_scalar("SELECT title FROM resources WHERE id = $1")
+                .bind(claim.resource_id)
+                .fetch_optional(pool)
+                .await
+                .ok()
+                .flatten()
+                .unwrap_or_default();
+
+        if let Err(e) = NotificationService::create_claim_result_notification(
+            pool,
+            claim.resource_id,
+            &resource_title,
+            claim.applicant_id,
+            status == ClaimStatus::Approved,
+            request.comment.as_deref(),
+        )
+        .await
+        {
+            log::warn!(
+                "[Claim] 发送申领结果通知失败 | claim_id={}, error={}",
+                claim_id,
+                e
+            );
+        }
+
+        for applicant_id in &auto_rejected {
+            if let Err(e) = NotificationService::create_claim_result_notification(
+                pool,
+                claim.resource_id,
+                &resource_title,
+                *applicant_id,
+                false,
+                Some("该资源已确认其他作者"),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Claim] 发送申领结果通知失败 | resource_id={}, applicant_id={}, error={}",
+                    claim.resource_id,
+                    applicant_id,
+                    e
+                );
+            }
+        }
+
+        Ok(ClaimReviewOutcome {
+            claim_id,
+            resource_id: claim.resource_id,
+            applicant_id: claim.applicant_id,
+            status,
+            auto_rejected: auto_rejected.len(),
+        })
+    }
+
+    async fn find_claim(pool: &PgPool, claim_id: Uuid) -> Result<Claim, ResourceError> {
+        sqlx::query_as::<_, Claim>(
+            "SELECT resource_id, applicant_id, status FROM claims WHERE id = $1",
+        )
+        .bind(claim_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| ResourceError::NotFound("申领不存在".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{
+        cleanup_test_user, create_test_resource, create_test_user, test_pool,
+    };
+    use crate::models::UserRole;
+
+    fn claim_request() -> CreateClaimRequest {
+        CreateClaimRequest {
+            reason: "这份讲义是我整理的，附原始文件截图".to_string(),
+            proof_image_ids: Vec::new(),
+        }
+    }
+
+    async fn submit(
+        pool: &PgPool,
+        applicant_id: Uuid,
+        resource_id: Uuid,
+    ) -> Result<ClaimResponse, ResourceError> {
+        ClaimService::create_claim(pool, applicant_id, resource_id, &claim_request(), "").await
+    }
+
+    #[tokio::test]
+    async fn test_create_claim_rejects_duplicate_pending() {
+        let pool = test_pool().await;
+        let uploader = create_test_user(&pool, UserRole::User).await;
+        let applicant = create_test_user(&pool, UserRole::User).await;
+        let hash = Uuid::new_v4().simple().to_string();
+        let resource_id = create_test_resource(&pool, uploader.id, &hash, "approved").await;
+
+        // 并发提交只能成功一条，另一条由唯一索引拦截
+        let (a, b) = tokio::join!(
+            submit(&pool, applicant.id, resource_id),
+            submit(&pool, applicant.id, resource_id)
+        );
+        assert_eq!([&a, &b].iter().filter(|r| r.is_ok()).count(), 1);
+        assert!([&a, &b]
+            .iter()
+            .any(|r| matches!(r, Err(ResourceError::Conflict(_)))));
+
+        // 之后再提交由预检查拦截
+        assert!(matches!(
+            submit(&pool, applicant.id, resource_id).await,
+            Err(ResourceError::Conflict(_))
+        ));
+
+        // 撤回后可以重新提交
+        let claim = a.or(b).unwrap();
+        ClaimService::withdraw_claim(&pool, applicant.id, claim.id)
+            .await
+            .unwrap();
+        submit(&pool, applicant.id, resource_id).await.unwrap();
+
+        cleanup_test_user(&pool, uploader.id).await;
+        cleanup_test_user(&pool, applicant.id).await;
+    }
+
+    #[tokio::test]
+    async fn test_review_claim_approves_and_rejects_others() {
+        let pool = test_pool().await;
+        let uploader = create_test_user(&pool, UserRole::User).await;
+        let admin = create_test_user(&pool, UserRole::Admin).await;
+        let first = create_test_user(&pool, UserRole::User).await;
+        let second = create_test_user(&pool, UserRole::User).await;
+        let hash = Uuid::new_v4().simple().to_string();
+        let resource_id = create_test_resource(&pool, uploader.id, &hash, "approved").await;
+
+        let claim = submit(&pool, first.id, resource_id).await.unwrap();
+        let other = submit(&pool, second.id, resource_id).await.unwrap();
+
+        let approve = ReviewClaimRequest {
+            status: "approved".to_string(),
+            comment: None,
+        };
+        let outcome = ClaimService::review_claim(&pool, admin.id, claim.id, &approve)
+            .await
+            .unwrap();
+        assert_eq!(outcome.status, ClaimStatus::Approved);
+        assert_eq!(outcome.auto_rejected, 1);
+
+        let author_id: Option<Uuid> =
+            sqlx::query_scalar("SELECT author_id FROM resources WHERE id = $1")
+                .bind(resource_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(author_id, Some(first.id));
+
+        let other = ClaimService::get_claim(&pool, other.id, "").await.unwrap();
+        assert_eq!(other.status, "rejected");
+
+        // 已审核的申领不能再次审核，也不能撤回
+        assert!(matches!(
+            ClaimService::review_claim(&pool, admin.id, claim.id, &approve).await,
+            Err(ResourceError::Conflict(_))
+        ));
+        assert!(matches!(
+            ClaimService::withdraw_claim(&pool, first.id, claim.id).await,
+            Err(ResourceError::Conflict(_))
+        ));
+        // 作者本人不能再申领
+        assert!(matches!(
+            submit(&pool, first.id, resource_id).await,
+            Err(ResourceError::Conflict(_))
+        ));
+
+        cleanup_test_user(&pool, uploader.id).await;
+        for user in [&admin, &first, &second] {
+            cleanup_test_user(&pool, user.id).await;
+        }
+    }
+}
@@ -1,16 +1,21 @@
+use chrono::NaiveDateTime;
 use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::models::{
-    Comment, CommentListQuery, CommentListResponse, CommentResponse, CreateCommentRequest,
+    Comment, CommentEditHistoryResponse, CommentEditRecord, CommentListQuery, CommentListResponse,
+    CommentReportResponse, CommentResponse, CreateCommentRequest, LikeToggleResponse,
+    ReportCommentRequest, UpdateCommentRequest, MAX_COMMENT_DEPTH, MAX_COMMENT_MENTIONS,
 };
-use crate::services::{NotificationService, ResourceError};
+use crate::services::{AiService, ModerationProvider, NotificationService, ResourceError};
 
 pub struct CommentService;
 
 /// HTML 转义，防止 XSS 攻击
 /// 将特殊字符转换为 HTML 实体
-fn escape_html(input: &str) -> String {
+pub(crate) fn escape_html(input: &str) -> String {
     let mut result = String::with_capacity(input.len());
     for c in input.chars() {
         match c {
@@ -26,29 +31,186 @@ fn escape_html(input: &str) -> String {
     result
 }
 
+/// 评论列表查询结果（关联评论者和被回复用户）
+#[derive(Debug, sqlx::FromRow)]
+struct CommentRow {
+    id: Uuid,
+    resource_id: Uuid,
+    user_id: Uuid,
+    user_name: String,
+    user_avatar: Option<String>,
+    content: String,
+    audit_status: String,
+    created_at: NaiveDateTime,
+    parent_id: Option<Uuid>,
+    root_id: Option<Uuid>,
+    depth: i32,
+    reply_to_user_id: Option<Uuid>,
+    reply_to_user_name: Option<String>,
+    like_count: i32,
+    edited_at: Option<NaiveDateTime>,
+}
+
+/// 统一的时间格式（ISO 8601）
+fn format_time(time: NaiveDateTime) -> String {
+    time.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
+}
+
+impl From<CommentRow> for CommentResponse {
+    fn from(row: CommentRow) -> Self {
+        Self {
+            id: row.id,
+            resource_id: row.resource_id,
+            user_id: row.user_id,
+            user_name: row.user_name,
+            user_avatar: row.user_avatar,
+            content: row.content,
+            audit_status: row.audit_status,
+            created_at: format_time(row.created_at),
+            parent_id: row.parent_id,
+            root_id: row.root_id,
+            depth: row.depth,
+            reply_to_user_id: row.reply_to_user_id,
+            reply_to_user_name: row.reply_to_user_name,
+            like_count: row.like_count,
+            is_liked: false,
+            edited_at: row.edited_at.map(format_time),
+            replies: Vec::new(),
+        }
+    }
+}
+
+const COMMENT_SELECT: &str = r#"
+    SELECT
+        c.id,
+        c.resource_id,
+        c.user_id,
+        u.username AS user_name,
+        u.avatar_url AS user_avatar,
+        c.content,
+        c.audit_status,
+        c.created_at,
+        c.parent_id,
+        c.root_id,
+        c.depth,
+        c.reply_to_user_id,
+        ru.username AS reply_to_user_name,
+        c.like_count,
+        c.edited_at
+    FROM comments c
+    JOIN users u ON c.user_id = u.id
+    LEFT JOIN users ru ON c.reply_to_user_id = ru.id
+"#;
+
+/// 验证评论内容，返回去除首尾空白后的内容
+fn validate_content(content: &str) -> Result<&str, ResourceError> {
+    let content = content.trim();
+    if content.is_empty() {
+        return Err(ResourceError::ValidationError(
+            "评论内容不能为空".to_string(),
+        ));
+    }
+    if content.len() > 1000 {
+        return Err(ResourceError::ValidationError(
+            "评论内容不能超过1000字".to_string(),
+        ));
+    }
+    Ok(content)
+}
+
+/// 提取评论中 `@用户名` 形式的提醒（去重，最多 `MAX_COMMENT_MENTIONS` 个）
+///
+/// 用户名由字母、数字、下划线（含中文）组成；`@` 前面紧跟字母数字时
+/// （如邮箱地址）不视为提醒
+pub(crate) fn extract_mentions(content: &str) -> Vec<String> {
+    let is_name_char = |c: char| c.is_alphanumeric() || c == '_';
+    let chars: Vec<char> = content.chars().collect();
+    let mut mentions: Vec<String> = Vec::new();
+
+    let mut i = 0;
+    while i < chars.len() && mentions.len() < MAX_COMMENT_MENTIONS {
+        if chars[i] == '@' && (i == 0 || !is_name_char(chars[i - 1])) {
+            let name: String = chars[i + 1..]
+                .iter()
+                .take_while(|c| is_name_char(**c))
+                .collect();
+            let len = name.chars().count();
+            if (3..=50).contains(&len) && !mentions.contains(&name) {
+                mentions.push(name);
+            }
+            i += len + 1;
+        } else {
+            i += 1;
+        }
+    }
+
+    mentions
+}
+
+/// 将回复挂到各自的上级评论下
+///
+/// `roots` 保持传入顺序，回复按 `replies` 中的顺序（时间正序）排列；
+/// 上级评论未公开时（`hidden_parents` 记录未公开回复的上级评论），回复挂到最近的
+/// 公开祖先评论下，找不到时挂到顶层评论下；顶层评论也不在结果中的回复会被丢弃
+fn build_comment_tree(
+    roots: Vec<CommentResponse>,
+    replies: Vec<CommentResponse>,
+    hidden_parents: &HashMap<Uuid, Option<Uuid>>,
+) -> Vec<CommentResponse> {
+    let visible: HashSet<Uuid> = roots.iter().chain(replies.iter()).map(|c| c.id).collect();
+    let mut children: HashMap<Uuid, Vec<CommentResponse>> = HashMap::new();
+    for reply in replies {
+        let mut parent_id = reply.parent_id;
+        // 步数限制防止数据异常时出现环
+        for _ in 0..=hidden_parents.len() {
+            match parent_id {
+                Some(id) if !visible.contains(&id) => {
+                    parent_id = hidden_parents.get(&id).copied().flatten()
+                }
+                _ => break,
+            }
+        }
+        let parent_id = parent_id
+            .filter(|id| visible.contains(id))
+            .or(reply.root_id.filter(|id| visible.contains(id)));
+        if let Some(parent_id) = parent_id {
+            children.entry(parent_id).or_default().push(reply);
+        }
+    }
+
+    fn attach(
+        mut comment: CommentResponse,
+        children: &mut HashMap<Uuid, Vec<CommentResponse>>,
+    ) -> CommentResponse {
+        if let Some(replies) = children.remove(&comment.id) {
+            comment.replies = replies
+                .into_iter()
+                .map(|reply| attach(reply, children))
+                .collect();
+        }
+        comment
+    }
+
+    roots
+        .into_iter()
+        .map(|root| attach(root, &mut children))
+        .collect()
+}
+
 impl CommentService {
     /// 创建评论
+    ///
+    /// 指定 `parent_id` 时为回复；超过最大层级时回复挂在上级评论下，
+    /// 但仍记录实际被回复的用户
     pub async fn create_comment(
         pool: &PgPool,
+        moderation: &Arc<dyn ModerationProvider>,
         resource_id: Uuid,
         user_id: Uuid,
         request: CreateCommentRequest,
     ) -> Result<CommentResponse, ResourceError> {
         // 验证评论内容
-        let content = request.content.trim();
-        if content.is_empty() {
-            return Err(ResourceError::ValidationError(
-                "评论内容不能为空".to_string(),
-            ));
-        }
-        if content.len() > 1000 {
-            return Err(ResourceError::ValidationError(
-                "评论内容不能超过1000字".to_string(),
-            ));
-        }
-
-        // HTML 转义，防止 XSS 攻击
-        let content = escape_html(content);
+        let content = validate_content(&request.content)?;
 
         // 验证资源是否存在
         let resource_exists: bool =
@@ -65,24 +227,77 @@ impl CommentService {
             )));
         }
 
+        // 确定回复位置
+        let (parent_id, root_id, depth, reply_to_user_id) = match request.parent_id {
+            None => (None, None, 0, None),
+            Some(target_id) => {
+                let target = sqlx::query_as::<_, Comment>(
+                    "SELECT * FROM comments WHERE id = $1 AND audit_status = 'approved'",
+                )
+                .bind(target_id)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| ResourceError::DatabaseError(e.to_string()))?
+                .ok_or_else(|| ResourceError::NotFound("回复的评论不存在".to_string()))?;
+
+                if target.resource_id != resource_id {
+                    return Err(ResourceError::ValidationError(
+                        "回复的评论不属于该资源".to_string(),
+                    ));
+                }
+
+                let (parent_id, depth) = if target.depth >= MAX_COMMENT_DEPTH {
+                    (target.parent_id.unwrap_or(target.id), target.depth)
+                } else {
+                    (target.id, target.depth + 1)
+                };
+
+                (
+                    Some(parent_id),
+                    Some(target.root_id.unwrap_or(target.id)),
+                    depth,
+                    Some(target.user_id),
+                )
+            }
+        };
+
+        // 内容审核（审核原文），未通过的评论进入人工审核队列，暂不公开
+        let ai_result = AiService::audit_comment(moderation, content).await;
+        let audit_status = if ai_result.passed {
+            "approved"
+        } else {
+            "pending"
+        };
+
+        let mentions = extract_mentions(content);
+
+        // HTML 转义，防止 XSS 攻击
+        let content = escape_html(content);
+
         log::debug!(
-            "[CommentService] 开始创建评论: resource_id={}, user_id={}, content={}",
+            "[CommentService] 开始创建评论: resource_id={}, user_id={}, parent_id={:?}, content={}",
             resource_id,
             user_id,
+            parent_id,
             content
         );
 
-        // 直接插入不使用事务（简化排查）
         let comment = sqlx::query_as::<_, Comment>(
             r#"
-            INSERT INTO comments (resource_id, user_id, content)
-            VALUES ($1, $2, $3)
-            RETURNING id, resource_id, user_id, content, audit_status, created_at, updated_at
+            INSERT INTO comments
+                (resource_id, user_id, content, audit_status, parent_id, root_id, depth, reply_to_user_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING *
             "#,
         )
         .bind(resource_id)
         .bind(user_id)
         .bind(content)
+        .bind(audit_status)
+        .bind(parent_id)
+        .bind(root_id)
+        .bind(depth)
+        .bind(reply_to_user_id)
         .fetch_one(pool)
         .await
         .map_err(|e| {
@@ -92,144 +307,216 @@ impl CommentService {
 
         log::debug!("[CommentService] 评论插入成功: comment_id={}", comment.id);
 
-        // 获取用户信息
-        let (user_name, user_avatar) = match sqlx::query_as::<_, (String, Option<String>)>(
-            "SELECT username, avatar_url FROM users WHERE id = $1",
-        )
-        .bind(user_id)
-        .fetch_one(pool)
-        .await
-        {
-            Ok((name, avatar)) => (name, avatar),
-            Err(e) => {
-                log::warn!("[CommentService] 获取用户信息失败: {}", e);
-                ("未知用户".to_string(), None)
-            }
-        };
+        let sql = format!("{} WHERE c.id = $1", COMMENT_SELECT);
+        let response: CommentResponse = sqlx::query_as::<_, CommentRow>(&sql)
+            .bind(comment.id)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?
+            .into();
 
         log::debug!(
             "[CommentService] 评论创建完成: comment_id={}, user_name={}",
             comment.id,
-            user_name
+            response.user_name
         );
 
-        // 发送通知给资源上传者（如果不是评论自己的资源）
-        Self::notify_uploader_on_comment(pool, resource_id, user_id, &user_name).await;
+        // 待审核的评论在审核通过前不通知
+        if ai_result.passed {
+            Self::send_comment_notifications(pool, &response, &mentions).await;
+        }
 
-        Ok(CommentResponse {
-            id: comment.id,
-            resource_id: comment.resource_id,
-            user_id: comment.user_id,
-            user_name,
-            user_avatar,
-            content: comment.content,
-            created_at: comment
-                .created_at
-                .format("%Y-%m-%dT%H:%M:%S%.3fZ")
-                .to_string(),
-        })
+        Ok(response)
     }
 
-    /// 评论时通知资源上传者
-    async fn notify_uploader_on_comment(
+    /// 发送评论相关通知
+    ///
+    /// 依次通知被回复的用户、被 @ 的用户和资源作者（没有作者时为上传者），
+    /// 同一用户只通知一次，不给自己发通知
+    async fn send_comment_notifications(
         pool: &PgPool,
-        resource_id: Uuid,
-        commenter_id: Uuid,
-        commenter_name: &str,
+        comment: &CommentResponse,
+        mentions: &[String],
     ) {
-        // 获取资源上传者信息
-        let result = sqlx::query_as::<_, (Uuid, String, Option<Uuid>)>(
+        let resource = sqlx::query_as::<_, (Uuid, String, Option<Uuid>)>(
             "SELECT uploader_id, title, author_id FROM resources WHERE id = $1",
         )
-        .bind(resource_id)
+        .bind(comment.resource_id)
         .fetch_optional(pool)
         .await;
 
-        if let Ok(Some((uploader_id, resource_title, author_id))) = result {
-            // 优先通知作者（如果存在），否则通知上传者
-            let notify_user_id = author_id.unwrap_or(uploader_id);
+        let Ok(Some((uploader_id, resource_title, author_id))) = resource else {
+            return;
+        };
 
-            // 不给自己发通知
-            if notify_user_id != commenter_id {
-                if let Err(e) = NotificationService::create_comment_notification(
+        let mut notified: HashSet<Uuid> = HashSet::from([comment.user_id]);
+
+        if let Some(reply_to) = comment.reply_to_user_id {
+            if notified.insert(reply_to) {
+                if let Err(e) = NotificationService::create_reply_notification(
                     pool,
-                    resource_id,
+                    comment.resource_id,
                     &resource_title,
-                    notify_user_id,
-                    commenter_name,
+                    reply_to,
+                    &comment.user_name,
                 )
                 .await
                 {
-                    log::warn!("[CommentService] 发送评论通知失败: {}", e);
+                    log::warn!("[CommentService] 发送回复通知失败: {}", e);
                 }
             }
         }
+
+        if !mentions.is_empty() {
+            let mentioned: Vec<Uuid> = sqlx::query_scalar(
+                "SELECT id FROM users WHERE username = ANY($1) AND is_active = true",
+            )
+            .bind(mentions)
+            .fetch_all(pool)
+            .await
+            .unwrap_or_else(|e| {
+                log::warn!("[CommentService] 查询被提醒用户失败: {}", e);
+                Vec::new()
+            });
+
+            for user_id in mentioned {
+                if !notified.insert(user_id) {
+                    continue;
+                }
+                if let Err(e) = NotificationService::create_mention_notification(
+                    pool,
+                    comment.resource_id,
+                    &resource_title,
+                    user_id,
+                    &comment.user_name,
+                )
+                .await
+                {
+                    log::warn!("[CommentService] 发送提醒通知失败: {}", e);
+                }
+            }
+        }
+
+        // 优先通知作者（如果存在），否则通知上传者
+        let owner_id = author_id.unwrap_or(uploader_id);
+        if notified.insert(owner_id) {
+            if let Err(e) = NotificationService::create_comment_notification(
+                pool,
+                comment.resource_id,
+                &resource_title,
+                owner_id,
+                &comment.user_name,
+            )
+            .await
+            {
+                log::warn!("[CommentService] 发送评论通知失败: {}", e);
+            }
+        }
     }
 
     /// 获取评论列表
+    ///
+    /// 按顶层评论分页（最新在前），每个顶层评论附带全部已公开的回复
+    ///
+    /// 传入 `viewer_id` 时标记该用户已点赞的评论
     pub async fn get_comments(
         pool: &PgPool,
         resource_id: Uuid,
         query: CommentListQuery,
+        viewer_id: Option<Uuid>,
     ) -> Result<CommentListResponse, ResourceError> {
         let page = query.page.unwrap_or(1).max(1);
-        let per_page = query.per_page.unwrap_or(20).min(100);
+        let per_page = query.per_page.unwrap_or(20).clamp(1, 100);
         let offset = (page - 1) * per_page;
 
-        // 获取评论总数
-        let total = sqlx::query_scalar::<_, i64>(
-            "SELECT COUNT(*) FROM comments WHERE resource_id = $1 AND audit_status = 'approved'",
+        // 顶层评论数和评论总数（顶层评论未公开时整个讨论串都不展示，不计入总数）
+        let (total, total_comments) = sqlx::query_as::<_, (i64, i64)>(
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE c.parent_id IS NULL),
+                COUNT(*)
+            FROM comments c
+            LEFT JOIN comments r ON r.id = c.root_id
+            WHERE c.resource_id = $1 AND c.audit_status = 'approved'
+              AND (c.root_id IS NULL OR r.audit_status = 'approved')
+            "#,
         )
         .bind(resource_id)
         .fetch_one(pool)
         .await?;
 
-        // 获取评论列表
-        let rows = sqlx::query!(
-            r#"
-            SELECT
-                c.id,
-                c.resource_id,
-                c.user_id,
-                c.content,
-                c.created_at,
-                u.username as user_name
-            FROM comments c
-            JOIN users u ON c.user_id = u.id
-            WHERE c.resource_id = $1 AND c.audit_status = 'approved'
+        let sql = format!(
+            r#"{}
+            WHERE c.resource_id = $1 AND c.audit_status = 'approved' AND c.parent_id IS NULL
             ORDER BY c.created_at DESC
             LIMIT $2 OFFSET $3
             "#,
-            resource_id,
-            per_page,
-            offset
-        )
-        .fetch_all(pool)
-        .await?;
+            COMMENT_SELECT
+        );
+        let mut roots: Vec<CommentResponse> = sqlx::query_as::<_, CommentRow>(&sql)
+            .bind(resource_id)
+            .bind(per_page)
+            .bind(offset)
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(CommentResponse::from)
+            .collect();
 
-        let comments = rows
+        let root_ids: Vec<Uuid> = roots.iter().map(|c| c.id).collect();
+        let (mut replies, hidden_parents): (Vec<CommentResponse>, HashMap<Uuid, Option<Uuid>>) =
+            if root_ids.is_empty() {
+                (Vec::new(), HashMap::new())
+            } else {
+                let sql = format!(
+                    r#"{}
+                WHERE c.root_id = ANY($1) AND c.audit_status = 'approved'
+                ORDER BY c.created_at ASC
+                "#,
+                    COMMENT_SELECT
+                );
+                let replies = sqlx::query_as::<_, CommentRow>(&sql)
+                    .bind(&root_ids)
+                    .fetch_all(pool)
+                    .await?
+                    .into_iter()
+                    .map(CommentResponse::from)
+                    .collect();
+                // 未公开的回复不展示，但需要其上级评论把下面的公开回复挂到可见的位置
+                let hidden_parents = sqlx::query_as::<_, (Uuid, Option<Uuid>)>(
+                "SELECT id, parent_id FROM comments WHERE root_id = ANY($1) AND audit_status <> 'approved'",
+            )
+            .bind(&root_ids)
+            .fetch_all(pool)
+            .await?
             .into_iter()
-            .map(|row| CommentResponse {
-                id: row.id,
-                resource_id: row.resource_id,
-                user_id: row.user_id,
-                user_name: row.user_name,
-                user_avatar: None,
-                content: row.content,
-                created_at: row
-                    .created_at
-                    .map(|dt| dt.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string())
-                    .unwrap_or_else(|| {
-                        chrono::Local::now()
-                            .format("%Y-%m-%dT%H:%M:%S%.3fZ")
-                            .to_string()
-                    }),
-            })
             .collect();
+                (replies, hidden_parents)
+            };
+
+        if let Some(viewer_id) = viewer_id {
+            let comment_ids: Vec<Uuid> = roots.iter().chain(replies.iter()).map(|c| c.id).collect();
+            if !comment_ids.is_empty() {
+                let liked: HashSet<Uuid> = sqlx::query_scalar(
+                    "SELECT comment_id FROM comment_likes WHERE user_id = $1 AND comment_id = ANY($2)",
+                )
+                .bind(viewer_id)
+                .bind(&comment_ids)
+                .fetch_all(pool)
+                .await?
+                .into_iter()
+                .collect();
+
+                for comment in roots.iter_mut().chain(replies.iter_mut()) {
+                    comment.is_liked = liked.contains(&comment.id);
+                }
+            }
+        }
 
         Ok(CommentListResponse {
-            comments,
+            comments: build_comment_tree(roots, replies, &hidden_parents),
             total,
+            total_comments,
             page,
             per_page,
         })
@@ -240,9 +527,9 @@ impl CommentService {
         pool: &PgPool,
         comment_id: Uuid,
         user_id: Uuid,
-        is_admin: bool,
+        can_moderate: bool,
     ) -> Result<bool, ResourceError> {
-        // 检查评论是否存在且属于该用户（或用户是管理员）
+        // 检查评论是否存在且属于该用户（或用户可以管理评论）
         let comment = sqlx::query_as::<_, Comment>("SELECT * FROM comments WHERE id = $1")
             .bind(comment_id)
             .fetch_optional(pool)
@@ -254,7 +541,7 @@ impl CommentService {
         };
 
         // 检查权限
-        if comment.user_id != user_id && !is_admin {
+        if comment.user_id != user_id && !can_moderate {
             return Ok(false);
         }
 
@@ -266,4 +553,629 @@ impl CommentService {
 
         Ok(true)
     }
+
+    /// 编辑评论
+    ///
+    /// 仅作者可在发表后 `edit_window_minutes` 分钟内编辑，编辑前的内容保存到
+    /// comment_edits。新内容重新审核，未通过时评论回到待审核状态；
+    /// 已处于待审核（含因举报被隐藏）的评论不会因编辑而自动公开。
+    /// 编辑不会重新发送回复和 @ 提醒通知
+    pub async fn update_comment(
+        pool: &PgPool,
+        moderation: &Arc<dyn ModerationProvider>,
+        comment_id: Uuid,
+        user_id: Uuid,
+        request: UpdateCommentRequest,
+        edit_window_minutes: i32,
+    ) -> Result<CommentResponse, ResourceError> {
+        let content = validate_content(&request.content)?;
+
+        let ai_result = AiService::audit_comment(moderation, content).await;
+        let content = escape_html(content);
+
+        let mut tx = pool.begin().await?;
+
+        let (author_id, old_content, audit_status, editable) =
+            sqlx::query_as::<_, (Uuid, String, String, bool)>(
+                r#"
+                SELECT user_id, content, audit_status,
+                       created_at + make_interval(mins => $2) >= CURRENT_TIMESTAMP
+                FROM comments
+                WHERE id = $1
+                FOR UPDATE
+                "#,
+            )
+            .bind(comment_id)
+            .bind(edit_window_minutes)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| ResourceError::NotFound("评论不存在".to_string()))?;
+
+        if author_id != user_id {
+            return Err(ResourceError::Unauthorized(
+                "只能编辑自己的评论".to_string(),
+            ));
+        }
+        if audit_status == "rejected" {
+            return Err(ResourceError::ValidationError(
+                "未通过审核的评论不能编辑".to_string(),
+            ));
+        }
+        if !editable {
+            return Err(ResourceError::ValidationError(format!(
+                "评论发表超过{}分钟后不能编辑",
+                edit_window_minutes
+            )));
+        }
+
+        if content != old_content {
+            let new_status = if ai_result.passed {
+                audit_status.as_str()
+            } else {
+                "pending"
+            };
+
+            sqlx::query("INSERT INTO comment_edits (comment_id, content) VALUES ($1, $2)")
+                .bind(comment_id)
+                .bind(&old_content)
+                .execute(&mut *tx)
+                .await?;
+
+            sqlx::query(
+                r#"
+                UPDATE comments
+                SET content = $1, audit_status = $2, edited_at = CURRENT_TIMESTAMP,
+                    updated_at = CURRENT_TIMESTAMP
+                WHERE id = $3
+                "#,
+            )
+            .bind(&content)
+            .bind(new_status)
+            .bind(comment_id)
+            .execute(&mut *tx)
+            .await?;
+
+            log::info!(
+                "[CommentService] 评论已编辑 | comment_id={}, user_id={}, audit_status={}",
+                comment_id,
+                user_id,
+                new_status
+            );
+        }
+
+        tx.commit().await?;
+
+        let sql = format!("{} WHERE c.id = $1", COMMENT_SELECT);
+        let mut response: CommentResponse = sqlx::query_as::<_, CommentRow>(&sql)
+            .bind(comment_id)
+            .fetch_one(pool)
+            .await?
+            .into();
+        response.is_liked = Self::is_liked(pool, comment_id, user_id).await?;
+
+        Ok(response)
+    }
+
+    /// 获取评论编辑历史
+    ///
+    /// 已公开的评论所有人可见，其他评论仅作者和可以管理评论的用户可见
+    pub async fn get_edit_history(
+        pool: &PgPool,
+        comment_id: Uuid,
+        user_id: Uuid,
+        can_moderate: bool,
+    ) -> Result<CommentEditHistoryResponse, ResourceError> {
+        let comment = sqlx::query_as::<_, Comment>("SELECT * FROM comments WHERE id = $1")
+            .bind(comment_id)
+            .fetch_optional(pool)
+            .await?
+            .filter(|c| c.audit_status == "approved" || c.user_id == user_id || can_moderate)
+            .ok_or_else(|| ResourceError::NotFound("评论不存在".to_string()))?;
+
+        let edits = sqlx::query_as::<_, CommentEditRecord>(
+            r#"
+            SELECT id, content, created_at
+            FROM comment_edits
+            WHERE comment_id = $1
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(comment_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(CommentEditHistoryResponse {
+            comment_id,
+            content: comment.content,
+            edited_at: comment.edited_at,
+            edits,
+        })
+    }
+
+    /// 检查用户是否已点赞评论
+    async fn is_liked(
+        pool: &PgPool,
+        comment_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<bool, ResourceError> {
+        let liked: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM comment_likes WHERE comment_id = $1 AND user_id = $2)",
+        )
+        .bind(comment_id)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+        Ok(liked)
+    }
+
+    /// 点赞/取消点赞评论（切换状态）
+    pub async fn toggle_like(
+        pool: &PgPool,
+        comment_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<LikeToggleResponse, ResourceError> {
+        let mut tx = pool.begin().await?;
+
+        // 锁定评论行，保证 like_count 与点赞记录一致
+        let exists: Option<Uuid> = sqlx::query_scalar(
+            "SELECT id FROM comments WHERE id = $1 AND audit_status = 'approved' FOR UPDATE",
+        )
+        .bind(comment_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if exists.is_none() {
+            return Err(ResourceError::NotFound("评论不存在".to_string()));
+        }
+
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO comment_likes (comment_id, user_id)
+            VALUES ($1, $2)
+            ON CONFLICT (comment_id, user_id) DO NOTHING
+            "#,
+        )
+        .bind(comment_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+
+        if !inserted {
+            sqlx::query("DELETE FROM comment_likes WHERE comment_id = $1 AND user_id = $2")
+                .bind(comment_id)
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        let like_count: i32 = sqlx::query_scalar(
+            r#"
+            UPDATE comments
+            SET like_count = GREATEST(like_count + $1, 0)
+            WHERE id = $2
+            RETURNING like_count
+            "#,
+        )
+        .bind(if inserted { 1 } else { -1 })
+        .bind(comment_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(LikeToggleResponse {
+            is_liked: inserted,
+            like_count: like_count as i64,
+            message: if inserted {
+                "点赞成功".to_string()
+            } else {
+                "已取消点赞".to_string()
+            },
+        })
+    }
+
+    /// 举报评论
+    ///
+    /// 每个用户对同一评论只能有一条待处理举报，举报被处理后可再次举报；
+    /// 未处理的举报数达到 `hide_threshold` 时评论自动转为待审核（对外隐藏），由管理员在举报队列中处理
+    pub async fn report_comment(
+        pool: &PgPool,
+        comment_id: Uuid,
+        reporter_id: Uuid,
+        request: ReportCommentRequest,
+        hide_threshold: i32,
+    ) -> Result<CommentReportResponse, ResourceError> {
+        let reason = request.validate().map_err(ResourceError::ValidationError)?;
+        let detail = request
+            .detail
+            .as_deref()
+            .map(str::trim)
+            .filter(|d| !d.is_empty())
+            .map(escape_html);
+
+        let mut tx = pool.begin().await?;
+
+        let author_id: Uuid = sqlx::query_scalar(
+            "SELECT user_id FROM comments WHERE id = $1 AND audit_status = 'approved' FOR UPDATE",
+        )
+        .bind(comment_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| ResourceError::NotFound("评论不存在".to_string()))?;
+
+        if author_id == reporter_id {
+            return Err(ResourceError::ValidationError(
+                "不能举报自己的评论".to_string(),
+            ));
+        }
+
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO comment_reports (comment_id, reporter_id, reason, detail)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (comment_id, reporter_id) WHERE status = 'pending' DO NOTHING
+            "#,
+        )
+        .bind(comment_id)
+        .bind(reporter_id)
+        .bind(reason.as_str())
+        .bind(&detail)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        if inserted == 0 {
+            return Err(ResourceError::Conflict("你已经举报过该评论".to_string()));
+        }
+
+        let (report_count, audit_status) = sqlx::query_as::<_, (i32, String)>(
+            r#"
+            UPDATE comments
+            SET report_count = report_count + 1,
+                audit_status = CASE
+                    WHEN report_count + 1 >= $1 THEN 'pending'
+                    ELSE audit_status
+                END
+            WHERE id = $2
+            RETURNING report_count, audit_status
+            "#,
+        )
+        .bind(hide_threshold)
+        .bind(comment_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        let hidden = audit_status == "pending";
+        if hidden {
+            log::warn!(
+                "[CommentService] 评论举报数达到阈值，已自动隐藏 | comment_id={}, report_count={}",
+                comment_id,
+                report_count
+            );
+        }
+
+        Ok(CommentReportResponse {
+            report_count,
+            hidden,
+            message: if hidden {
+                "举报成功，评论已隐藏等待管理员处理".to_string()
+            } else {
+                "举报成功，管理员会尽快处理".to_string()
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{
+        cleanup_test_user, create_test_resource, create_test_user, test_pool,
+    };
+    use crate::models::UserRole;
+    use crate::services::{AdminService, ChainedModerationProvider};
+
+    fn moderation() -> Arc<dyn ModerationProvider> {
+        Arc::new(ChainedModerationProvider::new(vec![]))
+    }
+
+    async fn post(pool: &PgPool, resource_id: Uuid, user_id: Uuid, content: &str) -> Uuid {
+        let request = CreateCommentRequest {
+            content: content.to_string(),
+            parent_id: None,
+        };
+        CommentService::create_comment(pool, &moderation(), resource_id, user_id, request)
+            .await
+            .unwrap()
+            .id
+    }
+
+    async fn report(
+        pool: &PgPool,
+        comment_id: Uuid,
+        reporter_id: Uuid,
+        hide_threshold: i32,
+    ) -> Result<CommentReportResponse, ResourceError> {
+        let request = ReportCommentRequest {
+            reason: "spam".to_string(),
+            detail: None,
+        };
+        CommentService::report_comment(pool, comment_id, reporter_id, request, hide_threshold).await
+    }
+
+    #[tokio::test]
+    async fn test_update_comment_records_history() {
+        let pool = test_pool().await;
+        let author = create_test_user(&pool, UserRole::User).await;
+        let other = create_test_user(&pool, UserRole::User).await;
+        let hash = Uuid::new_v4().simple().to_string();
+        let resource_id = create_test_resource(&pool, author.id, &hash, "approved").await;
+        let comment_id = post(&pool, resource_id, author.id, "第一版").await;
+
+        let edit = |user_id: Uuid, content: &str, window: i32| {
+            let request = UpdateCommentRequest {
+                content: content.to_string(),
+            };
+            let pool = pool.clone();
+            async move {
+                CommentService::update_comment(
+                    &pool,
+                    &moderation(),
+                    comment_id,
+                    user_id,
+                    request,
+                    window,
+                )
+                .await
+            }
+        };
+
+        // 只有作者可以编辑
+        assert!(matches!(
+            edit(other.id, "别人改的", 15).await,
+            Err(ResourceError::Unauthorized(_))
+        ));
+
+        let updated = edit(author.id, "第二版 <b>", 15).await.unwrap();
+        assert_eq!(updated.content, "第二版 &lt;b&gt;");
+        assert!(updated.edited_at.is_some());
+
+        // 内容未变化时不产生编辑记录
+        edit(author.id, "第二版 <b>", 15).await.unwrap();
+
+        let history = CommentService::get_edit_history(&pool, comment_id, other.id, false)
+            .await
+            .unwrap();
+        assert_eq!(history.edits.len(), 1);
+        assert_eq!(history.edits[0].content, "第一版");
+
+        // 超过编辑时限后不能编辑
+        sqlx::query(
+            "UPDATE comments SET created_at = created_at - INTERVAL '1 hour' WHERE id = $1",
+        )
+        .bind(comment_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert!(matches!(
+            edit(author.id, "第三版", 15).await,
+            Err(ResourceError::ValidationError(_))
+        ));
+
+        cleanup_test_user(&pool, author.id).await;
+        cleanup_test_user(&pool, other.id).await;
+    }
+
+    #[tokio::test]
+    async fn test_toggle_like_keeps_count_in_sync() {
+        let pool = test_pool().await;
+        let author = create_test_user(&pool, UserRole::User).await;
+        let fan = create_test_user(&pool, UserRole::User).await;
+        let hash = Uuid::new_v4().simple().to_string();
+        let resource_id = create_test_resource(&pool, author.id, &hash, "approved").await;
+        let comment_id = post(&pool, resource_id, author.id, "点赞测试").await;
+
+        let liked = CommentService::toggle_like(&pool, comment_id, fan.id)
+            .await
+            .unwrap();
+        assert!(liked.is_liked);
+        assert_eq!(liked.like_count, 1);
+
+        let unliked = CommentService::toggle_like(&pool, comment_id, fan.id)
+            .await
+            .unwrap();
+        assert!(!unliked.is_liked);
+        assert_eq!(unliked.like_count, 0);
+
+        // 未公开的评论不能点赞
+        sqlx::query("UPDATE comments SET audit_status = 'pending' WHERE id = $1")
+            .bind(comment_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(matches!(
+            CommentService::toggle_like(&pool, comment_id, fan.id).await,
+            Err(ResourceError::NotFound(_))
+        ));
+
+        cleanup_test_user(&pool, author.id).await;
+        cleanup_test_user(&pool, fan.id).await;
+    }
+
+    #[tokio::test]
+    async fn test_report_comment_allows_one_pending_report() {
+        let pool = test_pool().await;
+        let author = create_test_user(&pool, UserRole::User).await;
+        let reporter = create_test_user(&pool, UserRole::User).await;
+        let admin = create_test_user(&pool, UserRole::Admin).await;
+        let hash = Uuid::new_v4().simple().to_string();
+        let resource_id = create_test_resource(&pool, author.id, &hash, "approved").await;
+        let comment_id = post(&pool, resource_id, author.id, "举报测试").await;
+
+        assert!(matches!(
+            report(&pool, comment_id, author.id, 3).await,
+            Err(ResourceError::ValidationError(_))
+        ));
+
+        let first = report(&pool, comment_id, reporter.id, 3).await.unwrap();
+        assert_eq!(first.report_count, 1);
+        assert!(!first.hidden);
+
+        // 未处理的举报只能有一条
+        assert!(matches!(
+            report(&pool, comment_id, reporter.id, 3).await,
+            Err(ResourceError::Conflict(_))
+        ));
+
+        // 管理员驳回举报后可以再次举报
+        let resolved =
+            AdminService::audit_comment(&pool, comment_id, "approved".to_string(), admin.id)
+                .await
+                .unwrap();
+        assert_eq!(resolved, 1);
+
+        let again = report(&pool, comment_id, reporter.id, 1).await.unwrap();
+        assert_eq!(again.report_count, 1);
+        assert!(again.hidden);
+
+        // 隐藏后不能继续举报
+        assert!(matches!(
+            report(&pool, comment_id, reporter.id, 1).await,
+            Err(ResourceError::NotFound(_))
+        ));
+
+        cleanup_test_user(&pool, author.id).await;
+        cleanup_test_user(&pool, reporter.id).await;
+        cleanup_test_user(&pool, admin.id).await;
+    }
+
+    #[test]
+    fn test_extract_mentions() {
+        assert_eq!(
+            extract_mentions("@alice 说得对，@张三_2024 也看看，再 @alice 一次"),
+            vec!["alice".to_string(), "张三_2024".to_string()]
+        );
+        // 邮箱、过短的用户名和单独的 @ 不算提醒
+        assert!(extract_mentions("联系 bob@example.com 或 @ab @").is_empty());
+        assert_eq!(extract_mentions("（@carol）"), vec!["carol".to_string()]);
+
+        let many: String = (0..20).map(|i| format!("@user{:02} ", i)).collect();
+        assert_eq!(extract_mentions(&many).len(), MAX_COMMENT_MENTIONS);
+    }
+
+    #[tokio::test]
+    async fn test_get_comments_keeps_replies_under_hidden_comment() {
+        let pool = test_pool().await;
+        let author = create_test_user(&pool, UserRole::User).await;
+        let hash = Uuid::new_v4().simple().to_string();
+        let resource_id = create_test_resource(&pool, author.id, &hash, "approved").await;
+
+        let reply = |parent_id: Uuid, content: &str| {
+            let request = CreateCommentRequest {
+                content: content.to_string(),
+                parent_id: Some(parent_id),
+            };
+            let pool = pool.clone();
+            async move {
+                CommentService::create_comment(
+                    &pool,
+                    &moderation(),
+                    resource_id,
+                    author.id,
+                    request,
+                )
+                .await
+                .unwrap()
+                .id
+            }
+        };
+        let root_id = post(&pool, resource_id, author.id, "顶层评论").await;
+        let middle_id = reply(root_id, "中间的回复").await;
+        let leaf_id = reply(middle_id, "最里层的回复").await;
+
+        // 中间的回复被举报隐藏后，下面的回复挂到顶层评论下
+        sqlx::query("UPDATE comments SET audit_status = 'pending' WHERE id = $1")
+            .bind(middle_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let list = CommentService::get_comments(
+            &pool,
+            resource_id,
+            CommentListQuery {
+                page: None,
+                per_page: None,
+            },
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(list.total, 1);
+        assert_eq!(list.total_comments, 2);
+        assert_eq!(list.comments.len(), 1);
+        let replies: Vec<Uuid> = list.comments[0].replies.iter().map(|c| c.id).collect();
+        assert_eq!(replies, vec![leaf_id]);
+
+        cleanup_test_user(&pool, author.id).await;
+    }
+
+    fn comment(id: u128, parent_id: Option<u128>) -> CommentResponse {
+        CommentResponse {
+            id: Uuid::from_u128(id),
+            resource_id: Uuid::nil(),
+            user_id: Uuid::nil(),
+            user_name: "user".to_string(),
+            user_avatar: None,
+            content: String::new(),
+            audit_status: "approved".to_string(),
+            created_at: String::new(),
+            parent_id: parent_id.map(Uuid::from_u128),
+            root_id: None,
+            depth: 0,
+            reply_to_user_id: None,
+            reply_to_user_name: None,
+            like_count: 0,
+            is_liked: false,
+            edited_at: None,
+            replies: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_build_comment_tree() {
+        let roots = vec![comment(2, None), comment(1, None)];
+        let replies = vec![
+            comment(10, Some(1)),
+            comment(11, Some(10)),
+            comment(12, Some(1)),
+            comment(20, Some(2)),
+            // 上级评论 98 未公开，挂到最近的公开祖先评论 10 下
+            comment(30, Some(98)),
+            // 上级评论 99 及其祖先都已不在，挂到顶层评论下
+            CommentResponse {
+                root_id: Some(Uuid::from_u128(2)),
+                ..comment(31, Some(99))
+            },
+            // 顶层评论也不在结果中
+            comment(32, Some(97)),
+        ];
+        let hidden_parents = HashMap::from([
+            (Uuid::from_u128(98), Some(Uuid::from_u128(10))),
+            (Uuid::from_u128(99), Some(Uuid::from_u128(96))),
+        ]);
+
+        let tree = build_comment_tree(roots, replies, &hidden_parents);
+        let ids =
+            |list: &[CommentResponse]| list.iter().map(|c| c.id.as_u128()).collect::<Vec<_>>();
+
+        assert_eq!(ids(&tree), vec![2, 1]);
+        assert_eq!(ids(&tree[0].replies), vec![20, 31]);
+        assert_eq!(ids(&tree[1].replies), vec![10, 12]);
+        assert_eq!(ids(&tree[1].replies[0].replies), vec![11, 30]);
+    }
 }
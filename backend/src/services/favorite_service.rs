@@ -1,10 +1,12 @@
+use actix_web::web::Bytes;
+use futures_util::{Stream, StreamExt};
 use sqlx::PgPool;
-use std::io::Write;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::pin::Pin;
 use uuid::Uuid;
 
-use crate::config::Config;
-use crate::services::{create_local_storage, ResourceService, StorageBackend, StorageBackendType};
+use crate::services::{FileService, ResourceService, StorageError, StorageRegistry};
+use crate::utils::ZipStreamWriter;
 
 /// 计算平均分辅助函数
 fn calc_avg(total: Option<i32>, count: Option<i32>) -> Option<f64> {
@@ -17,7 +19,8 @@ fn calc_avg(total: Option<i32>, count: Option<i32>) -> Option<f64> {
 use crate::models::{
     AddToFavoriteRequest, CheckResourceInFavoriteResponse, CreateFavoriteRequest,
     CreateFavoriteResponse, Favorite, FavoriteDetailResponse, FavoriteListItem,
-    FavoriteListResponse, FavoriteResourceItem, FavoriteResourceStats, UpdateFavoriteRequest,
+    FavoriteListResponse, FavoritePackResource, FavoriteResourceItem, FavoriteResourceStats,
+    UpdateFavoriteRequest,
 };
 use crate::services::ResourceError;
 
@@ -237,7 +240,7 @@ impl FavoriteService {
                 .iter()
                 .max()
                 .copied()
-                .unwrap_or(0) as i32;
+                .unwrap_or(0);
 
                 FavoriteResourceItem {
                     id: row.id,
@@ -485,13 +488,12 @@ impl FavoriteService {
         })
     }
 
-    /// 获取收藏夹中所有资源的文件路径（用于打包下载）
-    /// 返回: Vec<(资源ID, 标题, 文件路径, 资源类型, 文件大小, 存储类型)>
+    /// 获取收藏夹中所有资源的文件信息（用于打包下载）
     pub async fn get_favorite_resource_paths(
         pool: &PgPool,
         favorite_id: Uuid,
         user_id: Uuid,
-    ) -> Result<Vec<(Uuid, String, String, String, i64, String)>, ResourceError> {
+    ) -> Result<Vec<FavoritePackResource>, ResourceError> {
         // 检查收藏夹是否存在且属于当前用户
         let favorite =
             sqlx::query_as::<_, Favorite>("SELECT * FROM favorites WHERE id = $1 AND user_id = $2")
@@ -504,14 +506,22 @@ impl FavoriteService {
             return Err(ResourceError::NotFound("收藏夹不存在".to_string()));
         }
 
-        // 获取资源文件路径、标题、资源类型、文件大小和存储类型
-        let rows = sqlx::query_as::<_, (Uuid, String, String, String, i64, String)>(
+        // 获取资源文件路径、标题、资源类型、文件大小、存储类型以及清单所需的课程和教师
+        let rows = sqlx::query_as::<_, FavoritePackResource>(
             r#"
             SELECT r.id, r.title, r.file_path, r.resource_type, r.file_size,
-                   COALESCE(r.storage_type, 'local') as storage_type
+                   COALESCE(r.storage_type, 'local') as storage_type,
+                   r.course_name,
+                   (SELECT string_agg(t.name, '、' ORDER BY t.name) FROM teachers t
+                        INNER JOIN resource_teachers rt ON t.sn = rt.teacher_sn
+                        WHERE rt.resource_id = r.id) AS teacher_names,
+                   (SELECT string_agg(c.name, '、' ORDER BY c.name) FROM courses c
+                        INNER JOIN resource_courses rc ON c.sn = rc.course_sn
+                        WHERE rc.resource_id = r.id) AS course_names
             FROM favorite_resources fr
             JOIN resources r ON fr.resource_id = r.id
             WHERE fr.favorite_id = $1
+            ORDER BY fr.added_at ASC
             "#,
         )
         .bind(favorite_id)
@@ -522,17 +532,16 @@ impl FavoriteService {
     }
 
     /// 打包下载收藏夹资源
-    /// 返回 ZIP 文件的字节数据
-    /// 限制：最多 100 个文件，总大小不超过 500MB
+    /// 返回边读边压缩的 ZIP 数据流，不在内存中缓存整个压缩包，
+    /// 文件数和总大小不设上限（超出普通 ZIP 上限时自动使用 ZIP64）
     /// 支持混合存储：根据每个资源的实际 storage_type 选择存储后端
     pub async fn pack_favorite_resources(
         pool: &PgPool,
-        storage: &Arc<dyn StorageBackend>,
-        config: &Config,
+        storages: &StorageRegistry,
         favorite_id: Uuid,
         user_id: Uuid,
         favorite_name: &str,
-    ) -> Result<(Vec<u8>, String), ResourceError> {
+    ) -> Result<FavoritePackage, ResourceError> {
         // 获取资源文件信息（包含存储类型）
         let resources = Self::get_favorite_resource_paths(pool, favorite_id, user_id).await?;
 
@@ -540,197 +549,259 @@ impl FavoriteService {
             return Err(ResourceError::ValidationError("收藏夹为空".to_string()));
         }
 
-        // 检查文件数量限制
-        const MAX_FILES: usize = 100;
-        if resources.len() > MAX_FILES {
-            return Err(ResourceError::ValidationError(format!(
-                "收藏夹资源数量超过限制，最多支持 {} 个文件",
-                MAX_FILES
-            )));
-        }
+        let total_size: i64 = resources.iter().map(|r| r.file_size.unwrap_or(0)).sum();
 
-        // 计算总文件大小并检查限制
-        const MAX_TOTAL_SIZE: i64 = 500 * 1024 * 1024; // 500MB
-        let total_size: i64 = resources.iter().map(|(_, _, _, _, size, _)| *size).sum();
-        if total_size > MAX_TOTAL_SIZE {
-            return Err(ResourceError::ValidationError(format!(
-                "收藏夹资源总大小超过限制，最大支持 500MB，当前 {:.2}MB",
-                total_size as f64 / 1024.0 / 1024.0
-            )));
-        }
+        // 生成下载文件名
+        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+        let file_name = format!("{}_{}.zip", sanitize_zip_name(favorite_name), timestamp);
+        let file_count = resources.len();
+
+        // 后台任务逐个读取文件并写入通道；通道有界，客户端读取慢时自动等待，
+        // 客户端断开后发送失败，任务随即结束
+        let (tx, rx) = tokio::sync::mpsc::channel::<Result<Bytes, ResourceError>>(8);
+        let pool = pool.clone();
+        let storages = storages.clone();
+        let log_name = file_name.clone();
+        tokio::spawn(async move {
+            match Self::write_package(&pool, &storages, &resources, &tx).await {
+                Ok(skipped) => log::info!(
+                    "[Favorite] 打包下载完成 | file={}, files={}, skipped={}",
+                    log_name,
+                    resources.len(),
+                    skipped
+                ),
+                Err(e) => {
+                    log::warn!("[Favorite] 打包下载中止 | file={}, error={}", log_name, e);
+                    let _ = tx.send(Err(e)).await;
+                }
+            }
+        });
 
-        // 在内存中创建 ZIP 文件
-        let mut zip_buffer = Vec::new();
-        {
-            let mut zip_writer = zip::ZipWriter::new(std::io::Cursor::new(&mut zip_buffer));
-            let options = zip::write::FileOptions::default()
-                .compression_method(zip::CompressionMethod::Deflated)
-                .unix_permissions(0o644);
-
-            // 用于检测文件名冲突
-            let mut file_names: std::collections::HashMap<String, usize> =
-                std::collections::HashMap::new();
-
-            for (resource_id, title, file_path, resource_type, _, storage_type) in &resources {
-                // 根据存储类型选择正确的存储后端读取文件
-                let file_content = if storage_type == "oss" {
-                    // OSS 存储：使用主 storage（如果是 OSS 模式）或创建 OSS 存储
-                    if storage.backend_type() == StorageBackendType::Oss {
-                        match storage.read_file(file_path).await {
-                            Ok(content) => content,
-                            Err(e) => {
-                                log::warn!(
-                                    "读取 OSS 资源文件失败: resource_id={}, path={}, error={}",
-                                    resource_id, file_path, e
-                                );
-                                continue; // 跳过无法读取的文件
-                            }
-                        }
-                    } else {
-                        // 当前是 local 模式，但需要读取 OSS 文件
-                        // 创建临时 OSS 存储实例
-                        match crate::services::create_storage_backend(config) {
-                            Ok(oss_storage) if oss_storage.backend_type() == StorageBackendType::Oss => {
-                                match oss_storage.read_file(file_path).await {
-                                    Ok(content) => content,
-                                    Err(e) => {
-                                        log::warn!(
-                                            "读取 OSS 资源文件失败: resource_id={}, path={}, error={}",
-                                            resource_id, file_path, e
-                                        );
-                                        continue;
-                                    }
-                                }
-                            }
-                            _ => {
-                                log::warn!(
-                                    "无法创建 OSS 存储实例来读取资源: resource_id={}",
-                                    resource_id
-                                );
-                                continue;
-                            }
-                        }
-                    }
-                } else {
-                    // 本地存储：使用本地存储实例读取
-                    match create_local_storage(config) {
-                        Ok(local_storage) => match local_storage.read_file(file_path).await {
-                            Ok(content) => content,
-                            Err(e) => {
-                                log::warn!(
-                                    "读取本地资源文件失败: resource_id={}, path={}, error={}",
-                                    resource_id, file_path, e
-                                );
-                                continue; // 跳过无法读取的文件
-                            }
-                        },
-                        Err(e) => {
-                            log::error!("创建本地存储失败: error={}", e);
-                            continue;
-                        }
-                    }
-                };
-
-                // 生成安全的文件名 - 保留 Unicode 字符（包括中文），只替换文件系统不安全字符
-                let safe_title = title
-                    .chars()
-                    .map(|c| {
-                        // 文件系统不安全的字符: / \ ? % * : | " < > 和控制字符
-                        if c.is_control()
-                            || matches!(
-                                c,
-                                '/' | '\\' | '?' | '%' | '*' | ':' | '|' | '"' | '<' | '>'
-                            )
-                        {
-                            '_'
-                        } else {
-                            c
-                        }
-                    })
-                    .collect::<String>();
-
-                // 确定文件扩展名
-                let ext = match resource_type.as_str() {
-                    "web_markdown" => "md",
-                    "pdf" => "pdf",
-                    "ppt" => "ppt",
-                    "pptx" => "pptx",
-                    "doc" => "doc",
-                    "docx" => "docx",
-                    "txt" => "txt",
-                    "zip" => "zip",
-                    _ => "bin",
-                };
-
-                // 生成唯一的文件名
-                let base_name = format!("{}.{}", safe_title, ext);
-                let file_name = if let Some(count) = file_names.get(&base_name) {
-                    let new_count = count + 1;
-                    file_names.insert(base_name.clone(), new_count);
-                    format!("{}_{}.{}", safe_title, new_count, ext)
-                } else {
-                    file_names.insert(base_name.clone(), 1);
-                    base_name
-                };
+        let stream = futures_util::stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|item| (item, rx))
+        });
 
-                // 添加到 ZIP
-                if let Err(e) = zip_writer.start_file(&file_name, options) {
-                    log::warn!("添加文件到ZIP失败: {}, error={}", file_name, e);
+        Ok(FavoritePackage {
+            stream: Box::pin(stream),
+            file_name,
+            total_size,
+            file_count,
+        })
+    }
+
+    /// 按顺序写入所有资源、清单和跳过说明，返回跳过的文件数
+    async fn write_package(
+        pool: &PgPool,
+        storages: &StorageRegistry,
+        resources: &[FavoritePackResource],
+        tx: &PackSender,
+    ) -> Result<usize, ResourceError> {
+        let mut zip = ZipStreamWriter::new();
+        let zip_error =
+            |e: std::io::Error| ResourceError::FileError(format!("创建ZIP文件失败: {}", e));
+
+        // 用于检测文件名冲突
+        let mut file_names: HashMap<String, usize> = HashMap::new();
+        let mut manifest: Vec<(Option<String>, &FavoritePackResource, String)> = Vec::new();
+        let mut skipped: Vec<(&FavoritePackResource, String)> = Vec::new();
+
+        for resource in resources {
+            // 根据存储类型选择正确的存储后端
+            let backend = match storages.for_storage_type(Some(&resource.storage_type)) {
+                Ok(backend) => backend,
+                Err(e) => {
+                    log::warn!(
+                        "[Favorite] 打包时无法访问存储 | resource_id={}, error={}",
+                        resource.id,
+                        e
+                    );
+                    let reason = format!("无法访问 {} 存储", resource.storage_type);
+                    manifest.push((None, resource, format!("未打包：{}", reason)));
+                    skipped.push((resource, reason));
                     continue;
                 }
+            };
 
-                if let Err(e) = zip_writer.write_all(&file_content) {
-                    log::warn!("写入文件内容到ZIP失败: {}, error={}", file_name, e);
+            let mut file_stream = match backend.read_stream(&resource.file_path, None).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    log::warn!(
+                        "[Favorite] 打包时读取资源文件失败 | resource_id={}, path={}, error={}",
+                        resource.id,
+                        resource.file_path,
+                        e
+                    );
+                    let reason = match e {
+                        StorageError::NotFound(_) => "文件不存在".to_string(),
+                        _ => "文件读取失败".to_string(),
+                    };
+                    manifest.push((None, resource, format!("未打包：{}", reason)));
+                    skipped.push((resource, reason));
                     continue;
                 }
+            };
+
+            // 生成唯一的文件名
+            let safe_title = sanitize_zip_name(&resource.title);
+            let ext = FileService::get_extension_by_type(&resource.resource_type);
+            let base_name = format!("{}.{}", safe_title, ext);
+            let file_name = match file_names.get(&base_name).copied() {
+                Some(count) => {
+                    file_names.insert(base_name, count + 1);
+                    format!("{}_{}.{}", safe_title, count + 1, ext)
+                }
+                None => {
+                    file_names.insert(base_name.clone(), 1);
+                    base_name
+                }
+            };
 
-                log::debug!(
-                    "已添加文件到ZIP: {} ({} bytes)",
-                    file_name,
-                    file_content.len()
-                );
+            let size_hint = resource.file_size.map(|size| size.max(0) as u64);
+            send_chunk(
+                tx,
+                zip.start_entry(&file_name, size_hint).map_err(zip_error)?,
+            )
+            .await?;
 
-                // 增加资源下载计数
-                if let Err(e) = ResourceService::increment_downloads(pool, *resource_id).await {
-                    log::warn!(
-                        "增加资源下载计数失败: resource_id={}, error={}",
-                        resource_id, e
-                    );
+            // 文件头已经发出，读取中途失败时只能保留已写入的部分，并在清单中注明
+            let mut interrupted = None;
+            while let Some(chunk) = file_stream.next().await {
+                match chunk {
+                    Ok(bytes) => send_chunk(tx, zip.write(&bytes).map_err(zip_error)?).await?,
+                    Err(e) => {
+                        interrupted = Some(e);
+                        break;
+                    }
                 }
             }
+            send_chunk(tx, zip.finish_entry().map_err(zip_error)?).await?;
+
+            if let Some(e) = interrupted {
+                log::warn!(
+                    "[Favorite] 打包时读取资源文件中断 | resource_id={}, path={}, error={}",
+                    resource.id,
+                    resource.file_path,
+                    e
+                );
+                let reason = "读取中断，压缩包内文件不完整".to_string();
+                manifest.push((Some(file_name), resource, reason.clone()));
+                skipped.push((resource, reason));
+                continue;
+            }
 
-            // 完成 ZIP 文件
-            if let Err(e) = zip_writer.finish() {
-                return Err(ResourceError::FileError(format!("创建ZIP文件失败: {}", e)));
+            log::debug!("[Favorite] 已添加文件到ZIP | file={}", file_name);
+            manifest.push((Some(file_name), resource, "已打包".to_string()));
+
+            // 增加资源下载计数
+            if let Err(e) = ResourceService::increment_downloads(pool, resource.id).await {
+                log::warn!(
+                    "[Favorite] 增加资源下载计数失败 | resource_id={}, error={}",
+                    resource.id,
+                    e
+                );
             }
         }
 
-        // 生成下载文件名 - 保留 Unicode 字符（包括中文），只替换文件系统不安全字符
-        let safe_favorite_name = favorite_name
-            .chars()
-            .map(|c| {
-                // 文件系统不安全的字符: / \ ? % * : | " < > 和控制字符
-                if c.is_control()
-                    || matches!(
-                        c,
-                        '/' | '\\' | '?' | '%' | '*' | ':' | '|' | '"' | '<' | '>'
-                    )
-                {
-                    '_'
-                } else {
-                    c
-                }
-            })
-            .collect::<String>();
-        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-        let download_filename = format!("{}_{}.zip", safe_favorite_name, timestamp);
+        // 清单：带 BOM 的 CSV，Excel 可直接打开
+        let mut writer = csv::Writer::from_writer(Vec::from("\u{feff}".as_bytes()));
+        let csv_error = |e: csv::Error| ResourceError::FileError(format!("生成清单失败: {}", e));
+        writer
+            .write_record(["文件名", "标题", "课程", "教师", "资源ID", "状态"])
+            .map_err(csv_error)?;
+        for (file_name, resource, status) in &manifest {
+            let courses = resource
+                .course_names
+                .as_deref()
+                .or(resource.course_name.as_deref())
+                .unwrap_or_default();
+            writer
+                .write_record([
+                    file_name.as_deref().unwrap_or_default(),
+                    resource.title.as_str(),
+                    courses,
+                    resource.teacher_names.as_deref().unwrap_or_default(),
+                    resource.id.to_string().as_str(),
+                    status.as_str(),
+                ])
+                .map_err(csv_error)?;
+        }
+        let manifest_data = writer
+            .into_inner()
+            .map_err(|e| ResourceError::FileError(format!("生成清单失败: {}", e)))?;
+        let manifest_entry = zip
+            .add_entry(MANIFEST_NAME, &manifest_data)
+            .map_err(zip_error)?;
+        send_chunk(tx, manifest_entry).await?;
+
+        // 有文件未能完整打包时，额外写一份说明放在压缩包里告知用户
+        if !skipped.is_empty() {
+            let mut notice = format!(
+                "以下 {} 个资源未能完整打包，可在网站上单独下载：\r\n\r\n",
+                skipped.len()
+            );
+            for (resource, reason) in &skipped {
+                notice.push_str(&format!(
+                    "- {}（资源ID: {}）：{}\r\n",
+                    resource.title, resource.id, reason
+                ));
+            }
+            let notice_entry = zip
+                .add_entry(SKIPPED_NOTICE_NAME, notice.as_bytes())
+                .map_err(zip_error)?;
+            send_chunk(tx, notice_entry).await?;
+        }
 
-        log::info!(
-            "打包下载完成: {}, 文件大小: {} bytes",
-            download_filename,
-            zip_buffer.len()
-        );
+        send_chunk(tx, zip.finish().map_err(zip_error)?).await?;
+
+        Ok(skipped.len())
+    }
+}
+
+/// 打包下载的数据流
+pub type FavoritePackStream = Pin<Box<dyn Stream<Item = Result<Bytes, ResourceError>> + Send>>;
+
+type PackSender = tokio::sync::mpsc::Sender<Result<Bytes, ResourceError>>;
+
+/// 打包下载结果
+pub struct FavoritePackage {
+    pub stream: FavoritePackStream,
+    /// 下载文件名
+    pub file_name: String,
+    /// 资源文件原始总大小（压缩前）
+    pub total_size: i64,
+    pub file_count: usize,
+}
+
+/// 压缩包内的清单文件名
+const MANIFEST_NAME: &str = "清单.csv";
+/// 压缩包内的跳过说明文件名
+const SKIPPED_NOTICE_NAME: &str = "未能打包的文件.txt";
 
-        Ok((zip_buffer, download_filename))
+/// 发送一段 ZIP 数据，客户端断开时返回错误
+async fn send_chunk(tx: &PackSender, chunk: Bytes) -> Result<(), ResourceError> {
+    if chunk.is_empty() {
+        return Ok(());
     }
+    tx.send(Ok(chunk))
+        .await
+        .map_err(|_| ResourceError::FileError("客户端已断开连接".to_string()))
+}
+
+/// 生成安全的文件名 - 保留 Unicode 字符（包括中文），只替换文件系统不安全字符
+fn sanitize_zip_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            // 文件系统不安全的字符: / \ ? % * : | " < > 和控制字符
+            if c.is_control()
+                || matches!(
+                    c,
+                    '/' | '\\' | '?' | '%' | '*' | ':' | '|' | '"' | '<' | '>'
+                )
+            {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect()
 }
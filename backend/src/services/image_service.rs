@@ -31,6 +31,22 @@ impl std::fmt::Display for ImageError {
 
 impl std::error::Error for ImageError {}
 
+impl From<super::storage_quota_service::StorageQuotaError> for ImageError {
+    fn from(err: super::storage_quota_service::StorageQuotaError) -> Self {
+        match err {
+            super::storage_quota_service::StorageQuotaError::DatabaseError(msg) => {
+                ImageError::DatabaseError(msg)
+            }
+            super::storage_quota_service::StorageQuotaError::UserNotFound(msg) => {
+                ImageError::NotFound(msg)
+            }
+            super::storage_quota_service::StorageQuotaError::QuotaExceeded(msg) => {
+                ImageError::ValidationError(msg)
+            }
+        }
+    }
+}
+
 impl From<super::storage_service::StorageError> for ImageError {
     fn from(err: super::storage_service::StorageError) -> Self {
         match err {
@@ -97,23 +113,39 @@ impl ImageService {
 
         let storage_type = storage.backend_type().as_str().to_string();
         let image_id = Uuid::new_v4();
-        let image: Image = match sqlx::query_as::<_, Image>(
-            r#"
-            INSERT INTO images (id, uploader_id, file_path, original_name, file_size, mime_type, storage_type)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
-            RETURNING *
-            "#,
-        )
-        .bind(image_id)
-        .bind(user.id)
-        .bind(oss_key)
-        .bind(original_name)
-        .bind(file_size as i32)
-        .bind(detected_mime)
-        .bind(&storage_type)
-        .fetch_one(pool)
-        .await
-        {
+        let result: Result<Image, ImageError> = async {
+            let mut tx = pool
+                .begin()
+                .await
+                .map_err(|e| ImageError::DatabaseError(e.to_string()))?;
+            super::StorageQuotaService::lock_and_ensure_quota(&mut tx, user.id, file_size as i64)
+                .await?;
+            let image = sqlx::query_as::<_, Image>(
+                r#"
+                INSERT INTO images (id, uploader_id, file_path, original_name, file_size, mime_type, storage_type)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                RETURNING *
+                "#,
+            )
+            .bind(image_id)
+            .bind(user.id)
+            .bind(oss_key)
+            .bind(original_name)
+            .bind(file_size as i32)
+            .bind(detected_mime)
+            .bind(&storage_type)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| ImageError::DatabaseError(e.to_string()))?;
+            tx.commit()
+                .await
+                .map_err(|e| ImageError::DatabaseError(e.to_string()))?;
+            Ok(image)
+        }
+        .await;
+
+        // 超出配额或入库失败时删除已上传的文件，避免绕过配额占用存储
+        let image = match result {
             Ok(image) => image,
             Err(e) => {
                 if let Err(cleanup_err) = storage.delete_file(oss_key).await {
@@ -123,7 +155,7 @@ impl ImageService {
                         cleanup_err
                     );
                 }
-                return Err(ImageError::DatabaseError(e.to_string()));
+                return Err(e);
             }
         };
 
@@ -131,8 +163,8 @@ impl ImageService {
         let fallback_name = original_name.unwrap_or("image");
         Ok(UploadImageResponse {
             id: image.id,
-            url: image.get_public_url(&base_url),
-            markdown_link: image.get_markdown_link(&base_url, fallback_name),
+            url: image.get_public_url(base_url),
+            markdown_link: image.get_markdown_link(base_url, fallback_name),
             original_name: image.original_name,
             file_size: image.file_size,
             created_at: image.created_at,
@@ -179,6 +211,9 @@ impl ImageService {
             )));
         }
 
+        // 提前检查配额，避免超出配额时仍写入文件；入库时还会在事务内再检查一次
+        super::StorageQuotaService::ensure_quota(pool, user.id, file_data.len() as i64).await?;
+
         let image_id = Uuid::new_v4();
         let ext = file_extension.unwrap_or_else(|| "png".to_string());
         let storage_key = format!("images/{}.{}", image_id, ext);
@@ -188,27 +223,43 @@ impl ImageService {
             .save_file(&storage_key, file_data, detected_mime)
             .await?;
 
-        let image: Image = match sqlx::query_as::<_, Image>(
-            r#"
-            INSERT INTO images (id, uploader_id, file_path, original_name, file_size, mime_type, storage_type)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
-            RETURNING *
-            "#,
-        )
-        .bind(image_id)
-        .bind(user.id)
-        .bind(&file_path)
-        .bind(file_name)
-        .bind(file_size)
-        .bind(detected_mime)
-        .bind(&storage_type)
-        .fetch_one(pool)
-        .await {
+        let result: Result<Image, ImageError> = async {
+            let mut tx = pool
+                .begin()
+                .await
+                .map_err(|e| ImageError::DatabaseError(e.to_string()))?;
+            super::StorageQuotaService::lock_and_ensure_quota(&mut tx, user.id, file_size as i64)
+                .await?;
+            let image = sqlx::query_as::<_, Image>(
+                r#"
+                INSERT INTO images (id, uploader_id, file_path, original_name, file_size, mime_type, storage_type)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                RETURNING *
+                "#,
+            )
+            .bind(image_id)
+            .bind(user.id)
+            .bind(&file_path)
+            .bind(file_name)
+            .bind(file_size)
+            .bind(detected_mime)
+            .bind(&storage_type)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| ImageError::DatabaseError(e.to_string()))?;
+            tx.commit()
+                .await
+                .map_err(|e| ImageError::DatabaseError(e.to_string()))?;
+            Ok(image)
+        }
+        .await;
+
+        let image = match result {
             Ok(img) => img,
             Err(e) => {
-                // 数据库插入失败时清理已保存的文件
+                // 超出配额或数据库插入失败时清理已保存的文件
                 log::warn!(
-                    "[Image] 数据库插入失败，清理文件 | image_id={}, path={}, error={}",
+                    "[Image] 图片入库失败，清理文件 | image_id={}, path={}, error={}",
                     image_id,
                     file_path,
                     e
@@ -220,7 +271,7 @@ impl ImageService {
                         cleanup_err
                     );
                 }
-                return Err(ImageError::DatabaseError(e.to_string()));
+                return Err(e);
             }
         };
 
@@ -296,7 +347,7 @@ impl ImageService {
     pub async fn delete_image(
         pool: &PgPool,
         user: &CurrentUser,
-        storage: &Arc<dyn super::StorageBackend>,
+        storages: &super::StorageRegistry,
         image_id: Uuid,
     ) -> Result<(), ImageError> {
         let image: Image = sqlx::query_as::<_, Image>("SELECT * FROM images WHERE id = $1")
@@ -306,18 +357,28 @@ impl ImageService {
             .map_err(|e| ImageError::DatabaseError(e.to_string()))?
             .ok_or_else(|| ImageError::NotFound(format!("图片 {} 不存在", image_id)))?;
 
-        if image.uploader_id != user.id && user.role != crate::models::UserRole::Admin {
+        if image.uploader_id != user.id && !user.is_admin() {
             return Err(ImageError::Unauthorized("没有权限删除此图片".to_string()));
         }
 
-        storage.delete_file(&image.file_path).await?;
+        let storage = storages.for_storage_type(image.storage_type.as_deref())?;
 
+        // 先删除记录再删除文件，文件删除失败时留下的孤儿文件由存储一致性检查清理
         sqlx::query("DELETE FROM images WHERE id = $1")
             .bind(image_id)
             .execute(pool)
             .await
             .map_err(|e| ImageError::DatabaseError(e.to_string()))?;
 
+        if let Err(e) = storage.delete_file(&image.file_path).await {
+            log::warn!(
+                "[Image] 删除图片文件失败 | image_id={}, path={}, error={}",
+                image_id,
+                image.file_path,
+                e
+            );
+        }
+
         Ok(())
     }
 
@@ -0,0 +1,398 @@
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::transport::smtp::extension::ClientId;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::Config;
+
+/// 邮件发送错误类型
+#[derive(Debug)]
+pub enum MailError {
+    Config(String),
+    Connection(String),
+    Protocol(String),
+}
+
+impl std::fmt::Display for MailError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MailError::Config(msg) => write!(f, "邮件配置错误: {}", msg),
+            MailError::Connection(msg) => write!(f, "邮件服务器连接失败: {}", msg),
+            MailError::Protocol(msg) => write!(f, "邮件发送失败: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for MailError {}
+
+pub type MailFuture<'a> = Pin<Box<dyn Future<Output = Result<(), MailError>> + Send + 'a>>;
+
+/// 待发送的邮件（纯文本）
+#[derive(Debug, Clone)]
+pub struct MailMessage {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// 邮件发送方
+pub trait Mailer: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn send<'a>(&'a self, message: &'a MailMessage) -> MailFuture<'a>;
+}
+
+/// 根据配置创建邮件发送方
+/// MAIL_PROVIDER 可选 log（写入日志，开发环境）、smtp（SMTP 服务器）
+pub fn create_mailer(config: &Config) -> Result<Arc<dyn Mailer>, MailError> {
+    match config.mail_provider.as_str() {
+        "log" => Ok(Arc::new(LogMailer)),
+        "smtp" => Ok(Arc::new(SmtpMailer::from_config(config)?)),
+        other => Err(MailError::Config(format!(
+            "未知的邮件发送方: {}（可选 log、smtp）",
+            other
+        ))),
+    }
+}
+
+/// 只把邮件写入日志，不真正发送（开发环境使用）
+pub struct LogMailer;
+
+impl Mailer for LogMailer {
+    fn name(&self) -> &'static str {
+        "log"
+    }
+
+    fn send<'a>(&'a self, message: &'a MailMessage) -> MailFuture<'a> {
+        Box::pin(async move {
+            log::info!(
+                "[Mail] 邮件未实际发送（MAIL_PROVIDER=log） | to={}, subject={}\n{}",
+                message.to,
+                message.subject,
+                message.body
+            );
+            Ok(())
+        })
+    }
+}
+
+/// SMTP 连接加密方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpSecurity {
+    /// 明文连接（仅用于本地测试的收件服务，不能配置认证信息）
+    None,
+    /// 明文连接后通过 STARTTLS 升级（通常为 587 端口）
+    StartTls,
+    /// 直接建立 TLS 连接（通常为 465 端口）
+    Tls,
+}
+
+impl SmtpSecurity {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "none" => Some(SmtpSecurity::None),
+            "starttls" => Some(SmtpSecurity::StartTls),
+            "tls" => Some(SmtpSecurity::Tls),
+            _ => None,
+        }
+    }
+}
+
+/// 通过 SMTP 服务器发送邮件
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    timeout: Duration,
+}
+
+impl SmtpMailer {
+    pub fn new(
+        host: &str,
+        port: u16,
+        security: SmtpSecurity,
+        credentials: Option<(String, String)>,
+        from: &str,
+        timeout: Duration,
+    ) -> Result<Self, MailError> {
+        let host = host.trim();
+        if host.is_empty() {
+            return Err(MailError::Config("SMTP_HOST 不能为空".to_string()));
+        }
+        let from: Mailbox = from
+            .trim()
+            .parse()
+            .map_err(|_| MailError::Config("MAIL_FROM 格式不正确".to_string()))?;
+        // 认证信息不能通过明文连接发送
+        if credentials.is_some() && security == SmtpSecurity::None {
+            return Err(MailError::Config(
+                "SMTP_SECURITY=none 时不能配置 SMTP_USERNAME 和 SMTP_PASSWORD".to_string(),
+            ));
+        }
+
+        let builder = match security {
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host),
+            SmtpSecurity::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host),
+            SmtpSecurity::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(
+                host,
+            )),
+        }
+        .map_err(|e| MailError::Config(format!("无效的 SMTP_HOST: {} {}", host, e)))?;
+
+        let mut builder = builder
+            .port(port)
+            .timeout(Some(timeout))
+            .hello_name(ClientId::Domain("shareustc".to_string()));
+        if let Some((username, password)) = credentials {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from,
+            timeout,
+        })
+    }
+
+    pub fn from_config(config: &Config) -> Result<Self, MailError> {
+        let host = config
+            .smtp_host
+            .as_deref()
+            .ok_or_else(|| MailError::Config("使用 smtp 发送邮件需要配置 SMTP_HOST".to_string()))?;
+        let security = SmtpSecurity::parse(&config.smtp_security).ok_or_else(|| {
+            MailError::Config(format!(
+                "未知的 SMTP_SECURITY: {}（可选 none、starttls、tls）",
+                config.smtp_security
+            ))
+        })?;
+        let credentials = match (&config.smtp_username, &config.smtp_password) {
+            (Some(username), Some(password)) => Some((username.clone(), password.clone())),
+            (None, None) => None,
+            _ => {
+                return Err(MailError::Config(
+                    "SMTP_USERNAME 和 SMTP_PASSWORD 需要同时配置".to_string(),
+                ))
+            }
+        };
+
+        Self::new(
+            host,
+            config.smtp_port,
+            security,
+            credentials,
+            &config.mail_from,
+            Duration::from_secs(config.smtp_timeout_secs),
+        )
+    }
+
+    /// 生成纯文本邮件
+    fn build_message(&self, message: &MailMessage) -> Result<Message, MailError> {
+        let to: Mailbox =
+            message.to.trim().parse().map_err(|_| {
+                MailError::Protocol(format!("收件人地址格式不正确: {}", message.to))
+            })?;
+
+        Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(message.subject.as_str())
+            .header(ContentType::TEXT_PLAIN)
+            .body(message.body.clone())
+            .map_err(|e| MailError::Protocol(e.to_string()))
+    }
+}
+
+impl Mailer for SmtpMailer {
+    fn name(&self) -> &'static str {
+        "smtp"
+    }
+
+    fn send<'a>(&'a self, message: &'a MailMessage) -> MailFuture<'a> {
+        Box::pin(async move {
+            if [&message.to, &message.subject]
+                .iter()
+                .any(|v| v.contains('\r') || v.contains('\n'))
+            {
+                return Err(MailError::Protocol("收件人或主题包含换行符".to_string()));
+            }
+            let email = self.build_message(message)?;
+
+            let result = tokio::time::timeout(self.timeout, self.transport.send(email))
+                .await
+                .map_err(|_| {
+                    MailError::Connection(format!("{}秒内未完成发送", self.timeout.as_secs()))
+                })?;
+
+            match result {
+                Ok(_) => Ok(()),
+                // 服务器返回的 4xx/5xx 错误
+                Err(e) if e.is_transient() || e.is_permanent() => {
+                    Err(MailError::Protocol(e.to_string()))
+                }
+                Err(e) => Err(MailError::Connection(e.to_string())),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    use tokio::sync::oneshot;
+
+    /// 本地 SMTP 收件服务：按顺序回复 `replies`，收到的全部内容通过 channel 返回
+    async fn smtp_sink(replies: Vec<&'static str>) -> (u16, oneshot::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (tx, rx) = oneshot::channel();
+
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut conn = BufReader::new(socket);
+            let mut transcript = String::new();
+            conn.get_mut()
+                .write_all(b"220 sink ESMTP ready\r\n")
+                .await
+                .unwrap();
+
+            let mut in_data = false;
+            let mut replies = replies.into_iter();
+            loop {
+                let mut line = String::new();
+                if conn.read_line(&mut line).await.unwrap_or(0) == 0 {
+                    break;
+                }
+                transcript.push_str(&line);
+
+                let reply = if in_data {
+                    if line != ".\r\n" {
+                        continue;
+                    }
+                    in_data = false;
+                    replies.next()
+                } else {
+                    in_data = line.starts_with("DATA");
+                    replies.next()
+                };
+                let Some(reply) = reply else { break };
+                conn.get_mut().write_all(reply.as_bytes()).await.unwrap();
+                if reply.starts_with("221") || reply.starts_with('5') {
+                    break;
+                }
+            }
+            let _ = tx.send(transcript);
+        });
+
+        (port, rx)
+    }
+
+    fn test_message() -> MailMessage {
+        MailMessage {
+            to: "alice@example.com".to_string(),
+            subject: "重置密码".to_string(),
+            body: "点击链接重置密码：\nhttp://localhost/reset?token=abc\n.\n".to_string(),
+        }
+    }
+
+    fn plain_mailer(port: u16) -> SmtpMailer {
+        SmtpMailer::new(
+            "127.0.0.1",
+            port,
+            SmtpSecurity::None,
+            None,
+            "ShareUSTC <noreply@example.com>",
+            Duration::from_secs(5),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_smtp_mailer_with_sink() {
+        let (port, transcript_rx) = smtp_sink(vec![
+            "250-sink\r\n250 8BITMIME\r\n",
+            "250 sender ok\r\n",
+            "250 recipient ok\r\n",
+            "354 go ahead\r\n",
+            "250 queued\r\n",
+            "221 bye\r\n",
+        ])
+        .await;
+
+        plain_mailer(port).send(&test_message()).await.unwrap();
+
+        let transcript = transcript_rx.await.unwrap();
+        assert!(transcript.starts_with("EHLO shareustc\r\n"));
+        assert!(!transcript.contains("AUTH"));
+        assert!(transcript.contains("MAIL FROM:<noreply@example.com>"));
+        assert!(transcript.contains("RCPT TO:<alice@example.com>"));
+        assert!(transcript.contains("To: alice@example.com\r\n"));
+        assert!(transcript
+            .to_ascii_lowercase()
+            .contains("subject: =?utf-8?"));
+        assert!(transcript.ends_with("QUIT\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_smtp_mailer_rejected_recipient() {
+        let (port, _transcript_rx) = smtp_sink(vec![
+            "250 sink\r\n",
+            "250 sender ok\r\n",
+            "550 no such user\r\n",
+        ])
+        .await;
+
+        let err = plain_mailer(port).send(&test_message()).await.unwrap_err();
+        assert!(matches!(err, MailError::Protocol(msg) if msg.contains("550")));
+    }
+
+    #[test]
+    fn test_smtp_mailer_refuses_plaintext_auth() {
+        let credentials = Some(("mailer".to_string(), "secret".to_string()));
+        let result = SmtpMailer::new(
+            "127.0.0.1",
+            25,
+            SmtpSecurity::None,
+            credentials.clone(),
+            "noreply@example.com",
+            Duration::from_secs(5),
+        );
+        assert!(matches!(result, Err(MailError::Config(_))));
+
+        let result = SmtpMailer::new(
+            "smtp.example.com",
+            587,
+            SmtpSecurity::StartTls,
+            credentials,
+            "not an address",
+            Duration::from_secs(5),
+        );
+        assert!(matches!(result, Err(MailError::Config(_))));
+    }
+
+    #[tokio::test]
+    async fn test_smtp_mailer_requires_starttls() {
+        // 服务器不支持 STARTTLS 时不能降级为明文，也不能发送认证信息
+        let (port, transcript_rx) = smtp_sink(vec!["250-sink\r\n250 AUTH PLAIN\r\n"]).await;
+
+        let mailer = SmtpMailer::new(
+            "127.0.0.1",
+            port,
+            SmtpSecurity::StartTls,
+            Some(("mailer".to_string(), "secret".to_string())),
+            "noreply@example.com",
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        let err = mailer.send(&test_message()).await.unwrap_err();
+        assert!(matches!(err, MailError::Connection(_)));
+
+        let transcript = transcript_rx.await.unwrap();
+        assert!(!transcript.contains("AUTH"));
+        assert!(!transcript.contains("MAIL FROM"));
+    }
+}
@@ -0,0 +1,423 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::models::{MfaEnrollmentResponse, MfaRecoveryCodesResponse, MfaStatusResponse};
+use crate::services::{ResourceError, SessionService};
+use crate::utils::{
+    generate_recovery_code, generate_totp_secret, hash_one_time_token, normalize_recovery_code,
+    totp_provisioning_uri, verify_totp, TotpSecretCipher,
+};
+
+/// 每次生成的恢复码数量
+const RECOVERY_CODE_COUNT: usize = 10;
+
+/// 两步验证（TOTP）服务
+pub struct MfaService;
+
+/// 第二步验证使用的凭据
+#[derive(Debug, Clone, Copy)]
+pub enum MfaCredential<'a> {
+    /// 身份验证器 App 生成的动态码
+    Code(&'a str),
+    /// 一次性恢复码
+    RecoveryCode(&'a str),
+}
+
+impl MfaService {
+    /// 按配置创建 TOTP 密钥的加解密器（未配置 TOTP_ENCRYPTION_KEY 时使用 JWT_SECRET）
+    pub fn secret_cipher(config: &Config) -> TotpSecretCipher {
+        TotpSecretCipher::new(
+            config
+                .totp_encryption_key
+                .as_deref()
+                .unwrap_or(&config.jwt_secret),
+        )
+    }
+
+    /// 用户是否已启用两步验证
+    pub async fn is_enabled(pool: &PgPool, user_id: Uuid) -> Result<bool, ResourceError> {
+        sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM user_totp WHERE user_id = $1 AND enabled_at IS NOT NULL)",
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))
+    }
+
+    /// 获取两步验证状态
+    ///
+    /// `required` 表示账号拥有管理权限，必须启用两步验证
+    pub async fn get_status(
+        pool: &PgPool,
+        user_id: Uuid,
+        required: bool,
+    ) -> Result<MfaStatusResponse, ResourceError> {
+        let enabled = Self::is_enabled(pool, user_id).await?;
+        let recovery_codes_remaining: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM mfa_recovery_codes WHERE user_id = $1 AND used_at IS NULL",
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        Ok(MfaStatusResponse {
+            enabled,
+            required,
+            recovery_codes_remaining,
+        })
+    }
+
+    /// 开始绑定身份验证器：生成新密钥，验证首个动态码后才启用
+    pub async fn begin_enrollment(
+        pool: &PgPool,
+        config: &Config,
+        user_id: Uuid,
+        username: &str,
+    ) -> Result<MfaEnrollmentResponse, ResourceError> {
+        if Self::is_enabled(pool, user_id).await? {
+            return Err(ResourceError::Conflict(
+                "已启用两步验证，如需更换身份验证器请先关闭".to_string(),
+            ));
+        }
+
+        let secret = generate_totp_secret();
+        sqlx::query(
+            r#"
+            INSERT INTO user_totp (user_id, secret, enabled_at, last_used_step)
+            VALUES ($1, $2, NULL, NULL)
+            ON CONFLICT (user_id) DO UPDATE SET
+                secret = EXCLUDED.secret,
+                enabled_at = NULL,
+                last_used_step = NULL,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(user_id)
+        .bind(Self::secret_cipher(config).encrypt(&secret))
+        .execute(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        log::info!("[MFA] 开始绑定身份验证器 | user_id={}", user_id);
+
+        Ok(MfaEnrollmentResponse {
+            otpauth_url: totp_provisioning_uri(&config.totp_issuer, username, &secret),
+            secret,
+        })
+    }
+
+    /// 验证首个动态码并启用两步验证，返回恢复码
+    ///
+    /// 当前会话随之标记为已完成两步验证
+    pub async fn confirm_enrollment(
+        pool: &PgPool,
+        cipher: &TotpSecretCipher,
+        user_id: Uuid,
+        session_id: Uuid,
+        code: &str,
+    ) -> Result<MfaRecoveryCodesResponse, ResourceError> {
+        let pending: Option<(String, Option<chrono::NaiveDateTime>)> =
+            sqlx::query_as("SELECT secret, enabled_at FROM user_totp WHERE user_id = $1")
+                .bind(user_id)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        let secret = match pending {
+            Some((_, Some(_))) => {
+                return Err(ResourceError::Conflict("已启用两步验证".to_string()));
+            }
+            Some((secret, None)) => cipher
+                .decrypt(&secret)
+                .map_err(ResourceError::DatabaseError)?,
+            None => {
+                return Err(ResourceError::ValidationError(
+                    "请先开始绑定身份验证器".to_string(),
+                ));
+            }
+        };
+
+        let step = verify_totp(&secret, code, chrono::Utc::now().timestamp())
+            .map_err(ResourceError::ValidationError)?
+            .ok_or_else(|| ResourceError::ValidationError("动态码错误".to_string()))?;
+
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            UPDATE user_totp
+            SET enabled_at = CURRENT_TIMESTAMP, last_used_step = $2, updated_at = CURRENT_TIMESTAMP
+            WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(step)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        let recovery_codes = Self::replace_recovery_codes(&mut tx, user_id).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        SessionService::mark_mfa_verified(pool, session_id, user_id)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        log::info!("[MFA] 已启用两步验证 | user_id={}", user_id);
+
+        Ok(MfaRecoveryCodesResponse { recovery_codes })
+    }
+
+    /// 关闭两步验证（需要当前动态码），拥有管理权限的账号不能关闭
+    pub async fn disable(
+        pool: &PgPool,
+        cipher: &TotpSecretCipher,
+        user_id: Uuid,
+        required: bool,
+        code: &str,
+    ) -> Result<(), ResourceError> {
+        if required {
+            return Err(ResourceError::Unauthorized(
+                "拥有管理权限的账号必须启用两步验证".to_string(),
+            ));
+        }
+        if !Self::verify(pool, cipher, user_id, MfaCredential::Code(code)).await? {
+            return Err(ResourceError::ValidationError("动态码错误".to_string()));
+        }
+
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        sqlx::query("DELETE FROM user_totp WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        sqlx::query("DELETE FROM mfa_recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        log::info!("[MFA] 已关闭两步验证 | user_id={}", user_id);
+        Ok(())
+    }
+
+    /// 重新生成恢复码（需要当前动态码），旧恢复码全部作废
+    pub async fn regenerate_recovery_codes(
+        pool: &PgPool,
+        cipher: &TotpSecretCipher,
+        user_id: Uuid,
+        code: &str,
+    ) -> Result<MfaRecoveryCodesResponse, ResourceError> {
+        if !Self::is_enabled(pool, user_id).await? {
+            return Err(ResourceError::ValidationError(
+                "尚未启用两步验证".to_string(),
+            ));
+        }
+        if !Self::verify(pool, cipher, user_id, MfaCredential::Code(code)).await? {
+            return Err(ResourceError::ValidationError("动态码错误".to_string()));
+        }
+
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+        let recovery_codes = Self::replace_recovery_codes(&mut tx, user_id).await?;
+        tx.commit()
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        log::info!("[MFA] 已重新生成恢复码 | user_id={}", user_id);
+        Ok(MfaRecoveryCodesResponse { recovery_codes })
+    }
+
+    /// 校验第二步验证凭据
+    ///
+    /// 动态码通过后记录时间步，同一个动态码不能再次使用；恢复码使用后作废。
+    /// 旧版本保存的明文密钥在验证通过时改为加密保存
+    pub async fn verify(
+        pool: &PgPool,
+        cipher: &TotpSecretCipher,
+        user_id: Uuid,
+        credential: MfaCredential<'_>,
+    ) -> Result<bool, ResourceError> {
+        match credential {
+            MfaCredential::Code(code) => {
+                let mut tx = pool
+                    .begin()
+                    .await
+                    .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+                let row: Option<(String, Option<i64>)> = sqlx::query_as(
+                    r#"
+                    SELECT secret, last_used_step FROM user_totp
+                    WHERE user_id = $1 AND enabled_at IS NOT NULL
+                    FOR UPDATE
+                    "#,
+                )
+                .bind(user_id)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+                let Some((stored_secret, last_used_step)) = row else {
+                    return Ok(false);
+                };
+                let secret = cipher
+                    .decrypt(&stored_secret)
+                    .map_err(ResourceError::DatabaseError)?;
+
+                let step = verify_totp(&secret, code, chrono::Utc::now().timestamp())
+                    .map_err(ResourceError::DatabaseError)?;
+                let Some(step) = step.filter(|step| last_used_step.is_none_or(|last| *step > last))
+                else {
+                    return Ok(false);
+                };
+
+                let reencrypted = (!TotpSecretCipher::is_encrypted(&stored_secret))
+                    .then(|| cipher.encrypt(&secret));
+                sqlx::query(
+                    r#"
+                    UPDATE user_totp
+                    SET last_used_step = $2, secret = COALESCE($3, secret), updated_at = CURRENT_TIMESTAMP
+                    WHERE user_id = $1
+                    "#,
+                )
+                .bind(user_id)
+                .bind(step)
+                .bind(reencrypted)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+                tx.commit()
+                    .await
+                    .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+                Ok(true)
+            }
+            MfaCredential::RecoveryCode(code) => {
+                let code_hash = hash_one_time_token(&normalize_recovery_code(code));
+                let result = sqlx::query(
+                    r#"
+                    UPDATE mfa_recovery_codes SET used_at = CURRENT_TIMESTAMP
+                    WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+                    "#,
+                )
+                .bind(user_id)
+                .bind(&code_hash)
+                .execute(pool)
+                .await
+                .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+                if result.rows_affected() > 0 {
+                    log::warn!("[MFA] 使用恢复码登录 | user_id={}", user_id);
+                }
+                Ok(result.rows_affected() > 0)
+            }
+        }
+    }
+
+    /// 删除旧恢复码并生成一组新的，返回明文
+    async fn replace_recovery_codes(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: Uuid,
+    ) -> Result<Vec<String>, ResourceError> {
+        sqlx::query("DELETE FROM mfa_recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+            .map(|_| generate_recovery_code())
+            .collect();
+        for code in &codes {
+            sqlx::query("INSERT INTO mfa_recovery_codes (user_id, code_hash) VALUES ($1, $2)")
+                .bind(user_id)
+                .bind(hash_one_time_token(&normalize_recovery_code(code)))
+                .execute(&mut **tx)
+                .await
+                .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+        }
+
+        Ok(codes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{cleanup_test_user, create_test_user, test_pool};
+    use crate::models::UserRole;
+    use crate::utils::{totp_code, totp_step};
+
+    async fn stored_secret(pool: &PgPool, user_id: Uuid) -> String {
+        sqlx::query_scalar("SELECT secret FROM user_totp WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_totp_secret_is_encrypted_at_rest() {
+        let pool = test_pool().await;
+        let user = create_test_user(&pool, UserRole::User).await;
+        let config = Config::from_env();
+        let cipher = MfaService::secret_cipher(&config);
+
+        let enrollment = MfaService::begin_enrollment(&pool, &config, user.id, &user.username)
+            .await
+            .unwrap();
+        let stored = stored_secret(&pool, user.id).await;
+        assert!(TotpSecretCipher::is_encrypted(&stored));
+        assert!(!stored.contains(&enrollment.secret));
+
+        let now = chrono::Utc::now().timestamp();
+        let code = totp_code(&enrollment.secret, totp_step(now)).unwrap();
+        MfaService::confirm_enrollment(&pool, &cipher, user.id, user.session_id, &code)
+            .await
+            .unwrap();
+        // 同一个动态码不能再次使用
+        assert!(
+            !MfaService::verify(&pool, &cipher, user.id, MfaCredential::Code(&code))
+                .await
+                .unwrap()
+        );
+
+        // 旧版本保存的明文密钥仍可验证，验证通过后改为加密保存
+        sqlx::query("UPDATE user_totp SET secret = $2, last_used_step = NULL WHERE user_id = $1")
+            .bind(user.id)
+            .bind(&enrollment.secret)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(
+            MfaService::verify(&pool, &cipher, user.id, MfaCredential::Code(&code))
+                .await
+                .unwrap()
+        );
+        let stored = stored_secret(&pool, user.id).await;
+        assert!(TotpSecretCipher::is_encrypted(&stored));
+        assert_eq!(cipher.decrypt(&stored).unwrap(), enrollment.secret);
+
+        cleanup_test_user(&pool, user.id).await;
+    }
+}
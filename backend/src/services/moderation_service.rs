@@ -0,0 +1,556 @@
+use serde_json::Value;
+use sqlx::PgPool;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::models::resource::AiAuditResult;
+use crate::models::{
+    CreateModerationRuleRequest, ModerationRule, RuleMatchType, UpdateModerationRuleRequest,
+};
+use crate::services::{AdminError, AiError, ModerationFuture, ModerationInput, ModerationProvider};
+
+/// 发送给外部审核接口的最大字符数
+const MAX_INPUT_CHARS: usize = 8000;
+
+/// 编译后的审核规则
+enum RuleMatcher {
+    Keyword(String),
+    Regex(regex::Regex),
+}
+
+struct CompiledRule {
+    matcher: RuleMatcher,
+    /// 命中时的说明
+    label: String,
+}
+
+impl CompiledRule {
+    /// 编译规则，正则无效时返回 None（保存时已校验，这里只做兜底）
+    fn compile(rule: &ModerationRule) -> Option<Self> {
+        let matcher = match RuleMatchType::parse(&rule.match_type)? {
+            RuleMatchType::Keyword => RuleMatcher::Keyword(rule.pattern.to_lowercase()),
+            RuleMatchType::Regex => match regex::RegexBuilder::new(&rule.pattern)
+                .case_insensitive(true)
+                .build()
+            {
+                Ok(re) => RuleMatcher::Regex(re),
+                Err(e) => {
+                    log::warn!(
+                        "[Moderation] 跳过无效的正则规则 | rule_id={}, error={}",
+                        rule.id,
+                        e
+                    );
+                    return None;
+                }
+            },
+        };
+
+        let label = rule
+            .reason
+            .clone()
+            .filter(|reason| !reason.trim().is_empty())
+            .unwrap_or_else(|| format!("命中敏感词「{}」", rule.pattern));
+
+        Some(Self { matcher, label })
+    }
+
+    fn is_match(&self, text: &str, lowercase_text: &str) -> bool {
+        match &self.matcher {
+            RuleMatcher::Keyword(keyword) => lowercase_text.contains(keyword.as_str()),
+            RuleMatcher::Regex(re) => re.is_match(text),
+        }
+    }
+}
+
+/// 返回文本命中的规则说明（去重，保持规则顺序）
+fn match_rules(rules: &[CompiledRule], text: &str) -> Vec<String> {
+    let lowercase_text = text.to_lowercase();
+    let mut hits: Vec<String> = Vec::new();
+    for rule in rules {
+        if rule.is_match(text, &lowercase_text) && !hits.contains(&rule.label) {
+            hits.push(rule.label.clone());
+        }
+    }
+    hits
+}
+
+/// 本地关键词 / 正则规则审核
+///
+/// 规则保存在 moderation_rules 表中，每次审核时读取，管理员修改后立即生效
+pub struct RuleModerationProvider {
+    pool: PgPool,
+}
+
+impl RuleModerationProvider {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl ModerationProvider for RuleModerationProvider {
+    fn name(&self) -> &'static str {
+        "rules"
+    }
+
+    fn moderate<'a>(&'a self, input: &'a ModerationInput<'a>) -> ModerationFuture<'a> {
+        Box::pin(async move {
+            let rules = sqlx::query_as::<_, ModerationRule>(
+                r#"
+                SELECT * FROM moderation_rules
+                WHERE is_active = true AND target IN ('all', $1)
+                ORDER BY created_at
+                "#,
+            )
+            .bind(input.target.as_str())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AiError::Rule(format!("读取审核规则失败: {}", e)))?;
+
+            let compiled: Vec<CompiledRule> =
+                rules.iter().filter_map(CompiledRule::compile).collect();
+            let hits = match_rules(&compiled, &input.text());
+
+            if hits.is_empty() {
+                return Ok(AiAuditResult {
+                    passed: true,
+                    reason: None,
+                    accuracy_score: None,
+                });
+            }
+
+            Ok(AiAuditResult {
+                passed: false,
+                reason: Some(format!("命中审核规则：{}", hits.join("；"))),
+                accuracy_score: None,
+            })
+        })
+    }
+}
+
+/// OpenAI 兼容的内容审核接口（POST {base}/moderations）
+pub struct HttpModerationProvider {
+    client: reqwest::Client,
+    endpoint: String,
+    api_key: Option<String>,
+    model: String,
+}
+
+impl HttpModerationProvider {
+    pub fn new(
+        api_base: &str,
+        api_key: Option<String>,
+        model: String,
+        timeout: Duration,
+    ) -> Result<Self, AiError> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| AiError::Config(format!("初始化审核 HTTP 客户端失败: {}", e)))?;
+
+        Ok(Self {
+            client,
+            endpoint: format!("{}/moderations", api_base.trim_end_matches('/')),
+            api_key,
+            model,
+        })
+    }
+
+    pub fn from_config(config: &Config) -> Result<Self, AiError> {
+        let api_base = config.moderation_api_base.as_deref().ok_or_else(|| {
+            AiError::Config("启用 openai 审核时必须配置 MODERATION_API_BASE".to_string())
+        })?;
+
+        Self::new(
+            api_base,
+            config.moderation_api_key.clone(),
+            config.moderation_model.clone(),
+            Duration::from_secs(config.moderation_timeout_secs.max(1)),
+        )
+    }
+}
+
+/// 解析 /moderations 响应
+/// `flagged` 为 true 时不通过，原因列出被标记的类别；准确度取 1 - 最高类别分数
+fn parse_moderation_response(body: &Value) -> Result<AiAuditResult, AiError> {
+    let result = body
+        .get("results")
+        .and_then(|results| results.get(0))
+        .ok_or_else(|| AiError::ApiError("响应缺少 results 字段".to_string()))?;
+
+    let flagged = result
+        .get("flagged")
+        .and_then(Value::as_bool)
+        .ok_or_else(|| AiError::ApiError("响应缺少 flagged 字段".to_string()))?;
+
+    let max_score = result
+        .get("category_scores")
+        .and_then(Value::as_object)
+        .and_then(|scores| scores.values().filter_map(Value::as_f64).reduce(f64::max));
+
+    if !flagged {
+        return Ok(AiAuditResult {
+            passed: true,
+            reason: None,
+            accuracy_score: max_score.map(|score| 1.0 - score),
+        });
+    }
+
+    let categories: Vec<&str> = result
+        .get("categories")
+        .and_then(Value::as_object)
+        .map(|categories| {
+            categories
+                .iter()
+                .filter(|(_, hit)| hit.as_bool() == Some(true))
+                .map(|(name, _)| name.as_str())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let reason = if categories.is_empty() {
+        "内容审核接口判定违规".to_string()
+    } else {
+        format!("内容审核接口判定违规：{}", categories.join("、"))
+    };
+
+    Ok(AiAuditResult {
+        passed: false,
+        reason: Some(reason),
+        accuracy_score: max_score.map(|score| 1.0 - score),
+    })
+}
+
+impl ModerationProvider for HttpModerationProvider {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    fn moderate<'a>(&'a self, input: &'a ModerationInput<'a>) -> ModerationFuture<'a> {
+        Box::pin(async move {
+            let text: String = input.text().chars().take(MAX_INPUT_CHARS).collect();
+            let payload = serde_json::json!({
+                "model": self.model,
+                "input": text,
+            });
+
+            let mut request = self
+                .client
+                .post(&self.endpoint)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(payload.to_string());
+            if let Some(api_key) = &self.api_key {
+                request = request.bearer_auth(api_key);
+            }
+
+            let response = request.send().await.map_err(|e| {
+                if e.is_timeout() {
+                    AiError::TimeoutError(e.to_string())
+                } else {
+                    AiError::ApiError(format!("请求审核接口失败: {}", e))
+                }
+            })?;
+
+            let status = response.status();
+            let body = response.text().await.map_err(|e| {
+                if e.is_timeout() {
+                    AiError::TimeoutError(e.to_string())
+                } else {
+                    AiError::ApiError(format!("读取审核接口响应失败: {}", e))
+                }
+            })?;
+
+            if !status.is_success() {
+                let snippet: String = body.chars().take(200).collect();
+                return Err(AiError::ApiError(format!(
+                    "审核接口返回 {}: {}",
+                    status, snippet
+                )));
+            }
+
+            let body: Value = serde_json::from_str(&body)
+                .map_err(|e| AiError::ApiError(format!("审核接口响应不是有效 JSON: {}", e)))?;
+            parse_moderation_response(&body)
+        })
+    }
+}
+
+/// 审核规则管理服务
+pub struct ModerationService;
+
+impl ModerationService {
+    /// 获取全部审核规则
+    pub async fn list_rules(pool: &PgPool) -> Result<Vec<ModerationRule>, AdminError> {
+        sqlx::query_as::<_, ModerationRule>(
+            "SELECT * FROM moderation_rules ORDER BY created_at DESC",
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))
+    }
+
+    /// 创建审核规则
+    pub async fn create_rule(
+        pool: &PgPool,
+        admin_id: Uuid,
+        request: CreateModerationRuleRequest,
+    ) -> Result<ModerationRule, AdminError> {
+        request.validate().map_err(AdminError::ValidationError)?;
+
+        sqlx::query_as::<_, ModerationRule>(
+            r#"
+            INSERT INTO moderation_rules (pattern, match_type, target, reason, created_by)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(request.pattern.trim())
+        .bind(&request.match_type)
+        .bind(&request.target)
+        .bind(request.reason.as_deref().map(str::trim))
+        .bind(admin_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))
+    }
+
+    /// 更新审核规则
+    pub async fn update_rule(
+        pool: &PgPool,
+        rule_id: Uuid,
+        request: UpdateModerationRuleRequest,
+    ) -> Result<ModerationRule, AdminError> {
+        let current =
+            sqlx::query_as::<_, ModerationRule>("SELECT * FROM moderation_rules WHERE id = $1")
+                .bind(rule_id)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| AdminError::DatabaseError(e.to_string()))?
+                .ok_or_else(|| AdminError::NotFound("审核规则不存在".to_string()))?;
+
+        request
+            .validate(&current)
+            .map_err(AdminError::ValidationError)?;
+
+        sqlx::query_as::<_, ModerationRule>(
+            r#"
+            UPDATE moderation_rules
+            SET pattern = COALESCE($1, pattern),
+                match_type = COALESCE($2, match_type),
+                target = COALESCE($3, target),
+                reason = COALESCE($4, reason),
+                is_active = COALESCE($5, is_active)
+            WHERE id = $6
+            RETURNING *
+            "#,
+        )
+        .bind(request.pattern.as_deref().map(str::trim))
+        .bind(request.match_type.as_deref())
+        .bind(request.target.as_deref())
+        .bind(request.reason.as_deref().map(str::trim))
+        .bind(request.is_active)
+        .bind(rule_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))
+    }
+
+    /// 删除审核规则
+    pub async fn delete_rule(pool: &PgPool, rule_id: Uuid) -> Result<(), AdminError> {
+        let result = sqlx::query("DELETE FROM moderation_rules WHERE id = $1")
+            .bind(rule_id)
+            .execute(pool)
+            .await
+            .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(AdminError::NotFound("审核规则不存在".to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ModerationTarget;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn rule(pattern: &str, match_type: &str, reason: Option<&str>) -> ModerationRule {
+        let now = chrono::Utc::now().naive_utc();
+        ModerationRule {
+            id: Uuid::new_v4(),
+            pattern: pattern.to_string(),
+            match_type: match_type.to_string(),
+            target: "all".to_string(),
+            reason: reason.map(str::to_string),
+            is_active: true,
+            created_by: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_match_rules() {
+        let rules: Vec<CompiledRule> = [
+            rule("代考", "keyword", Some("疑似代考广告")),
+            rule("VPN", "keyword", None),
+            rule(
+                r"(微信|vx)\s*[:：]?\s*\w{6,}",
+                "regex",
+                Some("包含联系方式"),
+            ),
+            rule("(", "regex", None),
+        ]
+        .iter()
+        .filter_map(CompiledRule::compile)
+        .collect();
+        // 无效正则在编译时被跳过
+        assert_eq!(rules.len(), 3);
+
+        assert!(match_rules(&rules, "高等数学期末复习笔记").is_empty());
+        assert_eq!(
+            match_rules(&rules, "专业代考，加 VX: abc12345"),
+            vec!["疑似代考广告".to_string(), "包含联系方式".to_string()]
+        );
+        // 关键词不区分大小写，未填写说明时使用默认说明
+        assert_eq!(
+            match_rules(&rules, "free vpn"),
+            vec!["命中敏感词「VPN」".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_parse_moderation_response() {
+        let flagged = serde_json::json!({
+            "id": "modr-1",
+            "results": [{
+                "flagged": true,
+                "categories": { "harassment": true, "violence": false, "sexual": true },
+                "category_scores": { "harassment": 0.9, "violence": 0.1, "sexual": 0.6 }
+            }]
+        });
+        let result = parse_moderation_response(&flagged).unwrap();
+        assert!(!result.passed);
+        let reason = result.reason.unwrap();
+        assert!(reason.contains("harassment") && reason.contains("sexual"));
+        assert!(!reason.contains("violence"));
+        assert!((result.accuracy_score.unwrap() - 0.1).abs() < 1e-9);
+
+        let clean = serde_json::json!({
+            "results": [{ "flagged": false, "categories": {}, "category_scores": { "hate": 0.02 } }]
+        });
+        let result = parse_moderation_response(&clean).unwrap();
+        assert!(result.passed);
+        assert!(result.reason.is_none());
+
+        assert!(parse_moderation_response(&serde_json::json!({ "results": [] })).is_err());
+    }
+
+    /// 启动只处理一个请求的本地模拟接口，返回地址和收到的请求
+    async fn mock_server(
+        status_line: &'static str,
+        body: &'static str,
+    ) -> (String, tokio::sync::oneshot::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = Vec::new();
+            let mut chunk = [0u8; 4096];
+            // 读取请求头和完整请求体
+            loop {
+                let n = socket.read(&mut chunk).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                buf.extend_from_slice(&chunk[..n]);
+                let text = String::from_utf8_lossy(&buf).to_string();
+                if let Some(header_end) = text.find("\r\n\r\n") {
+                    let content_length = text[..header_end]
+                        .lines()
+                        .find_map(|line| {
+                            let (name, value) = line.split_once(':')?;
+                            name.eq_ignore_ascii_case("content-length")
+                                .then(|| value.trim().parse::<usize>().ok())?
+                        })
+                        .unwrap_or(0);
+                    if buf.len() >= header_end + 4 + content_length {
+                        break;
+                    }
+                }
+            }
+
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status_line,
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            let _ = tx.send(String::from_utf8_lossy(&buf).to_string());
+        });
+
+        (format!("http://{}/v1", addr), rx)
+    }
+
+    fn comment_input(content: &str) -> ModerationInput<'_> {
+        ModerationInput {
+            target: ModerationTarget::Comment,
+            title: None,
+            description: None,
+            content: Some(content),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_provider_with_mock() {
+        let (base, request_rx) = mock_server(
+            "200 OK",
+            r#"{"results":[{"flagged":true,"categories":{"hate":true},"category_scores":{"hate":0.97}}]}"#,
+        )
+        .await;
+        let provider = HttpModerationProvider::new(
+            &base,
+            Some("test-key".to_string()),
+            "omni-moderation-latest".to_string(),
+            Duration::from_secs(5),
+        )
+        .unwrap();
+
+        let input = comment_input("一条需要审核的评论");
+        let result = provider.moderate(&input).await.unwrap();
+        assert!(!result.passed);
+        assert_eq!(result.reason.as_deref(), Some("内容审核接口判定违规：hate"));
+
+        let request = request_rx.await.unwrap();
+        assert!(request.starts_with("POST /v1/moderations "));
+        assert!(request
+            .to_lowercase()
+            .contains("authorization: bearer test-key"));
+        assert!(request.contains("一条需要审核的评论"));
+        assert!(request.contains("omni-moderation-latest"));
+    }
+
+    #[tokio::test]
+    async fn test_http_provider_error_status() {
+        let (base, _request_rx) =
+            mock_server("500 Internal Server Error", r#"{"error":"boom"}"#).await;
+        let provider = HttpModerationProvider::new(
+            &base,
+            None,
+            "omni-moderation-latest".to_string(),
+            Duration::from_secs(5),
+        )
+        .unwrap();
+
+        let input = comment_input("内容");
+        let err = provider.moderate(&input).await.unwrap_err();
+        assert!(matches!(err, AiError::ApiError(msg) if msg.contains("500")));
+    }
+}
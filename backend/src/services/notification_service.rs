@@ -449,6 +449,54 @@ impl NotificationService {
         Ok(())
     }
 
+    /// 创建回复通知（评论被回复时通知评论者）
+    pub async fn create_reply_notification(
+        pool: &PgPool,
+        resource_id: Uuid,
+        resource_title: &str,
+        recipient_id: Uuid,
+        replier_name: &str,
+    ) -> Result<(), ResourceError> {
+        let request = CreateNotificationRequest {
+            recipient_id: Some(recipient_id),
+            title: "您的评论收到新回复".to_string(),
+            content: format!(
+                "用户 {} 回复了您在资源《{}》下的评论",
+                replier_name, resource_title
+            ),
+            notification_type: NotificationType::CommentReply,
+            priority: NotificationPriority::Normal,
+            link_url: Some(format!("/resources/{}", resource_id)),
+        };
+
+        Self::create_notification(pool, request).await?;
+        Ok(())
+    }
+
+    /// 创建提醒通知（评论中 @ 了某个用户时通知该用户）
+    pub async fn create_mention_notification(
+        pool: &PgPool,
+        resource_id: Uuid,
+        resource_title: &str,
+        recipient_id: Uuid,
+        commenter_name: &str,
+    ) -> Result<(), ResourceError> {
+        let request = CreateNotificationRequest {
+            recipient_id: Some(recipient_id),
+            title: "有人在评论中提到了您".to_string(),
+            content: format!(
+                "用户 {} 在资源《{}》的评论中提到了您",
+                commenter_name, resource_title
+            ),
+            notification_type: NotificationType::CommentReply,
+            priority: NotificationPriority::Normal,
+            link_url: Some(format!("/resources/{}", resource_id)),
+        };
+
+        Self::create_notification(pool, request).await?;
+        Ok(())
+    }
+
     /// 创建评分通知（资源被评分时通知上传者）
     pub async fn create_rating_notification(
         pool: &PgPool,
@@ -469,4 +517,91 @@ impl NotificationService {
         Self::create_notification(pool, request).await?;
         Ok(())
     }
+
+    /// 创建申领结果通知（作者申领审核完成时通知申请人）
+    pub async fn create_claim_result_notification(
+        pool: &PgPool,
+        resource_id: Uuid,
+        resource_title: &str,
+        applicant_id: Uuid,
+        approved: bool,
+        comment: Option<&str>,
+    ) -> Result<(), ResourceError> {
+        let (title, mut content) = if approved {
+            (
+                "作者申领已通过",
+                format!("您对资源《{}》的作者申领已通过审核", resource_title),
+            )
+        } else {
+            (
+                "作者申领未通过",
+                format!("您对资源《{}》的作者申领未通过审核", resource_title),
+            )
+        };
+        if let Some(comment) = comment.filter(|c| !c.trim().is_empty()) {
+            content.push_str(&format!("，审核意见：{}", comment.trim()));
+        }
+
+        let request = CreateNotificationRequest {
+            recipient_id: Some(applicant_id),
+            title: title.to_string(),
+            content,
+            notification_type: NotificationType::ClaimResult,
+            priority: NotificationPriority::Normal,
+            link_url: Some(format!("/resources/{}", resource_id)),
+        };
+
+        Self::create_notification(pool, request).await?;
+        Ok(())
+    }
+
+    /// 创建实名认证结果通知（管理员审核完成时通知申请人）
+    pub async fn create_verification_result_notification(
+        pool: &PgPool,
+        user_id: Uuid,
+        approved: bool,
+        comment: Option<&str>,
+    ) -> Result<(), ResourceError> {
+        let (title, mut content) = if approved {
+            ("实名认证已通过", "您的实名认证申请已通过审核".to_string())
+        } else {
+            ("实名认证未通过", "您的实名认证申请未通过审核".to_string())
+        };
+        if let Some(comment) = comment.filter(|c| !c.trim().is_empty()) {
+            content.push_str(&format!("，审核意见：{}", comment.trim()));
+        }
+
+        let request = CreateNotificationRequest {
+            recipient_id: Some(user_id),
+            title: title.to_string(),
+            content,
+            notification_type: NotificationType::AuditResult,
+            priority: NotificationPriority::Normal,
+            link_url: Some("/verification".to_string()),
+        };
+
+        Self::create_notification(pool, request).await?;
+        Ok(())
+    }
+
+    /// 创建协作邀请通知（资源上传者邀请协作者时通知被邀请者）
+    pub async fn create_editor_invitation_notification(
+        pool: &PgPool,
+        resource_id: Uuid,
+        resource_title: &str,
+        editor_id: Uuid,
+        inviter_name: &str,
+    ) -> Result<(), ResourceError> {
+        let request = CreateNotificationRequest {
+            recipient_id: Some(editor_id),
+            title: "协作编辑邀请".to_string(),
+            content: format!("{} 邀请您共同编辑资源《{}》", inviter_name, resource_title),
+            notification_type: NotificationType::System,
+            priority: NotificationPriority::Normal,
+            link_url: Some(format!("/resources/{}", resource_id)),
+        };
+
+        Self::create_notification(pool, request).await?;
+        Ok(())
+    }
 }
@@ -9,9 +9,10 @@ use sha2::{Digest, Sha256};
 use std::collections::BTreeMap;
 use uuid::Uuid;
 
+use super::s3_service::parse_list_objects_response;
 use super::storage_service::{
-    StorageBackend, StorageBackendType, StorageError, StorageFileMetadata, StorageFuture,
-    StorageStsCredentials,
+    paginated_list, StorageBackend, StorageBackendType, StorageError, StorageFileMetadata,
+    StorageFuture, StorageObject, StorageObjectStream, StorageStsCredentials,
 };
 
 #[derive(Debug, Clone)]
@@ -228,16 +229,41 @@ impl OssStorage {
         expires_secs: u64,
         response_content_disposition: Option<String>,
         content_type: Option<&str>,
+    ) -> Result<String, StorageError> {
+        let normalized_key = self.normalize_key(key)?;
+        let mut extra_query = BTreeMap::new();
+        if let Some(content_disposition) = response_content_disposition {
+            extra_query.insert(
+                "response-content-disposition".to_string(),
+                content_disposition,
+            );
+        }
+        self.presign(
+            method,
+            &normalized_key,
+            expires_secs,
+            extra_query,
+            content_type,
+        )
+    }
+
+    /// 生成 V4 签名 URL；`normalized_key` 为空时签名的是存储桶本身（如列举文件）
+    fn presign(
+        &self,
+        method: &str,
+        normalized_key: &str,
+        expires_secs: u64,
+        extra_query: BTreeMap<String, String>,
+        content_type: Option<&str>,
     ) -> Result<String, StorageError> {
         let scheme = self.endpoint_scheme();
         let host = self.object_host();
         let region = self.resolve_region()?;
-        let normalized_key = self.normalize_key(key)?;
-        let request_uri = format!("/{}", percent_encode(&normalized_key, false));
+        let request_uri = format!("/{}", percent_encode(normalized_key, false));
         let canonical_uri = format!(
             "/{}/{}",
             percent_encode(&self.config.bucket, false),
-            percent_encode(&normalized_key, false)
+            percent_encode(normalized_key, false)
         );
         let expires = if expires_secs == 0 {
             self.config.signed_url_expiry
@@ -251,7 +277,7 @@ impl OssStorage {
         let credential_scope = format!("{}/{}/oss/aliyun_v4_request", short_date, region);
         let credential = format!("{}/{}", self.config.access_key_id, credential_scope);
 
-        let mut query_params = BTreeMap::new();
+        let mut query_params = extra_query;
         query_params.insert(
             "x-oss-signature-version".to_string(),
             "OSS4-HMAC-SHA256".to_string(),
@@ -279,13 +305,6 @@ impl OssStorage {
             additional_headers.join(";"),
         );
 
-        if let Some(content_disposition) = response_content_disposition {
-            query_params.insert(
-                "response-content-disposition".to_string(),
-                content_disposition,
-            );
-        }
-
         let canonical_query = canonical_query_string(&query_params);
         let canonical_request = format!(
             "{}\n{}\n{}\n{}\n{}\nUNSIGNED-PAYLOAD",
@@ -386,6 +405,42 @@ impl OssStorage {
         }
     }
 
+    /// 使用 ListObjectsV2 读取一页，返回本页对象和下一页的 continuation token
+    async fn list_objects_page(
+        &self,
+        prefix: &str,
+        continuation_token: Option<String>,
+    ) -> Result<(Vec<StorageObject>, Option<String>), StorageError> {
+        let normalized_prefix = self.normalize_key(prefix)?;
+        let mut query = BTreeMap::new();
+        query.insert("list-type".to_string(), "2".to_string());
+        query.insert("prefix".to_string(), normalized_prefix);
+        if let Some(token) = continuation_token {
+            query.insert("continuation-token".to_string(), token);
+        }
+
+        let signed_url = self.presign("GET", "", 60, query, None)?;
+        let response = self
+            .client
+            .get(&signed_url)
+            .send()
+            .await
+            .map_err(|e| StorageError::Backend(format!("OSS 列举请求失败: {}", e)))?;
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| StorageError::Backend(format!("OSS 响应读取失败: {}", e)))?;
+        if !status.is_success() {
+            return Err(StorageError::Backend(format!(
+                "OSS 列举文件失败，HTTP 状态码: {}，响应: {}",
+                status, body
+            )));
+        }
+
+        Ok(parse_list_objects_response(&body))
+    }
+
     #[allow(dead_code)]
     pub fn bucket_name(&self) -> &str {
         &self.config.bucket
@@ -539,6 +594,10 @@ impl StorageBackend for OssStorage {
         })
     }
 
+    fn list_files<'a>(&'a self, prefix: &'a str) -> StorageObjectStream<'a> {
+        paginated_list(move |token| self.list_objects_page(prefix, token))
+    }
+
     fn get_sts_token<'a>(
         &'a self,
         key: &'a str,
@@ -627,7 +686,7 @@ fn sign_sts_rpc_query(
     Ok(signature)
 }
 
-fn required(value: Option<&str>, env_name: &str) -> Result<String, StorageError> {
+pub(super) fn required(value: Option<&str>, env_name: &str) -> Result<String, StorageError> {
     value
         .map(|v| v.trim().to_string())
         .filter(|v| !v.is_empty())
@@ -654,9 +713,9 @@ fn encode_rfc5987(filename: &str) -> String {
 
 /// 构建 Content-Disposition 头部值，支持中文文件名
 /// 使用 RFC 5987 编码：filename*=UTF-8''%E4%B8%AD%E6%96%87
-fn build_content_disposition(filename: &str) -> String {
+pub(super) fn build_content_disposition(filename: &str) -> String {
     // 检查是否包含非 ASCII 字符
-    let has_non_ascii = filename.chars().any(|c| !c.is_ascii());
+    let has_non_ascii = !filename.is_ascii();
 
     if has_non_ascii {
         // 包含中文等非 ASCII 字符，使用 RFC 5987 编码
@@ -668,7 +727,7 @@ fn build_content_disposition(filename: &str) -> String {
     }
 }
 
-fn canonical_query_string(params: &BTreeMap<String, String>) -> String {
+pub(super) fn canonical_query_string(params: &BTreeMap<String, String>) -> String {
     params
         .iter()
         .map(|(k, v)| format!("{}={}", percent_encode(k, true), percent_encode(v, true)))
@@ -676,7 +735,7 @@ fn canonical_query_string(params: &BTreeMap<String, String>) -> String {
         .join("&")
 }
 
-fn parse_storage_metadata_from_headers(
+pub(super) fn parse_storage_metadata_from_headers(
     headers: &reqwest::header::HeaderMap,
     content_length_override: Option<u64>,
 ) -> StorageFileMetadata {
@@ -694,11 +753,17 @@ fn parse_storage_metadata_from_headers(
         .get(reqwest::header::ETAG)
         .and_then(|v| v.to_str().ok())
         .map(|v| v.trim_matches('"').to_string());
+    let last_modified = headers
+        .get(reqwest::header::LAST_MODIFIED)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<actix_web::http::header::HttpDate>().ok())
+        .map(std::time::SystemTime::from);
 
     StorageFileMetadata {
         content_length,
         content_type,
         etag,
+        last_modified,
     }
 }
 
@@ -711,7 +776,7 @@ fn parse_total_length_from_content_range(content_range: Option<&str>) -> Option<
     total.parse::<u64>().ok()
 }
 
-fn percent_encode(input: &str, encode_slash: bool) -> String {
+pub(super) fn percent_encode(input: &str, encode_slash: bool) -> String {
     let mut encoded = String::new();
     for &byte in input.as_bytes() {
         let is_unreserved =
@@ -727,14 +792,14 @@ fn percent_encode(input: &str, encode_slash: bool) -> String {
     encoded
 }
 
-fn hex_lower(bytes: &[u8]) -> String {
+pub(super) fn hex_lower(bytes: &[u8]) -> String {
     bytes
         .iter()
         .map(|b| format!("{:02x}", b))
         .collect::<String>()
 }
 
-fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+pub(super) fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
     const BLOCK_SIZE: usize = 64;
     let mut key_block = [0u8; BLOCK_SIZE];
 
@@ -0,0 +1,289 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::{
+    CurrentUser, Permission, PermissionGrantListResponse, PermissionInfo, UserPermissionsResponse,
+    UserRole,
+};
+use crate::services::AdminError;
+
+/// 管理权限服务
+pub struct PermissionService;
+
+impl PermissionService {
+    /// 检查用户是否拥有指定权限（管理员拥有全部权限）
+    pub async fn has_permission(
+        pool: &PgPool,
+        user: &CurrentUser,
+        permission: Permission,
+    ) -> Result<bool, sqlx::Error> {
+        if user.is_admin() {
+            return Ok(true);
+        }
+
+        sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM user_permissions WHERE user_id = $1 AND permission = $2)",
+        )
+        .bind(user.id)
+        .bind(permission.as_str())
+        .fetch_one(pool)
+        .await
+    }
+
+    /// 获取用户实际拥有的权限（管理员为全部权限）
+    pub async fn get_effective_permissions(
+        pool: &PgPool,
+        user: &CurrentUser,
+    ) -> Result<Vec<Permission>, sqlx::Error> {
+        if user.role == UserRole::Admin {
+            return Ok(Permission::ALL.to_vec());
+        }
+        Self::granted_permissions(pool, user.id).await
+    }
+
+    /// 获取指定用户的权限
+    pub async fn get_user_permissions(
+        pool: &PgPool,
+        user_id: Uuid,
+    ) -> Result<UserPermissionsResponse, AdminError> {
+        let (username, role): (String, String) =
+            sqlx::query_as("SELECT username, role FROM users WHERE id = $1")
+                .bind(user_id)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| AdminError::DatabaseError(e.to_string()))?
+                .ok_or_else(|| AdminError::NotFound("用户不存在".to_string()))?;
+
+        let permissions = if role == "admin" {
+            Permission::ALL.to_vec()
+        } else {
+            Self::granted_permissions(pool, user_id)
+                .await
+                .map_err(|e| AdminError::DatabaseError(e.to_string()))?
+        };
+
+        Ok(UserPermissionsResponse {
+            user_id,
+            username,
+            role,
+            permissions: permissions.iter().map(|p| p.as_str()).collect(),
+        })
+    }
+
+    /// 设置用户权限（整体替换），返回设置后的权限
+    pub async fn set_user_permissions(
+        pool: &PgPool,
+        admin_id: Uuid,
+        user_id: Uuid,
+        permissions: &[Permission],
+    ) -> Result<UserPermissionsResponse, AdminError> {
+        let role: String = sqlx::query_scalar("SELECT role FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| AdminError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| AdminError::NotFound("用户不存在".to_string()))?;
+
+        if role == "admin" {
+            return Err(AdminError::ValidationError(
+                "管理员已拥有全部权限，无需单独授予".to_string(),
+            ));
+        }
+
+        let names: Vec<&str> = permissions.iter().map(|p| p.as_str()).collect();
+
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        sqlx::query("DELETE FROM user_permissions WHERE user_id = $1 AND permission <> ALL($2)")
+            .bind(user_id)
+            .bind(&names)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        for name in &names {
+            sqlx::query(
+                r#"
+                INSERT INTO user_permissions (user_id, permission, granted_by)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (user_id, permission) DO NOTHING
+                "#,
+            )
+            .bind(user_id)
+            .bind(name)
+            .bind(admin_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        log::info!(
+            "[Permission] 更新用户权限 | admin_id={}, user_id={}, permissions={:?}",
+            admin_id,
+            user_id,
+            names
+        );
+
+        Self::get_user_permissions(pool, user_id).await
+    }
+
+    /// 获取被单独授予权限的用户列表
+    pub async fn list_grants(pool: &PgPool) -> Result<PermissionGrantListResponse, AdminError> {
+        let rows: Vec<(Uuid, String, String, Vec<String>)> = sqlx::query_as(
+            r#"
+            SELECT u.id, u.username, u.role, array_agg(p.permission ORDER BY p.permission)
+            FROM user_permissions p
+            JOIN users u ON p.user_id = u.id
+            GROUP BY u.id, u.username, u.role
+            ORDER BY u.username
+            "#,
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        let users = rows
+            .into_iter()
+            .map(
+                |(user_id, username, role, permissions)| UserPermissionsResponse {
+                    user_id,
+                    username,
+                    role,
+                    permissions: permissions
+                        .iter()
+                        .filter_map(|p| Permission::parse(p))
+                        .map(|p| p.as_str())
+                        .collect(),
+                },
+            )
+            .collect();
+
+        Ok(PermissionGrantListResponse {
+            users,
+            available_permissions: Permission::ALL
+                .into_iter()
+                .map(PermissionInfo::from)
+                .collect(),
+        })
+    }
+
+    /// 用户被单独授予的权限（忽略已不再支持的权限名）
+    async fn granted_permissions(
+        pool: &PgPool,
+        user_id: Uuid,
+    ) -> Result<Vec<Permission>, sqlx::Error> {
+        let names: Vec<String> =
+            sqlx::query_scalar("SELECT permission FROM user_permissions WHERE user_id = $1")
+                .bind(user_id)
+                .fetch_all(pool)
+                .await?;
+
+        Ok(Permission::ALL
+            .into_iter()
+            .filter(|p| names.iter().any(|name| name == p.as_str()))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{cleanup_test_user, create_test_user, test_pool};
+    use crate::models::UpdateUserPermissionsRequest;
+
+    fn request(permissions: &[&str]) -> UpdateUserPermissionsRequest {
+        UpdateUserPermissionsRequest {
+            permissions: permissions.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_permission_parse() {
+        for permission in Permission::ALL {
+            assert_eq!(Permission::parse(permission.as_str()), Some(permission));
+        }
+        assert_eq!(
+            Permission::parse("moderate_comments"),
+            Some(Permission::ModerateComments)
+        );
+        assert_eq!(Permission::parse("Moderate_Comments"), None);
+        assert_eq!(Permission::parse(" moderate_comments"), None);
+        assert_eq!(Permission::parse("manage_users"), None);
+        assert_eq!(Permission::parse(""), None);
+    }
+
+    #[test]
+    fn test_update_permissions_request_validate() {
+        assert_eq!(
+            request(&["view_stats", " audit_resources ", "view_stats"])
+                .validate()
+                .unwrap(),
+            vec![Permission::ViewStats, Permission::AuditResources]
+        );
+        // 空列表表示撤销全部权限
+        assert_eq!(request(&[]).validate().unwrap(), vec![]);
+        assert!(request(&["view_stats", "manage_users"]).validate().is_err());
+        assert!(request(&[""]).validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_has_permission() {
+        let pool = test_pool().await;
+        let admin = create_test_user(&pool, UserRole::Admin).await;
+        let moderator = create_test_user(&pool, UserRole::User).await;
+
+        assert!(!PermissionService::has_permission(
+            &pool,
+            &moderator,
+            Permission::ModerateComments
+        )
+        .await
+        .unwrap());
+        PermissionService::set_user_permissions(
+            &pool,
+            admin.id,
+            moderator.id,
+            &[Permission::ModerateComments],
+        )
+        .await
+        .unwrap();
+        assert!(
+            PermissionService::has_permission(&pool, &moderator, Permission::ModerateComments)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !PermissionService::has_permission(&pool, &moderator, Permission::AuditResources)
+                .await
+                .unwrap()
+        );
+
+        // 管理员拥有全部权限，但需要本次登录完成两步验证
+        assert!(
+            PermissionService::has_permission(&pool, &admin, Permission::AuditResources)
+                .await
+                .unwrap()
+        );
+        let admin_without_mfa = CurrentUser {
+            mfa: false,
+            ..admin.clone()
+        };
+        assert!(!PermissionService::has_permission(
+            &pool,
+            &admin_without_mfa,
+            Permission::AuditResources
+        )
+        .await
+        .unwrap());
+
+        cleanup_test_user(&pool, moderator.id).await;
+        cleanup_test_user(&pool, admin.id).await;
+    }
+}
@@ -0,0 +1,397 @@
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::config::Config;
+
+/// 限流错误类型
+#[derive(Debug)]
+pub enum RateLimitError {
+    Config(String),
+    Store(String),
+}
+
+impl std::fmt::Display for RateLimitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RateLimitError::Config(msg) => write!(f, "限流配置错误: {}", msg),
+            RateLimitError::Store(msg) => write!(f, "限流存储错误: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for RateLimitError {}
+
+/// 限流配额：桶容量为 `capacity`，每 `period_secs` 秒补满
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitQuota {
+    pub capacity: u32,
+    pub period_secs: u64,
+}
+
+impl RateLimitQuota {
+    /// 解析 `次数/秒数` 格式的配置（如 `10/60`），`off` 或 `0` 表示不限流
+    pub fn parse(spec: &str) -> Result<Option<Self>, RateLimitError> {
+        let spec = spec.trim();
+        if spec.eq_ignore_ascii_case("off") || spec == "0" {
+            return Ok(None);
+        }
+
+        let invalid =
+            || RateLimitError::Config(format!("无效的限流配置: {}（格式为 次数/秒数）", spec));
+        let (capacity, period) = spec.split_once('/').ok_or_else(invalid)?;
+        let capacity: u32 = capacity.trim().parse().map_err(|_| invalid())?;
+        let period_secs: u64 = period.trim().parse().map_err(|_| invalid())?;
+        if capacity == 0 || period_secs == 0 {
+            return Err(invalid());
+        }
+
+        Ok(Some(Self {
+            capacity,
+            period_secs,
+        }))
+    }
+
+    /// 每秒补充的令牌数
+    pub fn refill_per_sec(&self) -> f64 {
+        self.capacity as f64 / self.period_secs as f64
+    }
+}
+
+/// 限流判定结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateDecision {
+    pub allowed: bool,
+    /// 本次请求后剩余的可用次数
+    pub remaining: u32,
+    /// 被拒绝时距离下一个令牌的秒数
+    pub retry_after_secs: u64,
+}
+
+/// 按经过的时间补充令牌（不超过桶容量）
+pub(crate) fn refill_tokens(tokens: f64, elapsed_secs: f64, quota: &RateLimitQuota) -> f64 {
+    (tokens + elapsed_secs.max(0.0) * quota.refill_per_sec()).min(quota.capacity as f64)
+}
+
+/// 根据补充后的令牌数判定本次请求，返回 (扣除后的令牌数, 判定结果)
+pub(crate) fn take_token(refilled: f64, quota: &RateLimitQuota) -> (f64, RateDecision) {
+    if refilled >= 1.0 {
+        let tokens = refilled - 1.0;
+        (
+            tokens,
+            RateDecision {
+                allowed: true,
+                remaining: tokens.floor() as u32,
+                retry_after_secs: 0,
+            },
+        )
+    } else {
+        let wait = ((1.0 - refilled) / quota.refill_per_sec()).ceil() as u64;
+        (
+            refilled,
+            RateDecision {
+                allowed: false,
+                remaining: 0,
+                retry_after_secs: wait.max(1),
+            },
+        )
+    }
+}
+
+pub type RateLimitFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<RateDecision, RateLimitError>> + Send + 'a>>;
+
+/// 令牌桶存储
+pub trait RateLimitStore: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// 从 `key` 对应的桶中取一个令牌
+    fn acquire<'a>(&'a self, key: &'a str, quota: &'a RateLimitQuota) -> RateLimitFuture<'a>;
+}
+
+/// 根据配置创建令牌桶存储
+/// RATE_LIMIT_STORE 可选 memory（单实例，默认）、postgres（多实例共享）
+pub fn create_rate_limit_store(
+    config: &Config,
+    pool: PgPool,
+) -> Result<Arc<dyn RateLimitStore>, RateLimitError> {
+    match config.rate_limit_store.as_str() {
+        "memory" => Ok(Arc::new(MemoryRateLimitStore::new())),
+        "postgres" => Ok(Arc::new(PostgresRateLimitStore::new(pool))),
+        other => Err(RateLimitError::Config(format!(
+            "未知的限流存储: {}（可选 memory、postgres）",
+            other
+        ))),
+    }
+}
+
+/// 超过这个数量的桶后清理已补满的桶
+const MEMORY_STORE_PRUNE_THRESHOLD: usize = 10_000;
+
+/// 进程内的令牌桶，记录所属规则的配额，清理时按各自的配额判断是否已补满
+struct MemoryBucket {
+    tokens: f64,
+    updated: Instant,
+    quota: RateLimitQuota,
+}
+
+impl MemoryBucket {
+    fn refilled(&self, now: Instant) -> f64 {
+        refill_tokens(
+            self.tokens,
+            now.duration_since(self.updated).as_secs_f64(),
+            &self.quota,
+        )
+    }
+}
+
+/// 进程内令牌桶存储（多实例部署时各实例分别计数）
+pub struct MemoryRateLimitStore {
+    buckets: Mutex<HashMap<String, MemoryBucket>>,
+}
+
+impl MemoryRateLimitStore {
+    pub fn new() -> Self {
+        Self {
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn acquire_now(&self, key: &str, quota: &RateLimitQuota, now: Instant) -> RateDecision {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        if buckets.len() >= MEMORY_STORE_PRUNE_THRESHOLD && !buckets.contains_key(key) {
+            // 补满的桶与不存在的桶等价，可以直接丢弃
+            buckets.retain(|_, bucket| bucket.refilled(now) < bucket.quota.capacity as f64);
+        }
+
+        let refilled = match buckets.get(key) {
+            Some(bucket) => refill_tokens(
+                bucket.tokens,
+                now.duration_since(bucket.updated).as_secs_f64(),
+                quota,
+            ),
+            None => quota.capacity as f64,
+        };
+        let (tokens, decision) = take_token(refilled, quota);
+        buckets.insert(
+            key.to_string(),
+            MemoryBucket {
+                tokens,
+                updated: now,
+                quota: *quota,
+            },
+        );
+        decision
+    }
+}
+
+impl Default for MemoryRateLimitStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RateLimitStore for MemoryRateLimitStore {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn acquire<'a>(&'a self, key: &'a str, quota: &'a RateLimitQuota) -> RateLimitFuture<'a> {
+        Box::pin(async move { Ok(self.acquire_now(key, quota, Instant::now())) })
+    }
+}
+
+/// 每处理多少次请求清理一次长期未使用的桶
+const POSTGRES_STORE_PRUNE_INTERVAL: u64 = 1000;
+
+/// 基于 Postgres 的令牌桶存储（rate_limit_buckets 表），多个实例共享计数
+pub struct PostgresRateLimitStore {
+    pool: PgPool,
+    calls: AtomicU64,
+}
+
+impl PostgresRateLimitStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            calls: AtomicU64::new(0),
+        }
+    }
+
+    async fn acquire_db(
+        &self,
+        key: &str,
+        quota: &RateLimitQuota,
+    ) -> Result<RateDecision, RateLimitError> {
+        let calls = self.calls.fetch_add(1, Ordering::Relaxed);
+        if calls.is_multiple_of(POSTGRES_STORE_PRUNE_INTERVAL) {
+            sqlx::query(
+                "DELETE FROM rate_limit_buckets WHERE updated_at < CURRENT_TIMESTAMP - INTERVAL '1 day'",
+            )
+            .execute(&self.pool)
+            .await
+            .map_err(|e| RateLimitError::Store(e.to_string()))?;
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO rate_limit_buckets (bucket_key, tokens, updated_at)
+            VALUES ($1, $2, clock_timestamp()::timestamp)
+            ON CONFLICT (bucket_key) DO NOTHING
+            "#,
+        )
+        .bind(key)
+        .bind(quota.capacity as f64)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RateLimitError::Store(e.to_string()))?;
+
+        // 在行锁内完成补充和扣除，多个实例并发请求时不会超发
+        let refilled: f64 = sqlx::query_scalar(
+            r#"
+            UPDATE rate_limit_buckets b
+            SET tokens = CASE WHEN s.refilled >= 1 THEN s.refilled - 1 ELSE s.refilled END,
+                updated_at = clock_timestamp()::timestamp
+            FROM (
+                SELECT bucket_key,
+                       LEAST(
+                           $2,
+                           tokens + GREATEST(EXTRACT(EPOCH FROM (clock_timestamp()::timestamp - updated_at))::float8, 0) * $3
+                       ) AS refilled
+                FROM rate_limit_buckets
+                WHERE bucket_key = $1
+                FOR UPDATE
+            ) s
+            WHERE b.bucket_key = s.bucket_key
+            RETURNING s.refilled
+            "#,
+        )
+        .bind(key)
+        .bind(quota.capacity as f64)
+        .bind(quota.refill_per_sec())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| RateLimitError::Store(e.to_string()))?;
+
+        Ok(take_token(refilled, quota).1)
+    }
+}
+
+impl RateLimitStore for PostgresRateLimitStore {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    fn acquire<'a>(&'a self, key: &'a str, quota: &'a RateLimitQuota) -> RateLimitFuture<'a> {
+        Box::pin(self.acquire_db(key, quota))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_quota() {
+        assert_eq!(
+            RateLimitQuota::parse("10/60").unwrap(),
+            Some(RateLimitQuota {
+                capacity: 10,
+                period_secs: 60
+            })
+        );
+        assert_eq!(RateLimitQuota::parse(" off ").unwrap(), None);
+        assert_eq!(RateLimitQuota::parse("0").unwrap(), None);
+        assert!(RateLimitQuota::parse("10").is_err());
+        assert!(RateLimitQuota::parse("0/60").is_err());
+        assert!(RateLimitQuota::parse("10/abc").is_err());
+    }
+
+    #[test]
+    fn test_memory_store_token_bucket() {
+        let store = MemoryRateLimitStore::new();
+        let quota = RateLimitQuota {
+            capacity: 3,
+            period_secs: 30,
+        };
+        let start = Instant::now();
+
+        // 初始可以连续通过 capacity 次
+        for expected_remaining in [2, 1, 0] {
+            let decision = store.acquire_now("login:ip:1.2.3.4", &quota, start);
+            assert!(decision.allowed);
+            assert_eq!(decision.remaining, expected_remaining);
+        }
+
+        let denied = store.acquire_now("login:ip:1.2.3.4", &quota, start);
+        assert!(!denied.allowed);
+        assert_eq!(denied.retry_after_secs, 10);
+
+        // 不同的键互不影响
+        assert!(store.acquire_now("login:ip:5.6.7.8", &quota, start).allowed);
+
+        // 每 10 秒补充一个令牌
+        let later = start + Duration::from_secs(10);
+        assert!(store.acquire_now("login:ip:1.2.3.4", &quota, later).allowed);
+        assert!(!store.acquire_now("login:ip:1.2.3.4", &quota, later).allowed);
+
+        // 长时间后补满，但不超过容量
+        let much_later = later + Duration::from_secs(3600);
+        let decision = store.acquire_now("login:ip:1.2.3.4", &quota, much_later);
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 2);
+    }
+
+    #[test]
+    fn test_memory_store_prunes_with_each_bucket_quota() {
+        let login = RateLimitQuota {
+            capacity: 3,
+            period_secs: 3600,
+        };
+        let download = RateLimitQuota {
+            capacity: 100,
+            period_secs: 10,
+        };
+        let start = Instant::now();
+
+        // 登录桶用掉一个令牌，另有一个下载桶用掉 50 个令牌，其余下载桶各用掉一个
+        let filled_store = || {
+            let store = MemoryRateLimitStore::new();
+            assert!(store.acquire_now("login:ip:1.2.3.4", &login, start).allowed);
+            for _ in 0..50 {
+                store.acquire_now("download:ip:busy", &download, start);
+            }
+            for i in 2..MEMORY_STORE_PRUNE_THRESHOLD {
+                store.acquire_now(&format!("download:ip:{}", i), &download, start);
+            }
+            store
+        };
+
+        // 下载请求触发清理：登录桶按登录配额仍未补满，不能被丢弃
+        let store = filled_store();
+        let later = start + Duration::from_secs(20);
+        store.acquire_now("download:ip:new", &download, later);
+        assert_eq!(store.buckets.lock().unwrap().len(), 2);
+        let decision = store.acquire_now("login:ip:1.2.3.4", &login, later);
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 1);
+
+        // 登录请求触发清理：令牌数超过登录容量但未补满的下载桶不能被丢弃
+        let store = filled_store();
+        let later = start + Duration::from_secs(1);
+        store.acquire_now("login:ip:5.6.7.8", &login, later);
+        {
+            let buckets = store.buckets.lock().unwrap();
+            assert_eq!(buckets.len(), 3);
+            assert!(buckets.contains_key("download:ip:busy"));
+        }
+        let decision = store.acquire_now("download:ip:busy", &download, later);
+        assert_eq!(decision.remaining, 59);
+    }
+}
@@ -18,7 +18,7 @@ impl RatingService {
     ) -> Result<RatingResponse, sqlx::Error> {
         // 验证评分范围
         if let Err(msg) = request.validate() {
-            return Err(sqlx::Error::Protocol(msg.into()));
+            return Err(sqlx::Error::Protocol(msg));
         }
 
         // 插入或更新评分
@@ -0,0 +1,450 @@
+use sqlx::PgPool;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::models::resource::{RecommendationReason, RecommendedResource};
+
+use super::{ResourceError, ResourceService};
+
+/// 用户对资源的隐式反馈汇总（物化视图，列为 user_id / resource_id / weight）
+///
+/// 下载记 1 分、点赞 2 分、收藏 3 分，评分按总体质量折算（5 分为中性，低分为负反馈），
+/// 同一用户对同一资源的得分累加后上限为 5，只保留正反馈。
+/// 汇总需要扫描全部行为记录，因此预先计算，由后台任务定期刷新
+const USER_ITEM_VIEW: &str = "user_resource_interactions";
+
+/// 每次从数据库取出的候选数量上限
+const CANDIDATE_LIMIT: i64 = 200;
+/// 计算用户推荐时参考的相似用户数量
+const NEIGHBOR_LIMIT: i64 = 50;
+
+/// 各信号在综合得分中的权重
+const COLLABORATIVE_WEIGHT: f64 = 0.6;
+const COURSE_WEIGHT: f64 = 0.3;
+const POPULARITY_WEIGHT: f64 = 0.1;
+
+/// 推荐候选资源及其原始信号
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct Candidate {
+    resource_id: Uuid,
+    /// 协同过滤得分（行为相似度）
+    collaborative: f64,
+    /// 课程关联程度
+    course_affinity: f64,
+    /// 累计下载量
+    popularity: f64,
+}
+
+/// 将各信号按最大值归一化后加权求和，返回按得分排序的前 `limit` 项
+/// 推荐理由取协同过滤和课程关联中贡献较大的一项，两者都为 0 时视为热门补位
+fn rank_candidates(
+    candidates: &[Candidate],
+    limit: usize,
+) -> Vec<(Uuid, f64, RecommendationReason)> {
+    let max_of = |f: fn(&Candidate) -> f64| {
+        candidates
+            .iter()
+            .map(f)
+            .fold(0.0_f64, f64::max)
+            .max(f64::EPSILON)
+    };
+    let max_collaborative = max_of(|c| c.collaborative);
+    let max_course = max_of(|c| c.course_affinity);
+    let max_popularity = max_of(|c| c.popularity.ln_1p());
+
+    let mut ranked: Vec<(Uuid, f64, RecommendationReason)> = candidates
+        .iter()
+        .map(|c| {
+            let collaborative = COLLABORATIVE_WEIGHT * c.collaborative / max_collaborative;
+            let course = COURSE_WEIGHT * c.course_affinity / max_course;
+            let popularity = POPULARITY_WEIGHT * c.popularity.ln_1p() / max_popularity;
+
+            let reason = if collaborative <= 0.0 && course <= 0.0 {
+                RecommendationReason::Popular
+            } else if collaborative >= course {
+                RecommendationReason::AlsoDownloaded
+            } else {
+                RecommendationReason::SameCourse
+            };
+
+            (c.resource_id, collaborative + course + popularity, reason)
+        })
+        .collect();
+
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranked.truncate(limit);
+    ranked
+}
+
+/// 资源推荐服务
+pub struct RecommendationService;
+
+impl RecommendationService {
+    /// 行为汇总的刷新间隔
+    pub const REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
+    /// 相似资源：“下载过此资源的同学也下载了”
+    ///
+    /// 以用户行为向量的余弦相似度为主，同课程资源作为补充，不足时用热门资源补位
+    pub async fn similar_resources(
+        pool: &PgPool,
+        resource_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<RecommendedResource>, ResourceError> {
+        let exists: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM resources WHERE id = $1 AND audit_status = 'approved')",
+        )
+        .bind(resource_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        if !exists {
+            return Err(ResourceError::NotFound(format!(
+                "资源 {} 不存在",
+                resource_id
+            )));
+        }
+
+        let sql = format!(
+            r#"
+            WITH target AS (
+                SELECT user_id, weight FROM {user_item} WHERE resource_id = $1
+            ),
+            target_norm AS (
+                SELECT SQRT(SUM(weight * weight)) AS norm FROM target
+            ),
+            co AS (
+                SELECT ui.resource_id, SUM(ui.weight * t.weight) AS dot
+                FROM {user_item} ui
+                JOIN target t ON t.user_id = ui.user_id
+                WHERE ui.resource_id <> $1
+                GROUP BY ui.resource_id
+            ),
+            item_norm AS (
+                SELECT resource_id, SQRT(SUM(weight * weight)) AS norm
+                FROM {user_item}
+                WHERE resource_id IN (SELECT resource_id FROM co)
+                GROUP BY resource_id
+            ),
+            cf AS (
+                SELECT co.resource_id,
+                       co.dot / NULLIF(item_norm.norm * (SELECT norm FROM target_norm), 0) AS score
+                FROM co
+                JOIN item_norm ON item_norm.resource_id = co.resource_id
+            ),
+            course AS (
+                SELECT rc.resource_id, COUNT(DISTINCT rc.course_sn)::FLOAT8 AS shared
+                FROM resource_courses rc
+                WHERE rc.course_sn IN (SELECT course_sn FROM resource_courses WHERE resource_id = $1)
+                  AND rc.resource_id <> $1
+                GROUP BY rc.resource_id
+            ),
+            candidates AS (
+                SELECT resource_id FROM cf
+                UNION
+                SELECT resource_id FROM course
+            )
+            SELECT c.resource_id,
+                   COALESCE(cf.score, 0) AS collaborative,
+                   COALESCE(course.shared, 0) AS course_affinity,
+                   COALESCE(rs.downloads, 0)::FLOAT8 AS popularity
+            FROM candidates c
+            JOIN resources r ON r.id = c.resource_id AND r.audit_status = 'approved'
+            LEFT JOIN cf ON cf.resource_id = c.resource_id
+            LEFT JOIN course ON course.resource_id = c.resource_id
+            LEFT JOIN resource_stats rs ON rs.resource_id = c.resource_id
+            ORDER BY collaborative DESC, course_affinity DESC, popularity DESC
+            LIMIT $2
+            "#,
+            user_item = USER_ITEM_VIEW
+        );
+
+        let candidates = sqlx::query_as::<_, Candidate>(&sql)
+            .bind(resource_id)
+            .bind(CANDIDATE_LIMIT)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        let ranked = rank_candidates(&candidates, limit as usize);
+        let exclude = vec![resource_id];
+        Self::finish(pool, ranked, &exclude, None, limit).await
+    }
+
+    /// 个性化推荐
+    ///
+    /// 找出与用户行为最相近的同学，推荐他们下载/收藏过而用户还没接触过的资源，
+    /// 并结合用户关注课程下的资源；没有行为数据的新用户直接返回热门资源
+    pub async fn recommend_for_user(
+        pool: &PgPool,
+        user_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<RecommendedResource>, ResourceError> {
+        // 用户接触过的资源（包括打了低分的）都不再推荐
+        let interacted: Vec<Uuid> = sqlx::query_scalar(
+            r#"
+            SELECT resource_id FROM download_logs WHERE user_id = $1
+            UNION
+            SELECT resource_id FROM likes WHERE user_id = $1
+            UNION
+            SELECT fr.resource_id FROM favorite_resources fr
+            JOIN favorites f ON f.id = fr.favorite_id
+            WHERE f.user_id = $1
+            UNION
+            SELECT resource_id FROM ratings WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        let ranked = if interacted.is_empty() {
+            Vec::new()
+        } else {
+            let sql = format!(
+                r#"
+                WITH mine AS (
+                    SELECT resource_id, weight FROM {user_item} WHERE user_id = $1
+                ),
+                neighbors AS (
+                    SELECT ui.user_id, SUM(ui.weight * m.weight) AS sim
+                    FROM {user_item} ui
+                    JOIN mine m ON m.resource_id = ui.resource_id
+                    WHERE ui.user_id <> $1
+                    GROUP BY ui.user_id
+                    ORDER BY sim DESC
+                    LIMIT $3
+                ),
+                cf AS (
+                    SELECT ui.resource_id, SUM(n.sim * ui.weight) AS score
+                    FROM {user_item} ui
+                    JOIN neighbors n ON n.user_id = ui.user_id
+                    GROUP BY ui.resource_id
+                ),
+                my_courses AS (
+                    SELECT rc.course_sn, SUM(m.weight) AS affinity
+                    FROM resource_courses rc
+                    JOIN mine m ON m.resource_id = rc.resource_id
+                    GROUP BY rc.course_sn
+                ),
+                course AS (
+                    SELECT rc.resource_id, SUM(mc.affinity) AS affinity
+                    FROM resource_courses rc
+                    JOIN my_courses mc ON mc.course_sn = rc.course_sn
+                    GROUP BY rc.resource_id
+                ),
+                candidates AS (
+                    SELECT resource_id FROM cf
+                    UNION
+                    SELECT resource_id FROM course
+                )
+                SELECT c.resource_id,
+                       COALESCE(cf.score, 0) AS collaborative,
+                       COALESCE(course.affinity, 0) AS course_affinity,
+                       COALESCE(rs.downloads, 0)::FLOAT8 AS popularity
+                FROM candidates c
+                JOIN resources r ON r.id = c.resource_id
+                    AND r.audit_status = 'approved'
+                    AND r.uploader_id <> $1
+                LEFT JOIN cf ON cf.resource_id = c.resource_id
+                LEFT JOIN course ON course.resource_id = c.resource_id
+                LEFT JOIN resource_stats rs ON rs.resource_id = c.resource_id
+                WHERE NOT (c.resource_id = ANY($2))
+                ORDER BY collaborative DESC, course_affinity DESC, popularity DESC
+                LIMIT $4
+                "#,
+                user_item = USER_ITEM_VIEW
+            );
+
+            let candidates = sqlx::query_as::<_, Candidate>(&sql)
+                .bind(user_id)
+                .bind(&interacted)
+                .bind(NEIGHBOR_LIMIT)
+                .bind(CANDIDATE_LIMIT)
+                .fetch_all(pool)
+                .await
+                .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+            rank_candidates(&candidates, limit as usize)
+        };
+
+        log::debug!(
+            "[Recommend] 个性化推荐 | user_id={}, interacted={}, ranked={}",
+            user_id,
+            interacted.len(),
+            ranked.len()
+        );
+
+        Self::finish(pool, ranked, &interacted, Some(user_id), limit).await
+    }
+
+    /// 刷新行为汇总（不阻塞推荐查询）
+    pub async fn refresh_interactions(pool: &PgPool) -> Result<(), ResourceError> {
+        sqlx::query(&format!(
+            "REFRESH MATERIALIZED VIEW CONCURRENTLY {}",
+            USER_ITEM_VIEW
+        ))
+        .execute(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// 不足 `limit` 时用近 30 天热门资源补位，然后加载资源详情
+    async fn finish(
+        pool: &PgPool,
+        mut ranked: Vec<(Uuid, f64, RecommendationReason)>,
+        exclude: &[Uuid],
+        user_id: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<RecommendedResource>, ResourceError> {
+        if (ranked.len() as i64) < limit {
+            let mut skip: Vec<Uuid> = exclude.to_vec();
+            skip.extend(ranked.iter().map(|(id, _, _)| *id));
+
+            let popular: Vec<Uuid> = sqlx::query_scalar(
+                r#"
+                SELECT r.id
+                FROM resources r
+                LEFT JOIN resource_stats rs ON rs.resource_id = r.id
+                LEFT JOIN (
+                    SELECT resource_id, COUNT(*) AS recent
+                    FROM download_logs
+                    WHERE downloaded_at > NOW() - INTERVAL '30 days'
+                    GROUP BY resource_id
+                ) d ON d.resource_id = r.id
+                WHERE r.audit_status = 'approved'
+                  AND NOT (r.id = ANY($1))
+                  AND ($2::UUID IS NULL OR r.uploader_id <> $2)
+                ORDER BY COALESCE(d.recent, 0) DESC, COALESCE(rs.downloads, 0) DESC, r.created_at DESC
+                LIMIT $3
+                "#,
+            )
+            .bind(&skip)
+            .bind(user_id)
+            .bind(limit - ranked.len() as i64)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+            ranked.extend(
+                popular
+                    .into_iter()
+                    .map(|id| (id, 0.0, RecommendationReason::Popular)),
+            );
+        }
+
+        let ids: Vec<Uuid> = ranked.iter().map(|(id, _, _)| *id).collect();
+        let items = ResourceService::get_resource_items_by_ids(pool, &ids).await?;
+
+        let mut seen = HashSet::new();
+        Ok(items
+            .into_iter()
+            .filter(|item| seen.insert(item.id))
+            .filter_map(|item| {
+                let (_, score, reason) = ranked.iter().find(|(id, _, _)| *id == item.id)?;
+                Some(RecommendedResource {
+                    score: *score,
+                    reason: *reason,
+                    resource: item,
+                })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{
+        cleanup_test_user, create_test_resource, create_test_user, test_pool,
+    };
+    use crate::models::UserRole;
+
+    fn candidate(collaborative: f64, course_affinity: f64, popularity: f64) -> Candidate {
+        Candidate {
+            resource_id: Uuid::new_v4(),
+            collaborative,
+            course_affinity,
+            popularity,
+        }
+    }
+
+    #[test]
+    fn test_rank_candidates() {
+        let candidates = vec![
+            candidate(0.0, 0.0, 500.0),
+            candidate(0.9, 0.0, 3.0),
+            candidate(0.1, 2.0, 10.0),
+            candidate(0.45, 1.0, 0.0),
+        ];
+
+        let ranked = rank_candidates(&candidates, 10);
+        let order: Vec<Uuid> = ranked.iter().map(|(id, _, _)| *id).collect();
+        assert_eq!(
+            order,
+            vec![
+                candidates[1].resource_id,
+                candidates[3].resource_id,
+                candidates[2].resource_id,
+                candidates[0].resource_id,
+            ]
+        );
+
+        assert_eq!(ranked[0].2, RecommendationReason::AlsoDownloaded);
+        assert_eq!(ranked[1].2, RecommendationReason::AlsoDownloaded);
+        assert_eq!(ranked[2].2, RecommendationReason::SameCourse);
+        // 只有下载量的候选视为热门补位
+        assert_eq!(ranked[3].2, RecommendationReason::Popular);
+
+        assert_eq!(rank_candidates(&candidates, 2).len(), 2);
+    }
+
+    #[test]
+    fn test_rank_candidates_empty() {
+        assert!(rank_candidates(&[], 10).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_recommendations_use_refreshed_interactions() {
+        let pool = test_pool().await;
+        let uploader = create_test_user(&pool, UserRole::User).await;
+        let alice = create_test_user(&pool, UserRole::User).await;
+        let bob = create_test_user(&pool, UserRole::User).await;
+        let hash = Uuid::new_v4().simple().to_string();
+        let first = create_test_resource(&pool, uploader.id, &hash, "approved").await;
+        let second = create_test_resource(&pool, uploader.id, &hash, "approved").await;
+
+        // bob 下载了两份资源，alice 只下载了第一份
+        for (user_id, resource_id) in [(bob.id, first), (bob.id, second), (alice.id, first)] {
+            sqlx::query("INSERT INTO download_logs (resource_id, user_id) VALUES ($1, $2)")
+                .bind(resource_id)
+                .bind(user_id)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        RecommendationService::refresh_interactions(&pool)
+            .await
+            .unwrap();
+
+        let similar = RecommendationService::similar_resources(&pool, first, 10)
+            .await
+            .unwrap();
+        let item = similar.iter().find(|r| r.resource.id == second).unwrap();
+        assert_eq!(item.reason, RecommendationReason::AlsoDownloaded);
+
+        let feed = RecommendationService::recommend_for_user(&pool, alice.id, 10)
+            .await
+            .unwrap();
+        assert!(feed.iter().all(|r| r.resource.id != first));
+        let item = feed.iter().find(|r| r.resource.id == second).unwrap();
+        assert_eq!(item.reason, RecommendationReason::AlsoDownloaded);
+
+        for user in [&uploader, &alice, &bob] {
+            cleanup_test_user(&pool, user.id).await;
+        }
+    }
+}
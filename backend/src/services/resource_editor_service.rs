@@ -0,0 +1,340 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::resource::Resource;
+use crate::models::resource_editor::{ResourceEditorListResponse, ResourceEditorResponse};
+use crate::models::CurrentUser;
+
+use super::{NotificationService, ResourceError};
+
+/// 每个资源最多邀请的协作者数量
+pub const MAX_RESOURCE_EDITORS: i64 = 20;
+
+/// 资源协作服务
+///
+/// 上传者可以邀请其他用户共同编辑自己的 Markdown 资源。
+/// 协作者与上传者一样可以在线编辑内容、查看全部历史版本，
+/// 但不能重新上传文件、回滚版本、删除资源或管理协作者。
+pub struct ResourceEditorService;
+
+impl ResourceEditorService {
+    /// 用户是否为资源的协作者
+    pub async fn is_editor(
+        pool: &PgPool,
+        resource_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<bool, ResourceError> {
+        let exists = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM resource_editors WHERE resource_id = $1 AND user_id = $2)",
+        )
+        .bind(resource_id)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+        Ok(exists)
+    }
+
+    /// 用户是否可以编辑资源内容（上传者、管理员或协作者）
+    pub async fn can_edit(
+        pool: &PgPool,
+        user: &CurrentUser,
+        resource: &Resource,
+    ) -> Result<bool, ResourceError> {
+        if resource.uploader_id == user.id || user.is_admin() {
+            return Ok(true);
+        }
+        Self::is_editor(pool, resource.id, user.id).await
+    }
+
+    /// 获取协作者列表（上传者、管理员和协作者可见）
+    pub async fn list_editors(
+        pool: &PgPool,
+        user: &CurrentUser,
+        resource_id: Uuid,
+    ) -> Result<ResourceEditorListResponse, ResourceError> {
+        let resource = Self::load_resource(pool, resource_id).await?;
+        if !Self::can_edit(pool, user, &resource).await? {
+            return Err(ResourceError::Unauthorized(
+                "没有权限查看此资源的协作者".to_string(),
+            ));
+        }
+
+        let editors = sqlx::query_as::<_, ResourceEditorResponse>(
+            r#"
+            SELECT e.user_id, u.username, e.invited_by, e.created_at
+            FROM resource_editors e
+            JOIN users u ON e.user_id = u.id
+            WHERE e.resource_id = $1
+            ORDER BY e.created_at
+            "#,
+        )
+        .bind(resource_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(ResourceEditorListResponse {
+            resource_id,
+            owner_id: resource.uploader_id,
+            editors,
+        })
+    }
+
+    /// 邀请协作者（上传者或管理员），被邀请者会收到通知
+    pub async fn add_editor(
+        pool: &PgPool,
+        user: &CurrentUser,
+        resource_id: Uuid,
+        username: &str,
+    ) -> Result<ResourceEditorResponse, ResourceError> {
+        let resource = Self::load_resource(pool, resource_id).await?;
+        Self::require_owner(user, &resource, "没有权限邀请协作者")?;
+
+        if resource.resource_type != "web_markdown" {
+            return Err(ResourceError::ValidationError(
+                "只有Markdown类型资源可以邀请协作者".to_string(),
+            ));
+        }
+
+        let (editor_id, is_active): (Uuid, bool) =
+            sqlx::query_as("SELECT id, is_active FROM users WHERE username = $1")
+                .bind(username.trim())
+                .fetch_optional(pool)
+                .await?
+                .ok_or_else(|| ResourceError::NotFound("用户不存在".to_string()))?;
+
+        if !is_active {
+            return Err(ResourceError::ValidationError("该用户已被禁用".to_string()));
+        }
+        if editor_id == resource.uploader_id {
+            return Err(ResourceError::ValidationError(
+                "上传者本身即可编辑，无需邀请".to_string(),
+            ));
+        }
+
+        // 锁住资源行，保证并发邀请时协作者计数与插入的原子性
+        let mut tx = pool.begin().await?;
+        sqlx::query("SELECT id FROM resources WHERE id = $1 FOR UPDATE")
+            .bind(resource_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| ResourceError::NotFound(format!("资源 {} 不存在", resource_id)))?;
+
+        let count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM resource_editors WHERE resource_id = $1")
+                .bind(resource_id)
+                .fetch_one(&mut *tx)
+                .await?;
+        if count >= MAX_RESOURCE_EDITORS {
+            return Err(ResourceError::ValidationError(format!(
+                "每个资源最多邀请 {} 位协作者",
+                MAX_RESOURCE_EDITORS
+            )));
+        }
+
+        let editor = sqlx::query_as::<_, ResourceEditorResponse>(
+            r#"
+            WITH inserted AS (
+                INSERT INTO resource_editors (resource_id, user_id, invited_by)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (resource_id, user_id) DO NOTHING
+                RETURNING user_id, invited_by, created_at
+            )
+            SELECT i.user_id, u.username, i.invited_by, i.created_at
+            FROM inserted i
+            JOIN users u ON i.user_id = u.id
+            "#,
+        )
+        .bind(resource_id)
+        .bind(editor_id)
+        .bind(user.id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| ResourceError::Conflict("该用户已是协作者".to_string()))?;
+        tx.commit().await?;
+
+        log::info!(
+            "[Resource] 邀请协作者 | resource_id={}, editor_id={}, invited_by={}",
+            resource_id,
+            editor_id,
+            user.id
+        );
+
+        if let Err(e) = NotificationService::create_editor_invitation_notification(
+            pool,
+            resource_id,
+            &resource.title,
+            editor_id,
+            &user.username,
+        )
+        .await
+        {
+            log::warn!(
+                "[Resource] 发送协作邀请通知失败 | resource_id={}, editor_id={}, error={}",
+                resource_id,
+                editor_id,
+                e
+            );
+        }
+
+        Ok(editor)
+    }
+
+    /// 移除协作者（上传者或管理员），协作者也可以主动退出
+    pub async fn remove_editor(
+        pool: &PgPool,
+        user: &CurrentUser,
+        resource_id: Uuid,
+        editor_id: Uuid,
+    ) -> Result<(), ResourceError> {
+        let resource = Self::load_resource(pool, resource_id).await?;
+        if editor_id != user.id {
+            Self::require_owner(user, &resource, "没有权限移除协作者")?;
+        }
+
+        let result =
+            sqlx::query("DELETE FROM resource_editors WHERE resource_id = $1 AND user_id = $2")
+                .bind(resource_id)
+                .bind(editor_id)
+                .execute(pool)
+                .await?;
+        if result.rows_affected() == 0 {
+            return Err(ResourceError::NotFound("该用户不是协作者".to_string()));
+        }
+
+        log::info!(
+            "[Resource] 移除协作者 | resource_id={}, editor_id={}, operator_id={}",
+            resource_id,
+            editor_id,
+            user.id
+        );
+        Ok(())
+    }
+
+    async fn load_resource(pool: &PgPool, resource_id: Uuid) -> Result<Resource, ResourceError> {
+        sqlx::query_as::<_, Resource>("SELECT * FROM resources WHERE id = $1")
+            .bind(resource_id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| ResourceError::NotFound(format!("资源 {} 不存在", resource_id)))
+    }
+
+    fn require_owner(
+        user: &CurrentUser,
+        resource: &Resource,
+        message: &str,
+    ) -> Result<(), ResourceError> {
+        if resource.uploader_id == user.id || user.is_admin() {
+            Ok(())
+        } else {
+            Err(ResourceError::Unauthorized(message.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{
+        cleanup_test_user, create_test_resource, create_test_user, test_pool,
+    };
+    use crate::models::resource_editor::AddResourceEditorRequest;
+    use crate::models::UserRole;
+
+    async fn markdown_resource(pool: &PgPool, uploader_id: Uuid) -> Uuid {
+        let hash = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let resource_id = create_test_resource(pool, uploader_id, &hash, "approved").await;
+        sqlx::query("UPDATE resources SET resource_type = 'web_markdown' WHERE id = $1")
+            .bind(resource_id)
+            .execute(pool)
+            .await
+            .unwrap();
+        resource_id
+    }
+
+    #[test]
+    fn test_add_editor_request_validate() {
+        let request = |username: &str| AddResourceEditorRequest {
+            username: username.to_string(),
+        };
+        assert!(request("alice").validate().is_ok());
+        assert!(request("").validate().is_err());
+        assert!(request("   ").validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_admin_without_mfa_cannot_manage_editors() {
+        let pool = test_pool().await;
+        let uploader = create_test_user(&pool, UserRole::User).await;
+        let admin = create_test_user(&pool, UserRole::Admin).await;
+        let invitee = create_test_user(&pool, UserRole::User).await;
+        let resource_id = markdown_resource(&pool, uploader.id).await;
+        let resource = ResourceEditorService::load_resource(&pool, resource_id)
+            .await
+            .unwrap();
+
+        let admin_without_mfa = CurrentUser {
+            mfa: false,
+            ..admin.clone()
+        };
+        assert!(
+            !ResourceEditorService::can_edit(&pool, &admin_without_mfa, &resource)
+                .await
+                .unwrap()
+        );
+        assert!(matches!(
+            ResourceEditorService::add_editor(
+                &pool,
+                &admin_without_mfa,
+                resource_id,
+                &invitee.username
+            )
+            .await,
+            Err(ResourceError::Unauthorized(_))
+        ));
+
+        assert!(ResourceEditorService::can_edit(&pool, &admin, &resource)
+            .await
+            .unwrap());
+        ResourceEditorService::add_editor(&pool, &admin, resource_id, &invitee.username)
+            .await
+            .unwrap();
+
+        cleanup_test_user(&pool, uploader.id).await;
+        cleanup_test_user(&pool, admin.id).await;
+        cleanup_test_user(&pool, invitee.id).await;
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_invitations_respect_editor_limit() {
+        let pool = test_pool().await;
+        let uploader = create_test_user(&pool, UserRole::User).await;
+        let resource_id = markdown_resource(&pool, uploader.id).await;
+
+        let mut invitees = Vec::new();
+        for _ in 0..MAX_RESOURCE_EDITORS + 5 {
+            invitees.push(create_test_user(&pool, UserRole::User).await);
+        }
+
+        // 并发邀请：行锁保证计数与插入原子，不会超过上限
+        let results = futures_util::future::join_all(invitees.iter().map(|invitee| {
+            ResourceEditorService::add_editor(&pool, &uploader, resource_id, &invitee.username)
+        }))
+        .await;
+        let added = results.iter().filter(|r| r.is_ok()).count() as i64;
+        assert_eq!(added, MAX_RESOURCE_EDITORS);
+        assert!(results
+            .iter()
+            .filter_map(|r| r.as_ref().err())
+            .all(|e| matches!(e, ResourceError::ValidationError(_))));
+
+        let listed = ResourceEditorService::list_editors(&pool, &uploader, resource_id)
+            .await
+            .unwrap();
+        assert_eq!(listed.editors.len() as i64, MAX_RESOURCE_EDITORS);
+
+        cleanup_test_user(&pool, uploader.id).await;
+        for invitee in invitees {
+            cleanup_test_user(&pool, invitee.id).await;
+        }
+    }
+}
@@ -1,9 +1,13 @@
 use crate::models::{resource::*, CurrentUser};
+use crate::utils::is_foreign_key_violation;
 use sqlx::{PgPool, Row};
 use std::sync::Arc;
 use uuid::Uuid;
 
-use super::{AiService, FileService};
+use super::{
+    AiService, FileService, ModerationProvider, ResourceEditorService, ResourceVersionService,
+    SearchService, StorageQuotaService,
+};
 
 #[derive(Debug)]
 pub enum ResourceError {
@@ -12,8 +16,8 @@ pub enum ResourceError {
     NotFound(String),
     ValidationError(String),
     Unauthorized(String),
-    AiError(String),
-    Conflict(String), // 资源冲突（如：资源已存在）
+    Conflict(String),                 // 资源冲突（如：资源已存在）
+    Duplicate(DuplicateResourceInfo), // 上传内容与已通过审核的资源重复
 }
 
 impl std::fmt::Display for ResourceError {
@@ -24,8 +28,10 @@ impl std::fmt::Display for ResourceError {
             ResourceError::NotFound(msg) => write!(f, "未找到: {}", msg),
             ResourceError::ValidationError(msg) => write!(f, "验证错误: {}", msg),
             ResourceError::Unauthorized(msg) => write!(f, "未授权: {}", msg),
-            ResourceError::AiError(msg) => write!(f, "AI 错误: {}", msg),
             ResourceError::Conflict(msg) => write!(f, "资源冲突: {}", msg),
+            ResourceError::Duplicate(info) => {
+                write!(f, "资源已存在: {} ({})", info.title, info.id)
+            }
         }
     }
 }
@@ -56,6 +62,22 @@ impl From<super::storage_service::StorageError> for ResourceError {
     }
 }
 
+impl From<super::storage_quota_service::StorageQuotaError> for ResourceError {
+    fn from(err: super::storage_quota_service::StorageQuotaError) -> Self {
+        match err {
+            super::storage_quota_service::StorageQuotaError::DatabaseError(msg) => {
+                ResourceError::DatabaseError(msg)
+            }
+            super::storage_quota_service::StorageQuotaError::UserNotFound(msg) => {
+                ResourceError::NotFound(msg)
+            }
+            super::storage_quota_service::StorageQuotaError::QuotaExceeded(msg) => {
+                ResourceError::ValidationError(msg)
+            }
+        }
+    }
+}
+
 impl From<sqlx::Error> for ResourceError {
     fn from(err: sqlx::Error) -> Self {
         ResourceError::DatabaseError(err.to_string())
@@ -101,6 +123,7 @@ impl ResourceService {
         pool: &PgPool,
         user: &CurrentUser,
         storage: &Arc<dyn super::StorageBackend>,
+        moderation: &Arc<dyn ModerationProvider>,
         request: UploadResourceRequest,
         oss_key: &str,
         metadata: super::StorageFileMetadata,
@@ -137,10 +160,26 @@ impl ResourceService {
             )));
         }
 
-        let ai_result =
-            AiService::audit_resource(&request.title, request.description.as_deref(), None)
-                .await
-                .map_err(|e| ResourceError::AiError(e.to_string()))?;
+        // 超出配额时删除已上传的文件，避免绕过配额占用存储
+        if let Err(e) = StorageQuotaService::ensure_quota(pool, user.id, file_size as i64).await {
+            if let Err(cleanup_err) = storage.delete_file(oss_key).await {
+                log::warn!(
+                    "[Resource] 超出配额后清理直传文件失败 | key={}, error={}",
+                    oss_key,
+                    cleanup_err
+                );
+            }
+            return Err(e.into());
+        }
+
+        // 直传文件不经过后端，只审核标题和描述
+        let ai_result = AiService::audit_resource(
+            moderation,
+            &request.title,
+            request.description.as_deref(),
+            None,
+        )
+        .await;
         let audit_status = if ai_result.passed {
             AuditStatus::Approved
         } else {
@@ -159,14 +198,31 @@ impl ResourceService {
             .await
             .map_err(|e| ResourceError::DatabaseError(format!("开启事务失败: {}", e)))?;
 
+        if let Err(e) =
+            StorageQuotaService::lock_and_ensure_quota(&mut tx, user.id, file_size as i64).await
+        {
+            if let Err(rollback_err) = tx.rollback().await {
+                log::warn!("[Resource] 回滚事务失败: {}", rollback_err);
+            }
+            if let Err(cleanup_err) = storage.delete_file(oss_key).await {
+                log::warn!(
+                    "[Resource] 超出配额后清理直传文件失败 | key={}, error={}",
+                    oss_key,
+                    cleanup_err
+                );
+            }
+            return Err(e.into());
+        }
+
         let resource: Resource = match sqlx::query_as::<_, Resource>(
             r#"
             INSERT INTO resources (
                 id, title, author_id, uploader_id, course_name,
                 resource_type, category, tags, file_path, source_file_path,
-                file_hash, file_size, content_accuracy, audit_status, ai_reject_reason, storage_type
+                file_hash, file_size, content_accuracy, audit_status, ai_reject_reason, storage_type,
+                description
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
             RETURNING *
             "#,
         )
@@ -190,6 +246,7 @@ impl ResourceService {
             ai_result.reason.as_deref()
         })
         .bind(&storage_type)
+        .bind(request.description.as_deref())
         .fetch_one(&mut *tx)
         .await
         {
@@ -302,6 +359,9 @@ impl ResourceService {
             return Err(ResourceError::DatabaseError(format!("提交事务失败: {}", e)));
         }
 
+        // 直传文件不经过后端，暂只索引元数据
+        SearchService::refresh_resource_index_quietly(pool, resource_id, None).await;
+
         Ok(UploadResourceResponse {
             id: resource.id,
             title: resource.title,
@@ -321,35 +381,57 @@ impl ResourceService {
         pool: &PgPool,
         user: &CurrentUser,
         storage: &Arc<dyn super::StorageBackend>,
+        moderation: &Arc<dyn ModerationProvider>,
         request: UploadResourceRequest,
-        file_name: &str,
-        file_data: Vec<u8>,
-        mime_type: Option<&str>,
+        file: UploadedFile<'_>,
     ) -> Result<UploadResourceResponse, ResourceError> {
         // 验证请求
         request.validate().map_err(ResourceError::ValidationError)?;
 
+        let UploadedFile {
+            name: file_name,
+            data: file_data,
+            mime_type,
+        } = file;
+
         // 验证并确定资源类型
         let resource_type = FileService::validate_resource_file(file_name, &file_data, mime_type)?;
 
-        // AI 审核
-        let ai_result = AiService::audit_resource(
-            &request.title,
-            request.description.as_deref(),
-            Some(&file_data),
-        )
-        .await
-        .map_err(|e| ResourceError::AiError(e.to_string()))?;
+        // 内容去重：已有相同内容且通过审核的资源时，不再重复入库
+        let file_hash = FileService::calculate_hash(&file_data);
+        if let Some(existing) = Self::find_approved_by_hash(pool, &file_hash).await? {
+            log::info!(
+                "[Resource] 上传内容与已有资源重复 | user_id={}, existing_id={}",
+                user.id,
+                existing.id
+            );
+            return Err(ResourceError::Duplicate(existing));
+        }
+
+        // 检查存储配额
+        StorageQuotaService::ensure_quota(pool, user.id, file_data.len() as i64).await?;
 
         // 生成资源 ID
         let resource_id = Uuid::new_v4();
         let resource_type_str = resource_type.to_string();
         let extension = FileService::get_extension_by_type(&resource_type_str);
         let file_key = format!("resources/{}.{}", resource_id, extension);
-        let file_hash = FileService::calculate_hash(&file_data);
         let file_size = file_data.len() as i64;
         let storage_type = storage.backend_type().as_str().to_string();
 
+        // 提取正文用于全文检索（PDF/TXT/Markdown）
+        let (file_data, content_text) =
+            SearchService::extract_text_blocking(resource_type_str.clone(), file_data).await?;
+
+        // 内容审核（标题、描述和提取出的正文）
+        let ai_result = AiService::audit_resource(
+            moderation,
+            &request.title,
+            request.description.as_deref(),
+            content_text.as_deref(),
+        )
+        .await;
+
         // 保存文件（统一走存储抽象）
         let file_path = storage.save_file(&file_key, file_data, mime_type).await?;
 
@@ -386,11 +468,61 @@ impl ResourceService {
             }
         };
 
+        // 事务内按文件哈希加锁后复查，避免相同内容的并发上传都通过前面的检查
+        match Self::lock_and_find_approved_by_hash(&mut tx, &file_hash).await {
+            Ok(None) => {}
+            Ok(Some(existing)) => {
+                log::info!(
+                    "[Resource] 并发上传内容与已有资源重复 | user_id={}, existing_id={}",
+                    user.id,
+                    existing.id
+                );
+                if let Err(rollback_err) = tx.rollback().await {
+                    log::error!("[Resource] 回滚事务失败 | error={}", rollback_err);
+                }
+                if let Err(cleanup_err) = storage.delete_file(&file_path).await {
+                    log::error!(
+                        "[Resource] 重复上传后清理文件出错 | path={}, error={}",
+                        file_path,
+                        cleanup_err
+                    );
+                }
+                return Err(ResourceError::Duplicate(existing));
+            }
+            Err(e) => {
+                if let Err(cleanup_err) = storage.delete_file(&file_path).await {
+                    log::error!(
+                        "[Resource] 重复检查失败后清理文件出错 | path={}, error={}",
+                        file_path,
+                        cleanup_err
+                    );
+                }
+                return Err(ResourceError::DatabaseError(format!("检查重复资源失败: {}", e)));
+            }
+        }
+
+        // 事务内锁定上传者后复查配额，避免同一用户的并发上传合计超出配额
+        if let Err(e) =
+            StorageQuotaService::lock_and_ensure_quota(&mut tx, user.id, file_size).await
+        {
+            if let Err(rollback_err) = tx.rollback().await {
+                log::error!("[Resource] 回滚事务失败 | error={}", rollback_err);
+            }
+            if let Err(cleanup_err) = storage.delete_file(&file_path).await {
+                log::error!(
+                    "[Resource] 超出配额后清理文件出错 | path={}, error={}",
+                    file_path,
+                    cleanup_err
+                );
+            }
+            return Err(e.into());
+        }
+
         // 插入资源记录
         log::debug!(
             "[Resource] 准备插入资源记录 | title={}, resource_type={}",
             request.title,
-            resource_type.to_string()
+            resource_type
         );
 
         let resource: Resource = match sqlx::query_as::<_, Resource>(
@@ -398,9 +530,10 @@ impl ResourceService {
             INSERT INTO resources (
                 id, title, author_id, uploader_id, course_name,
                 resource_type, category, tags, file_path, source_file_path,
-                file_hash, file_size, content_accuracy, audit_status, ai_reject_reason, storage_type
+                file_hash, file_size, content_accuracy, audit_status, ai_reject_reason, storage_type,
+                description
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
             RETURNING *
             "#,
         )
@@ -424,6 +557,7 @@ impl ResourceService {
             ai_result.reason.as_deref()
         })
         .bind(&storage_type)
+        .bind(request.description.as_deref())
         .fetch_one(&mut *tx)
         .await
         {
@@ -553,6 +687,11 @@ impl ResourceService {
 
             return Err(ResourceError::DatabaseError(format!("提交事务失败: {}", e)));
         }
+
+        // 建立全文检索索引（需在关联信息写入后进行）
+        SearchService::refresh_resource_index_quietly(pool, resource_id, content_text.as_deref())
+            .await;
+
         Ok(UploadResourceResponse {
             id: resource.id,
             title: resource.title,
@@ -567,6 +706,151 @@ impl ResourceService {
         })
     }
 
+    const FIND_APPROVED_BY_HASH_SQL: &'static str = r#"
+        SELECT id, title, file_hash, created_at
+        FROM resources
+        WHERE file_hash = $1 AND audit_status = 'approved'
+        ORDER BY created_at ASC
+        LIMIT 1
+    "#;
+
+    /// 按文件哈希查找已通过审核的资源（最早上传的一份）
+    pub async fn find_approved_by_hash(
+        pool: &PgPool,
+        file_hash: &str,
+    ) -> Result<Option<DuplicateResourceInfo>, ResourceError> {
+        sqlx::query_as::<_, DuplicateResourceInfo>(Self::FIND_APPROVED_BY_HASH_SQL)
+            .bind(file_hash)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))
+    }
+
+    /// 在事务内对文件哈希加事务级咨询锁后再查一次重复资源
+    /// 锁在事务结束时释放，相同内容的并发上传会在这里排队，
+    /// 后到的一方能看到先提交的资源
+    async fn lock_and_find_approved_by_hash(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        file_hash: &str,
+    ) -> Result<Option<DuplicateResourceInfo>, sqlx::Error> {
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind(file_hash)
+            .execute(&mut **tx)
+            .await?;
+
+        sqlx::query_as::<_, DuplicateResourceInfo>(Self::FIND_APPROVED_BY_HASH_SQL)
+            .bind(file_hash)
+            .fetch_optional(&mut **tx)
+            .await
+    }
+
+    /// 为已有资源追加教师/课程关联（只增不删）
+    /// 上传者和管理员可直接追加；其他用户需提供与资源一致的文件哈希，
+    /// 证明其持有相同文件（即重复上传被拦截的用户）
+    /// 返回资源标题
+    pub async fn attach_resource_relations(
+        pool: &PgPool,
+        user: &CurrentUser,
+        resource_id: Uuid,
+        request: &AttachResourceRelationsRequest,
+    ) -> Result<String, ResourceError> {
+        request.validate().map_err(ResourceError::ValidationError)?;
+
+        let (title, uploader_id, file_hash, audit_status) =
+            sqlx::query_as::<_, (String, Uuid, Option<String>, String)>(
+                "SELECT title, uploader_id, file_hash, audit_status FROM resources WHERE id = $1",
+            )
+            .bind(resource_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| ResourceError::NotFound(format!("资源 {} 不存在", resource_id)))?;
+
+        let is_owner = uploader_id == user.id || user.is_admin();
+        if !is_owner {
+            let hash_matches = audit_status == "approved"
+                && file_hash.is_some()
+                && request
+                    .file_hash
+                    .as_deref()
+                    .map(|h| h.eq_ignore_ascii_case(file_hash.as_deref().unwrap_or_default()))
+                    .unwrap_or(false);
+            if !hash_matches {
+                return Err(ResourceError::Unauthorized(
+                    "只有资源上传者、管理员或上传了相同文件的用户可以追加关联".to_string(),
+                ));
+            }
+        }
+
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| ResourceError::DatabaseError(format!("开启事务失败: {}", e)))?;
+
+        for teacher_sn in &request.teacher_sns {
+            sqlx::query(
+                "INSERT INTO resource_teachers (resource_id, teacher_sn) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+            )
+            .bind(resource_id)
+            .bind(teacher_sn)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                log::warn!(
+                    "[Resource] 追加教师关联失败 | resource_id={}, teacher_sn={}, error={}",
+                    resource_id,
+                    teacher_sn,
+                    e
+                );
+                if is_foreign_key_violation(&e) {
+                    ResourceError::ValidationError(format!("教师 {} 不存在", teacher_sn))
+                } else {
+                    ResourceError::DatabaseError(format!("追加教师关联失败: {}", e))
+                }
+            })?;
+        }
+
+        for course_sn in &request.course_sns {
+            sqlx::query(
+                "INSERT INTO resource_courses (resource_id, course_sn) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+            )
+            .bind(resource_id)
+            .bind(course_sn)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                log::warn!(
+                    "[Resource] 追加课程关联失败 | resource_id={}, course_sn={}, error={}",
+                    resource_id,
+                    course_sn,
+                    e
+                );
+                if is_foreign_key_violation(&e) {
+                    ResourceError::ValidationError(format!("课程 {} 不存在", course_sn))
+                } else {
+                    ResourceError::DatabaseError(format!("追加课程关联失败: {}", e))
+                }
+            })?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| ResourceError::DatabaseError(format!("提交事务失败: {}", e)))?;
+
+        // 关联的教师/课程名称参与检索，需要重建索引
+        SearchService::refresh_resource_index_quietly(pool, resource_id, None).await;
+
+        log::info!(
+            "[Resource] 追加资源关联成功 | resource_id={}, user_id={}, teachers={}, courses={}",
+            resource_id,
+            user.id,
+            request.teacher_sns.len(),
+            request.course_sns.len()
+        );
+
+        Ok(title)
+    }
+
     /// 获取资源详情
     pub async fn get_resource_detail(
         pool: &PgPool,
@@ -682,7 +966,7 @@ impl ResourceService {
             resource_type: resource.resource_type,
             category: resource.category,
             tags,
-            description: None, // 暂不支持描述字段
+            description: resource.description,
             file_size: resource.file_size,
             audit_status: resource.audit_status,
             created_at: resource.created_at,
@@ -936,11 +1220,46 @@ impl ResourceService {
                     .ok()
                     .flatten()
                     .unwrap_or_else(|| "local".to_string()),
+                snippet: None,
             });
         }
         Ok(resources)
     }
 
+    /// 按给定顺序获取已通过审核的资源列表项（不存在或未通过审核的资源会被跳过）
+    pub async fn get_resource_items_by_ids(
+        pool: &PgPool,
+        ids: &[Uuid],
+    ) -> Result<Vec<ResourceListItem>, ResourceError> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let rows = sqlx::query(
+            r#"
+            SELECT r.*, rs.views, rs.downloads, rs.likes,
+                   rs.difficulty_total, rs.difficulty_count,
+                   rs.overall_quality_total, rs.overall_quality_count,
+                   rs.answer_quality_total, rs.answer_quality_count,
+                   rs.format_quality_total, rs.format_quality_count,
+                   rs.detail_level_total, rs.detail_level_count,
+                   u.username as uploader_name
+            FROM resources r
+            LEFT JOIN resource_stats rs ON r.id = rs.resource_id
+            LEFT JOIN users u ON r.uploader_id = u.id
+            WHERE r.id = ANY($1) AND r.audit_status = 'approved'
+            "#,
+        )
+        .bind(ids)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        let mut resources = Self::map_rows_to_resources(rows)?;
+        resources.sort_by_key(|item| ids.iter().position(|id| *id == item.id));
+        Ok(resources)
+    }
+
     /// 计算平均分辅助函数
     fn calc_avg(total: Option<i32>, count: Option<i32>) -> Option<f64> {
         match (total, count) {
@@ -950,7 +1269,8 @@ impl ResourceService {
     }
 
     /// 搜索资源
-    /// 使用 QueryBuilder 构建动态查询，避免字符串拼接
+    /// 基于 PostgreSQL 全文检索（jieba 分词），覆盖标题、描述、标签、课程/教师名称和文件正文，
+    /// 按相关度排序并返回命中摘要
     pub async fn search_resources(
         pool: &PgPool,
         query: &ResourceSearchQuery,
@@ -959,42 +1279,27 @@ impl ResourceService {
         let per_page = query.get_per_page();
         let offset = (page - 1) * per_page;
 
-        let search_pattern = format!("%{}%", query.q);
-
-        // 判断是否需要关联表
-        let need_teacher_join = !query.teacher_sns.is_empty();
-        let need_course_join = !query.course_sns.is_empty();
+        // 分词并构造 tsquery，没有可检索的词时直接返回空结果
+        let terms = SearchService::query_terms(&query.q);
+        let ts_query = match SearchService::build_tsquery(&terms) {
+            Some(q) => q,
+            None => {
+                return Ok(ResourceListResponse {
+                    resources: Vec::new(),
+                    total: 0,
+                    page,
+                    per_page,
+                })
+            }
+        };
 
         // 使用 QueryBuilder 构建 COUNT 查询
         let mut count_builder = sqlx::QueryBuilder::new(
-            "SELECT COUNT(DISTINCT r.id) FROM resources r WHERE r.audit_status = 'approved' AND (r.title ILIKE "
+            "SELECT COUNT(*) FROM resources r WHERE r.audit_status = 'approved' AND r.search_vector @@ to_tsquery('simple', "
         );
-        count_builder.push_bind(&search_pattern);
-        count_builder.push(" OR r.course_name ILIKE ");
-        count_builder.push_bind(&search_pattern);
+        count_builder.push_bind(&ts_query);
         count_builder.push(")");
-
-        // 添加关联表筛选条件
-        if need_teacher_join {
-            count_builder.push(" AND EXISTS (SELECT 1 FROM resource_teachers rt WHERE rt.resource_id = r.id AND rt.teacher_sn = ANY(");
-            count_builder.push_bind(&query.teacher_sns);
-            count_builder.push("))");
-        }
-
-        if need_course_join {
-            count_builder.push(" AND EXISTS (SELECT 1 FROM resource_courses rc WHERE rc.resource_id = r.id AND rc.course_sn = ANY(");
-            count_builder.push_bind(&query.course_sns);
-            count_builder.push("))");
-        }
-
-        // 处理资源类型筛选（支持合并类型）
-        Self::add_resource_type_condition(&mut count_builder, query.resource_type.as_deref());
-
-        // 处理分类筛选
-        if let Some(ref category) = query.category {
-            count_builder.push(" AND r.category = ");
-            count_builder.push_bind(category);
-        }
+        Self::add_search_filters(&mut count_builder, query);
 
         let total: i64 = count_builder
             .build_query_scalar()
@@ -1003,50 +1308,35 @@ impl ResourceService {
             .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
 
         // 使用 QueryBuilder 构建搜索查询
+        // 不取 search_vector 和 content_text，避免传输大字段；正文摘要之后按命中位置单独截取
         let mut search_builder = sqlx::QueryBuilder::new(
             r#"
-            SELECT r.*, rs.views, rs.downloads, rs.likes,
+            SELECT r.id, r.title, r.course_name, r.resource_type, r.category, r.tags,
+                   r.audit_status, r.created_at, r.storage_type, r.description,
+                   rs.views, rs.downloads, rs.likes,
                    rs.difficulty_total, rs.difficulty_count,
                    rs.overall_quality_total, rs.overall_quality_count,
                    rs.answer_quality_total, rs.answer_quality_count,
                    rs.format_quality_total, rs.format_quality_count,
                    rs.detail_level_total, rs.detail_level_count,
-                   u.username as uploader_name
+                   u.username as uploader_name,
+                   ts_rank_cd(r.search_vector, to_tsquery('simple',
+            "#,
+        );
+        search_builder.push_bind(&ts_query);
+        search_builder.push(
+            r#")) AS rank
             FROM resources r
             LEFT JOIN resource_stats rs ON r.id = rs.resource_id
             LEFT JOIN users u ON r.uploader_id = u.id
-            WHERE r.audit_status = 'approved' AND (r.title ILIKE
-            "#,
+            WHERE r.audit_status = 'approved' AND r.search_vector @@ to_tsquery('simple', "#,
         );
-        search_builder.push_bind(&search_pattern);
-        search_builder.push(" OR r.course_name ILIKE ");
-        search_builder.push_bind(&search_pattern);
+        search_builder.push_bind(&ts_query);
         search_builder.push(")");
+        Self::add_search_filters(&mut search_builder, query);
 
-        // 添加关联表筛选条件
-        if need_teacher_join {
-            search_builder.push(" AND EXISTS (SELECT 1 FROM resource_teachers rt WHERE rt.resource_id = r.id AND rt.teacher_sn = ANY(");
-            search_builder.push_bind(&query.teacher_sns);
-            search_builder.push("))");
-        }
-
-        if need_course_join {
-            search_builder.push(" AND EXISTS (SELECT 1 FROM resource_courses rc WHERE rc.resource_id = r.id AND rc.course_sn = ANY(");
-            search_builder.push_bind(&query.course_sns);
-            search_builder.push("))");
-        }
-
-        // 处理资源类型筛选
-        Self::add_resource_type_condition(&mut search_builder, query.resource_type.as_deref());
-
-        // 处理分类筛选
-        if let Some(ref category) = query.category {
-            search_builder.push(" AND r.category = ");
-            search_builder.push_bind(category);
-        }
-
-        // 添加排序和分页
-        search_builder.push(" ORDER BY r.created_at DESC LIMIT ");
+        // 按相关度排序，相关度相同时新资源优先
+        search_builder.push(" ORDER BY rank DESC, r.created_at DESC LIMIT ");
         search_builder.push_bind(per_page as i64);
         search_builder.push(" OFFSET ");
         search_builder.push_bind(offset as i64);
@@ -1057,7 +1347,46 @@ impl ResourceService {
             .await
             .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
 
-        let resources = Self::map_rows_to_resources(rows)?;
+        let ids: Vec<Uuid> = rows
+            .iter()
+            .filter_map(|row| row.try_get::<Uuid, _>("id").ok())
+            .collect();
+        let mut excerpts = SearchService::content_excerpts(pool, &ids, &terms)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        // 按描述、正文、课程名、标签、标题的顺序寻找命中位置生成摘要
+        let snippets: Vec<Option<String>> = rows
+            .iter()
+            .map(|row| {
+                let tags_text = row
+                    .try_get::<Option<serde_json::Value>, _>("tags")
+                    .ok()
+                    .flatten()
+                    .and_then(|t| serde_json::from_value::<Vec<String>>(t).ok())
+                    .map(|tags| tags.join(" "));
+                let excerpt = row
+                    .try_get::<Uuid, _>("id")
+                    .ok()
+                    .and_then(|id| excerpts.remove(&id));
+                let column = |name| row.try_get::<Option<String>, _>(name).ok().flatten();
+                [
+                    column("description"),
+                    excerpt,
+                    column("course_name"),
+                    tags_text,
+                    column("title"),
+                ]
+                .into_iter()
+                .flatten()
+                .find_map(|text| SearchService::build_snippet(&text, &terms))
+            })
+            .collect();
+
+        let mut resources = Self::map_rows_to_resources(rows)?;
+        for (resource, snippet) in resources.iter_mut().zip(snippets) {
+            resource.snippet = snippet;
+        }
 
         Ok(ResourceListResponse {
             resources,
@@ -1067,12 +1396,39 @@ impl ResourceService {
         })
     }
 
+    /// 辅助方法：为搜索查询添加教师、课程、类型和分类筛选条件
+    fn add_search_filters<'a>(
+        builder: &mut sqlx::QueryBuilder<'a, sqlx::Postgres>,
+        query: &'a ResourceSearchQuery,
+    ) {
+        if !query.teacher_sns.is_empty() {
+            builder.push(" AND EXISTS (SELECT 1 FROM resource_teachers rt WHERE rt.resource_id = r.id AND rt.teacher_sn = ANY(");
+            builder.push_bind(&query.teacher_sns);
+            builder.push("))");
+        }
+
+        if !query.course_sns.is_empty() {
+            builder.push(" AND EXISTS (SELECT 1 FROM resource_courses rc WHERE rc.resource_id = r.id AND rc.course_sn = ANY(");
+            builder.push_bind(&query.course_sns);
+            builder.push("))");
+        }
+
+        // 处理资源类型筛选（支持合并类型）
+        Self::add_resource_type_condition(builder, query.resource_type.as_deref());
+
+        // 处理分类筛选
+        if let Some(ref category) = query.category {
+            builder.push(" AND r.category = ");
+            builder.push_bind(category);
+        }
+    }
+
     /// 删除资源
     /// 返回被删除资源的标题
     pub async fn delete_resource(
         pool: &PgPool,
         user: &CurrentUser,
-        storage: &Arc<dyn super::StorageBackend>,
+        storages: &super::StorageRegistry,
         resource_id: Uuid,
     ) -> Result<String, ResourceError> {
         // 获取资源信息
@@ -1085,13 +1441,29 @@ impl ResourceService {
                 .ok_or_else(|| ResourceError::NotFound(format!("资源 {} 不存在", resource_id)))?;
 
         // 检查权限（上传者或管理员）
-        if resource.uploader_id != user.id && user.role != crate::models::UserRole::Admin {
+        if resource.uploader_id != user.id && !user.is_admin() {
             return Err(ResourceError::Unauthorized(
                 "没有权限删除此资源".to_string(),
             ));
         }
 
-        // 删除文件
+        let storage = storages.for_storage_type(resource.storage_type.as_deref())?;
+        let version_files =
+            ResourceVersionService::list_version_files(pool, resource_id, &resource.file_path)
+                .await?;
+
+        // 保存资源标题用于返回
+        let title = resource.title.clone();
+
+        // 先删除数据库记录再删除文件：文件删除失败只会留下孤儿文件，由存储一致性检查清理；
+        // 先删文件则可能在删除记录失败时留下指向不存在文件的记录
+        sqlx::query("DELETE FROM resources WHERE id = $1")
+            .bind(resource_id)
+            .execute(pool)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        // 删除文件（从资源所在的存储中删除）
         if let Err(e) = storage.delete_file(&resource.file_path).await {
             log::warn!(
                 "[Resource] 删除资源文件失败 | resource_id={}, path={}, error={}",
@@ -1099,7 +1471,6 @@ impl ResourceService {
                 resource.file_path,
                 e
             );
-            // 继续执行，即使文件删除失败也要删除数据库记录
         }
 
         // 删除源文件（如果存在）
@@ -1114,15 +1485,8 @@ impl ResourceService {
             }
         }
 
-        // 保存资源标题用于返回
-        let title = resource.title.clone();
-
-        // 删除数据库记录
-        sqlx::query("DELETE FROM resources WHERE id = $1")
-            .bind(resource_id)
-            .execute(pool)
-            .await
-            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+        // 删除历史版本文件
+        ResourceVersionService::delete_version_files(storages, resource_id, &version_files).await;
 
         Ok(title)
     }
@@ -1282,6 +1646,7 @@ impl ResourceService {
                     .ok()
                     .flatten()
                     .unwrap_or_else(|| "local".to_string()),
+                snippet: None,
             });
         }
 
@@ -1342,7 +1707,7 @@ impl ResourceService {
         let uploader_id = row.5;
 
         // 检查权限：非管理员且非上传者时，只能访问已通过审核的资源
-        let is_admin = matches!(user.role, crate::models::UserRole::Admin);
+        let is_admin = user.is_admin();
         let is_uploader = user.id == uploader_id;
 
         if audit_status != "approved" && !is_admin && !is_uploader {
@@ -1375,7 +1740,7 @@ impl ResourceService {
         let uploader_id = row.5;
 
         // 检查权限：非管理员且非上传者时，只能访问已通过审核的资源
-        let is_admin = matches!(user.role, crate::models::UserRole::Admin);
+        let is_admin = user.is_admin();
         let is_uploader = user.id == uploader_id;
 
         if audit_status != "approved" && !is_admin && !is_uploader {
@@ -1411,15 +1776,23 @@ impl ResourceService {
         Ok(())
     }
 
-    /// 更新资源内容（用于Markdown在线编辑）
-    /// 更新后会进行AI审核，并更新 file_hash、file_size、updated_at 字段
+    /// 更新资源内容（用于Markdown在线编辑，上传者、管理员和协作者可用）
+    /// 新内容作为资源的新版本保存（见 ResourceVersionService），旧内容保留在版本历史中。
+    /// 请求必须携带 expectedUpdatedAt 做乐观锁检查，资源已被他人修改则返回 Conflict
     pub async fn update_resource_content(
         pool: &PgPool,
         user: &CurrentUser,
-        storage: &Arc<dyn super::StorageBackend>,
+        storages: &super::StorageRegistry,
+        moderation: &Arc<dyn ModerationProvider>,
         resource_id: Uuid,
-        content: String,
+        request: crate::models::UpdateResourceContentRequest,
     ) -> Result<crate::models::UpdateResourceContentResponse, ResourceError> {
+        let crate::models::UpdateResourceContentRequest {
+            content,
+            change_note,
+            expected_updated_at,
+        } = request;
+
         // 验证内容长度
         if content.len() > 10 * 1024 * 1024 {
             return Err(ResourceError::ValidationError(
@@ -1436,8 +1809,8 @@ impl ResourceService {
                 .map_err(|e| ResourceError::DatabaseError(e.to_string()))?
                 .ok_or_else(|| ResourceError::NotFound(format!("资源 {} 不存在", resource_id)))?;
 
-        // 检查权限（上传者或管理员）
-        if resource.uploader_id != user.id && user.role != crate::models::UserRole::Admin {
+        // 检查权限（上传者、管理员或协作者）
+        if !ResourceEditorService::can_edit(pool, user, &resource).await? {
             return Err(ResourceError::Unauthorized(
                 "没有权限编辑此资源".to_string(),
             ));
@@ -1450,121 +1823,50 @@ impl ResourceService {
             ));
         }
 
-        // AI 审核更新后的内容
-        let ai_result =
-            AiService::audit_resource(&resource.title, Some(&content), Some(content.as_bytes()))
-                .await
-                .map_err(|e| ResourceError::AiError(e.to_string()))?;
-
-        // 根据资源实际的存储类型选择正确的存储后端写入文件
-        let is_oss = resource.storage_type.as_deref() == Some("oss");
-        if is_oss {
-            // OSS 存储
-            if storage.backend_type() == super::StorageBackendType::Oss {
-                storage
-                    .write_file(
-                        &resource.file_path,
-                        content.as_bytes().to_vec(),
-                        Some("text/markdown"),
-                    )
-                    .await?;
-            } else {
-                // 当前是 local 模式，但需要写入 OSS 文件
-                let config = crate::config::Config::from_env();
-                match super::create_storage_backend(&config) {
-                    Ok(oss_storage) if oss_storage.backend_type() == super::StorageBackendType::Oss => {
-                        oss_storage
-                            .write_file(
-                                &resource.file_path,
-                                content.as_bytes().to_vec(),
-                                Some("text/markdown"),
-                            )
-                            .await?;
-                    }
-                    _ => return Err(ResourceError::FileError("无法写入 OSS 资源".to_string())),
-                }
-            }
-        } else {
-            // 本地存储
-            if storage.backend_type() == super::StorageBackendType::Local {
-                storage
-                    .write_file(
-                        &resource.file_path,
-                        content.as_bytes().to_vec(),
-                        Some("text/markdown"),
-                    )
-                    .await?;
-            } else {
-                // 当前是 OSS 模式，但需要写入本地文件
-                let config = crate::config::Config::from_env();
-                match super::create_local_storage(&config) {
-                    Ok(local_storage) => {
-                        local_storage
-                            .write_file(
-                                &resource.file_path,
-                                content.as_bytes().to_vec(),
-                                Some("text/markdown"),
-                            )
-                            .await?;
-                    }
-                    Err(e) => return Err(ResourceError::FileError(format!("无法访问本地存储: {}", e))),
-                }
-            }
+        // 乐观锁：编辑期间资源已被他人保存过
+        if expected_updated_at != resource.updated_at {
+            return Err(ResourceError::Conflict(
+                "资源已被他人修改，请刷新后重试".to_string(),
+            ));
         }
 
-        // 计算新的文件哈希和大小（使用字节长度而非字符长度）
+        // 内容未变化时不产生新版本
         let file_hash = crate::services::FileService::calculate_hash(content.as_bytes());
-        let file_size = content.as_bytes().len() as i64;
-
-        // 确定审核状态
-        let audit_status = if ai_result.passed {
-            AuditStatus::Approved
-        } else {
-            AuditStatus::Pending
-        };
+        if resource.file_hash.as_deref() == Some(file_hash.as_str()) {
+            let version_number =
+                ResourceVersionService::current_version_number(pool, resource_id).await?;
+            return Ok(crate::models::UpdateResourceContentResponse {
+                id: resource_id,
+                updated_at: resource.updated_at,
+                version_number,
+            });
+        }
 
-        // 更新数据库中的 updated_at、file_hash、file_size、audit_status、content_accuracy
-        let updated_at = sqlx::query_scalar::<_, chrono::NaiveDateTime>(
-            r#"
-            UPDATE resources
-            SET
-                updated_at = CURRENT_TIMESTAMP,
-                file_hash = $1,
-                file_size = $2,
-                audit_status = $3,
-                content_accuracy = $4,
-                ai_reject_reason = $5
-            WHERE id = $6
-            RETURNING updated_at
-            "#,
+        let version = ResourceVersionService::create_version(
+            pool,
+            user,
+            storages,
+            moderation,
+            &resource,
+            content.into_bytes(),
+            change_note.as_deref(),
         )
-        .bind(file_hash)
-        .bind(file_size)
-        .bind(audit_status.to_string())
-        .bind(ai_result.accuracy_score)
-        .bind(if ai_result.passed {
-            None
-        } else {
-            ai_result.reason
-        })
-        .bind(resource_id)
-        .fetch_one(pool)
-        .await
-        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+        .await?;
 
         Ok(crate::models::UpdateResourceContentResponse {
             id: resource_id,
-            updated_at,
+            updated_at: version.updated_at,
+            version_number: version.version_number,
         })
     }
 
     /// 获取资源原始内容（用于编辑）
     pub async fn get_resource_content_raw(
         pool: &PgPool,
-        storage: &Arc<dyn super::StorageBackend>,
+        storages: &super::StorageRegistry,
         user: &CurrentUser,
         resource_id: Uuid,
-    ) -> Result<String, ResourceError> {
+    ) -> Result<crate::models::ResourceRawContentResponse, ResourceError> {
         // 获取资源信息
         let resource: crate::models::Resource =
             sqlx::query_as::<_, crate::models::Resource>("SELECT * FROM resources WHERE id = $1")
@@ -1574,47 +1876,30 @@ impl ResourceService {
                 .map_err(|e| ResourceError::DatabaseError(e.to_string()))?
                 .ok_or_else(|| ResourceError::NotFound(format!("资源 {} 不存在", resource_id)))?;
 
-        // 检查权限（上传者或管理员）
-        if resource.uploader_id != user.id && user.role != crate::models::UserRole::Admin {
+        // 检查权限（上传者、管理员或协作者）
+        if !ResourceEditorService::can_edit(pool, user, &resource).await? {
             return Err(ResourceError::Unauthorized(
                 "没有权限查看此资源的原始内容".to_string(),
             ));
         }
 
         // 根据资源实际的存储类型选择正确的存储后端读取文件
-        let is_oss = resource.storage_type.as_deref() == Some("oss");
-        let content_bytes = if is_oss {
-            // OSS 存储
-            if storage.backend_type() == super::StorageBackendType::Oss {
-                storage.read_file(&resource.file_path).await?
-            } else {
-                // 当前是 local 模式，但需要读取 OSS 文件
-                let config = crate::config::Config::from_env();
-                match super::create_storage_backend(&config) {
-                    Ok(oss_storage) if oss_storage.backend_type() == super::StorageBackendType::Oss => {
-                        oss_storage.read_file(&resource.file_path).await?
-                    }
-                    _ => return Err(ResourceError::FileError("无法读取 OSS 资源".to_string())),
-                }
-            }
-        } else {
-            // 本地存储
-            if storage.backend_type() == super::StorageBackendType::Local {
-                storage.read_file(&resource.file_path).await?
-            } else {
-                // 当前是 OSS 模式，但需要读取本地文件
-                let config = crate::config::Config::from_env();
-                match super::create_local_storage(&config) {
-                    Ok(local_storage) => local_storage.read_file(&resource.file_path).await?,
-                    Err(e) => return Err(ResourceError::FileError(format!("无法访问本地存储: {}", e))),
-                }
-            }
-        };
+        let content_bytes = storages
+            .for_storage_type(resource.storage_type.as_deref())?
+            .read_file(&resource.file_path)
+            .await?;
 
         let content = String::from_utf8(content_bytes)
             .map_err(|e| ResourceError::FileError(format!("文件内容不是有效 UTF-8: {}", e)))?;
+        let version_number =
+            ResourceVersionService::current_version_number(pool, resource_id).await?;
 
-        Ok(content)
+        // 返回 updated_at 供保存时做乐观锁检查
+        Ok(crate::models::ResourceRawContentResponse {
+            content,
+            updated_at: resource.updated_at,
+            version_number,
+        })
     }
 
     /// 获取热门资源列表
@@ -1624,7 +1909,7 @@ impl ResourceService {
         pool: &PgPool,
         limit: i32,
     ) -> Result<Vec<crate::models::HotResourceItem>, ResourceError> {
-        let limit = limit.max(1).min(20);
+        let limit = limit.clamp(1, 20);
 
         log::info!("获取热门资源，限制数量: {}", limit);
 
@@ -1808,6 +2093,9 @@ impl ResourceService {
             return Err(ResourceError::DatabaseError(format!("提交事务失败: {}", e)));
         }
 
+        // 关联的教师/课程名称参与检索，需要重建索引
+        SearchService::refresh_resource_index_quietly(pool, resource_id, None).await;
+
         log::info!(
             "[Resource] 资源关联信息更新成功 | resource_id={}, teachers={}, courses={}, related_resources={}",
             resource_id,
@@ -1829,7 +2117,7 @@ impl ResourceService {
         exclude_id: Option<Uuid>,
         limit: i32,
     ) -> Result<Vec<crate::models::RelatedResourceInfo>, ResourceError> {
-        let limit = limit.max(1).min(20);
+        let limit = limit.clamp(1, 20);
         let search_pattern = format!("%{}%", query);
 
         let mut builder = sqlx::QueryBuilder::new(
@@ -1893,3 +2181,207 @@ impl ResourceService {
         Ok(resources)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{
+        cleanup_test_user, create_test_resource, create_test_user, temp_storage_dir, test_pool,
+    };
+    use crate::models::UserRole;
+    use crate::services::{
+        ChainedModerationProvider, LocalStorage, StorageBackend, StorageRegistry,
+    };
+
+    fn random_hash() -> String {
+        format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+    }
+
+    fn upload_request() -> UploadResourceRequest {
+        UploadResourceRequest {
+            title: "去重测试资源".to_string(),
+            course_name: None,
+            resource_type: ResourceType::Txt,
+            category: ResourceCategory::Other,
+            tags: None,
+            description: None,
+            teacher_sns: None,
+            course_sns: None,
+            related_resource_ids: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_find_approved_by_hash_ignores_pending() {
+        let pool = test_pool().await;
+        let user = create_test_user(&pool, UserRole::User).await;
+        let hash = random_hash();
+
+        create_test_resource(&pool, user.id, &hash, "pending").await;
+        let found = ResourceService::find_approved_by_hash(&pool, &hash)
+            .await
+            .unwrap();
+        assert!(found.is_none());
+
+        let first = create_test_resource(&pool, user.id, &hash, "approved").await;
+        create_test_resource(&pool, user.id, &hash, "approved").await;
+        let found = ResourceService::find_approved_by_hash(&pool, &hash)
+            .await
+            .unwrap();
+        assert_eq!(found.map(|r| r.id), Some(first));
+
+        cleanup_test_user(&pool, user.id).await;
+    }
+
+    #[tokio::test]
+    async fn test_admin_without_mfa_cannot_access_pending_resource() {
+        let pool = test_pool().await;
+        let uploader = create_test_user(&pool, UserRole::User).await;
+        let admin = create_test_user(&pool, UserRole::Admin).await;
+        let resource_id = create_test_resource(&pool, uploader.id, &random_hash(), "pending").await;
+
+        assert!(
+            ResourceService::get_resource_file_path(&pool, resource_id, &admin)
+                .await
+                .is_ok()
+        );
+
+        let admin_without_mfa = CurrentUser {
+            mfa: false,
+            ..admin.clone()
+        };
+        assert!(matches!(
+            ResourceService::get_resource_file_path(&pool, resource_id, &admin_without_mfa).await,
+            Err(ResourceError::Unauthorized(_))
+        ));
+
+        cleanup_test_user(&pool, uploader.id).await;
+        cleanup_test_user(&pool, admin.id).await;
+    }
+
+    #[tokio::test]
+    async fn test_update_content_rejects_stale_updated_at() {
+        let pool = test_pool().await;
+        let uploader = create_test_user(&pool, UserRole::User).await;
+        let resource_id =
+            create_test_resource(&pool, uploader.id, &random_hash(), "approved").await;
+        sqlx::query("UPDATE resources SET resource_type = 'web_markdown' WHERE id = $1")
+            .bind(resource_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let storage: Arc<dyn StorageBackend> = Arc::new(LocalStorage::new(
+            temp_storage_dir(),
+            "http://localhost/uploads".to_string(),
+        ));
+        storage
+            .save_file("resources/test.txt", b"# v1".to_vec(), None)
+            .await
+            .unwrap();
+        let storages = StorageRegistry::new(storage);
+        let moderation: Arc<dyn ModerationProvider> =
+            Arc::new(ChainedModerationProvider::new(vec![]));
+
+        let loaded =
+            ResourceService::get_resource_content_raw(&pool, &storages, &uploader, resource_id)
+                .await
+                .unwrap();
+        let request =
+            |content: &str, expected_updated_at| crate::models::UpdateResourceContentRequest {
+                content: content.to_string(),
+                change_note: None,
+                expected_updated_at,
+            };
+
+        let saved = ResourceService::update_resource_content(
+            &pool,
+            &uploader,
+            &storages,
+            &moderation,
+            resource_id,
+            request("# v2", loaded.updated_at),
+        )
+        .await
+        .unwrap();
+        assert_ne!(saved.updated_at, loaded.updated_at);
+
+        // 另一个编辑者仍持有旧的 updatedAt，保存应被拒绝
+        assert!(matches!(
+            ResourceService::update_resource_content(
+                &pool,
+                &uploader,
+                &storages,
+                &moderation,
+                resource_id,
+                request("# v2 from another tab", loaded.updated_at),
+            )
+            .await,
+            Err(ResourceError::Conflict(_))
+        ));
+
+        cleanup_test_user(&pool, uploader.id).await;
+    }
+
+    #[tokio::test]
+    async fn test_upload_rejects_duplicate_content() {
+        let pool = test_pool().await;
+        let user = create_test_user(&pool, UserRole::User).await;
+        let base_path = temp_storage_dir();
+        let storage: Arc<dyn StorageBackend> = Arc::new(LocalStorage::new(
+            base_path.clone(),
+            "http://localhost/uploads".to_string(),
+        ));
+        let moderation: Arc<dyn ModerationProvider> =
+            Arc::new(ChainedModerationProvider::new(vec![]));
+        let content = format!("去重测试 {}", Uuid::new_v4()).into_bytes();
+
+        let upload = |data: Vec<u8>| {
+            let (pool, user, storage, moderation) = (
+                pool.clone(),
+                user.clone(),
+                storage.clone(),
+                moderation.clone(),
+            );
+            async move {
+                let file = UploadedFile {
+                    name: "notes.txt",
+                    data,
+                    mime_type: Some("text/plain"),
+                };
+                ResourceService::upload_resource(
+                    &pool,
+                    &user,
+                    &storage,
+                    &moderation,
+                    upload_request(),
+                    file,
+                )
+                .await
+            }
+        };
+
+        // 相同内容并发上传：事务内加锁复查，只能有一份入库
+        let (a, b) = tokio::join!(upload(content.clone()), upload(content.clone()));
+        let results = [a, b];
+        let created: Vec<_> = results.iter().filter_map(|r| r.as_ref().ok()).collect();
+        assert_eq!(created.len(), 1);
+        let duplicate = results
+            .iter()
+            .find_map(|r| match r {
+                Err(ResourceError::Duplicate(existing)) => Some(existing.id),
+                _ => None,
+            })
+            .expect("第二次上传应被判定为重复");
+        assert_eq!(duplicate, created[0].id);
+
+        // 之后的上传在事务外的预检查就会被拦截
+        assert!(matches!(
+            upload(content).await,
+            Err(ResourceError::Duplicate(_))
+        ));
+
+        cleanup_test_user(&pool, user.id).await;
+        let _ = std::fs::remove_dir_all(base_path);
+    }
+}
@@ -0,0 +1,839 @@
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::models::resource::{AuditStatus, Resource, UploadedFile};
+use crate::models::resource_version::{
+    CreateResourceVersionResponse, ResourceVersion, ResourceVersionDiffQuery,
+    ResourceVersionDiffResponse, ResourceVersionItem, ResourceVersionListResponse,
+};
+use crate::models::CurrentUser;
+use crate::utils::diff_lines;
+
+use super::{
+    AiService, FileService, ModerationProvider, ResourceEditorService, ResourceError,
+    SearchService, StorageBackend, StorageQuotaService, StorageRegistry,
+};
+
+/// 资源版本服务
+///
+/// 每次在线编辑、重新上传或回滚都会把新内容写入新的文件（`resources/{id}_{随机串}.{ext}`），
+/// 并在 resource_versions 中追加一条记录，resources 表始终指向最新版本。
+/// 旧版本文件保留到资源被删除为止。
+pub struct ResourceVersionService;
+
+/// 版本记录及创建者用户名
+#[derive(sqlx::FromRow)]
+struct VersionWithCreator {
+    #[sqlx(flatten)]
+    version: ResourceVersion,
+    created_by_name: Option<String>,
+}
+
+impl ResourceVersionService {
+    /// 获取资源版本列表（最新版本在前）
+    /// 上传者、管理员和协作者以外的用户（包括未登录用户）只能看到通过审核的版本
+    pub async fn list_versions(
+        pool: &PgPool,
+        user: Option<&CurrentUser>,
+        resource_id: Uuid,
+    ) -> Result<ResourceVersionListResponse, ResourceError> {
+        let resource = Self::load_visible_resource(pool, user, resource_id).await?;
+        let can_view_all = Self::can_view_all(pool, user, &resource).await?;
+        Self::ensure_initial_version(pool, resource_id).await?;
+
+        let rows: Vec<VersionWithCreator> = sqlx::query_as(
+            r#"
+            SELECT v.*, u.username AS created_by_name
+            FROM resource_versions v
+            LEFT JOIN users u ON v.created_by = u.id
+            WHERE v.resource_id = $1
+            ORDER BY v.version_number DESC
+            "#,
+        )
+        .bind(resource_id)
+        .fetch_all(pool)
+        .await?;
+
+        let current_version = rows
+            .first()
+            .map(|row| row.version.version_number)
+            .unwrap_or(1);
+
+        let versions = rows
+            .into_iter()
+            .map(|row| {
+                let is_current = row.version.version_number == current_version;
+                let audit_status = if is_current {
+                    resource.audit_status.clone()
+                } else {
+                    row.version.audit_status
+                };
+                ResourceVersionItem {
+                    version_number: row.version.version_number,
+                    resource_type: row.version.resource_type,
+                    file_size: row.version.file_size,
+                    file_hash: row.version.file_hash,
+                    audit_status,
+                    change_note: row.version.change_note,
+                    created_by: row.version.created_by,
+                    created_by_name: row.created_by_name,
+                    created_at: row.version.created_at,
+                    is_current,
+                }
+            })
+            .filter(|item| can_view_all || item.audit_status == "approved")
+            .collect();
+
+        Ok(ResourceVersionListResponse {
+            resource_id,
+            current_version,
+            versions,
+        })
+    }
+
+    /// 获取可下载的版本及资源标题（权限规则同版本列表）
+    pub async fn get_version_file(
+        pool: &PgPool,
+        user: Option<&CurrentUser>,
+        resource_id: Uuid,
+        version_number: i32,
+    ) -> Result<(ResourceVersion, String), ResourceError> {
+        let resource = Self::load_visible_resource(pool, user, resource_id).await?;
+        let version = Self::load_visible_version(pool, user, &resource, version_number).await?;
+        Ok((version, resource.title))
+    }
+
+    /// 比较两个文本版本（Markdown/TXT）的差异，to 为空时与当前版本比较
+    pub async fn diff_versions(
+        pool: &PgPool,
+        storages: &StorageRegistry,
+        user: Option<&CurrentUser>,
+        resource_id: Uuid,
+        query: &ResourceVersionDiffQuery,
+    ) -> Result<ResourceVersionDiffResponse, ResourceError> {
+        let resource = Self::load_visible_resource(pool, user, resource_id).await?;
+        Self::ensure_initial_version(pool, resource_id).await?;
+
+        let to = match query.to {
+            Some(to) => to,
+            None => Self::current_version_number(pool, resource_id).await?,
+        };
+        let from_version = Self::load_visible_version(pool, user, &resource, query.from).await?;
+        let to_version = Self::load_visible_version(pool, user, &resource, to).await?;
+
+        for version in [&from_version, &to_version] {
+            if !Self::is_text_type(&version.resource_type) {
+                return Err(ResourceError::ValidationError(
+                    "只有 Markdown/TXT 类型的版本可以比较差异".to_string(),
+                ));
+            }
+        }
+
+        let old_text = Self::read_version_text(storages, &from_version).await?;
+        let new_text = Self::read_version_text(storages, &to_version).await?;
+        let context = query.get_context();
+
+        // 长文档的差异计算可能较慢，放到阻塞线程池执行
+        let diff = tokio::task::spawn_blocking(move || diff_lines(&old_text, &new_text, context))
+            .await
+            .map_err(|e| ResourceError::FileError(format!("计算版本差异失败: {}", e)))?;
+
+        Ok(ResourceVersionDiffResponse {
+            resource_id,
+            from_version: from_version.version_number,
+            to_version: to_version.version_number,
+            additions: diff.additions,
+            deletions: diff.deletions,
+            hunks: diff.hunks,
+        })
+    }
+
+    /// 重新上传文件作为资源的新版本（上传者或管理员）
+    /// 新文件的类型必须与资源类型一致
+    pub async fn upload_version(
+        pool: &PgPool,
+        user: &CurrentUser,
+        storages: &StorageRegistry,
+        moderation: &Arc<dyn ModerationProvider>,
+        resource_id: Uuid,
+        file: UploadedFile<'_>,
+        change_note: Option<&str>,
+    ) -> Result<CreateResourceVersionResponse, ResourceError> {
+        let resource = Self::load_resource(pool, resource_id).await?;
+        if !Self::can_manage(Some(user), &resource) {
+            return Err(ResourceError::Unauthorized(
+                "没有权限更新此资源".to_string(),
+            ));
+        }
+
+        let resource_type =
+            FileService::validate_resource_file(file.name, &file.data, file.mime_type)?;
+        if resource_type.to_string() != resource.resource_type {
+            return Err(ResourceError::ValidationError(format!(
+                "新版本文件类型必须与原资源一致（{}）",
+                resource.resource_type
+            )));
+        }
+
+        Self::create_version(
+            pool,
+            user,
+            storages,
+            moderation,
+            &resource,
+            file.data,
+            change_note,
+        )
+        .await
+    }
+
+    /// 回滚到指定版本（上传者或管理员）
+    /// 回滚不会删除历史，而是以目标版本的内容创建一个新版本
+    pub async fn rollback(
+        pool: &PgPool,
+        user: &CurrentUser,
+        storages: &StorageRegistry,
+        moderation: &Arc<dyn ModerationProvider>,
+        resource_id: Uuid,
+        version_number: i32,
+    ) -> Result<CreateResourceVersionResponse, ResourceError> {
+        let resource = Self::load_resource(pool, resource_id).await?;
+        if !Self::can_manage(Some(user), &resource) {
+            return Err(ResourceError::Unauthorized(
+                "没有权限回滚此资源".to_string(),
+            ));
+        }
+
+        Self::ensure_initial_version(pool, resource_id).await?;
+        let target = Self::load_version(pool, resource_id, version_number).await?;
+        if version_number == Self::current_version_number(pool, resource_id).await? {
+            return Err(ResourceError::Conflict("该版本已是当前版本".to_string()));
+        }
+        if target.resource_type != resource.resource_type {
+            return Err(ResourceError::ValidationError(
+                "目标版本的文件类型与资源当前类型不一致，无法回滚".to_string(),
+            ));
+        }
+
+        let data = storages
+            .for_storage_type(target.storage_type.as_deref())?
+            .read_file(&target.file_path)
+            .await?;
+        let change_note = format!("回滚到版本 {}", version_number);
+
+        Self::create_version(
+            pool,
+            user,
+            storages,
+            moderation,
+            &resource,
+            data,
+            Some(&change_note),
+        )
+        .await
+    }
+
+    /// 写入新版本：保存文件、冻结上一版本的审核状态、追加版本记录并让资源指向新文件
+    /// `resource` 是调用方读取到的资源快照，内容与当前版本相同，
+    /// 或资源在读取之后已被修改（updated_at 不一致）时返回 Conflict
+    pub async fn create_version(
+        pool: &PgPool,
+        user: &CurrentUser,
+        storages: &StorageRegistry,
+        moderation: &Arc<dyn ModerationProvider>,
+        resource: &Resource,
+        data: Vec<u8>,
+        change_note: Option<&str>,
+    ) -> Result<CreateResourceVersionResponse, ResourceError> {
+        let change_note = change_note.map(str::trim).filter(|note| !note.is_empty());
+        if change_note.is_some_and(|note| note.chars().count() > 200) {
+            return Err(ResourceError::ValidationError(
+                "版本说明不能超过200个字符".to_string(),
+            ));
+        }
+
+        let file_hash = FileService::calculate_hash(&data);
+        if resource.file_hash.as_deref() == Some(file_hash.as_str()) {
+            return Err(ResourceError::Conflict("内容与当前版本相同".to_string()));
+        }
+
+        // 资源占用的是上传者的存储配额（协作者编辑也计入上传者）；
+        // 旧版本文件仍保留并计入用量，因此按新版本的完整大小检查
+        StorageQuotaService::ensure_quota(pool, resource.uploader_id, data.len() as i64).await?;
+
+        Self::ensure_initial_version(pool, resource.id).await?;
+
+        // 提取正文并对新内容做 AI 审核
+        let (data, content_text) =
+            SearchService::extract_text_blocking(resource.resource_type.clone(), data).await?;
+        let ai_result = AiService::audit_resource(
+            moderation,
+            &resource.title,
+            resource.description.as_deref(),
+            content_text.as_deref(),
+        )
+        .await;
+        let audit_status = if ai_result.passed {
+            AuditStatus::Approved
+        } else {
+            AuditStatus::Pending
+        };
+        let file_size = data.len() as i64;
+        // 新版本写入当前配置的存储
+        let storage = storages.active();
+        let storage_type = storage.backend_type().as_str();
+
+        // 先写入文件再锁定资源行，避免在持有行锁时等待存储写入；
+        // 文件名不依赖版本号，写入之后的任何失败都删除该文件
+        let file_key = format!(
+            "resources/{}_{}.{}",
+            resource.id,
+            Uuid::new_v4().simple(),
+            FileService::get_extension_by_type(&resource.resource_type)
+        );
+        let mime_type = FileService::get_mime_type_by_type(&resource.resource_type);
+        let file_path = storage.save_file(&file_key, data, Some(&mime_type)).await?;
+
+        let result: Result<(i32, chrono::NaiveDateTime), ResourceError> = async {
+            let mut tx = pool.begin().await?;
+
+            // 锁定资源行，保证并发提交时版本号连续
+            let (previous_status, updated_at): (String, chrono::NaiveDateTime) = sqlx::query_as(
+                "SELECT audit_status, updated_at FROM resources WHERE id = $1 FOR UPDATE",
+            )
+            .bind(resource.id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| ResourceError::NotFound(format!("资源 {} 不存在", resource.id)))?;
+
+            // 乐观锁：读取资源后如果已有其他人保存了新版本，放弃本次写入
+            if updated_at != resource.updated_at {
+                return Err(ResourceError::Conflict(
+                    "资源已被他人修改，请刷新后重试".to_string(),
+                ));
+            }
+            StorageQuotaService::lock_and_ensure_quota(&mut tx, resource.uploader_id, file_size)
+                .await?;
+            let version_number: i32 = sqlx::query_scalar(
+                "SELECT COALESCE(MAX(version_number), 0) + 1 FROM resource_versions WHERE resource_id = $1",
+            )
+            .bind(resource.id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+            // 上一版本被替代，记录它此刻的审核状态
+            sqlx::query(
+                "UPDATE resource_versions SET audit_status = $1 WHERE resource_id = $2 AND version_number = $3",
+            )
+            .bind(&previous_status)
+            .bind(resource.id)
+            .bind(version_number - 1)
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query(
+                r#"
+                INSERT INTO resource_versions (
+                    resource_id, version_number, resource_type, file_path, storage_type,
+                    file_hash, file_size, audit_status, change_note, created_by
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                "#,
+            )
+            .bind(resource.id)
+            .bind(version_number)
+            .bind(&resource.resource_type)
+            .bind(&file_path)
+            .bind(storage_type)
+            .bind(&file_hash)
+            .bind(file_size)
+            .bind(audit_status.to_string())
+            .bind(change_note)
+            .bind(user.id)
+            .execute(&mut *tx)
+            .await?;
+
+            let updated_at = sqlx::query_scalar(
+                r#"
+                UPDATE resources
+                SET
+                    file_path = $1,
+                    storage_type = $2,
+                    file_hash = $3,
+                    file_size = $4,
+                    audit_status = $5,
+                    content_accuracy = $6,
+                    ai_reject_reason = $7,
+                    updated_at = CURRENT_TIMESTAMP
+                WHERE id = $8
+                RETURNING updated_at
+                "#,
+            )
+            .bind(&file_path)
+            .bind(storage_type)
+            .bind(&file_hash)
+            .bind(file_size)
+            .bind(audit_status.to_string())
+            .bind(ai_result.accuracy_score)
+            .bind(if ai_result.passed {
+                None
+            } else {
+                ai_result.reason.as_deref()
+            })
+            .bind(resource.id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+            tx.commit().await?;
+            Ok((version_number, updated_at))
+        }
+        .await;
+
+        let (version_number, updated_at) = match result {
+            Ok(created) => created,
+            Err(e) => {
+                log::warn!(
+                    "[Resource] 写入版本记录失败 | resource_id={}, error={}",
+                    resource.id,
+                    e
+                );
+                Self::cleanup_file(storage, &file_path).await;
+                return Err(e);
+            }
+        };
+
+        // 新版本可能没有可提取的正文，此时清空旧版本的正文
+        SearchService::refresh_resource_index_quietly(
+            pool,
+            resource.id,
+            Some(content_text.as_deref().unwrap_or_default()),
+        )
+        .await;
+
+        log::info!(
+            "[Resource] 创建资源新版本 | resource_id={}, version={}, user_id={}, audit_status={}",
+            resource.id,
+            version_number,
+            user.id,
+            audit_status
+        );
+
+        Ok(CreateResourceVersionResponse {
+            resource_id: resource.id,
+            title: resource.title.clone(),
+            version_number,
+            audit_status: audit_status.to_string(),
+            ai_message: if ai_result.passed {
+                Some("AI 审核通过".to_string())
+            } else {
+                Some("AI 审核未通过，等待人工审核".to_string())
+            },
+            updated_at,
+        })
+    }
+
+    /// 当前版本号（没有版本记录时先补建初始版本）
+    pub async fn current_version_number(
+        pool: &PgPool,
+        resource_id: Uuid,
+    ) -> Result<i32, ResourceError> {
+        Self::ensure_initial_version(pool, resource_id).await?;
+        let version: Option<i32> = sqlx::query_scalar(
+            "SELECT MAX(version_number) FROM resource_versions WHERE resource_id = $1",
+        )
+        .bind(resource_id)
+        .fetch_one(pool)
+        .await?;
+        version.ok_or_else(|| ResourceError::NotFound(format!("资源 {} 不存在", resource_id)))
+    }
+
+    /// 获取资源的全部历史版本文件（不含与资源共用的当前版本文件）
+    pub async fn list_version_files(
+        pool: &PgPool,
+        resource_id: Uuid,
+        current_file_path: &str,
+    ) -> Result<Vec<(String, Option<String>)>, ResourceError> {
+        let files = sqlx::query_as(
+            "SELECT file_path, storage_type FROM resource_versions WHERE resource_id = $1 AND file_path <> $2",
+        )
+        .bind(resource_id)
+        .bind(current_file_path)
+        .fetch_all(pool)
+        .await?;
+        Ok(files)
+    }
+
+    /// 删除历史版本文件，失败的文件留给存储一致性检查清理
+    pub async fn delete_version_files(
+        storages: &StorageRegistry,
+        resource_id: Uuid,
+        files: &[(String, Option<String>)],
+    ) {
+        for (file_path, storage_type) in files {
+            let result = match storages.for_storage_type(storage_type.as_deref()) {
+                Ok(backend) => backend.delete_file(file_path).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                log::warn!(
+                    "[Resource] 删除历史版本文件失败 | resource_id={}, path={}, error={}",
+                    resource_id,
+                    file_path,
+                    e
+                );
+            }
+        }
+    }
+
+    /// 为还没有版本记录的资源补建初始版本（版本功能上线前创建的资源，或刚上传的资源）
+    async fn ensure_initial_version(pool: &PgPool, resource_id: Uuid) -> Result<(), ResourceError> {
+        sqlx::query(
+            r#"
+            INSERT INTO resource_versions (
+                resource_id, version_number, resource_type, file_path, storage_type,
+                file_hash, file_size, audit_status, created_by, created_at
+            )
+            SELECT id, 1, resource_type, file_path, storage_type,
+                   file_hash, file_size, audit_status, uploader_id, created_at
+            FROM resources r
+            WHERE id = $1
+              AND NOT EXISTS (SELECT 1 FROM resource_versions WHERE resource_id = r.id)
+            ON CONFLICT (resource_id, version_number) DO NOTHING
+            "#,
+        )
+        .bind(resource_id)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn load_resource(pool: &PgPool, resource_id: Uuid) -> Result<Resource, ResourceError> {
+        sqlx::query_as::<_, Resource>("SELECT * FROM resources WHERE id = $1")
+            .bind(resource_id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| ResourceError::NotFound(format!("资源 {} 不存在", resource_id)))
+    }
+
+    /// 获取资源并检查可见性：上传者、管理员和协作者以外的用户只能访问已通过审核的资源
+    async fn load_visible_resource(
+        pool: &PgPool,
+        user: Option<&CurrentUser>,
+        resource_id: Uuid,
+    ) -> Result<Resource, ResourceError> {
+        let resource = Self::load_resource(pool, resource_id).await?;
+        if resource.audit_status != "approved" && !Self::can_view_all(pool, user, &resource).await?
+        {
+            return Err(ResourceError::Unauthorized(
+                "该资源尚未通过审核，无法查看历史版本".to_string(),
+            ));
+        }
+        Ok(resource)
+    }
+
+    async fn load_version(
+        pool: &PgPool,
+        resource_id: Uuid,
+        version_number: i32,
+    ) -> Result<ResourceVersion, ResourceError> {
+        sqlx::query_as::<_, ResourceVersion>(
+            "SELECT * FROM resource_versions WHERE resource_id = $1 AND version_number = $2",
+        )
+        .bind(resource_id)
+        .bind(version_number)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| ResourceError::NotFound(format!("版本 {} 不存在", version_number)))
+    }
+
+    /// 获取版本并检查可见性：上传者、管理员和协作者以外的用户只能访问通过审核的版本
+    async fn load_visible_version(
+        pool: &PgPool,
+        user: Option<&CurrentUser>,
+        resource: &Resource,
+        version_number: i32,
+    ) -> Result<ResourceVersion, ResourceError> {
+        Self::ensure_initial_version(pool, resource.id).await?;
+        let version = Self::load_version(pool, resource.id, version_number).await?;
+        if Self::can_view_all(pool, user, resource).await? {
+            return Ok(version);
+        }
+
+        let is_current = resource.file_path == version.file_path;
+        let audit_status = if is_current {
+            &resource.audit_status
+        } else {
+            &version.audit_status
+        };
+        if audit_status != "approved" {
+            return Err(ResourceError::Unauthorized(
+                "该版本尚未通过审核，无法访问".to_string(),
+            ));
+        }
+        Ok(version)
+    }
+
+    async fn read_version_text(
+        storages: &StorageRegistry,
+        version: &ResourceVersion,
+    ) -> Result<String, ResourceError> {
+        let data = storages
+            .for_storage_type(version.storage_type.as_deref())?
+            .read_file(&version.file_path)
+            .await?;
+        String::from_utf8(data).map_err(|e| {
+            ResourceError::FileError(format!(
+                "版本 {} 内容不是有效 UTF-8: {}",
+                version.version_number, e
+            ))
+        })
+    }
+
+    async fn cleanup_file(storage: &Arc<dyn StorageBackend>, file_path: &str) {
+        if let Err(e) = storage.delete_file(file_path).await {
+            log::error!(
+                "[Resource] 清理新版本文件失败 | path={}, error={}",
+                file_path,
+                e
+            );
+        }
+    }
+
+    fn can_manage(user: Option<&CurrentUser>, resource: &Resource) -> bool {
+        user.is_some_and(|user| resource.uploader_id == user.id || user.is_admin())
+    }
+
+    /// 上传者、管理员和协作者可以查看全部版本（包括未通过审核的）
+    async fn can_view_all(
+        pool: &PgPool,
+        user: Option<&CurrentUser>,
+        resource: &Resource,
+    ) -> Result<bool, ResourceError> {
+        match user {
+            Some(user) => ResourceEditorService::can_edit(pool, user, resource).await,
+            None => Ok(false),
+        }
+    }
+
+    fn is_text_type(resource_type: &str) -> bool {
+        matches!(resource_type, "web_markdown" | "txt")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{
+        cleanup_test_user, create_test_resource, create_test_user, temp_storage_dir, test_pool,
+    };
+    use crate::models::UserRole;
+    use crate::services::{ChainedModerationProvider, LocalStorage};
+
+    struct Fixture {
+        storages: StorageRegistry,
+        moderation: Arc<dyn ModerationProvider>,
+        base_path: String,
+    }
+
+    /// 临时本地存储，写入 create_test_resource 使用的初始文件
+    async fn fixture() -> Fixture {
+        let base_path = temp_storage_dir();
+        let storage: Arc<dyn StorageBackend> = Arc::new(LocalStorage::new(
+            base_path.clone(),
+            "http://localhost/uploads".to_string(),
+        ));
+        storage
+            .save_file("resources/test.txt", b"v1 content".to_vec(), None)
+            .await
+            .unwrap();
+        Fixture {
+            storages: StorageRegistry::new(storage),
+            moderation: Arc::new(ChainedModerationProvider::new(vec![])),
+            base_path,
+        }
+    }
+
+    fn stored_file_count(base_path: &str) -> usize {
+        std::fs::read_dir(format!("{}/resources", base_path))
+            .map(|entries| entries.count())
+            .unwrap_or(0)
+    }
+
+    async fn load(pool: &PgPool, resource_id: Uuid) -> Resource {
+        ResourceVersionService::load_resource(pool, resource_id)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_versions_are_numbered_and_rollback_appends() {
+        let pool = test_pool().await;
+        let fx = fixture().await;
+        let uploader = create_test_user(&pool, UserRole::User).await;
+        let resource_id = create_test_resource(&pool, uploader.id, "hash-v1", "approved").await;
+
+        let stale = load(&pool, resource_id).await;
+        let created = ResourceVersionService::create_version(
+            &pool,
+            &uploader,
+            &fx.storages,
+            &fx.moderation,
+            &stale,
+            b"v2 content".to_vec(),
+            Some("第二版"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(created.version_number, 2);
+        assert_eq!(created.audit_status, "approved");
+
+        // 内容未变化
+        let current = load(&pool, resource_id).await;
+        assert!(matches!(
+            ResourceVersionService::create_version(
+                &pool,
+                &uploader,
+                &fx.storages,
+                &fx.moderation,
+                &current,
+                b"v2 content".to_vec(),
+                None,
+            )
+            .await,
+            Err(ResourceError::Conflict(_))
+        ));
+
+        // 基于过期快照提交：拒绝写入，并删除已写入的文件
+        let files_before = stored_file_count(&fx.base_path);
+        assert!(matches!(
+            ResourceVersionService::create_version(
+                &pool,
+                &uploader,
+                &fx.storages,
+                &fx.moderation,
+                &stale,
+                b"v3 content".to_vec(),
+                None,
+            )
+            .await,
+            Err(ResourceError::Conflict(_))
+        ));
+        assert_eq!(stored_file_count(&fx.base_path), files_before);
+        assert_eq!(
+            ResourceVersionService::current_version_number(&pool, resource_id)
+                .await
+                .unwrap(),
+            2
+        );
+
+        // 回滚以目标版本的内容追加新版本，不删除历史
+        let rolled_back = ResourceVersionService::rollback(
+            &pool,
+            &uploader,
+            &fx.storages,
+            &fx.moderation,
+            resource_id,
+            1,
+        )
+        .await
+        .unwrap();
+        assert_eq!(rolled_back.version_number, 3);
+        let current = load(&pool, resource_id).await;
+        assert_eq!(
+            fx.storages
+                .active()
+                .read_file(&current.file_path)
+                .await
+                .unwrap(),
+            b"v1 content"
+        );
+
+        let versions = ResourceVersionService::list_versions(&pool, Some(&uploader), resource_id)
+            .await
+            .unwrap();
+        assert_eq!(versions.current_version, 3);
+        let numbers: Vec<i32> = versions.versions.iter().map(|v| v.version_number).collect();
+        assert_eq!(numbers, vec![3, 2, 1]);
+        assert_eq!(
+            versions.versions[0].change_note.as_deref(),
+            Some("回滚到版本 1")
+        );
+
+        // 回滚到当前版本没有意义
+        assert!(matches!(
+            ResourceVersionService::rollback(
+                &pool,
+                &uploader,
+                &fx.storages,
+                &fx.moderation,
+                resource_id,
+                3,
+            )
+            .await,
+            Err(ResourceError::Conflict(_))
+        ));
+
+        cleanup_test_user(&pool, uploader.id).await;
+        let _ = std::fs::remove_dir_all(&fx.base_path);
+    }
+
+    #[tokio::test]
+    async fn test_only_uploader_or_admin_can_roll_back() {
+        let pool = test_pool().await;
+        let fx = fixture().await;
+        let uploader = create_test_user(&pool, UserRole::User).await;
+        let other = create_test_user(&pool, UserRole::User).await;
+        let admin = create_test_user(&pool, UserRole::Admin).await;
+        let resource_id = create_test_resource(&pool, uploader.id, "hash-v1", "approved").await;
+
+        let resource = load(&pool, resource_id).await;
+        ResourceVersionService::create_version(
+            &pool,
+            &uploader,
+            &fx.storages,
+            &fx.moderation,
+            &resource,
+            b"v2 content".to_vec(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let admin_without_mfa = CurrentUser {
+            mfa: false,
+            ..admin.clone()
+        };
+        for user in [&other, &admin_without_mfa] {
+            assert!(matches!(
+                ResourceVersionService::rollback(
+                    &pool,
+                    user,
+                    &fx.storages,
+                    &fx.moderation,
+                    resource_id,
+                    1,
+                )
+                .await,
+                Err(ResourceError::Unauthorized(_))
+            ));
+        }
+
+        let rolled_back = ResourceVersionService::rollback(
+            &pool,
+            &admin,
+            &fx.storages,
+            &fx.moderation,
+            resource_id,
+            1,
+        )
+        .await
+        .unwrap();
+        assert_eq!(rolled_back.version_number, 3);
+
+        cleanup_test_user(&pool, uploader.id).await;
+        cleanup_test_user(&pool, other.id).await;
+        cleanup_test_user(&pool, admin.id).await;
+        let _ = std::fs::remove_dir_all(&fx.base_path);
+    }
+}
@@ -0,0 +1,452 @@
+use futures_util::StreamExt;
+use jieba_rs::Jieba;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use uuid::Uuid;
+
+use super::comment_service::escape_html;
+use super::ResourceError;
+
+/// 全文检索服务
+///
+/// PostgreSQL 自带的解析器只能按空白和标点切分，无法处理中文，
+/// 因此统一在后端用 jieba 分词后再交给 `to_tsvector('simple', ...)`。
+/// 写入索引和构造查询使用同一套分词规则，保证词元一致。
+pub struct SearchService;
+
+/// 全局分词器（加载词典开销较大，只初始化一次）
+fn jieba() -> &'static Jieba {
+    static JIEBA: OnceLock<Jieba> = OnceLock::new();
+    JIEBA.get_or_init(Jieba::new)
+}
+
+impl SearchService {
+    /// 提取正文的最大字符数，避免超大文件撑爆索引
+    pub const MAX_CONTENT_CHARS: usize = 100_000;
+    /// 单个词元的最大字符数（tsvector 词元有长度限制）
+    const MAX_TOKEN_CHARS: usize = 64;
+    /// 摘要窗口：命中位置之前/之后保留的字符数
+    const SNIPPET_BEFORE: usize = 30;
+    const SNIPPET_AFTER: usize = 90;
+
+    /// 分词：搜索引擎模式切分，统一小写，去掉标点和空白
+    pub fn segment_tokens(text: &str) -> Vec<String> {
+        jieba()
+            .cut_for_search(text, true)
+            .into_iter()
+            .filter_map(|word| {
+                let token: String = word
+                    .chars()
+                    .filter(|c| c.is_alphanumeric())
+                    .flat_map(char::to_lowercase)
+                    .collect();
+                if token.is_empty() || token.chars().count() > Self::MAX_TOKEN_CHARS {
+                    None
+                } else {
+                    Some(token)
+                }
+            })
+            .collect()
+    }
+
+    /// 分词后以空格拼接，供 `to_tsvector('simple', ...)` 使用
+    pub fn segment(text: &str) -> String {
+        Self::segment_tokens(text).join(" ")
+    }
+
+    /// 提取查询关键词（去重，保持顺序）
+    pub fn query_terms(query: &str) -> Vec<String> {
+        let mut terms: Vec<String> = Vec::new();
+        for token in Self::segment_tokens(query) {
+            if !terms.contains(&token) {
+                terms.push(token);
+            }
+        }
+        terms
+    }
+
+    /// 构造 `to_tsquery('simple', ...)` 的查询表达式
+    /// 所有关键词取交集；纯英文/数字词支持前缀匹配
+    /// 返回 None 表示查询中没有可检索的词
+    pub fn build_tsquery(terms: &[String]) -> Option<String> {
+        if terms.is_empty() {
+            return None;
+        }
+        let expr = terms
+            .iter()
+            .map(|term| {
+                if term.is_ascii() {
+                    format!("{}:*", term)
+                } else {
+                    term.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" & ");
+        Some(expr)
+    }
+
+    /// 从文件中提取可检索的正文（支持 PDF、TXT、Markdown）
+    /// 其他类型返回 None
+    pub fn extract_text(resource_type: &str, data: &[u8]) -> Option<String> {
+        let raw = match resource_type {
+            "pdf" => {
+                // pdf-extract 遇到格式异常的 PDF 可能 panic，这里兜底
+                match std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem(data)) {
+                    Ok(Ok(text)) => text,
+                    Ok(Err(e)) => {
+                        log::warn!("[Search] PDF 正文提取失败 | error={}", e);
+                        return None;
+                    }
+                    Err(_) => {
+                        log::warn!("[Search] PDF 正文提取异常中止");
+                        return None;
+                    }
+                }
+            }
+            "txt" | "web_markdown" => String::from_utf8_lossy(data).into_owned(),
+            _ => return None,
+        };
+
+        // 折叠空白并截断
+        let text = raw
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .chars()
+            .take(Self::MAX_CONTENT_CHARS)
+            .collect::<String>();
+
+        if text.is_empty() {
+            None
+        } else {
+            Some(text)
+        }
+    }
+
+    /// 在阻塞线程池中提取正文
+    /// 文件数据会原样返回，便于调用方继续保存
+    pub async fn extract_text_blocking(
+        resource_type: String,
+        data: Vec<u8>,
+    ) -> Result<(Vec<u8>, Option<String>), ResourceError> {
+        tokio::task::spawn_blocking(move || {
+            let text = Self::extract_text(&resource_type, &data);
+            (data, text)
+        })
+        .await
+        .map_err(|e| ResourceError::FileError(format!("提取文件正文失败: {}", e)))
+    }
+
+    /// 重建单个资源的检索向量
+    ///
+    /// 权重：A 标题；B 课程名、标签、关联教师与课程；C 描述；D 文件正文。
+    /// `content_text` 为 Some 时同时更新保存的正文，为 None 时沿用已有正文。
+    pub async fn refresh_resource_index(
+        pool: &PgPool,
+        resource_id: Uuid,
+        content_text: Option<&str>,
+    ) -> Result<(), ResourceError> {
+        let row = sqlx::query_as::<
+            _,
+            (
+                String,
+                Option<String>,
+                Option<String>,
+                Option<serde_json::Value>,
+                Option<String>,
+                Option<String>,
+                Option<String>,
+            ),
+        >(
+            r#"
+            SELECT
+                r.title,
+                r.description,
+                r.course_name,
+                r.tags,
+                r.content_text,
+                (SELECT string_agg(t.name, ' ') FROM teachers t
+                    INNER JOIN resource_teachers rt ON t.sn = rt.teacher_sn
+                    WHERE rt.resource_id = r.id) AS teacher_names,
+                (SELECT string_agg(c.name, ' ') FROM courses c
+                    INNER JOIN resource_courses rc ON c.sn = rc.course_sn
+                    WHERE rc.resource_id = r.id) AS course_names
+            FROM resources r
+            WHERE r.id = $1
+            "#,
+        )
+        .bind(resource_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| ResourceError::NotFound(format!("资源 {} 不存在", resource_id)))?;
+
+        let (title, description, course_name, tags, stored_content, teacher_names, course_names) =
+            row;
+
+        let tags_text = tags
+            .and_then(|t| serde_json::from_value::<Vec<String>>(t).ok())
+            .unwrap_or_default()
+            .join(" ");
+        let related_text = [
+            course_name.unwrap_or_default(),
+            tags_text,
+            teacher_names.unwrap_or_default(),
+            course_names.unwrap_or_default(),
+        ]
+        .join(" ");
+        let content = content_text.or(stored_content.as_deref()).unwrap_or_default();
+
+        sqlx::query(
+            r#"
+            UPDATE resources
+            SET
+                content_text = COALESCE($2, content_text),
+                search_vector =
+                    setweight(to_tsvector('simple', $3), 'A') ||
+                    setweight(to_tsvector('simple', $4), 'B') ||
+                    setweight(to_tsvector('simple', $5), 'C') ||
+                    setweight(to_tsvector('simple', $6), 'D')
+            WHERE id = $1
+            "#,
+        )
+        .bind(resource_id)
+        .bind(content_text)
+        .bind(Self::segment(&title))
+        .bind(Self::segment(&related_text))
+        .bind(Self::segment(description.as_deref().unwrap_or_default()))
+        .bind(Self::segment(content))
+        .execute(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// 重建检索向量，失败只记录日志，不影响主流程
+    pub async fn refresh_resource_index_quietly(
+        pool: &PgPool,
+        resource_id: Uuid,
+        content_text: Option<&str>,
+    ) {
+        if let Err(e) = Self::refresh_resource_index(pool, resource_id, content_text).await {
+            log::warn!(
+                "[Search] 更新检索索引失败 | resource_id={}, error={}",
+                resource_id,
+                e
+            );
+        }
+    }
+
+    /// 补建索引时的最大并发数，避免占满连接池影响正常请求
+    const REINDEX_CONCURRENCY: usize = 4;
+
+    /// 为尚未建立索引的资源补建检索向量（启动后在后台调用）
+    /// 历史资源没有提取过正文，这里只索引元数据
+    pub async fn index_missing_resources(pool: &PgPool) -> Result<usize, ResourceError> {
+        let ids: Vec<Uuid> =
+            sqlx::query_scalar("SELECT id FROM resources WHERE search_vector IS NULL")
+                .fetch_all(pool)
+                .await
+                .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        futures_util::stream::iter(ids.iter().copied())
+            .for_each_concurrent(Self::REINDEX_CONCURRENCY, |id| {
+                Self::refresh_resource_index_quietly(pool, id, None)
+            })
+            .await;
+
+        Ok(ids.len())
+    }
+
+    /// 取资源正文中第一个命中位置附近的片段，用于生成搜索摘要
+    ///
+    /// 正文最长可达 `MAX_CONTENT_CHARS` 字符，只在数据库中定位命中位置并截取窗口，
+    /// 窗口两端各多取一个字符，`build_snippet` 据此判断是否需要加省略号；没有命中的资源不返回
+    pub async fn content_excerpts(
+        pool: &PgPool,
+        resource_ids: &[Uuid],
+        terms: &[String],
+    ) -> Result<HashMap<Uuid, String>, sqlx::Error> {
+        if resource_ids.is_empty() || terms.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let rows: Vec<(Uuid, String)> = sqlx::query_as(
+            r#"
+            SELECT r.id,
+                   substr(r.content_text, GREATEST(m.first - $3 - 1, 1),
+                          m.first + $4 + 1 - GREATEST(m.first - $3 - 1, 1))
+            FROM resources r
+            CROSS JOIN LATERAL (
+                SELECT MIN(NULLIF(strpos(lower(r.content_text), t), 0)) AS first
+                FROM unnest($2::text[]) AS t
+            ) m
+            WHERE r.id = ANY($1) AND m.first IS NOT NULL
+            "#,
+        )
+        .bind(resource_ids)
+        .bind(terms)
+        .bind(Self::SNIPPET_BEFORE as i32)
+        .bind(Self::SNIPPET_AFTER as i32)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    /// 生成高亮摘要：取第一个命中位置附近的一段文本，
+    /// 命中的关键词用 `<mark>` 包裹，其余内容做 HTML 转义
+    pub fn build_snippet(text: &str, terms: &[String]) -> Option<String> {
+        if terms.is_empty() {
+            return None;
+        }
+
+        let chars: Vec<char> = text.chars().collect();
+        let lower: Vec<char> = chars
+            .iter()
+            .map(|c| c.to_lowercase().next().unwrap_or(*c))
+            .collect();
+
+        // 长词优先匹配，避免"线性代数"被拆成"线性"高亮
+        let mut terms: Vec<Vec<char>> = terms.iter().map(|t| t.chars().collect()).collect();
+        terms.sort_by_key(|t| std::cmp::Reverse(t.len()));
+
+        let match_at = |pos: usize| -> Option<usize> {
+            terms
+                .iter()
+                .find(|t| !t.is_empty() && lower[pos..].starts_with(t))
+                .map(|t| t.len())
+        };
+
+        let first = (0..lower.len()).find(|&pos| match_at(pos).is_some())?;
+        let start = first.saturating_sub(Self::SNIPPET_BEFORE);
+        let end = (first + Self::SNIPPET_AFTER).min(chars.len());
+
+        let mut snippet = String::new();
+        if start > 0 {
+            snippet.push('…');
+        }
+        let mut pos = start;
+        while pos < end {
+            match match_at(pos) {
+                Some(len) => {
+                    let stop = (pos + len).min(chars.len());
+                    let word: String = chars[pos..stop].iter().collect();
+                    snippet.push_str("<mark>");
+                    snippet.push_str(&escape_html(&word));
+                    snippet.push_str("</mark>");
+                    pos = stop;
+                }
+                None => {
+                    snippet.push_str(&escape_html(&chars[pos].to_string()));
+                    pos += 1;
+                }
+            }
+        }
+        if pos < chars.len() {
+            snippet.push('…');
+        }
+
+        Some(snippet)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{
+        cleanup_test_user, create_test_resource, create_test_user, test_pool,
+    };
+    use crate::models::UserRole;
+
+    #[test]
+    fn test_segment_chinese() {
+        let tokens = SearchService::segment_tokens("线性代数期末复习");
+        assert!(tokens.contains(&"线性".to_string()));
+        assert!(tokens.contains(&"代数".to_string()));
+    }
+
+    #[test]
+    fn test_segment_strips_punctuation_and_lowercases() {
+        let tokens = SearchService::segment_tokens("Linear Algebra, 2024!");
+        assert_eq!(tokens, vec!["linear", "algebra", "2024"]);
+    }
+
+    #[test]
+    fn test_build_tsquery() {
+        let terms = SearchService::query_terms("数学 Calc 数学");
+        assert_eq!(
+            SearchService::build_tsquery(&terms).as_deref(),
+            Some("数学 & calc:*")
+        );
+        assert!(SearchService::build_tsquery(&SearchService::query_terms("，。!")).is_none());
+    }
+
+    #[test]
+    fn test_extract_text_plain() {
+        let text = SearchService::extract_text("txt", "  第一章\n\n  极限  ".as_bytes());
+        assert_eq!(text.as_deref(), Some("第一章 极限"));
+        assert!(SearchService::extract_text("png", b"abc").is_none());
+    }
+
+    #[test]
+    fn test_build_snippet_highlights_terms() {
+        let terms = vec!["极限".to_string()];
+        let snippet = SearchService::build_snippet("本章介绍<函数>的极限与连续", &terms).unwrap();
+        assert_eq!(snippet, "本章介绍&lt;函数&gt;的<mark>极限</mark>与连续");
+        assert!(SearchService::build_snippet("没有命中", &terms).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_content_excerpts_match_full_text_snippet() {
+        let pool = test_pool().await;
+        let user = create_test_user(&pool, UserRole::User).await;
+        let terms = SearchService::query_terms("calculus 极限");
+
+        let set_content = |content: String| {
+            let pool = pool.clone();
+            let hash = Uuid::new_v4().simple().to_string();
+            async move {
+                let id = create_test_resource(&pool, user.id, &hash, "approved").await;
+                sqlx::query("UPDATE resources SET content_text = $2 WHERE id = $1")
+                    .bind(id)
+                    .bind(&content)
+                    .execute(&pool)
+                    .await
+                    .unwrap();
+                (id, content)
+            }
+        };
+        // 命中位置在正文中间、开头附近和没有命中
+        let middle = set_content(format!(
+            "{}Calculus 与极限{}",
+            "前文".repeat(5000),
+            "后文".repeat(5000)
+        ))
+        .await;
+        let near_start = set_content("第一章 极限与连续".to_string()).await;
+        let no_match = set_content("与检索词无关的正文".to_string()).await;
+
+        let mut excerpts =
+            SearchService::content_excerpts(&pool, &[middle.0, near_start.0, no_match.0], &terms)
+                .await
+                .unwrap();
+        assert!(!excerpts.contains_key(&no_match.0));
+        for (id, content) in [middle, near_start] {
+            let excerpt = excerpts.remove(&id).unwrap();
+            assert!(
+                excerpt.chars().count()
+                    <= SearchService::SNIPPET_BEFORE + SearchService::SNIPPET_AFTER + 2
+            );
+            assert_eq!(
+                SearchService::build_snippet(&excerpt, &terms),
+                SearchService::build_snippet(&content, &terms)
+            );
+        }
+
+        cleanup_test_user(&pool, user.id).await;
+    }
+}
@@ -0,0 +1,575 @@
+use sqlx::{PgExecutor, PgPool};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::models::{
+    ClientInfo, SessionListResponse, SessionResponse, UserSession, REVOKE_REASON_REUSE,
+};
+use crate::services::AuthError;
+use crate::utils::get_refresh_token_expire_seconds;
+
+/// 会话服务（Refresh Token 持久化、轮换与吊销）
+pub struct SessionService;
+
+/// 会话有效性缓存的有效期
+///
+/// 本进程吊销会话时立即清除对应缓存；多实例部署时，
+/// 其他实例最迟在这段时间后感知到吊销
+const SESSION_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// 超过这个数量的缓存项后清理已过期的项
+const SESSION_CACHE_PRUNE_THRESHOLD: usize = 10_000;
+
+/// 已确认有效的会话：会话ID -> (用户ID, 确认时间)
+fn session_cache() -> &'static Mutex<HashMap<Uuid, (Uuid, Instant)>> {
+    static CACHE: OnceLock<Mutex<HashMap<Uuid, (Uuid, Instant)>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 由 User-Agent 粗略识别浏览器和操作系统，用于会话列表展示
+pub(crate) fn describe_user_agent(user_agent: Option<&str>) -> String {
+    let Some(ua) = user_agent.filter(|ua| !ua.trim().is_empty()) else {
+        return "未知设备".to_string();
+    };
+
+    // 顺序有意义：Edge / Opera / 微信 的 UA 中同时包含 Chrome 和 Safari
+    let browser = [
+        ("MicroMessenger/", "微信"),
+        ("Edg/", "Edge"),
+        ("OPR/", "Opera"),
+        ("Firefox/", "Firefox"),
+        ("Chrome/", "Chrome"),
+        ("Safari/", "Safari"),
+        ("curl/", "curl"),
+    ]
+    .iter()
+    .find(|(marker, _)| ua.contains(marker))
+    .map(|(_, name)| *name)
+    .unwrap_or("未知浏览器");
+
+    let os = [
+        ("Windows", "Windows"),
+        ("Android", "Android"),
+        ("iPhone", "iOS"),
+        ("iPad", "iPadOS"),
+        ("Mac OS X", "macOS"),
+        ("Linux", "Linux"),
+    ]
+    .iter()
+    .find(|(marker, _)| ua.contains(marker))
+    .map(|(_, name)| *name);
+
+    match os {
+        Some(os) => format!("{} / {}", browser, os),
+        None => browser.to_string(),
+    }
+}
+
+impl SessionService {
+    /// 创建登录会话，返回 (会话ID, Refresh Token 标识)
+    ///
+    /// `mfa_verified` 表示本次登录完成了两步验证
+    pub async fn create_session(
+        pool: &PgPool,
+        user_id: Uuid,
+        client: &ClientInfo,
+        mfa_verified: bool,
+    ) -> Result<(Uuid, Uuid), sqlx::Error> {
+        // 顺带清理该用户早已过期的会话
+        sqlx::query(
+            "DELETE FROM user_sessions WHERE user_id = $1 AND expires_at < CURRENT_TIMESTAMP - INTERVAL '30 days'",
+        )
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+        let refresh_jti = Uuid::new_v4();
+        let session_id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO user_sessions
+                (user_id, refresh_jti, user_agent, ip_address, expires_at, mfa_verified)
+            VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP + make_interval(secs => $5), $6)
+            RETURNING id
+            "#,
+        )
+        .bind(user_id)
+        .bind(refresh_jti)
+        .bind(&client.user_agent)
+        .bind(&client.ip_address)
+        .bind(get_refresh_token_expire_seconds() as f64)
+        .bind(mfa_verified)
+        .fetch_one(pool)
+        .await?;
+
+        log::info!(
+            "[Session] 创建会话 | user_id={}, session_id={}, ip={:?}",
+            user_id,
+            session_id,
+            client.ip_address
+        );
+
+        Ok((session_id, refresh_jti))
+    }
+
+    /// 轮换 Refresh Token，返回 (新的 Token 标识, 会话是否完成了两步验证)
+    ///
+    /// `presented_jti` 为客户端提交的 Refresh Token 标识，与会话当前标识不一致
+    /// 说明旧 Token 被重复使用（可能已泄露），此时吊销整个会话。
+    /// 为 `None` 时表示服务端主动重新签发（如实名认证后更新 Token），不做重放检查
+    pub async fn rotate_refresh_token(
+        pool: &PgPool,
+        session_id: Uuid,
+        user_id: Uuid,
+        presented_jti: Option<Uuid>,
+        client: &ClientInfo,
+    ) -> Result<(Uuid, bool), AuthError> {
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+        let session = sqlx::query_as::<_, (Uuid, bool, bool)>(
+            r#"
+            SELECT s.refresh_jti,
+                   s.revoked_at IS NULL AND s.expires_at > CURRENT_TIMESTAMP AND u.is_active,
+                   s.mfa_verified
+            FROM user_sessions s
+            JOIN users u ON s.user_id = u.id
+            WHERE s.id = $1 AND s.user_id = $2
+            FOR UPDATE OF s
+            "#,
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+        let Some((current_jti, active, mfa_verified)) = session else {
+            return Err(AuthError::TokenInvalid("会话不存在".to_string()));
+        };
+        if !active {
+            return Err(AuthError::TokenInvalid(
+                "会话已失效，请重新登录".to_string(),
+            ));
+        }
+
+        if let Some(presented_jti) = presented_jti {
+            if presented_jti != current_jti {
+                sqlx::query(
+                    r#"
+                    UPDATE user_sessions
+                    SET revoked_at = CURRENT_TIMESTAMP, revoked_reason = $1
+                    WHERE id = $2
+                    "#,
+                )
+                .bind(REVOKE_REASON_REUSE)
+                .bind(session_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+                tx.commit()
+                    .await
+                    .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+                Self::forget_session(session_id);
+
+                log::warn!(
+                    "[Session] 检测到 Refresh Token 重复使用，已吊销会话 | user_id={}, session_id={}, ip={:?}",
+                    user_id,
+                    session_id,
+                    client.ip_address
+                );
+                return Err(AuthError::TokenInvalid(
+                    "登录凭证已被使用，会话已失效，请重新登录".to_string(),
+                ));
+            }
+        }
+
+        let refresh_jti = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            UPDATE user_sessions
+            SET refresh_jti = $1,
+                last_used_at = CURRENT_TIMESTAMP,
+                expires_at = CURRENT_TIMESTAMP + make_interval(secs => $2),
+                ip_address = COALESCE($3, ip_address),
+                user_agent = COALESCE($4, user_agent)
+            WHERE id = $5
+            "#,
+        )
+        .bind(refresh_jti)
+        .bind(get_refresh_token_expire_seconds() as f64)
+        .bind(&client.ip_address)
+        .bind(&client.user_agent)
+        .bind(session_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
+
+        Ok((refresh_jti, mfa_verified))
+    }
+
+    /// 将会话标记为已完成两步验证（登录后才启用两步验证时使用），刷新 Token 后生效
+    pub async fn mark_mfa_verified(
+        pool: &PgPool,
+        session_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE user_sessions SET mfa_verified = true WHERE id = $1 AND user_id = $2")
+            .bind(session_id)
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// 检查会话是否仍然有效（未吊销、未过期且用户未被禁用）
+    ///
+    /// 每个已认证请求都会调用，有效的结果缓存 `SESSION_CACHE_TTL`
+    pub async fn is_session_active(
+        pool: &PgPool,
+        session_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        if Self::is_cached_active(session_id, user_id, Instant::now()) {
+            return Ok(true);
+        }
+
+        let active: bool = sqlx::query_scalar(
+            r#"
+            SELECT EXISTS(
+                SELECT 1
+                FROM user_sessions s
+                JOIN users u ON s.user_id = u.id
+                WHERE s.id = $1 AND s.user_id = $2
+                  AND s.revoked_at IS NULL
+                  AND s.expires_at > CURRENT_TIMESTAMP
+                  AND u.is_active = true
+            )
+            "#,
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+
+        if active {
+            Self::cache_active(session_id, user_id, Instant::now());
+        }
+        Ok(active)
+    }
+
+    fn is_cached_active(session_id: Uuid, user_id: Uuid, now: Instant) -> bool {
+        let cache = session_cache().lock().unwrap_or_else(|e| e.into_inner());
+        cache
+            .get(&session_id)
+            .is_some_and(|(cached_user, checked)| {
+                *cached_user == user_id && now.duration_since(*checked) < SESSION_CACHE_TTL
+            })
+    }
+
+    fn cache_active(session_id: Uuid, user_id: Uuid, now: Instant) {
+        let mut cache = session_cache().lock().unwrap_or_else(|e| e.into_inner());
+        if cache.len() >= SESSION_CACHE_PRUNE_THRESHOLD {
+            cache.retain(|_, (_, checked)| now.duration_since(*checked) < SESSION_CACHE_TTL);
+        }
+        cache.insert(session_id, (user_id, now));
+    }
+
+    /// 清除会话的有效性缓存（会话被吊销时调用）
+    pub fn forget_session(session_id: Uuid) {
+        let mut cache = session_cache().lock().unwrap_or_else(|e| e.into_inner());
+        cache.remove(&session_id);
+    }
+
+    /// 清除用户全部会话的有效性缓存
+    pub fn forget_user_sessions(user_id: Uuid) {
+        let mut cache = session_cache().lock().unwrap_or_else(|e| e.into_inner());
+        cache.retain(|_, (cached_user, _)| *cached_user != user_id);
+    }
+
+    /// 获取用户的有效会话列表（最近使用的在前）
+    pub async fn list_sessions(
+        pool: &PgPool,
+        user_id: Uuid,
+        current_session_id: Uuid,
+    ) -> Result<SessionListResponse, sqlx::Error> {
+        let sessions = sqlx::query_as::<_, UserSession>(
+            r#"
+            SELECT id, user_agent, ip_address, created_at, last_used_at, expires_at
+            FROM user_sessions
+            WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > CURRENT_TIMESTAMP
+            ORDER BY last_used_at DESC NULLS LAST
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|s| SessionResponse {
+            id: s.id,
+            device: describe_user_agent(s.user_agent.as_deref()),
+            user_agent: s.user_agent,
+            ip_address: s.ip_address,
+            created_at: s.created_at,
+            last_used_at: s.last_used_at,
+            expires_at: s.expires_at,
+            is_current: s.id == current_session_id,
+        })
+        .collect();
+
+        Ok(SessionListResponse { sessions })
+    }
+
+    /// 吊销用户的指定会话，会话不存在或已失效时返回 false
+    pub async fn revoke_session(
+        pool: &PgPool,
+        user_id: Uuid,
+        session_id: Uuid,
+        reason: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE user_sessions
+            SET revoked_at = CURRENT_TIMESTAMP, revoked_reason = $1
+            WHERE id = $2 AND user_id = $3 AND revoked_at IS NULL
+            "#,
+        )
+        .bind(reason)
+        .bind(session_id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+        Self::forget_session(session_id);
+
+        if result.rows_affected() > 0 {
+            log::info!(
+                "[Session] 会话已吊销 | user_id={}, session_id={}, reason={}",
+                user_id,
+                session_id,
+                reason
+            );
+        }
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 吊销用户的全部会话（可保留一个，如当前会话），返回吊销数量
+    ///
+    /// 可以在调用方的事务中执行，此时调用方需在提交后再调用
+    /// `forget_user_sessions`，避免提交前的并发请求重新缓存会话
+    pub async fn revoke_all_sessions<'e>(
+        executor: impl PgExecutor<'e>,
+        user_id: Uuid,
+        except_session_id: Option<Uuid>,
+        reason: &str,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE user_sessions
+            SET revoked_at = CURRENT_TIMESTAMP, revoked_reason = $1
+            WHERE user_id = $2 AND revoked_at IS NULL
+              AND ($3::uuid IS NULL OR id <> $3)
+            "#,
+        )
+        .bind(reason)
+        .bind(user_id)
+        .bind(except_session_id)
+        .execute(executor)
+        .await?;
+        Self::forget_user_sessions(user_id);
+
+        log::info!(
+            "[Session] 批量吊销会话 | user_id={}, count={}, reason={}",
+            user_id,
+            result.rows_affected(),
+            reason
+        );
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{cleanup_test_user, create_test_user, test_pool};
+    use crate::models::{UserRole, REVOKE_REASON_USER};
+
+    #[tokio::test]
+    async fn test_rotate_refresh_token_detects_reuse() {
+        let pool = test_pool().await;
+        let user = create_test_user(&pool, UserRole::User).await;
+        let client = ClientInfo::default();
+
+        let (session_id, first_jti) = SessionService::create_session(&pool, user.id, &client, true)
+            .await
+            .unwrap();
+        assert!(
+            SessionService::is_session_active(&pool, session_id, user.id)
+                .await
+                .unwrap()
+        );
+
+        // 正常轮换：提交当前标识，得到新标识
+        let (second_jti, mfa_verified) = SessionService::rotate_refresh_token(
+            &pool,
+            session_id,
+            user.id,
+            Some(first_jti),
+            &client,
+        )
+        .await
+        .unwrap();
+        assert_ne!(second_jti, first_jti);
+        assert!(mfa_verified);
+
+        // 服务端主动重新签发不做重放检查
+        let (third_jti, _) =
+            SessionService::rotate_refresh_token(&pool, session_id, user.id, None, &client)
+                .await
+                .unwrap();
+
+        // 其他用户不能轮换该会话
+        assert!(matches!(
+            SessionService::rotate_refresh_token(
+                &pool,
+                session_id,
+                Uuid::new_v4(),
+                Some(third_jti),
+                &client
+            )
+            .await,
+            Err(AuthError::TokenInvalid(_))
+        ));
+
+        // 重复使用旧标识：吊销整个会话，缓存立即失效
+        assert!(matches!(
+            SessionService::rotate_refresh_token(
+                &pool,
+                session_id,
+                user.id,
+                Some(second_jti),
+                &client
+            )
+            .await,
+            Err(AuthError::TokenInvalid(_))
+        ));
+        assert!(
+            !SessionService::is_session_active(&pool, session_id, user.id)
+                .await
+                .unwrap()
+        );
+
+        // 会话吊销后当前标识也不能再使用
+        assert!(matches!(
+            SessionService::rotate_refresh_token(
+                &pool,
+                session_id,
+                user.id,
+                Some(third_jti),
+                &client
+            )
+            .await,
+            Err(AuthError::TokenInvalid(_))
+        ));
+
+        cleanup_test_user(&pool, user.id).await;
+    }
+
+    #[tokio::test]
+    async fn test_revoke_clears_session_cache() {
+        let pool = test_pool().await;
+        let user = create_test_user(&pool, UserRole::User).await;
+        let client = ClientInfo::default();
+
+        let (first, _) = SessionService::create_session(&pool, user.id, &client, false)
+            .await
+            .unwrap();
+        let (second, _) = SessionService::create_session(&pool, user.id, &client, false)
+            .await
+            .unwrap();
+        for session_id in [first, second] {
+            assert!(
+                SessionService::is_session_active(&pool, session_id, user.id)
+                    .await
+                    .unwrap()
+            );
+        }
+        // 缓存按用户区分
+        assert!(
+            !SessionService::is_session_active(&pool, first, Uuid::new_v4())
+                .await
+                .unwrap()
+        );
+
+        SessionService::revoke_session(&pool, user.id, first, REVOKE_REASON_USER)
+            .await
+            .unwrap();
+        assert!(!SessionService::is_session_active(&pool, first, user.id)
+            .await
+            .unwrap());
+        assert!(SessionService::is_session_active(&pool, second, user.id)
+            .await
+            .unwrap());
+
+        SessionService::revoke_all_sessions(&pool, user.id, None, REVOKE_REASON_USER)
+            .await
+            .unwrap();
+        assert!(!SessionService::is_session_active(&pool, second, user.id)
+            .await
+            .unwrap());
+
+        cleanup_test_user(&pool, user.id).await;
+    }
+
+    #[test]
+    fn test_session_cache_expires() {
+        let session_id = Uuid::new_v4();
+        let user_id = Uuid::new_v4();
+        let now = Instant::now();
+
+        SessionService::cache_active(session_id, user_id, now);
+        assert!(SessionService::is_cached_active(session_id, user_id, now));
+        assert!(!SessionService::is_cached_active(
+            session_id,
+            user_id,
+            now + SESSION_CACHE_TTL
+        ));
+
+        SessionService::forget_session(session_id);
+        assert!(!SessionService::is_cached_active(session_id, user_id, now));
+    }
+
+    #[test]
+    fn test_describe_user_agent() {
+        assert_eq!(
+            describe_user_agent(Some(
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0 Safari/537.36 Edg/120.0"
+            )),
+            "Edge / Windows"
+        );
+        assert_eq!(
+            describe_user_agent(Some(
+                "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1"
+            )),
+            "Safari / iOS"
+        );
+        assert_eq!(
+            describe_user_agent(Some(
+                "Mozilla/5.0 (Linux; Android 14) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0 Mobile Safari/537.36"
+            )),
+            "Chrome / Android"
+        );
+        assert_eq!(describe_user_agent(Some("curl/8.5.0")), "curl");
+        assert_eq!(describe_user_agent(None), "未知设备");
+        assert_eq!(describe_user_agent(Some("  ")), "未知设备");
+    }
+}
@@ -0,0 +1,931 @@
+use futures_util::TryStreamExt;
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use uuid::Uuid;
+
+use crate::models::{
+    CurrentUser, StartStorageCheckRequest, StorageCheckDetailResponse, StorageCheckIssueItem,
+    StorageCheckIssueQuery, StorageCheckResponse, StorageCheckStatus, StorageIssueAction,
+    StorageIssueType, StorageMigrationStatus,
+};
+use crate::services::{
+    storage_key_for, AdminError, FileService, StorageBackend, StorageBackendType, StorageError,
+    StorageObject, StorageRegistry,
+};
+
+/// 检查的存储目录前缀
+const CHECKED_PREFIXES: [&str; 2] = ["resources/", "images/"];
+
+/// 任务详情中返回的问题条目上限
+const MAX_ISSUES_IN_DETAIL: i64 = 200;
+
+/// 存储一致性检查服务
+///
+/// 列举各存储后端 `resources/`、`images/` 下的文件，与资源、历史版本和图片记录对比，
+/// 找出没有记录引用的孤儿文件、文件缺失的记录以及大小/哈希不一致的文件。
+/// 默认只生成报告；清理模式只删除超过宽限期的孤儿文件，从不修改或删除数据库记录
+pub struct StorageCheckService;
+
+/// 引用存储文件的一条记录
+#[derive(Debug, sqlx::FromRow)]
+struct StoredItem {
+    item_type: String,
+    id: Uuid,
+    file_path: String,
+    file_hash: Option<String>,
+    file_size: Option<i64>,
+}
+
+impl StoredItem {
+    fn key_prefix(&self) -> &'static str {
+        if self.item_type == "image" {
+            "images/"
+        } else {
+            "resources/"
+        }
+    }
+
+    /// 用于和列举结果对比的存储 key，无法推断时使用原始路径
+    fn storage_key(&self) -> String {
+        storage_key_for(&self.file_path, self.key_prefix())
+            .unwrap_or_else(|| self.file_path.clone())
+    }
+}
+
+/// 一条待记录的问题
+struct CheckIssue<'a> {
+    issue_type: StorageIssueType,
+    file_path: &'a str,
+    item: Option<&'a StoredItem>,
+    expected_size: Option<i64>,
+    actual_size: Option<i64>,
+    action: StorageIssueAction,
+    detail: Option<String>,
+}
+
+/// 单个存储后端的检查统计
+#[derive(Debug, Default)]
+struct CheckCounters {
+    scanned_files: i32,
+    scanned_records: i32,
+    orphan_files: i32,
+    missing_files: i32,
+    mismatched_files: i32,
+    deleted_files: i32,
+    deleted_bytes: i64,
+}
+
+/// 后台执行中的检查任务
+struct CheckJob {
+    id: Uuid,
+    backends: Vec<(StorageBackendType, Arc<dyn StorageBackend>)>,
+    cleanup: bool,
+    verify_hash: bool,
+    grace_period: Duration,
+}
+
+impl StorageCheckService {
+    /// 发起检查任务，扫描在后台进行
+    ///
+    /// 同一时间只允许一个检查任务运行，且不能与存储迁移同时进行
+    /// （迁移过程中目标存储里会短暂出现尚未被记录引用的文件）
+    pub async fn start_check(
+        pool: &PgPool,
+        storages: &StorageRegistry,
+        user: &CurrentUser,
+        request: &StartStorageCheckRequest,
+    ) -> Result<StorageCheckResponse, AdminError> {
+        let backend_types = match request.backend.as_deref() {
+            Some(backend) => vec![StorageBackendType::from_storage_type(Some(backend))],
+            None => storages.backend_types(),
+        };
+        let mut backends = Vec::new();
+        for backend_type in backend_types {
+            let storage = storages.get(backend_type).cloned().ok_or_else(|| {
+                AdminError::ValidationError(format!("存储 {} 未配置", backend_type.as_str()))
+            })?;
+            backends.push((backend_type, storage));
+        }
+
+        let migration_running: bool = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM storage_migrations WHERE status = $1)",
+        )
+        .bind(StorageMigrationStatus::Running.as_str())
+        .fetch_one(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+        if migration_running {
+            return Err(AdminError::Conflict(
+                "存储迁移进行中，请等待迁移结束后再检查".to_string(),
+            ));
+        }
+
+        let cleanup = request.cleanup.unwrap_or(false);
+        let verify_hash = request.verify_hash.unwrap_or(false);
+        let grace_hours = request
+            .grace_hours
+            .unwrap_or(StartStorageCheckRequest::DEFAULT_GRACE_HOURS);
+        let backend_names = backends
+            .iter()
+            .map(|(backend_type, _)| backend_type.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let check: StorageCheckResponse = sqlx::query_as(
+            r#"
+            INSERT INTO storage_checks (backends, cleanup, verify_hash, grace_hours, status, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT DO NOTHING
+            RETURNING *
+            "#,
+        )
+        .bind(&backend_names)
+        .bind(cleanup)
+        .bind(verify_hash)
+        .bind(grace_hours)
+        .bind(StorageCheckStatus::Running.as_str())
+        .bind(user.id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| AdminError::Conflict("已有正在进行的存储一致性检查任务".to_string()))?;
+
+        log::info!(
+            "[Storage] 存储一致性检查开始 | check_id={}, backends={}, cleanup={}, verify_hash={}, grace_hours={}",
+            check.id,
+            backend_names,
+            cleanup,
+            verify_hash,
+            grace_hours
+        );
+
+        let job = CheckJob {
+            id: check.id,
+            backends,
+            cleanup,
+            verify_hash,
+            grace_period: Duration::from_secs(grace_hours as u64 * 3600),
+        };
+        let pool = pool.clone();
+        tokio::spawn(async move {
+            Self::run(&pool, job).await;
+        });
+
+        Ok(check)
+    }
+
+    /// 获取最近的检查任务
+    pub async fn list_checks(pool: &PgPool) -> Result<Vec<StorageCheckResponse>, AdminError> {
+        sqlx::query_as("SELECT * FROM storage_checks ORDER BY created_at DESC LIMIT 20")
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AdminError::DatabaseError(e.to_string()))
+    }
+
+    /// 获取检查任务详情（含发现的问题）
+    pub async fn get_check(
+        pool: &PgPool,
+        check_id: Uuid,
+        query: &StorageCheckIssueQuery,
+    ) -> Result<StorageCheckDetailResponse, AdminError> {
+        let check: StorageCheckResponse =
+            sqlx::query_as("SELECT * FROM storage_checks WHERE id = $1")
+                .bind(check_id)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| AdminError::DatabaseError(e.to_string()))?
+                .ok_or_else(|| AdminError::NotFound("检查任务不存在".to_string()))?;
+
+        let issues: Vec<StorageCheckIssueItem> = sqlx::query_as(
+            r#"
+            SELECT backend, issue_type, file_path, item_type, item_id,
+                   expected_size, actual_size, action, detail, created_at
+            FROM storage_check_issues
+            WHERE check_id = $1 AND ($2::VARCHAR IS NULL OR issue_type = $2)
+            ORDER BY created_at
+            LIMIT $3
+            "#,
+        )
+        .bind(check_id)
+        .bind(query.issue_type.as_deref())
+        .bind(MAX_ISSUES_IN_DETAIL)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        Ok(StorageCheckDetailResponse { check, issues })
+    }
+
+    /// 服务启动时把上次运行中断的任务标记为失败，以便重新发起
+    pub async fn fail_interrupted_checks(pool: &PgPool) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE storage_checks
+            SET status = $1, last_error = '服务重启，任务中断', finished_at = NOW(), updated_at = NOW()
+            WHERE status = $2
+            "#,
+        )
+        .bind(StorageCheckStatus::Failed.as_str())
+        .bind(StorageCheckStatus::Running.as_str())
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// 是否有正在运行的检查任务
+    pub async fn has_running_check(pool: &PgPool) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM storage_checks WHERE status = $1)")
+            .bind(StorageCheckStatus::Running.as_str())
+            .fetch_one(pool)
+            .await
+    }
+
+    /// 后台执行检查任务并记录最终状态
+    async fn run(pool: &PgPool, job: CheckJob) {
+        let mut result = Ok(());
+        for (backend_type, storage) in &job.backends {
+            result = Self::check_backend(pool, &job, *backend_type, storage.as_ref()).await;
+            if result.is_err() {
+                break;
+            }
+        }
+        let (status, error) = match result {
+            Ok(()) => (StorageCheckStatus::Completed, None),
+            Err(e) => (StorageCheckStatus::Failed, Some(e)),
+        };
+
+        let result = sqlx::query_as::<_, (i32, i32, i32, i32)>(
+            r#"
+            UPDATE storage_checks
+            SET status = $2, last_error = COALESCE($3, last_error),
+                finished_at = NOW(), updated_at = NOW()
+            WHERE id = $1
+            RETURNING orphan_files, missing_files, mismatched_files, deleted_files
+            "#,
+        )
+        .bind(job.id)
+        .bind(status.as_str())
+        .bind(&error)
+        .fetch_one(pool)
+        .await;
+
+        match result {
+            Ok((orphan, missing, mismatched, deleted)) => log::info!(
+                "[Storage] 存储一致性检查结束 | check_id={}, status={}, orphan={}, missing={}, mismatched={}, deleted={}",
+                job.id,
+                status.as_str(),
+                orphan,
+                missing,
+                mismatched,
+                deleted
+            ),
+            Err(e) => log::error!(
+                "[Storage] 更新存储一致性检查状态失败 | check_id={}, status={}, error={}",
+                job.id,
+                status.as_str(),
+                e
+            ),
+        }
+    }
+
+    /// 检查单个存储后端，完成后累加任务统计
+    async fn check_backend(
+        pool: &PgPool,
+        job: &CheckJob,
+        backend_type: StorageBackendType,
+        storage: &dyn StorageBackend,
+    ) -> Result<(), String> {
+        // 先读取记录再列举文件：检查期间新上传的文件最多被当作孤儿文件，由宽限期保护
+        let items = Self::load_items(pool, backend_type)
+            .await
+            .map_err(|e| format!("读取 {} 存储的文件记录失败: {}", backend_type.as_str(), e))?;
+        let mut counters = CheckCounters {
+            scanned_records: items.len() as i32,
+            ..Default::default()
+        };
+
+        // 同一文件可能被资源和它的当前版本同时引用，资源记录排在前面
+        let mut items_by_key: HashMap<String, Vec<&StoredItem>> = HashMap::new();
+        for item in &items {
+            items_by_key
+                .entry(item.storage_key())
+                .or_default()
+                .push(item);
+        }
+
+        // 边列举边对比，只保留已找到的 key，不在内存中保存完整的文件列表
+        let mut found_keys = HashSet::new();
+        for prefix in CHECKED_PREFIXES {
+            let mut listed = storage.list_files(prefix);
+            while let Some(object) = listed.try_next().await.map_err(|e| {
+                format!(
+                    "列举 {} 存储的 {} 失败: {}",
+                    backend_type.as_str(),
+                    prefix,
+                    e
+                )
+            })? {
+                counters.scanned_files += 1;
+                let key =
+                    storage_key_for(&object.key, prefix).unwrap_or_else(|| object.key.clone());
+                match items_by_key.get(&key) {
+                    Some(referenced) => {
+                        found_keys.insert(key);
+                        Self::compare_file(
+                            pool,
+                            job,
+                            backend_type,
+                            storage,
+                            &object,
+                            referenced,
+                            &mut counters,
+                        )
+                        .await?;
+                    }
+                    None => {
+                        Self::handle_orphan(
+                            pool,
+                            job,
+                            backend_type,
+                            storage,
+                            &key,
+                            &object,
+                            &mut counters,
+                        )
+                        .await?;
+                    }
+                }
+            }
+        }
+
+        for (key, referenced) in &items_by_key {
+            if !found_keys.contains(key) {
+                Self::confirm_missing(pool, job, backend_type, storage, referenced, &mut counters)
+                    .await?;
+            }
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE storage_checks
+            SET scanned_files = scanned_files + $2,
+                scanned_records = scanned_records + $3,
+                orphan_files = orphan_files + $4,
+                missing_files = missing_files + $5,
+                mismatched_files = mismatched_files + $6,
+                deleted_files = deleted_files + $7,
+                deleted_bytes = deleted_bytes + $8,
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(job.id)
+        .bind(counters.scanned_files)
+        .bind(counters.scanned_records)
+        .bind(counters.orphan_files)
+        .bind(counters.missing_files)
+        .bind(counters.mismatched_files)
+        .bind(counters.deleted_files)
+        .bind(counters.deleted_bytes)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("更新检查进度失败: {}", e))?;
+
+        log::info!(
+            "[Storage] 存储后端检查完成 | check_id={}, backend={}, files={}, records={}, orphan={}, missing={}, mismatched={}",
+            job.id,
+            backend_type.as_str(),
+            counters.scanned_files,
+            counters.scanned_records,
+            counters.orphan_files,
+            counters.missing_files,
+            counters.mismatched_files
+        );
+
+        Ok(())
+    }
+
+    /// 读取位于指定存储的全部文件记录
+    async fn load_items(
+        pool: &PgPool,
+        backend_type: StorageBackendType,
+    ) -> Result<Vec<StoredItem>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT 'resource' AS item_type, id, file_path, file_hash, file_size, 0 AS sort_order
+            FROM resources
+            WHERE COALESCE(storage_type, 'local') = $1 AND COALESCE(file_path, '') <> ''
+            UNION ALL
+            SELECT 'resource', id, source_file_path, NULL, NULL, 1
+            FROM resources
+            WHERE COALESCE(storage_type, 'local') = $1 AND COALESCE(source_file_path, '') <> ''
+            UNION ALL
+            SELECT 'resource_version', id, file_path, file_hash, file_size, 2
+            FROM resource_versions
+            WHERE COALESCE(storage_type, 'local') = $1 AND file_path <> ''
+            UNION ALL
+            SELECT 'image', id, file_path, NULL, file_size::BIGINT, 3
+            FROM images
+            WHERE COALESCE(storage_type, 'local') = $1 AND file_path <> ''
+            ORDER BY sort_order, id
+            "#,
+        )
+        .bind(backend_type.as_str())
+        .fetch_all(pool)
+        .await
+    }
+
+    /// 对比有记录引用的文件的大小（以及可选的哈希）
+    async fn compare_file(
+        pool: &PgPool,
+        job: &CheckJob,
+        backend_type: StorageBackendType,
+        storage: &dyn StorageBackend,
+        object: &StorageObject,
+        referenced: &[&StoredItem],
+        counters: &mut CheckCounters,
+    ) -> Result<(), String> {
+        let actual_size = object.size as i64;
+        let size_mismatch = referenced.iter().find(|item| {
+            item.file_size
+                .is_some_and(|expected| expected != actual_size)
+        });
+        if let Some(item) = size_mismatch {
+            counters.mismatched_files += 1;
+            return Self::record_issue(
+                pool,
+                job.id,
+                backend_type,
+                CheckIssue {
+                    issue_type: StorageIssueType::SizeMismatch,
+                    file_path: &object.key,
+                    item: Some(item),
+                    expected_size: item.file_size,
+                    actual_size: Some(actual_size),
+                    action: StorageIssueAction::Reported,
+                    detail: None,
+                },
+            )
+            .await;
+        }
+
+        let with_hash: Vec<&StoredItem> = referenced
+            .iter()
+            .copied()
+            .filter(|item| item.file_hash.as_deref().is_some_and(|h| !h.is_empty()))
+            .collect();
+        if !job.verify_hash || with_hash.is_empty() {
+            return Ok(());
+        }
+
+        let hash = match storage.read_file(&object.key).await {
+            Ok(data) => FileService::calculate_hash(&data),
+            Err(e) => {
+                log::warn!(
+                    "[Storage] 读取文件校验哈希失败 | check_id={}, path={}, error={}",
+                    job.id,
+                    object.key,
+                    e
+                );
+                return Ok(());
+            }
+        };
+        let hash_mismatch = with_hash.into_iter().find(|item| {
+            !item
+                .file_hash
+                .as_deref()
+                .is_some_and(|expected| expected.eq_ignore_ascii_case(&hash))
+        });
+        if let Some(item) = hash_mismatch {
+            counters.mismatched_files += 1;
+            Self::record_issue(
+                pool,
+                job.id,
+                backend_type,
+                CheckIssue {
+                    issue_type: StorageIssueType::HashMismatch,
+                    file_path: &object.key,
+                    item: Some(item),
+                    expected_size: item.file_size,
+                    actual_size: Some(actual_size),
+                    action: StorageIssueAction::Reported,
+                    detail: Some(format!("实际哈希: {}", hash)),
+                },
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// 处理没有记录引用的文件：报告模式只记录；清理模式删除超过宽限期且复查仍无引用的文件
+    async fn handle_orphan(
+        pool: &PgPool,
+        job: &CheckJob,
+        backend_type: StorageBackendType,
+        storage: &dyn StorageBackend,
+        key: &str,
+        object: &StorageObject,
+        counters: &mut CheckCounters,
+    ) -> Result<(), String> {
+        let (action, detail) = if !job.cleanup {
+            (StorageIssueAction::Reported, None)
+        } else if !past_grace_period(object.last_modified, SystemTime::now(), job.grace_period) {
+            (StorageIssueAction::KeptRecent, None)
+        } else {
+            // 删除前复查：扫描期间可能有新记录引用了这个文件
+            let referenced = Self::is_referenced(pool, backend_type, &object.key, key)
+                .await
+                .map_err(|e| format!("复查文件引用失败: {}", e))?;
+            if referenced {
+                return Ok(());
+            }
+            match storage.delete_file(&object.key).await {
+                Ok(()) => {
+                    counters.deleted_files += 1;
+                    counters.deleted_bytes += object.size as i64;
+                    (StorageIssueAction::Deleted, None)
+                }
+                Err(e) => (StorageIssueAction::DeleteFailed, Some(e.to_string())),
+            }
+        };
+
+        counters.orphan_files += 1;
+        Self::record_issue(
+            pool,
+            job.id,
+            backend_type,
+            CheckIssue {
+                issue_type: StorageIssueType::OrphanFile,
+                file_path: &object.key,
+                item: None,
+                expected_size: None,
+                actual_size: Some(object.size as i64),
+                action,
+                detail,
+            },
+        )
+        .await
+    }
+
+    /// 列举结果中找不到记录引用的文件时，逐个确认文件确实不存在且记录仍指向它
+    async fn confirm_missing(
+        pool: &PgPool,
+        job: &CheckJob,
+        backend_type: StorageBackendType,
+        storage: &dyn StorageBackend,
+        referenced: &[&StoredItem],
+        counters: &mut CheckCounters,
+    ) -> Result<(), String> {
+        let Some(item) = referenced.first() else {
+            return Ok(());
+        };
+
+        match storage.head_file(&item.file_path).await {
+            // 文件存在但不在检查的目录下（如历史遗留路径），同样对比大小
+            Ok(metadata) => {
+                let Some(size) = metadata.content_length else {
+                    return Ok(());
+                };
+                let object = StorageObject {
+                    key: item.file_path.clone(),
+                    size,
+                    last_modified: metadata.last_modified,
+                };
+                Self::compare_file(
+                    pool,
+                    job,
+                    backend_type,
+                    storage,
+                    &object,
+                    referenced,
+                    counters,
+                )
+                .await
+            }
+            Err(StorageError::NotFound(_)) => {
+                let still_referenced =
+                    Self::is_referenced(pool, backend_type, &item.file_path, &item.storage_key())
+                        .await
+                        .map_err(|e| format!("复查文件引用失败: {}", e))?;
+                if !still_referenced {
+                    // 扫描期间记录已被删除
+                    return Ok(());
+                }
+                counters.missing_files += 1;
+                Self::record_issue(
+                    pool,
+                    job.id,
+                    backend_type,
+                    CheckIssue {
+                        issue_type: StorageIssueType::MissingFile,
+                        file_path: &item.file_path,
+                        item: Some(item),
+                        expected_size: item.file_size,
+                        actual_size: None,
+                        action: StorageIssueAction::Reported,
+                        detail: None,
+                    },
+                )
+                .await
+            }
+            Err(e) => {
+                log::warn!(
+                    "[Storage] 确认文件是否存在失败 | check_id={}, path={}, error={}",
+                    job.id,
+                    item.file_path,
+                    e
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// 指定存储中是否有记录引用该文件（按完整路径或存储 key 匹配）
+    async fn is_referenced(
+        pool: &PgPool,
+        backend_type: StorageBackendType,
+        file_path: &str,
+        key: &str,
+    ) -> Result<bool, sqlx::Error> {
+        // 路径以 "/{key}" 结尾即视为引用
+        let suffix_pattern = like_suffix_pattern(key);
+        sqlx::query_scalar(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM resources
+                WHERE COALESCE(storage_type, 'local') = $1
+                  AND (file_path IN ($2, $3) OR file_path LIKE $4 ESCAPE '\'
+                       OR source_file_path IN ($2, $3) OR source_file_path LIKE $4 ESCAPE '\')
+            ) OR EXISTS (
+                SELECT 1 FROM resource_versions
+                WHERE COALESCE(storage_type, 'local') = $1
+                  AND (file_path IN ($2, $3) OR file_path LIKE $4 ESCAPE '\')
+            ) OR EXISTS (
+                SELECT 1 FROM images
+                WHERE COALESCE(storage_type, 'local') = $1
+                  AND (file_path IN ($2, $3) OR file_path LIKE $4 ESCAPE '\')
+            )
+            "#,
+        )
+        .bind(backend_type.as_str())
+        .bind(file_path)
+        .bind(key)
+        .bind(&suffix_pattern)
+        .fetch_one(pool)
+        .await
+    }
+
+    async fn record_issue(
+        pool: &PgPool,
+        check_id: Uuid,
+        backend_type: StorageBackendType,
+        issue: CheckIssue<'_>,
+    ) -> Result<(), String> {
+        log::warn!(
+            "[Storage] 存储不一致 | check_id={}, backend={}, type={}, path={}, action={}",
+            check_id,
+            backend_type.as_str(),
+            issue.issue_type.as_str(),
+            issue.file_path,
+            issue.action.as_str()
+        );
+
+        sqlx::query(
+            r#"
+            INSERT INTO storage_check_issues (
+                check_id, backend, issue_type, file_path, item_type, item_id,
+                expected_size, actual_size, action, detail
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            "#,
+        )
+        .bind(check_id)
+        .bind(backend_type.as_str())
+        .bind(issue.issue_type.as_str())
+        .bind(issue.file_path)
+        .bind(issue.item.map(|item| item.item_type.as_str()))
+        .bind(issue.item.map(|item| item.id))
+        .bind(issue.expected_size)
+        .bind(issue.actual_size)
+        .bind(issue.action.as_str())
+        .bind(&issue.detail)
+        .execute(pool)
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("记录检查结果失败: {}", e))
+    }
+}
+
+/// 匹配以 "/{key}" 结尾的路径的 LIKE 模式，key 中的通配符按字面匹配
+fn like_suffix_pattern(key: &str) -> String {
+    let escaped = key
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%/{}", escaped)
+}
+
+/// 文件最后修改时间是否早于宽限期；无法获知修改时间时视为仍在宽限期内
+fn past_grace_period(last_modified: Option<SystemTime>, now: SystemTime, grace: Duration) -> bool {
+    last_modified
+        .and_then(|modified| now.duration_since(modified).ok())
+        .is_some_and(|age| age >= grace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{
+        cleanup_test_user, create_test_resource, create_test_user, test_pool, typed_storage,
+    };
+    use crate::models::UserRole;
+
+    #[test]
+    fn test_past_grace_period() {
+        let now = SystemTime::now();
+        let grace = Duration::from_secs(3600);
+
+        assert!(past_grace_period(
+            Some(now - Duration::from_secs(7200)),
+            now,
+            grace
+        ));
+        assert!(!past_grace_period(
+            Some(now - Duration::from_secs(60)),
+            now,
+            grace
+        ));
+        // 修改时间在未来（时钟偏差）或未知时不清理
+        assert!(!past_grace_period(
+            Some(now + Duration::from_secs(60)),
+            now,
+            grace
+        ));
+        assert!(!past_grace_period(None, now, grace));
+    }
+
+    #[test]
+    fn test_stored_item_storage_key() {
+        let item = |item_type: &str, file_path: &str| StoredItem {
+            item_type: item_type.to_string(),
+            id: Uuid::nil(),
+            file_path: file_path.to_string(),
+            file_hash: None,
+            file_size: None,
+        };
+
+        assert_eq!(
+            item("resource", "./uploads/resources/a.pdf").storage_key(),
+            "resources/a.pdf"
+        );
+        assert_eq!(
+            item("resource_version", "share/resources/a_v2.md").storage_key(),
+            "resources/a_v2.md"
+        );
+        assert_eq!(
+            item("image", "share/images/b.png").storage_key(),
+            "images/b.png"
+        );
+        // 无法推断 key 的历史路径保持原样
+        assert_eq!(item("image", "legacy/b.png").storage_key(), "legacy/b.png");
+    }
+
+    #[test]
+    fn test_like_suffix_pattern_escapes_wildcards() {
+        assert_eq!(like_suffix_pattern("resources/a.md"), "%/resources/a.md");
+        assert_eq!(
+            like_suffix_pattern("resources/a_b%c\\d.md"),
+            "%/resources/a\\_b\\%c\\\\d.md"
+        );
+    }
+
+    /// 在 OSS 存储下登记一条资源记录（不写入文件）
+    async fn oss_record(pool: &PgPool, uploader_id: Uuid, file_path: &str, file_size: i64) -> Uuid {
+        let hash = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let resource_id = create_test_resource(pool, uploader_id, &hash, "approved").await;
+        sqlx::query(
+            "UPDATE resources SET storage_type = 'oss', file_path = $2, file_size = $3, file_hash = NULL WHERE id = $1",
+        )
+        .bind(resource_id)
+        .bind(file_path)
+        .bind(file_size)
+        .execute(pool)
+        .await
+        .unwrap();
+        resource_id
+    }
+
+    #[tokio::test]
+    async fn test_check_backend_matches_orphan_and_missing_files() {
+        let pool = test_pool().await;
+        let user = create_test_user(&pool, UserRole::User).await;
+        let storage = typed_storage(StorageBackendType::Oss);
+        let tag = Uuid::new_v4().simple().to_string();
+        let save = |name: String, data: &'static [u8]| {
+            let storage = storage.clone();
+            async move {
+                storage
+                    .save_file(&format!("resources/{}", name), data.to_vec(), None)
+                    .await
+                    .unwrap()
+            }
+        };
+
+        // 大小一致的文件、大小不一致的文件、记录存在但文件缺失
+        let ok_path = save(format!("ok_{}.txt", tag), b"hello").await;
+        oss_record(&pool, user.id, &ok_path, 5).await;
+        let resized_path = save(format!("resized_{}.txt", tag), b"hello").await;
+        let resized = oss_record(&pool, user.id, &resized_path, 99).await;
+        let missing_path = save(format!("missing_{}.txt", tag), b"gone").await;
+        storage.delete_file(&missing_path).await.unwrap();
+        let missing = oss_record(&pool, user.id, &missing_path, 4).await;
+
+        // 没有记录引用的文件；其中一个文件名里的 `_` 不能被当作通配符匹配到别的记录
+        let orphan_path = save(format!("orphan-{}.txt", tag), b"orphan").await;
+        let wildcard_path = save(format!("report_{}.txt", tag), b"orphan").await;
+        let lookalike = oss_record(
+            &pool,
+            user.id,
+            &format!("share/resources/reportX{}.txt", tag),
+            6,
+        )
+        .await;
+
+        let check_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO storage_checks (backends, cleanup, status) VALUES ('oss', TRUE, 'completed') RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let job = CheckJob {
+            id: check_id,
+            backends: Vec::new(),
+            cleanup: true,
+            verify_hash: false,
+            grace_period: Duration::ZERO,
+        };
+        StorageCheckService::check_backend(&pool, &job, StorageBackendType::Oss, storage.as_ref())
+            .await
+            .unwrap();
+
+        // 其他测试同时写入的 OSS 记录也会被检查，只核对本测试的路径和记录
+        let issues: Vec<(String, String, Option<Uuid>, String)> = sqlx::query_as(
+            "SELECT issue_type, file_path, item_id, action FROM storage_check_issues WHERE check_id = $1",
+        )
+        .bind(check_id)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        let issue_for = |path: &str| {
+            issues
+                .iter()
+                .filter(|(_, file_path, _, _)| file_path == path)
+                .map(|(issue_type, _, item_id, action)| {
+                    (issue_type.as_str(), *item_id, action.as_str())
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert!(issue_for(&ok_path).is_empty());
+        assert_eq!(
+            issue_for(&resized_path),
+            [("size_mismatch", Some(resized), "reported")]
+        );
+        assert_eq!(
+            issue_for(&missing_path),
+            [("missing_file", Some(missing), "reported")]
+        );
+        assert_eq!(issue_for(&orphan_path), [("orphan_file", None, "deleted")]);
+        assert_eq!(
+            issue_for(&wildcard_path),
+            [("orphan_file", None, "deleted")]
+        );
+        assert_eq!(
+            issue_for(&format!("share/resources/reportX{}.txt", tag)),
+            [("missing_file", Some(lookalike), "reported")]
+        );
+
+        // 清理只删除孤儿文件
+        assert!(storage.read_file(&ok_path).await.is_ok());
+        assert!(storage.read_file(&resized_path).await.is_ok());
+        assert!(matches!(
+            storage.read_file(&orphan_path).await,
+            Err(StorageError::NotFound(_))
+        ));
+        assert!(matches!(
+            storage.read_file(&wildcard_path).await,
+            Err(StorageError::NotFound(_))
+        ));
+
+        sqlx::query("DELETE FROM storage_checks WHERE id = $1")
+            .bind(check_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        cleanup_test_user(&pool, user.id).await;
+    }
+}
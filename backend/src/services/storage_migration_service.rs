@@ -0,0 +1,954 @@
+use futures_util::TryStreamExt;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use crate::models::{
+    CurrentUser, StartStorageMigrationRequest, StorageMigrationDetailResponse,
+    StorageMigrationFailureItem, StorageMigrationResponse, StorageMigrationStatus,
+};
+use crate::services::{
+    storage_key_for, AdminError, FileService, StorageBackend, StorageBackendType,
+    StorageCheckService, StorageError, StorageRegistry,
+};
+
+/// 每批读取的待迁移记录数
+const MIGRATION_BATCH_SIZE: i64 = 50;
+
+/// 任务详情中返回的失败条目上限
+const MAX_FAILURES_IN_DETAIL: i64 = 100;
+
+/// 心跳超过该时长未刷新的运行中任务视为执行进程已退出
+const HEARTBEAT_TIMEOUT_SECS: i64 = 300;
+
+/// 流式计算文件大小和 SHA-256
+#[derive(Clone, Default)]
+struct StreamDigest {
+    hasher: Sha256,
+    size: i64,
+}
+
+impl StreamDigest {
+    fn update(&mut self, chunk: &[u8]) {
+        self.hasher.update(chunk);
+        self.size += chunk.len() as i64;
+    }
+
+    fn finish(self) -> (i64, String) {
+        (self.size, format!("{:x}", self.hasher.finalize()))
+    }
+}
+
+/// 存储迁移服务
+///
+/// 把资源文件（含历史版本）和图床图片从一个存储后端复制到另一个，
+/// 校验哈希后在事务中更新 storage_type / file_path。
+/// 运行中的任务定期刷新心跳；心跳超时的任务由任一实例标记为失败，
+/// 已被标记的任务停止执行且不再改写状态
+pub struct StorageMigrationService;
+
+/// 待迁移的文件类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MigrationItemKind {
+    /// 资源当前文件，同时更新指向同一文件的版本记录
+    Resource,
+    /// 已被替代的历史版本文件
+    ResourceVersion,
+    Image,
+}
+
+impl MigrationItemKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MigrationItemKind::Resource => "resource",
+            MigrationItemKind::ResourceVersion => "resource_version",
+            MigrationItemKind::Image => "image",
+        }
+    }
+
+    /// 存储 key 的目录前缀
+    fn key_prefix(&self) -> &'static str {
+        match self {
+            MigrationItemKind::Resource | MigrationItemKind::ResourceVersion => "resources/",
+            MigrationItemKind::Image => "images/",
+        }
+    }
+
+    /// 查询位于源存储的记录；`$1` 为源存储类型，`$2` 为上一批最后的 ID
+    fn select_sql(&self) -> &'static str {
+        match self {
+            MigrationItemKind::Resource => {
+                r#"
+                SELECT id, file_path, file_hash, file_size, resource_type AS content_type
+                FROM resources
+                WHERE COALESCE(storage_type, 'local') = $1
+                  AND COALESCE(file_path, '') <> ''
+                  AND id > $2
+                ORDER BY id
+                LIMIT $3
+                "#
+            }
+            MigrationItemKind::ResourceVersion => {
+                r#"
+                SELECT v.id, v.file_path, v.file_hash, v.file_size, v.resource_type AS content_type
+                FROM resource_versions v
+                WHERE COALESCE(v.storage_type, 'local') = $1
+                  AND v.file_path <> ''
+                  AND v.id > $2
+                  AND NOT EXISTS (
+                      SELECT 1 FROM resources r
+                      WHERE r.id = v.resource_id AND r.file_path = v.file_path
+                  )
+                ORDER BY v.id
+                LIMIT $3
+                "#
+            }
+            MigrationItemKind::Image => {
+                r#"
+                SELECT id, file_path, NULL::VARCHAR AS file_hash, file_size::BIGINT AS file_size,
+                       mime_type AS content_type
+                FROM images
+                WHERE COALESCE(storage_type, 'local') = $1
+                  AND file_path <> ''
+                  AND id > $2
+                ORDER BY id
+                LIMIT $3
+                "#
+            }
+        }
+    }
+
+    /// 统计位于源存储的记录数
+    fn count_sql(&self) -> &'static str {
+        match self {
+            MigrationItemKind::Resource => {
+                "SELECT COUNT(*) FROM resources WHERE COALESCE(storage_type, 'local') = $1 AND COALESCE(file_path, '') <> ''"
+            }
+            MigrationItemKind::ResourceVersion => {
+                r#"
+                SELECT COUNT(*) FROM resource_versions v
+                WHERE COALESCE(v.storage_type, 'local') = $1
+                  AND v.file_path <> ''
+                  AND NOT EXISTS (
+                      SELECT 1 FROM resources r
+                      WHERE r.id = v.resource_id AND r.file_path = v.file_path
+                  )
+                "#
+            }
+            MigrationItemKind::Image => {
+                "SELECT COUNT(*) FROM images WHERE COALESCE(storage_type, 'local') = $1 AND file_path <> ''"
+            }
+        }
+    }
+
+    /// 文件的 MIME 类型，资源记录里保存的是资源类型
+    fn content_type(&self, value: Option<&str>) -> Option<String> {
+        match self {
+            MigrationItemKind::Resource | MigrationItemKind::ResourceVersion => {
+                value.map(FileService::get_mime_type_by_type)
+            }
+            MigrationItemKind::Image => value.map(str::to_string),
+        }
+    }
+}
+
+/// 一条待迁移的记录
+#[derive(Debug, sqlx::FromRow)]
+struct MigrationItem {
+    id: Uuid,
+    file_path: String,
+    file_hash: Option<String>,
+    file_size: Option<i64>,
+    content_type: Option<String>,
+}
+
+/// 单条记录的迁移结果
+enum ItemOutcome {
+    Migrated(i64),
+    /// 迁移期间记录被修改或删除，已放弃本次复制
+    Skipped,
+}
+
+/// 后台任务的结束方式
+#[derive(Debug, PartialEq, Eq)]
+enum RunEnd {
+    Completed,
+    Cancelled,
+    /// 任务已不处于运行状态（心跳超时被标记为失败），放弃执行
+    Abandoned,
+}
+
+/// 后台执行中的迁移任务
+struct MigrationJob {
+    id: Uuid,
+    source_type: StorageBackendType,
+    target_type: StorageBackendType,
+    source: Arc<dyn StorageBackend>,
+    target: Arc<dyn StorageBackend>,
+    kinds: Vec<MigrationItemKind>,
+    delete_source: bool,
+}
+
+impl StorageMigrationService {
+    /// 运行中任务刷新心跳、以及检查心跳超时任务的间隔
+    pub const HEARTBEAT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+    /// 发起迁移任务，文件复制在后台进行
+    ///
+    /// 同一时间只允许一个任务运行（数据库部分唯一索引保证）
+    pub async fn start_migration(
+        pool: &PgPool,
+        storages: &StorageRegistry,
+        user: &CurrentUser,
+        request: &StartStorageMigrationRequest,
+    ) -> Result<StorageMigrationResponse, AdminError> {
+        let source_type = StorageBackendType::from_storage_type(Some(&request.source));
+        let target_type = StorageBackendType::from_storage_type(Some(&request.target));
+        let include_resources = request.include_resources.unwrap_or(true);
+        let include_images = request.include_images.unwrap_or(true);
+        let delete_source = request.delete_source.unwrap_or(false);
+
+        // 两端存储都必须已配置
+        let source = storages.get(source_type).cloned().ok_or_else(|| {
+            AdminError::ValidationError(format!("源存储 {} 未配置", source_type.as_str()))
+        })?;
+        let target = storages.get(target_type).cloned().ok_or_else(|| {
+            AdminError::ValidationError(format!("目标存储 {} 未配置", target_type.as_str()))
+        })?;
+
+        // 一致性检查会把迁移中尚未被记录引用的目标文件当作孤儿文件
+        let check_running = StorageCheckService::has_running_check(pool)
+            .await
+            .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+        if check_running {
+            return Err(AdminError::Conflict(
+                "存储一致性检查进行中，请等待检查结束后再迁移".to_string(),
+            ));
+        }
+
+        // 先回收执行进程已退出的任务，避免其一直占用运行名额
+        Self::fail_interrupted_migrations(pool)
+            .await
+            .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        let mut kinds = Vec::new();
+        if include_resources {
+            kinds.push(MigrationItemKind::Resource);
+            kinds.push(MigrationItemKind::ResourceVersion);
+        }
+        if include_images {
+            kinds.push(MigrationItemKind::Image);
+        }
+
+        let mut total_items: i64 = 0;
+        for kind in &kinds {
+            let count: i64 = sqlx::query_scalar(kind.count_sql())
+                .bind(source_type.as_str())
+                .fetch_one(pool)
+                .await
+                .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+            total_items += count;
+        }
+
+        let migration: StorageMigrationResponse = sqlx::query_as(
+            r#"
+            INSERT INTO storage_migrations (
+                source_backend, target_backend, include_resources, include_images,
+                delete_source, status, total_items, created_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT DO NOTHING
+            RETURNING *
+            "#,
+        )
+        .bind(source_type.as_str())
+        .bind(target_type.as_str())
+        .bind(include_resources)
+        .bind(include_images)
+        .bind(delete_source)
+        .bind(StorageMigrationStatus::Running.as_str())
+        .bind(total_items as i32)
+        .bind(user.id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| AdminError::Conflict("已有正在进行的存储迁移任务".to_string()))?;
+
+        log::info!(
+            "[Storage] 存储迁移开始 | migration_id={}, source={}, target={}, total={}, delete_source={}",
+            migration.id,
+            migration.source_backend,
+            migration.target_backend,
+            total_items,
+            delete_source
+        );
+
+        let job = MigrationJob {
+            id: migration.id,
+            source_type,
+            target_type,
+            source,
+            target,
+            kinds,
+            delete_source,
+        };
+        let pool = pool.clone();
+        tokio::spawn(async move {
+            Self::run(&pool, job).await;
+        });
+
+        Ok(migration)
+    }
+
+    /// 获取最近的迁移任务
+    pub async fn list_migrations(
+        pool: &PgPool,
+    ) -> Result<Vec<StorageMigrationResponse>, AdminError> {
+        sqlx::query_as("SELECT * FROM storage_migrations ORDER BY created_at DESC LIMIT 20")
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AdminError::DatabaseError(e.to_string()))
+    }
+
+    /// 获取迁移任务详情（含最近的失败条目）
+    pub async fn get_migration(
+        pool: &PgPool,
+        migration_id: Uuid,
+    ) -> Result<StorageMigrationDetailResponse, AdminError> {
+        let migration = Self::find_migration(pool, migration_id).await?;
+
+        let failures: Vec<StorageMigrationFailureItem> = sqlx::query_as(
+            r#"
+            SELECT item_type, item_id, file_path, error, created_at
+            FROM storage_migration_failures
+            WHERE migration_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(migration_id)
+        .bind(MAX_FAILURES_IN_DETAIL)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        Ok(StorageMigrationDetailResponse {
+            migration,
+            failures,
+        })
+    }
+
+    /// 请求取消迁移任务，后台任务处理完当前文件后停止
+    pub async fn cancel_migration(
+        pool: &PgPool,
+        migration_id: Uuid,
+    ) -> Result<StorageMigrationResponse, AdminError> {
+        let migration: Option<StorageMigrationResponse> = sqlx::query_as(
+            r#"
+            UPDATE storage_migrations
+            SET cancel_requested = TRUE, updated_at = NOW()
+            WHERE id = $1 AND status = $2
+            RETURNING *
+            "#,
+        )
+        .bind(migration_id)
+        .bind(StorageMigrationStatus::Running.as_str())
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        match migration {
+            Some(migration) => Ok(migration),
+            None => {
+                // 区分任务不存在和任务已结束
+                Self::find_migration(pool, migration_id).await?;
+                Err(AdminError::Conflict("迁移任务已结束，无法取消".to_string()))
+            }
+        }
+    }
+
+    /// 把心跳超时的运行中任务标记为失败（执行它的进程已退出），以便重新发起
+    ///
+    /// 只处理心跳超时的任务，其他实例上正常运行的任务不受影响
+    pub async fn fail_interrupted_migrations(pool: &PgPool) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE storage_migrations
+            SET status = $1, last_error = '任务心跳超时，执行进程可能已退出',
+                finished_at = NOW(), updated_at = NOW()
+            WHERE status = $2
+              AND COALESCE(heartbeat_at, updated_at) < NOW() - make_interval(secs => $3)
+            "#,
+        )
+        .bind(StorageMigrationStatus::Failed.as_str())
+        .bind(StorageMigrationStatus::Running.as_str())
+        .bind(HEARTBEAT_TIMEOUT_SECS as f64)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// 刷新运行中任务的心跳，任务已不处于运行状态时返回 false
+    async fn heartbeat(pool: &PgPool, migration_id: Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE storage_migrations SET heartbeat_at = NOW() WHERE id = $1 AND status = $2",
+        )
+        .bind(migration_id)
+        .bind(StorageMigrationStatus::Running.as_str())
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn find_migration(
+        pool: &PgPool,
+        migration_id: Uuid,
+    ) -> Result<StorageMigrationResponse, AdminError> {
+        sqlx::query_as("SELECT * FROM storage_migrations WHERE id = $1")
+            .bind(migration_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| AdminError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| AdminError::NotFound("迁移任务不存在".to_string()))
+    }
+
+    /// 后台执行迁移任务并记录最终状态
+    async fn run(pool: &PgPool, job: MigrationJob) {
+        // 单个大文件的复制可能持续很久，心跳由独立任务按固定间隔刷新
+        let heartbeat = tokio::spawn({
+            let pool = pool.clone();
+            let migration_id = job.id;
+            async move {
+                let mut interval = tokio::time::interval(Self::HEARTBEAT_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    match Self::heartbeat(&pool, migration_id).await {
+                        Ok(true) => {}
+                        Ok(false) => break,
+                        Err(e) => log::warn!(
+                            "[Storage] 刷新存储迁移心跳失败 | migration_id={}, error={}",
+                            migration_id,
+                            e
+                        ),
+                    }
+                }
+            }
+        });
+        let outcome = Self::process_all(pool, &job).await;
+        heartbeat.abort();
+
+        let (status, error) = match outcome {
+            Ok(RunEnd::Abandoned) => {
+                log::warn!(
+                    "[Storage] 存储迁移已被标记为中断，停止执行 | migration_id={}",
+                    job.id
+                );
+                return;
+            }
+            Ok(RunEnd::Cancelled) => (StorageMigrationStatus::Cancelled, None),
+            Ok(RunEnd::Completed) => (StorageMigrationStatus::Completed, None),
+            Err(e) => (StorageMigrationStatus::Failed, Some(e.to_string())),
+        };
+
+        // 只结束仍处于运行状态的任务，不覆盖已被标记为中断的结果
+        let result = sqlx::query_as::<_, (i32, i32, i32)>(
+            r#"
+            UPDATE storage_migrations
+            SET status = $2, last_error = COALESCE($3, last_error),
+                finished_at = NOW(), updated_at = NOW()
+            WHERE id = $1 AND status = $4
+            RETURNING migrated_items, failed_items, skipped_items
+            "#,
+        )
+        .bind(job.id)
+        .bind(status.as_str())
+        .bind(&error)
+        .bind(StorageMigrationStatus::Running.as_str())
+        .fetch_optional(pool)
+        .await;
+
+        match result {
+            Ok(Some((migrated, failed, skipped))) => log::info!(
+                "[Storage] 存储迁移结束 | migration_id={}, status={}, migrated={}, failed={}, skipped={}",
+                job.id,
+                status.as_str(),
+                migrated,
+                failed,
+                skipped
+            ),
+            Ok(None) => log::warn!(
+                "[Storage] 存储迁移已被标记为中断，未记录结束状态 | migration_id={}, status={}",
+                job.id,
+                status.as_str()
+            ),
+            Err(e) => log::error!(
+                "[Storage] 更新存储迁移状态失败 | migration_id={}, status={}, error={}",
+                job.id,
+                status.as_str(),
+                e
+            ),
+        }
+    }
+
+    /// 按类别分批处理所有待迁移记录，返回结束方式
+    async fn process_all(pool: &PgPool, job: &MigrationJob) -> Result<RunEnd, sqlx::Error> {
+        for kind in &job.kinds {
+            // 按 ID 顺序分页；迁移成功的记录不再属于源存储，失败的记录靠 last_id 跳过
+            let mut last_id = Uuid::nil();
+            loop {
+                let items: Vec<MigrationItem> = sqlx::query_as(kind.select_sql())
+                    .bind(job.source_type.as_str())
+                    .bind(last_id)
+                    .bind(MIGRATION_BATCH_SIZE)
+                    .fetch_all(pool)
+                    .await?;
+                let Some(last) = items.last() else {
+                    break;
+                };
+                last_id = last.id;
+
+                for item in &items {
+                    let outcome = Self::migrate_item(pool, job, *kind, item).await;
+                    let (migrated, skipped, bytes, error) = match &outcome {
+                        Ok(ItemOutcome::Migrated(bytes)) => (1, 0, *bytes, None),
+                        Ok(ItemOutcome::Skipped) => (0, 1, 0, None),
+                        Err(e) => {
+                            log::warn!(
+                                "[Storage] 迁移文件失败 | migration_id={}, type={}, id={}, path={}, error={}",
+                                job.id,
+                                kind.as_str(),
+                                item.id,
+                                item.file_path,
+                                e
+                            );
+                            sqlx::query(
+                                r#"
+                                INSERT INTO storage_migration_failures
+                                    (migration_id, item_type, item_id, file_path, error)
+                                VALUES ($1, $2, $3, $4, $5)
+                                "#,
+                            )
+                            .bind(job.id)
+                            .bind(kind.as_str())
+                            .bind(item.id)
+                            .bind(&item.file_path)
+                            .bind(e)
+                            .execute(pool)
+                            .await?;
+                            (0, 0, 0, Some(e.as_str()))
+                        }
+                    };
+
+                    let cancel_requested: Option<bool> = sqlx::query_scalar(
+                        r#"
+                        UPDATE storage_migrations
+                        SET processed_items = processed_items + 1,
+                            migrated_items = migrated_items + $2,
+                            skipped_items = skipped_items + $3,
+                            failed_items = failed_items + $4,
+                            migrated_bytes = migrated_bytes + $5,
+                            last_error = COALESCE($6, last_error),
+                            updated_at = NOW(), heartbeat_at = NOW()
+                        WHERE id = $1 AND status = $7
+                        RETURNING cancel_requested
+                        "#,
+                    )
+                    .bind(job.id)
+                    .bind(migrated)
+                    .bind(skipped)
+                    .bind(i32::from(error.is_some()))
+                    .bind(bytes)
+                    .bind(error)
+                    .bind(StorageMigrationStatus::Running.as_str())
+                    .fetch_optional(pool)
+                    .await?;
+
+                    match cancel_requested {
+                        None => return Ok(RunEnd::Abandoned),
+                        Some(true) => {
+                            log::info!("[Storage] 存储迁移已取消 | migration_id={}", job.id);
+                            return Ok(RunEnd::Cancelled);
+                        }
+                        Some(false) => {}
+                    }
+                }
+            }
+        }
+
+        Ok(RunEnd::Completed)
+    }
+
+    /// 迁移单条记录：复制文件、校验哈希、更新数据库，最后按需删除源文件
+    async fn migrate_item(
+        pool: &PgPool,
+        job: &MigrationJob,
+        kind: MigrationItemKind,
+        item: &MigrationItem,
+    ) -> Result<ItemOutcome, String> {
+        let key = storage_key_for(&item.file_path, kind.key_prefix())
+            .ok_or_else(|| "无法从文件路径推断存储 key".to_string())?;
+        let content_type = kind.content_type(item.content_type.as_deref());
+
+        // 边读源文件边写入目标存储，同时统计大小和哈希，避免整个文件驻留内存
+        let source = job
+            .source
+            .read_stream(&item.file_path, None)
+            .await
+            .map_err(|e| format!("读取源文件失败: {}", e))?;
+        let digest = Arc::new(Mutex::new(StreamDigest::default()));
+        let tap = digest.clone();
+        let source = source.inspect_ok(move |chunk| {
+            tap.lock().unwrap_or_else(|e| e.into_inner()).update(chunk);
+        });
+        let new_path = job
+            .target
+            .save_stream(&key, Box::pin(source), content_type.as_deref())
+            .await
+            .map_err(|e| format!("写入目标存储失败: {}", e))?;
+        let (size, hash) = digest
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+            .finish();
+
+        if let Err(e) = Self::check_source(item, size, &hash) {
+            // 源文件与记录不符，删除刚写入的副本
+            if let Err(e) = job.target.delete_file(&new_path).await {
+                log::warn!(
+                    "[Storage] 清理目标存储副本失败 | migration_id={}, path={}, error={}",
+                    job.id,
+                    new_path,
+                    e
+                );
+            }
+            return Err(e);
+        }
+
+        // 流式回读目标文件确认内容一致
+        let verified = match Self::digest_file(job.target.as_ref(), &new_path).await {
+            Ok((copied_size, copied_hash)) if copied_size == size && copied_hash == hash => Ok(()),
+            Ok(_) => Err("目标文件哈希校验失败".to_string()),
+            Err(e) => Err(format!("回读目标文件失败: {}", e)),
+        };
+        let updated = match verified {
+            Ok(()) => Self::switch_storage(pool, job, kind, item, &new_path)
+                .await
+                .map_err(|e| format!("更新数据库失败: {}", e)),
+            Err(e) => Err(e),
+        };
+
+        match updated {
+            Ok(true) => {}
+            result => {
+                // 数据库仍指向源文件，删除刚写入的副本
+                if let Err(e) = job.target.delete_file(&new_path).await {
+                    log::warn!(
+                        "[Storage] 清理目标存储副本失败 | migration_id={}, path={}, error={}",
+                        job.id,
+                        new_path,
+                        e
+                    );
+                }
+                return result.map(|_| ItemOutcome::Skipped);
+            }
+        }
+
+        if job.delete_source {
+            if let Err(e) = job.source.delete_file(&item.file_path).await {
+                log::warn!(
+                    "[Storage] 删除源文件失败 | migration_id={}, path={}, error={}",
+                    job.id,
+                    item.file_path,
+                    e
+                );
+            }
+        }
+
+        Ok(ItemOutcome::Migrated(size))
+    }
+
+    /// 流式读取文件并计算大小和哈希
+    async fn digest_file(
+        storage: &dyn StorageBackend,
+        path: &str,
+    ) -> Result<(i64, String), StorageError> {
+        let mut stream = storage.read_stream(path, None).await?;
+        let mut digest = StreamDigest::default();
+        while let Some(chunk) = stream.try_next().await? {
+            digest.update(&chunk);
+        }
+        Ok(digest.finish())
+    }
+
+    /// 源文件大小和哈希须与数据库记录一致（记录缺失时不校验）
+    fn check_source(item: &MigrationItem, size: i64, hash: &str) -> Result<(), String> {
+        if let Some(expected) = item.file_size {
+            if expected != size {
+                return Err(format!(
+                    "源文件大小与记录不一致: 记录 {}，实际 {}",
+                    expected, size
+                ));
+            }
+        }
+        if let Some(expected) = item.file_hash.as_deref().filter(|h| !h.is_empty()) {
+            if !expected.eq_ignore_ascii_case(hash) {
+                return Err("源文件哈希与记录不一致".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// 在事务中把记录指向目标存储，记录已被修改（文件路径或存储类型变化）时返回 false
+    async fn switch_storage(
+        pool: &PgPool,
+        job: &MigrationJob,
+        kind: MigrationItemKind,
+        item: &MigrationItem,
+        new_path: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+
+        let sql = match kind {
+            MigrationItemKind::Resource => {
+                "UPDATE resources SET file_path = $1, storage_type = $2 \
+                 WHERE id = $3 AND file_path = $4 AND COALESCE(storage_type, 'local') = $5"
+            }
+            MigrationItemKind::ResourceVersion => {
+                "UPDATE resource_versions SET file_path = $1, storage_type = $2 \
+                 WHERE id = $3 AND file_path = $4 AND COALESCE(storage_type, 'local') = $5"
+            }
+            MigrationItemKind::Image => {
+                "UPDATE images SET file_path = $1, storage_type = $2 \
+                 WHERE id = $3 AND file_path = $4 AND COALESCE(storage_type, 'local') = $5"
+            }
+        };
+        let result = sqlx::query(sql)
+            .bind(new_path)
+            .bind(job.target_type.as_str())
+            .bind(item.id)
+            .bind(&item.file_path)
+            .bind(job.source_type.as_str())
+            .execute(&mut *tx)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        // 当前版本记录与资源共用同一个文件
+        if kind == MigrationItemKind::Resource {
+            sqlx::query(
+                r#"
+                UPDATE resource_versions SET file_path = $1, storage_type = $2
+                WHERE resource_id = $3 AND file_path = $4 AND COALESCE(storage_type, 'local') = $5
+                "#,
+            )
+            .bind(new_path)
+            .bind(job.target_type.as_str())
+            .bind(item.id)
+            .bind(&item.file_path)
+            .bind(job.source_type.as_str())
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{
+        cleanup_test_user, create_test_resource, create_test_user, test_pool, typed_storage,
+    };
+    use crate::models::UserRole;
+
+    /// OSS -> S3 的迁移任务；测试库中只有本测试创建的记录位于 OSS
+    fn job(
+        id: Uuid,
+        source: &Arc<dyn StorageBackend>,
+        target: &Arc<dyn StorageBackend>,
+    ) -> MigrationJob {
+        MigrationJob {
+            id,
+            source_type: StorageBackendType::Oss,
+            target_type: StorageBackendType::S3,
+            source: source.clone(),
+            target: target.clone(),
+            kinds: vec![MigrationItemKind::Resource],
+            delete_source: false,
+        }
+    }
+
+    /// 在源存储中准备一条资源记录及其文件
+    async fn oss_resource(
+        pool: &PgPool,
+        source: &Arc<dyn StorageBackend>,
+        uploader_id: Uuid,
+    ) -> Uuid {
+        let content = format!("迁移测试 {}", Uuid::new_v4()).into_bytes();
+        let key = format!("resources/{}.txt", Uuid::new_v4().simple());
+        let path = source.save_file(&key, content.clone(), None).await.unwrap();
+        let resource_id = create_test_resource(
+            pool,
+            uploader_id,
+            &FileService::calculate_hash(&content),
+            "approved",
+        )
+        .await;
+        sqlx::query(
+            "UPDATE resources SET storage_type = 'oss', file_path = $2, file_size = $3 WHERE id = $1",
+        )
+        .bind(resource_id)
+        .bind(path)
+        .bind(content.len() as i64)
+        .execute(pool)
+        .await
+        .unwrap();
+        resource_id
+    }
+
+    /// 直接插入一条运行中的任务，`heartbeat_age_secs` 为上次心跳至今的秒数
+    async fn insert_running(
+        pool: &PgPool,
+        cancel_requested: bool,
+        heartbeat_age_secs: i64,
+    ) -> Uuid {
+        sqlx::query_scalar(
+            r#"
+            INSERT INTO storage_migrations (source_backend, target_backend, status, cancel_requested, heartbeat_at)
+            VALUES ('oss', 's3', 'running', $1, NOW() - make_interval(secs => $2))
+            RETURNING id
+            "#,
+        )
+        .bind(cancel_requested)
+        .bind(heartbeat_age_secs as f64)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn status_of(pool: &PgPool, migration_id: Uuid) -> (String, i32) {
+        sqlx::query_as("SELECT status, migrated_items FROM storage_migrations WHERE id = $1")
+            .bind(migration_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn storage_type_of(pool: &PgPool, resource_id: Uuid) -> String {
+        sqlx::query_scalar("SELECT storage_type FROM resources WHERE id = $1")
+            .bind(resource_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    /// 同一时间只允许一个运行中的任务，各状态转换放在同一个测试中顺序执行
+    #[tokio::test]
+    async fn test_migration_state_machine() {
+        let pool = test_pool().await;
+        let user = create_test_user(&pool, UserRole::Admin).await;
+        let source = typed_storage(StorageBackendType::Oss);
+        let target = typed_storage(StorageBackendType::S3);
+        let mut migration_ids = Vec::new();
+
+        // running -> completed：文件复制到目标存储，记录指向目标存储
+        let resource_id = oss_resource(&pool, &source, user.id).await;
+        let id = insert_running(&pool, false, 0).await;
+        migration_ids.push(id);
+        StorageMigrationService::run(&pool, job(id, &source, &target)).await;
+        assert_eq!(status_of(&pool, id).await, ("completed".to_string(), 1));
+        assert_eq!(storage_type_of(&pool, resource_id).await, "s3");
+
+        // 已结束的任务不能取消，不存在的任务返回 NotFound
+        assert!(matches!(
+            StorageMigrationService::cancel_migration(&pool, id).await,
+            Err(AdminError::Conflict(_))
+        ));
+        assert!(matches!(
+            StorageMigrationService::cancel_migration(&pool, Uuid::new_v4()).await,
+            Err(AdminError::NotFound(_))
+        ));
+
+        // running -> cancelled：处理完当前文件后停止
+        let first = oss_resource(&pool, &source, user.id).await;
+        let second = oss_resource(&pool, &source, user.id).await;
+        let id = insert_running(&pool, false, 0).await;
+        migration_ids.push(id);
+        let cancelled = StorageMigrationService::cancel_migration(&pool, id)
+            .await
+            .unwrap();
+        assert!(cancelled.cancel_requested);
+        StorageMigrationService::run(&pool, job(id, &source, &target)).await;
+        assert_eq!(status_of(&pool, id).await, ("cancelled".to_string(), 1));
+        let mut types = vec![
+            storage_type_of(&pool, first).await,
+            storage_type_of(&pool, second).await,
+        ];
+        types.sort();
+        assert_eq!(types, ["oss", "s3"]);
+
+        // 心跳超时的任务被回收，心跳正常的任务不受影响
+        let fresh = insert_running(&pool, false, 0).await;
+        migration_ids.push(fresh);
+        assert_eq!(
+            StorageMigrationService::fail_interrupted_migrations(&pool)
+                .await
+                .unwrap(),
+            0
+        );
+        // 已有运行中的任务时再次发起返回 Conflict
+        let request = StartStorageMigrationRequest {
+            source: "local".to_string(),
+            target: "local".to_string(),
+            include_resources: None,
+            include_images: None,
+            delete_source: None,
+        };
+        let registry = StorageRegistry::new(typed_storage(StorageBackendType::Local));
+        assert!(matches!(
+            StorageMigrationService::start_migration(&pool, &registry, &user, &request).await,
+            Err(AdminError::Conflict(_))
+        ));
+
+        sqlx::query(
+            "UPDATE storage_migrations SET heartbeat_at = NOW() - INTERVAL '1 hour' WHERE id = $1",
+        )
+        .bind(fresh)
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            StorageMigrationService::fail_interrupted_migrations(&pool)
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(status_of(&pool, fresh).await.0, "failed");
+        assert!(!StorageMigrationService::heartbeat(&pool, fresh)
+            .await
+            .unwrap());
+
+        // 已被判定中断的任务停止执行，且不改写失败状态
+        let id = insert_running(&pool, false, 0).await;
+        migration_ids.push(id);
+        sqlx::query("UPDATE storage_migrations SET status = 'failed' WHERE id = $1")
+            .bind(id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        StorageMigrationService::run(&pool, job(id, &source, &target)).await;
+        assert_eq!(status_of(&pool, id).await, ("failed".to_string(), 0));
+
+        sqlx::query("DELETE FROM storage_migrations WHERE id = ANY($1)")
+            .bind(&migration_ids)
+            .execute(&pool)
+            .await
+            .unwrap();
+        cleanup_test_user(&pool, user.id).await;
+    }
+}
@@ -0,0 +1,574 @@
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::models::{
+    RoleStorageQuotaItem, StorageUsage, UserRole, UserStorageQuotaResponse, QUOTA_ROLES,
+};
+use crate::services::AdminError;
+
+const MB: i64 = 1024 * 1024;
+const GB: i64 = 1024 * MB;
+
+#[derive(Debug)]
+pub enum StorageQuotaError {
+    DatabaseError(String),
+    UserNotFound(String),
+    QuotaExceeded(String),
+}
+
+impl std::fmt::Display for StorageQuotaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageQuotaError::DatabaseError(msg) => write!(f, "数据库错误: {}", msg),
+            StorageQuotaError::UserNotFound(msg) => write!(f, "{}", msg),
+            StorageQuotaError::QuotaExceeded(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for StorageQuotaError {}
+
+impl From<sqlx::Error> for StorageQuotaError {
+    fn from(err: sqlx::Error) -> Self {
+        StorageQuotaError::DatabaseError(err.to_string())
+    }
+}
+
+/// 计算用量和配额所需的数据
+#[derive(Debug, sqlx::FromRow)]
+struct QuotaRow {
+    role: String,
+    resource_bytes: i64,
+    version_bytes: i64,
+    image_bytes: i64,
+    has_user_quota: bool,
+    user_quota_bytes: Option<i64>,
+    has_role_quota: bool,
+    role_quota_bytes: Option<i64>,
+}
+
+impl QuotaRow {
+    fn usage(&self) -> StorageUsage {
+        let (quota_bytes, quota_source) = if self.has_user_quota {
+            (self.user_quota_bytes, "user")
+        } else if self.has_role_quota {
+            (self.role_quota_bytes, "role")
+        } else {
+            (
+                StorageQuotaService::default_role_quota(&parse_role(&self.role)),
+                "role",
+            )
+        };
+        let used_bytes = self.resource_bytes + self.version_bytes + self.image_bytes;
+        StorageUsage {
+            used_bytes,
+            resource_bytes: self.resource_bytes,
+            version_bytes: self.version_bytes,
+            image_bytes: self.image_bytes,
+            quota_bytes,
+            remaining_bytes: quota_bytes.map(|quota| (quota - used_bytes).max(0)),
+            quota_source: quota_source.to_string(),
+        }
+    }
+}
+
+/// 存储配额服务
+///
+/// 用量按用户上传的资源（resources.file_size）、资源的历史版本文件
+/// （非最新版本的 resource_versions.file_size）和图片（images.file_size）合计。
+/// 配额优先取管理员为用户单独设置的值，其次是角色配额（role_storage_quotas 表），
+/// 表中没有对应角色时使用代码中的默认值
+pub struct StorageQuotaService;
+
+impl StorageQuotaService {
+    /// 角色默认配额，None 表示不限
+    pub fn default_role_quota(role: &UserRole) -> Option<i64> {
+        match role {
+            UserRole::Admin => None,
+            UserRole::Verified => Some(5 * GB),
+            UserRole::User => Some(GB),
+            UserRole::Guest => Some(0),
+        }
+    }
+
+    async fn fetch_quota_row<'e>(
+        executor: impl PgExecutor<'e>,
+        user_id: Uuid,
+    ) -> Result<Option<QuotaRow>, sqlx::Error> {
+        sqlx::query_as::<_, QuotaRow>(
+            r#"
+            SELECT u.role,
+                   COALESCE((SELECT SUM(file_size) FROM resources WHERE uploader_id = u.id), 0)::BIGINT AS resource_bytes,
+                   COALESCE((
+                       SELECT SUM(v.file_size)
+                       FROM resource_versions v
+                       JOIN resources r ON r.id = v.resource_id
+                       WHERE r.uploader_id = u.id
+                         AND v.version_number < (
+                             SELECT MAX(version_number) FROM resource_versions WHERE resource_id = v.resource_id
+                         )
+                   ), 0)::BIGINT AS version_bytes,
+                   COALESCE((SELECT SUM(file_size) FROM images WHERE uploader_id = u.id), 0)::BIGINT AS image_bytes,
+                   uq.id IS NOT NULL AS has_user_quota,
+                   uq.quota_bytes AS user_quota_bytes,
+                   rq.id IS NOT NULL AS has_role_quota,
+                   rq.quota_bytes AS role_quota_bytes
+            FROM users u
+            LEFT JOIN user_storage_quotas uq ON uq.user_id = u.id
+            LEFT JOIN role_storage_quotas rq ON rq.role = u.role
+            WHERE u.id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(executor)
+        .await
+    }
+
+    /// 获取用户的存储空间使用情况
+    pub async fn get_usage(
+        pool: &PgPool,
+        user_id: Uuid,
+    ) -> Result<StorageUsage, StorageQuotaError> {
+        Self::fetch_quota_row(pool, user_id)
+            .await?
+            .map(|row| row.usage())
+            .ok_or_else(|| StorageQuotaError::UserNotFound("用户不存在".to_string()))
+    }
+
+    /// 检查用户是否还能再存储 additional_bytes 字节
+    ///
+    /// 上传前调用，用于在写入文件之前尽早拒绝；并发上传可能同时通过此检查，
+    /// 写入记录的事务内还需调用 `lock_and_ensure_quota`
+    pub async fn ensure_quota(
+        pool: &PgPool,
+        user_id: Uuid,
+        additional_bytes: i64,
+    ) -> Result<(), StorageQuotaError> {
+        if additional_bytes <= 0 {
+            return Ok(());
+        }
+        let usage = Self::get_usage(pool, user_id).await?;
+        Self::check_usage(user_id, &usage, additional_bytes)
+    }
+
+    /// 在写入记录的事务内锁定用户行后再检查配额
+    ///
+    /// 同一用户的并发上传在此排队，直到前一个事务提交后才能看到其新增的用量，
+    /// 避免都通过检查后合计超出配额
+    pub async fn lock_and_ensure_quota(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: Uuid,
+        additional_bytes: i64,
+    ) -> Result<(), StorageQuotaError> {
+        // NO KEY UPDATE 与外键引用所需的 KEY SHARE 锁不冲突，不阻塞其他表插入该用户的记录
+        sqlx::query("SELECT id FROM users WHERE id = $1 FOR NO KEY UPDATE")
+            .bind(user_id)
+            .fetch_optional(&mut **tx)
+            .await?
+            .ok_or_else(|| StorageQuotaError::UserNotFound("用户不存在".to_string()))?;
+
+        if additional_bytes <= 0 {
+            return Ok(());
+        }
+        let usage = Self::fetch_quota_row(&mut **tx, user_id)
+            .await?
+            .map(|row| row.usage())
+            .ok_or_else(|| StorageQuotaError::UserNotFound("用户不存在".to_string()))?;
+        Self::check_usage(user_id, &usage, additional_bytes)
+    }
+
+    fn check_usage(
+        user_id: Uuid,
+        usage: &StorageUsage,
+        additional_bytes: i64,
+    ) -> Result<(), StorageQuotaError> {
+        if let Err(msg) = check_quota(usage, additional_bytes) {
+            log::info!(
+                "[StorageQuota] 存储空间不足 | user_id={}, used={}, quota={:?}, additional={}",
+                user_id,
+                usage.used_bytes,
+                usage.quota_bytes,
+                additional_bytes
+            );
+            return Err(StorageQuotaError::QuotaExceeded(msg));
+        }
+        Ok(())
+    }
+
+    /// 获取各角色的存储配额
+    pub async fn list_role_quotas(pool: &PgPool) -> Result<Vec<RoleStorageQuotaItem>, AdminError> {
+        let mut rows: Vec<RoleStorageQuotaItem> = sqlx::query_as(
+            "SELECT role, quota_bytes, FALSE AS is_default, updated_by, updated_at FROM role_storage_quotas",
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        Ok(QUOTA_ROLES
+            .iter()
+            .map(|role| match rows.iter().position(|row| row.role == *role) {
+                Some(index) => rows.swap_remove(index),
+                None => RoleStorageQuotaItem {
+                    role: role.to_string(),
+                    quota_bytes: Self::default_role_quota(&parse_role(role)),
+                    is_default: true,
+                    updated_by: None,
+                    updated_at: None,
+                },
+            })
+            .collect())
+    }
+
+    /// 设置角色存储配额
+    pub async fn set_role_quota(
+        pool: &PgPool,
+        admin_id: Uuid,
+        role: &str,
+        quota_bytes: Option<i64>,
+    ) -> Result<(), AdminError> {
+        if !QUOTA_ROLES.contains(&role) {
+            return Err(AdminError::ValidationError(format!(
+                "不支持的角色: {}",
+                role
+            )));
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO role_storage_quotas (role, quota_bytes, updated_by)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (role) DO UPDATE
+            SET quota_bytes = EXCLUDED.quota_bytes,
+                updated_by = EXCLUDED.updated_by,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(role)
+        .bind(quota_bytes)
+        .bind(admin_id)
+        .execute(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// 获取指定用户的存储配额和用量
+    pub async fn get_user_quota(
+        pool: &PgPool,
+        user_id: Uuid,
+    ) -> Result<UserStorageQuotaResponse, AdminError> {
+        let username: String = sqlx::query_scalar("SELECT username FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| AdminError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| AdminError::NotFound("用户不存在".to_string()))?;
+
+        let row = Self::fetch_quota_row(pool, user_id)
+            .await
+            .map_err(|e| AdminError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| AdminError::NotFound("用户不存在".to_string()))?;
+
+        let (note, updated_by, updated_at): (
+            Option<String>,
+            Option<Uuid>,
+            Option<chrono::NaiveDateTime>,
+        ) = sqlx::query_as(
+            "SELECT note, updated_by, updated_at FROM user_storage_quotas WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?
+        .unwrap_or_default();
+
+        Ok(UserStorageQuotaResponse {
+            user_id,
+            username,
+            usage: row.usage(),
+            role: row.role,
+            has_override: row.has_user_quota,
+            note,
+            updated_by,
+            updated_at,
+        })
+    }
+
+    /// 为用户单独设置存储配额（覆盖角色配额）
+    pub async fn set_user_quota(
+        pool: &PgPool,
+        admin_id: Uuid,
+        user_id: Uuid,
+        quota_bytes: Option<i64>,
+        note: Option<&str>,
+    ) -> Result<UserStorageQuotaResponse, AdminError> {
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
+            .bind(user_id)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+        if !exists {
+            return Err(AdminError::NotFound("用户不存在".to_string()));
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO user_storage_quotas (user_id, quota_bytes, note, updated_by)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (user_id) DO UPDATE
+            SET quota_bytes = EXCLUDED.quota_bytes,
+                note = EXCLUDED.note,
+                updated_by = EXCLUDED.updated_by,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(user_id)
+        .bind(quota_bytes)
+        .bind(note)
+        .bind(admin_id)
+        .execute(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        Self::get_user_quota(pool, user_id).await
+    }
+
+    /// 取消用户的单独配额，恢复为角色配额
+    pub async fn clear_user_quota(
+        pool: &PgPool,
+        user_id: Uuid,
+    ) -> Result<UserStorageQuotaResponse, AdminError> {
+        sqlx::query("DELETE FROM user_storage_quotas WHERE user_id = $1")
+            .bind(user_id)
+            .execute(pool)
+            .await
+            .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        Self::get_user_quota(pool, user_id).await
+    }
+}
+
+/// 将 users.role 解析为 UserRole，未知角色按游客处理（不分配存储空间）
+fn parse_role(role: &str) -> UserRole {
+    match role {
+        "admin" => UserRole::Admin,
+        "verified" => UserRole::Verified,
+        "user" => UserRole::User,
+        _ => UserRole::Guest,
+    }
+}
+
+/// 判断在当前用量上再增加 additional_bytes 是否超出配额，超出时返回提示信息
+fn check_quota(usage: &StorageUsage, additional_bytes: i64) -> Result<(), String> {
+    let Some(quota) = usage.quota_bytes else {
+        return Ok(());
+    };
+    if usage.used_bytes.saturating_add(additional_bytes) <= quota {
+        return Ok(());
+    }
+    Err(format!(
+        "存储空间不足：本次上传需要 {}，剩余 {}（已使用 {} / 配额 {}）。请删除不需要的资源或图片后重试",
+        format_bytes(additional_bytes),
+        format_bytes((quota - usage.used_bytes).max(0)),
+        format_bytes(usage.used_bytes),
+        format_bytes(quota)
+    ))
+}
+
+/// 格式化字节数，如 1.50MB
+fn format_bytes(bytes: i64) -> String {
+    if bytes >= GB {
+        format!("{:.2}GB", bytes as f64 / GB as f64)
+    } else if bytes >= MB {
+        format!("{:.2}MB", bytes as f64 / MB as f64)
+    } else if bytes >= 1024 {
+        format!("{:.2}KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{}B", bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{
+        cleanup_test_user, create_test_resource, create_test_user, test_pool,
+    };
+    use crate::models::UserRole;
+
+    fn usage(used_bytes: i64, quota_bytes: Option<i64>) -> StorageUsage {
+        StorageUsage {
+            used_bytes,
+            resource_bytes: used_bytes,
+            version_bytes: 0,
+            image_bytes: 0,
+            quota_bytes,
+            remaining_bytes: None,
+            quota_source: "role".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_check_quota() {
+        assert!(check_quota(&usage(GB, None), GB).is_ok());
+        assert!(check_quota(&usage(GB - MB, Some(GB)), MB).is_ok());
+
+        let err = check_quota(&usage(GB - MB, Some(GB)), MB + 1).unwrap_err();
+        assert!(err.contains("需要 1.00MB"));
+        assert!(err.contains("剩余 1.00MB"));
+        assert!(err.contains("已使用 1023.00MB / 配额 1.00GB"));
+
+        // 配额为 0 时不能上传任何文件
+        assert!(check_quota(&usage(0, Some(0)), 1).is_err());
+    }
+
+    #[test]
+    fn test_usage_prefers_user_quota() {
+        let mut row = QuotaRow {
+            role: "user".to_string(),
+            resource_bytes: 2 * MB,
+            version_bytes: MB,
+            image_bytes: MB,
+            has_user_quota: false,
+            user_quota_bytes: None,
+            has_role_quota: false,
+            role_quota_bytes: None,
+        };
+        let usage = row.usage();
+        assert_eq!(usage.used_bytes, 4 * MB);
+        assert_eq!(usage.quota_bytes, Some(GB));
+        assert_eq!(usage.remaining_bytes, Some(GB - 4 * MB));
+
+        row.has_role_quota = true;
+        row.role_quota_bytes = Some(2 * MB);
+        let usage = row.usage();
+        assert_eq!(usage.quota_bytes, Some(2 * MB));
+        assert_eq!(usage.remaining_bytes, Some(0));
+
+        // 单独设置为不限时优先于角色配额
+        row.has_user_quota = true;
+        let usage = row.usage();
+        assert_eq!(usage.quota_bytes, None);
+        assert_eq!(usage.quota_source, "user");
+    }
+
+    #[test]
+    fn test_default_role_quota() {
+        assert_eq!(
+            StorageQuotaService::default_role_quota(&UserRole::Admin),
+            None
+        );
+        assert_eq!(
+            StorageQuotaService::default_role_quota(&UserRole::User),
+            Some(GB)
+        );
+        assert_eq!(
+            StorageQuotaService::default_role_quota(&UserRole::Verified),
+            Some(5 * GB)
+        );
+        assert_eq!(
+            StorageQuotaService::default_role_quota(&UserRole::Guest),
+            Some(0)
+        );
+
+        // 未知角色不分配存储空间
+        assert_eq!(
+            StorageQuotaService::default_role_quota(&parse_role("banned")),
+            Some(0)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_usage_counts_previous_versions() {
+        let pool = test_pool().await;
+        let user = create_test_user(&pool, UserRole::User).await;
+        let resource_id = create_test_resource(&pool, user.id, "quota_versions", "approved").await;
+
+        // 当前版本（最大版本号）即资源本身，只有之前的版本额外计入用量
+        for (version_number, file_size) in [(1, 10_i64), (2, 20), (3, 4)] {
+            sqlx::query(
+                r#"
+                INSERT INTO resource_versions (
+                    resource_id, version_number, resource_type, file_path, file_size, created_by
+                )
+                VALUES ($1, $2, 'txt', $3, $4, $5)
+                "#,
+            )
+            .bind(resource_id)
+            .bind(version_number)
+            .bind(format!("resources/v{}.txt", version_number))
+            .bind(file_size)
+            .bind(user.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let usage = StorageQuotaService::get_usage(&pool, user.id)
+            .await
+            .unwrap();
+        assert_eq!(usage.resource_bytes, 4);
+        assert_eq!(usage.version_bytes, 30);
+        assert_eq!(usage.used_bytes, 34);
+
+        cleanup_test_user(&pool, user.id).await;
+
+        let err = StorageQuotaService::get_usage(&pool, user.id)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, StorageQuotaError::UserNotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_uploads_respect_quota() {
+        let pool = test_pool().await;
+        let user = create_test_user(&pool, UserRole::User).await;
+        sqlx::query("INSERT INTO user_storage_quotas (user_id, quota_bytes) VALUES ($1, 10)")
+            .bind(user.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        async fn insert_image(
+            tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+            user_id: Uuid,
+        ) -> Result<(), StorageQuotaError> {
+            StorageQuotaService::lock_and_ensure_quota(tx, user_id, 6).await?;
+            sqlx::query("INSERT INTO images (uploader_id, file_path, file_size) VALUES ($1, 'images/quota.png', 6)")
+                .bind(user_id)
+                .execute(&mut **tx)
+                .await?;
+            Ok(())
+        }
+
+        // 两次上传单独检查都能通过，合计则超出配额
+        let mut first = pool.begin().await.unwrap();
+        insert_image(&mut first, user.id).await.unwrap();
+
+        let second = tokio::spawn({
+            let pool = pool.clone();
+            let user_id = user.id;
+            async move {
+                let mut tx = pool.begin().await.unwrap();
+                insert_image(&mut tx, user_id).await?;
+                tx.commit().await?;
+                Ok::<(), StorageQuotaError>(())
+            }
+        });
+
+        // 第二次上传需等待第一次的事务结束
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        assert!(!second.is_finished());
+        first.commit().await.unwrap();
+
+        let err = second.await.unwrap().unwrap_err();
+        assert!(matches!(err, StorageQuotaError::QuotaExceeded(_)));
+        let usage = StorageQuotaService::get_usage(&pool, user.id)
+            .await
+            .unwrap();
+        assert_eq!(usage.image_bytes, 6);
+
+        cleanup_test_user(&pool, user.id).await;
+    }
+}
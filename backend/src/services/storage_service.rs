@@ -1,29 +1,61 @@
+use std::collections::HashMap;
 use std::future::Future;
+use std::io::SeekFrom;
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use actix_web::web::Bytes;
+use futures_util::{Stream, TryStreamExt};
 use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 
 use crate::config::Config;
 
 use super::oss_service::OssStorage;
+use super::s3_service::S3Storage;
 
 pub type StorageFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, StorageError>> + Send + 'a>>;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// 流式读取返回的字节流
+pub type StorageByteStream = Pin<Box<dyn Stream<Item = Result<Bytes, StorageError>> + Send>>;
+
+/// 列举文件返回的条目流，按需分页读取
+pub type StorageObjectStream<'a> =
+    Pin<Box<dyn Stream<Item = Result<StorageObject, StorageError>> + Send + 'a>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum StorageBackendType {
     Local,
     Oss,
+    S3,
 }
 
 impl StorageBackendType {
+    pub const ALL: [StorageBackendType; 3] = [Self::Local, Self::Oss, Self::S3];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             Self::Local => "local",
             Self::Oss => "oss",
+            Self::S3 => "s3",
+        }
+    }
+
+    /// 解析数据库中记录的 storage_type，空值或未知值视为本地存储
+    pub fn from_storage_type(storage_type: Option<&str>) -> Self {
+        match storage_type {
+            Some("oss") => Self::Oss,
+            Some("s3") => Self::S3,
+            _ => Self::Local,
         }
     }
+
+    /// 是否为对象存储（可生成签名 URL 让浏览器直接访问）
+    pub fn is_remote(&self) -> bool {
+        *self != Self::Local
+    }
 }
 
 #[derive(Debug)]
@@ -53,8 +85,18 @@ impl std::error::Error for StorageError {}
 pub struct StorageFileMetadata {
     pub content_length: Option<u64>,
     pub content_type: Option<String>,
-    #[allow(dead_code)]
+    /// 不含引号的 ETag
     pub etag: Option<String>,
+    pub last_modified: Option<SystemTime>,
+}
+
+/// 列举文件时返回的条目
+#[derive(Debug, Clone)]
+pub struct StorageObject {
+    /// 与 save_file 返回值形式相同（本地为完整路径，对象存储为带前缀的 key），可直接用于读取和删除
+    pub key: String,
+    pub size: u64,
+    pub last_modified: Option<SystemTime>,
 }
 
 #[derive(Debug, Clone)]
@@ -78,6 +120,23 @@ pub trait StorageBackend: Send + Sync {
         content_type: Option<&'a str>,
     ) -> StorageFuture<'a, String>;
 
+    /// 流式保存文件，返回值与 `save_file` 相同
+    /// 默认实现读完整个流后调用 `save_file`，支持边读边写的后端应覆盖此方法
+    fn save_stream<'a>(
+        &'a self,
+        key: &'a str,
+        mut stream: StorageByteStream,
+        content_type: Option<&'a str>,
+    ) -> StorageFuture<'a, String> {
+        Box::pin(async move {
+            let mut data = Vec::new();
+            while let Some(chunk) = stream.try_next().await? {
+                data.extend_from_slice(&chunk);
+            }
+            self.save_file(key, data, content_type).await
+        })
+    }
+
     fn read_file<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Vec<u8>>;
 
     fn write_file<'a>(
@@ -107,6 +166,30 @@ pub trait StorageBackend: Send + Sync {
 
     fn head_file<'a>(&'a self, key: &'a str) -> StorageFuture<'a, StorageFileMetadata>;
 
+    /// 列举指定目录前缀（如 `resources/`）下的全部文件，边列举边返回，不在内存中保留完整列表
+    fn list_files<'a>(&'a self, prefix: &'a str) -> StorageObjectStream<'a>;
+
+    /// 流式读取文件，`range` 为闭区间字节范围 `(start, end)`，None 表示整个文件
+    /// 默认实现整体读取后再切片，支持按需读取的后端应覆盖此方法
+    fn read_stream<'a>(
+        &'a self,
+        key: &'a str,
+        range: Option<(u64, u64)>,
+    ) -> StorageFuture<'a, StorageByteStream> {
+        Box::pin(async move {
+            let data = self.read_file(key).await?;
+            let data = match range {
+                Some((start, end)) => data
+                    .get(start as usize..=end as usize)
+                    .map(<[u8]>::to_vec)
+                    .ok_or_else(|| StorageError::Validation("读取范围超出文件大小".to_string()))?,
+                None => data,
+            };
+            let stream = futures_util::stream::once(async move { Ok(Bytes::from(data)) });
+            Ok(Box::pin(stream) as StorageByteStream)
+        })
+    }
+
     fn get_sts_token<'a>(
         &'a self,
         _key: &'a str,
@@ -137,6 +220,9 @@ pub struct LocalStorage {
 }
 
 impl LocalStorage {
+    /// 流式读取时每次读取的字节数
+    const STREAM_CHUNK_SIZE: u64 = 64 * 1024;
+
     pub fn new(base_path: String, base_url: String) -> Self {
         Self {
             base_path: PathBuf::from(base_path),
@@ -194,6 +280,46 @@ impl StorageBackend for LocalStorage {
         })
     }
 
+    fn save_stream<'a>(
+        &'a self,
+        key: &'a str,
+        mut stream: StorageByteStream,
+        _content_type: Option<&'a str>,
+    ) -> StorageFuture<'a, String> {
+        Box::pin(async move {
+            let full_path = self.resolve_local_path(key)?;
+
+            if let Some(parent) = full_path.parent() {
+                fs::create_dir_all(parent)
+                    .await
+                    .map_err(|e| StorageError::Io(format!("创建目录失败: {}", e)))?;
+            }
+
+            let mut file = fs::File::create(&full_path)
+                .await
+                .map_err(|e| StorageError::Io(format!("写入文件失败: {}", e)))?;
+            let written: Result<(), StorageError> = async {
+                while let Some(chunk) = stream.try_next().await? {
+                    file.write_all(&chunk)
+                        .await
+                        .map_err(|e| StorageError::Io(format!("写入文件失败: {}", e)))?;
+                }
+                file.flush()
+                    .await
+                    .map_err(|e| StorageError::Io(format!("写入文件失败: {}", e)))
+            }
+            .await;
+
+            // 写入中途失败时删除不完整的文件
+            if let Err(e) = written {
+                let _ = fs::remove_file(&full_path).await;
+                return Err(e);
+            }
+
+            Ok(full_path.to_string_lossy().to_string())
+        })
+    }
+
     fn read_file<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Vec<u8>> {
         Box::pin(async move {
             let full_path = self.resolve_local_path(key)?;
@@ -291,35 +417,393 @@ impl StorageBackend for LocalStorage {
                 }
             })?;
 
+            // 本地文件没有内容摘要，用大小和修改时间生成 ETag，文件变化时随之变化
+            let last_modified = metadata.modified().ok();
+            let etag = last_modified
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|mtime| format!("{:x}-{:x}", metadata.len(), mtime.as_nanos()));
+
             Ok(StorageFileMetadata {
                 content_length: Some(metadata.len()),
                 content_type: None,
-                etag: None,
+                etag,
+                last_modified,
             })
         })
     }
 
+    fn list_files<'a>(&'a self, prefix: &'a str) -> StorageObjectStream<'a> {
+        let root = match self.resolve_local_path(prefix) {
+            Ok(root) => root,
+            Err(e) => return Box::pin(futures_util::stream::once(async move { Err(e) })),
+        };
+
+        // 状态：待遍历的目录和正在读取的目录
+        let state: (Vec<PathBuf>, Option<fs::ReadDir>) = (vec![root], None);
+        Box::pin(futures_util::stream::try_unfold(
+            state,
+            |(mut dirs, mut entries)| async move {
+                loop {
+                    let Some(current) = entries.as_mut() else {
+                        let Some(dir) = dirs.pop() else {
+                            return Ok(None);
+                        };
+                        match fs::read_dir(&dir).await {
+                            Ok(read_dir) => entries = Some(read_dir),
+                            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                            Err(e) => return Err(StorageError::Io(format!("读取目录失败: {}", e))),
+                        }
+                        continue;
+                    };
+
+                    let Some(entry) = current
+                        .next_entry()
+                        .await
+                        .map_err(|e| StorageError::Io(format!("读取目录失败: {}", e)))?
+                    else {
+                        entries = None;
+                        continue;
+                    };
+                    let metadata = entry
+                        .metadata()
+                        .await
+                        .map_err(|e| StorageError::Io(format!("读取文件元信息失败: {}", e)))?;
+                    if metadata.is_dir() {
+                        dirs.push(entry.path());
+                    } else if metadata.is_file() {
+                        let object = StorageObject {
+                            key: entry.path().to_string_lossy().to_string(),
+                            size: metadata.len(),
+                            last_modified: metadata.modified().ok(),
+                        };
+                        return Ok(Some((object, (dirs, entries))));
+                    }
+                }
+            },
+        ))
+    }
+
+    fn read_stream<'a>(
+        &'a self,
+        key: &'a str,
+        range: Option<(u64, u64)>,
+    ) -> StorageFuture<'a, StorageByteStream> {
+        Box::pin(async move {
+            let full_path = self.resolve_local_path(key)?;
+            let mut file = fs::File::open(&full_path).await.map_err(|e| {
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    StorageError::NotFound(format!("文件不存在: {}", full_path.to_string_lossy()))
+                } else {
+                    StorageError::Io(format!("打开文件失败: {}", e))
+                }
+            })?;
+
+            let (start, length) = match range {
+                Some((start, end)) => (start, end.saturating_sub(start) + 1),
+                None => {
+                    let metadata = file
+                        .metadata()
+                        .await
+                        .map_err(|e| StorageError::Io(format!("读取文件元信息失败: {}", e)))?;
+                    (0, metadata.len())
+                }
+            };
+
+            if start > 0 {
+                file.seek(SeekFrom::Start(start))
+                    .await
+                    .map_err(|e| StorageError::Io(format!("定位文件失败: {}", e)))?;
+            }
+
+            // 每次最多读取 STREAM_CHUNK_SIZE 字节，直到读满请求的长度
+            let stream = futures_util::stream::try_unfold(
+                (file, length),
+                |(mut file, remaining)| async move {
+                    if remaining == 0 {
+                        return Ok(None);
+                    }
+                    let mut buf = vec![0u8; remaining.min(Self::STREAM_CHUNK_SIZE) as usize];
+                    let read = file
+                        .read(&mut buf)
+                        .await
+                        .map_err(|e| StorageError::Io(format!("读取文件失败: {}", e)))?;
+                    if read == 0 {
+                        // 文件在读取过程中被截断
+                        return Ok(None);
+                    }
+                    buf.truncate(read);
+                    Ok(Some((Bytes::from(buf), (file, remaining - read as u64))))
+                },
+            );
+
+            Ok(Box::pin(stream) as StorageByteStream)
+        })
+    }
+
     fn backend_type(&self) -> StorageBackendType {
         StorageBackendType::Local
     }
 }
 
 pub fn create_storage_backend(config: &Config) -> Result<Arc<dyn StorageBackend>, StorageError> {
-    if config.storage_backend == "oss" {
-        let storage = OssStorage::from_config(config)?;
-        return Ok(Arc::new(storage));
+    let backend_type = StorageBackendType::from_storage_type(Some(&config.storage_backend));
+    create_storage_backend_of_type(config, backend_type)
+}
+
+/// 按指定类型创建存储后端，不受 STORAGE_BACKEND 影响
+/// 用于读取与当前配置不同的存储中的文件，以及在存储之间迁移文件
+pub fn create_storage_backend_of_type(
+    config: &Config,
+    backend_type: StorageBackendType,
+) -> Result<Arc<dyn StorageBackend>, StorageError> {
+    match backend_type {
+        StorageBackendType::Oss => Ok(Arc::new(OssStorage::from_config(config)?)),
+        StorageBackendType::S3 => Ok(Arc::new(S3Storage::from_config(config)?)),
+        StorageBackendType::Local => Ok(Arc::new(LocalStorage::new(
+            config.file_upload_path.clone(),
+            config.image_base_url.clone(),
+        ))),
     }
+}
 
-    Ok(Arc::new(LocalStorage::new(
-        config.file_upload_path.clone(),
-        config.image_base_url.clone(),
-    )))
+/// 存储后端注册表
+///
+/// 启动时按配置创建所有可用的存储后端。新文件写入当前配置（STORAGE_BACKEND）的后端，
+/// 读取和删除已有文件时按记录中的 storage_type 选择后端
+#[derive(Clone)]
+pub struct StorageRegistry {
+    active: Arc<dyn StorageBackend>,
+    backends: HashMap<StorageBackendType, Arc<dyn StorageBackend>>,
 }
 
-/// 创建一个本地存储实例，用于在OSS模式下读取本地文件
-pub fn create_local_storage(config: &Config) -> Result<Arc<dyn StorageBackend>, StorageError> {
-    Ok(Arc::new(LocalStorage::new(
-        config.file_upload_path.clone(),
-        config.image_base_url.clone(),
-    )))
+impl StorageRegistry {
+    /// 只包含一个后端的注册表
+    pub fn new(active: Arc<dyn StorageBackend>) -> Self {
+        let mut backends = HashMap::new();
+        backends.insert(active.backend_type(), active.clone());
+        Self { active, backends }
+    }
+
+    /// 按配置创建注册表：当前后端必须可用，其他后端配置不完整时不注册
+    pub fn from_config(config: &Config) -> Result<Self, StorageError> {
+        let mut registry = Self::new(create_storage_backend(config)?);
+
+        for backend_type in StorageBackendType::ALL {
+            if registry.backends.contains_key(&backend_type) {
+                continue;
+            }
+            match create_storage_backend_of_type(config, backend_type) {
+                Ok(storage) => {
+                    registry.backends.insert(backend_type, storage);
+                }
+                Err(e) => log::debug!(
+                    "[Storage] 未注册存储后端 | backend={}, reason={}",
+                    backend_type.as_str(),
+                    e
+                ),
+            }
+        }
+
+        Ok(registry)
+    }
+
+    /// 当前配置的存储后端，新文件写入这里
+    pub fn active(&self) -> &Arc<dyn StorageBackend> {
+        &self.active
+    }
+
+    /// 获取指定类型的存储后端，未配置时返回 None
+    pub fn get(&self, backend_type: StorageBackendType) -> Option<&Arc<dyn StorageBackend>> {
+        self.backends.get(&backend_type)
+    }
+
+    /// 按记录中的 storage_type 获取文件所在的存储后端
+    pub fn for_storage_type(
+        &self,
+        storage_type: Option<&str>,
+    ) -> Result<&Arc<dyn StorageBackend>, StorageError> {
+        let backend_type = StorageBackendType::from_storage_type(storage_type);
+        self.get(backend_type)
+            .ok_or_else(|| StorageError::Config(format!("{} 存储未配置", backend_type.as_str())))
+    }
+
+    /// 已注册的存储后端类型
+    pub fn backend_types(&self) -> Vec<StorageBackendType> {
+        StorageBackendType::ALL
+            .into_iter()
+            .filter(|backend_type| self.backends.contains_key(backend_type))
+            .collect()
+    }
+}
+
+/// 从文件路径推断存储 key（如 `resources/a.pdf`）：本地路径包含上传目录前缀，对象存储 key 可能带有 key 前缀
+pub fn storage_key_for(file_path: &str, prefix: &str) -> Option<String> {
+    let normalized = file_path.replace('\\', "/");
+    let start = normalized.rfind(prefix)?;
+    let key = &normalized[start..];
+    (key.len() > prefix.len()).then(|| key.to_string())
+}
+
+/// 把按 continuation token 分页的列举接口包装成条目流，读完一页再请求下一页
+pub(super) fn paginated_list<'a, F, Fut>(fetch_page: F) -> StorageObjectStream<'a>
+where
+    F: Fn(Option<String>) -> Fut + Send + 'a,
+    Fut: Future<Output = Result<(Vec<StorageObject>, Option<String>), StorageError>> + Send + 'a,
+{
+    // 状态：下一页的 token（None 表示已读完）
+    let pages = futures_util::stream::try_unfold(Some(None::<String>), move |next_page| {
+        let page = next_page.map(&fetch_page);
+        async move {
+            let Some(page) = page else {
+                return Ok(None);
+            };
+            let (objects, next_token) = page.await?;
+            Ok(Some((objects, next_token.map(Some))))
+        }
+    });
+    Box::pin(
+        pages
+            .map_ok(|objects| futures_util::stream::iter(objects.into_iter().map(Ok)))
+            .try_flatten(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local_storage() -> Arc<dyn StorageBackend> {
+        Arc::new(LocalStorage::new(
+            "./uploads".to_string(),
+            "http://localhost:8080".to_string(),
+        ))
+    }
+
+    #[test]
+    fn test_registry_routes_by_storage_type() {
+        let registry = StorageRegistry::new(local_storage());
+
+        assert_eq!(registry.active().backend_type(), StorageBackendType::Local);
+        assert_eq!(registry.backend_types(), vec![StorageBackendType::Local]);
+
+        // 空值和未知值都视为本地存储
+        for storage_type in [None, Some("local"), Some("unknown")] {
+            let storage = registry.for_storage_type(storage_type).unwrap();
+            assert_eq!(storage.backend_type(), StorageBackendType::Local);
+        }
+    }
+
+    #[test]
+    fn test_registry_rejects_unconfigured_backend() {
+        let registry = StorageRegistry::new(local_storage());
+
+        assert!(registry.get(StorageBackendType::S3).is_none());
+        assert!(matches!(
+            registry.for_storage_type(Some("oss")),
+            Err(StorageError::Config(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_local_list_files_returns_saved_paths() {
+        let base = std::env::temp_dir().join(format!("shareustc-list-{}", uuid::Uuid::new_v4()));
+        let storage = LocalStorage::new(
+            base.to_string_lossy().to_string(),
+            "http://localhost:8080".to_string(),
+        );
+
+        let saved = storage
+            .save_file("resources/a.md", b"hello".to_vec(), None)
+            .await
+            .unwrap();
+        storage
+            .save_file("images/b.png", vec![0u8; 3], None)
+            .await
+            .unwrap();
+
+        let objects: Vec<StorageObject> = storage
+            .list_files("resources/")
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(objects.len(), 1);
+        assert_eq!(objects[0].key, saved);
+        assert_eq!(objects[0].size, 5);
+        assert!(objects[0].last_modified.is_some());
+
+        // 目录不存在时返回空列表
+        assert!(storage
+            .list_files("chunks/")
+            .try_next()
+            .await
+            .unwrap()
+            .is_none());
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn test_storage_key_for_local_path() {
+        assert_eq!(
+            storage_key_for("./uploads/resources/abc.pdf", "resources/").as_deref(),
+            Some("resources/abc.pdf")
+        );
+        assert_eq!(
+            storage_key_for("C:\\data\\uploads\\images\\a.png", "images/").as_deref(),
+            Some("images/a.png")
+        );
+    }
+
+    #[test]
+    fn test_storage_key_for_object_key() {
+        assert_eq!(
+            storage_key_for("share/resources/abc_v2.md", "resources/").as_deref(),
+            Some("resources/abc_v2.md")
+        );
+        assert_eq!(
+            storage_key_for("images/a.png", "images/").as_deref(),
+            Some("images/a.png")
+        );
+    }
+
+    #[test]
+    fn test_storage_key_for_unknown_path() {
+        assert_eq!(storage_key_for("uploads/other/a.pdf", "resources/"), None);
+        assert_eq!(storage_key_for("uploads/resources/", "resources/"), None);
+    }
+
+    #[tokio::test]
+    async fn test_local_save_stream_writes_chunks_and_removes_partial_file() {
+        let storage = LocalStorage::new(
+            crate::db::test_support::temp_storage_dir(),
+            "http://localhost:8080".to_string(),
+        );
+
+        let chunks = ["流式", "写入", "测试"].map(|c| Ok(Bytes::from(c)));
+        let path = storage
+            .save_stream(
+                "resources/stream.txt",
+                Box::pin(futures_util::stream::iter(chunks)),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.read_file(&path).await.unwrap(),
+            "流式写入测试".as_bytes()
+        );
+
+        let failing = futures_util::stream::iter([
+            Ok(Bytes::from("partial")),
+            Err(StorageError::Backend("源读取失败".to_string())),
+        ]);
+        assert!(storage
+            .save_stream("resources/broken.txt", Box::pin(failing), None)
+            .await
+            .is_err());
+        assert!(matches!(
+            storage.read_file("resources/broken.txt").await,
+            Err(StorageError::NotFound(_))
+        ));
+    }
 }
@@ -0,0 +1,791 @@
+use futures_util::StreamExt;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::path::Path;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::models::resource::{
+    ResourceType, UploadResourceRequest, UploadResourceResponse, UploadedFile,
+};
+use crate::models::upload_session::{
+    InitUploadSessionRequest, UploadSession, UploadSessionResponse,
+};
+use crate::models::CurrentUser;
+
+use super::{
+    FileService, ModerationProvider, ResourceError, ResourceService, StorageBackend,
+    StorageBackendType, StorageQuotaService,
+};
+
+/// 分片上传服务（仅本地存储模式）
+///
+/// 大文件拆成若干分片逐个上传，分片先写入存储的 `chunks/` 目录，
+/// 全部到齐后合并、校验 SHA-256，再交给 `ResourceService::upload_resource`
+/// 走与普通上传完全相同的校验、审核和入库流程。
+/// OSS 模式下客户端应使用 `/oss/sts-token` 直传，不走这里。
+pub struct UploadSessionService;
+
+const SESSION_COLUMNS: &str = "id, user_id, file_name, file_size, file_hash, mime_type, \
+     chunk_size, total_chunks, received_chunks, status, resource_id, expires_at, created_at";
+
+impl UploadSessionService {
+    /// 默认分片大小 (5MB)
+    pub const DEFAULT_CHUNK_SIZE: i64 = 5 * 1024 * 1024;
+    /// 最小分片大小 (256KB)
+    pub const MIN_CHUNK_SIZE: i64 = 256 * 1024;
+    /// 最大分片大小 (20MB)
+    pub const MAX_CHUNK_SIZE: i64 = 20 * 1024 * 1024;
+    /// 每个用户同时进行中的会话上限
+    const MAX_ACTIVE_SESSIONS: i64 = 5;
+    /// 合并超过该时长仍处于 merging 状态，视为进程中途退出，恢复为可继续上传
+    const MERGE_TIMEOUT_SECS: i64 = 30 * 60;
+
+    /// 分片在存储中的 key
+    fn chunk_key(session_id: Uuid, index: i32) -> String {
+        format!("chunks/{}_{}.part", session_id, index)
+    }
+
+    /// 根据文件大小和期望分片大小计算实际分片大小和分片数
+    pub fn plan_chunks(file_size: i64, chunk_size: Option<i64>) -> (i64, i32) {
+        let chunk_size = chunk_size
+            .unwrap_or(Self::DEFAULT_CHUNK_SIZE)
+            .clamp(Self::MIN_CHUNK_SIZE, Self::MAX_CHUNK_SIZE);
+        let total_chunks = (file_size + chunk_size - 1) / chunk_size;
+        (chunk_size, total_chunks as i32)
+    }
+
+    /// 第 index 个分片应有的字节数（最后一片可能不足一个分片大小）
+    pub fn expected_chunk_len(file_size: i64, chunk_size: i64, index: i32) -> i64 {
+        let start = chunk_size * index as i64;
+        (file_size - start).clamp(0, chunk_size)
+    }
+
+    /// 创建上传会话
+    pub async fn init_session(
+        pool: &PgPool,
+        user: &CurrentUser,
+        storage: &Arc<dyn StorageBackend>,
+        request: &InitUploadSessionRequest,
+    ) -> Result<UploadSessionResponse, ResourceError> {
+        if storage.backend_type() != StorageBackendType::Local {
+            return Err(ResourceError::ValidationError(
+                "当前为 OSS 存储模式，请使用直传凭证上传".to_string(),
+            ));
+        }
+
+        request
+            .validate(FileService::MAX_FILE_SIZE as i64)
+            .map_err(ResourceError::ValidationError)?;
+
+        // 提前检查扩展名，避免传完才发现类型不支持
+        let file_name = request.file_name.trim();
+        let extension = Path::new(file_name)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default();
+        if ResourceType::from_extension(extension) == ResourceType::Other {
+            return Err(ResourceError::ValidationError(format!(
+                "不支持的文件类型。支持的类型: {}",
+                ResourceType::supported_extensions().join(", ")
+            )));
+        }
+
+        // 已有相同内容的资源时直接拦截，省去整份文件的上传
+        let file_hash = request.file_hash.to_lowercase();
+        if let Some(existing) = ResourceService::find_approved_by_hash(pool, &file_hash).await? {
+            log::info!(
+                "[Upload] 分片上传内容与已有资源重复 | user_id={}, existing_id={}",
+                user.id,
+                existing.id
+            );
+            return Err(ResourceError::Duplicate(existing));
+        }
+
+        // 提前检查存储配额，完成上传时还会再检查一次
+        StorageQuotaService::ensure_quota(pool, user.id, request.file_size).await?;
+
+        Self::cleanup_expired_sessions(pool, storage).await?;
+
+        let active: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM upload_sessions WHERE user_id = $1 AND status = 'uploading'",
+        )
+        .bind(user.id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        if active >= Self::MAX_ACTIVE_SESSIONS {
+            return Err(ResourceError::Conflict(format!(
+                "未完成的上传会话过多（最多 {} 个），请先完成或取消已有上传",
+                Self::MAX_ACTIVE_SESSIONS
+            )));
+        }
+
+        let (chunk_size, total_chunks) = Self::plan_chunks(request.file_size, request.chunk_size);
+
+        let session = sqlx::query_as::<_, UploadSession>(&format!(
+            r#"
+            INSERT INTO upload_sessions (
+                user_id, file_name, file_size, file_hash, mime_type,
+                chunk_size, total_chunks, expires_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, NOW() + INTERVAL '24 hours')
+            RETURNING {}
+            "#,
+            SESSION_COLUMNS
+        ))
+        .bind(user.id)
+        .bind(file_name)
+        .bind(request.file_size)
+        .bind(&file_hash)
+        .bind(&request.mime_type)
+        .bind(chunk_size as i32)
+        .bind(total_chunks)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        log::info!(
+            "[Upload] 创建分片上传会话 | session_id={}, user_id={}, file_size={}, total_chunks={}",
+            session.id,
+            user.id,
+            session.file_size,
+            session.total_chunks
+        );
+
+        Ok(session.into())
+    }
+
+    /// 获取会话并校验归属
+    async fn get_own_session(
+        pool: &PgPool,
+        user: &CurrentUser,
+        session_id: Uuid,
+    ) -> Result<UploadSession, ResourceError> {
+        let session = sqlx::query_as::<_, UploadSession>(&format!(
+            "SELECT {} FROM upload_sessions WHERE id = $1",
+            SESSION_COLUMNS
+        ))
+        .bind(session_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| ResourceError::NotFound("上传会话不存在".to_string()))?;
+
+        if session.user_id != user.id {
+            return Err(ResourceError::Unauthorized(
+                "无权访问该上传会话".to_string(),
+            ));
+        }
+
+        Ok(session)
+    }
+
+    /// 获取进行中的会话（已完成或已过期的会话不能继续上传）
+    async fn get_uploading_session(
+        pool: &PgPool,
+        user: &CurrentUser,
+        session_id: Uuid,
+    ) -> Result<UploadSession, ResourceError> {
+        let mut session = Self::get_own_session(pool, user, session_id).await?;
+
+        if session.status == "merging"
+            && Self::reclaim_stale_merges(pool, Some(session_id)).await? > 0
+        {
+            session.status = "uploading".to_string();
+        }
+
+        if session.status != "uploading" {
+            return Err(ResourceError::Conflict("上传会话已结束".to_string()));
+        }
+
+        // 过期时间由数据库 NOW() 写入，比较也交给数据库，避免时区不一致
+        let expired: bool =
+            sqlx::query_scalar("SELECT expires_at < NOW() FROM upload_sessions WHERE id = $1")
+                .bind(session_id)
+                .fetch_one(pool)
+                .await
+                .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+        if expired {
+            return Err(ResourceError::Conflict(
+                "上传会话已过期，请重新发起上传".to_string(),
+            ));
+        }
+
+        Ok(session)
+    }
+
+    /// 查询会话状态（含已收到和缺失的分片，供断点续传使用）
+    pub async fn get_session(
+        pool: &PgPool,
+        user: &CurrentUser,
+        session_id: Uuid,
+    ) -> Result<UploadSessionResponse, ResourceError> {
+        Ok(Self::get_own_session(pool, user, session_id).await?.into())
+    }
+
+    /// 上传单个分片（重复上传同一分片会覆盖旧数据）
+    pub async fn put_chunk(
+        pool: &PgPool,
+        user: &CurrentUser,
+        storage: &Arc<dyn StorageBackend>,
+        session_id: Uuid,
+        index: i32,
+        data: Vec<u8>,
+    ) -> Result<UploadSessionResponse, ResourceError> {
+        let session = Self::get_uploading_session(pool, user, session_id).await?;
+
+        if index < 0 || index >= session.total_chunks {
+            return Err(ResourceError::ValidationError(format!(
+                "分片序号超出范围（0 - {}）",
+                session.total_chunks - 1
+            )));
+        }
+
+        let expected =
+            Self::expected_chunk_len(session.file_size, session.chunk_size as i64, index);
+        if data.len() as i64 != expected {
+            return Err(ResourceError::ValidationError(format!(
+                "分片 {} 大小应为 {} 字节，实际 {} 字节",
+                index,
+                expected,
+                data.len()
+            )));
+        }
+
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        // 先在状态检查下记录已收到的分片（去重并保持有序）并顺延过期时间，再写入分片文件；
+        // 写入期间持有会话行锁，提交合并时会等待写入完成，不会读到写了一半的分片
+        let session = sqlx::query_as::<_, UploadSession>(&format!(
+            r#"
+            UPDATE upload_sessions
+            SET received_chunks = ARRAY(
+                    SELECT DISTINCT unnest(array_append(received_chunks, $2)) ORDER BY 1
+                ),
+                expires_at = NOW() + INTERVAL '24 hours'
+            WHERE id = $1 AND status = 'uploading'
+            RETURNING {}
+            "#,
+            SESSION_COLUMNS
+        ))
+        .bind(session_id)
+        .bind(index)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| ResourceError::Conflict("上传会话已结束".to_string()))?;
+
+        // 写入失败时事务回滚，分片不会被记为已收到
+        storage
+            .write_file(&Self::chunk_key(session_id, index), data, None)
+            .await?;
+        tx.commit()
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        log::debug!(
+            "[Upload] 分片已接收 | session_id={}, index={}, received={}/{}",
+            session_id,
+            index,
+            session.received_chunks.len(),
+            session.total_chunks
+        );
+
+        Ok(session.into())
+    }
+
+    /// 合并分片并创建资源
+    ///
+    /// 合并结果的大小和 SHA-256 必须与创建会话时声明的一致；
+    /// 元数据校验失败时会话保留，客户端修正后可再次提交；
+    /// 文件校验失败时清空已收分片，需要重新上传。
+    pub async fn complete_session(
+        pool: &PgPool,
+        user: &CurrentUser,
+        storage: &Arc<dyn StorageBackend>,
+        moderation: &Arc<dyn ModerationProvider>,
+        session_id: Uuid,
+        request: UploadResourceRequest,
+    ) -> Result<UploadResourceResponse, ResourceError> {
+        let session = Self::get_uploading_session(pool, user, session_id).await?;
+
+        let missing = session.total_chunks as usize - session.received_chunks.len();
+        if missing > 0 {
+            return Err(ResourceError::ValidationError(format!(
+                "还有 {} 个分片未上传",
+                missing
+            )));
+        }
+
+        // 先把状态切到 merging，防止并发提交重复创建资源
+        let locked = sqlx::query(
+            "UPDATE upload_sessions SET status = 'merging' WHERE id = $1 AND status = 'uploading'",
+        )
+        .bind(session_id)
+        .execute(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+        if locked.rows_affected() == 0 {
+            return Err(ResourceError::Conflict(
+                "上传会话正在合并或已结束".to_string(),
+            ));
+        }
+
+        let result =
+            Self::merge_and_upload(pool, user, storage, moderation, &session, request).await;
+
+        match &result {
+            Ok(response) => {
+                sqlx::query(
+                    "UPDATE upload_sessions SET status = 'completed', resource_id = $2 WHERE id = $1",
+                )
+                .bind(session_id)
+                .bind(response.id)
+                .execute(pool)
+                .await
+                .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+                Self::delete_chunks(storage, &session).await;
+
+                log::info!(
+                    "[Upload] 分片上传完成 | session_id={}, resource_id={}, user_id={}",
+                    session_id,
+                    response.id,
+                    user.id
+                );
+            }
+            Err(ResourceError::Duplicate(_)) => {
+                // 合并期间已有相同内容通过审核，会话没有继续的意义
+                Self::delete_chunks(storage, &session).await;
+                Self::delete_session_row(pool, session_id).await?;
+            }
+            Err(e) => {
+                log::warn!(
+                    "[Upload] 分片合并失败 | session_id={}, user_id={}, error={}",
+                    session_id,
+                    user.id,
+                    e
+                );
+                Self::release_session(pool, session_id).await?;
+            }
+        }
+
+        result
+    }
+
+    /// 读取全部分片，校验后调用普通上传流程
+    ///
+    /// 普通上传流程（类型校验、提取正文）需要完整内容，分片按顺序读入内存，
+    /// 读取时同时计算 SHA-256
+    async fn merge_and_upload(
+        pool: &PgPool,
+        user: &CurrentUser,
+        storage: &Arc<dyn StorageBackend>,
+        moderation: &Arc<dyn ModerationProvider>,
+        session: &UploadSession,
+        request: UploadResourceRequest,
+    ) -> Result<UploadResourceResponse, ResourceError> {
+        let mut data = Vec::with_capacity(session.file_size as usize);
+        let mut hasher = Sha256::new();
+
+        for index in 0..session.total_chunks {
+            let mut stream = storage
+                .read_stream(&Self::chunk_key(session.id, index), None)
+                .await?;
+            while let Some(bytes) = stream.next().await {
+                let bytes = bytes?;
+                hasher.update(&bytes);
+                data.extend_from_slice(&bytes);
+            }
+        }
+
+        if data.len() as i64 != session.file_size
+            || format!("{:x}", hasher.finalize()) != session.file_hash
+        {
+            log::warn!(
+                "[Upload] 分片合并后校验失败 | session_id={}, expected_hash={}",
+                session.id,
+                session.file_hash
+            );
+            Self::delete_chunks(storage, session).await;
+            sqlx::query("UPDATE upload_sessions SET received_chunks = '{}' WHERE id = $1")
+                .bind(session.id)
+                .execute(pool)
+                .await
+                .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+            return Err(ResourceError::ValidationError(
+                "文件校验失败（SHA-256 不匹配），已清空分片，请重新上传".to_string(),
+            ));
+        }
+
+        ResourceService::upload_resource(
+            pool,
+            user,
+            storage,
+            moderation,
+            request,
+            UploadedFile {
+                name: &session.file_name,
+                data,
+                mime_type: session.mime_type.as_deref(),
+            },
+        )
+        .await
+    }
+
+    /// 取消上传会话并删除已上传的分片
+    pub async fn abort_session(
+        pool: &PgPool,
+        user: &CurrentUser,
+        storage: &Arc<dyn StorageBackend>,
+        session_id: Uuid,
+    ) -> Result<(), ResourceError> {
+        let session = Self::get_own_session(pool, user, session_id).await?;
+
+        if session.status == "merging" {
+            return Err(ResourceError::Conflict(
+                "上传会话正在合并，无法取消".to_string(),
+            ));
+        }
+
+        Self::delete_chunks(storage, &session).await;
+        Self::delete_session_row(pool, session_id).await?;
+
+        log::info!(
+            "[Upload] 取消分片上传 | session_id={}, user_id={}",
+            session_id,
+            user.id
+        );
+
+        Ok(())
+    }
+
+    /// 清理过期会话及其分片
+    pub async fn cleanup_expired_sessions(
+        pool: &PgPool,
+        storage: &Arc<dyn StorageBackend>,
+    ) -> Result<usize, ResourceError> {
+        Self::reclaim_stale_merges(pool, None).await?;
+
+        let sessions = sqlx::query_as::<_, UploadSession>(&format!(
+            "SELECT {} FROM upload_sessions WHERE expires_at < NOW() AND status <> 'merging'",
+            SESSION_COLUMNS
+        ))
+        .fetch_all(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        for session in &sessions {
+            Self::delete_chunks(storage, session).await;
+            Self::delete_session_row(pool, session.id).await?;
+        }
+
+        if !sessions.is_empty() {
+            log::info!("[Upload] 清理过期上传会话 | count={}", sessions.len());
+        }
+
+        Ok(sessions.len())
+    }
+
+    /// 把合并超时的会话恢复为 uploading（合并中进程退出时状态会停留在 merging）
+    /// `session_id` 为 None 时处理所有会话；返回恢复的会话数
+    async fn reclaim_stale_merges(
+        pool: &PgPool,
+        session_id: Option<Uuid>,
+    ) -> Result<u64, ResourceError> {
+        // 开始合并时会更新 status，updated_at 由触发器写入，即合并开始时间
+        let result = sqlx::query(
+            r#"
+            UPDATE upload_sessions SET status = 'uploading'
+            WHERE status = 'merging'
+              AND updated_at < NOW() - make_interval(secs => $1)
+              AND ($2::uuid IS NULL OR id = $2)
+            "#,
+        )
+        .bind(Self::MERGE_TIMEOUT_SECS as f64)
+        .bind(session_id)
+        .execute(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        if result.rows_affected() > 0 {
+            log::warn!(
+                "[Upload] 恢复合并超时的上传会话 | count={}",
+                result.rows_affected()
+            );
+        }
+
+        Ok(result.rows_affected())
+    }
+
+    /// 合并失败后恢复为可继续上传状态
+    async fn release_session(pool: &PgPool, session_id: Uuid) -> Result<(), ResourceError> {
+        sqlx::query("UPDATE upload_sessions SET status = 'uploading' WHERE id = $1")
+            .bind(session_id)
+            .execute(pool)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn delete_session_row(pool: &PgPool, session_id: Uuid) -> Result<(), ResourceError> {
+        sqlx::query("DELETE FROM upload_sessions WHERE id = $1")
+            .bind(session_id)
+            .execute(pool)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// 删除会话的分片文件，失败只记录日志
+    async fn delete_chunks(storage: &Arc<dyn StorageBackend>, session: &UploadSession) {
+        for index in &session.received_chunks {
+            if let Err(e) = storage
+                .delete_file(&Self::chunk_key(session.id, *index))
+                .await
+            {
+                log::warn!(
+                    "[Upload] 删除分片失败 | session_id={}, index={}, error={}",
+                    session.id,
+                    index,
+                    e
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{
+        cleanup_test_user, create_test_user, slow_write_storage, temp_storage_dir, test_pool,
+    };
+    use crate::models::resource::{ResourceCategory, ResourceType};
+    use crate::models::UserRole;
+    use crate::services::{ChainedModerationProvider, LocalStorage};
+
+    fn local_storage(base_path: &str) -> Arc<dyn StorageBackend> {
+        Arc::new(LocalStorage::new(
+            base_path.to_string(),
+            "http://localhost/uploads".to_string(),
+        ))
+    }
+
+    fn upload_request() -> UploadResourceRequest {
+        UploadResourceRequest {
+            title: "分片上传测试".to_string(),
+            course_name: None,
+            resource_type: ResourceType::Txt,
+            category: ResourceCategory::Other,
+            tags: None,
+            description: None,
+            teacher_sns: None,
+            course_sns: None,
+            related_resource_ids: None,
+        }
+    }
+
+    /// 直接插入一条会话记录，`merge_age_secs` 为开始合并至今的秒数
+    async fn insert_merging_session(pool: &PgPool, user_id: Uuid, merge_age_secs: i64) -> Uuid {
+        sqlx::query_scalar(
+            r#"
+            INSERT INTO upload_sessions (user_id, file_name, file_size, file_hash, chunk_size, total_chunks, status, updated_at)
+            VALUES ($1, 'notes.txt', 4, $2, 4, 1, 'merging', NOW() - make_interval(secs => $3))
+            RETURNING id
+            "#,
+        )
+        .bind(user_id)
+        .bind("0".repeat(64))
+        .bind(merge_age_secs as f64)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_complete_session_merges_chunks() {
+        let pool = test_pool().await;
+        let user = create_test_user(&pool, UserRole::User).await;
+        let base_path = temp_storage_dir();
+        let storage = local_storage(&base_path);
+        let moderation: Arc<dyn ModerationProvider> =
+            Arc::new(ChainedModerationProvider::new(vec![]));
+
+        let chunk_size = UploadSessionService::MIN_CHUNK_SIZE;
+        let mut content = format!("{}\n", Uuid::new_v4()).into_bytes();
+        content.resize(chunk_size as usize * 2 + 100, b'a');
+
+        let session = UploadSessionService::init_session(
+            &pool,
+            &user,
+            &storage,
+            &InitUploadSessionRequest {
+                file_name: "notes.txt".to_string(),
+                file_size: content.len() as i64,
+                file_hash: FileService::calculate_hash(&content),
+                mime_type: Some("text/plain".to_string()),
+                chunk_size: Some(chunk_size),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(session.total_chunks, 3);
+
+        for (index, chunk) in content.chunks(chunk_size as usize).enumerate() {
+            UploadSessionService::put_chunk(
+                &pool,
+                &user,
+                &storage,
+                session.id,
+                index as i32,
+                chunk.to_vec(),
+            )
+            .await
+            .unwrap();
+        }
+
+        let response = UploadSessionService::complete_session(
+            &pool,
+            &user,
+            &storage,
+            &moderation,
+            session.id,
+            upload_request(),
+        )
+        .await
+        .unwrap();
+
+        let (file_path, file_size): (String, i64) =
+            sqlx::query_as("SELECT file_path, file_size FROM resources WHERE id = $1")
+                .bind(response.id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(file_size, content.len() as i64);
+        assert_eq!(storage.read_file(&file_path).await.unwrap(), content);
+
+        cleanup_test_user(&pool, user.id).await;
+        let _ = std::fs::remove_dir_all(base_path);
+    }
+
+    #[tokio::test]
+    async fn test_complete_session_waits_for_chunk_write() {
+        let pool = test_pool().await;
+        let user = create_test_user(&pool, UserRole::User).await;
+        let base_path = temp_storage_dir();
+        let storage = slow_write_storage(&base_path, std::time::Duration::from_millis(500));
+        let moderation: Arc<dyn ModerationProvider> =
+            Arc::new(ChainedModerationProvider::new(vec![]));
+
+        let content = format!("{}\n", Uuid::new_v4()).into_bytes();
+        let session = UploadSessionService::init_session(
+            &pool,
+            &user,
+            &storage,
+            &InitUploadSessionRequest {
+                file_name: "notes.txt".to_string(),
+                file_size: content.len() as i64,
+                file_hash: FileService::calculate_hash(&content),
+                mime_type: Some("text/plain".to_string()),
+                chunk_size: None,
+            },
+        )
+        .await
+        .unwrap();
+        UploadSessionService::put_chunk(&pool, &user, &storage, session.id, 0, content.clone())
+            .await
+            .unwrap();
+
+        // 重新上传分片的过程中提交合并：合并要等分片写完，不会读到写了一半的分片
+        let rewrite = tokio::spawn({
+            let (pool, user, storage, content) =
+                (pool.clone(), user.clone(), storage.clone(), content.clone());
+            async move {
+                UploadSessionService::put_chunk(&pool, &user, &storage, session.id, 0, content)
+                    .await
+            }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert!(!rewrite.is_finished());
+
+        UploadSessionService::complete_session(
+            &pool,
+            &user,
+            &storage,
+            &moderation,
+            session.id,
+            upload_request(),
+        )
+        .await
+        .unwrap();
+        assert!(rewrite.await.unwrap().is_ok());
+
+        cleanup_test_user(&pool, user.id).await;
+        let _ = std::fs::remove_dir_all(base_path);
+    }
+
+    #[tokio::test]
+    async fn test_stale_merging_session_is_reclaimed() {
+        let pool = test_pool().await;
+        let user = create_test_user(&pool, UserRole::User).await;
+        let base_path = temp_storage_dir();
+        let storage = local_storage(&base_path);
+
+        // 刚开始合并的会话不能继续上传
+        let fresh = insert_merging_session(&pool, user.id, 0).await;
+        let result =
+            UploadSessionService::put_chunk(&pool, &user, &storage, fresh, 0, b"data".to_vec())
+                .await;
+        assert!(matches!(result, Err(ResourceError::Conflict(_))));
+
+        // 合并超时的会话恢复为 uploading，可以重新上传分片
+        let stale = insert_merging_session(
+            &pool,
+            user.id,
+            UploadSessionService::MERGE_TIMEOUT_SECS + 60,
+        )
+        .await;
+        let response =
+            UploadSessionService::put_chunk(&pool, &user, &storage, stale, 0, b"data".to_vec())
+                .await
+                .unwrap();
+        assert_eq!(response.status, "uploading");
+
+        cleanup_test_user(&pool, user.id).await;
+        let _ = std::fs::remove_dir_all(base_path);
+    }
+
+    #[test]
+    fn test_plan_chunks() {
+        let mb = 1024 * 1024;
+        assert_eq!(
+            UploadSessionService::plan_chunks(12 * mb, None),
+            (5 * mb, 3)
+        );
+        assert_eq!(
+            UploadSessionService::plan_chunks(10 * mb, Some(5 * mb)),
+            (5 * mb, 2)
+        );
+        // 过小/过大的分片会被限制在允许范围内
+        assert_eq!(
+            UploadSessionService::plan_chunks(mb, Some(1)),
+            (UploadSessionService::MIN_CHUNK_SIZE, 4)
+        );
+        assert_eq!(
+            UploadSessionService::plan_chunks(100 * mb, Some(1024 * mb)),
+            (UploadSessionService::MAX_CHUNK_SIZE, 5)
+        );
+    }
+
+    #[test]
+    fn test_expected_chunk_len() {
+        assert_eq!(UploadSessionService::expected_chunk_len(10, 4, 0), 4);
+        assert_eq!(UploadSessionService::expected_chunk_len(10, 4, 2), 2);
+        assert_eq!(UploadSessionService::expected_chunk_len(10, 4, 3), 0);
+    }
+}
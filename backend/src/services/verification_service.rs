@@ -0,0 +1,795 @@
+use sqlx::PgPool;
+use std::sync::Arc;
+use url::Url;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::models::{
+    MyVerificationResponse, ReviewVerificationRequest, VerificationListQuery,
+    VerificationListResponse, VerificationRequest, VerificationResponse, VerificationStatus,
+    VERIFICATION_METHOD_ADMIN, VERIFICATION_METHOD_CAMPUS_EMAIL, VERIFICATION_METHOD_SSO,
+};
+use crate::services::{MailMessage, Mailer, NotificationService, ResourceError};
+use crate::utils::{
+    generate_one_time_token, hash_one_time_token, is_unique_violation, one_time_token_link,
+};
+
+/// 邮箱确认与实名认证服务
+pub struct VerificationService;
+
+/// 邮箱确认结果
+#[derive(Debug)]
+pub struct EmailConfirmOutcome {
+    pub user_id: Uuid,
+    pub email: String,
+    /// 确认的是校园邮箱，且待审核的实名认证申请因此自动通过
+    pub auto_approved: bool,
+}
+
+/// 实名认证审核结果
+#[derive(Debug)]
+pub struct VerificationReviewOutcome {
+    pub request_id: Uuid,
+    pub user_id: Uuid,
+    pub status: VerificationStatus,
+}
+
+/// 判断邮箱是否属于配置的校园域名（同时匹配子域名）
+pub(crate) fn is_campus_email(email: &str, campus_domains: &[String]) -> bool {
+    let Some((_, domain)) = email.trim().rsplit_once('@') else {
+        return false;
+    };
+    let domain = domain.to_lowercase();
+
+    campus_domains
+        .iter()
+        .any(|campus| domain == *campus || domain.ends_with(&format!(".{}", campus)))
+}
+
+const STUDENT_ID_TAKEN: &str = "该学号已被其他账号实名认证";
+
+const VERIFICATION_SELECT: &str = r#"
+    SELECT v.id, v.user_id, u.username, u.email,
+           u.email_verified_at IS NOT NULL AS email_verified,
+           v.real_info, v.status, v.method, v.reviewer_id, v.review_comment,
+           v.reviewed_at, v.created_at
+    FROM verification_requests v
+    JOIN users u ON u.id = v.user_id
+"#;
+
+impl VerificationService {
+    /// 发送邮箱确认邮件
+    ///
+    /// 同一邮箱只保留最新的一条确认链接，一分钟内不重复发送
+    pub async fn send_email_confirmation(
+        pool: &PgPool,
+        mailer: Arc<dyn Mailer>,
+        config: &Config,
+        user_id: Uuid,
+    ) -> Result<(), ResourceError> {
+        let verify_page = Url::parse(&config.email_verify_url).map_err(|e| {
+            log::error!("[Verification] EMAIL_VERIFY_URL 配置错误 | error={}", e);
+            ResourceError::DatabaseError(format!("邮箱确认页面地址配置错误: {}", e))
+        })?;
+
+        let (username, email, email_verified): (String, Option<String>, bool) = sqlx::query_as(
+            r#"
+            SELECT username, email, email_verified_at IS NOT NULL
+            FROM users
+            WHERE id = $1 AND is_active = true
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| ResourceError::NotFound("用户不存在".to_string()))?;
+
+        let email = email
+            .map(|email| email.trim().to_string())
+            .filter(|email| !email.is_empty())
+            .ok_or_else(|| {
+                ResourceError::ValidationError("请先在个人资料中填写邮箱".to_string())
+            })?;
+        if email_verified {
+            return Err(ResourceError::Conflict("邮箱已确认".to_string()));
+        }
+
+        let recently_sent: bool = sqlx::query_scalar(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM email_verification_tokens
+                WHERE user_id = $1 AND LOWER(email) = LOWER($2) AND used_at IS NULL
+                  AND created_at > CURRENT_TIMESTAMP - INTERVAL '1 minute'
+            )
+            "#,
+        )
+        .bind(user_id)
+        .bind(&email)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+        if recently_sent {
+            return Err(ResourceError::ValidationError(
+                "确认邮件发送过于频繁，请稍后再试".to_string(),
+            ));
+        }
+
+        sqlx::query(
+            "UPDATE email_verification_tokens SET used_at = CURRENT_TIMESTAMP WHERE user_id = $1 AND used_at IS NULL",
+        )
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        let token = generate_one_time_token();
+        sqlx::query(
+            r#"
+            INSERT INTO email_verification_tokens (user_id, email, token_hash, expires_at)
+            VALUES ($1, $2, $3, CURRENT_TIMESTAMP + make_interval(mins => $4))
+            "#,
+        )
+        .bind(user_id)
+        .bind(&email)
+        .bind(hash_one_time_token(&token))
+        .bind(config.email_verify_token_ttl_minutes)
+        .execute(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        let campus_hint = if is_campus_email(&email, &config.campus_email_domains) {
+            "确认校园邮箱后，提交的实名认证申请将自动通过。\n\n"
+        } else {
+            ""
+        };
+        let message = MailMessage {
+            to: email,
+            subject: "ShareUSTC 邮箱确认".to_string(),
+            body: format!(
+                "{}，你好：\n\n请打开下面的链接确认你的 ShareUSTC 账号邮箱（{} 小时内有效）：\n\n{}\n\n{}如果这不是你本人的操作，请忽略本邮件。\n",
+                username,
+                (config.email_verify_token_ttl_minutes + 59) / 60,
+                one_time_token_link(&verify_page, &token),
+                campus_hint
+            ),
+        };
+
+        tokio::spawn(async move {
+            if let Err(e) = mailer.send(&message).await {
+                log::error!(
+                    "[Verification] 发送邮箱确认邮件失败 | user_id={}, mailer={}, error={}",
+                    user_id,
+                    mailer.name(),
+                    e
+                );
+            }
+        });
+
+        log::info!("[Verification] 已生成邮箱确认链接 | user_id={}", user_id);
+        Ok(())
+    }
+
+    /// 使用邮件中的令牌确认邮箱
+    ///
+    /// 令牌只在用户邮箱未被修改时有效；确认的是校园邮箱时，
+    /// 待审核的实名认证申请自动通过
+    pub async fn confirm_email(
+        pool: &PgPool,
+        config: &Config,
+        token: &str,
+    ) -> Result<EmailConfirmOutcome, ResourceError> {
+        if token.trim().is_empty() {
+            return Err(ResourceError::ValidationError("确认链接无效".to_string()));
+        }
+
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        let (token_id, user_id, email): (Uuid, Uuid, String) = sqlx::query_as(
+            r#"
+            SELECT t.id, t.user_id, t.email
+            FROM email_verification_tokens t
+            JOIN users u ON t.user_id = u.id
+            WHERE t.token_hash = $1
+              AND t.used_at IS NULL
+              AND t.expires_at > CURRENT_TIMESTAMP
+              AND u.is_active = true
+              AND LOWER(u.email) = LOWER(t.email)
+            FOR UPDATE OF t
+            "#,
+        )
+        .bind(hash_one_time_token(token))
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| ResourceError::ValidationError("确认链接无效或已过期".to_string()))?;
+
+        sqlx::query(
+            "UPDATE email_verification_tokens SET used_at = CURRENT_TIMESTAMP WHERE user_id = $1 AND (id = $2 OR used_at IS NULL)",
+        )
+        .bind(user_id)
+        .bind(token_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        sqlx::query(
+            "UPDATE users SET email_verified_at = CURRENT_TIMESTAMP, updated_at = NOW() WHERE id = $1",
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        let mut auto_approved = false;
+        if is_campus_email(&email, &config.campus_email_domains) {
+            let real_info: Option<serde_json::Value> = sqlx::query_scalar(
+                r#"
+                UPDATE verification_requests
+                SET status = 'approved', method = $1, reviewed_at = NOW()
+                WHERE user_id = $2 AND status = 'pending'
+                RETURNING real_info
+                "#,
+            )
+            .bind(VERIFICATION_METHOD_CAMPUS_EMAIL)
+            .bind(user_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+            if let Some(real_info) = real_info {
+                Self::mark_user_verified(&mut tx, user_id, &real_info).await?;
+                auto_approved = true;
+            }
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        log::info!(
+            "[Verification] 邮箱已确认 | user_id={}, auto_approved={}",
+            user_id,
+            auto_approved
+        );
+
+        Ok(EmailConfirmOutcome {
+            user_id,
+            email,
+            auto_approved,
+        })
+    }
+
+    /// 提交实名认证申请
+    ///
+    /// 已确认校园邮箱的用户立即通过，否则进入待审核状态等待管理员审核
+    /// （或之后确认校园邮箱时自动通过）
+    pub async fn submit_verification(
+        pool: &PgPool,
+        config: &Config,
+        user_id: Uuid,
+        request: &VerificationRequest,
+    ) -> Result<VerificationResponse, ResourceError> {
+        request.validate().map_err(ResourceError::ValidationError)?;
+
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        let (is_verified, email, email_verified): (bool, Option<String>, bool) = sqlx::query_as(
+            r#"
+            SELECT is_verified, email, email_verified_at IS NOT NULL
+            FROM users
+            WHERE id = $1 AND is_active = true
+            FOR UPDATE
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| ResourceError::NotFound("用户不存在".to_string()))?;
+
+        if is_verified {
+            return Err(ResourceError::ValidationError(
+                "用户已完成实名认证".to_string(),
+            ));
+        }
+
+        let pending: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM verification_requests WHERE user_id = $1 AND status = 'pending')",
+        )
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+        if pending {
+            return Err(ResourceError::Conflict(
+                "已有待审核的实名认证申请".to_string(),
+            ));
+        }
+
+        let student_id = request.student_id.as_deref().map(str::trim);
+        if let Some(student_id) = student_id {
+            if Self::is_student_id_taken(&mut tx, user_id, student_id).await? {
+                return Err(ResourceError::Conflict(STUDENT_ID_TAKEN.to_string()));
+            }
+        }
+
+        let real_info = serde_json::json!({
+            "real_name": request.real_name.as_deref().map(str::trim),
+            "student_id": student_id,
+            "major": request.major,
+            "grade": request.grade,
+        });
+
+        let auto_approved = email_verified
+            && email
+                .as_deref()
+                .is_some_and(|email| is_campus_email(email, &config.campus_email_domains));
+
+        let (status, method) = if auto_approved {
+            (
+                VerificationStatus::Approved,
+                Some(VERIFICATION_METHOD_CAMPUS_EMAIL),
+            )
+        } else {
+            (VerificationStatus::Pending, None)
+        };
+
+        let request_id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO verification_requests (user_id, real_info, status, method, reviewed_at)
+            VALUES ($1, $2, $3, $4, CASE WHEN $3 = 'approved' THEN NOW() END)
+            RETURNING id
+            "#,
+        )
+        .bind(user_id)
+        .bind(&real_info)
+        .bind(status.as_str())
+        .bind(method)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        if auto_approved {
+            Self::mark_user_verified(&mut tx, user_id, &real_info).await?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        log::info!(
+            "[Verification] 提交实名认证申请 | user_id={}, request_id={}, status={}",
+            user_id,
+            request_id,
+            status.as_str()
+        );
+
+        Self::get_request(pool, request_id).await
+    }
+
+    /// 获取当前用户的邮箱确认和实名认证状态
+    pub async fn get_my_verification(
+        pool: &PgPool,
+        config: &Config,
+        user_id: Uuid,
+    ) -> Result<MyVerificationResponse, ResourceError> {
+        let (email, email_verified, is_verified): (Option<String>, bool, bool) = sqlx::query_as(
+            r#"
+            SELECT email, email_verified_at IS NOT NULL, is_verified
+            FROM users
+            WHERE id = $1 AND is_active = true
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| ResourceError::NotFound("用户不存在".to_string()))?;
+
+        let sql = format!(
+            "{} WHERE v.user_id = $1 ORDER BY v.created_at DESC LIMIT 1",
+            VERIFICATION_SELECT
+        );
+        let latest_request = sqlx::query_as::<_, VerificationResponse>(&sql)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        Ok(MyVerificationResponse {
+            campus_email: email
+                .as_deref()
+                .is_some_and(|email| is_campus_email(email, &config.campus_email_domains)),
+            email,
+            email_verified,
+            is_verified,
+            latest_request,
+        })
+    }
+
+    /// 获取实名认证申请列表（管理员）
+    pub async fn list_requests(
+        pool: &PgPool,
+        query: &VerificationListQuery,
+    ) -> Result<VerificationListResponse, ResourceError> {
+        let status = match query.status.as_deref() {
+            Some(s) => Some(
+                VerificationStatus::parse(s)
+                    .ok_or_else(|| {
+                        ResourceError::ValidationError(
+                            "status 只能是 pending、approved 或 rejected".to_string(),
+                        )
+                    })?
+                    .as_str(),
+            ),
+            None => None,
+        };
+
+        let page = query.get_page();
+        let per_page = query.get_per_page();
+        let offset = (page - 1) * per_page;
+
+        let sql = format!(
+            "{} WHERE ($1::VARCHAR IS NULL OR v.status = $1) ORDER BY v.created_at DESC LIMIT $2 OFFSET $3",
+            VERIFICATION_SELECT
+        );
+        let requests = sqlx::query_as::<_, VerificationResponse>(&sql)
+            .bind(status)
+            .bind(per_page as i64)
+            .bind(offset as i64)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        let total: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM verification_requests v WHERE ($1::VARCHAR IS NULL OR v.status = $1)",
+        )
+        .bind(status)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        Ok(VerificationListResponse {
+            requests,
+            total,
+            page,
+            per_page,
+        })
+    }
+
+    /// 审核实名认证申请（管理员），审核结果以通知发送给申请人
+    pub async fn review_request(
+        pool: &PgPool,
+        reviewer_id: Uuid,
+        request_id: Uuid,
+        request: &ReviewVerificationRequest,
+    ) -> Result<VerificationReviewOutcome, ResourceError> {
+        let status = request.validate().map_err(ResourceError::ValidationError)?;
+        let comment = request
+            .comment
+            .as_deref()
+            .map(str::trim)
+            .filter(|c| !c.is_empty());
+
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        let (user_id, current_status, real_info): (Uuid, String, serde_json::Value) =
+            sqlx::query_as(
+                "SELECT user_id, status, real_info FROM verification_requests WHERE id = $1 FOR UPDATE",
+            )
+            .bind(request_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| ResourceError::NotFound("实名认证申请不存在".to_string()))?;
+
+        if current_status != VerificationStatus::Pending.as_str() {
+            return Err(ResourceError::Conflict("该申请已审核".to_string()));
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE verification_requests
+            SET status = $1, method = $2, reviewer_id = $3, review_comment = $4, reviewed_at = NOW()
+            WHERE id = $5
+            "#,
+        )
+        .bind(status.as_str())
+        .bind(VERIFICATION_METHOD_ADMIN)
+        .bind(reviewer_id)
+        .bind(comment)
+        .bind(request_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        if status == VerificationStatus::Approved {
+            Self::mark_user_verified(&mut tx, user_id, &real_info).await?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        log::info!(
+            "[Verification] 实名认证审核完成 | request_id={}, user_id={}, status={}",
+            request_id,
+            user_id,
+            status.as_str()
+        );
+
+        // 通知失败不影响审核结果
+        if let Err(e) = NotificationService::create_verification_result_notification(
+            pool,
+            user_id,
+            status == VerificationStatus::Approved,
+            comment,
+        )
+        .await
+        {
+            log::warn!(
+                "[Verification] 发送实名认证结果通知失败 | request_id={}, error={}",
+                request_id,
+                e
+            );
+        }
+
+        Ok(VerificationReviewOutcome {
+            request_id,
+            user_id,
+            status,
+        })
+    }
+
+    /// 统一身份认证提供了学号时自动完成实名认证，返回是否新完成了认证
+    ///
+    /// 已有待审核的申请时直接通过该申请（学号和姓名以身份提供方为准），
+    /// 否则新建一条已通过的申请；已实名的用户不做处理
+    pub async fn verify_by_sso(
+        pool: &PgPool,
+        user_id: Uuid,
+        real_name: Option<&str>,
+        student_id: &str,
+    ) -> Result<bool, ResourceError> {
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        let is_verified: bool =
+            sqlx::query_scalar("SELECT is_verified FROM users WHERE id = $1 FOR UPDATE")
+                .bind(user_id)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| ResourceError::DatabaseError(e.to_string()))?
+                .ok_or_else(|| ResourceError::NotFound("用户不存在".to_string()))?;
+        if is_verified {
+            return Ok(false);
+        }
+
+        let pending: Option<(Uuid, serde_json::Value)> = sqlx::query_as(
+            "SELECT id, real_info FROM verification_requests WHERE user_id = $1 AND status = 'pending' FOR UPDATE",
+        )
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        let mut real_info = match &pending {
+            Some((_, serde_json::Value::Object(info))) => info.clone(),
+            _ => serde_json::Map::new(),
+        };
+        real_info.insert("student_id".to_string(), student_id.into());
+        if let Some(real_name) = real_name {
+            real_info.insert("real_name".to_string(), real_name.into());
+        }
+        let real_info = serde_json::Value::Object(real_info);
+
+        let request_id: Uuid = match pending {
+            Some((request_id, _)) => {
+                sqlx::query(
+                    r#"
+                    UPDATE verification_requests
+                    SET status = 'approved', method = $1, real_info = $2, reviewed_at = NOW()
+                    WHERE id = $3
+                    "#,
+                )
+                .bind(VERIFICATION_METHOD_SSO)
+                .bind(&real_info)
+                .bind(request_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+                request_id
+            }
+            None => sqlx::query_scalar(
+                r#"
+                INSERT INTO verification_requests (user_id, real_info, status, method, reviewed_at)
+                VALUES ($1, $2, 'approved', $3, NOW())
+                RETURNING id
+                "#,
+            )
+            .bind(user_id)
+            .bind(&real_info)
+            .bind(VERIFICATION_METHOD_SSO)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?,
+        };
+
+        Self::mark_user_verified(&mut tx, user_id, &real_info).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        log::info!(
+            "[Verification] 统一身份认证自动通过实名认证 | user_id={}, request_id={}",
+            user_id,
+            request_id
+        );
+        Ok(true)
+    }
+
+    async fn get_request(
+        pool: &PgPool,
+        request_id: Uuid,
+    ) -> Result<VerificationResponse, ResourceError> {
+        let sql = format!("{} WHERE v.id = $1", VERIFICATION_SELECT);
+        sqlx::query_as::<_, VerificationResponse>(&sql)
+            .bind(request_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| ResourceError::NotFound("实名认证申请不存在".to_string()))
+    }
+
+    /// 学号是否已被其他实名用户使用（不区分大小写）
+    async fn is_student_id_taken(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: Uuid,
+        student_id: &str,
+    ) -> Result<bool, ResourceError> {
+        sqlx::query_scalar(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM users
+                WHERE is_verified = true AND id <> $1
+                  AND UPPER(real_info->>'student_id') = UPPER($2)
+            )
+            "#,
+        )
+        .bind(user_id)
+        .bind(student_id)
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(|e| ResourceError::DatabaseError(e.to_string()))
+    }
+
+    /// 将用户标记为实名用户（保持原有角色，只更新 is_verified）
+    ///
+    /// 学号已被其他实名用户使用时返回冲突（由唯一索引保证）
+    async fn mark_user_verified(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: Uuid,
+        real_info: &serde_json::Value,
+    ) -> Result<(), ResourceError> {
+        sqlx::query(
+            "UPDATE users SET is_verified = true, real_info = $1, updated_at = NOW() WHERE id = $2",
+        )
+        .bind(real_info)
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| {
+            if is_unique_violation(&e) {
+                ResourceError::Conflict(STUDENT_ID_TAKEN.to_string())
+            } else {
+                ResourceError::DatabaseError(e.to_string())
+            }
+        })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{cleanup_test_user, create_test_user, test_pool};
+    use crate::models::UserRole;
+
+    fn verification_request(student_id: &str) -> VerificationRequest {
+        VerificationRequest {
+            real_name: Some("张三".to_string()),
+            student_id: Some(student_id.to_string()),
+            major: None,
+            grade: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_student_id_verifies_one_account() {
+        let pool = test_pool().await;
+        let config = Config::from_env();
+        let first = create_test_user(&pool, UserRole::User).await;
+        let second = create_test_user(&pool, UserRole::User).await;
+        let third = create_test_user(&pool, UserRole::User).await;
+        let admin = create_test_user(&pool, UserRole::Admin).await;
+        let student_id = format!("PB{}", &Uuid::new_v4().simple().to_string()[..8]);
+
+        let approve = ReviewVerificationRequest {
+            status: "approved".to_string(),
+            comment: None,
+        };
+
+        // 两个账号都可以先提交申请，第一个通过后第二个不能再通过
+        let first_request = VerificationService::submit_verification(
+            &pool,
+            &config,
+            first.id,
+            &verification_request(&student_id),
+        )
+        .await
+        .unwrap();
+        let second_request = VerificationService::submit_verification(
+            &pool,
+            &config,
+            second.id,
+            &verification_request(&student_id.to_lowercase()),
+        )
+        .await
+        .unwrap();
+
+        VerificationService::review_request(&pool, admin.id, first_request.id, &approve)
+            .await
+            .unwrap();
+        assert!(matches!(
+            VerificationService::review_request(&pool, admin.id, second_request.id, &approve).await,
+            Err(ResourceError::Conflict(_))
+        ));
+
+        // 已被认证的学号不能再提交申请，也不能通过统一身份认证自动实名
+        assert!(matches!(
+            VerificationService::submit_verification(
+                &pool,
+                &config,
+                third.id,
+                &verification_request(&format!(" {} ", student_id)),
+            )
+            .await,
+            Err(ResourceError::Conflict(_))
+        ));
+        assert!(matches!(
+            VerificationService::verify_by_sso(&pool, third.id, None, &student_id).await,
+            Err(ResourceError::Conflict(_))
+        ));
+
+        for user in [first, second, third, admin] {
+            cleanup_test_user(&pool, user.id).await;
+        }
+    }
+
+    #[test]
+    fn test_is_campus_email() {
+        let domains = vec!["ustc.edu.cn".to_string(), "mail.ustc.edu.cn".to_string()];
+
+        assert!(is_campus_email("alice@mail.ustc.edu.cn", &domains));
+        assert!(is_campus_email("Bob@USTC.edu.cn ", &domains));
+        assert!(is_campus_email("carol@ms.ustc.edu.cn", &domains));
+        assert!(!is_campus_email("mallory@notustc.edu.cn", &domains));
+        assert!(!is_campus_email("mallory@ustc.edu.cn.evil.com", &domains));
+        assert!(!is_campus_email("alice@gmail.com", &domains));
+        assert!(!is_campus_email("not-an-email", &domains));
+        assert!(!is_campus_email("alice@mail.ustc.edu.cn", &[]));
+    }
+}
@@ -0,0 +1,148 @@
+use actix_web::{http::header::HeaderMap, web, HttpRequest};
+use std::net::{IpAddr, SocketAddr};
+
+use crate::db::AppState;
+
+/// 获取客户端 IP
+///
+/// 直连地址是受信任的反向代理（TRUSTED_PROXIES）时，从右向左查找 X-Forwarded-For 中
+/// 第一个不受信任的地址，否则取直连地址，防止客户端伪造请求头
+pub fn client_ip(req: &HttpRequest) -> Option<String> {
+    let trusted_proxies = req
+        .app_data::<web::Data<AppState>>()
+        .map(|state| state.trusted_proxies.as_slice())
+        .unwrap_or_default();
+    resolve_client_ip(req.peer_addr(), req.headers(), trusted_proxies)
+}
+
+fn resolve_client_ip(
+    peer_addr: Option<SocketAddr>,
+    headers: &HeaderMap,
+    trusted_proxies: &[IpAddr],
+) -> Option<String> {
+    let peer_ip = peer_addr?.ip();
+
+    // X-Forwarded-For 由每一跳代理在末尾追加，只有右侧由受信任代理写入的部分可信，
+    // 左侧的内容和 Forwarded 头都可能由客户端伪造，不予采用
+    let hops: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect();
+
+    let mut client_ip = peer_ip;
+    for hop in hops.into_iter().rev() {
+        if !trusted_proxies.contains(&client_ip) {
+            break;
+        }
+        // 无法解析的地址之后的内容都不可信，取最后一个受信任代理的地址
+        match parse_ip(hop) {
+            Some(ip) => client_ip = ip,
+            None => break,
+        }
+    }
+    Some(client_ip.to_string())
+}
+
+/// 解析转发的地址，可能带端口（如 1.2.3.4:5678、[::1]:5678）
+fn parse_ip(addr: &str) -> Option<IpAddr> {
+    addr.parse::<IpAddr>()
+        .ok()
+        .or_else(|| addr.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+        .or_else(|| {
+            addr.trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse::<IpAddr>()
+                .ok()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    fn resolve(peer: &str, headers: &[(&str, &str)], trusted: &[IpAddr]) -> Option<String> {
+        let mut req = TestRequest::default();
+        for (name, value) in headers {
+            req = req.append_header((*name, *value));
+        }
+        let req = req.to_http_request();
+        resolve_client_ip(peer.parse().ok(), req.headers(), trusted)
+    }
+
+    #[test]
+    fn test_resolve_client_ip() {
+        let proxies: Vec<IpAddr> = vec!["127.0.0.1".parse().unwrap(), "::1".parse().unwrap()];
+
+        // 不信任的直连地址：忽略转发头
+        assert_eq!(
+            resolve("10.0.0.8:4000", &[("x-forwarded-for", "1.2.3.4")], &proxies).as_deref(),
+            Some("10.0.0.8")
+        );
+        assert_eq!(
+            resolve("10.0.0.8:4000", &[("x-forwarded-for", "1.2.3.4")], &[]).as_deref(),
+            Some("10.0.0.8")
+        );
+
+        // 受信任的代理：从右向左取第一个不受信任的地址，左侧客户端自带的内容被忽略
+        assert_eq!(
+            resolve(
+                "127.0.0.1:4000",
+                &[("x-forwarded-for", "6.6.6.6, 1.2.3.4, 127.0.0.1")],
+                &proxies
+            )
+            .as_deref(),
+            Some("1.2.3.4")
+        );
+        assert_eq!(
+            resolve(
+                "[::1]:4000",
+                &[
+                    ("x-forwarded-for", "6.6.6.6"),
+                    ("x-forwarded-for", "[2001:db8::1]:5678")
+                ],
+                &proxies
+            )
+            .as_deref(),
+            Some("2001:db8::1")
+        );
+
+        // 代理没有转发地址或地址无法解析时取最后一个受信任代理的地址
+        assert_eq!(
+            resolve("127.0.0.1:4000", &[], &proxies).as_deref(),
+            Some("127.0.0.1")
+        );
+        assert_eq!(
+            resolve(
+                "127.0.0.1:4000",
+                &[("x-forwarded-for", "1.2.3.4, unknown")],
+                &proxies
+            )
+            .as_deref(),
+            Some("127.0.0.1")
+        );
+
+        assert_eq!(resolve("", &[], &proxies), None);
+    }
+
+    #[test]
+    fn test_resolve_client_ip_ignores_forwarded_header() {
+        let proxies: Vec<IpAddr> = vec!["127.0.0.1".parse().unwrap()];
+
+        assert_eq!(
+            resolve(
+                "127.0.0.1:4000",
+                &[("forwarded", "for=6.6.6.6"), ("x-forwarded-for", "1.2.3.4")],
+                &proxies
+            )
+            .as_deref(),
+            Some("1.2.3.4")
+        );
+        assert_eq!(
+            resolve("127.0.0.1:4000", &[("forwarded", "for=6.6.6.6")], &proxies).as_deref(),
+            Some("127.0.0.1")
+        );
+    }
+}
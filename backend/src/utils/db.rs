@@ -0,0 +1,15 @@
+// 数据库错误判断工具
+
+/// 是否为唯一约束冲突（并发插入相同记录时由唯一索引拦截）
+pub fn is_unique_violation(err: &sqlx::Error) -> bool {
+    err.as_database_error()
+        .map(|e| e.is_unique_violation())
+        .unwrap_or(false)
+}
+
+/// 是否为外键约束冲突（引用的记录不存在）
+pub fn is_foreign_key_violation(err: &sqlx::Error) -> bool {
+    err.as_database_error()
+        .map(|e| e.is_foreign_key_violation())
+        .unwrap_or(false)
+}
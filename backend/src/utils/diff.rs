@@ -0,0 +1,348 @@
+use serde::Serialize;
+
+/// 差异计算允许的最大编辑距离，超过后剩余部分按“整体删除 + 整体新增”处理，
+/// 避免两份差异很大的长文档占用过多内存
+const MAX_EDIT_DISTANCE: usize = 2000;
+
+/// 差异行类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffOp {
+    Equal,
+    Insert,
+    Delete,
+}
+
+/// 差异中的一行（行号从 1 开始，新增行没有旧行号，删除行没有新行号）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    pub op: DiffOp,
+    pub old_line: Option<usize>,
+    pub new_line: Option<usize>,
+    pub content: String,
+}
+
+/// 差异片段（与 unified diff 的 hunk 对应，包含前后若干行上下文）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffHunk {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+/// 两段文本的按行差异
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineDiff {
+    pub additions: usize,
+    pub deletions: usize,
+    pub hunks: Vec<DiffHunk>,
+}
+
+/// 按行比较两段文本（Myers 算法），每个差异片段保留 `context` 行上下文
+pub fn diff_lines(old: &str, new: &str, context: usize) -> LineDiff {
+    diff_lines_with_limit(old, new, context, MAX_EDIT_DISTANCE)
+}
+
+fn diff_lines_with_limit(old: &str, new: &str, context: usize, limit: usize) -> LineDiff {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+
+    // 先去掉公共前缀和后缀，只对中间变化的部分做差异计算
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+
+    let mut ops: Vec<(DiffOp, usize, usize)> = (0..prefix).map(|i| (DiffOp::Equal, i, i)).collect();
+    let middle = myers(
+        &a[prefix..a.len() - suffix],
+        &b[prefix..b.len() - suffix],
+        limit,
+    );
+    ops.extend(
+        middle
+            .into_iter()
+            .map(|(op, x, y)| (op, x + prefix, y + prefix)),
+    );
+    ops.extend((0..suffix).map(|i| (DiffOp::Equal, a.len() - suffix + i, b.len() - suffix + i)));
+
+    build_hunks(&a, &b, &ops, context)
+}
+
+/// Myers 差异算法，返回 (操作, 旧行下标, 新行下标)
+/// 新增行的旧行下标、删除行的新行下标表示该行插入/删除的位置
+fn myers(a: &[&str], b: &[&str], limit: usize) -> Vec<(DiffOp, usize, usize)> {
+    let (n, m) = (a.len(), b.len());
+    let max = n + m;
+    let offset = max as isize;
+    let mut v = vec![0isize; 2 * max + 2];
+    // 每一步只保存 k ∈ [-d, d] 范围内的结果，回溯时使用
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    let mut found = None;
+    for d in 0..=max.min(limit) as isize {
+        for k in (-d..=d).step_by(2) {
+            let idx = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[idx - 1] < v[idx + 1]) {
+                v[idx + 1]
+            } else {
+                v[idx - 1] + 1
+            };
+            let mut y = x - k;
+            while (x as usize) < n && (y as usize) < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[idx] = x;
+            if x as usize >= n && y as usize >= m {
+                found = Some(d);
+                break;
+            }
+        }
+        trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+        if found.is_some() {
+            break;
+        }
+    }
+
+    let Some(depth) = found else {
+        // 差异过大：整体删除旧内容，整体新增新内容
+        let mut ops: Vec<_> = (0..n).map(|x| (DiffOp::Delete, x, 0)).collect();
+        ops.extend((0..m).map(|y| (DiffOp::Insert, n, y)));
+        return ops;
+    };
+
+    let mut ops = Vec::new();
+    let (mut x, mut y) = (n as isize, m as isize);
+    for d in (1..=depth).rev() {
+        let prev = &trace[(d - 1) as usize];
+        let at = |k: isize| prev[(k + d - 1) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = at(prev_k);
+        let prev_y = prev_x - prev_k;
+
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            ops.push((DiffOp::Equal, x as usize, y as usize));
+        }
+        if x == prev_x {
+            ops.push((DiffOp::Insert, x as usize, prev_y as usize));
+        } else {
+            ops.push((DiffOp::Delete, prev_x as usize, y as usize));
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    while x > 0 && y > 0 {
+        x -= 1;
+        y -= 1;
+        ops.push((DiffOp::Equal, x as usize, y as usize));
+    }
+
+    ops.reverse();
+    ops
+}
+
+/// 把逐行操作按变化位置分组，相距不超过 2 * context 行的变化合并到同一片段
+fn build_hunks(a: &[&str], b: &[&str], ops: &[(DiffOp, usize, usize)], context: usize) -> LineDiff {
+    let mut additions = 0;
+    let mut deletions = 0;
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+
+    for (i, (op, _, _)) in ops.iter().enumerate() {
+        match op {
+            DiffOp::Equal => continue,
+            DiffOp::Insert => additions += 1,
+            DiffOp::Delete => deletions += 1,
+        }
+        let start = i.saturating_sub(context);
+        let end = (i + context + 1).min(ops.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    let hunks = ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let lines: Vec<DiffLine> = ops[start..end]
+                .iter()
+                .map(|&(op, x, y)| match op {
+                    DiffOp::Equal => DiffLine {
+                        op,
+                        old_line: Some(x + 1),
+                        new_line: Some(y + 1),
+                        content: a[x].to_string(),
+                    },
+                    DiffOp::Insert => DiffLine {
+                        op,
+                        old_line: None,
+                        new_line: Some(y + 1),
+                        content: b[y].to_string(),
+                    },
+                    DiffOp::Delete => DiffLine {
+                        op,
+                        old_line: Some(x + 1),
+                        new_line: None,
+                        content: a[x].to_string(),
+                    },
+                })
+                .collect();
+
+            let (_, first_x, first_y) = ops[start];
+            DiffHunk {
+                old_start: first_x + 1,
+                old_lines: lines.iter().filter(|l| l.old_line.is_some()).count(),
+                new_start: first_y + 1,
+                new_lines: lines.iter().filter(|l| l.new_line.is_some()).count(),
+                lines,
+            }
+        })
+        .collect();
+
+    LineDiff {
+        additions,
+        deletions,
+        hunks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 把差异还原成 unified diff 风格的文本，方便断言
+    fn render(diff: &LineDiff) -> Vec<String> {
+        diff.hunks
+            .iter()
+            .flat_map(|h| {
+                std::iter::once(format!(
+                    "@@ -{},{} +{},{} @@",
+                    h.old_start, h.old_lines, h.new_start, h.new_lines
+                ))
+                .chain(h.lines.iter().map(|l| {
+                    let sign = match l.op {
+                        DiffOp::Equal => ' ',
+                        DiffOp::Insert => '+',
+                        DiffOp::Delete => '-',
+                    };
+                    format!("{}{}", sign, l.content)
+                }))
+            })
+            .collect()
+    }
+
+    /// 按差异把旧文本重放成新文本
+    fn apply(old: &str, diff: &LineDiff) -> Vec<String> {
+        let old_lines: Vec<&str> = old.lines().collect();
+        let mut result = Vec::new();
+        let mut cursor = 0;
+        for hunk in &diff.hunks {
+            while cursor + 1 < hunk.old_start {
+                result.push(old_lines[cursor].to_string());
+                cursor += 1;
+            }
+            for line in &hunk.lines {
+                match line.op {
+                    DiffOp::Equal => {
+                        result.push(line.content.clone());
+                        cursor += 1;
+                    }
+                    DiffOp::Insert => result.push(line.content.clone()),
+                    DiffOp::Delete => cursor += 1,
+                }
+            }
+        }
+        result.extend(old_lines[cursor..].iter().map(|s| s.to_string()));
+        result
+    }
+
+    #[test]
+    fn test_identical_text_has_no_hunks() {
+        let diff = diff_lines("a\nb\nc\n", "a\nb\nc\n", 3);
+        assert_eq!(diff.additions, 0);
+        assert_eq!(diff.deletions, 0);
+        assert!(diff.hunks.is_empty());
+    }
+
+    #[test]
+    fn test_modified_line() {
+        let diff = diff_lines(
+            "# 标题\n第一行\n第二行\n第三行\n",
+            "# 标题\n第一行\n第二行（修改）\n第三行\n",
+            1,
+        );
+        assert_eq!(diff.additions, 1);
+        assert_eq!(diff.deletions, 1);
+        assert_eq!(
+            render(&diff),
+            vec![
+                "@@ -2,3 +2,3 @@",
+                " 第一行",
+                "-第二行",
+                "+第二行（修改）",
+                " 第三行",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_insert_and_delete_at_edges() {
+        let diff = diff_lines("a\nb\nc", "b\nc\nd", 0);
+        assert_eq!(
+            render(&diff),
+            vec!["@@ -1,1 +1,0 @@", "-a", "@@ -4,0 +3,1 @@", "+d"]
+        );
+
+        let diff = diff_lines("", "x\ny", 3);
+        assert_eq!(diff.additions, 2);
+        assert_eq!(render(&diff), vec!["@@ -1,0 +1,2 @@", "+x", "+y"]);
+    }
+
+    #[test]
+    fn test_close_changes_share_a_hunk() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12";
+        let new = "1\n2x\n3\n4\n5x\n6\n7\n8\n9\n10\n11x\n12";
+        let diff = diff_lines(old, new, 2);
+        assert_eq!(diff.hunks.len(), 2);
+        assert_eq!(diff.hunks[0].old_start, 1);
+        assert_eq!(diff.hunks[0].old_lines, 7);
+        assert_eq!(diff.hunks[1].old_start, 9);
+        assert_eq!(apply(old, &diff), new.lines().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_interleaved_changes_round_trip() {
+        let old = "a\nb\nc\na\nb\nb\na";
+        let new = "c\nb\na\nb\na\nc";
+        let diff = diff_lines(old, new, 1);
+        // 经典示例的最短编辑距离为 5
+        assert_eq!(diff.additions + diff.deletions, 5);
+        assert_eq!(apply(old, &diff), new.lines().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_edit_distance_limit_falls_back_to_replace() {
+        let old = "keep\na\nb\nc\nend";
+        let new = "keep\nx\ny\nend";
+        let diff = diff_lines_with_limit(old, new, 1, 2);
+        assert_eq!(diff.deletions, 3);
+        assert_eq!(diff.additions, 2);
+        assert_eq!(apply(old, &diff), new.lines().collect::<Vec<_>>());
+    }
+}
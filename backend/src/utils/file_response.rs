@@ -0,0 +1,301 @@
+use actix_web::body::SizedStream;
+use actix_web::http::header::{self, HttpDate};
+use actix_web::http::StatusCode;
+use actix_web::{HttpRequest, HttpResponse};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::services::{StorageBackend, StorageError, StorageFileMetadata};
+
+/// Range 请求头解析结果
+#[derive(Debug, PartialEq, Eq)]
+pub enum ByteRange {
+    /// 返回完整内容（无 Range 头、格式无法识别或多段范围）
+    Full,
+    /// 返回闭区间 [start, end] 的内容
+    Partial { start: u64, end: u64 },
+    /// 范围超出文件大小，应返回 416
+    Unsatisfiable,
+}
+
+/// 解析 `Range: bytes=...` 请求头
+/// 只支持单段范围；语法错误或多段范围按规范忽略，返回完整内容
+pub fn parse_range(value: &str, size: u64) -> ByteRange {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((start, end)) = spec.split_once('-') else {
+        return ByteRange::Full;
+    };
+    let (start, end) = (start.trim(), end.trim());
+
+    // 后缀范围：bytes=-N 表示最后 N 个字节
+    if start.is_empty() {
+        return match end.parse::<u64>() {
+            Ok(0) => ByteRange::Unsatisfiable,
+            Ok(_) if size == 0 => ByteRange::Unsatisfiable,
+            Ok(suffix) => ByteRange::Partial {
+                start: size - suffix.min(size),
+                end: size - 1,
+            },
+            Err(_) => ByteRange::Full,
+        };
+    }
+
+    let Ok(start) = start.parse::<u64>() else {
+        return ByteRange::Full;
+    };
+    let end = if end.is_empty() {
+        None
+    } else {
+        match end.parse::<u64>() {
+            Ok(end) if end >= start => Some(end),
+            _ => return ByteRange::Full,
+        }
+    };
+
+    if start >= size {
+        return ByteRange::Unsatisfiable;
+    }
+
+    ByteRange::Partial {
+        start,
+        end: end.map_or(size - 1, |end| end.min(size - 1)),
+    }
+}
+
+/// HTTP 日期只精确到秒，比较前统一截断
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn header_str(req: &HttpRequest, name: header::HeaderName) -> Option<&str> {
+    req.headers().get(name).and_then(|v| v.to_str().ok())
+}
+
+/// If-None-Match 列表中是否包含当前 ETag（弱比较）
+fn etag_list_matches(list: &str, etag: &str) -> bool {
+    list.split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/").trim_matches('"') == etag)
+}
+
+/// 客户端缓存是否仍然有效（应返回 304）
+/// If-None-Match 优先于 If-Modified-Since
+pub fn is_not_modified(req: &HttpRequest, metadata: &StorageFileMetadata) -> bool {
+    if let Some(list) = header_str(req, header::IF_NONE_MATCH) {
+        return metadata
+            .etag
+            .as_deref()
+            .is_some_and(|etag| etag_list_matches(list, etag));
+    }
+
+    let since = header_str(req, header::IF_MODIFIED_SINCE)
+        .and_then(|v| v.parse::<HttpDate>().ok())
+        .map(SystemTime::from);
+    match (since, metadata.last_modified) {
+        (Some(since), Some(modified)) => unix_secs(modified) <= unix_secs(since),
+        _ => false,
+    }
+}
+
+/// If-Range 校验：文件未变化时才按 Range 返回部分内容，否则返回完整文件
+/// ETag 需强比较，日期需完全一致
+pub fn if_range_allows(req: &HttpRequest, metadata: &StorageFileMetadata) -> bool {
+    let Some(value) = header_str(req, header::IF_RANGE).map(str::trim) else {
+        return true;
+    };
+
+    if value.starts_with("W/") {
+        return false;
+    }
+    if value.starts_with('"') {
+        return metadata
+            .etag
+            .as_deref()
+            .is_some_and(|etag| value.trim_matches('"') == etag);
+    }
+
+    match (value.parse::<HttpDate>(), metadata.last_modified) {
+        (Ok(date), Some(modified)) => unix_secs(SystemTime::from(date)) == unix_secs(modified),
+        _ => false,
+    }
+}
+
+/// 以流的形式返回存储中的文件
+///
+/// 自动处理 ETag / Last-Modified 条件请求（304）以及 Range / If-Range（206、416），
+/// `extra_headers` 为调用方需要附加的响应头（如 Content-Disposition、Cache-Control）
+pub async fn stream_storage_file(
+    req: &HttpRequest,
+    storage: &Arc<dyn StorageBackend>,
+    key: &str,
+    content_type: &str,
+    extra_headers: &[(&str, &str)],
+) -> Result<HttpResponse, StorageError> {
+    let metadata = storage.head_file(key).await?;
+
+    let mut builder = HttpResponse::Ok();
+    builder
+        .content_type(content_type)
+        .insert_header((header::ACCEPT_RANGES, "bytes"));
+    for (name, value) in extra_headers {
+        builder.insert_header((*name, *value));
+    }
+    if let Some(etag) = &metadata.etag {
+        builder.insert_header((header::ETAG, format!("\"{}\"", etag)));
+    }
+    if let Some(modified) = metadata.last_modified {
+        builder.insert_header((header::LAST_MODIFIED, HttpDate::from(modified).to_string()));
+    }
+
+    if is_not_modified(req, &metadata) {
+        return Ok(builder.status(StatusCode::NOT_MODIFIED).finish());
+    }
+
+    // 无法得知文件大小时不支持 Range，直接返回完整内容
+    let Some(size) = metadata.content_length else {
+        let stream = storage.read_stream(key, None).await?;
+        return Ok(builder.streaming(stream));
+    };
+
+    let range = match header_str(req, header::RANGE) {
+        Some(value) if if_range_allows(req, &metadata) => parse_range(value, size),
+        _ => ByteRange::Full,
+    };
+
+    match range {
+        ByteRange::Full => {
+            let stream = storage.read_stream(key, None).await?;
+            Ok(builder.body(SizedStream::new(size, stream)))
+        }
+        ByteRange::Partial { start, end } => {
+            let stream = storage.read_stream(key, Some((start, end))).await?;
+            Ok(builder
+                .status(StatusCode::PARTIAL_CONTENT)
+                .insert_header((
+                    header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", start, end, size),
+                ))
+                .body(SizedStream::new(end - start + 1, stream)))
+        }
+        ByteRange::Unsatisfiable => Ok(builder
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .insert_header((header::CONTENT_RANGE, format!("bytes */{}", size)))
+            .finish()),
+    }
+}
+
+/// 响应是否从文件开头开始传输
+/// 用于下载计数：阅读器按 Range 分段拉取时只在第一段计一次
+pub fn is_initial_transfer(response: &HttpResponse) -> bool {
+    match response.status() {
+        StatusCode::OK => true,
+        StatusCode::PARTIAL_CONTENT => response
+            .headers()
+            .get(header::CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("bytes 0-")),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(
+            parse_range("bytes=0-99", 1000),
+            ByteRange::Partial { start: 0, end: 99 }
+        );
+        assert_eq!(
+            parse_range("bytes=900-", 1000),
+            ByteRange::Partial {
+                start: 900,
+                end: 999
+            }
+        );
+        assert_eq!(
+            parse_range("bytes=-100", 1000),
+            ByteRange::Partial {
+                start: 900,
+                end: 999
+            }
+        );
+        // 结束位置超出文件大小时截断
+        assert_eq!(
+            parse_range("bytes=500-5000", 1000),
+            ByteRange::Partial {
+                start: 500,
+                end: 999
+            }
+        );
+        assert_eq!(parse_range("bytes=1000-", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=-0", 1000), ByteRange::Unsatisfiable);
+        // 无法识别或多段范围时忽略
+        assert_eq!(parse_range("bytes=5-3", 1000), ByteRange::Full);
+        assert_eq!(parse_range("bytes=0-1,5-6", 1000), ByteRange::Full);
+        assert_eq!(parse_range("items=0-1", 1000), ByteRange::Full);
+    }
+
+    fn metadata() -> StorageFileMetadata {
+        StorageFileMetadata {
+            content_length: Some(1000),
+            content_type: None,
+            etag: Some("abc".to_string()),
+            last_modified: Some(UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000)),
+        }
+    }
+
+    #[test]
+    fn test_is_not_modified() {
+        let meta = metadata();
+        let modified = HttpDate::from(meta.last_modified.unwrap()).to_string();
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, "W/\"abc\""))
+            .to_http_request();
+        assert!(is_not_modified(&req, &meta));
+
+        // If-None-Match 不匹配时忽略 If-Modified-Since
+        let req = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, "\"other\""))
+            .insert_header((header::IF_MODIFIED_SINCE, modified.as_str()))
+            .to_http_request();
+        assert!(!is_not_modified(&req, &meta));
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_MODIFIED_SINCE, modified.as_str()))
+            .to_http_request();
+        assert!(is_not_modified(&req, &meta));
+    }
+
+    #[test]
+    fn test_if_range_allows() {
+        let meta = metadata();
+
+        let req = TestRequest::default().to_http_request();
+        assert!(if_range_allows(&req, &meta));
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_RANGE, "\"abc\""))
+            .to_http_request();
+        assert!(if_range_allows(&req, &meta));
+
+        // 弱 ETag 和过期的 ETag 都不能用于 If-Range
+        for value in ["W/\"abc\"", "\"old\""] {
+            let req = TestRequest::default()
+                .insert_header((header::IF_RANGE, value))
+                .to_http_request();
+            assert!(!if_range_allows(&req, &meta));
+        }
+    }
+}
@@ -2,7 +2,9 @@ use argon2::{
     password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
     Argon2,
 };
-use rand::rngs::OsRng;
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use url::Url;
 
 /// 使用 Argon2id 算法对密码进行哈希
 ///
@@ -52,6 +54,25 @@ pub fn verify_password(password: &str, hash: &str) -> Result<bool, String> {
     }
 }
 
+/// 生成 32 字节随机的一次性令牌（十六进制），用于邮件中的重置/确认链接
+pub fn generate_one_time_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 一次性令牌的 SHA-256 摘要，数据库只保存摘要，泄露时无法直接使用
+pub fn hash_one_time_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.trim().as_bytes()))
+}
+
+/// 生成带一次性令牌的页面链接（追加 `token` 查询参数，保留页面地址中已有的参数）
+pub fn one_time_token_link(page_url: &Url, token: &str) -> String {
+    let mut link = page_url.clone();
+    link.query_pairs_mut().append_pair("token", token);
+    link.into()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -101,4 +122,37 @@ mod tests {
         assert!(verify_password(password, &hash1).expect("验证失败"));
         assert!(verify_password(password, &hash2).expect("验证失败"));
     }
+
+    #[test]
+    fn test_one_time_token() {
+        let token = generate_one_time_token();
+        assert_eq!(token.len(), 64);
+        assert_ne!(token, generate_one_time_token());
+
+        let hash = hash_one_time_token(&token);
+        assert_eq!(hash.len(), 64);
+        assert_ne!(hash, token);
+        assert_eq!(hash, hash_one_time_token(&format!(" {} ", token)));
+    }
+
+    #[test]
+    fn test_one_time_token_link() {
+        let page = Url::parse("https://share.example.com/reset-password").unwrap();
+        assert_eq!(
+            one_time_token_link(&page, "abc"),
+            "https://share.example.com/reset-password?token=abc"
+        );
+
+        // 已有的查询参数保留，特殊字符被编码
+        let page = Url::parse("https://share.example.com/#/reset?lang=zh").unwrap();
+        assert_eq!(
+            one_time_token_link(&page, "a&b=c"),
+            "https://share.example.com/?token=a%26b%3Dc#/reset?lang=zh"
+        );
+        let page = Url::parse("http://localhost:5173/verify?from=mail").unwrap();
+        assert_eq!(
+            one_time_token_link(&page, "abc"),
+            "http://localhost:5173/verify?from=mail&token=abc"
+        );
+    }
 }
@@ -7,6 +7,8 @@ use uuid::Uuid;
 const ACCESS_TOKEN_EXPIRE_MINUTES: i64 = 60;
 /// Refresh Token 有效期：7天
 const REFRESH_TOKEN_EXPIRE_DAYS: i64 = 7;
+/// 两步验证临时凭证有效期：5分钟
+const MFA_TOKEN_EXPIRE_MINUTES: i64 = 5;
 
 /// 生成 Access Token
 ///
@@ -15,6 +17,8 @@ const REFRESH_TOKEN_EXPIRE_DAYS: i64 = 7;
 /// * `username` - 用户名
 /// * `role` - 用户角色
 /// * `is_verified` - 是否实名认证
+/// * `session_id` - 登录会话ID
+/// * `mfa` - 本次登录是否完成了两步验证
 /// * `secret` - JWT密钥
 ///
 /// # Returns
@@ -25,6 +29,8 @@ pub fn generate_access_token(
     username: String,
     role: UserRole,
     is_verified: bool,
+    session_id: Uuid,
+    mfa: bool,
     secret: &str,
 ) -> Result<String, String> {
     let now = Utc::now();
@@ -38,6 +44,9 @@ pub fn generate_access_token(
         exp: exp.timestamp(),
         iat: now.timestamp(),
         token_type: "access".to_string(),
+        sid: session_id.to_string(),
+        jti: None,
+        mfa,
     };
 
     encode(
@@ -55,6 +64,8 @@ pub fn generate_access_token(
 /// * `username` - 用户名
 /// * `role` - 用户角色
 /// * `is_verified` - 是否实名认证
+/// * `session_id` - 登录会话ID
+/// * `jti` - Token 标识（与 user_sessions.refresh_jti 对应）
 /// * `secret` - JWT密钥
 ///
 /// # Returns
@@ -65,6 +76,8 @@ pub fn generate_refresh_token(
     username: String,
     role: UserRole,
     is_verified: bool,
+    session_id: Uuid,
+    jti: Uuid,
     secret: &str,
 ) -> Result<String, String> {
     let now = Utc::now();
@@ -78,6 +91,10 @@ pub fn generate_refresh_token(
         exp: exp.timestamp(),
         iat: now.timestamp(),
         token_type: "refresh".to_string(),
+        sid: session_id.to_string(),
+        jti: Some(jti.to_string()),
+        // 刷新时以会话记录的两步验证状态为准
+        mfa: false,
     };
 
     encode(
@@ -88,6 +105,41 @@ pub fn generate_refresh_token(
     .map_err(|e| format!("生成Refresh Token失败: {}", e))
 }
 
+/// 生成两步验证临时凭证（密码验证通过后签发，只能用于完成登录第二步）
+///
+/// # Arguments
+/// * `user_id` - 用户ID
+/// * `username` - 用户名
+/// * `secret` - JWT密钥
+///
+/// # Returns
+/// * `Ok(String)` - JWT Token
+/// * `Err(String)` - 错误信息
+pub fn generate_mfa_token(user_id: Uuid, username: String, secret: &str) -> Result<String, String> {
+    let now = Utc::now();
+    let exp = now + Duration::minutes(MFA_TOKEN_EXPIRE_MINUTES);
+
+    let claims = Claims {
+        sub: user_id.to_string(),
+        username,
+        role: String::new(),
+        is_verified: false,
+        exp: exp.timestamp(),
+        iat: now.timestamp(),
+        token_type: "mfa".to_string(),
+        sid: String::new(),
+        jti: None,
+        mfa: false,
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .map_err(|e| format!("生成两步验证凭证失败: {}", e))
+}
+
 /// 验证 Token
 ///
 /// # Arguments
@@ -132,6 +184,7 @@ pub fn verify_token(token: &str, secret: &str, token_type: Option<&str>) -> Resu
 /// * `Err(String)` - 错误信息
 pub fn extract_current_user(claims: Claims) -> Result<CurrentUser, String> {
     let user_id = Uuid::parse_str(&claims.sub).map_err(|_| "无效的用户ID")?;
+    let session_id = Uuid::parse_str(&claims.sid).map_err(|_| "无效的会话ID")?;
 
     let role = match claims.role.as_str() {
         "admin" => UserRole::Admin,
@@ -145,6 +198,8 @@ pub fn extract_current_user(claims: Claims) -> Result<CurrentUser, String> {
         username: claims.username,
         role,
         is_verified: claims.is_verified,
+        session_id,
+        mfa: claims.mfa,
     })
 }
 
@@ -154,12 +209,16 @@ pub fn get_access_token_expire_seconds() -> i64 {
     ACCESS_TOKEN_EXPIRE_MINUTES * 60
 }
 
-/// 获取 Refresh Token 有效期（秒）（预留接口）
-#[allow(dead_code)]
+/// 获取 Refresh Token 有效期（秒）
 pub fn get_refresh_token_expire_seconds() -> i64 {
     REFRESH_TOKEN_EXPIRE_DAYS * 24 * 60 * 60
 }
 
+/// 获取两步验证临时凭证有效期（秒）
+pub fn get_mfa_token_expire_seconds() -> i64 {
+    MFA_TOKEN_EXPIRE_MINUTES * 60
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -172,9 +231,18 @@ mod tests {
         let username = "testuser".to_string();
         let role = UserRole::User;
 
-        let token =
-            generate_access_token(user_id, username.clone(), role.clone(), false, TEST_SECRET)
-                .expect("生成Token失败");
+        let session_id = Uuid::new_v4();
+
+        let token = generate_access_token(
+            user_id,
+            username.clone(),
+            role.clone(),
+            false,
+            session_id,
+            true,
+            TEST_SECRET,
+        )
+        .expect("生成Token失败");
 
         // 验证Token
         let claims = verify_token(&token, TEST_SECRET, Some("access")).expect("验证Token失败");
@@ -182,8 +250,11 @@ mod tests {
         assert_eq!(claims.sub, user_id.to_string());
         assert_eq!(claims.username, username);
         assert_eq!(claims.role, "user");
-        assert_eq!(claims.is_verified, false);
+        assert!(!claims.is_verified);
         assert_eq!(claims.token_type, "access");
+        assert_eq!(claims.sid, session_id.to_string());
+        assert!(claims.jti.is_none());
+        assert!(claims.mfa);
     }
 
     #[test]
@@ -192,9 +263,19 @@ mod tests {
         let username = "testuser".to_string();
         let role = UserRole::User;
 
-        let token =
-            generate_refresh_token(user_id, username.clone(), role.clone(), false, TEST_SECRET)
-                .expect("生成Token失败");
+        let session_id = Uuid::new_v4();
+        let jti = Uuid::new_v4();
+
+        let token = generate_refresh_token(
+            user_id,
+            username.clone(),
+            role.clone(),
+            false,
+            session_id,
+            jti,
+            TEST_SECRET,
+        )
+        .expect("生成Token失败");
 
         // 验证Token
         let claims = verify_token(&token, TEST_SECRET, Some("refresh")).expect("验证Token失败");
@@ -202,6 +283,8 @@ mod tests {
         assert_eq!(claims.sub, user_id.to_string());
         assert_eq!(claims.username, username);
         assert_eq!(claims.token_type, "refresh");
+        assert_eq!(claims.sid, session_id.to_string());
+        assert_eq!(claims.jti, Some(jti.to_string()));
     }
 
     #[test]
@@ -211,8 +294,16 @@ mod tests {
         let role = UserRole::User;
 
         // 生成Access Token
-        let access_token = generate_access_token(user_id, username, role, false, TEST_SECRET)
-            .expect("生成Token失败");
+        let access_token = generate_access_token(
+            user_id,
+            username,
+            role,
+            false,
+            Uuid::new_v4(),
+            false,
+            TEST_SECRET,
+        )
+        .expect("生成Token失败");
 
         // 尝试用refresh类型验证access token
         let result = verify_token(&access_token, TEST_SECRET, Some("refresh"));
@@ -229,6 +320,7 @@ mod tests {
     #[test]
     fn test_extract_current_user() {
         let user_id = Uuid::new_v4();
+        let session_id = Uuid::new_v4();
         let claims = Claims {
             sub: user_id.to_string(),
             username: "testuser".to_string(),
@@ -237,6 +329,9 @@ mod tests {
             exp: Utc::now().timestamp() + 3600,
             iat: Utc::now().timestamp(),
             token_type: "access".to_string(),
+            sid: session_id.to_string(),
+            jti: None,
+            mfa: true,
         };
 
         let current_user = extract_current_user(claims).expect("提取用户信息失败");
@@ -244,6 +339,38 @@ mod tests {
         assert_eq!(current_user.id, user_id);
         assert_eq!(current_user.username, "testuser");
         assert_eq!(current_user.role, UserRole::Admin);
-        assert_eq!(current_user.is_verified, true);
+        assert!(current_user.is_verified);
+        assert_eq!(current_user.session_id, session_id);
+        assert!(current_user.mfa);
+    }
+
+    #[test]
+    fn test_extract_current_user_without_session() {
+        // 旧版本签发的 Token 没有会话ID，不再被接受
+        let claims = Claims {
+            sub: Uuid::new_v4().to_string(),
+            username: "testuser".to_string(),
+            role: "user".to_string(),
+            is_verified: false,
+            exp: Utc::now().timestamp() + 3600,
+            iat: Utc::now().timestamp(),
+            token_type: "access".to_string(),
+            sid: String::new(),
+            jti: None,
+            mfa: false,
+        };
+
+        assert!(extract_current_user(claims).is_err());
+    }
+
+    #[test]
+    fn test_mfa_token_cannot_be_used_as_access_token() {
+        let user_id = Uuid::new_v4();
+        let token = generate_mfa_token(user_id, "testuser".to_string(), TEST_SECRET)
+            .expect("生成Token失败");
+
+        let claims = verify_token(&token, TEST_SECRET, Some("mfa")).expect("验证Token失败");
+        assert_eq!(claims.sub, user_id.to_string());
+        assert!(verify_token(&token, TEST_SECRET, Some("access")).is_err());
     }
 }
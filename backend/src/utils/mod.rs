@@ -1,9 +1,21 @@
 // 工具函数模块
 
+pub mod client_ip;
+pub mod db;
+pub mod diff;
+pub mod file_response;
 pub mod hash;
 pub mod jwt;
 pub mod response;
+pub mod totp;
+pub mod zip_stream;
 
+pub use client_ip::*;
+pub use db::*;
+pub use diff::*;
+pub use file_response::*;
 pub use hash::*;
 pub use jwt::*;
 pub use response::*;
+pub use totp::*;
+pub use zip_stream::*;
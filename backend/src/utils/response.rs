@@ -4,6 +4,11 @@ use actix_web::HttpResponse;
 
 /// 构建错误响应
 pub fn error_response(status: u16, message: &str) -> HttpResponse {
+    error_response_with(status, message, serde_json::Value::Null)
+}
+
+/// 构建错误响应，并把 `extra` 中的字段合并到响应体中
+pub fn error_response_with(status: u16, message: &str, extra: serde_json::Value) -> HttpResponse {
     let error = match status {
         400 => "BadRequest",
         401 => "Unauthorized",
@@ -11,20 +16,26 @@ pub fn error_response(status: u16, message: &str) -> HttpResponse {
         404 => "NotFound",
         409 => "Conflict",
         422 => "UnprocessableEntity",
+        429 => "TooManyRequests",
         500 => "InternalServerError",
         502 => "BadGateway",
         503 => "ServiceUnavailable",
         _ => "UnknownError",
     };
 
+    let mut body = serde_json::json!({
+        "error": error,
+        "message": message
+    });
+    if let (Some(body), serde_json::Value::Object(extra)) = (body.as_object_mut(), extra) {
+        body.extend(extra);
+    }
+
     HttpResponse::build(
         actix_web::http::StatusCode::from_u16(status)
             .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR),
     )
-    .json(serde_json::json!({
-        "error": error,
-        "message": message
-    }))
+    .json(body)
 }
 
 /// 快速构建 400 Bad Request 错误
@@ -52,6 +63,21 @@ pub fn conflict(message: &str) -> HttpResponse {
     error_response(409, message)
 }
 
+/// 构建 409 Conflict 错误，附带冲突对象等额外字段
+pub fn conflict_with(message: &str, extra: serde_json::Value) -> HttpResponse {
+    error_response_with(409, message, extra)
+}
+
+/// 构建 429 Too Many Requests 错误（附带 Retry-After 头）
+pub fn too_many_requests(message: &str, retry_after_secs: u64) -> HttpResponse {
+    let mut response = error_response(429, message);
+    response.headers_mut().insert(
+        actix_web::http::header::RETRY_AFTER,
+        actix_web::http::header::HeaderValue::from(retry_after_secs),
+    );
+    response
+}
+
 /// 快速构建 500 Internal Server Error 错误
 pub fn internal_error(message: &str) -> HttpResponse {
     error_response(500, message)
@@ -0,0 +1,320 @@
+// TOTP 两步验证（RFC 6238，HMAC-SHA1，6 位，30 秒步长）
+
+use aes_gcm::aead::{Aead, AeadCore};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hmac::{Hmac, Mac};
+use rand::{rngs::OsRng, RngCore};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+/// 时间步长（秒）
+pub const TOTP_STEP_SECS: i64 = 30;
+/// 动态码位数
+const TOTP_DIGITS: u32 = 6;
+/// 允许的时钟偏差（前后各一个时间步）
+const TOTP_SKEW_STEPS: i64 = 1;
+
+/// 加密保存的 TOTP 密钥前缀（明文 Base32 密钥不含冒号，可以据此区分旧数据）
+const ENCRYPTED_SECRET_PREFIX: &str = "v1:";
+/// AES-GCM nonce 长度
+const NONCE_LEN: usize = 12;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Base32 编码（RFC 4648，不带填充）
+pub fn base32_encode(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(5) * 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &byte in data {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    encoded
+}
+
+/// Base32 解码，忽略大小写、空格和填充符
+pub fn base32_decode(input: &str) -> Result<Vec<u8>, String> {
+    let mut decoded = Vec::with_capacity(input.len() * 5 / 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in input.chars() {
+        if c == '=' || c.is_whitespace() {
+            continue;
+        }
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&a| a as char == c.to_ascii_uppercase())
+            .ok_or_else(|| format!("无效的 Base32 字符: {}", c))?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(decoded)
+}
+
+/// 生成 160 位随机 TOTP 密钥（Base32）
+pub fn generate_totp_secret() -> String {
+    let mut bytes = [0u8; 20];
+    OsRng.fill_bytes(&mut bytes);
+    base32_encode(&bytes)
+}
+
+/// TOTP 密钥的加解密（AES-256-GCM）
+///
+/// 数据库中保存 `v1:` 前缀加 Base64(nonce || 密文)，加密密钥由配置的密钥材料经 SHA-256 派生
+#[derive(Clone)]
+pub struct TotpSecretCipher {
+    cipher: Aes256Gcm,
+}
+
+impl TotpSecretCipher {
+    pub fn new(key_material: &str) -> Self {
+        let key = Sha256::digest(key_material.as_bytes());
+        Self {
+            cipher: <Aes256Gcm as aes_gcm::KeyInit>::new(&key),
+        }
+    }
+
+    /// 加密 Base32 密钥，每次使用新的随机 nonce
+    pub fn encrypt(&self, secret: &str) -> String {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, secret.as_bytes())
+            .expect("AES-GCM 加密内存中的数据不会失败");
+        let mut payload = nonce.to_vec();
+        payload.extend_from_slice(&ciphertext);
+        format!("{}{}", ENCRYPTED_SECRET_PREFIX, STANDARD.encode(payload))
+    }
+
+    /// 解密数据库中保存的密钥，未加密的旧数据原样返回
+    pub fn decrypt(&self, stored: &str) -> Result<String, String> {
+        let Some(encoded) = stored.strip_prefix(ENCRYPTED_SECRET_PREFIX) else {
+            return Ok(stored.to_string());
+        };
+        let payload = STANDARD
+            .decode(encoded)
+            .map_err(|e| format!("TOTP 密钥格式无效: {}", e))?;
+        if payload.len() <= NONCE_LEN {
+            return Err("TOTP 密钥格式无效".to_string());
+        }
+        let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "TOTP 密钥解密失败，请检查 TOTP_ENCRYPTION_KEY".to_string())?;
+        String::from_utf8(plaintext).map_err(|_| "TOTP 密钥格式无效".to_string())
+    }
+
+    /// 数据库中的密钥是否已加密
+    pub fn is_encrypted(stored: &str) -> bool {
+        stored.starts_with(ENCRYPTED_SECRET_PREFIX)
+    }
+}
+
+/// 计算 HOTP 动态码（RFC 4226）
+fn hotp(key: &[u8], counter: u64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC 可以接受任意长度的密钥");
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    binary % 10u32.pow(TOTP_DIGITS)
+}
+
+/// 计算指定时间步的动态码
+pub fn totp_code(secret: &str, step: i64) -> Result<String, String> {
+    let key = base32_decode(secret)?;
+    if key.is_empty() {
+        return Err("TOTP 密钥为空".to_string());
+    }
+    Ok(format!(
+        "{:0width$}",
+        hotp(&key, step as u64),
+        width = TOTP_DIGITS as usize
+    ))
+}
+
+/// 时间戳对应的时间步
+pub fn totp_step(unix_time: i64) -> i64 {
+    unix_time.div_euclid(TOTP_STEP_SECS)
+}
+
+/// 校验动态码，通过时返回匹配的时间步（用于防止同一动态码被重复使用）
+pub fn verify_totp(secret: &str, code: &str, unix_time: i64) -> Result<Option<i64>, String> {
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    if code.len() != TOTP_DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(None);
+    }
+
+    let current = totp_step(unix_time);
+    for step in (current - TOTP_SKEW_STEPS)..=(current + TOTP_SKEW_STEPS) {
+        let expected = totp_code(secret, step)?;
+        // 逐字节比较，避免提前返回泄露匹配位数
+        let matched = expected
+            .bytes()
+            .zip(code.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0;
+        if matched {
+            return Ok(Some(step));
+        }
+    }
+    Ok(None)
+}
+
+/// 生成身份验证器 App 扫码用的 otpauth:// 链接
+pub fn totp_provisioning_uri(issuer: &str, account: &str, secret: &str) -> String {
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        percent_encode(issuer),
+        percent_encode(account),
+        secret,
+        percent_encode(issuer),
+        TOTP_DIGITS,
+        TOTP_STEP_SECS
+    )
+}
+
+/// 生成一个恢复码（10 位 Base32 字符，格式 xxxxx-xxxxx）
+pub fn generate_recovery_code() -> String {
+    let mut bytes = [0u8; 7];
+    OsRng.fill_bytes(&mut bytes);
+    let code = base32_encode(&bytes)[..10].to_lowercase();
+    format!("{}-{}", &code[..5], &code[5..])
+}
+
+/// 规范化用户输入的恢复码（忽略大小写、空格和连字符）
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn percent_encode(input: &str) -> String {
+    let mut encoded = String::new();
+    for &byte in input.as_bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push('%');
+            encoded.push_str(&format!("{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RFC 6238 附录 B 的 SHA1 测试密钥 "12345678901234567890"
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn test_base32_roundtrip() {
+        assert_eq!(base32_encode(b"12345678901234567890"), RFC_SECRET);
+        assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+        assert_eq!(base32_decode("mzxw 6ytb oi==").unwrap(), b"foobar");
+        assert!(base32_decode("MZXW1").is_err());
+
+        let secret = generate_totp_secret();
+        assert_eq!(secret.len(), 32);
+        assert_eq!(base32_decode(&secret).unwrap().len(), 20);
+    }
+
+    #[test]
+    fn test_secret_cipher_roundtrip() {
+        let cipher = TotpSecretCipher::new("test-key");
+        let encrypted = cipher.encrypt(RFC_SECRET);
+        assert!(TotpSecretCipher::is_encrypted(&encrypted));
+        assert!(!encrypted.contains(RFC_SECRET));
+        // 每次加密使用不同的 nonce
+        assert_ne!(encrypted, cipher.encrypt(RFC_SECRET));
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), RFC_SECRET);
+
+        // 未加密的旧数据原样返回
+        assert!(!TotpSecretCipher::is_encrypted(RFC_SECRET));
+        assert_eq!(cipher.decrypt(RFC_SECRET).unwrap(), RFC_SECRET);
+
+        // 密钥不对或数据被篡改时解密失败
+        assert!(TotpSecretCipher::new("other-key")
+            .decrypt(&encrypted)
+            .is_err());
+        let mut payload = STANDARD.decode(&encrypted["v1:".len()..]).unwrap();
+        *payload.last_mut().unwrap() ^= 1;
+        let tampered = format!("v1:{}", STANDARD.encode(payload));
+        assert!(cipher.decrypt(&tampered).is_err());
+        assert!(cipher.decrypt("v1:AAAA").is_err());
+    }
+
+    #[test]
+    fn test_totp_rfc6238_vectors() {
+        // RFC 6238 给出的是 8 位动态码，6 位动态码为其后 6 位
+        for (time, expected) in [
+            (59, "94287082"),
+            (1111111109, "07081804"),
+            (1111111111, "14050471"),
+            (1234567890, "89005924"),
+            (2000000000, "69279037"),
+        ] {
+            let code = totp_code(RFC_SECRET, totp_step(time)).unwrap();
+            assert_eq!(code, expected[2..]);
+        }
+    }
+
+    #[test]
+    fn test_verify_totp_window() {
+        let now = 1234567890;
+        let code = totp_code(RFC_SECRET, totp_step(now)).unwrap();
+
+        assert_eq!(
+            verify_totp(RFC_SECRET, &code, now).unwrap(),
+            Some(totp_step(now))
+        );
+        // 允许前后一个时间步的时钟偏差
+        assert!(verify_totp(RFC_SECRET, &code, now + TOTP_STEP_SECS)
+            .unwrap()
+            .is_some());
+        assert!(verify_totp(RFC_SECRET, &code, now + 3 * TOTP_STEP_SECS)
+            .unwrap()
+            .is_none());
+        assert!(verify_totp(RFC_SECRET, "12345", now).unwrap().is_none());
+        assert!(verify_totp(RFC_SECRET, "abcdef", now).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_recovery_code_format() {
+        let code = generate_recovery_code();
+        assert_eq!(code.len(), 11);
+        assert_eq!(code.as_bytes()[5], b'-');
+        assert_eq!(normalize_recovery_code(&code.to_uppercase()).len(), 10);
+        assert_eq!(normalize_recovery_code(" ABCDE-fghij "), "abcdefghij");
+    }
+
+    #[test]
+    fn test_provisioning_uri() {
+        let uri = totp_provisioning_uri("ShareUSTC", "alice bob", RFC_SECRET);
+        assert!(uri.starts_with("otpauth://totp/ShareUSTC:alice%20bob?secret="));
+        assert!(uri.contains("&issuer=ShareUSTC"));
+    }
+}
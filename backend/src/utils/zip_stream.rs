@@ -0,0 +1,363 @@
+use actix_web::web::Bytes;
+use chrono::{Datelike, Timelike};
+use flate2::write::DeflateEncoder;
+use flate2::{Compression, Crc};
+use std::io::Write;
+
+/// 流式 ZIP 编码器
+///
+/// `zip::ZipWriter` 需要 `Seek` 回填文件头，只能先在内存中写完整个压缩包。
+/// 这里改用数据描述符（通用标志位 3）：文件头中的 CRC 和大小先写 0，
+/// 条目数据之后再补写，因此每段输出都可以立即发送给客户端，
+/// 内存中只保留中央目录所需的少量元信息。
+///
+/// 条目大小、偏移或条目数超出普通 ZIP 的上限（4GB / 65535 个）时使用 ZIP64 扩展。
+/// 单个条目是否使用 ZIP64 需要在写文件头时决定，由 `start_entry` 的大小提示判断。
+pub struct ZipStreamWriter {
+    offset: u64,
+    dos_time: u16,
+    dos_date: u16,
+    entries: Vec<ZipEntryRecord>,
+    current: Option<ZipEntryState>,
+}
+
+/// 已写完的条目（用于生成中央目录）
+struct ZipEntryRecord {
+    name: Vec<u8>,
+    zip64: bool,
+    header_offset: u64,
+    crc: u32,
+    compressed_size: u64,
+    size: u64,
+}
+
+/// 正在写入的条目
+struct ZipEntryState {
+    name: Vec<u8>,
+    /// 本地文件头带 ZIP64 扩展字段，数据描述符中的大小为 8 字节
+    zip64: bool,
+    header_offset: u64,
+    crc: Crc,
+    encoder: DeflateEncoder<Vec<u8>>,
+    /// 原始字节数（`Crc::amount` 只有 32 位，超过 4GB 会回绕）
+    bytes_written: u64,
+    compressed_size: u64,
+}
+
+/// 通用标志：位 3 使用数据描述符，位 11 文件名为 UTF-8
+const FLAGS: u16 = 0x0808;
+/// 压缩方式：Deflate
+const METHOD_DEFLATE: u16 = 8;
+const VERSION_NEEDED: u16 = 20;
+const VERSION_NEEDED_ZIP64: u16 = 45;
+/// 创建者版本：高字节 3 表示 Unix，便于解压时保留文件权限
+const VERSION_MADE_BY: u16 = 0x0314;
+/// ZIP64 扩展字段的标识
+const ZIP64_EXTRA_ID: u16 = 0x0001;
+/// 预计大小超过该值的条目使用 ZIP64（为压缩后可能略有膨胀留出余量）
+const ZIP64_ENTRY_THRESHOLD: u64 = 0xF000_0000;
+/// 普通 ZIP 字段能表示的上限，达到时写入 0xFFFFFFFF 并改用 ZIP64 字段
+const ZIP32_LIMIT: u64 = 0xFFFF_FFFF;
+
+impl Default for ZipStreamWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ZipStreamWriter {
+    pub fn new() -> Self {
+        let now = chrono::Local::now();
+        let dos_time =
+            ((now.hour() as u16) << 11) | ((now.minute() as u16) << 5) | (now.second() as u16 / 2);
+        let dos_date = (((now.year().clamp(1980, 2107) - 1980) as u16) << 9)
+            | ((now.month() as u16) << 5)
+            | now.day() as u16;
+
+        Self {
+            offset: 0,
+            dos_time,
+            dos_date,
+            entries: Vec::new(),
+            current: None,
+        }
+    }
+
+    /// 开始一个新条目，返回本地文件头
+    /// `size_hint` 为预计的原始大小，未知或接近 4GB 时该条目使用 ZIP64；
+    /// 上一个条目未结束时会先自动结束
+    pub fn start_entry(&mut self, name: &str, size_hint: Option<u64>) -> std::io::Result<Bytes> {
+        let mut out = self.finish_entry()?.to_vec();
+
+        let name = name.as_bytes().to_vec();
+        let header_offset = self.offset;
+        let zip64 = size_hint.is_none_or(|size| size >= ZIP64_ENTRY_THRESHOLD);
+        let extra_len: u16 = if zip64 { 20 } else { 0 };
+
+        out.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        out.extend_from_slice(
+            &(if zip64 {
+                VERSION_NEEDED_ZIP64
+            } else {
+                VERSION_NEEDED
+            })
+            .to_le_bytes(),
+        );
+        out.extend_from_slice(&FLAGS.to_le_bytes());
+        out.extend_from_slice(&METHOD_DEFLATE.to_le_bytes());
+        out.extend_from_slice(&self.dos_time.to_le_bytes());
+        out.extend_from_slice(&self.dos_date.to_le_bytes());
+        // CRC、压缩后大小、原始大小由数据描述符给出
+        out.extend_from_slice(&[0u8; 12]);
+        out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        out.extend_from_slice(&extra_len.to_le_bytes());
+        out.extend_from_slice(&name);
+        if zip64 {
+            // ZIP64 扩展字段：原始大小和压缩后大小，同样由数据描述符给出
+            out.extend_from_slice(&ZIP64_EXTRA_ID.to_le_bytes());
+            out.extend_from_slice(&16u16.to_le_bytes());
+            out.extend_from_slice(&[0u8; 16]);
+        }
+
+        self.offset = header_offset + 30 + name.len() as u64 + extra_len as u64;
+        self.current = Some(ZipEntryState {
+            name,
+            zip64,
+            header_offset,
+            crc: Crc::new(),
+            encoder: DeflateEncoder::new(Vec::new(), Compression::default()),
+            bytes_written: 0,
+            compressed_size: 0,
+        });
+
+        Ok(Bytes::from(out))
+    }
+
+    /// 写入当前条目的数据，返回目前可以发送的压缩数据（可能为空）
+    pub fn write(&mut self, data: &[u8]) -> std::io::Result<Bytes> {
+        let Some(entry) = self.current.as_mut() else {
+            return Err(std::io::Error::other("没有正在写入的 ZIP 条目"));
+        };
+
+        entry.crc.update(data);
+        entry.bytes_written += data.len() as u64;
+        entry.encoder.write_all(data)?;
+        let out = std::mem::take(entry.encoder.get_mut());
+        entry.compressed_size += out.len() as u64;
+        self.offset += out.len() as u64;
+
+        Ok(Bytes::from(out))
+    }
+
+    /// 结束当前条目，返回剩余的压缩数据和数据描述符
+    pub fn finish_entry(&mut self) -> std::io::Result<Bytes> {
+        let Some(entry) = self.current.take() else {
+            return Ok(Bytes::new());
+        };
+
+        let mut out = entry.encoder.finish()?;
+        let compressed_size = entry.compressed_size + out.len() as u64;
+        let crc = entry.crc.sum();
+        let size = entry.bytes_written;
+
+        out.extend_from_slice(&0x08074b50u32.to_le_bytes());
+        out.extend_from_slice(&crc.to_le_bytes());
+        if entry.zip64 {
+            out.extend_from_slice(&compressed_size.to_le_bytes());
+            out.extend_from_slice(&size.to_le_bytes());
+        } else if compressed_size >= ZIP32_LIMIT || size >= ZIP32_LIMIT {
+            return Err(std::io::Error::other(
+                "ZIP 条目超过 4GB，但写入文件头时的大小提示不足",
+            ));
+        } else {
+            out.extend_from_slice(&(compressed_size as u32).to_le_bytes());
+            out.extend_from_slice(&(size as u32).to_le_bytes());
+        }
+
+        self.offset += out.len() as u64;
+        self.entries.push(ZipEntryRecord {
+            name: entry.name,
+            zip64: entry.zip64,
+            header_offset: entry.header_offset,
+            crc,
+            compressed_size,
+            size,
+        });
+
+        Ok(Bytes::from(out))
+    }
+
+    /// 写入一个完整的小文件（如清单），返回全部输出
+    pub fn add_entry(&mut self, name: &str, data: &[u8]) -> std::io::Result<Bytes> {
+        let mut out = self.start_entry(name, Some(data.len() as u64))?.to_vec();
+        out.extend_from_slice(&self.write(data)?);
+        out.extend_from_slice(&self.finish_entry()?);
+        Ok(Bytes::from(out))
+    }
+
+    /// 结束压缩包，返回中央目录和目录结束记录
+    pub fn finish(mut self) -> std::io::Result<Bytes> {
+        let pending = self.finish_entry()?;
+        let directory_offset = self.offset;
+        let mut out = Vec::new();
+
+        for entry in &self.entries {
+            // 超出 32 位的字段写 0xFFFFFFFF，实际值按顺序放进 ZIP64 扩展字段
+            let mut zip64_extra = Vec::new();
+            let mut field = |value: u64| {
+                if value >= ZIP32_LIMIT {
+                    zip64_extra.extend_from_slice(&value.to_le_bytes());
+                    ZIP32_LIMIT as u32
+                } else {
+                    value as u32
+                }
+            };
+            let size = field(entry.size);
+            let compressed_size = field(entry.compressed_size);
+            let header_offset = field(entry.header_offset);
+            let version_needed = if entry.zip64 || !zip64_extra.is_empty() {
+                VERSION_NEEDED_ZIP64
+            } else {
+                VERSION_NEEDED
+            };
+            let extra_len = if zip64_extra.is_empty() {
+                0
+            } else {
+                4 + zip64_extra.len() as u16
+            };
+
+            out.extend_from_slice(&0x02014b50u32.to_le_bytes());
+            out.extend_from_slice(&VERSION_MADE_BY.to_le_bytes());
+            out.extend_from_slice(&version_needed.to_le_bytes());
+            out.extend_from_slice(&FLAGS.to_le_bytes());
+            out.extend_from_slice(&METHOD_DEFLATE.to_le_bytes());
+            out.extend_from_slice(&self.dos_time.to_le_bytes());
+            out.extend_from_slice(&self.dos_date.to_le_bytes());
+            out.extend_from_slice(&entry.crc.to_le_bytes());
+            out.extend_from_slice(&compressed_size.to_le_bytes());
+            out.extend_from_slice(&size.to_le_bytes());
+            out.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+            out.extend_from_slice(&extra_len.to_le_bytes());
+            // 注释长度、起始磁盘号、内部属性
+            out.extend_from_slice(&[0u8; 6]);
+            // 外部属性：普通文件，权限 0644
+            out.extend_from_slice(&(0o100644u32 << 16).to_le_bytes());
+            out.extend_from_slice(&header_offset.to_le_bytes());
+            out.extend_from_slice(&entry.name);
+            if !zip64_extra.is_empty() {
+                out.extend_from_slice(&ZIP64_EXTRA_ID.to_le_bytes());
+                out.extend_from_slice(&(zip64_extra.len() as u16).to_le_bytes());
+                out.extend_from_slice(&zip64_extra);
+            }
+        }
+
+        let directory_size = out.len() as u64;
+        let count = self.entries.len() as u64;
+
+        if count >= 0xFFFF || directory_size >= ZIP32_LIMIT || directory_offset >= ZIP32_LIMIT {
+            // ZIP64 目录结束记录及其定位器
+            let record_offset = directory_offset + directory_size;
+            out.extend_from_slice(&0x06064b50u32.to_le_bytes());
+            out.extend_from_slice(&44u64.to_le_bytes());
+            out.extend_from_slice(&VERSION_MADE_BY.to_le_bytes());
+            out.extend_from_slice(&VERSION_NEEDED_ZIP64.to_le_bytes());
+            out.extend_from_slice(&[0u8; 8]);
+            out.extend_from_slice(&count.to_le_bytes());
+            out.extend_from_slice(&count.to_le_bytes());
+            out.extend_from_slice(&directory_size.to_le_bytes());
+            out.extend_from_slice(&directory_offset.to_le_bytes());
+
+            out.extend_from_slice(&0x07064b50u32.to_le_bytes());
+            out.extend_from_slice(&0u32.to_le_bytes());
+            out.extend_from_slice(&record_offset.to_le_bytes());
+            out.extend_from_slice(&1u32.to_le_bytes());
+        }
+
+        let count = count.min(0xFFFF) as u16;
+        out.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        out.extend_from_slice(&[0u8; 4]);
+        out.extend_from_slice(&count.to_le_bytes());
+        out.extend_from_slice(&count.to_le_bytes());
+        out.extend_from_slice(&(directory_size.min(ZIP32_LIMIT) as u32).to_le_bytes());
+        out.extend_from_slice(&(directory_offset.min(ZIP32_LIMIT) as u32).to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+
+        Ok(Bytes::from([pending.to_vec(), out].concat()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_zip_stream_roundtrip() {
+        let mut writer = ZipStreamWriter::new();
+        let mut archive = Vec::new();
+
+        archive.extend_from_slice(&writer.start_entry("第一章/讲义.txt", Some(1024)).unwrap());
+        for _ in 0..100 {
+            archive.extend_from_slice(&writer.write("极限与连续\n".as_bytes()).unwrap());
+        }
+        // 未显式结束的条目由下一个条目或 finish 自动结束
+        archive.extend_from_slice(&writer.add_entry("manifest.csv", b"id,title\n").unwrap());
+        // 大小未知的条目使用 ZIP64 文件头和数据描述符
+        archive.extend_from_slice(&writer.start_entry("附录.md", None).unwrap());
+        archive.extend_from_slice(&writer.write(b"# appendix").unwrap());
+        archive.extend_from_slice(&writer.finish().unwrap());
+
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(archive)).unwrap();
+        assert_eq!(zip.len(), 3);
+
+        let mut content = String::new();
+        zip.by_name("第一章/讲义.txt")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "极限与连续\n".repeat(100));
+
+        let mut manifest = String::new();
+        zip.by_index(1)
+            .unwrap()
+            .read_to_string(&mut manifest)
+            .unwrap();
+        assert_eq!(manifest, "id,title\n");
+
+        let mut appendix = String::new();
+        zip.by_name("附录.md")
+            .unwrap()
+            .read_to_string(&mut appendix)
+            .unwrap();
+        assert_eq!(appendix, "# appendix");
+    }
+
+    #[test]
+    fn test_zip_stream_zip64_entry_count() {
+        // 超过 65535 个条目时需要 ZIP64 目录结束记录
+        let count = 0xFFFF + 10;
+        let mut writer = ZipStreamWriter::new();
+        let mut archive = Vec::new();
+        for i in 0..count {
+            archive.extend_from_slice(&writer.add_entry(&format!("{}.txt", i), b"x").unwrap());
+        }
+        archive.extend_from_slice(&writer.finish().unwrap());
+
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(archive)).unwrap();
+        assert_eq!(zip.len(), count);
+
+        let mut content = String::new();
+        zip.by_name(&format!("{}.txt", count - 1))
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "x");
+    }
+
+    #[test]
+    fn test_zip_stream_empty_archive() {
+        let archive = ZipStreamWriter::new().finish().unwrap();
+        let zip = zip::ZipArchive::new(std::io::Cursor::new(archive.to_vec())).unwrap();
+        assert_eq!(zip.len(), 0);
+    }
+}
@@ -296,17 +296,23 @@ export function getResourceTypeFromFileName(fileName: string): ResourceTypeType
 // 更新资源内容请求（用于Markdown在线编辑）
 export interface UpdateResourceContentRequest {
   content: string;
+  changeNote?: string;
+  // 开始编辑时读取到的 updatedAt（乐观锁）
+  expectedUpdatedAt: string;
 }
 
 // 更新资源内容响应
 export interface UpdateResourceContentResponse {
   id: string;
   updatedAt: string;
+  versionNumber: number;
 }
 
 // 获取资源原始内容响应
 export interface GetResourceRawContentResponse {
   content: string;
+  updatedAt: string;
+  versionNumber: number;
 }
 
 // 热门资源列表项
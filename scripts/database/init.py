@@ -145,6 +145,11 @@ BEGIN
     IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'users' AND column_name = 'updated_at') THEN
         ALTER TABLE users ADD COLUMN updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP;
     END IF;
+
+    -- email_verified_at: 邮箱确认时间，修改邮箱后清空
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'users' AND column_name = 'email_verified_at') THEN
+        ALTER TABLE users ADD COLUMN email_verified_at TIMESTAMP;
+    END IF;
 END $$;
 
 -- ============================================
@@ -221,6 +226,21 @@ BEGIN
     IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resources' AND column_name = 'source_file_url') THEN
         ALTER TABLE resources ADD COLUMN source_file_url VARCHAR(1000);
     END IF;
+
+    -- 全文检索：资源描述
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resources' AND column_name = 'description') THEN
+        ALTER TABLE resources ADD COLUMN description TEXT;
+    END IF;
+
+    -- 全文检索：从 PDF/TXT/Markdown 文件中提取的正文
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resources' AND column_name = 'content_text') THEN
+        ALTER TABLE resources ADD COLUMN content_text TEXT;
+    END IF;
+
+    -- 全文检索：分词后的检索向量（由后端分词后写入）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resources' AND column_name = 'search_vector') THEN
+        ALTER TABLE resources ADD COLUMN search_vector TSVECTOR;
+    END IF;
 END $$;
 
 -- ============================================
@@ -396,6 +416,39 @@ BEGIN
     IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comments' AND column_name = 'updated_at') THEN
         ALTER TABLE comments ADD COLUMN updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP;
     END IF;
+
+    -- parent_id: 回复的上级评论（顶层评论为 NULL）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comments' AND column_name = 'parent_id') THEN
+        ALTER TABLE comments ADD COLUMN parent_id UUID REFERENCES comments(id) ON DELETE CASCADE;
+    END IF;
+
+    -- root_id: 所属楼层的顶层评论（顶层评论为 NULL），用于按楼层分页
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comments' AND column_name = 'root_id') THEN
+        ALTER TABLE comments ADD COLUMN root_id UUID REFERENCES comments(id) ON DELETE CASCADE;
+    END IF;
+
+    -- depth: 回复层级（顶层评论为 0）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comments' AND column_name = 'depth') THEN
+        ALTER TABLE comments ADD COLUMN depth INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    -- reply_to_user_id: 被回复的用户（超过最大层级时回复挂在上级评论下，仍记录实际回复对象）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comments' AND column_name = 'reply_to_user_id') THEN
+        ALTER TABLE comments ADD COLUMN reply_to_user_id UUID REFERENCES users(id) ON DELETE SET NULL;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comments' AND column_name = 'like_count') THEN
+        ALTER TABLE comments ADD COLUMN like_count INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    -- report_count: 未处理的举报数，达到阈值后评论自动隐藏（audit_status 改为 pending）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comments' AND column_name = 'report_count') THEN
+        ALTER TABLE comments ADD COLUMN report_count INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comments' AND column_name = 'edited_at') THEN
+        ALTER TABLE comments ADD COLUMN edited_at TIMESTAMP;
+    END IF;
 END $$;
 
 -- ============================================
@@ -827,125 +880,1362 @@ EXCEPTION
 END $$;
 
 -- ============================================
--- 为现有用户分配 sn（增量更新支持）
+-- 19. 分片上传会话表（本地存储模式下的断点续传）
 -- ============================================
+CREATE TABLE IF NOT EXISTS upload_sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
 DO $$
-DECLARE
-    user_record RECORD;
-    current_sn BIGINT := 1;
 BEGIN
-    FOR user_record IN
-        SELECT id FROM users WHERE sn IS NULL ORDER BY created_at ASC
-    LOOP
-        UPDATE users SET sn = current_sn WHERE id = user_record.id;
-        current_sn := current_sn + 1;
-    END LOOP;
-    IF current_sn > 1 THEN
-        PERFORM setval('user_sn_seq', current_sn - 1, true);
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'upload_sessions' AND column_name = 'user_id') THEN
+        IF EXISTS (SELECT 1 FROM upload_sessions LIMIT 1) THEN
+            ALTER TABLE upload_sessions ADD COLUMN user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE upload_sessions ADD COLUMN user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'upload_sessions' AND column_name = 'file_name') THEN
+        ALTER TABLE upload_sessions ADD COLUMN file_name VARCHAR(255) NOT NULL DEFAULT '';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'upload_sessions' AND column_name = 'file_size') THEN
+        ALTER TABLE upload_sessions ADD COLUMN file_size BIGINT NOT NULL DEFAULT 0;
+    END IF;
+
+    -- file_hash: 客户端声明的完整文件 SHA-256，合并时校验
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'upload_sessions' AND column_name = 'file_hash') THEN
+        ALTER TABLE upload_sessions ADD COLUMN file_hash VARCHAR(64) NOT NULL DEFAULT '';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'upload_sessions' AND column_name = 'mime_type') THEN
+        ALTER TABLE upload_sessions ADD COLUMN mime_type VARCHAR(100);
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'upload_sessions' AND column_name = 'chunk_size') THEN
+        ALTER TABLE upload_sessions ADD COLUMN chunk_size INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'upload_sessions' AND column_name = 'total_chunks') THEN
+        ALTER TABLE upload_sessions ADD COLUMN total_chunks INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    -- received_chunks: 已接收的分片序号
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'upload_sessions' AND column_name = 'received_chunks') THEN
+        ALTER TABLE upload_sessions ADD COLUMN received_chunks INTEGER[] NOT NULL DEFAULT '{}';
+    END IF;
+
+    -- status: uploading / completed / aborted
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'upload_sessions' AND column_name = 'status') THEN
+        ALTER TABLE upload_sessions ADD COLUMN status VARCHAR(20) NOT NULL DEFAULT 'uploading';
+    END IF;
+
+    -- resource_id: 合并成功后生成的资源
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'upload_sessions' AND column_name = 'resource_id') THEN
+        ALTER TABLE upload_sessions ADD COLUMN resource_id UUID REFERENCES resources(id) ON DELETE SET NULL;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'upload_sessions' AND column_name = 'expires_at') THEN
+        ALTER TABLE upload_sessions ADD COLUMN expires_at TIMESTAMP NOT NULL DEFAULT (CURRENT_TIMESTAMP + INTERVAL '24 hours');
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'upload_sessions' AND column_name = 'updated_at') THEN
+        ALTER TABLE upload_sessions ADD COLUMN updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP;
     END IF;
 END $$;
 
 -- ============================================
--- 创建索引
+-- 20. 内容审核规则表（本地关键词 / 正则审核）
 -- ============================================
-CREATE INDEX IF NOT EXISTS idx_users_role ON users(role);
-CREATE INDEX IF NOT EXISTS idx_users_is_verified ON users(is_verified);
-CREATE INDEX IF NOT EXISTS idx_users_sn ON users(sn);
-CREATE INDEX IF NOT EXISTS idx_resources_uploader ON resources(uploader_id);
-CREATE INDEX IF NOT EXISTS idx_resources_author ON resources(author_id);
-CREATE INDEX IF NOT EXISTS idx_resources_course ON resources(course_name);
-CREATE INDEX IF NOT EXISTS idx_resources_type ON resources(resource_type);
-CREATE INDEX IF NOT EXISTS idx_resources_category ON resources(category);
-CREATE INDEX IF NOT EXISTS idx_resources_audit_status ON resources(audit_status);
-CREATE INDEX IF NOT EXISTS idx_resources_tags ON resources USING GIN(tags);
-CREATE INDEX IF NOT EXISTS idx_resources_created_at ON resources(created_at DESC);
-CREATE INDEX IF NOT EXISTS idx_resources_storage_type ON resources(storage_type);
-CREATE INDEX IF NOT EXISTS idx_ratings_resource ON ratings(resource_id);
-CREATE INDEX IF NOT EXISTS idx_ratings_user ON ratings(user_id);
-CREATE INDEX IF NOT EXISTS idx_likes_user ON likes(user_id);
-CREATE INDEX IF NOT EXISTS idx_comments_resource ON comments(resource_id);
-CREATE INDEX IF NOT EXISTS idx_comments_user ON comments(user_id);
-CREATE INDEX IF NOT EXISTS idx_comments_created_at ON comments(created_at DESC);
-CREATE INDEX IF NOT EXISTS idx_favorites_user ON favorites(user_id);
-CREATE INDEX IF NOT EXISTS idx_fav_res_resource ON favorite_resources(resource_id);
-CREATE INDEX IF NOT EXISTS idx_claims_resource ON claims(resource_id);
-CREATE INDEX IF NOT EXISTS idx_claims_applicant ON claims(applicant_id);
-CREATE INDEX IF NOT EXISTS idx_claims_status ON claims(status);
-CREATE INDEX IF NOT EXISTS idx_notifications_recipient ON notifications(recipient_id);
-CREATE INDEX IF NOT EXISTS idx_notifications_priority ON notifications(priority);
-CREATE INDEX IF NOT EXISTS idx_notifications_is_read ON notifications(is_read);
-CREATE INDEX IF NOT EXISTS idx_notifications_created_at ON notifications(created_at DESC);
-CREATE INDEX IF NOT EXISTS idx_notification_reads_notification ON notification_reads(notification_id);
-CREATE INDEX IF NOT EXISTS idx_notification_reads_user ON notification_reads(user_id);
-CREATE INDEX IF NOT EXISTS idx_notification_reads_unique ON notification_reads(notification_id, user_id);
-CREATE INDEX IF NOT EXISTS idx_audit_logs_user ON audit_logs(user_id);
-CREATE INDEX IF NOT EXISTS idx_audit_logs_action ON audit_logs(action);
-CREATE INDEX IF NOT EXISTS idx_audit_logs_created_at ON audit_logs(created_at DESC);
-CREATE INDEX IF NOT EXISTS idx_download_logs_resource ON download_logs(resource_id);
-CREATE INDEX IF NOT EXISTS idx_download_logs_user ON download_logs(user_id);
-CREATE INDEX IF NOT EXISTS idx_download_logs_time ON download_logs(downloaded_at DESC);
-CREATE INDEX IF NOT EXISTS idx_images_uploader ON images(uploader_id);
-CREATE INDEX IF NOT EXISTS idx_images_storage_type ON images(storage_type);
-CREATE INDEX IF NOT EXISTS idx_teachers_sn ON teachers(sn);
-CREATE INDEX IF NOT EXISTS idx_teachers_department ON teachers(department);
-CREATE INDEX IF NOT EXISTS idx_teachers_is_active ON teachers(is_active);
-CREATE INDEX IF NOT EXISTS idx_courses_sn ON courses(sn);
-CREATE INDEX IF NOT EXISTS idx_courses_semester ON courses(semester);
-CREATE INDEX IF NOT EXISTS idx_courses_is_active ON courses(is_active);
-CREATE INDEX IF NOT EXISTS idx_resource_teachers_resource ON resource_teachers(resource_id);
-CREATE INDEX IF NOT EXISTS idx_resource_teachers_teacher ON resource_teachers(teacher_sn);
-CREATE INDEX IF NOT EXISTS idx_resource_courses_resource ON resource_courses(resource_id);
-CREATE INDEX IF NOT EXISTS idx_resource_courses_course ON resource_courses(course_sn);
+CREATE TABLE IF NOT EXISTS moderation_rules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
 
--- 资源关联表索引
-CREATE INDEX IF NOT EXISTS idx_resource_relations_source ON resource_relations(source_resource_id);
-CREATE INDEX IF NOT EXISTS idx_resource_relations_target ON resource_relations(target_resource_id);
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'moderation_rules' AND column_name = 'pattern') THEN
+        ALTER TABLE moderation_rules ADD COLUMN pattern VARCHAR(500) NOT NULL DEFAULT '';
+    END IF;
+
+    -- match_type: keyword（不区分大小写的子串匹配）/ regex（正则表达式）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'moderation_rules' AND column_name = 'match_type') THEN
+        ALTER TABLE moderation_rules ADD COLUMN match_type VARCHAR(20) NOT NULL DEFAULT 'keyword';
+    END IF;
+
+    -- target: all / resource / comment
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'moderation_rules' AND column_name = 'target') THEN
+        ALTER TABLE moderation_rules ADD COLUMN target VARCHAR(20) NOT NULL DEFAULT 'all';
+    END IF;
+
+    -- reason: 命中后写入 ai_reject_reason 的说明
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'moderation_rules' AND column_name = 'reason') THEN
+        ALTER TABLE moderation_rules ADD COLUMN reason VARCHAR(200);
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'moderation_rules' AND column_name = 'is_active') THEN
+        ALTER TABLE moderation_rules ADD COLUMN is_active BOOLEAN NOT NULL DEFAULT TRUE;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'moderation_rules' AND column_name = 'created_by') THEN
+        ALTER TABLE moderation_rules ADD COLUMN created_by UUID REFERENCES users(id) ON DELETE SET NULL;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'moderation_rules' AND column_name = 'updated_at') THEN
+        ALTER TABLE moderation_rules ADD COLUMN updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP;
+    END IF;
+END $$;
 
 -- ============================================
--- 创建触发器
+-- 21. 评论编辑历史表（保存每次编辑前的内容）
 -- ============================================
-CREATE OR REPLACE FUNCTION update_updated_at_column()
-RETURNS TRIGGER AS $$
+CREATE TABLE IF NOT EXISTS comment_edits (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
 BEGIN
-    NEW.updated_at = CURRENT_TIMESTAMP;
-    RETURN NEW;
-END;
-$$
-language 'plpgsql';
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comment_edits' AND column_name = 'comment_id') THEN
+        IF EXISTS (SELECT 1 FROM comment_edits LIMIT 1) THEN
+            ALTER TABLE comment_edits ADD COLUMN comment_id UUID REFERENCES comments(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE comment_edits ADD COLUMN comment_id UUID NOT NULL REFERENCES comments(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
 
-DROP TRIGGER IF EXISTS update_users_updated_at ON users;
-CREATE TRIGGER update_users_updated_at
-    BEFORE UPDATE ON users
-    FOR EACH ROW
-    EXECUTE FUNCTION update_updated_at_column();
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comment_edits' AND column_name = 'content') THEN
+        ALTER TABLE comment_edits ADD COLUMN content TEXT NOT NULL DEFAULT '';
+    END IF;
+END $$;
 
-DROP TRIGGER IF EXISTS update_resources_updated_at ON resources;
-CREATE TRIGGER update_resources_updated_at
-    BEFORE UPDATE ON resources
-    FOR EACH ROW
-    EXECUTE FUNCTION update_updated_at_column();
+-- ============================================
+-- 22. 评论点赞表
+-- ============================================
+CREATE TABLE IF NOT EXISTS comment_likes (
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
 
-DROP TRIGGER IF EXISTS update_ratings_updated_at ON ratings;
-CREATE TRIGGER update_ratings_updated_at
-    BEFORE UPDATE ON ratings
-    FOR EACH ROW
-    EXECUTE FUNCTION update_updated_at_column();
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comment_likes' AND column_name = 'comment_id') THEN
+        ALTER TABLE comment_likes ADD COLUMN comment_id UUID REFERENCES comments(id) ON DELETE CASCADE;
+    END IF;
 
-DROP TRIGGER IF EXISTS update_comments_updated_at ON comments;
-CREATE TRIGGER update_comments_updated_at
-    BEFORE UPDATE ON comments
-    FOR EACH ROW
-    EXECUTE FUNCTION update_updated_at_column();
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comment_likes' AND column_name = 'user_id') THEN
+        ALTER TABLE comment_likes ADD COLUMN user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+    END IF;
+END $$;
 
-DROP TRIGGER IF EXISTS update_teachers_updated_at ON teachers;
-CREATE TRIGGER update_teachers_updated_at
-    BEFORE UPDATE ON teachers
-    FOR EACH ROW
-    EXECUTE FUNCTION update_updated_at_column();
+-- 添加主键约束
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM pg_constraint
+        WHERE conname = 'comment_likes_pkey' AND conrelid = 'comment_likes'::regclass
+    ) THEN
+        ALTER TABLE comment_likes ADD PRIMARY KEY (comment_id, user_id);
+    END IF;
+EXCEPTION
+    WHEN unique_violation THEN
+        RAISE NOTICE '无法添加主键约束：存在重复数据';
+END $$;
 
-DROP TRIGGER IF EXISTS update_courses_updated_at ON courses;
-CREATE TRIGGER update_courses_updated_at
-    BEFORE UPDATE ON courses
+-- ============================================
+-- 23. 评论举报表
+-- ============================================
+CREATE TABLE IF NOT EXISTS comment_reports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comment_reports' AND column_name = 'comment_id') THEN
+        IF EXISTS (SELECT 1 FROM comment_reports LIMIT 1) THEN
+            ALTER TABLE comment_reports ADD COLUMN comment_id UUID REFERENCES comments(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE comment_reports ADD COLUMN comment_id UUID NOT NULL REFERENCES comments(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comment_reports' AND column_name = 'reporter_id') THEN
+        IF EXISTS (SELECT 1 FROM comment_reports LIMIT 1) THEN
+            ALTER TABLE comment_reports ADD COLUMN reporter_id UUID REFERENCES users(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE comment_reports ADD COLUMN reporter_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    -- reason: spam（广告）/ abuse（辱骂攻击）/ illegal（违法违规）/ other（其他）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comment_reports' AND column_name = 'reason') THEN
+        ALTER TABLE comment_reports ADD COLUMN reason VARCHAR(20) NOT NULL DEFAULT 'other';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comment_reports' AND column_name = 'detail') THEN
+        ALTER TABLE comment_reports ADD COLUMN detail VARCHAR(500);
+    END IF;
+
+    -- status: pending（待处理）/ accepted（举报成立）/ dismissed（已驳回）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comment_reports' AND column_name = 'status') THEN
+        ALTER TABLE comment_reports ADD COLUMN status VARCHAR(20) NOT NULL DEFAULT 'pending';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comment_reports' AND column_name = 'handled_by') THEN
+        ALTER TABLE comment_reports ADD COLUMN handled_by UUID REFERENCES users(id) ON DELETE SET NULL;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comment_reports' AND column_name = 'handled_at') THEN
+        ALTER TABLE comment_reports ADD COLUMN handled_at TIMESTAMP;
+    END IF;
+END $$;
+
+-- 同一用户对同一评论只能有一条待处理举报（由部分唯一索引 idx_comment_reports_pending_unique 保证），
+-- 举报处理后允许再次举报，因此移除旧的全量唯一约束
+ALTER TABLE comment_reports DROP CONSTRAINT IF EXISTS comment_reports_comment_id_reporter_id_key;
+
+-- ============================================
+-- 24. 登录会话表（持久化 Refresh Token，支持轮换与吊销）
+-- ============================================
+CREATE TABLE IF NOT EXISTS user_sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_sessions' AND column_name = 'user_id') THEN
+        IF EXISTS (SELECT 1 FROM user_sessions LIMIT 1) THEN
+            ALTER TABLE user_sessions ADD COLUMN user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE user_sessions ADD COLUMN user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    -- refresh_jti: 当前有效的 Refresh Token 标识，每次刷新轮换；旧标识再次出现视为重放
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_sessions' AND column_name = 'refresh_jti') THEN
+        ALTER TABLE user_sessions ADD COLUMN refresh_jti UUID NOT NULL DEFAULT gen_random_uuid();
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_sessions' AND column_name = 'user_agent') THEN
+        ALTER TABLE user_sessions ADD COLUMN user_agent VARCHAR(500);
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_sessions' AND column_name = 'ip_address') THEN
+        ALTER TABLE user_sessions ADD COLUMN ip_address VARCHAR(45);
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_sessions' AND column_name = 'last_used_at') THEN
+        ALTER TABLE user_sessions ADD COLUMN last_used_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_sessions' AND column_name = 'expires_at') THEN
+        ALTER TABLE user_sessions ADD COLUMN expires_at TIMESTAMP NOT NULL DEFAULT (CURRENT_TIMESTAMP + INTERVAL '7 days');
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_sessions' AND column_name = 'revoked_at') THEN
+        ALTER TABLE user_sessions ADD COLUMN revoked_at TIMESTAMP;
+    END IF;
+
+    -- revoked_reason: logout / user_revoked / user_disabled / reuse_detected
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_sessions' AND column_name = 'revoked_reason') THEN
+        ALTER TABLE user_sessions ADD COLUMN revoked_reason VARCHAR(50);
+    END IF;
+
+    -- mfa_verified: 登录时是否完成了两步验证（刷新 Token 时沿用）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_sessions' AND column_name = 'mfa_verified') THEN
+        ALTER TABLE user_sessions ADD COLUMN mfa_verified BOOLEAN NOT NULL DEFAULT false;
+    END IF;
+END $$;
+
+-- ============================================
+-- 25. 密码重置令牌表（只保存令牌哈希，一次性使用）
+-- ============================================
+CREATE TABLE IF NOT EXISTS password_reset_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'password_reset_tokens' AND column_name = 'user_id') THEN
+        IF EXISTS (SELECT 1 FROM password_reset_tokens LIMIT 1) THEN
+            ALTER TABLE password_reset_tokens ADD COLUMN user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE password_reset_tokens ADD COLUMN user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    -- token_hash: 令牌的 SHA-256 十六进制摘要，明文令牌只出现在邮件中
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'password_reset_tokens' AND column_name = 'token_hash') THEN
+        ALTER TABLE password_reset_tokens ADD COLUMN token_hash VARCHAR(64) NOT NULL DEFAULT '';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'password_reset_tokens' AND column_name = 'expires_at') THEN
+        ALTER TABLE password_reset_tokens ADD COLUMN expires_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'password_reset_tokens' AND column_name = 'used_at') THEN
+        ALTER TABLE password_reset_tokens ADD COLUMN used_at TIMESTAMP;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'password_reset_tokens' AND column_name = 'requested_ip') THEN
+        ALTER TABLE password_reset_tokens ADD COLUMN requested_ip VARCHAR(45);
+    END IF;
+END $$;
+
+-- ============================================
+-- 26. 邮箱确认令牌表（只保存令牌哈希，一次性使用）
+-- ============================================
+CREATE TABLE IF NOT EXISTS email_verification_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'email_verification_tokens' AND column_name = 'user_id') THEN
+        IF EXISTS (SELECT 1 FROM email_verification_tokens LIMIT 1) THEN
+            ALTER TABLE email_verification_tokens ADD COLUMN user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE email_verification_tokens ADD COLUMN user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    -- email: 发送确认邮件时的邮箱，确认时需与用户当前邮箱一致
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'email_verification_tokens' AND column_name = 'email') THEN
+        ALTER TABLE email_verification_tokens ADD COLUMN email VARCHAR(255) NOT NULL DEFAULT '';
+    END IF;
+
+    -- token_hash: 令牌的 SHA-256 十六进制摘要，明文令牌只出现在邮件中
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'email_verification_tokens' AND column_name = 'token_hash') THEN
+        ALTER TABLE email_verification_tokens ADD COLUMN token_hash VARCHAR(64) NOT NULL DEFAULT '';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'email_verification_tokens' AND column_name = 'expires_at') THEN
+        ALTER TABLE email_verification_tokens ADD COLUMN expires_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'email_verification_tokens' AND column_name = 'used_at') THEN
+        ALTER TABLE email_verification_tokens ADD COLUMN used_at TIMESTAMP;
+    END IF;
+END $$;
+
+-- ============================================
+-- 27. 实名认证申请表（校园邮箱自动通过或管理员审核）
+-- ============================================
+CREATE TABLE IF NOT EXISTS verification_requests (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'verification_requests' AND column_name = 'user_id') THEN
+        IF EXISTS (SELECT 1 FROM verification_requests LIMIT 1) THEN
+            ALTER TABLE verification_requests ADD COLUMN user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE verification_requests ADD COLUMN user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    -- real_info: 提交的实名信息 (real_name, student_id, major, grade)
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'verification_requests' AND column_name = 'real_info') THEN
+        ALTER TABLE verification_requests ADD COLUMN real_info JSONB NOT NULL DEFAULT '{}';
+    END IF;
+
+    -- status: pending, approved, rejected
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'verification_requests' AND column_name = 'status') THEN
+        ALTER TABLE verification_requests ADD COLUMN status VARCHAR(20) NOT NULL DEFAULT 'pending';
+    END IF;
+
+    -- method: campus_email（已确认校园邮箱自动通过）、sso（统一身份认证提供学号自动通过）或 admin（管理员审核）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'verification_requests' AND column_name = 'method') THEN
+        ALTER TABLE verification_requests ADD COLUMN method VARCHAR(20);
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'verification_requests' AND column_name = 'reviewer_id') THEN
+        ALTER TABLE verification_requests ADD COLUMN reviewer_id UUID REFERENCES users(id) ON DELETE SET NULL;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'verification_requests' AND column_name = 'review_comment') THEN
+        ALTER TABLE verification_requests ADD COLUMN review_comment TEXT;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'verification_requests' AND column_name = 'reviewed_at') THEN
+        ALTER TABLE verification_requests ADD COLUMN reviewed_at TIMESTAMP;
+    END IF;
+END $$;
+
+-- ============================================
+-- 28. 限流令牌桶表（RATE_LIMIT_STORE=postgres 时多实例共享）
+-- ============================================
+CREATE TABLE IF NOT EXISTS rate_limit_buckets (
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    -- bucket_key: 规则名:user:<用户ID> 或 规则名:ip:<IP>
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'rate_limit_buckets' AND column_name = 'bucket_key') THEN
+        ALTER TABLE rate_limit_buckets ADD COLUMN bucket_key VARCHAR(255);
+    END IF;
+
+    -- tokens: 上次更新时桶内剩余的令牌数
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'rate_limit_buckets' AND column_name = 'tokens') THEN
+        ALTER TABLE rate_limit_buckets ADD COLUMN tokens DOUBLE PRECISION NOT NULL DEFAULT 0;
+    END IF;
+END $$;
+
+-- 添加主键约束
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM pg_constraint
+        WHERE conname = 'rate_limit_buckets_pkey' AND conrelid = 'rate_limit_buckets'::regclass
+    ) THEN
+        ALTER TABLE rate_limit_buckets ADD PRIMARY KEY (bucket_key);
+    END IF;
+EXCEPTION
+    WHEN unique_violation THEN
+        RAISE NOTICE '无法添加主键约束：存在重复数据';
+END $$;
+
+-- ============================================
+-- 29. 登录失败记录表（按用户名和客户端 IP 累计，用于渐进式锁定）
+-- ============================================
+CREATE TABLE IF NOT EXISTS login_failures (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'login_failures' AND column_name = 'username') THEN
+        ALTER TABLE login_failures ADD COLUMN username VARCHAR(50) NOT NULL DEFAULT '';
+    END IF;
+
+    -- failed_count: 连续失败次数，登录成功后清零
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'login_failures' AND column_name = 'failed_count') THEN
+        ALTER TABLE login_failures ADD COLUMN failed_count INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'login_failures' AND column_name = 'last_failed_at') THEN
+        ALTER TABLE login_failures ADD COLUMN last_failed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+    END IF;
+
+    -- locked_until: 锁定截止时间，期间拒绝该用户名的登录请求
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'login_failures' AND column_name = 'locked_until') THEN
+        ALTER TABLE login_failures ADD COLUMN locked_until TIMESTAMP;
+    END IF;
+
+    -- ip_address: 客户端 IP，同一用户名来自不同 IP 的失败分别计数，避免他人借此锁定账号
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'login_failures' AND column_name = 'ip_address') THEN
+        ALTER TABLE login_failures ADD COLUMN ip_address VARCHAR(45) NOT NULL DEFAULT '';
+    END IF;
+END $$;
+
+-- ============================================
+-- 30. 两步验证（TOTP）密钥表
+-- ============================================
+CREATE TABLE IF NOT EXISTS user_totp (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_totp' AND column_name = 'user_id') THEN
+        IF EXISTS (SELECT 1 FROM user_totp LIMIT 1) THEN
+            ALTER TABLE user_totp ADD COLUMN user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE user_totp ADD COLUMN user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    -- secret: 加密保存的 TOTP 密钥（AES-256-GCM，格式 v1:<Base64(nonce || 密文)>）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_totp' AND column_name = 'secret') THEN
+        ALTER TABLE user_totp ADD COLUMN secret TEXT NOT NULL DEFAULT '';
+    END IF;
+
+    -- 旧版本以 VARCHAR(64) 保存明文密钥，加密后长度超出，改为 TEXT
+    IF EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_totp' AND column_name = 'secret' AND data_type <> 'text') THEN
+        ALTER TABLE user_totp ALTER COLUMN secret TYPE TEXT;
+    END IF;
+
+    -- enabled_at: 验证首个动态码后启用，为空表示尚未完成绑定
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_totp' AND column_name = 'enabled_at') THEN
+        ALTER TABLE user_totp ADD COLUMN enabled_at TIMESTAMP;
+    END IF;
+
+    -- last_used_step: 最近一次通过验证的时间步，同一个动态码不能重复使用
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_totp' AND column_name = 'last_used_step') THEN
+        ALTER TABLE user_totp ADD COLUMN last_used_step BIGINT;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_totp' AND column_name = 'updated_at') THEN
+        ALTER TABLE user_totp ADD COLUMN updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP;
+    END IF;
+END $$;
+
+-- ============================================
+-- 31. 两步验证恢复码表（只保存哈希，一次性使用）
+-- ============================================
+CREATE TABLE IF NOT EXISTS mfa_recovery_codes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'mfa_recovery_codes' AND column_name = 'user_id') THEN
+        IF EXISTS (SELECT 1 FROM mfa_recovery_codes LIMIT 1) THEN
+            ALTER TABLE mfa_recovery_codes ADD COLUMN user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE mfa_recovery_codes ADD COLUMN user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    -- code_hash: 恢复码的 SHA-256 十六进制摘要
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'mfa_recovery_codes' AND column_name = 'code_hash') THEN
+        ALTER TABLE mfa_recovery_codes ADD COLUMN code_hash VARCHAR(64) NOT NULL DEFAULT '';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'mfa_recovery_codes' AND column_name = 'used_at') THEN
+        ALTER TABLE mfa_recovery_codes ADD COLUMN used_at TIMESTAMP;
+    END IF;
+END $$;
+
+-- ============================================
+-- 32. 个人访问令牌表（供脚本调用 API，只保存哈希）
+-- ============================================
+CREATE TABLE IF NOT EXISTS api_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'api_tokens' AND column_name = 'user_id') THEN
+        IF EXISTS (SELECT 1 FROM api_tokens LIMIT 1) THEN
+            ALTER TABLE api_tokens ADD COLUMN user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE api_tokens ADD COLUMN user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'api_tokens' AND column_name = 'name') THEN
+        ALTER TABLE api_tokens ADD COLUMN name VARCHAR(100) NOT NULL DEFAULT '';
+    END IF;
+
+    -- token_hash: 令牌的 SHA-256 十六进制摘要
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'api_tokens' AND column_name = 'token_hash') THEN
+        ALTER TABLE api_tokens ADD COLUMN token_hash VARCHAR(64) NOT NULL DEFAULT '';
+    END IF;
+
+    -- token_prefix: 令牌开头几位明文，便于用户在列表中辨认
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'api_tokens' AND column_name = 'token_prefix') THEN
+        ALTER TABLE api_tokens ADD COLUMN token_prefix VARCHAR(20) NOT NULL DEFAULT '';
+    END IF;
+
+    -- scopes: 权限范围，如 resources:read / resources:write / favorites:read
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'api_tokens' AND column_name = 'scopes') THEN
+        ALTER TABLE api_tokens ADD COLUMN scopes TEXT[] NOT NULL DEFAULT '{}';
+    END IF;
+
+    -- expires_at: 为空表示永不过期
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'api_tokens' AND column_name = 'expires_at') THEN
+        ALTER TABLE api_tokens ADD COLUMN expires_at TIMESTAMP;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'api_tokens' AND column_name = 'last_used_at') THEN
+        ALTER TABLE api_tokens ADD COLUMN last_used_at TIMESTAMP;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'api_tokens' AND column_name = 'last_used_ip') THEN
+        ALTER TABLE api_tokens ADD COLUMN last_used_ip VARCHAR(45);
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'api_tokens' AND column_name = 'revoked_at') THEN
+        ALTER TABLE api_tokens ADD COLUMN revoked_at TIMESTAMP;
+    END IF;
+END $$;
+
+-- ============================================
+-- 33. 用户权限表（向非管理员授予部分管理功能）
+-- ============================================
+CREATE TABLE IF NOT EXISTS user_permissions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_permissions' AND column_name = 'user_id') THEN
+        IF EXISTS (SELECT 1 FROM user_permissions LIMIT 1) THEN
+            ALTER TABLE user_permissions ADD COLUMN user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE user_permissions ADD COLUMN user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    -- permission: 权限名，如 audit_resources / manage_courses / send_notifications / moderate_comments
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_permissions' AND column_name = 'permission') THEN
+        ALTER TABLE user_permissions ADD COLUMN permission VARCHAR(50) NOT NULL DEFAULT '';
+    END IF;
+
+    -- granted_by: 授权的管理员
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_permissions' AND column_name = 'granted_by') THEN
+        ALTER TABLE user_permissions ADD COLUMN granted_by UUID REFERENCES users(id) ON DELETE SET NULL;
+    END IF;
+END $$;
+
+-- ============================================
+-- 34. 外部身份表（统一身份认证登录绑定的账号）
+-- ============================================
+CREATE TABLE IF NOT EXISTS user_identities (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_identities' AND column_name = 'user_id') THEN
+        IF EXISTS (SELECT 1 FROM user_identities LIMIT 1) THEN
+            ALTER TABLE user_identities ADD COLUMN user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE user_identities ADD COLUMN user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    -- provider: oidc 或 cas
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_identities' AND column_name = 'provider') THEN
+        ALTER TABLE user_identities ADD COLUMN provider VARCHAR(20) NOT NULL DEFAULT '';
+    END IF;
+
+    -- subject: 身份提供方中的唯一标识（OIDC 的 sub，CAS 的用户名）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_identities' AND column_name = 'subject') THEN
+        ALTER TABLE user_identities ADD COLUMN subject VARCHAR(255) NOT NULL DEFAULT '';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_identities' AND column_name = 'email') THEN
+        ALTER TABLE user_identities ADD COLUMN email VARCHAR(255);
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_identities' AND column_name = 'last_login_at') THEN
+        ALTER TABLE user_identities ADD COLUMN last_login_at TIMESTAMP;
+    END IF;
+END $$;
+
+-- ============================================
+-- 35. 统一身份认证登录状态表（授权跳转期间暂存，使用一次后删除）
+-- ============================================
+CREATE TABLE IF NOT EXISTS sso_login_states (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    -- state_hash: state 参数的 SHA-256 十六进制摘要
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'sso_login_states' AND column_name = 'state_hash') THEN
+        ALTER TABLE sso_login_states ADD COLUMN state_hash VARCHAR(64) NOT NULL DEFAULT '';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'sso_login_states' AND column_name = 'provider') THEN
+        ALTER TABLE sso_login_states ADD COLUMN provider VARCHAR(20) NOT NULL DEFAULT '';
+    END IF;
+
+    -- nonce / code_verifier: OIDC 校验 ID Token 和 PKCE 使用，CAS 为空
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'sso_login_states' AND column_name = 'nonce') THEN
+        ALTER TABLE sso_login_states ADD COLUMN nonce VARCHAR(100);
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'sso_login_states' AND column_name = 'code_verifier') THEN
+        ALTER TABLE sso_login_states ADD COLUMN code_verifier VARCHAR(128);
+    END IF;
+
+    -- link_user_id: 已登录用户发起的绑定操作，为空表示登录
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'sso_login_states' AND column_name = 'link_user_id') THEN
+        ALTER TABLE sso_login_states ADD COLUMN link_user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'sso_login_states' AND column_name = 'expires_at') THEN
+        ALTER TABLE sso_login_states ADD COLUMN expires_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+    END IF;
+END $$;
+
+-- ============================================
+-- 36. 资源版本表（在线编辑和重新上传的历史版本）
+-- ============================================
+CREATE TABLE IF NOT EXISTS resource_versions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_versions' AND column_name = 'resource_id') THEN
+        IF EXISTS (SELECT 1 FROM resource_versions LIMIT 1) THEN
+            ALTER TABLE resource_versions ADD COLUMN resource_id UUID REFERENCES resources(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE resource_versions ADD COLUMN resource_id UUID NOT NULL REFERENCES resources(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    -- version_number: 从 1 开始递增，最大的版本即资源当前内容
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_versions' AND column_name = 'version_number') THEN
+        ALTER TABLE resource_versions ADD COLUMN version_number INTEGER NOT NULL DEFAULT 1;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_versions' AND column_name = 'resource_type') THEN
+        ALTER TABLE resource_versions ADD COLUMN resource_type VARCHAR(50) NOT NULL DEFAULT '';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_versions' AND column_name = 'file_path') THEN
+        ALTER TABLE resource_versions ADD COLUMN file_path VARCHAR(500) NOT NULL DEFAULT '';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_versions' AND column_name = 'storage_type') THEN
+        ALTER TABLE resource_versions ADD COLUMN storage_type VARCHAR(20) DEFAULT 'local';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_versions' AND column_name = 'file_hash') THEN
+        ALTER TABLE resource_versions ADD COLUMN file_hash VARCHAR(64);
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_versions' AND column_name = 'file_size') THEN
+        ALTER TABLE resource_versions ADD COLUMN file_size BIGINT;
+    END IF;
+
+    -- audit_status: 被新版本替代时的审核状态，当前版本以 resources 表为准
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_versions' AND column_name = 'audit_status') THEN
+        ALTER TABLE resource_versions ADD COLUMN audit_status VARCHAR(20) NOT NULL DEFAULT 'pending';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_versions' AND column_name = 'change_note') THEN
+        ALTER TABLE resource_versions ADD COLUMN change_note VARCHAR(200);
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_versions' AND column_name = 'created_by') THEN
+        ALTER TABLE resource_versions ADD COLUMN created_by UUID REFERENCES users(id) ON DELETE SET NULL;
+    END IF;
+END $$;
+
+-- ============================================
+-- 37. 资源协作者表（受邀共同编辑 Markdown 资源的用户）
+-- ============================================
+CREATE TABLE IF NOT EXISTS resource_editors (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_editors' AND column_name = 'resource_id') THEN
+        IF EXISTS (SELECT 1 FROM resource_editors LIMIT 1) THEN
+            ALTER TABLE resource_editors ADD COLUMN resource_id UUID REFERENCES resources(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE resource_editors ADD COLUMN resource_id UUID NOT NULL REFERENCES resources(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_editors' AND column_name = 'user_id') THEN
+        IF EXISTS (SELECT 1 FROM resource_editors LIMIT 1) THEN
+            ALTER TABLE resource_editors ADD COLUMN user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE resource_editors ADD COLUMN user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_editors' AND column_name = 'invited_by') THEN
+        ALTER TABLE resource_editors ADD COLUMN invited_by UUID REFERENCES users(id) ON DELETE SET NULL;
+    END IF;
+END $$;
+
+-- ============================================
+-- 38. 存储迁移任务表（在存储后端之间复制资源和图片文件）
+-- ============================================
+CREATE TABLE IF NOT EXISTS storage_migrations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migrations' AND column_name = 'source_backend') THEN
+        ALTER TABLE storage_migrations ADD COLUMN source_backend VARCHAR(20) NOT NULL DEFAULT 'local';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migrations' AND column_name = 'target_backend') THEN
+        ALTER TABLE storage_migrations ADD COLUMN target_backend VARCHAR(20) NOT NULL DEFAULT 'local';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migrations' AND column_name = 'include_resources') THEN
+        ALTER TABLE storage_migrations ADD COLUMN include_resources BOOLEAN NOT NULL DEFAULT TRUE;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migrations' AND column_name = 'include_images') THEN
+        ALTER TABLE storage_migrations ADD COLUMN include_images BOOLEAN NOT NULL DEFAULT TRUE;
+    END IF;
+
+    -- delete_source: 迁移并校验成功后是否删除源文件
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migrations' AND column_name = 'delete_source') THEN
+        ALTER TABLE storage_migrations ADD COLUMN delete_source BOOLEAN NOT NULL DEFAULT FALSE;
+    END IF;
+
+    -- status: running / completed / failed / cancelled
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migrations' AND column_name = 'status') THEN
+        ALTER TABLE storage_migrations ADD COLUMN status VARCHAR(20) NOT NULL DEFAULT 'running';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migrations' AND column_name = 'cancel_requested') THEN
+        ALTER TABLE storage_migrations ADD COLUMN cancel_requested BOOLEAN NOT NULL DEFAULT FALSE;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migrations' AND column_name = 'total_items') THEN
+        ALTER TABLE storage_migrations ADD COLUMN total_items INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migrations' AND column_name = 'processed_items') THEN
+        ALTER TABLE storage_migrations ADD COLUMN processed_items INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migrations' AND column_name = 'migrated_items') THEN
+        ALTER TABLE storage_migrations ADD COLUMN migrated_items INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migrations' AND column_name = 'failed_items') THEN
+        ALTER TABLE storage_migrations ADD COLUMN failed_items INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    -- skipped_items: 迁移期间记录已被修改或删除而跳过的文件
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migrations' AND column_name = 'skipped_items') THEN
+        ALTER TABLE storage_migrations ADD COLUMN skipped_items INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migrations' AND column_name = 'migrated_bytes') THEN
+        ALTER TABLE storage_migrations ADD COLUMN migrated_bytes BIGINT NOT NULL DEFAULT 0;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migrations' AND column_name = 'last_error') THEN
+        ALTER TABLE storage_migrations ADD COLUMN last_error TEXT;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migrations' AND column_name = 'created_by') THEN
+        ALTER TABLE storage_migrations ADD COLUMN created_by UUID REFERENCES users(id) ON DELETE SET NULL;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migrations' AND column_name = 'finished_at') THEN
+        ALTER TABLE storage_migrations ADD COLUMN finished_at TIMESTAMP;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migrations' AND column_name = 'updated_at') THEN
+        ALTER TABLE storage_migrations ADD COLUMN updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP;
+    END IF;
+
+    -- heartbeat_at: 运行中的任务定期刷新，超时未刷新视为所在进程已退出
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migrations' AND column_name = 'heartbeat_at') THEN
+        ALTER TABLE storage_migrations ADD COLUMN heartbeat_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP;
+    END IF;
+END $$;
+
+-- ============================================
+-- 39. 存储迁移失败记录表
+-- ============================================
+CREATE TABLE IF NOT EXISTS storage_migration_failures (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migration_failures' AND column_name = 'migration_id') THEN
+        IF EXISTS (SELECT 1 FROM storage_migration_failures LIMIT 1) THEN
+            ALTER TABLE storage_migration_failures ADD COLUMN migration_id UUID REFERENCES storage_migrations(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE storage_migration_failures ADD COLUMN migration_id UUID NOT NULL REFERENCES storage_migrations(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    -- item_type: resource / resource_version / image
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migration_failures' AND column_name = 'item_type') THEN
+        ALTER TABLE storage_migration_failures ADD COLUMN item_type VARCHAR(20) NOT NULL DEFAULT '';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migration_failures' AND column_name = 'item_id') THEN
+        ALTER TABLE storage_migration_failures ADD COLUMN item_id UUID NOT NULL DEFAULT '00000000-0000-0000-0000-000000000000';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migration_failures' AND column_name = 'file_path') THEN
+        ALTER TABLE storage_migration_failures ADD COLUMN file_path VARCHAR(500) NOT NULL DEFAULT '';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migration_failures' AND column_name = 'error') THEN
+        ALTER TABLE storage_migration_failures ADD COLUMN error TEXT NOT NULL DEFAULT '';
+    END IF;
+END $$;
+
+-- ============================================
+-- 40. 存储一致性检查任务表（对比存储中的文件与数据库记录，可清理孤儿文件）
+-- ============================================
+CREATE TABLE IF NOT EXISTS storage_checks (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    -- backends: 本次检查的存储后端，逗号分隔
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_checks' AND column_name = 'backends') THEN
+        ALTER TABLE storage_checks ADD COLUMN backends VARCHAR(50) NOT NULL DEFAULT '';
+    END IF;
+
+    -- cleanup: FALSE 为只生成报告（dry-run），TRUE 时删除超过宽限期的孤儿文件
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_checks' AND column_name = 'cleanup') THEN
+        ALTER TABLE storage_checks ADD COLUMN cleanup BOOLEAN NOT NULL DEFAULT FALSE;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_checks' AND column_name = 'verify_hash') THEN
+        ALTER TABLE storage_checks ADD COLUMN verify_hash BOOLEAN NOT NULL DEFAULT FALSE;
+    END IF;
+
+    -- grace_hours: 孤儿文件最后修改时间距今超过该小时数才会被清理
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_checks' AND column_name = 'grace_hours') THEN
+        ALTER TABLE storage_checks ADD COLUMN grace_hours INTEGER NOT NULL DEFAULT 24;
+    END IF;
+
+    -- status: running / completed / failed
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_checks' AND column_name = 'status') THEN
+        ALTER TABLE storage_checks ADD COLUMN status VARCHAR(20) NOT NULL DEFAULT 'running';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_checks' AND column_name = 'scanned_files') THEN
+        ALTER TABLE storage_checks ADD COLUMN scanned_files INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_checks' AND column_name = 'scanned_records') THEN
+        ALTER TABLE storage_checks ADD COLUMN scanned_records INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_checks' AND column_name = 'orphan_files') THEN
+        ALTER TABLE storage_checks ADD COLUMN orphan_files INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_checks' AND column_name = 'missing_files') THEN
+        ALTER TABLE storage_checks ADD COLUMN missing_files INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_checks' AND column_name = 'mismatched_files') THEN
+        ALTER TABLE storage_checks ADD COLUMN mismatched_files INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_checks' AND column_name = 'deleted_files') THEN
+        ALTER TABLE storage_checks ADD COLUMN deleted_files INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_checks' AND column_name = 'deleted_bytes') THEN
+        ALTER TABLE storage_checks ADD COLUMN deleted_bytes BIGINT NOT NULL DEFAULT 0;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_checks' AND column_name = 'last_error') THEN
+        ALTER TABLE storage_checks ADD COLUMN last_error TEXT;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_checks' AND column_name = 'created_by') THEN
+        ALTER TABLE storage_checks ADD COLUMN created_by UUID REFERENCES users(id) ON DELETE SET NULL;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_checks' AND column_name = 'finished_at') THEN
+        ALTER TABLE storage_checks ADD COLUMN finished_at TIMESTAMP;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_checks' AND column_name = 'updated_at') THEN
+        ALTER TABLE storage_checks ADD COLUMN updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP;
+    END IF;
+END $$;
+
+-- ============================================
+-- 41. 存储一致性问题表
+-- ============================================
+CREATE TABLE IF NOT EXISTS storage_check_issues (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_check_issues' AND column_name = 'check_id') THEN
+        IF EXISTS (SELECT 1 FROM storage_check_issues LIMIT 1) THEN
+            ALTER TABLE storage_check_issues ADD COLUMN check_id UUID REFERENCES storage_checks(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE storage_check_issues ADD COLUMN check_id UUID NOT NULL REFERENCES storage_checks(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_check_issues' AND column_name = 'backend') THEN
+        ALTER TABLE storage_check_issues ADD COLUMN backend VARCHAR(20) NOT NULL DEFAULT 'local';
+    END IF;
+
+    -- issue_type: orphan_file / missing_file / size_mismatch / hash_mismatch
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_check_issues' AND column_name = 'issue_type') THEN
+        ALTER TABLE storage_check_issues ADD COLUMN issue_type VARCHAR(20) NOT NULL DEFAULT '';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_check_issues' AND column_name = 'file_path') THEN
+        ALTER TABLE storage_check_issues ADD COLUMN file_path VARCHAR(500) NOT NULL DEFAULT '';
+    END IF;
+
+    -- item_type: resource / resource_version / image，孤儿文件为空
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_check_issues' AND column_name = 'item_type') THEN
+        ALTER TABLE storage_check_issues ADD COLUMN item_type VARCHAR(20);
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_check_issues' AND column_name = 'item_id') THEN
+        ALTER TABLE storage_check_issues ADD COLUMN item_id UUID;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_check_issues' AND column_name = 'expected_size') THEN
+        ALTER TABLE storage_check_issues ADD COLUMN expected_size BIGINT;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_check_issues' AND column_name = 'actual_size') THEN
+        ALTER TABLE storage_check_issues ADD COLUMN actual_size BIGINT;
+    END IF;
+
+    -- action: reported / deleted / kept_recent / delete_failed
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_check_issues' AND column_name = 'action') THEN
+        ALTER TABLE storage_check_issues ADD COLUMN action VARCHAR(20) NOT NULL DEFAULT 'reported';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_check_issues' AND column_name = 'detail') THEN
+        ALTER TABLE storage_check_issues ADD COLUMN detail TEXT;
+    END IF;
+END $$;
+
+-- ============================================
+-- 42. 角色存储配额表（覆盖代码中的默认角色配额）
+-- ============================================
+CREATE TABLE IF NOT EXISTS role_storage_quotas (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    -- role: user / verified / admin
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'role_storage_quotas' AND column_name = 'role') THEN
+        ALTER TABLE role_storage_quotas ADD COLUMN role VARCHAR(20) NOT NULL DEFAULT '';
+    END IF;
+
+    -- quota_bytes: 配额字节数，NULL 表示不限
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'role_storage_quotas' AND column_name = 'quota_bytes') THEN
+        ALTER TABLE role_storage_quotas ADD COLUMN quota_bytes BIGINT;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'role_storage_quotas' AND column_name = 'updated_by') THEN
+        ALTER TABLE role_storage_quotas ADD COLUMN updated_by UUID REFERENCES users(id) ON DELETE SET NULL;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'role_storage_quotas' AND column_name = 'updated_at') THEN
+        ALTER TABLE role_storage_quotas ADD COLUMN updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP;
+    END IF;
+END $$;
+
+-- ============================================
+-- 43. 用户存储配额表（管理员为单个用户设置的配额，优先于角色配额）
+-- ============================================
+CREATE TABLE IF NOT EXISTS user_storage_quotas (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_storage_quotas' AND column_name = 'user_id') THEN
+        IF EXISTS (SELECT 1 FROM user_storage_quotas LIMIT 1) THEN
+            ALTER TABLE user_storage_quotas ADD COLUMN user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE user_storage_quotas ADD COLUMN user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    -- quota_bytes: 配额字节数，NULL 表示不限
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_storage_quotas' AND column_name = 'quota_bytes') THEN
+        ALTER TABLE user_storage_quotas ADD COLUMN quota_bytes BIGINT;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_storage_quotas' AND column_name = 'note') THEN
+        ALTER TABLE user_storage_quotas ADD COLUMN note TEXT;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_storage_quotas' AND column_name = 'updated_by') THEN
+        ALTER TABLE user_storage_quotas ADD COLUMN updated_by UUID REFERENCES users(id) ON DELETE SET NULL;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_storage_quotas' AND column_name = 'updated_at') THEN
+        ALTER TABLE user_storage_quotas ADD COLUMN updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP;
+    END IF;
+END $$;
+
+-- ============================================
+-- 44. 用户-资源行为汇总（推荐使用的物化视图，后端定期刷新）
+-- 下载 1 分、点赞 2 分、收藏 3 分，评分按总体质量折算（5 分为中性，低分为负反馈），
+-- 同一用户对同一资源的得分累加后上限为 5，只保留正反馈
+-- ============================================
+CREATE MATERIALIZED VIEW IF NOT EXISTS user_resource_interactions AS
+WITH interactions AS (
+    SELECT user_id, resource_id, 1.0::FLOAT8 AS weight
+    FROM download_logs WHERE user_id IS NOT NULL
+    UNION ALL
+    SELECT user_id, resource_id, 2.0::FLOAT8
+    FROM likes WHERE user_id IS NOT NULL
+    UNION ALL
+    SELECT f.user_id, fr.resource_id, 3.0::FLOAT8
+    FROM favorite_resources fr JOIN favorites f ON f.id = fr.favorite_id
+    UNION ALL
+    SELECT user_id, resource_id, (overall_quality - 5)::FLOAT8 / 2.5
+    FROM ratings WHERE overall_quality IS NOT NULL
+)
+SELECT user_id, resource_id, LEAST(SUM(weight), 5.0) AS weight
+FROM interactions
+GROUP BY user_id, resource_id
+HAVING SUM(weight) > 0;
+
+-- ============================================
+-- 为现有用户分配 sn（增量更新支持）
+-- ============================================
+DO $$
+DECLARE
+    user_record RECORD;
+    current_sn BIGINT := 1;
+BEGIN
+    FOR user_record IN
+        SELECT id FROM users WHERE sn IS NULL ORDER BY created_at ASC
+    LOOP
+        UPDATE users SET sn = current_sn WHERE id = user_record.id;
+        current_sn := current_sn + 1;
+    END LOOP;
+    IF current_sn > 1 THEN
+        PERFORM setval('user_sn_seq', current_sn - 1, true);
+    END IF;
+END $$;
+
+-- ============================================
+-- 创建索引
+-- ============================================
+CREATE INDEX IF NOT EXISTS idx_users_role ON users(role);
+CREATE INDEX IF NOT EXISTS idx_users_is_verified ON users(is_verified);
+CREATE INDEX IF NOT EXISTS idx_users_sn ON users(sn);
+CREATE INDEX IF NOT EXISTS idx_resources_uploader ON resources(uploader_id);
+CREATE INDEX IF NOT EXISTS idx_resources_author ON resources(author_id);
+CREATE INDEX IF NOT EXISTS idx_resources_course ON resources(course_name);
+CREATE INDEX IF NOT EXISTS idx_resources_type ON resources(resource_type);
+CREATE INDEX IF NOT EXISTS idx_resources_category ON resources(category);
+CREATE INDEX IF NOT EXISTS idx_resources_audit_status ON resources(audit_status);
+CREATE INDEX IF NOT EXISTS idx_resources_tags ON resources USING GIN(tags);
+CREATE INDEX IF NOT EXISTS idx_resources_created_at ON resources(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_resources_storage_type ON resources(storage_type);
+CREATE INDEX IF NOT EXISTS idx_resources_search_vector ON resources USING GIN(search_vector);
+CREATE INDEX IF NOT EXISTS idx_resources_file_hash ON resources(file_hash);
+CREATE INDEX IF NOT EXISTS idx_ratings_resource ON ratings(resource_id);
+CREATE INDEX IF NOT EXISTS idx_ratings_user ON ratings(user_id);
+CREATE INDEX IF NOT EXISTS idx_likes_user ON likes(user_id);
+CREATE INDEX IF NOT EXISTS idx_comments_resource ON comments(resource_id);
+CREATE INDEX IF NOT EXISTS idx_comments_user ON comments(user_id);
+CREATE INDEX IF NOT EXISTS idx_comments_created_at ON comments(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_comments_root ON comments(root_id);
+CREATE INDEX IF NOT EXISTS idx_comments_parent ON comments(parent_id);
+CREATE INDEX IF NOT EXISTS idx_favorites_user ON favorites(user_id);
+CREATE INDEX IF NOT EXISTS idx_fav_res_resource ON favorite_resources(resource_id);
+CREATE INDEX IF NOT EXISTS idx_claims_resource ON claims(resource_id);
+CREATE INDEX IF NOT EXISTS idx_claims_applicant ON claims(applicant_id);
+CREATE INDEX IF NOT EXISTS idx_claims_status ON claims(status);
+-- 同一用户对同一资源最多一条待审核申领（并发提交由唯一索引拦截）
+CREATE UNIQUE INDEX IF NOT EXISTS idx_claims_pending_unique ON claims(resource_id, applicant_id) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_notifications_recipient ON notifications(recipient_id);
+CREATE INDEX IF NOT EXISTS idx_notifications_priority ON notifications(priority);
+CREATE INDEX IF NOT EXISTS idx_notifications_is_read ON notifications(is_read);
+CREATE INDEX IF NOT EXISTS idx_notifications_created_at ON notifications(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_notification_reads_notification ON notification_reads(notification_id);
+CREATE INDEX IF NOT EXISTS idx_notification_reads_user ON notification_reads(user_id);
+CREATE INDEX IF NOT EXISTS idx_notification_reads_unique ON notification_reads(notification_id, user_id);
+CREATE INDEX IF NOT EXISTS idx_audit_logs_user ON audit_logs(user_id);
+CREATE INDEX IF NOT EXISTS idx_audit_logs_action ON audit_logs(action);
+CREATE INDEX IF NOT EXISTS idx_audit_logs_created_at ON audit_logs(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_download_logs_resource ON download_logs(resource_id);
+CREATE INDEX IF NOT EXISTS idx_download_logs_user ON download_logs(user_id);
+CREATE INDEX IF NOT EXISTS idx_download_logs_time ON download_logs(downloaded_at DESC);
+CREATE INDEX IF NOT EXISTS idx_images_uploader ON images(uploader_id);
+CREATE INDEX IF NOT EXISTS idx_images_storage_type ON images(storage_type);
+CREATE INDEX IF NOT EXISTS idx_teachers_sn ON teachers(sn);
+CREATE INDEX IF NOT EXISTS idx_teachers_department ON teachers(department);
+CREATE INDEX IF NOT EXISTS idx_teachers_is_active ON teachers(is_active);
+CREATE INDEX IF NOT EXISTS idx_courses_sn ON courses(sn);
+CREATE INDEX IF NOT EXISTS idx_courses_semester ON courses(semester);
+CREATE INDEX IF NOT EXISTS idx_courses_is_active ON courses(is_active);
+CREATE INDEX IF NOT EXISTS idx_resource_teachers_resource ON resource_teachers(resource_id);
+CREATE INDEX IF NOT EXISTS idx_resource_teachers_teacher ON resource_teachers(teacher_sn);
+CREATE INDEX IF NOT EXISTS idx_resource_courses_resource ON resource_courses(resource_id);
+CREATE INDEX IF NOT EXISTS idx_resource_courses_course ON resource_courses(course_sn);
+
+-- 资源关联表索引
+CREATE INDEX IF NOT EXISTS idx_resource_relations_source ON resource_relations(source_resource_id);
+CREATE INDEX IF NOT EXISTS idx_resource_relations_target ON resource_relations(target_resource_id);
+
+-- 分片上传会话表索引
+CREATE INDEX IF NOT EXISTS idx_upload_sessions_user ON upload_sessions(user_id, status);
+CREATE INDEX IF NOT EXISTS idx_upload_sessions_expires ON upload_sessions(expires_at);
+
+-- 内容审核规则表索引
+CREATE INDEX IF NOT EXISTS idx_moderation_rules_active ON moderation_rules(is_active, target);
+
+-- 评论编辑历史 / 点赞 / 举报表索引
+CREATE INDEX IF NOT EXISTS idx_comment_edits_comment ON comment_edits(comment_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_comment_likes_user ON comment_likes(user_id);
+CREATE INDEX IF NOT EXISTS idx_comment_reports_status ON comment_reports(status, comment_id);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_comment_reports_pending_unique ON comment_reports(comment_id, reporter_id) WHERE status = 'pending';
+
+-- 登录会话表索引
+CREATE INDEX IF NOT EXISTS idx_user_sessions_user ON user_sessions(user_id, revoked_at, expires_at);
+
+-- 密码重置令牌表索引
+CREATE UNIQUE INDEX IF NOT EXISTS idx_password_reset_tokens_hash ON password_reset_tokens(token_hash);
+CREATE INDEX IF NOT EXISTS idx_password_reset_tokens_user ON password_reset_tokens(user_id, used_at);
+
+-- 邮箱确认令牌表索引
+CREATE UNIQUE INDEX IF NOT EXISTS idx_email_verification_tokens_hash ON email_verification_tokens(token_hash);
+CREATE INDEX IF NOT EXISTS idx_email_verification_tokens_user ON email_verification_tokens(user_id, used_at);
+
+-- 实名认证申请表索引（每个用户同时只能有一个待审核申请）
+CREATE UNIQUE INDEX IF NOT EXISTS idx_verification_requests_pending ON verification_requests(user_id) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_verification_requests_status ON verification_requests(status, created_at);
+-- 同一学号只能认证一个账号（学号不区分大小写）
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_student_id_unique ON users((UPPER(real_info->>'student_id'))) WHERE is_verified = true;
+
+-- 限流令牌桶表索引（清理长期未使用的桶）
+CREATE INDEX IF NOT EXISTS idx_rate_limit_buckets_updated ON rate_limit_buckets(updated_at);
+
+-- 登录失败记录表索引
+DROP INDEX IF EXISTS idx_login_failures_username;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_login_failures_username_ip ON login_failures(username, ip_address);
+CREATE INDEX IF NOT EXISTS idx_login_failures_last_failed ON login_failures(last_failed_at);
+
+-- 两步验证密钥表索引
+CREATE UNIQUE INDEX IF NOT EXISTS idx_user_totp_user ON user_totp(user_id);
+
+-- 两步验证恢复码表索引
+CREATE INDEX IF NOT EXISTS idx_mfa_recovery_codes_user ON mfa_recovery_codes(user_id);
+
+-- 个人访问令牌表索引
+CREATE UNIQUE INDEX IF NOT EXISTS idx_api_tokens_hash ON api_tokens(token_hash);
+CREATE INDEX IF NOT EXISTS idx_api_tokens_user ON api_tokens(user_id);
+
+-- 用户权限表索引
+CREATE UNIQUE INDEX IF NOT EXISTS idx_user_permissions_user_permission ON user_permissions(user_id, permission);
+
+-- 外部身份表索引
+CREATE UNIQUE INDEX IF NOT EXISTS idx_user_identities_provider_subject ON user_identities(provider, subject);
+CREATE INDEX IF NOT EXISTS idx_user_identities_user_id ON user_identities(user_id);
+
+-- 统一身份认证登录状态表索引
+CREATE UNIQUE INDEX IF NOT EXISTS idx_sso_login_states_state_hash ON sso_login_states(state_hash);
+CREATE INDEX IF NOT EXISTS idx_sso_login_states_expires_at ON sso_login_states(expires_at);
+
+-- 资源版本表索引
+CREATE UNIQUE INDEX IF NOT EXISTS idx_resource_versions_resource_version ON resource_versions(resource_id, version_number);
+
+-- 资源协作者表索引
+CREATE UNIQUE INDEX IF NOT EXISTS idx_resource_editors_resource_user ON resource_editors(resource_id, user_id);
+CREATE INDEX IF NOT EXISTS idx_resource_editors_user_id ON resource_editors(user_id);
+
+-- 存储迁移任务表索引
+CREATE INDEX IF NOT EXISTS idx_storage_migrations_created_at ON storage_migrations(created_at DESC);
+-- 同一时间只允许一个进行中的迁移任务
+CREATE UNIQUE INDEX IF NOT EXISTS idx_storage_migrations_running ON storage_migrations((TRUE)) WHERE status = 'running';
+
+-- 存储迁移失败记录表索引
+CREATE INDEX IF NOT EXISTS idx_storage_migration_failures_migration_id ON storage_migration_failures(migration_id, created_at);
+
+-- 存储一致性检查任务表索引
+CREATE INDEX IF NOT EXISTS idx_storage_checks_created_at ON storage_checks(created_at DESC);
+-- 同一时间只允许一个进行中的检查任务
+CREATE UNIQUE INDEX IF NOT EXISTS idx_storage_checks_running ON storage_checks((TRUE)) WHERE status = 'running';
+
+-- 存储一致性问题表索引
+CREATE INDEX IF NOT EXISTS idx_storage_check_issues_check_id ON storage_check_issues(check_id, issue_type);
+
+-- 角色存储配额表索引
+CREATE UNIQUE INDEX IF NOT EXISTS idx_role_storage_quotas_role ON role_storage_quotas(role);
+
+-- 用户存储配额表索引
+CREATE UNIQUE INDEX IF NOT EXISTS idx_user_storage_quotas_user_id ON user_storage_quotas(user_id);
+
+-- 用户-资源行为汇总索引（唯一索引供 REFRESH MATERIALIZED VIEW CONCURRENTLY 使用）
+CREATE UNIQUE INDEX IF NOT EXISTS idx_user_resource_interactions_user_resource ON user_resource_interactions(user_id, resource_id);
+CREATE INDEX IF NOT EXISTS idx_user_resource_interactions_resource ON user_resource_interactions(resource_id, user_id);
+
+-- ============================================
+-- 创建触发器
+-- ============================================
+CREATE OR REPLACE FUNCTION update_updated_at_column()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at = CURRENT_TIMESTAMP;
+    RETURN NEW;
+END;
+$$
+language 'plpgsql';
+
+DROP TRIGGER IF EXISTS update_users_updated_at ON users;
+CREATE TRIGGER update_users_updated_at
+    BEFORE UPDATE ON users
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+DROP TRIGGER IF EXISTS update_resources_updated_at ON resources;
+CREATE TRIGGER update_resources_updated_at
+    BEFORE UPDATE ON resources
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+DROP TRIGGER IF EXISTS update_ratings_updated_at ON ratings;
+CREATE TRIGGER update_ratings_updated_at
+    BEFORE UPDATE ON ratings
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+DROP TRIGGER IF EXISTS update_comments_updated_at ON comments;
+CREATE TRIGGER update_comments_updated_at
+    BEFORE UPDATE ON comments
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+DROP TRIGGER IF EXISTS update_teachers_updated_at ON teachers;
+CREATE TRIGGER update_teachers_updated_at
+    BEFORE UPDATE ON teachers
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+DROP TRIGGER IF EXISTS update_courses_updated_at ON courses;
+CREATE TRIGGER update_courses_updated_at
+    BEFORE UPDATE ON courses
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- 分片上传会话表触发器
+DROP TRIGGER IF EXISTS update_upload_sessions_updated_at ON upload_sessions;
+CREATE TRIGGER update_upload_sessions_updated_at
+    BEFORE UPDATE ON upload_sessions
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- 内容审核规则表触发器
+DROP TRIGGER IF EXISTS update_moderation_rules_updated_at ON moderation_rules;
+CREATE TRIGGER update_moderation_rules_updated_at
+    BEFORE UPDATE ON moderation_rules
     FOR EACH ROW
     EXECUTE FUNCTION update_updated_at_column();
 
@@ -1233,9 +2523,35 @@ def main():
     print("  - resource_teachers (资源教师关联表)")
     print("  - resource_courses (资源课程关联表)")
     print("  - resource_relations (资源关联表)")
+    print("  - upload_sessions (分片上传会话表)")
+    print("  - moderation_rules (内容审核规则表)")
+    print("  - comment_edits (评论编辑历史表)")
+    print("  - comment_likes (评论点赞表)")
+    print("  - comment_reports (评论举报表)")
+    print("  - user_sessions (登录会话表)")
+    print("  - password_reset_tokens (密码重置令牌表)")
+    print("  - email_verification_tokens (邮箱确认令牌表)")
+    print("  - verification_requests (实名认证申请表)")
+    print("  - rate_limit_buckets (限流令牌桶表)")
+    print("  - login_failures (登录失败记录表)")
+    print("  - user_totp (两步验证密钥表)")
+    print("  - mfa_recovery_codes (两步验证恢复码表)")
+    print("  - api_tokens (个人访问令牌表)")
+    print("  - user_permissions (用户权限表)")
+    print("  - user_identities (外部身份表)")
+    print("  - sso_login_states (统一身份认证登录状态表)")
+    print("  - resource_versions (资源版本表)")
+    print("  - resource_editors (资源协作者表)")
+    print("  - storage_migrations (存储迁移任务表)")
+    print("  - storage_migration_failures (存储迁移失败记录表)")
+    print("  - storage_checks (存储一致性检查任务表)")
+    print("  - storage_check_issues (存储一致性问题表)")
+    print("  - role_storage_quotas (角色存储配额表)")
+    print("  - user_storage_quotas (用户存储配额表)")
+    print("  - user_resource_interactions (用户-资源行为汇总物化视图)")
     print()
     print("索引: 42+")
-    print("触发器: 6 (自动更新 updated_at)")
+    print("触发器: 8 (自动更新 updated_at)")
     print()
     print("说明: 此脚本支持增量更新，可重复执行。")
     print()